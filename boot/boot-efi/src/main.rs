@@ -2,6 +2,9 @@
 #![no_std]
 #![no_main]
 
+extern crate alloc;
+
+use alloc::boxed::Box;
 use boot_efi::{invocation::*, loader::*, page::*};
 use bootprot::*;
 use core::{fmt::Write, mem::*};
@@ -9,7 +12,7 @@ use lib_efi::*;
 use uefi::{
     data_types::Guid,
     prelude::*,
-    proto::console::gop,
+    proto::{console::gop, loaded_image::LoadedImage},
     table::{
         boot::{MemoryType, OpenProtocolAttributes, OpenProtocolParams, SearchType},
         cfg::{ACPI2_GUID, SMBIOS_GUID},
@@ -36,6 +39,20 @@ fn efi_main(handle: Handle, mut st: SystemTable<Boot>) -> Status {
     };
     let bs = st.boot_services();
 
+    // Pass the load options to the kernel as the command line
+    if let Ok(image) = bs.open_protocol_exclusive::<LoadedImage>(handle) {
+        if let Some(options) = image.load_options_as_bytes() {
+            let mut cmdline = Box::new([0; BootInfo::MAX_CMDLINE_LEN]);
+            BootInfo::encode_cmdline(
+                options
+                    .chunks_exact(2)
+                    .map(|v| u16::from_le_bytes([v[0], v[1]])),
+                &mut cmdline,
+            );
+            info.cmdline = Box::leak(cmdline).as_ptr() as u64;
+        }
+    }
+
     // Find the ACPI Table
     info.acpi_rsdptr = match st.find_config_table(ACPI2_GUID) {
         Some(val) => val,
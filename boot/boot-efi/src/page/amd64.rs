@@ -90,11 +90,9 @@ impl PageManager {
                 if last_pa <= MAX_REAL_MEMORY {
                     let base = page_base / 0x1000;
                     let count = page_size / 0x1000;
-                    let limit = core::cmp::min(base + count, 256);
+                    let limit = core::cmp::min(base + count, BootInfo::REAL_PAGES as u64);
                     for i in base..limit {
-                        let index = i as usize / 32;
-                        let bit = 1 << (i & 31);
-                        info.real_bitmap[index] |= bit;
+                        info.mark_real_page(i as usize, true);
                     }
                     has_to_copy = false;
                 }
@@ -1,10 +1,11 @@
 //! MEG-OS Boot Procotol
 
-#![no_std]
+#![cfg_attr(not(test), no_std)]
+#![feature(const_default)]
 #![feature(const_trait_impl)]
 
 use bitflags::*;
-use core::fmt;
+use core::{fmt, slice, str};
 
 #[repr(C)]
 #[derive(Default)]
@@ -26,10 +27,137 @@ pub struct BootInfo {
     pub initrd_size: u32,
     pub mmap_base: u32,
     pub mmap_len: u32,
+    /// Free pages in the first 1MB, see [`BootInfo::is_real_page_free`]
     pub real_bitmap: [u32; 8],
     pub flags: BootFlags,
 }
 
+impl BootInfo {
+    /// Maximum length of the command line
+    pub const MAX_CMDLINE_LEN: usize = 1024;
+
+    /// Number of 4KB pages in the first 1MB described by `real_bitmap`
+    pub const REAL_PAGES: usize = 256;
+
+    /// Returns whether the page in the first 1MB is free.
+    ///
+    /// Pages out of the range are never free.
+    #[inline]
+    pub const fn is_real_page_free(&self, index: usize) -> bool {
+        index < Self::REAL_PAGES && (self.real_bitmap[index / 32] & (1 << (index % 32))) != 0
+    }
+
+    /// Marks the page in the first 1MB as free or in use.
+    ///
+    /// Pages out of the range are ignored.
+    #[inline]
+    pub fn mark_real_page(&mut self, index: usize, is_free: bool) {
+        if index < Self::REAL_PAGES {
+            let bit = 1 << (index % 32);
+            if is_free {
+                self.real_bitmap[index / 32] |= bit;
+            } else {
+                self.real_bitmap[index / 32] &= !bit;
+            }
+        }
+    }
+
+    /// Returns the command line, which ends at NUL or at [`Self::MAX_CMDLINE_LEN`] bytes.
+    ///
+    /// # Safety
+    ///
+    /// `cmdline` must be null or point to a readable string.
+    pub unsafe fn cmdline_str(&self) -> Option<&str> {
+        if self.cmdline == 0 {
+            return None;
+        }
+        let base = self.cmdline as usize as *const u8;
+        let mut len = 0;
+        while len < Self::MAX_CMDLINE_LEN && unsafe { base.add(len).read() } != 0 {
+            len += 1;
+        }
+        str::from_utf8(unsafe { slice::from_raw_parts(base, len) }).ok()
+    }
+
+    /// Converts UCS-2 text such as the UEFI load options to the NUL-terminated command line.
+    ///
+    /// Stops at NUL. Non-ASCII characters are replaced with `?` and the excess is truncated.
+    pub fn encode_cmdline<I>(text: I, buf: &mut [u8; Self::MAX_CMDLINE_LEN]) -> usize
+    where
+        I: IntoIterator<Item = u16>,
+    {
+        let mut len = 0;
+        for c in text
+            .into_iter()
+            .take_while(|&c| c != 0)
+            .take(Self::MAX_CMDLINE_LEN - 1)
+        {
+            buf[len] = if c < 0x80 { c as u8 } else { b'?' };
+            len += 1;
+        }
+        buf[len] = 0;
+        len
+    }
+
+    /// Returns the arguments of the command line.
+    ///
+    /// # Safety
+    ///
+    /// Same as [`Self::cmdline_str`].
+    #[inline]
+    pub unsafe fn cmdline_args(&self) -> CmdlineArgs<'_> {
+        CmdlineArgs::new(unsafe { self.cmdline_str() }.unwrap_or_default())
+    }
+}
+
+/// An argument of the command line
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CmdlineArg<'a> {
+    /// An argument without `=`
+    Flag(&'a str),
+    /// An argument in the form of `key=value`
+    KeyValue(&'a str, &'a str),
+}
+
+/// Iterator over the arguments of the command line separated by whitespaces
+#[derive(Clone)]
+pub struct CmdlineArgs<'a>(str::SplitAsciiWhitespace<'a>);
+
+impl<'a> CmdlineArgs<'a> {
+    #[inline]
+    pub fn new(cmdline: &'a str) -> Self {
+        Self(cmdline.split_ascii_whitespace())
+    }
+
+    /// Returns the value of the first argument with the key.
+    #[inline]
+    pub fn value_of(self, key: &str) -> Option<&'a str> {
+        self.filter_map(|arg| match arg {
+            CmdlineArg::KeyValue(k, v) if k == key => Some(v),
+            _ => None,
+        })
+        .next()
+    }
+
+    /// Returns whether the flag is specified.
+    #[inline]
+    pub fn has_flag(mut self, flag: &str) -> bool {
+        self.any(|arg| arg == CmdlineArg::Flag(flag))
+    }
+}
+
+impl<'a> Iterator for CmdlineArgs<'a> {
+    type Item = CmdlineArg<'a>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(|arg| match arg.split_once('=') {
+            Some((key, value)) => CmdlineArg::KeyValue(key, value),
+            None => CmdlineArg::Flag(arg),
+        })
+    }
+}
+
 #[non_exhaustive]
 #[repr(u8)]
 #[derive(Debug, Clone, Copy)]
@@ -123,3 +251,87 @@ pub enum BootMemoryType {
     Reserved,
     Unavailable,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cmdline() {
+        let cmdline = b"debug console=uart serial=115200\0garbage";
+        let mut info = BootInfo::default();
+        assert_eq!(unsafe { info.cmdline_str() }, None);
+
+        info.cmdline = cmdline.as_ptr() as u64;
+        assert_eq!(
+            unsafe { info.cmdline_str() },
+            Some("debug console=uart serial=115200")
+        );
+
+        let args = unsafe { info.cmdline_args() };
+        assert_eq!(
+            args.clone().collect::<Vec<_>>(),
+            [
+                CmdlineArg::Flag("debug"),
+                CmdlineArg::KeyValue("console", "uart"),
+                CmdlineArg::KeyValue("serial", "115200"),
+            ]
+        );
+        assert!(args.clone().has_flag("debug"));
+        assert!(!args.clone().has_flag("console"));
+        assert_eq!(args.clone().value_of("serial"), Some("115200"));
+        assert_eq!(args.value_of("root"), None);
+    }
+
+    #[test]
+    fn encode_cmdline() {
+        let options = "\\EFI\\BOOT\\BOOTX64.EFI panic=10 name=\u{3042}\0garbage";
+        let mut buf = [0xFF; BootInfo::MAX_CMDLINE_LEN];
+        let len = BootInfo::encode_cmdline(options.encode_utf16(), &mut buf);
+        assert_eq!(&buf[..=len], b"\\EFI\\BOOT\\BOOTX64.EFI panic=10 name=?\0");
+
+        let info = BootInfo {
+            cmdline: buf.as_ptr() as u64,
+            ..Default::default()
+        };
+        let args = unsafe { info.cmdline_args() };
+        assert_eq!(
+            args.clone()
+                .value_of("panic")
+                .and_then(|v| v.parse::<u64>().ok()),
+            Some(10)
+        );
+        assert_eq!(args.value_of("name"), Some("?"));
+
+        let long = [b'a' as u16; BootInfo::MAX_CMDLINE_LEN * 2];
+        let len = BootInfo::encode_cmdline(long, &mut buf);
+        assert_eq!(len, BootInfo::MAX_CMDLINE_LEN - 1);
+        assert_eq!(buf[len], 0);
+        assert_eq!(unsafe { info.cmdline_str() }.map(|v| v.len()), Some(len));
+    }
+
+    #[test]
+    fn real_bitmap() {
+        let mut info = BootInfo::default();
+        assert!(!info.is_real_page_free(0));
+
+        for index in [1, 31, 32, 0x9F, 255] {
+            info.mark_real_page(index, true);
+            assert!(info.is_real_page_free(index));
+        }
+        assert_eq!(info.real_bitmap[0], 0x8000_0002);
+        assert_eq!(info.real_bitmap[1], 0x0000_0001);
+        assert_eq!(info.real_bitmap[4], 0x8000_0000);
+        assert_eq!(info.real_bitmap[7], 0x8000_0000);
+        assert!(!info.is_real_page_free(2));
+        assert!(!info.is_real_page_free(33));
+
+        info.mark_real_page(31, false);
+        assert!(!info.is_real_page_free(31));
+        assert!(info.is_real_page_free(1));
+        assert_eq!(info.real_bitmap[0], 0x0000_0002);
+
+        info.mark_real_page(256, true);
+        assert!(!info.is_real_page_free(256));
+    }
+}
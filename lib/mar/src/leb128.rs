@@ -46,6 +46,29 @@ impl Leb128Writer {
         self.inner.clear();
     }
 
+    /// Reserves capacity for at least `additional` more bytes.
+    #[inline]
+    pub fn reserve(&mut self, additional: usize) -> Result<(), WriteError> {
+        self.inner
+            .try_reserve(additional)
+            .map_err(|_| WriteError::OutOfMemory)
+    }
+
+    /// Appends `len` bytes filled by `f`, without an intermediate buffer.
+    ///
+    /// The slice passed to `f` is initialized with zeros.
+    pub fn write_with<F>(&mut self, len: usize, f: F) -> Result<(), WriteError>
+    where
+        F: FnOnce(&mut [u8]),
+    {
+        let offset = self.inner.len();
+        self.reserve(len)?;
+        self.inner.resize(offset + len, 0);
+        f(&mut self.inner[offset..]);
+
+        Ok(())
+    }
+
     pub fn write_bytes(&mut self, bytes: &[u8]) -> Result<(), WriteError> {
         let additional: usize = bytes.len();
         if self.inner.capacity() - self.inner.len() < additional {
@@ -581,6 +604,25 @@ mod tests {
         assert_eq!(writer.as_slice(), &[0xEF, 0xFD, 0xB6, 0xF5, 0x7D]);
     }
 
+    #[test]
+    fn leb128_write_with() {
+        let content = (0..1000).map(|v| v as u8).collect::<Vec<_>>();
+
+        let mut expected = Leb128Writer::new();
+        expected.write(content.len()).unwrap();
+        expected.write_bytes(&content).unwrap();
+
+        let mut writer = Leb128Writer::new();
+        writer.reserve(content.len() + 2).unwrap();
+        writer.write(content.len()).unwrap();
+        writer
+            .write_with(content.len(), |buf| buf.copy_from_slice(&content))
+            .unwrap();
+        writer.write_with(0, |buf| assert!(buf.is_empty())).unwrap();
+
+        assert_eq!(writer.as_slice(), expected.as_slice());
+    }
+
     #[test]
     fn leb128_read_write() {
         for i in 0..64 {
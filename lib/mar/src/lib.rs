@@ -35,8 +35,8 @@
 #![cfg_attr(not(test), no_std)]
 
 extern crate alloc;
-use alloc::vec::Vec;
-use core::mem::transmute;
+use alloc::{string::String, vec::Vec};
+use megstd::mem::try_from_bytes;
 
 pub const MAGIC: u32 = 0x0002beef;
 
@@ -73,17 +73,25 @@ impl Header {
         self.magic == MAGIC
     }
 
-    fn from_slice<'a>(slice: &'a [u8; Self::SIZE_OF_HEADER]) -> Result<&'a Self, ReadError> {
-        let header: &Self = unsafe { transmute(slice) };
+    /// Reads the header in place, the slice must be aligned for `Self`.
+    fn from_slice(slice: &[u8; Self::SIZE_OF_HEADER]) -> Result<&Self, ReadError> {
+        let header: &Self = unsafe { try_from_bytes(slice) }.ok_or(ReadError::InvalidData)?;
         header
             .is_valid()
-            .then(|| header)
+            .then_some(header)
             .ok_or(ReadError::InvalidData)
     }
 
     #[inline]
     fn into_bytes(self) -> [u8; Self::SIZE_OF_HEADER] {
-        unsafe { transmute(self) }
+        let mut bytes = [0; Self::SIZE_OF_HEADER];
+        for (index, field) in [self.magic, self._reserved, self.offset, self.size]
+            .into_iter()
+            .enumerate()
+        {
+            bytes[index * 4..index * 4 + 4].copy_from_slice(&field.to_le_bytes());
+        }
+        bytes
     }
 }
 
@@ -161,6 +169,8 @@ impl Entry<'_> {
                 };
                 let total_size = leading.len() + payload.len();
 
+                // The tag and the LEB128 encoded size take 11 bytes at most
+                writer.reserve(total_size + 11)?;
                 writer.write_byte(TAG_FILE)?;
                 writer.write(total_size)?;
                 writer.write_bytes(&leading)?;
@@ -203,7 +213,7 @@ impl<'a> ArchiveReader<'a> {
         let header = reader
             .read_bytes(Header::SIZE_OF_HEADER)
             .and_then(|v| v.try_into().map_err(|_| ReadError::UnexpectedEof))
-            .and_then(|v| Header::from_slice(v))?;
+            .and_then(Header::from_slice)?;
         let offset: usize = header
             .offset
             .try_into()
@@ -231,6 +241,55 @@ impl ArchiveReader<'static> {
 }
 
 impl<'a> ArchiveReader<'a> {
+    /// Returns the content of the file at the path relative to the root of the archive.
+    ///
+    /// Paths of namespaces are also relative to the root, files belong to the last namespace.
+    pub fn find(self, path: &str) -> Option<&'a [u8]> {
+        let mut namespace = "";
+        for entry in self {
+            match entry {
+                Entry::Namespace(name, _) => namespace = name,
+                Entry::File(name, _, content) => {
+                    if Self::_components(namespace)
+                        .chain(Self::_components(name))
+                        .eq(Self::_components(path))
+                    {
+                        return Some(content);
+                    }
+                }
+                Entry::End => break,
+            }
+        }
+        None
+    }
+
+    /// Returns the files with their full paths relative to the root of the archive.
+    ///
+    /// The components of the paths are joined with `/`, without the leading one.
+    pub fn entries_with_paths(self) -> impl Iterator<Item = (String, Entry<'a>)> {
+        let mut namespace = "";
+        self.take_while(|entry| !matches!(entry, Entry::End))
+            .filter_map(move |entry| match entry {
+                Entry::Namespace(name, _) => {
+                    namespace = name;
+                    None
+                }
+                Entry::File(name, _, _) => {
+                    let path = Self::_components(namespace)
+                        .chain(Self::_components(name))
+                        .collect::<Vec<_>>()
+                        .join("/");
+                    Some((path, entry))
+                }
+                Entry::End => None,
+            })
+    }
+
+    #[inline]
+    fn _components(path: &str) -> impl Iterator<Item = &str> {
+        path.split('/').filter(|v| !v.is_empty() && *v != ".")
+    }
+
     pub fn reader_test(&mut self) -> &mut Leb128Reader<'a> {
         &mut self.reader
     }
@@ -266,3 +325,59 @@ impl<'a> Iterator for ArchiveReader<'a> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn archive() -> Vec<u8> {
+        let mut writer = ArchiveWriter::new();
+        for entry in [
+            Entry::File("readme.txt", ExtendedAttributes::empty(), b"hello"),
+            Entry::Namespace("fonts", ExtendedAttributes::empty()),
+            Entry::File("small.fnt", ExtendedAttributes::empty(), &[1, 2, 3]),
+            Entry::Namespace("/images/icons/", ExtendedAttributes::empty()),
+            Entry::File("apps.png", ExtendedAttributes::empty(), &[4, 5]),
+        ] {
+            writer.write(entry).unwrap();
+        }
+        writer.finalize(&[]).unwrap()
+    }
+
+    #[test]
+    fn find() {
+        let archive = archive();
+        let find = |path| ArchiveReader::from_slice(&archive).unwrap().find(path);
+
+        assert_eq!(find("readme.txt"), Some(b"hello".as_slice()));
+        assert_eq!(find("fonts/small.fnt"), Some([1, 2, 3].as_slice()));
+        assert_eq!(find("/images/icons/apps.png"), Some([4, 5].as_slice()));
+        assert_eq!(find("./fonts//small.fnt"), Some([1, 2, 3].as_slice()));
+
+        assert_eq!(find("small.fnt"), None);
+        assert_eq!(find("fonts/large.fnt"), None);
+        assert_eq!(find("fonts"), None);
+    }
+
+    #[test]
+    fn entries_with_paths() {
+        let archive = archive();
+        let paths = ArchiveReader::from_slice(&archive)
+            .unwrap()
+            .entries_with_paths()
+            .map(|(path, entry)| match entry {
+                Entry::File(_, _, content) => (path, content),
+                _ => unreachable!(),
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            paths,
+            [
+                ("readme.txt".into(), b"hello".as_slice()),
+                ("fonts/small.fnt".into(), [1, 2, 3].as_slice()),
+                ("images/icons/apps.png".into(), [4, 5].as_slice()),
+            ]
+        );
+    }
+}
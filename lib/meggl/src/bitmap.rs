@@ -12,6 +12,22 @@ use core::{
 };
 use paste::paste;
 
+/// How to blend the colors in `blt_blend_with`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BltOption {
+    /// The colors have straight alpha
+    StraightAlpha,
+    /// The colors have premultiplied alpha, which avoids dark fringes on translucent edges
+    PremultipliedAlpha,
+}
+
+impl Default for BltOption {
+    #[inline]
+    fn default() -> Self {
+        Self::StraightAlpha
+    }
+}
+
 pub trait Blt<T: Drawable>: Drawable {
     fn blt(&mut self, src: &T, origin: Point, rect: Rect);
 }
@@ -562,15 +578,31 @@ macro_rules! define_bitmap {
             }
 
             impl [<OwnedBitmap $suffix>] {
+                /// # Panics
+                ///
+                /// Panics if the slice is shorter than the size requires.
                 #[inline]
                 pub fn from_boxed_slice(slice: Box<[$slice_type]>, size: Size) -> Self {
+                    let stride = <Self as Drawable>::ColorType::stride_for(size.width());
+                    assert!(
+                        size.width() >= 0
+                            && size.height() >= 0
+                            && stride
+                                .checked_mul(size.height() as usize)
+                                .is_some_and(|len| slice.len() >= len),
+                        "bitmap slice too short for {:?}",
+                        size,
+                    );
                     Self {
                         size: size,
-                        stride: <Self as Drawable>::ColorType::stride_for(size.width()),
+                        stride,
                         slice: UnsafeCell::new(slice),
                     }
                 }
 
+                /// # Panics
+                ///
+                /// Panics if the vector is shorter than the size requires.
                 #[inline]
                 pub fn from_vec(vec: Vec<$slice_type>, size: Size) -> Self {
                     Self::from_boxed_slice(vec.into_boxed_slice(), size)
@@ -737,6 +769,33 @@ macro_rules! define_bitmap {
                 }
             }
 
+            impl [<BitmapRefMut $suffix>]<'_> {
+                /// Returns a mutable view of the area clipped to the bounds, sharing the storage.
+                ///
+                /// Returns `None` if the area does not overlap the bounds.
+                pub fn sub_bitmap(&mut self, rect: Rect) -> Option<[<BitmapRefMut $suffix>]<'_>> {
+                    let rect = rect.intersection(self.bounds())?;
+                    let stride = self.stride();
+                    let offset = rect.min_x() as usize + rect.min_y() as usize * stride;
+                    let new_len = (rect.height() as usize - 1) * stride + rect.width() as usize;
+                    let slice = &mut self.slice_mut()[offset..offset + new_len];
+                    Some([<BitmapRefMut $suffix>] {
+                        size: rect.size(),
+                        stride,
+                        slice: UnsafeCell::new(slice),
+                    })
+                }
+            }
+
+            impl [<OwnedBitmap $suffix>] {
+                /// Returns a mutable view of the area clipped to the bounds, sharing the storage.
+                #[inline]
+                pub fn sub_bitmap(&mut self, rect: Rect) -> Option<[<BitmapRefMut $suffix>]<'_>> {
+                    let bitmap: &mut [<BitmapRefMut $suffix>] = self.as_mut();
+                    bitmap.sub_bitmap(rect)
+                }
+            }
+
             impl [<BitmapRefMut $suffix>]<'_> {
                 pub fn copy(&mut self, origin: Point, rect: Rect) {
                     let (dx, dy, sx, sy, width, height) =
@@ -790,10 +849,10 @@ macro_rules! define_bitmap {
                             .add(sx as usize + sy as usize * ss) as *const _;
 
                         if ds == width && ss == width {
-                            dest_fb.copy_from_nonoverlapping(src_fb, width * height);
+                            memory::copy_nonoverlapping(src_fb, dest_fb, width * height);
                         } else {
                             for _ in 0..height {
-                                dest_fb.copy_from_nonoverlapping(src_fb, width);
+                                memory::copy_nonoverlapping(src_fb, dest_fb, width);
                                 dest_fb = dest_fb.add(ds);
                                 src_fb = src_fb.add(ss);
                             }
@@ -1025,6 +1084,44 @@ impl BitmapRefMut32<'_> {
         }
     }
 
+    /// Copies the whole source, skipping the rows that are identical to the previous frame.
+    ///
+    /// `prev` must be the previous contents of `src`; if it is absent or has a different size,
+    /// all rows are copied. Returns the number of rows copied.
+    pub fn blt32_if_changed(
+        &mut self,
+        src: &BitmapRef32,
+        origin: Point,
+        prev: Option<&BitmapRef32>,
+    ) -> usize {
+        let (dx, dy, sx, sy, width, height) =
+            _adjust_blt_coords(self.size(), src.size(), origin, src.bounds());
+        if width <= 0 || height <= 0 {
+            return 0;
+        }
+        let width = width as usize;
+        let prev = prev.filter(|v| v.size() == src.size());
+
+        let ds = self.stride();
+        let ss = src.stride();
+        let dest = self.slice_mut();
+        let mut count = 0;
+        for y in 0..height as usize {
+            let src_cursor = sx as usize + (sy as usize + y) * ss;
+            let src_row = &src.slice()[src_cursor..src_cursor + width];
+            if let Some(prev) = prev {
+                let prev_cursor = sx as usize + (sy as usize + y) * prev.stride();
+                if prev.slice()[prev_cursor..prev_cursor + width] == *src_row {
+                    continue;
+                }
+            }
+            let dest_cursor = dx as usize + (dy as usize + y) * ds;
+            dest[dest_cursor..dest_cursor + width].copy_from_slice(src_row);
+            count += 1;
+        }
+        count
+    }
+
     pub fn blt_blend(&mut self, src: &BitmapRef32, origin: Point, rect: Rect, opacity: Alpha8) {
         let (dx, dy, sx, sy, width, height) =
             _adjust_blt_coords(self.size(), src.size(), origin, rect);
@@ -1057,6 +1154,46 @@ impl BitmapRefMut32<'_> {
         }
     }
 
+    /// Blends the bitmap, using the blending method of the option.
+    pub fn blt_blend_with(
+        &mut self,
+        src: &BitmapRef32,
+        origin: Point,
+        rect: Rect,
+        option: BltOption,
+    ) {
+        match option {
+            BltOption::StraightAlpha => self.blt_blend(src, origin, rect, Alpha8::OPAQUE),
+            BltOption::PremultipliedAlpha => {
+                let (dx, dy, sx, sy, width, height) =
+                    _adjust_blt_coords(self.size(), src.size(), origin, rect);
+                if width <= 0 || height <= 0 {
+                    return;
+                }
+                let width = width as usize;
+                let height = height as usize;
+
+                let ds = self.stride();
+                let ss = src.stride();
+                let mut dest_cursor = dx as usize + dy as usize * ds;
+                let mut src_cursor = sx as usize + sy as usize * ss;
+                let dest_fb = self.slice_mut();
+                let src_fb = src.slice();
+                for _ in 0..height {
+                    memory_colors::_memcpy_blend32_premultiplied(
+                        dest_fb,
+                        dest_cursor,
+                        src_fb,
+                        src_cursor,
+                        width,
+                    );
+                    dest_cursor += ds;
+                    src_cursor += ss;
+                }
+            }
+        }
+    }
+
     pub fn blt8(&mut self, src: &BitmapRef8, origin: Point, rect: Rect, palette: &[u32; 256]) {
         self.blt_convert(src, origin, rect, |c| {
             ARGB8888::from_argb(palette[c.0 as usize])
@@ -1272,6 +1409,15 @@ impl<'a> BitmapRefMut<'a> {
             BitmapRefMut::Argb32(v) => v.view(rect).map(|v| BitmapRefMut::Argb32(v)),
         }
     }
+
+    /// Returns a mutable view of the area clipped to the bounds, sharing the storage.
+    /// The function returns None if the rectangle does not overlap the bitmap.
+    pub fn sub_bitmap(&mut self, rect: Rect) -> Option<BitmapRefMut<'_>> {
+        match self {
+            BitmapRefMut::Indexed(v) => v.sub_bitmap(rect).map(BitmapRefMut::Indexed),
+            BitmapRefMut::Argb32(v) => v.sub_bitmap(rect).map(BitmapRefMut::Argb32),
+        }
+    }
 }
 
 impl BitmapRefMut<'_> {
@@ -1531,6 +1677,70 @@ impl<'a> AsMut<BitmapRefMut<'a>> for OwnedBitmap {
     }
 }
 
+/// An 8-bit indexed bitmap with its own 256-entry palette.
+///
+/// Pixels hold palette indexes, so changing a palette entry recolors every pixel using that index at display time.
+pub struct PalettedBitmap8 {
+    bitmap: OwnedBitmap8,
+    palette: [TrueColor; 256],
+}
+
+impl Drawable for PalettedBitmap8 {
+    type ColorType = IndexedColor;
+
+    #[inline]
+    fn size(&self) -> Size {
+        self.bitmap.size()
+    }
+}
+
+impl PalettedBitmap8 {
+    /// Creates a new bitmap with the default system palette.
+    #[inline]
+    pub fn new(size: Size, bg_color: IndexedColor) -> Self {
+        let mut palette = [TrueColor::default(); 256];
+        for (entry, argb) in palette.iter_mut().zip(IndexedColor::COLOR_PALETTE.iter()) {
+            *entry = TrueColor::from_argb(*argb);
+        }
+        Self {
+            bitmap: OwnedBitmap8::new(size, bg_color),
+            palette,
+        }
+    }
+
+    #[inline]
+    pub fn bitmap(&self) -> &OwnedBitmap8 {
+        &self.bitmap
+    }
+
+    #[inline]
+    pub fn bitmap_mut(&mut self) -> &mut OwnedBitmap8 {
+        &mut self.bitmap
+    }
+
+    #[inline]
+    pub fn palette(&self) -> &[TrueColor] {
+        &self.palette
+    }
+
+    #[inline]
+    pub fn set_palette_entry(&mut self, index: u8, color: TrueColor) {
+        self.palette[index as usize] = color;
+    }
+
+    /// Returns the actual color of the specified index.
+    #[inline]
+    pub fn resolve(&self, color: IndexedColor) -> TrueColor {
+        self.palette[color.0 as usize]
+    }
+
+    /// Draws the bitmap to the 32-bit bitmap through the palette.
+    #[inline]
+    pub fn blt_to(&self, dest: &mut BitmapRefMut32, origin: Point, rect: Rect) {
+        dest.blt_convert(&self.bitmap, origin, rect, |c| self.resolve(c));
+    }
+}
+
 /// A special bitmap type that can be used for operations such as transparency and shading.
 pub struct OperationalBitmap {
     size: Size,
@@ -2054,16 +2264,18 @@ mod memory_colors {
 
     #[inline]
     pub fn _memset_colors16(slice: &mut [RGB565], cursor: usize, count: usize, color: RGB565) {
-        for v in unsafe { slice.get_unchecked_mut(cursor..cursor + count) }.iter_mut() {
-            *v = color;
-        }
+        memory::fill(
+            unsafe { slice.get_unchecked_mut(cursor..cursor + count) },
+            color,
+        );
     }
 
     #[inline]
     pub fn _memset_colors32(slice: &mut [ARGB8888], cursor: usize, count: usize, color: ARGB8888) {
-        for v in unsafe { slice.get_unchecked_mut(cursor..cursor + count) }.iter_mut() {
-            *v = color;
-        }
+        memory::fill(
+            unsafe { slice.get_unchecked_mut(cursor..cursor + count) },
+            color,
+        );
     }
 
     // Alpha blending
@@ -2081,6 +2293,21 @@ mod memory_colors {
             *dest = dest.blend_draw(*src);
         }
     }
+
+    #[inline]
+    pub fn _memcpy_blend32_premultiplied(
+        dest: &mut [ARGB8888],
+        dest_cursor: usize,
+        src: &[ARGB8888],
+        src_cursor: usize,
+        count: usize,
+    ) {
+        let dest = unsafe { &mut dest.get_unchecked_mut(dest_cursor..dest_cursor + count) };
+        let src = unsafe { &src.get_unchecked(src_cursor..src_cursor + count) };
+        for (dest, src) in dest.iter_mut().zip(src.iter()) {
+            *dest = dest.blend_draw_premultiplied(*src);
+        }
+    }
 }
 
 define_bitmap!(1, u8, Monochrome, Octet,);
@@ -1,7 +1,7 @@
 use core::{
     fmt,
     mem::transmute,
-    ops::{Add, AddAssign, Sub, SubAssign},
+    ops::{Add, AddAssign, Mul, MulAssign, Sub, SubAssign},
 };
 
 /// Common color trait
@@ -242,6 +242,12 @@ impl Alpha8 {
         Self(self.0.saturating_sub(rhs.0))
     }
 
+    /// Multiplies two alpha values, as when applying a layer opacity to a translucent color.
+    #[inline]
+    pub const fn multiply(self, rhs: Self) -> Self {
+        Self(((self.0 as u32 * rhs.0 as u32 + 127) / 255) as u8)
+    }
+
     #[inline]
     pub const fn is_transparent(&self) -> bool {
         self.0 == Self::TRANSPARENT.0
@@ -334,6 +340,22 @@ impl SubAssign<Self> for Alpha8 {
     }
 }
 
+impl Mul<Self> for Alpha8 {
+    type Output = Self;
+
+    #[inline]
+    fn mul(self, rhs: Self) -> Self::Output {
+        self.multiply(rhs)
+    }
+}
+
+impl MulAssign<Self> for Alpha8 {
+    #[inline]
+    fn mul_assign(&mut self, rhs: Self) {
+        *self = self.multiply(rhs);
+    }
+}
+
 impl Add<u8> for Alpha8 {
     type Output = Self;
 
@@ -451,17 +473,26 @@ impl ARGB8888 {
 
     #[inline]
     pub const fn brightness(&self) -> Option<u8> {
-        let cc = self.components();
-        if cc.is_transparent() {
+        if self.components().is_transparent() {
             None
         } else {
-            Some(
-                ((cc.r as usize * 19589 + cc.g as usize * 38444 + cc.b as usize * 7502 + 32767)
-                    >> 16) as u8,
-            )
+            Some(self.luminance())
         }
     }
 
+    /// Returns the luma weighted by Rec.601, ignoring the alpha channel.
+    #[inline]
+    pub const fn luminance(&self) -> u8 {
+        let cc = self.components();
+        ((cc.r as usize * 19589 + cc.g as usize * 38444 + cc.b as usize * 7502 + 32767) >> 16) as u8
+    }
+
+    /// Returns the gray color with the same luminance and opacity.
+    #[inline]
+    pub const fn to_grayscale(&self) -> Self {
+        Self::from_gray(self.luminance(), self.opacity())
+    }
+
     #[inline]
     pub const fn opacity(&self) -> Alpha8 {
         Alpha8((self.0 >> 24) as u8)
@@ -517,6 +548,24 @@ impl ARGB8888 {
         }
     }
 
+    /// Blends the color whose alpha is premultiplied, like `blend_draw`.
+    ///
+    /// Both colors must be in premultiplied alpha.
+    #[inline]
+    pub fn blend_draw_premultiplied(&self, rhs: Self) -> Self {
+        if rhs.is_opaque() {
+            return rhs;
+        } else if rhs.is_transparent() {
+            return *self;
+        }
+        let alpha_n = 255 - rhs.opacity().0 as u32;
+        self.blending(
+            rhs,
+            |l, r| (r as u32 + (l as u32 * alpha_n + 127) / 255) as u8,
+            |l, r| Alpha8((r.0 as u32 + (l.0 as u32 * alpha_n + 127) / 255) as u8),
+        )
+    }
+
     #[inline]
     pub const fn is_transparent(&self) -> bool {
         self.opacity().is_transparent()
@@ -602,6 +651,41 @@ impl ColorComponents {
     pub const fn is_transparent(self) -> bool {
         self.a.is_transparent()
     }
+
+    /// Converts straight alpha into premultiplied alpha.
+    #[inline]
+    pub const fn premultiplied(self) -> Self {
+        let a = self.a.0 as u32;
+        Self {
+            r: ((self.r as u32 * a + 127) / 255) as u8,
+            g: ((self.g as u32 * a + 127) / 255) as u8,
+            b: ((self.b as u32 * a + 127) / 255) as u8,
+            a: self.a,
+        }
+    }
+
+    /// Converts premultiplied alpha into straight alpha.
+    #[inline]
+    pub const fn unpremultiplied(self) -> Self {
+        let a = self.a.0 as u32;
+        if a == 0 {
+            return Self::from_rgba(0, 0, 0, self.a);
+        }
+        const fn unpremultiply(c: u8, a: u32) -> u8 {
+            let c = (c as u32 * 255 + a / 2) / a;
+            if c > 255 {
+                255
+            } else {
+                c as u8
+            }
+        }
+        Self {
+            r: unpremultiply(self.r, a),
+            g: unpremultiply(self.g, a),
+            b: unpremultiply(self.b, a),
+            a: self.a,
+        }
+    }
 }
 
 #[cfg(target_endian = "little")]
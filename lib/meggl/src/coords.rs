@@ -1,4 +1,4 @@
-use core::{convert::TryFrom, mem::swap, ops::*};
+use core::{convert::TryFrom, mem::swap, num::TryFromIntError, ops::*};
 pub use num_traits::Zero;
 
 #[repr(C)]
@@ -37,56 +37,95 @@ impl Point {
         }
     }
 
-    pub fn line_to<F>(&self, other: Point, mut f: F)
+    #[inline]
+    pub fn line_to<F>(&self, other: Point, f: F)
     where
         F: FnMut(Self),
     {
-        let c0 = *self;
-        let c1 = other;
+        line_points(*self, other).for_each(f);
+    }
 
-        let d = Point::new(
-            if c1.x > c0.x {
-                c1.x - c0.x
-            } else {
-                c0.x - c1.x
-            },
-            if c1.y > c0.y {
-                c1.y - c0.y
-            } else {
-                c0.y - c1.y
-            },
-        );
+    #[inline]
+    pub fn distance2(&self, other: Point) -> Distance2 {
+        self.sub(other).distance2()
+    }
+
+    /// Returns the nearest point within the bounds.
+    ///
+    /// If the bounds are empty, the point is returned as is.
+    #[inline]
+    pub const fn clamp(&self, bounds: Rect) -> Self {
+        let Ok(coords) = Coordinates::from_rect(bounds) else { return *self };
+        Self {
+            x: max(coords.left, min(self.x, coords.right - 1)),
+            y: max(coords.top, min(self.y, coords.bottom - 1)),
+        }
+    }
+}
+
+/// Returns an iterator over the rasterized points of the line, including both endpoints.
+#[inline]
+pub fn line_points(from: Point, to: Point) -> LinePoints {
+    LinePoints::new(from, to)
+}
 
-        let s = Self::new(
-            if c1.x > c0.x { 1 } else { -1 },
-            if c1.y > c0.y { 1 } else { -1 },
+/// An iterator over the points of a line using Bresenham's algorithm
+#[derive(Debug, Clone)]
+pub struct LinePoints {
+    cursor: Point,
+    to: Point,
+    d: Point,
+    s: Point,
+    e: isize,
+    is_done: bool,
+}
+
+impl LinePoints {
+    #[inline]
+    pub const fn new(from: Point, to: Point) -> Self {
+        let d = Point::new(abs(to.x - from.x), abs(to.y - from.y));
+        let s = Point::new(
+            if to.x > from.x { 1 } else { -1 },
+            if to.y > from.y { 1 } else { -1 },
         );
+        Self {
+            cursor: from,
+            to,
+            d,
+            s,
+            e: d.x - d.y,
+            is_done: false,
+        }
+    }
+}
 
-        let mut c0 = c0;
-        let mut e = d.x - d.y;
-        loop {
-            f(c0);
-            if c0.x == c1.x && c0.y == c1.y {
-                break;
-            }
-            let e2 = e + e;
-            if e2 > -d.y {
-                e -= d.y;
-                c0.x += s.x;
+impl Iterator for LinePoints {
+    type Item = Point;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.is_done {
+            return None;
+        }
+        let result = self.cursor;
+        if result == self.to {
+            self.is_done = true;
+        } else {
+            let e2 = self.e + self.e;
+            if e2 > -self.d.y {
+                self.e -= self.d.y;
+                self.cursor.x += self.s.x;
             }
-            if e2 < d.x {
-                e += d.x;
-                c0.y += s.y;
+            if e2 < self.d.x {
+                self.e += self.d.x;
+                self.cursor.y += self.s.y;
             }
         }
-    }
-
-    #[inline]
-    pub fn distance2(&self, other: Point) -> Distance2 {
-        self.sub(other).distance2()
+        Some(result)
     }
 }
 
+impl core::iter::FusedIterator for LinePoints {}
+
 impl Add<isize> for Point {
     type Output = Self;
 
@@ -125,6 +164,44 @@ impl SubAssign<isize> for Point {
     }
 }
 
+impl Mul<isize> for Point {
+    type Output = Self;
+
+    #[inline]
+    fn mul(self, rhs: isize) -> Self {
+        Point {
+            x: self.x * rhs,
+            y: self.y * rhs,
+        }
+    }
+}
+
+impl MulAssign<isize> for Point {
+    #[inline]
+    fn mul_assign(&mut self, rhs: isize) {
+        *self = self.mul(rhs);
+    }
+}
+
+impl Div<isize> for Point {
+    type Output = Self;
+
+    #[inline]
+    fn div(self, rhs: isize) -> Self {
+        Point {
+            x: self.x / rhs,
+            y: self.y / rhs,
+        }
+    }
+}
+
+impl DivAssign<isize> for Point {
+    #[inline]
+    fn div_assign(&mut self, rhs: isize) {
+        *self = self.div(rhs);
+    }
+}
+
 impl Sub<Self> for Point {
     type Output = Movement;
 
@@ -683,6 +760,43 @@ impl Rect {
         cl.left < cr.right && cr.left < cl.right && cl.top < cr.bottom && cr.top < cl.bottom
     }
 
+    /// Returns the overlapping area of two rectangles, or `None` if they do not overlap.
+    ///
+    /// Rectangles that only touch at an edge do not overlap.
+    #[inline]
+    pub const fn intersection(&self, other: Self) -> Option<Self> {
+        let Ok(cl) = Coordinates::from_rect(*self) else { return None };
+        let Ok(cr) = Coordinates::from_rect(other) else { return None };
+
+        let coords = cl.trimmed(cr);
+        if coords.is_valid() {
+            Some(Self::from_coordinates(coords))
+        } else {
+            None
+        }
+    }
+
+    /// Returns the smallest rectangle containing both rectangles.
+    ///
+    /// An empty rectangle does not contribute to the result.
+    #[inline]
+    pub const fn union(&self, other: Self) -> Self {
+        let Ok(cl) = Coordinates::from_rect(*self) else { return other };
+        let Ok(cr) = Coordinates::from_rect(other) else { return *self };
+
+        Self::from_coordinates(cl.merged(cr))
+    }
+
+    #[inline]
+    pub fn contains_point(&self, point: Point) -> bool {
+        self.contains(point)
+    }
+
+    #[inline]
+    pub fn contains_rect(&self, rect: Rect) -> bool {
+        self.contains(rect)
+    }
+
     #[inline]
     pub const fn center(&self) -> Point {
         Point::new(self.mid_x(), self.mid_y())
@@ -739,6 +853,63 @@ impl From<(Point, Size)> for Rect {
     }
 }
 
+/// Conversions between the coordinates and the tuples of the other integer types
+macro_rules! int_conversions {
+    (@ty $_field:ident $ty:ty) => {
+        $ty
+    };
+    (@cast $type:ident ( $($field:ident),+ )) => {
+        impl $type {
+            /// Converts the components to another integer type,
+            /// or returns `None` if any of them overflows.
+            #[inline]
+            pub fn cast<T>(&self) -> Option<($(int_conversions!(@ty $field T)),+)>
+            where
+                T: TryFrom<isize>,
+            {
+                Some(($(T::try_from(self.$field()).ok()?),+))
+            }
+        }
+    };
+    (@from $type:ident ( $($field:ident),+ ) $from:ty) => {
+        impl From<($(int_conversions!(@ty $field $from)),+)> for $type {
+            #[inline]
+            fn from(value: ($(int_conversions!(@ty $field $from)),+)) -> Self {
+                let ($($field),+) = value;
+                Self::new($($field as isize),+)
+            }
+        }
+    };
+    (@try_from $type:ident ( $($field:ident),+ ) $from:ty) => {
+        impl TryFrom<($(int_conversions!(@ty $field $from)),+)> for $type {
+            type Error = TryFromIntError;
+
+            #[inline]
+            fn try_from(value: ($(int_conversions!(@ty $field $from)),+)) -> Result<Self, Self::Error> {
+                let ($($field),+) = value;
+                Ok(Self::new($(isize::try_from($field)?),+))
+            }
+        }
+    };
+    ($type:ident $fields:tt, [$($from:ty),*], [$($try_from:ty),*]) => {
+        int_conversions!(@cast $type $fields);
+        $(int_conversions!(@from $type $fields $from);)*
+        $(int_conversions!(@try_from $type $fields $try_from);)*
+    };
+}
+
+int_conversions!(Point(x, y), [i8, i16, i32, u8, u16], [i64, u32, u64, usize]);
+int_conversions!(
+    Size(width, height),
+    [i8, i16, i32, u8, u16],
+    [i64, u32, u64, usize]
+);
+int_conversions!(
+    Rect(min_x, min_y, width, height),
+    [i8, i16, i32, u8, u16],
+    [i64, u32, u64, usize]
+);
+
 impl Add<Size> for Rect {
     type Output = Self;
 
@@ -1061,6 +1232,121 @@ impl From<Size> for Coordinates {
     }
 }
 
+/// A region to be redrawn, coalesced into a small number of rectangles
+///
+/// Overlapping rectangles are merged, and when the region is full,
+/// the rectangle is merged into the one that grows the least.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct DirtyRegion {
+    rects: [Coordinates; Self::MAX_RECTS],
+    len: usize,
+}
+
+impl DirtyRegion {
+    pub const MAX_RECTS: usize = 8;
+
+    pub const EMPTY: Self = Self::new();
+
+    #[inline]
+    pub const fn new() -> Self {
+        Self {
+            rects: [Coordinates::VOID; Self::MAX_RECTS],
+            len: 0,
+        }
+    }
+
+    #[inline]
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    #[inline]
+    pub fn clear(&mut self) {
+        self.len = 0;
+    }
+
+    /// Returns the region and leaves this empty.
+    #[inline]
+    pub fn take(&mut self) -> Self {
+        core::mem::take(self)
+    }
+
+    #[inline]
+    pub fn add_rect(&mut self, rect: Rect) {
+        if let Ok(coords) = Coordinates::from_rect(rect) {
+            self.add(coords);
+        }
+    }
+
+    pub fn add(&mut self, coords: Coordinates) {
+        if !coords.is_valid() {
+            return;
+        }
+        let mut coords = coords;
+        // Merging may make the rectangle overlap the others
+        let mut index = 0;
+        while index < self.len {
+            let rect = self.rects[index];
+            if rect.trimmed(coords) == coords {
+                return;
+            }
+            if rect.trimmed(coords).is_valid() {
+                coords.merge(rect);
+                self.len -= 1;
+                self.rects[index] = self.rects[self.len];
+                index = 0;
+            } else {
+                index += 1;
+            }
+        }
+        if self.len < Self::MAX_RECTS {
+            self.rects[self.len] = coords;
+            self.len += 1;
+            return;
+        }
+        let index = (0..self.len)
+            .min_by_key(|&index| {
+                let rect = self.rects[index];
+                Self::area(rect.merged(coords)) - Self::area(rect)
+            })
+            .unwrap();
+        let merged = self.rects[index].merged(coords);
+        self.len -= 1;
+        self.rects[index] = self.rects[self.len];
+        self.add(merged);
+    }
+
+    #[inline]
+    fn area(coords: Coordinates) -> usize {
+        let size = coords.size();
+        size.width() as usize * size.height() as usize
+    }
+
+    /// Returns the bounding box of the region.
+    #[inline]
+    pub fn bounds(&self) -> Coordinates {
+        self.coords()
+            .fold(Coordinates::VOID, |acc, v| acc.merged(*v))
+    }
+
+    #[inline]
+    pub fn coords(&self) -> impl Iterator<Item = &Coordinates> {
+        self.rects[..self.len].iter()
+    }
+
+    #[inline]
+    pub fn rects(&self) -> impl Iterator<Item = Rect> + '_ {
+        self.coords().map(|v| Rect::from(*v))
+    }
+}
+
+impl Default for DirtyRegion {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 #[repr(C)]
 #[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
 pub struct EdgeInsets {
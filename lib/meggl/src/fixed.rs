@@ -0,0 +1,216 @@
+//! Fixed-point arithmetic for targets without a fast FPU
+
+use core::{
+    fmt,
+    ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Sub, SubAssign},
+};
+
+/// Signed Q16.16 fixed-point number
+#[repr(transparent)]
+#[derive(Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Fix16(i32);
+
+impl Fix16 {
+    pub const FRACTION_BITS: u32 = 16;
+    pub const ZERO: Self = Self(0);
+    pub const ONE: Self = Self(1 << Self::FRACTION_BITS);
+    pub const HALF: Self = Self(1 << (Self::FRACTION_BITS - 1));
+    pub const MIN: Self = Self(i32::MIN);
+    pub const MAX: Self = Self(i32::MAX);
+
+    #[inline]
+    pub const fn from_raw(raw: i32) -> Self {
+        Self(raw)
+    }
+
+    #[inline]
+    pub const fn into_raw(self) -> i32 {
+        self.0
+    }
+
+    /// Converts from an integer, saturating if out of range.
+    #[inline]
+    pub const fn from_int(value: isize) -> Self {
+        if value > i16::MAX as isize {
+            Self::MAX
+        } else if value < i16::MIN as isize {
+            Self::MIN
+        } else {
+            Self((value as i32) << Self::FRACTION_BITS)
+        }
+    }
+
+    /// Returns the integer part, rounding toward negative infinity.
+    #[inline]
+    pub const fn floor(self) -> isize {
+        (self.0 >> Self::FRACTION_BITS) as isize
+    }
+
+    /// Returns the nearest integer, rounding half up.
+    #[inline]
+    pub const fn round(self) -> isize {
+        ((self.0 as i64 + Self::HALF.0 as i64) >> Self::FRACTION_BITS) as isize
+    }
+
+    #[inline]
+    pub const fn from_f64(value: f64) -> Self {
+        let raw = value * Self::ONE.0 as f64;
+        if raw >= i32::MAX as f64 {
+            Self::MAX
+        } else if raw <= i32::MIN as f64 {
+            Self::MIN
+        } else if raw < 0.0 {
+            Self((raw - 0.5) as i32)
+        } else {
+            Self((raw + 0.5) as i32)
+        }
+    }
+
+    #[inline]
+    pub const fn into_f64(self) -> f64 {
+        self.0 as f64 / Self::ONE.0 as f64
+    }
+
+    #[inline]
+    pub const fn abs(self) -> Self {
+        Self(self.0.saturating_abs())
+    }
+
+    /// Multiplies with rounding to the nearest, saturating on overflow.
+    #[inline]
+    pub const fn saturating_mul(self, rhs: Self) -> Self {
+        let value = (self.0 as i64 * rhs.0 as i64 + Self::HALF.0 as i64) >> Self::FRACTION_BITS;
+        Self::saturate(value)
+    }
+
+    /// Divides, saturating on overflow. Division by zero saturates toward the sign of `self`.
+    #[inline]
+    pub const fn saturating_div(self, rhs: Self) -> Self {
+        if rhs.0 == 0 {
+            if self.0 < 0 {
+                Self::MIN
+            } else {
+                Self::MAX
+            }
+        } else {
+            Self::saturate(((self.0 as i64) << Self::FRACTION_BITS) / rhs.0 as i64)
+        }
+    }
+
+    #[inline]
+    const fn saturate(value: i64) -> Self {
+        if value > i32::MAX as i64 {
+            Self::MAX
+        } else if value < i32::MIN as i64 {
+            Self::MIN
+        } else {
+            Self(value as i32)
+        }
+    }
+}
+
+impl From<isize> for Fix16 {
+    #[inline]
+    fn from(value: isize) -> Self {
+        Self::from_int(value)
+    }
+}
+
+impl From<f64> for Fix16 {
+    #[inline]
+    fn from(value: f64) -> Self {
+        Self::from_f64(value)
+    }
+}
+
+impl From<Fix16> for f64 {
+    #[inline]
+    fn from(value: Fix16) -> Self {
+        value.into_f64()
+    }
+}
+
+impl fmt::Debug for Fix16 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Fix16({})", self.into_f64())
+    }
+}
+
+impl fmt::Display for Fix16 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.into_f64(), f)
+    }
+}
+
+impl Add<Self> for Fix16 {
+    type Output = Self;
+
+    #[inline]
+    fn add(self, rhs: Self) -> Self::Output {
+        Self(self.0.saturating_add(rhs.0))
+    }
+}
+
+impl AddAssign<Self> for Fix16 {
+    #[inline]
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs;
+    }
+}
+
+impl Sub<Self> for Fix16 {
+    type Output = Self;
+
+    #[inline]
+    fn sub(self, rhs: Self) -> Self::Output {
+        Self(self.0.saturating_sub(rhs.0))
+    }
+}
+
+impl SubAssign<Self> for Fix16 {
+    #[inline]
+    fn sub_assign(&mut self, rhs: Self) {
+        *self = *self - rhs;
+    }
+}
+
+impl Mul<Self> for Fix16 {
+    type Output = Self;
+
+    #[inline]
+    fn mul(self, rhs: Self) -> Self::Output {
+        self.saturating_mul(rhs)
+    }
+}
+
+impl MulAssign<Self> for Fix16 {
+    #[inline]
+    fn mul_assign(&mut self, rhs: Self) {
+        *self = *self * rhs;
+    }
+}
+
+impl Div<Self> for Fix16 {
+    type Output = Self;
+
+    #[inline]
+    fn div(self, rhs: Self) -> Self::Output {
+        self.saturating_div(rhs)
+    }
+}
+
+impl DivAssign<Self> for Fix16 {
+    #[inline]
+    fn div_assign(&mut self, rhs: Self) {
+        *self = *self / rhs;
+    }
+}
+
+impl Neg for Fix16 {
+    type Output = Self;
+
+    #[inline]
+    fn neg(self) -> Self::Output {
+        Self(self.0.saturating_neg())
+    }
+}
@@ -0,0 +1,458 @@
+//! Embedded bitmap fonts and font metrics
+
+use crate::{Movement, Point, Size};
+use alloc::vec::Vec;
+use core::num::NonZeroUsize;
+
+include!("font/megh0816.rs");
+
+/// Returns the horizontal advance of the single line string, including kerning.
+///
+/// The string drawn next starts at this advance, so that drawing can be chained.
+pub fn advance_of<W, K>(text: &str, width_of: W, kern: K) -> isize
+where
+    W: Fn(char) -> isize,
+    K: Fn(char, char) -> isize,
+{
+    let mut advance = 0;
+    let mut prev_char = None;
+    for c in text.chars() {
+        if let Some(prev_char) = prev_char {
+            advance += kern(prev_char, c);
+        }
+        advance += width_of(c);
+        prev_char = Some(c);
+    }
+    advance
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LineBreakMode {
+    NoWrap,
+    #[default]
+    CharWrapping,
+    WordWrapping,
+    TrancatingTail,
+}
+
+/// A line laid out by [line_statuses]
+///
+/// The positions are the indexes of the characters, not the bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LineStatus {
+    pub start_position: usize,
+    pub end_position: usize,
+    pub width: isize,
+    pub height: isize,
+}
+
+impl LineStatus {
+    #[inline]
+    const fn empty() -> Self {
+        Self {
+            start_position: 0,
+            end_position: 0,
+            width: 0,
+            height: 0,
+        }
+    }
+
+    #[inline]
+    fn new_line(&mut self, start_position: usize, width: isize, height: isize) {
+        self.start_position = start_position;
+        self.end_position = start_position;
+        self.width = width;
+        self.height = height;
+    }
+}
+
+/// Lays out the text into the lines that fit in the size, including kerning.
+///
+/// `max_lines` of `0` means no limit. The width of the last line is where the text ends,
+/// so that drawing can be continued from there.
+pub fn line_statuses<W, K>(
+    text: &str,
+    size: Size,
+    max_lines: usize,
+    line_break: LineBreakMode,
+    line_height: isize,
+    width_of: W,
+    kern: K,
+) -> Vec<LineStatus>
+where
+    W: Fn(char) -> isize,
+    K: Fn(char, char) -> isize,
+{
+    let max_lines = NonZeroUsize::new(max_lines)
+        .map(|v| v.get())
+        .unwrap_or(usize::MAX);
+    let limit_max_lines = 64;
+    let mut vec = Vec::with_capacity(usize::min(max_lines, limit_max_lines));
+
+    let no_wrap = max_lines == 1 && line_break == LineBreakMode::NoWrap;
+    let word_wrap = line_break == LineBreakMode::WordWrapping;
+
+    let mut current_line = LineStatus::empty();
+    current_line.height = line_height;
+    let mut current_height = current_line.height;
+    let mut prev_char = ' ';
+    // The last whitespace in the current line: (index, width before it, width through it)
+    let mut break_point: Option<(usize, isize, isize)> = None;
+    for (index, c) in text.chars().enumerate() {
+        if c == '\n' {
+            current_line.end_position = index;
+            current_height += current_line.height;
+            vec.push(current_line);
+            current_line = LineStatus::empty();
+            if vec.len() >= max_lines || current_height >= size.height() {
+                break;
+            }
+            current_line.new_line(index + 1, 0, line_height);
+            prev_char = ' ';
+            break_point = None;
+        } else {
+            current_line.end_position = index;
+            let current_width = width_of(c);
+            let new_line_width = current_line.width + kern(prev_char, c) + current_width;
+            let line_is_over = if no_wrap {
+                current_line.width > size.width
+            } else {
+                current_line.width > 0 && new_line_width > size.width
+            };
+            if line_is_over {
+                // Break after the last word if possible, otherwise break the word
+                let carried_over = match break_point.take() {
+                    Some((position, width_before, width_through))
+                        if word_wrap && !c.is_whitespace() =>
+                    {
+                        let carried_over = current_line.width - width_through;
+                        current_line.end_position = position;
+                        current_line.width = width_before;
+                        Some((position + 1, carried_over + kern(prev_char, c)))
+                    }
+                    _ => None,
+                };
+                // The whitespace at the break does not start the next line
+                let skips_whitespace = word_wrap && c.is_whitespace();
+
+                current_height += current_line.height;
+                vec.push(current_line);
+                current_line = LineStatus::empty();
+                if vec.len() >= max_lines || current_height >= size.height() {
+                    break;
+                }
+                match carried_over {
+                    Some((start_position, width)) => {
+                        current_line.new_line(start_position, width + current_width, line_height);
+                        current_line.end_position = index;
+                        prev_char = c;
+                    }
+                    None if skips_whitespace => {
+                        current_line.new_line(index + 1, 0, line_height);
+                        prev_char = ' ';
+                    }
+                    None => {
+                        current_line.new_line(index, current_width, line_height);
+                        prev_char = ' ';
+                    }
+                }
+            } else {
+                if c.is_whitespace() {
+                    break_point = Some((index, current_line.width, new_line_width));
+                }
+                current_line.width = new_line_width;
+                prev_char = c;
+            }
+        }
+    }
+    if vec.len() < max_lines && current_line.width > 0 {
+        current_line.end_position += 1;
+        vec.push(current_line);
+    }
+
+    vec
+}
+
+/// Fixed pitch bitmap font
+///
+/// The glyphs are 1 bit per pixel bitmaps of the printable ASCII characters.
+pub struct FixedFontDriver<'a> {
+    size: Size,
+    data: &'a [u8],
+    offset: Movement,
+    line_height: isize,
+    stride: usize,
+}
+
+impl FixedFontDriver<'_> {
+    const MAX_NOTDEF_STRIDE: usize = 256;
+
+    pub const fn new(width: usize, height: usize, data: &'static [u8]) -> FixedFontDriver<'static> {
+        let width = width as isize;
+        let height = height as isize;
+        let line_height = height * 5 / 4;
+        let offset = Movement::new(0, (line_height - height) / 2);
+        let stride = ((width as usize + 7) >> 3) * height as usize;
+        FixedFontDriver {
+            size: Size::new(width, height),
+            offset,
+            line_height,
+            stride,
+            data,
+        }
+    }
+
+    #[inline]
+    pub const fn size(&self) -> Size {
+        self.size
+    }
+
+    #[inline]
+    pub const fn width(&self) -> isize {
+        self.size.width
+    }
+
+    #[inline]
+    pub const fn line_height(&self) -> isize {
+        self.line_height
+    }
+
+    /// Glyph Data for Rasterized Font
+    #[inline]
+    fn glyph_for(&self, character: char) -> Option<&[u8]> {
+        let c = character as usize;
+        if c > 0x20 && c < 0x80 {
+            let base = self.stride * (c - 0x20);
+            self.data.get(base..base + self.stride)
+        } else {
+            None
+        }
+    }
+
+    /// Draws a hollow box as the `.notdef` glyph into the buffer.
+    fn notdef_glyph<'a>(&self, buf: &'a mut [u8]) -> Option<&'a [u8]> {
+        let width = self.size.width() as usize;
+        let height = self.size.height() as usize;
+        let row_stride = (width + 7) >> 3;
+        if width < 4 || height < 4 || self.stride > buf.len() {
+            return None;
+        }
+        let buf = &mut buf[..self.stride];
+        buf.fill(0);
+
+        let mut set_pixel = |x: usize, y: usize| buf[y * row_stride + x / 8] |= 0x80 >> (x & 7);
+        for x in 1..width - 1 {
+            set_pixel(x, 1);
+            set_pixel(x, height - 2);
+        }
+        for y in 1..height - 1 {
+            set_pixel(1, y);
+            set_pixel(width - 2, y);
+        }
+        Some(buf)
+    }
+
+    /// Passes the glyph of the character, its size and where to draw it to `f`.
+    ///
+    /// Every character takes a single cell. Characters not in this font are drawn as
+    /// the `.notdef` glyph, except for the whitespaces and controls that have no glyph.
+    #[inline]
+    pub fn draw_glyph<F>(&self, character: char, origin: Point, f: F)
+    where
+        F: FnOnce(&[u8], Size, Point),
+    {
+        let size = self.size;
+        let mut buf = [0u8; Self::MAX_NOTDEF_STRIDE];
+        if let Some(glyph) = self.glyph_for(character) {
+            f(glyph, size, origin + self.offset);
+        } else if let Some(glyph) = box_drawing_glyph(
+            character,
+            size.width() as usize,
+            self.line_height as usize,
+            &mut buf,
+        ) {
+            f(glyph, Size::new(size.width(), self.line_height), origin);
+        } else if (character as u32) >= 0x80
+            && !character.is_whitespace()
+            && !character.is_control()
+        {
+            if let Some(glyph) = self.notdef_glyph(&mut buf) {
+                f(glyph, size, origin + self.offset);
+            }
+        }
+    }
+}
+
+/// Draws the box drawing, block and shade characters of the cell size into the buffer.
+///
+/// The glyph is a 1 bit per pixel bitmap whose rows are padded to bytes.
+/// Unlike the other glyphs, these fill the whole line height so that adjacent lines connect.
+pub fn box_drawing_glyph(
+    character: char,
+    width: usize,
+    height: usize,
+    buf: &mut [u8],
+) -> Option<&[u8]> {
+    let shape = BoxShape::from_char(character)?;
+    let row_stride = (width + 7) >> 3;
+    let stride = row_stride * height;
+    if width < 4 || height < 4 || stride > buf.len() {
+        return None;
+    }
+    let buf = &mut buf[..stride];
+    buf.fill(0);
+
+    let mut set_pixel = |x: usize, y: usize| buf[y * row_stride + x / 8] |= 0x80 >> (x & 7);
+    match shape {
+        BoxShape::Lines([up, down, left, right]) => {
+            let (cx, cy) = ((width as isize - 1) / 2, (height as isize - 1) / 2);
+            let half = |a: u8, b: u8| a.max(b).saturating_sub(1) as isize;
+            let offsets = |weight: u8| match weight {
+                1 => &[0isize][..],
+                2 => &[-1, 1][..],
+                _ => &[][..],
+            };
+            // (weight, opposite, direction, [sides], is_vertical)
+            let arms = [
+                (up, down, -1, [left, right], true),
+                (down, up, 1, [left, right], true),
+                (left, right, -1, [up, down], false),
+                (right, left, 1, [up, down], false),
+            ];
+            for (weight, opposite, dir, [neg, pos], is_vertical) in arms {
+                let (center, cross, len) = if is_vertical {
+                    (cy, cx, height as isize)
+                } else {
+                    (cx, cy, width as isize)
+                };
+                for &offset in offsets(weight) {
+                    // How far the stroke goes beyond the center, so that double lines meet
+                    // at the correct rail
+                    let side = match offset {
+                        0 => 0,
+                        _ if offset < 0 => neg,
+                        _ => pos,
+                    };
+                    let reach = if side > 0 {
+                        -half(side, 0)
+                    } else if opposite > 0 {
+                        0
+                    } else if offset == 0 && neg > 0 && pos > 0 {
+                        -half(neg, pos)
+                    } else {
+                        half(neg, pos)
+                    };
+                    let range = if dir < 0 {
+                        0..=center + reach
+                    } else {
+                        center - reach..=len - 1
+                    };
+                    let q = (cross + offset) as usize;
+                    for p in range {
+                        if is_vertical {
+                            set_pixel(q, p as usize);
+                        } else {
+                            set_pixel(p as usize, q);
+                        }
+                    }
+                }
+            }
+        }
+        BoxShape::Block(left, top, right, bottom) => {
+            for y in height * top as usize / 2..height * bottom as usize / 2 {
+                for x in width * left as usize / 2..width * right as usize / 2 {
+                    set_pixel(x, y);
+                }
+            }
+        }
+        BoxShape::Shade(level) => {
+            for y in 0..height {
+                for x in 0..width {
+                    let is_set = match level {
+                        1 => (x & 1) == 0 && (y & 1) == 0,
+                        2 => ((x ^ y) & 1) == 0,
+                        _ => (x & 1) == 0 || (y & 1) == 0,
+                    };
+                    if is_set {
+                        set_pixel(x, y);
+                    }
+                }
+            }
+        }
+    }
+    Some(buf)
+}
+
+/// Shapes of the box drawing, block and shade characters drawn by [`box_drawing_glyph`]
+#[derive(Debug, Clone, Copy)]
+enum BoxShape {
+    /// Weights of the lines towards up, down, left and right (0: none, 1: single, 2: double)
+    Lines([u8; 4]),
+    /// Filled area as left, top, right and bottom in halves of the cell
+    Block(u8, u8, u8, u8),
+    /// Shade level from 1 (light) to 3 (dark)
+    Shade(u8),
+}
+
+impl BoxShape {
+    fn from_char(character: char) -> Option<Self> {
+        match character {
+            '\u{2591}' => Some(Self::Shade(1)),
+            '\u{2592}' => Some(Self::Shade(2)),
+            '\u{2593}' => Some(Self::Shade(3)),
+            '\u{2588}' => Some(Self::Block(0, 0, 2, 2)),
+            '\u{2580}' => Some(Self::Block(0, 0, 2, 1)),
+            '\u{2584}' => Some(Self::Block(0, 1, 2, 2)),
+            '\u{258C}' => Some(Self::Block(0, 0, 1, 2)),
+            '\u{2590}' => Some(Self::Block(1, 0, 2, 2)),
+            _ => BOX_DRAWING_LINES
+                .iter()
+                .find(|(c, _)| *c == character)
+                .map(|(_, lines)| Self::Lines(*lines)),
+        }
+    }
+}
+
+// (character, [up, down, left, right])
+static BOX_DRAWING_LINES: [(char, [u8; 4]); 40] = [
+    ('\u{2500}', [0, 0, 1, 1]),
+    ('\u{2502}', [1, 1, 0, 0]),
+    ('\u{250C}', [0, 1, 0, 1]),
+    ('\u{2510}', [0, 1, 1, 0]),
+    ('\u{2514}', [1, 0, 0, 1]),
+    ('\u{2518}', [1, 0, 1, 0]),
+    ('\u{251C}', [1, 1, 0, 1]),
+    ('\u{2524}', [1, 1, 1, 0]),
+    ('\u{252C}', [0, 1, 1, 1]),
+    ('\u{2534}', [1, 0, 1, 1]),
+    ('\u{253C}', [1, 1, 1, 1]),
+    ('\u{2550}', [0, 0, 2, 2]),
+    ('\u{2551}', [2, 2, 0, 0]),
+    ('\u{2552}', [0, 1, 0, 2]),
+    ('\u{2553}', [0, 2, 0, 1]),
+    ('\u{2554}', [0, 2, 0, 2]),
+    ('\u{2555}', [0, 1, 2, 0]),
+    ('\u{2556}', [0, 2, 1, 0]),
+    ('\u{2557}', [0, 2, 2, 0]),
+    ('\u{2558}', [1, 0, 0, 2]),
+    ('\u{2559}', [2, 0, 0, 1]),
+    ('\u{255A}', [2, 0, 0, 2]),
+    ('\u{255B}', [1, 0, 2, 0]),
+    ('\u{255C}', [2, 0, 1, 0]),
+    ('\u{255D}', [2, 0, 2, 0]),
+    ('\u{255E}', [1, 1, 0, 2]),
+    ('\u{255F}', [2, 2, 0, 1]),
+    ('\u{2560}', [2, 2, 0, 2]),
+    ('\u{2561}', [1, 1, 2, 0]),
+    ('\u{2562}', [2, 2, 1, 0]),
+    ('\u{2563}', [2, 2, 2, 0]),
+    ('\u{2564}', [0, 1, 2, 2]),
+    ('\u{2565}', [0, 2, 1, 1]),
+    ('\u{2566}', [0, 2, 2, 2]),
+    ('\u{2567}', [1, 0, 2, 2]),
+    ('\u{2568}', [2, 0, 1, 1]),
+    ('\u{2569}', [2, 0, 2, 2]),
+    ('\u{256A}', [1, 1, 2, 2]),
+    ('\u{256B}', [2, 2, 1, 1]),
+    ('\u{256C}', [2, 2, 2, 2]),
+];
@@ -0,0 +1,323 @@
+//! Image file decoders
+
+use super::*;
+use alloc::{vec, vec::Vec};
+use core::mem::swap;
+
+mod inflate;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageError {
+    /// The data is not in the expected format
+    NotSupported,
+    /// The data is corrupted
+    InvalidData,
+    /// The bit depth is not supported
+    UnsupportedBitDepth(u8),
+    /// The color type is not supported
+    UnsupportedColorType(u8),
+    /// Interlaced images are not supported
+    UnsupportedInterlace,
+}
+
+/// Decodes the PNG image.
+///
+/// Supports non-interlaced images with 8 bits per channel:
+/// grayscale, RGB, grayscale with alpha and RGBA.
+pub fn decode_png(bytes: &[u8]) -> Result<OwnedBitmap32, ImageError> {
+    const SIGNATURE: [u8; 8] = [0x89, 0x50, 0x4E, 0x47, 0x0D, 0x0A, 0x1A, 0x0A];
+    const IHDR_LEN: usize = 13;
+    const MAX_CHUNK_LEN: usize = i32::MAX as usize;
+
+    if bytes.len() < SIGNATURE.len() || bytes[..SIGNATURE.len()] != SIGNATURE {
+        return Err(ImageError::NotSupported);
+    }
+
+    let mut header = None;
+    let mut idat = Vec::new();
+    let mut cursor = SIGNATURE.len();
+    loop {
+        // length, type, data and CRC
+        let data_start = cursor + 8;
+        let chunk_header = bytes
+            .get(cursor..data_start)
+            .ok_or(ImageError::InvalidData)?;
+        let len = read_be32(&chunk_header[..4]) as usize;
+        if len > MAX_CHUNK_LEN {
+            return Err(ImageError::InvalidData);
+        }
+        let chunk_type = &chunk_header[4..8];
+        let data_end = data_start.checked_add(len).ok_or(ImageError::InvalidData)?;
+        let chunk_end = data_end.checked_add(4).ok_or(ImageError::InvalidData)?;
+        if chunk_end > bytes.len() {
+            return Err(ImageError::InvalidData);
+        }
+        let data = &bytes[data_start..data_end];
+        cursor = chunk_end;
+
+        match chunk_type {
+            b"IHDR" => {
+                if len != IHDR_LEN {
+                    return Err(ImageError::InvalidData);
+                }
+                header = Some(data);
+            }
+            b"IDAT" => idat.extend_from_slice(data),
+            b"IEND" => break,
+            _ => (),
+        }
+    }
+
+    let header = header.ok_or(ImageError::InvalidData)?;
+    let width = read_be32(&header[0..4]) as usize;
+    let height = read_be32(&header[4..8]) as usize;
+    let bit_depth = header[8];
+    let color_type = header[9];
+    let interlace = header[12];
+    if width == 0 || height == 0 || header[10] != 0 || header[11] != 0 {
+        return Err(ImageError::InvalidData);
+    }
+    if bit_depth != 8 {
+        return Err(ImageError::UnsupportedBitDepth(bit_depth));
+    }
+    let bpp = match color_type {
+        0 => 1,
+        2 => 3,
+        4 => 2,
+        6 => 4,
+        _ => return Err(ImageError::UnsupportedColorType(color_type)),
+    };
+    if interlace != 0 {
+        return Err(ImageError::UnsupportedInterlace);
+    }
+
+    let stride = width.checked_mul(bpp).ok_or(ImageError::InvalidData)?;
+    let raw_len = stride
+        .checked_add(1)
+        .and_then(|v| v.checked_mul(height))
+        .ok_or(ImageError::InvalidData)?;
+    let len = width.checked_mul(height).ok_or(ImageError::InvalidData)?;
+    let raw = inflate::zlib_decompress(&idat, raw_len)?;
+    if raw.len() < raw_len {
+        return Err(ImageError::InvalidData);
+    }
+
+    let mut vec = Vec::with_capacity(len);
+    let mut prev_line = vec![0; stride];
+    let mut line = vec![0; stride];
+    for src in raw.chunks_exact(stride + 1).take(height) {
+        line.copy_from_slice(&src[1..]);
+        unfilter_png(src[0], bpp, &mut line, &prev_line)?;
+
+        for pixel in line.chunks_exact(bpp) {
+            let color = match *pixel {
+                [l] => ColorComponents::from_rgb(l, l, l),
+                [l, a] => ColorComponents::from_rgba(l, l, l, Alpha8(a)),
+                [r, g, b] => ColorComponents::from_rgb(r, g, b),
+                [r, g, b, a] => ColorComponents::from_rgba(r, g, b, Alpha8(a)),
+                _ => unreachable!(),
+            };
+            vec.push(color.into_true_color());
+        }
+
+        swap(&mut line, &mut prev_line);
+    }
+
+    Ok(OwnedBitmap32::from_vec(
+        vec,
+        Size::new(width as isize, height as isize),
+    ))
+}
+
+/// Decodes the uncompressed 24-bit or 32-bit BMP image.
+pub fn decode_bmp(bytes: &[u8]) -> Result<OwnedBitmap32, ImageError> {
+    const FILE_HEADER_LEN: usize = 14;
+    const INFO_HEADER_LEN: usize = 40;
+    const BI_RGB: u32 = 0;
+
+    if bytes.len() < FILE_HEADER_LEN + INFO_HEADER_LEN || &bytes[0..2] != b"BM" {
+        return Err(ImageError::NotSupported);
+    }
+    let offset = read_le32(&bytes[0x0A..]) as usize;
+    if (read_le32(&bytes[0x0E..]) as usize) < INFO_HEADER_LEN {
+        return Err(ImageError::NotSupported);
+    }
+    let width = read_le32(&bytes[0x12..]) as i32;
+    let height = read_le32(&bytes[0x16..]) as i32;
+    let bit_count = read_le16(&bytes[0x1C..]);
+    let compression = read_le32(&bytes[0x1E..]);
+    if compression != BI_RGB {
+        return Err(ImageError::NotSupported);
+    }
+    let bpp = match bit_count {
+        24 => 3,
+        32 => 4,
+        _ => return Err(ImageError::UnsupportedBitDepth(bit_count as u8)),
+    };
+    if width <= 0 || height == 0 {
+        return Err(ImageError::InvalidData);
+    }
+
+    // Positive height means the rows are stored bottom-up
+    let is_bottom_up = height > 0;
+    let width = width as usize;
+    let height = height.unsigned_abs() as usize;
+    let stride = width
+        .checked_mul(bpp)
+        .and_then(|v| v.checked_add(3))
+        .ok_or(ImageError::InvalidData)?
+        & !3;
+    let end = stride
+        .checked_mul(height)
+        .and_then(|v| v.checked_add(offset))
+        .ok_or(ImageError::InvalidData)?;
+    let len = width.checked_mul(height).ok_or(ImageError::InvalidData)?;
+    let pixels = bytes.get(offset..end).ok_or(ImageError::InvalidData)?;
+
+    let mut vec = Vec::with_capacity(len);
+    for y in 0..height {
+        let line = if is_bottom_up { height - y - 1 } else { y };
+        let line = &pixels[line * stride..line * stride + width * bpp];
+        for pixel in line.chunks_exact(bpp) {
+            let color = match *pixel {
+                [b, g, r] => ColorComponents::from_rgb(r, g, b),
+                [b, g, r, a] => ColorComponents::from_rgba(r, g, b, Alpha8(a)),
+                _ => unreachable!(),
+            };
+            vec.push(color.into_true_color());
+        }
+    }
+
+    // Most encoders leave the alpha channel of 32-bit images empty
+    if bpp == 4 && vec.iter().all(|v| v.opacity().is_transparent()) {
+        for pixel in vec.iter_mut() {
+            *pixel = pixel.with_opacity(Alpha8::OPAQUE);
+        }
+    }
+
+    Ok(OwnedBitmap32::from_vec(
+        vec,
+        Size::new(width as isize, height as isize),
+    ))
+}
+
+/// Encodes the bitmap as a 32-bit bottom-up BMP image.
+pub fn encode_bmp(bitmap: &BitmapRef32) -> Vec<u8> {
+    const HEADER_LEN: usize = 14 + 40;
+    const PIXELS_PER_METER: u32 = 2835;
+
+    let width = bitmap.width();
+    let height = bitmap.height();
+    let image_size = width * height * 4;
+    let mut vec = Vec::with_capacity(HEADER_LEN + image_size);
+
+    // BITMAPFILEHEADER
+    vec.extend_from_slice(b"BM");
+    vec.extend_from_slice(&((HEADER_LEN + image_size) as u32).to_le_bytes());
+    vec.extend_from_slice(&0u32.to_le_bytes());
+    vec.extend_from_slice(&(HEADER_LEN as u32).to_le_bytes());
+
+    // BITMAPINFOHEADER
+    vec.extend_from_slice(&40u32.to_le_bytes());
+    vec.extend_from_slice(&(width as u32).to_le_bytes());
+    vec.extend_from_slice(&(height as u32).to_le_bytes());
+    vec.extend_from_slice(&1u16.to_le_bytes());
+    vec.extend_from_slice(&32u16.to_le_bytes());
+    vec.extend_from_slice(&0u32.to_le_bytes());
+    vec.extend_from_slice(&(image_size as u32).to_le_bytes());
+    vec.extend_from_slice(&PIXELS_PER_METER.to_le_bytes());
+    vec.extend_from_slice(&PIXELS_PER_METER.to_le_bytes());
+    vec.extend_from_slice(&0u32.to_le_bytes());
+    vec.extend_from_slice(&0u32.to_le_bytes());
+
+    let stride = bitmap.stride();
+    let slice = bitmap.slice();
+    for y in (0..height).rev() {
+        for pixel in &slice[y * stride..y * stride + width] {
+            vec.extend_from_slice(&pixel.argb().to_le_bytes());
+        }
+    }
+
+    vec
+}
+
+fn unfilter_png(filter: u8, bpp: usize, line: &mut [u8], prev: &[u8]) -> Result<(), ImageError> {
+    match filter {
+        // None
+        0 => (),
+        // Sub
+        1 => {
+            for i in bpp..line.len() {
+                line[i] = line[i].wrapping_add(line[i - bpp]);
+            }
+        }
+        // Up
+        2 => {
+            for i in 0..line.len() {
+                line[i] = line[i].wrapping_add(prev[i]);
+            }
+        }
+        // Average
+        3 => {
+            for i in 0..line.len() {
+                let left = if i >= bpp { line[i - bpp] } else { 0 };
+                let avg = ((left as u16 + prev[i] as u16) / 2) as u8;
+                line[i] = line[i].wrapping_add(avg);
+            }
+        }
+        // Paeth
+        4 => {
+            for i in 0..line.len() {
+                let (left, upper_left) = if i >= bpp {
+                    (line[i - bpp], prev[i - bpp])
+                } else {
+                    (0, 0)
+                };
+                line[i] = line[i].wrapping_add(paeth(left, prev[i], upper_left));
+            }
+        }
+        _ => return Err(ImageError::InvalidData),
+    }
+    Ok(())
+}
+
+#[inline]
+fn paeth(a: u8, b: u8, c: u8) -> u8 {
+    let p = a as i16 + b as i16 - c as i16;
+    let pa = (p - a as i16).abs();
+    let pb = (p - b as i16).abs();
+    let pc = (p - c as i16).abs();
+    if pa <= pb && pa <= pc {
+        a
+    } else if pb <= pc {
+        b
+    } else {
+        c
+    }
+}
+
+#[inline]
+fn read_le16(bytes: &[u8]) -> u16 {
+    bytes
+        .iter()
+        .take(2)
+        .rev()
+        .fold(0, |acc, v| (acc << 8) | *v as u16)
+}
+
+#[inline]
+fn read_le32(bytes: &[u8]) -> u32 {
+    bytes
+        .iter()
+        .take(4)
+        .rev()
+        .fold(0, |acc, v| (acc << 8) | *v as u32)
+}
+
+#[inline]
+fn read_be32(bytes: &[u8]) -> u32 {
+    bytes
+        .iter()
+        .take(4)
+        .fold(0, |acc, v| (acc << 8) | *v as u32)
+}
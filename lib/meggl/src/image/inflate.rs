@@ -0,0 +1,299 @@
+//! zlib (RFC 1950) and DEFLATE (RFC 1951) decompressor
+
+use super::ImageError;
+use alloc::{vec, vec::Vec};
+
+const MAX_BITS: usize = 15;
+
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+const LENGTH_EXTRA: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+const DIST_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DIST_EXTRA: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+const CODE_LENGTH_ORDER: [usize; 19] = [
+    16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15,
+];
+
+/// Decompresses the zlib stream into at most `limit` bytes.
+pub fn zlib_decompress(data: &[u8], limit: usize) -> Result<Vec<u8>, ImageError> {
+    if data.len() < 6 {
+        return Err(ImageError::InvalidData);
+    }
+    let cmf = data[0];
+    let flg = data[1];
+    if cmf & 0x0F != 8
+        || !(((cmf as u16) << 8) | flg as u16).is_multiple_of(31)
+        || (flg & 0x20) != 0
+    {
+        return Err(ImageError::InvalidData);
+    }
+
+    let result = inflate(&data[2..], limit)?;
+
+    let expected = data[data.len() - 4..]
+        .iter()
+        .fold(0u32, |acc, v| (acc << 8) | *v as u32);
+    if adler32(&result) != expected {
+        return Err(ImageError::InvalidData);
+    }
+    Ok(result)
+}
+
+/// Decompresses the raw DEFLATE stream into at most `limit` bytes.
+///
+/// Streams that expand beyond the limit are rejected as invalid.
+pub fn inflate(data: &[u8], limit: usize) -> Result<Vec<u8>, ImageError> {
+    let mut reader = BitReader::new(data);
+    let mut output = Vec::new();
+    loop {
+        let is_final = reader.bits(1)? != 0;
+        match reader.bits(2)? {
+            0 => {
+                reader.align_byte();
+                let len = reader.bits(16)?;
+                let nlen = reader.bits(16)?;
+                if len != !nlen & 0xFFFF || output.len() + len as usize > limit {
+                    return Err(ImageError::InvalidData);
+                }
+                for _ in 0..len {
+                    output.push(reader.bits(8)? as u8);
+                }
+            }
+            1 => {
+                let (lit, dist) = Huffman::fixed();
+                inflate_block(&mut reader, &mut output, limit, &lit, &dist)?;
+            }
+            2 => {
+                let (lit, dist) = Huffman::dynamic(&mut reader)?;
+                inflate_block(&mut reader, &mut output, limit, &lit, &dist)?;
+            }
+            _ => return Err(ImageError::InvalidData),
+        }
+        if is_final {
+            break;
+        }
+    }
+    Ok(output)
+}
+
+fn inflate_block(
+    reader: &mut BitReader,
+    output: &mut Vec<u8>,
+    limit: usize,
+    lit: &Huffman,
+    dist: &Huffman,
+) -> Result<(), ImageError> {
+    loop {
+        let symbol = lit.decode(reader)? as usize;
+        match symbol {
+            0..=255 => {
+                if output.len() >= limit {
+                    return Err(ImageError::InvalidData);
+                }
+                output.push(symbol as u8);
+            }
+            256 => return Ok(()),
+            _ => {
+                let index = symbol - 257;
+                if index >= LENGTH_BASE.len() {
+                    return Err(ImageError::InvalidData);
+                }
+                let len = LENGTH_BASE[index] as usize
+                    + reader.bits(LENGTH_EXTRA[index] as usize)? as usize;
+
+                let index = dist.decode(reader)? as usize;
+                if index >= DIST_BASE.len() {
+                    return Err(ImageError::InvalidData);
+                }
+                let distance =
+                    DIST_BASE[index] as usize + reader.bits(DIST_EXTRA[index] as usize)? as usize;
+                if distance > output.len() || output.len() + len > limit {
+                    return Err(ImageError::InvalidData);
+                }
+
+                let start = output.len() - distance;
+                for i in 0..len {
+                    output.push(output[start + i]);
+                }
+            }
+        }
+    }
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    const MOD_ADLER: u32 = 65521;
+    let mut a = 1u32;
+    let mut b = 0u32;
+    for chunk in data.chunks(5552) {
+        for v in chunk {
+            a += *v as u32;
+            b += a;
+        }
+        a %= MOD_ADLER;
+        b %= MOD_ADLER;
+    }
+    (b << 16) | a
+}
+
+struct BitReader<'a> {
+    data: &'a [u8],
+    position: usize,
+    bit_buf: u32,
+    bit_count: usize,
+}
+
+impl<'a> BitReader<'a> {
+    #[inline]
+    const fn new(data: &'a [u8]) -> Self {
+        Self {
+            data,
+            position: 0,
+            bit_buf: 0,
+            bit_count: 0,
+        }
+    }
+
+    /// Reads up to 16 bits, LSB first.
+    fn bits(&mut self, count: usize) -> Result<u32, ImageError> {
+        while self.bit_count < count {
+            let byte = *self
+                .data
+                .get(self.position)
+                .ok_or(ImageError::InvalidData)?;
+            self.position += 1;
+            self.bit_buf |= (byte as u32) << self.bit_count;
+            self.bit_count += 8;
+        }
+        let result = self.bit_buf & ((1 << count) - 1);
+        self.bit_buf >>= count;
+        self.bit_count -= count;
+        Ok(result)
+    }
+
+    /// Discards the bits remaining in the current byte.
+    #[inline]
+    fn align_byte(&mut self) {
+        self.bit_buf = 0;
+        self.bit_count = 0;
+    }
+}
+
+/// Canonical Huffman decoding table
+struct Huffman {
+    counts: [u16; MAX_BITS + 1],
+    symbols: Vec<u16>,
+}
+
+impl Huffman {
+    fn new(lengths: &[u8]) -> Self {
+        let mut counts = [0u16; MAX_BITS + 1];
+        for len in lengths {
+            counts[*len as usize] += 1;
+        }
+        counts[0] = 0;
+
+        let mut offsets = [0u16; MAX_BITS + 2];
+        for len in 1..=MAX_BITS {
+            offsets[len + 1] = offsets[len] + counts[len];
+        }
+
+        let mut symbols = vec![0; lengths.len()];
+        for (symbol, len) in lengths.iter().enumerate() {
+            if *len != 0 {
+                let offset = &mut offsets[*len as usize];
+                symbols[*offset as usize] = symbol as u16;
+                *offset += 1;
+            }
+        }
+
+        Self { counts, symbols }
+    }
+
+    fn fixed() -> (Self, Self) {
+        let mut lengths = [0u8; 288];
+        for (index, len) in lengths.iter_mut().enumerate() {
+            *len = match index {
+                0..=143 => 8,
+                144..=255 => 9,
+                256..=279 => 7,
+                _ => 8,
+            };
+        }
+        let lit = Self::new(&lengths);
+        let dist = Self::new(&[5u8; 30]);
+        (lit, dist)
+    }
+
+    fn dynamic(reader: &mut BitReader) -> Result<(Self, Self), ImageError> {
+        let hlit = reader.bits(5)? as usize + 257;
+        let hdist = reader.bits(5)? as usize + 1;
+        let hclen = reader.bits(4)? as usize + 4;
+
+        let mut code_lengths = [0u8; 19];
+        for index in CODE_LENGTH_ORDER.iter().take(hclen) {
+            code_lengths[*index] = reader.bits(3)? as u8;
+        }
+        let code = Self::new(&code_lengths);
+
+        let mut lengths = Vec::with_capacity(hlit + hdist);
+        while lengths.len() < hlit + hdist {
+            let symbol = code.decode(reader)?;
+            match symbol {
+                0..=15 => lengths.push(symbol as u8),
+                16 => {
+                    let prev = *lengths.last().ok_or(ImageError::InvalidData)?;
+                    let repeat = 3 + reader.bits(2)? as usize;
+                    lengths.extend(core::iter::repeat_n(prev, repeat));
+                }
+                17 => {
+                    let repeat = 3 + reader.bits(3)? as usize;
+                    lengths.extend(core::iter::repeat_n(0, repeat));
+                }
+                18 => {
+                    let repeat = 11 + reader.bits(7)? as usize;
+                    lengths.extend(core::iter::repeat_n(0, repeat));
+                }
+                _ => return Err(ImageError::InvalidData),
+            }
+        }
+        if lengths.len() > hlit + hdist || lengths[256] == 0 {
+            return Err(ImageError::InvalidData);
+        }
+
+        let lit = Self::new(&lengths[..hlit]);
+        let dist = Self::new(&lengths[hlit..]);
+        Ok((lit, dist))
+    }
+
+    fn decode(&self, reader: &mut BitReader) -> Result<u16, ImageError> {
+        let mut code = 0i32;
+        let mut first = 0i32;
+        let mut index = 0i32;
+        for len in 1..=MAX_BITS {
+            code |= reader.bits(1)? as i32;
+            let count = self.counts[len] as i32;
+            if code - count < first {
+                return self
+                    .symbols
+                    .get((index + code - first) as usize)
+                    .copied()
+                    .ok_or(ImageError::InvalidData);
+            }
+            index += count;
+            first += count;
+            first <<= 1;
+            code <<= 1;
+        }
+        Err(ImageError::InvalidData)
+    }
+}
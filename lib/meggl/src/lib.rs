@@ -8,10 +8,16 @@ mod bitmap;
 mod color;
 mod coords;
 mod drawable;
+mod fixed;
+pub mod font;
+mod image;
+pub mod memory;
 pub use bitmap::*;
 pub use color::*;
 pub use coords::*;
 pub use drawable::*;
+pub use fixed::*;
+pub use image::*;
 
 #[cfg(test)]
 pub mod tests;
@@ -0,0 +1,108 @@
+//! Memory operations for the large bitmaps
+//!
+//! On x86-64, the vectorized path is used as SSE2 is always available.
+
+use crate::color::{ARGB8888, RGB565};
+
+/// Copies `count` elements from `src` to `dest`, like [`core::ptr::copy_nonoverlapping`].
+///
+/// # Safety
+///
+/// Same as [`core::ptr::copy_nonoverlapping`].
+#[inline]
+pub unsafe fn copy_nonoverlapping<T>(src: *const T, dest: *mut T, count: usize) {
+    #[cfg(target_arch = "x86_64")]
+    return x64::copy_sse2(
+        src as *const u8,
+        dest as *mut u8,
+        count * core::mem::size_of::<T>(),
+    );
+    #[cfg(not(target_arch = "x86_64"))]
+    core::ptr::copy_nonoverlapping(src, dest, count);
+}
+
+mod private {
+    pub trait Sealed {}
+}
+
+/// Plain values without padding that [`fill`] can write as a repeated byte pattern
+pub trait FillValue: Copy + private::Sealed {
+    /// Returns the value repeated to 64 bits, in little endian.
+    fn fill_pattern(self) -> u64;
+}
+
+macro_rules! fill_value {
+    ($ty:ty, $get:expr, $repeat:expr) => {
+        impl private::Sealed for $ty {}
+
+        impl FillValue for $ty {
+            #[inline]
+            fn fill_pattern(self) -> u64 {
+                ($get)(self) as u64 * $repeat
+            }
+        }
+    };
+}
+
+fill_value!(u8, |v: u8| v, 0x0101_0101_0101_0101);
+fill_value!(u16, |v: u16| v, 0x0001_0001_0001_0001);
+fill_value!(u32, |v: u32| v, 0x0000_0001_0000_0001);
+fill_value!(u64, |v: u64| v, 1);
+fill_value!(RGB565, |v: RGB565| v.0, 0x0001_0001_0001_0001);
+fill_value!(ARGB8888, |v: ARGB8888| v.0, 0x0000_0001_0000_0001);
+
+/// Fills the slice with the value, like [`slice::fill`].
+#[inline]
+pub fn fill<T: FillValue>(slice: &mut [T], value: T) {
+    #[cfg(target_arch = "x86_64")]
+    {
+        let len = core::mem::size_of_val(slice);
+        unsafe { x64::fill_sse2(slice.as_mut_ptr() as *mut u8, len, value.fill_pattern()) }
+    }
+    #[cfg(not(target_arch = "x86_64"))]
+    slice.fill(value);
+}
+
+#[cfg(target_arch = "x86_64")]
+mod x64 {
+    use core::arch::x86_64::*;
+
+    pub unsafe fn copy_sse2(src: *const u8, dest: *mut u8, len: usize) {
+        let mut offset = 0;
+        while offset + 64 <= len {
+            let p = src.add(offset) as *const __m128i;
+            let q = dest.add(offset) as *mut __m128i;
+            let x0 = _mm_loadu_si128(p);
+            let x1 = _mm_loadu_si128(p.add(1));
+            let x2 = _mm_loadu_si128(p.add(2));
+            let x3 = _mm_loadu_si128(p.add(3));
+            _mm_storeu_si128(q, x0);
+            _mm_storeu_si128(q.add(1), x1);
+            _mm_storeu_si128(q.add(2), x2);
+            _mm_storeu_si128(q.add(3), x3);
+            offset += 64;
+        }
+        while offset + 16 <= len {
+            let x = _mm_loadu_si128(src.add(offset) as *const __m128i);
+            _mm_storeu_si128(dest.add(offset) as *mut __m128i, x);
+            offset += 16;
+        }
+        for i in offset..len {
+            dest.add(i).write(src.add(i).read());
+        }
+    }
+
+    /// Fills the memory with the 64-bit pattern, whose phase is aligned to `dest`.
+    pub unsafe fn fill_sse2(dest: *mut u8, len: usize, pattern: u64) {
+        let x = _mm_set1_epi64x(pattern as i64);
+        let mut offset = 0;
+        while offset + 16 <= len {
+            _mm_storeu_si128(dest.add(offset) as *mut __m128i, x);
+            offset += 16;
+        }
+        let bytes = pattern.to_le_bytes();
+        for i in offset..len {
+            dest.add(i).write(bytes[i & 7]);
+        }
+    }
+}
@@ -1,4 +1,5 @@
 use super::*;
+use alloc::{string::String, vec::Vec};
 
 #[test]
 fn components() {
@@ -285,3 +286,708 @@ fn one_bit_colors() {
     assert_eq!(canvas.get(6), Monochrome::Zero);
     assert_eq!(canvas.get(7), Monochrome::One);
 }
+
+#[test]
+fn rect_intersection() {
+    let a = Rect::new(0, 0, 100, 100);
+    let b = Rect::new(50, 40, 100, 100);
+    let c = Rect::new(100, 0, 50, 50);
+    let d = Rect::new(200, 200, 10, 10);
+
+    // overlapping
+    assert_eq!(a.intersection(b), Some(Rect::new(50, 40, 50, 60)));
+    assert_eq!(b.intersection(a), Some(Rect::new(50, 40, 50, 60)));
+    assert_eq!(a.union(b), Rect::new(0, 0, 150, 140));
+    assert!(a.contains_point(Point::new(50, 40)));
+    assert!(!a.contains_point(Point::new(100, 40)));
+    assert!(a.contains_rect(Rect::new(10, 10, 90, 90)));
+    assert!(!a.contains_rect(b));
+
+    // touching
+    assert_eq!(a.intersection(c), None);
+    assert_eq!(a.union(c), Rect::new(0, 0, 150, 100));
+    assert!(!a.contains_rect(c));
+
+    // disjoint
+    assert_eq!(a.intersection(d), None);
+    assert_eq!(a.union(d), Rect::new(0, 0, 210, 210));
+    assert!(!a.contains_point(Point::new(205, 205)));
+    assert!(d.contains_point(Point::new(205, 205)));
+
+    // empty
+    assert_eq!(a.union(Rect::new(300, 300, 0, 0)), a);
+}
+
+#[test]
+fn point_arithmetic() {
+    let p = Point::new(12, -34);
+    let q = Point::new(-56, 78);
+    let m = Movement::new(7, 8);
+
+    assert_eq!(p + m - m, p);
+    assert_eq!(p - m + m, p);
+    assert_eq!(q + (p - q), p);
+    assert_eq!(p + (q - p), q);
+    assert_eq!(p * 3, Point::new(36, -102));
+    assert_eq!(p * 3 / 3, p);
+
+    let bounds = Rect::new(10, 20, 100, 50);
+    assert_eq!(Point::new(50, 40).clamp(bounds), Point::new(50, 40));
+    assert_eq!(Point::new(0, 40).clamp(bounds), Point::new(10, 40));
+    assert_eq!(Point::new(200, 40).clamp(bounds), Point::new(109, 40));
+    assert_eq!(Point::new(50, 0).clamp(bounds), Point::new(50, 20));
+    assert_eq!(Point::new(50, 100).clamp(bounds), Point::new(50, 69));
+    assert_eq!(Point::new(-5, 500).clamp(bounds), Point::new(10, 69));
+}
+
+#[test]
+fn paletted_bitmap() {
+    let mut bitmap = PalettedBitmap8::new(Size::new(4, 4), IndexedColor::BLACK);
+    assert_eq!(bitmap.palette().len(), 256);
+    assert_eq!(
+        bitmap.resolve(IndexedColor::BLUE),
+        ARGB8888::from(IndexedColor::BLUE)
+    );
+
+    let index = IndexedColor(123);
+    bitmap.bitmap_mut().set_pixel(Point::new(1, 2), index);
+    bitmap.set_palette_entry(index.0, ARGB8888::from_rgb(0x123456));
+
+    let pixel = bitmap.bitmap().get_pixel(Point::new(1, 2)).unwrap();
+    assert_eq!(pixel, index);
+    assert_eq!(bitmap.resolve(pixel), ARGB8888::from_rgb(0x123456));
+
+    let mut screen = OwnedBitmap32::new(Size::new(4, 4), ARGB8888::default());
+    bitmap.blt_to(screen.as_mut(), Point::new(0, 0), bitmap.bounds());
+    assert_eq!(
+        screen.get_pixel(Point::new(1, 2)),
+        Some(ARGB8888::from_rgb(0x123456))
+    );
+}
+
+#[test]
+fn decode_png_rgba() {
+    #[rustfmt::skip]
+    let png = [
+        0x89, 0x50, 0x4e, 0x47, 0x0d, 0x0a, 0x1a, 0x0a, 0x00, 0x00, 0x00, 0x0d,
+        0x49, 0x48, 0x44, 0x52, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00, 0x02,
+        0x08, 0x06, 0x00, 0x00, 0x00, 0x72, 0xb6, 0x0d, 0x24, 0x00, 0x00, 0x00,
+        0x13, 0x49, 0x44, 0x41, 0x54, 0x78, 0x9c, 0x63, 0xf8, 0xcf, 0xc0, 0xf0,
+        0x1f, 0x0c, 0x81, 0x34, 0x08, 0x34, 0x00, 0x00, 0x49, 0x49, 0x09, 0x78,
+        0x28, 0xa0, 0xdb, 0x77, 0x00, 0x00, 0x00, 0x00, 0x49, 0x45, 0x4e, 0x44,
+        0xae, 0x42, 0x60, 0x82,
+    ];
+    let bitmap = decode_png(&png).unwrap();
+    assert_eq!(bitmap.size(), Size::new(2, 2));
+    assert_eq!(
+        bitmap.get_pixel(Point::new(0, 0)),
+        Some(ARGB8888(0xFFFF0000))
+    );
+    assert_eq!(
+        bitmap.get_pixel(Point::new(1, 0)),
+        Some(ARGB8888(0xFF00FF00))
+    );
+    assert_eq!(
+        bitmap.get_pixel(Point::new(0, 1)),
+        Some(ARGB8888(0xFF0000FF))
+    );
+    assert_eq!(
+        bitmap.get_pixel(Point::new(1, 1)),
+        Some(ARGB8888(0x80FFFFFF))
+    );
+
+    let mut png16 = png;
+    png16[24] = 16;
+    assert_eq!(
+        decode_png(&png16).err(),
+        Some(ImageError::UnsupportedBitDepth(16))
+    );
+    assert_eq!(decode_png(&png[1..]).err(), Some(ImageError::NotSupported));
+
+    let mut huge = png;
+    huge[16..24].fill(0xFF);
+    assert_eq!(decode_png(&huge).err(), Some(ImageError::InvalidData));
+
+    // the IDAT chunk claims more data than the file has
+    let mut overrun = png;
+    overrun[33..37].copy_from_slice(&0x40u32.to_be_bytes());
+    assert_eq!(decode_png(&overrun).err(), Some(ImageError::InvalidData));
+    overrun[33..37].fill(0xFF);
+    assert_eq!(decode_png(&overrun).err(), Some(ImageError::InvalidData));
+
+    // the IEND chunk without its CRC
+    assert_eq!(
+        decode_png(&png[..png.len() - 4]).err(),
+        Some(ImageError::InvalidData)
+    );
+
+    // the image data expands beyond the size in the header
+    let mut short = png;
+    short[20..24].copy_from_slice(&1u32.to_be_bytes());
+    assert_eq!(decode_png(&short).err(), Some(ImageError::InvalidData));
+}
+
+#[test]
+#[should_panic]
+fn owned_bitmap_from_short_vec() {
+    OwnedBitmap32::from_vec([ARGB8888::default(); 5].to_vec(), Size::new(3, 2));
+}
+
+#[test]
+fn bmp_round_trip() {
+    let pixels = [
+        0xFFFF0000, 0xFF00FF00, 0xFF0000FF, //
+        0x80FFFFFF, 0xFF123456, 0x00000000, //
+    ];
+    let bitmap = OwnedBitmap32::from_vec(
+        pixels.iter().map(|v| ARGB8888(*v)).collect(),
+        Size::new(3, 2),
+    );
+
+    let bmp = encode_bmp(bitmap.as_ref());
+    assert_eq!(&bmp[0..2], b"BM");
+    assert_eq!(bmp.len(), 54 + 3 * 2 * 4);
+    // bottom-up: the first stored row is the last line
+    assert_eq!(&bmp[54..58], &[0xFF, 0xFF, 0xFF, 0x80]);
+
+    let decoded = decode_bmp(&bmp).unwrap();
+    assert_eq!(decoded.size(), bitmap.size());
+    assert_eq!(decoded.slice(), bitmap.slice());
+
+    let mut huge = bmp.clone();
+    huge[0x12..0x16].copy_from_slice(&0x7FFF_FFFFu32.to_le_bytes());
+    huge[0x16..0x1A].copy_from_slice(&0x8000_0000u32.to_le_bytes());
+    assert_eq!(decode_bmp(&huge).err(), Some(ImageError::InvalidData));
+
+    let mut truncated = bmp.clone();
+    truncated.pop();
+    assert_eq!(decode_bmp(&truncated).err(), Some(ImageError::InvalidData));
+}
+
+#[test]
+fn line_points_diagonal() {
+    let from = Point::new(2, 3);
+    let to = Point::new(7, -2);
+    let points = line_points(from, to).collect::<Vec<_>>();
+    assert_eq!(points.len(), 6);
+    assert_eq!(points.first(), Some(&from));
+    assert_eq!(points.last(), Some(&to));
+    assert_eq!(points.iter().filter(|&&v| v == from).count(), 1);
+    assert_eq!(points.iter().filter(|&&v| v == to).count(), 1);
+
+    let mut drawn = Vec::new();
+    from.line_to(to, |v| drawn.push(v));
+    assert_eq!(drawn, points);
+
+    assert_eq!(line_points(from, from).collect::<Vec<_>>(), [from]);
+}
+
+#[test]
+fn premultiplied_alpha() {
+    for alpha in [255, 254, 224, 192, 160, 128] {
+        for value in 0..=255u8 {
+            let color = ColorComponents::from_rgba(value, 255 - value, value / 2, Alpha8(alpha));
+            let premultiplied = color.premultiplied();
+            assert_eq!(premultiplied.a, color.a);
+            assert!(premultiplied.r <= color.r);
+
+            let result = premultiplied.unpremultiplied();
+            assert_eq!(result.a, color.a);
+            assert!((result.r as isize - color.r as isize).abs() <= 1);
+            assert!((result.g as isize - color.g as isize).abs() <= 1);
+            assert!((result.b as isize - color.b as isize).abs() <= 1);
+        }
+    }
+
+    let half_white = ColorComponents::from_rgba(255, 255, 255, Alpha8(128)).premultiplied();
+    let black = ARGB8888::from_rgb(0x000000);
+    let result = black.blend_draw_premultiplied(half_white.into_true_color());
+    assert_eq!(result.components().a, Alpha8::OPAQUE);
+    assert_eq!(result.components().r, 128);
+}
+
+#[test]
+fn luminance() {
+    let red = ARGB8888::from_rgb(0xFF0000).luminance();
+    let green = ARGB8888::from_rgb(0x00FF00).luminance();
+    let blue = ARGB8888::from_rgb(0x0000FF).luminance();
+    let white = ARGB8888::from_rgb(0xFFFFFF).luminance();
+
+    assert!(green > red);
+    assert!(red > blue);
+    assert!(white > green);
+    assert_eq!(white, 255);
+    assert_eq!(ARGB8888::from_rgb(0x000000).luminance(), 0);
+
+    let gray = ARGB8888::from_argb(0x80FF0000).to_grayscale();
+    assert_eq!(gray, ARGB8888::from_gray(red, Alpha8(0x80)));
+}
+
+#[test]
+fn alpha_multiply() {
+    for alpha in [0u8, 1, 64, 128, 200, 255] {
+        assert_eq!(Alpha8(alpha) * Alpha8::OPAQUE, Alpha8(alpha));
+        assert_eq!(Alpha8(alpha) * Alpha8::TRANSPARENT, Alpha8::TRANSPARENT);
+    }
+    assert_eq!(Alpha8::OPAQUE * Alpha8(128), Alpha8(128));
+    assert_eq!(Alpha8(200) * Alpha8(128), Alpha8(100));
+
+    let color = TrueColor::from_argb(0xC0123456);
+    let faded = color.with_opacity(color.opacity() * Alpha8(128));
+    assert_eq!(faded.rgb(), color.rgb());
+    assert_eq!(faded.opacity(), Alpha8(0x60));
+}
+
+#[test]
+fn fix16_arithmetic() {
+    for value in [0isize, 1, -1, 100, -100, 32767, -32768] {
+        assert_eq!(Fix16::from_int(value).floor(), value);
+    }
+    for value in [0.0, 0.5, -0.5, 1.25, -3.75, 123.0625] {
+        assert_eq!(Fix16::from_f64(value).into_f64(), value);
+    }
+    assert_eq!(Fix16::from_int(40000), Fix16::MAX);
+
+    let a = Fix16::from_f64(1.5);
+    let b = Fix16::from_f64(-2.25);
+    assert_eq!(a + b, Fix16::from_f64(-0.75));
+    assert_eq!(a - b, Fix16::from_f64(3.75));
+    assert_eq!(a * b, Fix16::from_f64(-3.375));
+    assert_eq!(b / a, Fix16::from_f64(-1.5));
+    assert_eq!(Fix16::ONE / Fix16::ZERO, Fix16::MAX);
+
+    // The product of two values with fractional parts stays within half an ulp
+    let x = Fix16::from_f64(0.1);
+    let y = Fix16::from_f64(0.3);
+    let error = ((x * y).into_f64() - x.into_f64() * y.into_f64()).abs();
+    assert!(error <= 0.5 / 65536.0);
+
+    assert_eq!(Fix16::from_f64(2.5).round(), 3);
+    assert_eq!(Fix16::from_f64(-2.5).floor(), -3);
+}
+
+#[test]
+fn memory_copy() {
+    let src = (0..300).map(|v| (v * 7 + 3) as u8).collect::<Vec<_>>();
+    for src_offset in 0..16 {
+        for dest_offset in 0..16 {
+            for len in 0..(src.len() - 16) {
+                let mut expected = [0u8; 300];
+                let mut actual = [0u8; 300];
+                unsafe {
+                    core::ptr::copy_nonoverlapping(
+                        src.as_ptr().add(src_offset),
+                        expected.as_mut_ptr().add(dest_offset),
+                        len.min(300 - dest_offset),
+                    );
+                    memory::copy_nonoverlapping(
+                        src.as_ptr().add(src_offset),
+                        actual.as_mut_ptr().add(dest_offset),
+                        len.min(300 - dest_offset),
+                    );
+                }
+                assert_eq!(expected, actual, "{} {} {}", src_offset, dest_offset, len);
+            }
+        }
+    }
+}
+
+#[test]
+fn memory_fill() {
+    for offset in 0..16 {
+        for len in 0..100 {
+            let mut expected = [0u8; 128];
+            let mut actual = [0u8; 128];
+            expected[offset..offset + len].fill(0x5A);
+            memory::fill(&mut actual[offset..offset + len], 0x5A);
+            assert_eq!(expected, actual, "{} {}", offset, len);
+
+            let mut expected = [RGB565(0); 128];
+            let mut actual = [RGB565(0); 128];
+            expected[offset..offset + len].fill(RGB565(0x1234));
+            memory::fill(&mut actual[offset..offset + len], RGB565(0x1234));
+            assert!(expected == actual, "{} {}", offset, len);
+
+            let mut expected = [ARGB8888(0); 128];
+            let mut actual = [ARGB8888(0); 128];
+            expected[offset..offset + len].fill(ARGB8888(0x12345678));
+            memory::fill(&mut actual[offset..offset + len], ARGB8888(0x12345678));
+            assert_eq!(expected, actual, "{} {}", offset, len);
+
+            let mut expected = [0u64; 128];
+            let mut actual = [0u64; 128];
+            expected[offset..offset + len].fill(0x0123_4567_89AB_CDEF);
+            memory::fill(&mut actual[offset..offset + len], 0x0123_4567_89AB_CDEF);
+            assert_eq!(expected, actual, "{} {}", offset, len);
+        }
+    }
+}
+
+#[test]
+fn dirty_region() {
+    let mut region = DirtyRegion::new();
+    assert!(region.is_empty());
+
+    region.add_rect(Rect::new(10, 20, 30, 40));
+    assert_eq!(
+        region.rects().collect::<Vec<_>>(),
+        [Rect::new(10, 20, 30, 40)]
+    );
+    assert_eq!(region.bounds(), Coordinates::new(10, 20, 40, 60));
+
+    // Contained and empty rectangles do not grow the region
+    region.add_rect(Rect::new(15, 25, 5, 5));
+    region.add_rect(Rect::new(100, 100, 0, 10));
+    assert_eq!(
+        region.rects().collect::<Vec<_>>(),
+        [Rect::new(10, 20, 30, 40)]
+    );
+
+    // Distant rectangles are kept apart, overlapping ones are merged
+    region.add_rect(Rect::new(200, 200, 10, 10));
+    assert_eq!(region.rects().count(), 2);
+    region.add_rect(Rect::new(35, 55, 10, 10));
+    let mut rects = region.rects().collect::<Vec<_>>();
+    rects.sort_by_key(|v| v.min_x());
+    assert_eq!(
+        rects,
+        [Rect::new(10, 20, 35, 45), Rect::new(200, 200, 10, 10)]
+    );
+
+    // The number of rectangles is bounded
+    for i in 0..20 {
+        region.add_rect(Rect::new(i * 100, 1000, 10, 10));
+    }
+    assert!(region.rects().count() <= DirtyRegion::MAX_RECTS);
+    assert_eq!(region.bounds(), Coordinates::new(0, 20, 1910, 1010));
+
+    let taken = region.take();
+    assert!(region.is_empty());
+    assert!(!taken.is_empty());
+}
+
+#[test]
+fn coords_cast() {
+    let large = Point::try_from((i32::MAX as i64 + 1, 0i64)).unwrap();
+    assert_eq!(large.cast::<i32>(), None);
+    assert_eq!(large.cast::<i64>(), Some((i32::MAX as i64 + 1, 0)));
+
+    let small = Point::try_from((-5i64, 7i64)).unwrap();
+    assert_eq!(small.cast::<i32>(), Some((-5, 7)));
+    assert_eq!(small.cast::<u32>(), None);
+
+    assert_eq!(Point::from((3i32, -4i32)), Point::new(3, -4));
+    assert_eq!(Size::from((640u16, 480u16)), Size::new(640, 480));
+    assert!(Size::try_from((u64::MAX, 0u64)).is_err());
+    assert_eq!(
+        Rect::new(-1, 2, 30, 40).cast::<i16>(),
+        Some((-1, 2, 30, 40))
+    );
+    assert_eq!(Rect::new(0, 0, 0x1_0000, 1).cast::<u16>(), None);
+}
+
+#[test]
+fn sub_bitmap() {
+    let mut bitmap = OwnedBitmap32::new(Size::new(4, 4), ARGB8888::BLACK);
+    {
+        let mut panel = bitmap.sub_bitmap(Rect::new(1, 1, 2, 2)).unwrap();
+        assert_eq!(panel.size(), Size::new(2, 2));
+        panel.fill_rect(panel.bounds(), ARGB8888::WHITE);
+    }
+    for y in 0..4 {
+        for x in 0..4 {
+            let expected = if (1..3).contains(&x) && (1..3).contains(&y) {
+                ARGB8888::WHITE
+            } else {
+                ARGB8888::BLACK
+            };
+            assert_eq!(bitmap.get_pixel(Point::new(x, y)), Some(expected));
+        }
+    }
+
+    // The area is clipped to the parent bounds
+    let panel = bitmap.sub_bitmap(Rect::new(-1, 2, 3, 5)).unwrap();
+    assert_eq!(panel.size(), Size::new(2, 2));
+    assert_eq!(panel.get_pixel(Point::new(1, 0)), Some(ARGB8888::WHITE));
+    assert!(bitmap.sub_bitmap(Rect::new(4, 0, 2, 2)).is_none());
+}
+
+#[test]
+fn fill_rect_spans() {
+    let size = Size::new(13, 7);
+    let color = ARGB8888::from_rgb(0x123456);
+    for rect in [
+        Rect::new(0, 0, 13, 7),
+        Rect::new(0, 2, 13, 3),
+        Rect::new(-3, -2, 20, 20),
+        Rect::new(1, 1, 5, 4),
+        Rect::new(7, 3, 10, 10),
+        Rect::new(-5, 4, 6, 1),
+        Rect::new(13, 0, 1, 1),
+    ] {
+        let mut actual = OwnedBitmap32::new(size, ARGB8888::BLACK);
+        actual.as_mut().fill_rect(rect, color);
+
+        // Same fill on a view whose stride is wider than its width
+        let mut parent = OwnedBitmap32::new(Size::new(16, 7), ARGB8888::BLACK);
+        parent
+            .sub_bitmap(size.into())
+            .unwrap()
+            .fill_rect(rect, color);
+
+        let mut expected = OwnedBitmap32::new(size, ARGB8888::BLACK);
+        for y in rect.min_y()..rect.max_y() {
+            for x in rect.min_x()..rect.max_x() {
+                expected.as_mut().set_pixel(Point::new(x, y), color);
+            }
+        }
+
+        for y in 0..size.height() {
+            for x in 0..size.width() {
+                let point = Point::new(x, y);
+                assert_eq!(
+                    actual.get_pixel(point),
+                    expected.get_pixel(point),
+                    "{:?}",
+                    rect
+                );
+                assert_eq!(
+                    parent.get_pixel(point),
+                    expected.get_pixel(point),
+                    "{:?}",
+                    rect
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn blt32_if_changed() {
+    let size = Size::new(8, 4);
+    let origin = Point::new(0, 0);
+    let mut prev = OwnedBitmap32::new(size, ARGB8888::BLACK);
+    let mut frame = OwnedBitmap32::new(size, ARGB8888::BLACK);
+    let mut screen = OwnedBitmap32::new(size, ARGB8888::TRANSPARENT);
+    let screen: &mut BitmapRefMut32 = screen.as_mut();
+
+    assert_eq!(screen.blt32_if_changed(frame.as_ref(), origin, None), 4);
+    assert_eq!(
+        screen.blt32_if_changed(frame.as_ref(), origin, Some(prev.as_ref())),
+        0
+    );
+
+    frame.as_mut().set_pixel(Point::new(5, 2), ARGB8888::WHITE);
+    assert_eq!(
+        screen.blt32_if_changed(frame.as_ref(), origin, Some(prev.as_ref())),
+        1
+    );
+    assert_eq!(screen.get_pixel(Point::new(5, 2)), Some(ARGB8888::WHITE));
+
+    prev.as_mut().set_pixel(Point::new(5, 2), ARGB8888::WHITE);
+    assert_eq!(
+        screen.blt32_if_changed(frame.as_ref(), origin, Some(prev.as_ref())),
+        0
+    );
+}
+
+#[test]
+fn advance_with_kerning() {
+    // proportional widths, and "AV" is kerned
+    let width_of = |c| match c {
+        'i' | ' ' => 3,
+        'W' => 10,
+        _ => 7,
+    };
+    let kern = |a, b| if (a, b) == ('A', 'V') { -2 } else { 0 };
+    let advance_of = |text| font::advance_of(text, width_of, kern);
+
+    assert_eq!(advance_of(""), 0);
+    assert_eq!(advance_of("Wi"), 13);
+    assert_eq!(advance_of("AV"), 12);
+    assert_eq!(advance_of("WAVi"), 10 + 7 - 2 + 7 + 3);
+}
+
+#[test]
+fn line_layout() {
+    use font::LineBreakMode;
+    let layout = |text, line_break| {
+        let lines = font::line_statuses(
+            text,
+            Size::new(8 * 8, 100),
+            0,
+            line_break,
+            16,
+            |_| 8,
+            |_, _| 0,
+        );
+        lines
+            .iter()
+            .map(|v| (v.start_position, v.end_position, v.width))
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(
+        layout("Hello World", LineBreakMode::WordWrapping),
+        [(0, 5, 40), (6, 11, 40)]
+    );
+    assert_eq!(
+        layout("Hello World", LineBreakMode::CharWrapping),
+        [(0, 8, 64), (8, 11, 24)]
+    );
+    assert_eq!(
+        layout("Hi\nWorld", LineBreakMode::WordWrapping),
+        [(0, 2, 16), (3, 8, 40)]
+    );
+
+    // the text ends at the width of the last line, below the lines before it
+    let lines = font::line_statuses(
+        "Hello World",
+        Size::new(8 * 8, 100),
+        0,
+        LineBreakMode::WordWrapping,
+        16,
+        |_| 8,
+        |_, _| 0,
+    );
+    let (last, rest) = lines.split_last().unwrap();
+    let end = Point::new(last.width, rest.iter().map(|v| v.height).sum());
+    assert_eq!(end, Point::new(40, 16));
+
+    // the lines below the size are dropped
+    let lines = font::line_statuses(
+        "a\nb\nc",
+        Size::new(64, 40),
+        0,
+        LineBreakMode::CharWrapping,
+        16,
+        |_| 8,
+        |_, _| 0,
+    );
+    assert_eq!(lines.len(), 2);
+}
+
+#[test]
+fn word_wrap() {
+    use font::{line_statuses, LineBreakMode};
+    // 10 characters of 8 pixels fit in a line
+    let wrap = |text: &str, size: Size| {
+        line_statuses(
+            text,
+            size,
+            0,
+            LineBreakMode::WordWrapping,
+            16,
+            |_| 8,
+            |_, _| 0,
+        )
+        .iter()
+        .map(|v| {
+            text.chars()
+                .skip(v.start_position)
+                .take(v.end_position - v.start_position)
+                .collect::<String>()
+        })
+        .collect::<Vec<_>>()
+    };
+
+    let text = "The quick brown fox jumps over the lazy dog";
+    assert_eq!(
+        wrap(text, Size::new(80, 1000)),
+        ["The quick", "brown fox", "jumps over", "the lazy", "dog"]
+    );
+
+    // the words wider than the line are broken
+    assert_eq!(
+        wrap("a abcdefghijklmnopqrstuvwxyz", Size::new(80, 1000)),
+        ["a", "abcdefghij", "klmnopqrst", "uvwxyz"]
+    );
+
+    // the lines below the rectangle are clipped
+    assert_eq!(wrap(text, Size::new(80, 20)), ["The quick"]);
+    assert_eq!(wrap(text, Size::new(80, 40)), ["The quick", "brown fox"]);
+}
+
+#[test]
+fn fixed_font_notdef() {
+    use font::*;
+    let font = FixedFontDriver::new(
+        FONT_MEGH0816_WIDTH,
+        FONT_MEGH0816_HEIGHT,
+        &FONT_MEGH0816_DATA,
+    );
+
+    // "é" is two bytes in UTF-8, but takes a single cell of the `.notdef` glyph
+    let text = "aé b";
+    let mut glyphs = Vec::new();
+    let mut x = 0;
+    for c in text.chars() {
+        font.draw_glyph(c, Point::new(x, 0), |glyph, size, origin| {
+            glyphs.push((c, origin.x, size, glyph.to_vec()))
+        });
+        x += font.width();
+    }
+    assert_eq!(
+        glyphs.iter().map(|v| (v.0, v.1)).collect::<Vec<_>>(),
+        [('a', 0), ('é', 8), ('b', 24)]
+    );
+    assert_eq!(advance_of(text, |_| font.width(), |_, _| 0), 8 * 4);
+
+    let (_, _, size, notdef) = &glyphs[1];
+    assert_eq!(*size, Size::new(8, 16));
+    assert_eq!(notdef[0], 0);
+    assert_eq!(notdef[1], 0b0111_1110);
+    assert_eq!(notdef[2], 0b0100_0010);
+    assert_eq!(notdef[14], 0b0111_1110);
+    assert_eq!(notdef[15], 0);
+}
+
+#[test]
+fn box_drawing_glyph() {
+    let mut buf = [0xAAu8; 64];
+    let pixels = |glyph: &[u8], width: usize| {
+        let row_stride = width.div_ceil(8);
+        glyph
+            .chunks(row_stride)
+            .map(|row| {
+                (0..width)
+                    .map(|x| ['.', '#'][(row[x / 8] >> (7 - (x & 7))) as usize & 1])
+                    .collect::<alloc::string::String>()
+            })
+            .collect::<Vec<_>>()
+    };
+
+    // CP437 0xDB is the full block, which fills the whole cell
+    let glyph = font::box_drawing_glyph('\u{2588}', 7, 12, &mut buf).unwrap();
+    assert_eq!(glyph.len(), 12);
+    assert!(glyph.iter().all(|row| *row == 0xFE));
+    let glyph = font::box_drawing_glyph('\u{2588}', 8, 20, &mut buf).unwrap();
+    assert!(glyph.iter().all(|row| *row == 0xFF));
+
+    // the halves
+    let glyph = font::box_drawing_glyph('\u{2580}', 8, 4, &mut buf).unwrap();
+    assert_eq!(glyph, [0xFF, 0xFF, 0x00, 0x00]);
+    let glyph = font::box_drawing_glyph('\u{258C}', 8, 4, &mut buf).unwrap();
+    assert_eq!(glyph, [0xF0, 0xF0, 0xF0, 0xF0]);
+
+    // the lines reach the edges so that adjacent cells connect
+    let glyph = font::box_drawing_glyph('\u{253C}', 5, 5, &mut buf).unwrap();
+    assert_eq!(
+        pixels(glyph, 5),
+        ["..#..", "..#..", "#####", "..#..", "..#.."]
+    );
+    let glyph = font::box_drawing_glyph('\u{2554}', 7, 7, &mut buf).unwrap();
+    assert_eq!(
+        pixels(glyph, 7),
+        [".......", ".......", "..#####", "..#....", "..#.###", "..#.#..", "..#.#.."]
+    );
+
+    // the light shade
+    let glyph = font::box_drawing_glyph('\u{2591}', 4, 4, &mut buf).unwrap();
+    assert_eq!(pixels(glyph, 4), ["#.#.", "....", "#.#.", "...."]);
+
+    // other characters, too small cells and buffers
+    assert!(font::box_drawing_glyph('A', 8, 16, &mut buf).is_none());
+    assert!(font::box_drawing_glyph('\u{2588}', 3, 16, &mut buf).is_none());
+    assert!(font::box_drawing_glyph('\u{2588}', 8, 16, &mut buf[..15]).is_none());
+}
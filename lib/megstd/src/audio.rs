@@ -0,0 +1,165 @@
+//! PCM Audio Mixer
+
+use alloc::{collections::BTreeMap, vec::Vec};
+
+/// Volume at which the samples are mixed as is
+pub const PCM_VOLUME_MAX: u16 = 256;
+
+/// A 16-bit monaural PCM stream played by [`PcmMixer`]
+#[derive(Debug)]
+pub struct PcmStream {
+    samples: Vec<i16>,
+    /// Source samples per output sample in 16.16 fixed point
+    step: u64,
+    /// Current position in 16.16 fixed point
+    position: u64,
+    volume: u16,
+}
+
+impl PcmStream {
+    #[inline]
+    pub fn new(samples: Vec<i16>, sample_rate: u32, output_rate: u32) -> Self {
+        Self {
+            samples,
+            step: ((sample_rate as u64) << 16) / output_rate.max(1) as u64,
+            position: 0,
+            volume: PCM_VOLUME_MAX,
+        }
+    }
+
+    #[inline]
+    pub const fn volume(&self) -> u16 {
+        self.volume
+    }
+
+    #[inline]
+    pub fn set_volume(&mut self, volume: u16) {
+        self.volume = volume.min(PCM_VOLUME_MAX);
+    }
+
+    #[inline]
+    pub fn is_finished(&self) -> bool {
+        (self.position >> 16) as usize >= self.samples.len()
+    }
+
+    /// Returns the next sample scaled by the volume.
+    #[inline]
+    fn next_sample(&mut self) -> Option<i32> {
+        let sample = *self.samples.get((self.position >> 16) as usize)?;
+        self.position += self.step;
+        Some(sample as i32 * self.volume as i32 / PCM_VOLUME_MAX as i32)
+    }
+}
+
+/// Mixes multiple PCM streams identified by the handles into a single output
+#[derive(Debug)]
+pub struct PcmMixer<K> {
+    output_rate: u32,
+    streams: BTreeMap<K, PcmStream>,
+}
+
+impl<K: Ord> PcmMixer<K> {
+    #[inline]
+    pub const fn new(output_rate: u32) -> Self {
+        Self {
+            output_rate,
+            streams: BTreeMap::new(),
+        }
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.streams.is_empty()
+    }
+
+    /// Starts playing the samples as the stream of the handle.
+    #[inline]
+    pub fn play(&mut self, handle: K, samples: Vec<i16>, sample_rate: u32) {
+        self.streams.insert(
+            handle,
+            PcmStream::new(samples, sample_rate, self.output_rate),
+        );
+    }
+
+    #[inline]
+    pub fn set_volume(&mut self, handle: &K, volume: u16) {
+        if let Some(stream) = self.streams.get_mut(handle) {
+            stream.set_volume(volume);
+        }
+    }
+
+    #[inline]
+    pub fn stop(&mut self, handle: &K) {
+        self.streams.remove(handle);
+    }
+
+    /// Adds the streams to the samples in the buffer, the results are saturated.
+    ///
+    /// Finished streams are removed.
+    pub fn mix_into(&mut self, buffer: &mut [i16]) {
+        for data in buffer.iter_mut() {
+            let mut acc = *data as i32;
+            for stream in self.streams.values_mut() {
+                acc += stream.next_sample().unwrap_or(0);
+            }
+            *data = acc.clamp(i16::MIN as i32, i16::MAX as i32) as i16;
+        }
+        self.streams.retain(|_, stream| !stream.is_finished());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    #[test]
+    fn pcm_stream() {
+        // plays every other sample at the double rate
+        let mut stream = PcmStream::new(vec![1000, 2000, 3000, 4000], 16000, 8000);
+        assert_eq!(stream.next_sample(), Some(1000));
+        assert_eq!(stream.next_sample(), Some(3000));
+        assert!(stream.is_finished());
+        assert_eq!(stream.next_sample(), None);
+
+        // repeats each sample at the half rate
+        let mut stream = PcmStream::new(vec![1000, -2000], 4000, 8000);
+        stream.set_volume(PCM_VOLUME_MAX * 2);
+        assert_eq!(stream.volume(), PCM_VOLUME_MAX);
+        stream.set_volume(PCM_VOLUME_MAX / 2);
+        assert_eq!(stream.next_sample(), Some(500));
+        assert_eq!(stream.next_sample(), Some(500));
+        assert_eq!(stream.next_sample(), Some(-1000));
+        assert!(!stream.is_finished());
+        assert_eq!(stream.next_sample(), Some(-1000));
+        assert!(stream.is_finished());
+    }
+
+    #[test]
+    fn pcm_mixer() {
+        let mut mixer = PcmMixer::new(8000);
+        assert!(mixer.is_empty());
+
+        mixer.play(1, vec![30000, 30000, -30000, 100], 8000);
+        mixer.play(2, vec![10000, -1000, -10000], 8000);
+        let mut buffer = [0, 0, 0, 0, 0];
+        mixer.mix_into(&mut buffer[..2]);
+        // saturates instead of wrapping around
+        assert_eq!(buffer[..2], [i16::MAX, 29000]);
+        assert!(!mixer.is_empty());
+
+        mixer.set_volume(&1, 0);
+        mixer.mix_into(&mut buffer[2..]);
+        assert_eq!(buffer[2..], [-10000, 0, 0]);
+        // finished streams are removed
+        assert!(mixer.is_empty());
+
+        // mixed with the samples already in the buffer
+        mixer.play(3, vec![-20000, 1, 2], 8000);
+        let mut buffer = [-20000, 10];
+        mixer.mix_into(&mut buffer);
+        assert_eq!(buffer, [i16::MIN, 11]);
+        mixer.stop(&3);
+        assert!(mixer.is_empty());
+    }
+}
@@ -0,0 +1,212 @@
+//! Stack backtrace by walking the frame pointers
+
+use core::fmt;
+
+/// Maximum number of frames to walk
+pub const MAX_DEPTH: usize = 64;
+
+/// Maximum distance between the adjacent frames
+pub const MAX_FRAME_SIZE: usize = 0x10_0000;
+
+/// Walks the chain of the frame pointers.
+///
+/// Each frame consists of the saved frame pointer of the caller, followed by the return address.
+/// Since the stack grows down, the walk stops when the next frame is not above the current one,
+/// so that a corrupted chain can not make a loop.
+pub struct FrameWalker<F> {
+    frame_pointer: usize,
+    depth: usize,
+    read: F,
+}
+
+impl<F> FrameWalker<F>
+where
+    F: FnMut(usize) -> Option<usize>,
+{
+    /// Creates a walker starting at the frame pointer.
+    ///
+    /// `read` returns the word at the address, or `None` if it can not be read.
+    #[inline]
+    pub const fn new(frame_pointer: usize, read: F) -> Self {
+        Self {
+            frame_pointer,
+            depth: 0,
+            read,
+        }
+    }
+
+    #[inline]
+    fn is_valid_frame(frame_pointer: usize) -> bool {
+        frame_pointer != 0 && (frame_pointer & (core::mem::size_of::<usize>() - 1)) == 0
+    }
+}
+
+impl<F> Iterator for FrameWalker<F>
+where
+    F: FnMut(usize) -> Option<usize>,
+{
+    /// Return address of the frame
+    type Item = usize;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let frame_pointer = self.frame_pointer;
+        if self.depth >= MAX_DEPTH || !Self::is_valid_frame(frame_pointer) {
+            return None;
+        }
+        let return_address = frame_pointer
+            .checked_add(core::mem::size_of::<usize>())
+            .and_then(&mut self.read)
+            .filter(|&v| v != 0)?;
+        let next = (self.read)(frame_pointer).unwrap_or(0);
+        self.frame_pointer = if next > frame_pointer && next - frame_pointer <= MAX_FRAME_SIZE {
+            next
+        } else {
+            0
+        };
+        self.depth += 1;
+        Some(return_address)
+    }
+}
+
+/// Symbol table in the format of `nm -n`
+///
+/// Each line consists of the address in hexadecimal, the type and the name of the symbol,
+/// and the lines are sorted by the address.
+#[derive(Clone, Copy)]
+pub struct SymbolTable<'a> {
+    text: &'a str,
+}
+
+impl<'a> SymbolTable<'a> {
+    #[inline]
+    pub const fn new(text: &'a str) -> Self {
+        Self { text }
+    }
+
+    #[inline]
+    pub fn from_bytes(bytes: &'a [u8]) -> Option<Self> {
+        core::str::from_utf8(bytes).ok().map(Self::new)
+    }
+
+    /// Returns the symbols of the code.
+    fn symbols(&self) -> impl Iterator<Item = (usize, &'a str)> {
+        self.text.lines().filter_map(|line| {
+            let mut iter = line.splitn(3, ' ');
+            let address = usize::from_str_radix(iter.next()?, 16).ok()?;
+            match iter.next()? {
+                "T" | "t" | "W" | "w" => (),
+                _ => return None,
+            }
+            Some((address, iter.next()?))
+        })
+    }
+
+    /// Returns the symbol that contains the address, and the offset from the symbol.
+    pub fn lookup(&self, address: usize) -> Option<Symbol<'a>> {
+        self.symbols()
+            .take_while(|&(base, _)| base <= address)
+            .last()
+            .map(|(base, name)| Symbol {
+                name,
+                offset: address - base,
+            })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Symbol<'a> {
+    pub name: &'a str,
+    pub offset: usize,
+}
+
+impl fmt::Display for Symbol<'_> {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}+{:#x}", self.name, self.offset)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec::Vec;
+
+    const STACK_BASE: usize = 0x1000;
+
+    /// Reads the word from the synthetic stack starting at `STACK_BASE`.
+    fn reader(stack: &[usize]) -> impl FnMut(usize) -> Option<usize> + '_ {
+        move |address| {
+            let offset = address.checked_sub(STACK_BASE)?;
+            if offset % 8 != 0 {
+                return None;
+            }
+            stack.get(offset / 8).copied()
+        }
+    }
+
+    #[test]
+    fn walk() {
+        // Three frames at 0x1000, 0x1020 and 0x1040, the last of which terminates the chain
+        let mut stack = [0usize; 16];
+        stack[0] = 0x1020;
+        stack[1] = 0xAAAA;
+        stack[4] = 0x1040;
+        stack[5] = 0xBBBB;
+        stack[8] = 0;
+        stack[9] = 0xCCCC;
+
+        let frames = FrameWalker::new(STACK_BASE, reader(&stack)).collect::<Vec<_>>();
+        assert_eq!(frames, [0xAAAA, 0xBBBB, 0xCCCC]);
+    }
+
+    #[test]
+    fn corrupted() {
+        // The chain pointing to itself must not loop
+        let mut stack = [0usize; 4];
+        stack[0] = STACK_BASE;
+        stack[1] = 0xAAAA;
+        let frames = FrameWalker::new(STACK_BASE, reader(&stack)).collect::<Vec<_>>();
+        assert_eq!(frames, [0xAAAA]);
+
+        // Misaligned or unreadable frames stop the walk
+        assert_eq!(FrameWalker::new(STACK_BASE + 1, reader(&stack)).count(), 0);
+        assert_eq!(FrameWalker::new(0x8000, reader(&stack)).count(), 0);
+        assert_eq!(FrameWalker::new(0, reader(&stack)).count(), 0);
+    }
+
+    #[test]
+    fn max_depth() {
+        let mut stack = [0usize; 256];
+        for (index, frame) in stack.chunks_exact_mut(2).enumerate() {
+            frame[0] = STACK_BASE + (index + 1) * 16;
+            frame[1] = 0x1_0000 + index;
+        }
+        let walker = FrameWalker::new(STACK_BASE, reader(&stack));
+        assert_eq!(walker.count(), MAX_DEPTH);
+    }
+
+    #[test]
+    fn symbols() {
+        let table = SymbolTable::new(
+            "ffffffff80001000 T _start\n\
+             ffffffff80001100 t kernel::panic\n\
+             ffffffff80002000 r PANIC_MESSAGE\n\
+             ffffffff80003000 T kernel::main\n",
+        );
+        assert_eq!(table.lookup(0xffff_ffff_8000_0fff), None);
+        assert_eq!(
+            table.lookup(0xffff_ffff_8000_1000),
+            Some(Symbol {
+                name: "_start",
+                offset: 0
+            })
+        );
+        let symbol = table.lookup(0xffff_ffff_8000_2010).unwrap();
+        assert_eq!(symbol.name, "kernel::panic");
+        assert_eq!(symbol.offset, 0xF10);
+        assert_eq!(
+            alloc::format!("{}", table.lookup(0xffff_ffff_8000_3004).unwrap()),
+            "kernel::main+0x4"
+        );
+    }
+}
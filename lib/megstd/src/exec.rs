@@ -0,0 +1,88 @@
+//! Executable formats
+
+use alloc::vec::Vec;
+
+/// A recognizer registered to [`MagicRecognizers`]
+#[derive(Debug)]
+struct MagicRecognizer<T> {
+    magic_matcher: fn(&[u8]) -> bool,
+    loader_factory: fn() -> T,
+}
+
+/// Recognizers of additional executable formats, tried in the order of registration
+#[derive(Debug)]
+pub struct MagicRecognizers<T> {
+    entries: Vec<MagicRecognizer<T>>,
+}
+
+impl<T> MagicRecognizers<T> {
+    #[inline]
+    pub const fn new() -> Self {
+        Self {
+            entries: Vec::new(),
+        }
+    }
+
+    /// Registers the factory of the loaders for blobs that the matcher accepts.
+    #[inline]
+    pub fn register(&mut self, magic_matcher: fn(&[u8]) -> bool, loader_factory: fn() -> T) {
+        self.entries.push(MagicRecognizer {
+            magic_matcher,
+            loader_factory,
+        });
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns the loader of the first registered recognizer that accepts the blob.
+    pub fn recognize(&self, blob: &[u8]) -> Option<T> {
+        self.entries
+            .iter()
+            .find(|v| (v.magic_matcher)(blob))
+            .map(|v| (v.loader_factory)())
+    }
+}
+
+impl<T> Default for MagicRecognizers<T> {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn magic_recognizers() {
+        fn builtin(blob: &[u8]) -> Option<&'static str> {
+            blob.starts_with(b"\0asm").then_some("wasm")
+        }
+        let recognize = |recognizers: &MagicRecognizers<&'static str>, blob: &[u8]| {
+            recognizers.recognize(blob).or_else(|| builtin(blob))
+        };
+
+        let mut recognizers = MagicRecognizers::new();
+        assert!(recognizers.is_empty());
+        assert_eq!(recognize(&recognizers, b"\0asm\x01\0\0\0"), Some("wasm"));
+
+        recognizers.register(|blob| blob.starts_with(b"FLAT"), || "flat");
+        // a compressed wasm also begins with the magic of the built-in one
+        recognizers.register(|blob| blob.starts_with(b"\0asmZ"), || "compressed");
+        recognizers.register(|blob| blob.starts_with(b"\0as"), || "shadowed");
+        assert!(!recognizers.is_empty());
+
+        assert_eq!(recognize(&recognizers, b"FLAT\x10\0"), Some("flat"));
+        // registered recognizers are preferred over the built-in ones, in the order of registration
+        assert_eq!(recognize(&recognizers, b"\0asmZ"), Some("compressed"));
+        assert_eq!(
+            recognize(&recognizers, b"\0asm\x01\0\0\0"),
+            Some("shadowed")
+        );
+        assert_eq!(recognize(&recognizers, b"MZ"), None);
+    }
+}
@@ -8,6 +8,8 @@ use crate::{
     *,
 };
 
+pub mod tree;
+
 pub struct File {
     _phantom: (),
 }
@@ -0,0 +1,302 @@
+//! Recursive operations on directory trees
+
+use crate::io::{Error, ErrorKind, Result};
+use alloc::{format, string::String, vec::Vec};
+
+/// Maximum depth of nested directories to walk
+pub const MAX_RECURSION_DEPTH: usize = 64;
+
+const PATH_SEPARATOR: &str = "/";
+
+/// Type of a directory entry
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EntryKind {
+    File,
+    Dir,
+    Symlink,
+}
+
+/// Operations of a file system needed to walk and modify a directory tree
+///
+/// All paths are canonical absolute paths.
+pub trait DirTree {
+    /// Returns the names and types of the entries in the directory.
+    fn entries(&self, path: &str) -> Result<Vec<(String, EntryKind)>>;
+
+    /// Returns the type of the entry at the path.
+    fn kind_of(&self, path: &str) -> Result<EntryKind>;
+
+    fn mkdir(&self, path: &str) -> Result<()>;
+
+    /// Removes a file or an empty directory.
+    fn unlink(&self, path: &str) -> Result<()>;
+
+    /// Copies the contents of a file, creating or truncating the destination.
+    fn copy_contents(&self, src: &str, dst: &str) -> Result<()>;
+}
+
+/// Joins the name to the directory path.
+pub fn join(dir: &str, name: &str) -> String {
+    if dir.ends_with(PATH_SEPARATOR) {
+        format!("{}{}", dir, name)
+    } else {
+        format!("{}{}{}", dir, PATH_SEPARATOR, name)
+    }
+}
+
+/// Returns the last component of the path.
+pub fn file_name(path: &str) -> Option<&str> {
+    path.rsplit(PATH_SEPARATOR).find(|v| !v.is_empty())
+}
+
+/// Removes a directory and all its contents recursively.
+///
+/// If some entries cannot be removed, the remaining entries are still removed
+/// and the first error is returned.
+pub fn remove_dir_all<T: DirTree + ?Sized>(fs: &T, path: &str) -> Result<()> {
+    _remove_dir_all(fs, path, 0)
+}
+
+fn _remove_dir_all<T: DirTree + ?Sized>(fs: &T, path: &str, depth: usize) -> Result<()> {
+    if depth > MAX_RECURSION_DEPTH {
+        return Err(Error::with_context(
+            ErrorKind::FilesystemLoop,
+            "remove directory",
+        ));
+    }
+
+    let mut result = Ok(());
+    for (name, kind) in fs.entries(path)? {
+        let child = join(path, &name);
+        let child_result = match kind {
+            EntryKind::Dir => _remove_dir_all(fs, &child, depth + 1),
+            EntryKind::File | EntryKind::Symlink => fs.unlink(&child),
+        };
+        if result.is_ok() {
+            result = child_result;
+        }
+    }
+    result?;
+
+    fs.unlink(path)
+}
+
+/// Copies a directory and all its contents recursively.
+///
+/// Copying a directory into itself is rejected. Symbolic links are not followed.
+/// If some entries cannot be copied, the remaining entries are still copied
+/// and the first error is returned.
+pub fn copy_dir_all<T: DirTree + ?Sized>(fs: &T, src: &str, dst: &str) -> Result<()> {
+    let src_prefix = join(src, "");
+    let dst_prefix = join(dst, "");
+    if dst_prefix.starts_with(&src_prefix) {
+        return Err(Error::with_context(
+            ErrorKind::InvalidInput,
+            "copy directory into itself",
+        ));
+    }
+
+    _copy_dir_all(fs, src, dst, 0)
+}
+
+fn _copy_dir_all<T: DirTree + ?Sized>(fs: &T, src: &str, dst: &str, depth: usize) -> Result<()> {
+    if depth > MAX_RECURSION_DEPTH {
+        return Err(Error::with_context(
+            ErrorKind::FilesystemLoop,
+            "copy directory",
+        ));
+    }
+
+    let entries = fs.entries(src)?;
+    match fs.mkdir(dst) {
+        Ok(_) => (),
+        Err(err) if err.kind() == ErrorKind::AlreadyExists => (),
+        Err(err) => return Err(err),
+    }
+
+    let mut result = Ok(());
+    for (name, kind) in entries {
+        let src_child = join(src, &name);
+        let dst_child = join(dst, &name);
+        let child_result = match kind {
+            EntryKind::Dir => _copy_dir_all(fs, &src_child, &dst_child, depth + 1),
+            EntryKind::Symlink => Ok(()),
+            EntryKind::File => fs.copy_contents(&src_child, &dst_child),
+        };
+        if result.is_ok() {
+            result = child_result;
+        }
+    }
+    result
+}
+
+/// Copies a file like `cp src dst`.
+///
+/// If `dst` is a directory, the file is copied into it with the same name.
+/// Copying a file onto itself is rejected, as the destination is truncated first.
+pub fn copy_file<T: DirTree + ?Sized>(fs: &T, src: &str, dst: &str) -> Result<String> {
+    let dst = match fs.kind_of(dst) {
+        Ok(EntryKind::Dir) => {
+            let name = file_name(src).ok_or(Error::from(ErrorKind::InvalidInput))?;
+            join(dst, name)
+        }
+        _ => dst.into(),
+    };
+    if src == dst {
+        return Err(Error::with_context(
+            ErrorKind::InvalidInput,
+            "source and destination are the same file",
+        ));
+    }
+    fs.copy_contents(src, &dst)?;
+    Ok(dst)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::{borrow::ToOwned, collections::BTreeMap, vec};
+    use core::cell::RefCell;
+
+    /// Files have contents, directories have none
+    struct MemoryFs(RefCell<BTreeMap<String, Option<Vec<u8>>>>);
+
+    impl MemoryFs {
+        fn new() -> Self {
+            let mut map = BTreeMap::new();
+            map.insert("/".to_owned(), None);
+            Self(RefCell::new(map))
+        }
+
+        fn parent(path: &str) -> String {
+            match path.rfind('/') {
+                Some(0) => "/".to_owned(),
+                Some(index) => path[..index].to_owned(),
+                None => "/".to_owned(),
+            }
+        }
+
+        fn create(&self, path: &str, contents: &[u8]) {
+            self.0
+                .borrow_mut()
+                .insert(path.to_owned(), Some(contents.to_vec()));
+        }
+
+        fn contents(&self, path: &str) -> Option<Vec<u8>> {
+            self.0.borrow().get(path).cloned().flatten()
+        }
+
+        fn paths(&self) -> Vec<String> {
+            self.0.borrow().keys().cloned().collect()
+        }
+    }
+
+    impl DirTree for MemoryFs {
+        fn entries(&self, path: &str) -> Result<Vec<(String, EntryKind)>> {
+            match self.kind_of(path)? {
+                EntryKind::Dir => (),
+                _ => return Err(ErrorKind::NotADirectory.into()),
+            }
+            Ok(self
+                .0
+                .borrow()
+                .iter()
+                .filter(|(k, _)| k.as_str() != path && Self::parent(k) == path)
+                .map(|(k, v)| {
+                    let kind = if v.is_some() {
+                        EntryKind::File
+                    } else {
+                        EntryKind::Dir
+                    };
+                    (file_name(k).unwrap().to_owned(), kind)
+                })
+                .collect())
+        }
+
+        fn kind_of(&self, path: &str) -> Result<EntryKind> {
+            match self.0.borrow().get(path) {
+                Some(Some(_)) => Ok(EntryKind::File),
+                Some(None) => Ok(EntryKind::Dir),
+                None => Err(ErrorKind::NotFound.into()),
+            }
+        }
+
+        fn mkdir(&self, path: &str) -> Result<()> {
+            if self.0.borrow().contains_key(path) {
+                return Err(ErrorKind::AlreadyExists.into());
+            }
+            self.0.borrow_mut().insert(path.to_owned(), None);
+            Ok(())
+        }
+
+        fn unlink(&self, path: &str) -> Result<()> {
+            if self.kind_of(path)? == EntryKind::Dir && !self.entries(path)?.is_empty() {
+                return Err(ErrorKind::DirectoryNotEmpty.into());
+            }
+            self.0.borrow_mut().remove(path);
+            Ok(())
+        }
+
+        fn copy_contents(&self, src: &str, dst: &str) -> Result<()> {
+            let contents = self.contents(src).ok_or(Error::from(ErrorKind::NotFound))?;
+            // Truncates first, like the real implementation
+            self.create(dst, &[]);
+            self.create(dst, &contents);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn copy_and_remove_tree() {
+        let fs = MemoryFs::new();
+        fs.mkdir("/a").unwrap();
+        fs.mkdir("/a/b").unwrap();
+        fs.mkdir("/a/b/c").unwrap();
+        fs.create("/a/1.txt", b"one");
+        fs.create("/a/b/2.txt", b"two");
+        fs.create("/a/b/c/3.txt", b"three");
+
+        copy_dir_all(&fs, "/a", "/z").unwrap();
+        assert_eq!(fs.contents("/z/1.txt").unwrap(), b"one");
+        assert_eq!(fs.contents("/z/b/2.txt").unwrap(), b"two");
+        assert_eq!(fs.contents("/z/b/c/3.txt").unwrap(), b"three");
+
+        assert_eq!(
+            copy_dir_all(&fs, "/a", "/a/b/d").unwrap_err().kind(),
+            ErrorKind::InvalidInput
+        );
+
+        remove_dir_all(&fs, "/a").unwrap();
+        remove_dir_all(&fs, "/z").unwrap();
+        assert_eq!(fs.paths(), vec!["/".to_owned()]);
+    }
+
+    #[test]
+    fn copy_file_into_dir() {
+        let fs = MemoryFs::new();
+        fs.mkdir("/dir").unwrap();
+        fs.create("/a.txt", b"data");
+
+        assert_eq!(copy_file(&fs, "/a.txt", "/dir").unwrap(), "/dir/a.txt");
+        assert_eq!(fs.contents("/dir/a.txt").unwrap(), b"data");
+
+        assert_eq!(copy_file(&fs, "/a.txt", "/b.txt").unwrap(), "/b.txt");
+        assert_eq!(fs.contents("/b.txt").unwrap(), b"data");
+    }
+
+    #[test]
+    fn copy_file_onto_itself() {
+        let fs = MemoryFs::new();
+        fs.create("/a.txt", b"data");
+
+        assert_eq!(
+            copy_file(&fs, "/a.txt", "/a.txt").unwrap_err().kind(),
+            ErrorKind::InvalidInput
+        );
+        assert_eq!(
+            copy_file(&fs, "/a.txt", "/").unwrap_err().kind(),
+            ErrorKind::InvalidInput
+        );
+        assert_eq!(fs.contents("/a.txt").unwrap(), b"data");
+    }
+}
@@ -0,0 +1,104 @@
+//! Asynchronous utilities
+
+use core::{
+    future::{poll_fn, Future},
+    pin::pin,
+    task::Poll,
+};
+
+/// Runs the operation until the timer completes first, then cancels it.
+///
+/// After `cancel` succeeds, the operation is still awaited to completion, because it may own
+/// resources such as DMA buffers until the cancellation takes effect.
+/// Returns the output of the operation and whether it was cancelled.
+pub async fn cancel_on_timeout<F, T, C, E>(
+    operation: F,
+    timer: T,
+    cancel: C,
+) -> Result<(F::Output, bool), E>
+where
+    F: Future,
+    T: Future<Output = ()>,
+    C: FnOnce() -> Result<(), E>,
+{
+    let mut operation = pin!(operation);
+    let mut timer = pin!(timer);
+    let output = poll_fn(|cx| match operation.as_mut().poll(cx) {
+        Poll::Ready(v) => Poll::Ready(Some(v)),
+        Poll::Pending => timer.as_mut().poll(cx).map(|_| None),
+    })
+    .await;
+    match output {
+        Some(v) => Ok((v, false)),
+        None => {
+            cancel()?;
+            Ok((operation.await, true))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::{
+        cell::Cell,
+        future::{pending, ready},
+        task::{Context, Waker},
+    };
+
+    fn block_on<F: Future>(future: F) -> F::Output {
+        let mut future = pin!(future);
+        let mut cx = Context::from_waker(Waker::noop());
+        loop {
+            if let Poll::Ready(v) = future.as_mut().poll(&mut cx) {
+                return v;
+            }
+        }
+    }
+
+    /// A mock transfer that never completes until it is aborted
+    async fn hung_transfer(is_aborted: &Cell<bool>) -> Result<usize, &'static str> {
+        poll_fn(|_| {
+            if is_aborted.get() {
+                Poll::Ready(Err("aborted"))
+            } else {
+                Poll::Pending
+            }
+        })
+        .await
+    }
+
+    #[test]
+    fn cancel_on_timeout_completes() {
+        let result = block_on(cancel_on_timeout(
+            ready(Ok::<_, &str>(42)),
+            pending(),
+            || -> Result<(), &str> { panic!("must not be cancelled") },
+        ));
+        assert_eq!(result, Ok((Ok(42), false)));
+    }
+
+    #[test]
+    fn cancel_on_timeout_expires() {
+        let is_aborted = Cell::new(false);
+        let result = block_on(cancel_on_timeout(
+            hung_transfer(&is_aborted),
+            ready(()),
+            || -> Result<(), &str> {
+                is_aborted.set(true);
+                Ok(())
+            },
+        ));
+        assert_eq!(result, Ok((Err("aborted"), true)));
+
+        // The operation is dropped without waiting if the cancellation fails
+        let is_aborted = Cell::new(false);
+        let result = block_on(cancel_on_timeout(
+            hung_transfer(&is_aborted),
+            ready(()),
+            || Err("busy"),
+        ));
+        assert_eq!(result, Err("busy"));
+        assert!(!is_aborted.get());
+    }
+}
@@ -0,0 +1,386 @@
+//! ANSI/VT100 escape sequences
+
+/// Maps the ANSI color numbers to the indexes of the console palette
+const ANSI_TO_INDEX: [u8; 8] = [0, 4, 2, 6, 1, 5, 3, 7];
+
+/// Events decoded by [`AnsiParser`]
+#[derive(Debug, Clone, Copy)]
+pub enum AnsiEvent {
+    /// A character to be printed or a control character
+    Char(char),
+    /// `CSI n A`
+    CursorUp(usize),
+    /// `CSI n B`
+    CursorDown(usize),
+    /// `CSI n C`
+    CursorForward(usize),
+    /// `CSI n D`
+    CursorBack(usize),
+    /// `CSI row ; col H`, zero based
+    CursorPosition(usize, usize),
+    /// `CSI n J`, 0: cursor to end, 1: beginning to cursor, 2: entire screen
+    EraseDisplay(usize),
+    /// `CSI n K`, 0: cursor to end, 1: beginning to cursor, 2: entire line
+    EraseLine(usize),
+    /// `CSI n ; ... m`
+    SelectGraphicRendition(CsiParams),
+    /// `ESC ( B` selects [`GlyphSet::Unicode`], `ESC ( U` selects [`GlyphSet::Cp437`]
+    SelectGlyphSet(GlyphSet),
+}
+
+/// How the console interprets characters in `U+0080..=U+00FF`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum GlyphSet {
+    /// Characters are drawn as they are
+    #[default]
+    Unicode,
+    /// Bytes `0xB0..=0xDF` are drawn as the shade, block and box drawing glyphs of Code Page 437
+    Cp437,
+}
+
+impl GlyphSet {
+    /// Converts the character to the one to be drawn in this glyph set.
+    #[inline]
+    pub fn map_char(&self, c: char) -> char {
+        match self {
+            Self::Unicode => c,
+            Self::Cp437 => match c as u32 {
+                0xB0..=0xDF => CP437_B0_DF[c as usize - 0xB0],
+                _ => c,
+            },
+        }
+    }
+}
+
+static CP437_B0_DF: [char; 48] = [
+    '░', '▒', '▓', '│', '┤', '╡', '╢', '╖', '╕', '╣', '║', '╗', '╝', '╜', '╛', '┐', //
+    '└', '┴', '┬', '├', '─', '┼', '╞', '╟', '╚', '╔', '╩', '╦', '╠', '═', '╬', '╧', //
+    '╨', '╤', '╥', '╙', '╘', '╒', '╓', '╫', '╪', '┘', '┌', '█', '▄', '▌', '▐', '▀', //
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    Ground,
+    Escape,
+    Csi,
+    /// `ESC (`, designating the character set
+    Charset,
+    /// Private or malformed sequences that are swallowed until the final byte
+    Ignore,
+    /// Operating system commands that are swallowed until BEL or ST
+    Osc,
+}
+
+/// Decodes the subset of ANSI escape sequences supported by the console.
+///
+/// Unsupported sequences are swallowed.
+pub struct AnsiParser {
+    state: State,
+    params: CsiParams,
+}
+
+impl Default for AnsiParser {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AnsiParser {
+    #[inline]
+    pub const fn new() -> Self {
+        Self {
+            state: State::Ground,
+            params: CsiParams::new(),
+        }
+    }
+
+    /// Feeds a character and returns an event if a character or a sequence is completed.
+    pub fn feed(&mut self, c: char) -> Option<AnsiEvent> {
+        if c == '\x1B' {
+            self.state = State::Escape;
+            return None;
+        }
+        match self.state {
+            State::Ground => Some(AnsiEvent::Char(c)),
+            State::Escape => {
+                self.state = match c {
+                    '[' => {
+                        self.params = CsiParams::new();
+                        State::Csi
+                    }
+                    ']' => State::Osc,
+                    '(' => State::Charset,
+                    _ => State::Ground,
+                };
+                None
+            }
+            State::Charset => {
+                self.state = State::Ground;
+                match c {
+                    'B' => Some(AnsiEvent::SelectGlyphSet(GlyphSet::Unicode)),
+                    'U' => Some(AnsiEvent::SelectGlyphSet(GlyphSet::Cp437)),
+                    _ => None,
+                }
+            }
+            State::Csi => match c {
+                '0'..='9' => {
+                    self.params.push_digit(c as u8 - b'0');
+                    None
+                }
+                ';' => {
+                    self.params.next();
+                    None
+                }
+                '\x40'..='\x7E' => {
+                    self.state = State::Ground;
+                    self.dispatch(c)
+                }
+                _ => {
+                    self.state = State::Ignore;
+                    None
+                }
+            },
+            State::Ignore => {
+                if matches!(c, '\x40'..='\x7E') {
+                    self.state = State::Ground;
+                }
+                None
+            }
+            State::Osc => {
+                if c == '\x07' {
+                    self.state = State::Ground;
+                }
+                None
+            }
+        }
+    }
+
+    fn dispatch(&self, c: char) -> Option<AnsiEvent> {
+        let params = &self.params;
+        let n = params.get(0).max(1) as usize;
+        match c {
+            'A' => Some(AnsiEvent::CursorUp(n)),
+            'B' => Some(AnsiEvent::CursorDown(n)),
+            'C' => Some(AnsiEvent::CursorForward(n)),
+            'D' => Some(AnsiEvent::CursorBack(n)),
+            'H' | 'f' => Some(AnsiEvent::CursorPosition(
+                params.get(0).max(1) as usize - 1,
+                params.get(1).max(1) as usize - 1,
+            )),
+            'J' => Some(AnsiEvent::EraseDisplay(params.get(0) as usize)),
+            'K' => Some(AnsiEvent::EraseLine(params.get(0) as usize)),
+            'm' => Some(AnsiEvent::SelectGraphicRendition(*params)),
+            _ => None,
+        }
+    }
+}
+
+/// Numeric parameters of a control sequence
+#[derive(Debug, Clone, Copy)]
+pub struct CsiParams {
+    values: [u16; Self::MAX_PARAMS],
+    len: usize,
+}
+
+impl CsiParams {
+    const MAX_PARAMS: usize = 16;
+
+    #[inline]
+    const fn new() -> Self {
+        Self {
+            values: [0; Self::MAX_PARAMS],
+            len: 0,
+        }
+    }
+
+    fn push_digit(&mut self, digit: u8) {
+        if self.len == 0 {
+            self.len = 1;
+        }
+        if let Some(value) = self.values.get_mut(self.len - 1) {
+            *value = value.saturating_mul(10).saturating_add(digit as u16);
+        }
+    }
+
+    fn next(&mut self) {
+        // An omitted parameter before ';' counts as 0
+        self.len = (self.len.max(1) + 1).min(Self::MAX_PARAMS + 1);
+    }
+
+    /// Returns the parameter at the index, omitted ones are 0.
+    #[inline]
+    pub fn get(&self, index: usize) -> u16 {
+        self.as_slice().get(index).copied().unwrap_or(0)
+    }
+
+    #[inline]
+    pub fn as_slice(&self) -> &[u16] {
+        &self.values[..self.len.min(Self::MAX_PARAMS)]
+    }
+
+    /// Applies the graphic rendition to the console attribute (background << 4 | foreground).
+    pub fn apply_sgr(&self, attribute: u8, default_attribute: u8) -> u8 {
+        let mut fg = attribute & 0x0F;
+        let mut bg = attribute >> 4;
+        let params = self.as_slice();
+        let params = if params.is_empty() { &[0] } else { params };
+        let mut iter = params.iter();
+        while let Some(&param) = iter.next() {
+            match param {
+                0 => {
+                    fg = default_attribute & 0x0F;
+                    bg = default_attribute >> 4;
+                }
+                1 => fg |= 0x08,
+                22 => fg &= 0x07,
+                7 => (fg, bg) = (bg, fg),
+                30..=37 => fg = ANSI_TO_INDEX[param as usize - 30] | (fg & 0x08),
+                39 => fg = default_attribute & 0x0F,
+                40..=47 => bg = ANSI_TO_INDEX[param as usize - 40],
+                49 => bg = default_attribute >> 4,
+                90..=97 => fg = ANSI_TO_INDEX[param as usize - 90] | 0x08,
+                100..=107 => bg = ANSI_TO_INDEX[param as usize - 100] | 0x08,
+                38 | 48 => {
+                    // Extended colors are not supported, skip their arguments
+                    match iter.next() {
+                        Some(5) => {
+                            iter.next();
+                        }
+                        Some(2) => {
+                            iter.nth(2);
+                        }
+                        _ => (),
+                    }
+                }
+                _ => (),
+            }
+        }
+        (bg << 4) | fg
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn feed_str(parser: &mut AnsiParser, s: &str) -> Option<AnsiEvent> {
+        let mut result = None;
+        for c in s.chars() {
+            if let Some(event) = parser.feed(c) {
+                assert!(result.is_none(), "more than one event in {:?}", s);
+                result = Some(event);
+            }
+        }
+        result
+    }
+
+    #[test]
+    fn chars() {
+        let mut parser = AnsiParser::new();
+        assert!(matches!(parser.feed('a'), Some(AnsiEvent::Char('a'))));
+        assert!(matches!(parser.feed('\n'), Some(AnsiEvent::Char('\n'))));
+    }
+
+    #[test]
+    fn cursor() {
+        let mut parser = AnsiParser::new();
+        assert!(matches!(
+            feed_str(&mut parser, "\x1B[A"),
+            Some(AnsiEvent::CursorUp(1))
+        ));
+        assert!(matches!(
+            feed_str(&mut parser, "\x1B[12D"),
+            Some(AnsiEvent::CursorBack(12))
+        ));
+        assert!(matches!(
+            feed_str(&mut parser, "\x1B[5;10H"),
+            Some(AnsiEvent::CursorPosition(4, 9))
+        ));
+        assert!(matches!(
+            feed_str(&mut parser, "\x1B[H"),
+            Some(AnsiEvent::CursorPosition(0, 0))
+        ));
+    }
+
+    #[test]
+    fn erase() {
+        let mut parser = AnsiParser::new();
+        assert!(matches!(
+            feed_str(&mut parser, "\x1B[2J"),
+            Some(AnsiEvent::EraseDisplay(2))
+        ));
+        assert!(matches!(
+            feed_str(&mut parser, "\x1B[J"),
+            Some(AnsiEvent::EraseDisplay(0))
+        ));
+        assert!(matches!(
+            feed_str(&mut parser, "\x1B[1K"),
+            Some(AnsiEvent::EraseLine(1))
+        ));
+    }
+
+    #[test]
+    fn sgr() {
+        let mut parser = AnsiParser::new();
+        let Some(AnsiEvent::SelectGraphicRendition(params)) =
+            feed_str(&mut parser, "\x1B[1;31;44m")
+        else {
+            panic!()
+        };
+        assert_eq!(params.as_slice(), &[1, 31, 44]);
+        assert_eq!(params.apply_sgr(0x07, 0x07), 0x1C);
+
+        let Some(AnsiEvent::SelectGraphicRendition(params)) = feed_str(&mut parser, "\x1B[m")
+        else {
+            panic!()
+        };
+        assert_eq!(params.apply_sgr(0x1C, 0x07), 0x07);
+
+        // Extended colors are skipped with their arguments
+        let Some(AnsiEvent::SelectGraphicRendition(params)) =
+            feed_str(&mut parser, "\x1B[38;5;196;32m")
+        else {
+            panic!()
+        };
+        assert_eq!(params.apply_sgr(0x07, 0x07), 0x02);
+    }
+
+    #[test]
+    fn glyph_set() {
+        let mut parser = AnsiParser::new();
+        assert!(matches!(
+            feed_str(&mut parser, "\x1B(U"),
+            Some(AnsiEvent::SelectGlyphSet(GlyphSet::Cp437))
+        ));
+        assert!(matches!(
+            feed_str(&mut parser, "\x1B(B"),
+            Some(AnsiEvent::SelectGlyphSet(GlyphSet::Unicode))
+        ));
+        // DEC special graphics are not supported
+        assert!(feed_str(&mut parser, "\x1B(0").is_none());
+        assert!(matches!(parser.feed('x'), Some(AnsiEvent::Char('x'))));
+
+        let cp437 = GlyphSet::Cp437;
+        assert_eq!(cp437.map_char('\u{B0}'), '░');
+        assert_eq!(cp437.map_char('\u{B3}'), '│');
+        assert_eq!(cp437.map_char('\u{C5}'), '┼');
+        assert_eq!(cp437.map_char('\u{CD}'), '═');
+        assert_eq!(cp437.map_char('\u{DB}'), '█');
+        assert_eq!(cp437.map_char('\u{DF}'), '▀');
+        // outside of the box drawing range
+        assert_eq!(cp437.map_char('\u{AF}'), '\u{AF}');
+        assert_eq!(cp437.map_char('\u{E0}'), '\u{E0}');
+        assert_eq!(cp437.map_char('A'), 'A');
+        assert_eq!(GlyphSet::Unicode.map_char('\u{B3}'), '\u{B3}');
+    }
+
+    #[test]
+    fn unsupported() {
+        let mut parser = AnsiParser::new();
+        assert!(feed_str(&mut parser, "\x1B[?25l").is_none());
+        assert!(feed_str(&mut parser, "\x1B]0;title\x07").is_none());
+        assert!(feed_str(&mut parser, "\x1B[5S").is_none());
+        assert!(matches!(parser.feed('x'), Some(AnsiEvent::Char('x'))));
+    }
+}
@@ -0,0 +1,82 @@
+//! Character devices
+
+use super::{ErrorKind, Result};
+
+/// Callbacks of a character device
+#[derive(Clone, Copy)]
+pub struct CharDeviceOps {
+    pub read: fn(buf: &mut [u8]) -> Result<usize>,
+    pub write: fn(buf: &[u8]) -> Result<usize>,
+    pub ioctl: fn(request: usize, arg: usize) -> Result<usize>,
+}
+
+impl CharDeviceOps {
+    /// Reads nothing, discards writes, and supports no requests
+    pub const DEFAULT: Self = Self {
+        read: |_| Ok(0),
+        write: |buf| Ok(buf.len()),
+        ioctl: |_, _| Err(ErrorKind::Unsupported.into()),
+    };
+
+    /// Null device, that is the same as [`Self::DEFAULT`]
+    pub const NULL: Self = Self::DEFAULT;
+
+    /// Reads zeros and discards writes
+    pub const ZERO: Self = Self {
+        read: Self::read_zeros,
+        ..Self::DEFAULT
+    };
+
+    /// Reads zeros and fails to write for lack of space
+    pub const FULL: Self = Self {
+        read: Self::read_zeros,
+        write: |_| Err(ErrorKind::StorageFull.into()),
+        ..Self::DEFAULT
+    };
+
+    #[inline]
+    fn read_zeros(buf: &mut [u8]) -> Result<usize> {
+        buf.fill(0);
+        Ok(buf.len())
+    }
+}
+
+impl Default for CharDeviceOps {
+    #[inline]
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn char_device_ops() {
+        let mut buf = [0xFF; 8];
+
+        let null = CharDeviceOps::NULL;
+        assert_eq!((null.read)(&mut buf).unwrap(), 0);
+        assert_eq!(buf, [0xFF; 8]);
+        assert_eq!((null.write)(b"discarded").unwrap(), 9);
+        assert_eq!(
+            (null.ioctl)(1, 0).unwrap_err().kind(),
+            ErrorKind::Unsupported
+        );
+
+        let zero = CharDeviceOps::ZERO;
+        assert_eq!((zero.read)(&mut buf).unwrap(), 8);
+        assert_eq!(buf, [0; 8]);
+        assert_eq!((zero.write)(b"discarded").unwrap(), 9);
+
+        let full = CharDeviceOps::FULL;
+        buf.fill(0xFF);
+        assert_eq!((full.read)(&mut buf[..4]).unwrap(), 4);
+        assert_eq!(buf, [0, 0, 0, 0, 0xFF, 0xFF, 0xFF, 0xFF]);
+        assert_eq!(
+            (full.write)(b"rejected").unwrap_err().kind(),
+            ErrorKind::StorageFull
+        );
+    }
+}
@@ -185,6 +185,61 @@ pub enum ErrorKind {
     Uncategorized,
 }
 
+impl ErrorKind {
+    pub(crate) fn as_str(&self) -> &'static str {
+        use ErrorKind::*;
+        match *self {
+            NotFound => "entity not found",
+            PermissionDenied => "permission denied",
+            ConnectionRefused => "connection refused",
+            ConnectionReset => "connection reset",
+            HostUnreachable => "host unreachable",
+            NetworkUnreachable => "network unreachable",
+            ConnectionAborted => "connection aborted",
+            NotConnected => "not connected",
+            AddrInUse => "address in use",
+            AddrNotAvailable => "address not available",
+            NetworkDown => "network down",
+            BrokenPipe => "broken pipe",
+            AlreadyExists => "entity already exists",
+            WouldBlock => "operation would block",
+            NotADirectory => "not a directory",
+            IsADirectory => "is a directory",
+            DirectoryNotEmpty => "directory not empty",
+            ReadOnlyFilesystem => "read-only filesystem or storage medium",
+            FilesystemLoop => "filesystem loop or indirection limit (e.g. symlink loop)",
+            StaleNetworkFileHandle => "stale network file handle",
+            InvalidInput => "invalid input parameter",
+            InvalidData => "invalid data",
+            TimedOut => "timed out",
+            WriteZero => "write zero",
+            StorageFull => "no storage space",
+            NotSeekable => "seek on unseekable file",
+            FilesystemQuotaExceeded => "filesystem quota exceeded",
+            FileTooLarge => "file too large",
+            ResourceBusy => "resource busy",
+            ExecutableFileBusy => "executable file busy",
+            Deadlock => "deadlock",
+            CrossesDevices => "cross-device link or rename",
+            TooManyLinks => "too many links",
+            FilenameTooLong => "filename too long",
+            ArgumentListTooLong => "argument list too long",
+            Interrupted => "operation interrupted",
+            Unsupported => "unsupported",
+            UnexpectedEof => "unexpected end of file",
+            OutOfMemory => "out of memory",
+            Other => "other error",
+            Uncategorized => "uncategorized error",
+        }
+    }
+}
+
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
 pub struct Error {
     repr: Repr,
 }
@@ -195,10 +250,24 @@ impl fmt::Debug for Error {
     }
 }
 
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.repr {
+            Repr::Os(code) => write!(f, "os error {}", code),
+            Repr::Simple(kind) => f.write_str(kind.as_str()),
+            Repr::SimpleMessage(kind, context) => write!(f, "{}: {}", context, kind.as_str()),
+            Repr::Custom(ref v) => fmt::Display::fmt(&v.error, f),
+        }
+    }
+}
+
+impl error::Error for Error {}
+
 #[derive(Debug)]
 enum Repr {
     Os(i32),
     Simple(ErrorKind),
+    SimpleMessage(ErrorKind, &'static str),
     Custom(Box<Custom>),
 }
 
@@ -221,6 +290,25 @@ impl Error {
         }
     }
 
+    /// Creates an error with a static description of the operation that failed.
+    ///
+    /// Unlike [`Error::new`], this does not allocate.
+    #[inline]
+    pub const fn with_context(kind: ErrorKind, context: &'static str) -> Self {
+        Self {
+            repr: Repr::SimpleMessage(kind, context),
+        }
+    }
+
+    #[inline]
+    pub fn context(&self) -> Option<&'static str> {
+        if let Repr::SimpleMessage(_, context) = self.repr {
+            Some(context)
+        } else {
+            None
+        }
+    }
+
     pub fn last_os_error() -> Self {
         todo!()
     }
@@ -267,6 +355,7 @@ impl Error {
         match self.repr {
             Repr::Os(_) => todo!(),
             Repr::Simple(kind) => kind,
+            Repr::SimpleMessage(kind, _) => kind,
             Repr::Custom(ref v) => v.kind,
         }
     }
@@ -279,3 +368,24 @@ impl From<ErrorKind> for Error {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::ToString;
+
+    #[test]
+    fn display_with_context() {
+        let error = Error::with_context(ErrorKind::NotADirectory, "chdir");
+        assert_eq!(error.kind(), ErrorKind::NotADirectory);
+        assert_eq!(error.context(), Some("chdir"));
+
+        let message = error.to_string();
+        assert!(message.contains("chdir"));
+        assert!(message.contains(ErrorKind::NotADirectory.as_str()));
+
+        let error = Error::from(ErrorKind::NotFound);
+        assert_eq!(error.context(), None);
+        assert_eq!(error.to_string(), "entity not found");
+    }
+}
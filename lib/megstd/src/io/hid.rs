@@ -353,7 +353,9 @@ impl Usage {
     pub const KEY_F10: Self = Self(0x43);
     pub const KEY_F11: Self = Self(0x44);
     pub const KEY_F12: Self = Self(0x45);
+    pub const KEY_PAGE_UP: Self = Self(0x4B);
     pub const DELETE: Self = Self(0x4C);
+    pub const KEY_PAGE_DOWN: Self = Self(0x4E);
     pub const KEY_RIGHT_ARROW: Self = Self(0x4F);
     pub const KEY_LEFT_ARROW: Self = Self(0x50);
     pub const KEY_DOWN_ARROW: Self = Self(0x51);
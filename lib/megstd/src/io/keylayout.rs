@@ -0,0 +1,270 @@
+//! Keyboard layouts that map HID usages to characters
+
+use super::hid::*;
+
+/// The character for keys that do not produce any characters
+pub const INVALID_UNICHAR: char = '\u{FEFF}';
+
+/// Keyboard layout
+#[repr(u8)]
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum KeyLayout {
+    /// Japanese 109 keyboard
+    #[default]
+    Jis = 0,
+    /// US 101 keyboard
+    Us,
+    /// US 101 keyboard with dead keys for accents
+    UsInternational,
+}
+
+impl KeyLayout {
+    pub const ALL: [Self; 3] = [Self::Jis, Self::Us, Self::UsInternational];
+
+    #[inline]
+    pub const fn name(&self) -> &'static str {
+        match self {
+            Self::Jis => "jis",
+            Self::Us => "us",
+            Self::UsInternational => "us-intl",
+        }
+    }
+
+    #[inline]
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|v| v.name() == name)
+    }
+
+    /// Returns the character of the key, or [`INVALID_UNICHAR`] if the key does not produce any.
+    ///
+    /// Dead keys are not taken into account, see [`KeyLayout::dead_key`].
+    pub fn usage_to_char(&self, usage: Usage, modifier: Modifier) -> char {
+        match self {
+            Self::Jis => Self::usage_to_char_109(usage, modifier),
+            Self::Us | Self::UsInternational => Self::usage_to_char_101(usage, modifier),
+        }
+    }
+
+    /// Returns the accent if the key is a dead key in this layout.
+    pub fn dead_key(&self, usage: Usage, modifier: Modifier) -> Option<char> {
+        match self {
+            Self::UsInternational => {
+                if modifier.has_ctrl() || modifier.has_alt() {
+                    return None;
+                }
+                match Self::usage_to_char_101(usage, modifier) {
+                    c @ ('\'' | '"' | '`' | '^' | '~') => Some(c),
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+
+    /// Composes the accent of the dead key and the following character.
+    ///
+    /// A space produces the accent itself.
+    pub fn compose(accent: char, base: char) -> Option<char> {
+        if base == ' ' {
+            return Some(accent);
+        }
+        DEAD_KEY_COMPOSITION
+            .iter()
+            .find(|(a, b, _)| *a == accent && *b == base)
+            .map(|(_, _, c)| *c)
+    }
+
+    fn usage_to_char_101(usage: Usage, modifier: Modifier) -> char {
+        let mut uni: char = INVALID_UNICHAR;
+
+        if usage >= Usage::ALPHABET_MIN && usage <= Usage::ALPHABET_MAX {
+            uni = (usage.0 - Usage::KEY_A.0 + 0x61) as char;
+            if modifier.has_shift() {
+                uni = (uni as u8 ^ 0x20) as char;
+            }
+        } else if usage >= Usage::NUMBER_MIN && usage <= Usage::NON_ALPHABET_MAX {
+            if modifier.has_shift() {
+                uni = USAGE_TO_CHAR_NON_ALPLABET_101_S[(usage.0 - Usage::NUMBER_MIN.0) as usize];
+            } else {
+                uni = USAGE_TO_CHAR_NON_ALPLABET_101[(usage.0 - Usage::NUMBER_MIN.0) as usize];
+            }
+        } else if usage == Usage::DELETE {
+            uni = '\x7F';
+        } else if usage >= Usage::NUMPAD_MIN && usage <= Usage::NUMPAD_MAX {
+            uni = USAGE_TO_CHAR_NUMPAD[(usage.0 - Usage::NUMPAD_MIN.0) as usize];
+        }
+
+        if ('\x40'..'\x7F').contains(&uni) && modifier.has_ctrl() {
+            uni = (uni as u8 & 0x1F) as char;
+        }
+
+        uni
+    }
+
+    fn usage_to_char_109(usage: Usage, modifier: Modifier) -> char {
+        let mut uni: char = INVALID_UNICHAR;
+
+        if usage >= Usage::ALPHABET_MIN && usage <= Usage::ALPHABET_MAX {
+            uni = (usage.0 - Usage::KEY_A.0 + 0x61) as char;
+        } else if usage >= Usage::NUMBER_MIN && usage <= Usage::NON_ALPHABET_MAX {
+            uni = USAGE_TO_CHAR_NON_ALPLABET_109[(usage.0 - Usage::NUMBER_MIN.0) as usize];
+            if uni > ' ' && uni < '\x40' && uni != '0' && modifier.has_shift() {
+                uni = (uni as u8 ^ 0x10) as char;
+            }
+        } else if usage == Usage::DELETE {
+            uni = '\x7F';
+        } else if usage >= Usage::NUMPAD_MIN && usage <= Usage::NUMPAD_MAX {
+            uni = USAGE_TO_CHAR_NUMPAD[(usage.0 - Usage::NUMPAD_MIN.0) as usize];
+        } else if usage == Usage::INTERNATIONAL_3 {
+            // '\|'
+            uni = '\\';
+        }
+
+        if ('\x40'..'\x7F').contains(&uni) {
+            if modifier.has_ctrl() {
+                uni = (uni as u8 & 0x1F) as char;
+            } else if modifier.has_shift() {
+                uni = (uni as u8 ^ 0x20) as char;
+            }
+        }
+
+        if usage == Usage::INTERNATIONAL_1 {
+            if modifier.has_shift() {
+                uni = '_';
+            } else {
+                uni = '\\';
+            }
+        }
+
+        uni
+    }
+}
+
+// Non Alphabet
+static USAGE_TO_CHAR_NON_ALPLABET_101: [char; 27] = [
+    '1', '2', '3', '4', '5', '6', '7', '8', '9', '0', '\x0D', '\x1B', '\x08', '\x09', ' ', '-',
+    '=', '[', ']', '\\', '\\', ';', '\'', '`', ',', '.', '/',
+];
+
+static USAGE_TO_CHAR_NON_ALPLABET_101_S: [char; 27] = [
+    '!', '@', '#', '$', '%', '^', '&', '*', '(', ')', '\x0D', '\x1B', '\x08', '\x09', ' ', '_',
+    '+', '{', '}', '|', '|', ':', '"', '~', '<', '>', '?',
+];
+
+static USAGE_TO_CHAR_NON_ALPLABET_109: [char; 27] = [
+    '1', '2', '3', '4', '5', '6', '7', '8', '9', '0', '\x0D', '\x1B', '\x08', '\x09', ' ', '-',
+    '^', '@', '[', ']', ']', ';', ':', '`', ',', '.', '/',
+];
+
+// Numpads
+static USAGE_TO_CHAR_NUMPAD: [char; 16] = [
+    '/', '*', '-', '+', '\x0D', '1', '2', '3', '4', '5', '6', '7', '8', '9', '0', '.',
+];
+
+// (accent, base, composed)
+static DEAD_KEY_COMPOSITION: [(char, char, char); 46] = [
+    ('\'', 'a', 'á'),
+    ('\'', 'e', 'é'),
+    ('\'', 'i', 'í'),
+    ('\'', 'o', 'ó'),
+    ('\'', 'u', 'ú'),
+    ('\'', 'y', 'ý'),
+    ('\'', 'c', 'ç'),
+    ('\'', 'A', 'Á'),
+    ('\'', 'E', 'É'),
+    ('\'', 'I', 'Í'),
+    ('\'', 'O', 'Ó'),
+    ('\'', 'U', 'Ú'),
+    ('\'', 'Y', 'Ý'),
+    ('\'', 'C', 'Ç'),
+    ('`', 'a', 'à'),
+    ('`', 'e', 'è'),
+    ('`', 'i', 'ì'),
+    ('`', 'o', 'ò'),
+    ('`', 'u', 'ù'),
+    ('`', 'A', 'À'),
+    ('`', 'E', 'È'),
+    ('`', 'I', 'Ì'),
+    ('`', 'O', 'Ò'),
+    ('`', 'U', 'Ù'),
+    ('^', 'a', 'â'),
+    ('^', 'e', 'ê'),
+    ('^', 'i', 'î'),
+    ('^', 'o', 'ô'),
+    ('^', 'u', 'û'),
+    ('^', 'A', 'Â'),
+    ('^', 'E', 'Ê'),
+    ('^', 'O', 'Ô'),
+    ('~', 'a', 'ã'),
+    ('~', 'o', 'õ'),
+    ('~', 'n', 'ñ'),
+    ('~', 'A', 'Ã'),
+    ('~', 'O', 'Õ'),
+    ('~', 'N', 'Ñ'),
+    ('"', 'a', 'ä'),
+    ('"', 'e', 'ë'),
+    ('"', 'i', 'ï'),
+    ('"', 'o', 'ö'),
+    ('"', 'u', 'ü'),
+    ('"', 'A', 'Ä'),
+    ('"', 'O', 'Ö'),
+    ('"', 'U', 'Ü'),
+];
+
+impl From<u8> for KeyLayout {
+    #[inline]
+    fn from(value: u8) -> Self {
+        Self::ALL
+            .into_iter()
+            .find(|v| *v as u8 == value)
+            .unwrap_or_default()
+    }
+}
+
+impl From<KeyLayout> for u8 {
+    #[inline]
+    fn from(value: KeyLayout) -> Self {
+        value as u8
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn layouts() {
+        // The key right of P is '[' on US and '@' on JIS
+        let usage = Usage(0x2F);
+        assert_eq!(KeyLayout::Us.usage_to_char(usage, Modifier::empty()), '[');
+        assert_eq!(KeyLayout::Jis.usage_to_char(usage, Modifier::empty()), '@');
+
+        let shift = Modifier::LEFT_SHIFT;
+        assert_eq!(KeyLayout::Us.usage_to_char(Usage::KEY_2, shift), '@');
+        assert_eq!(KeyLayout::Jis.usage_to_char(Usage::KEY_2, shift), '"');
+        assert_eq!(KeyLayout::Us.usage_to_char(Usage::KEY_A, shift), 'A');
+        assert_eq!(KeyLayout::Jis.usage_to_char(Usage::KEY_A, shift), 'A');
+
+        assert_eq!(KeyLayout::from_name("jis"), Some(KeyLayout::Jis));
+        assert_eq!(KeyLayout::from(KeyLayout::Us as u8), KeyLayout::Us);
+    }
+
+    #[test]
+    fn dead_keys() {
+        let apostrophe = Usage(0x34);
+        assert_eq!(KeyLayout::Us.dead_key(apostrophe, Modifier::empty()), None);
+        assert_eq!(
+            KeyLayout::UsInternational.dead_key(apostrophe, Modifier::empty()),
+            Some('\'')
+        );
+        assert_eq!(
+            KeyLayout::UsInternational.dead_key(apostrophe, Modifier::LEFT_SHIFT),
+            Some('"')
+        );
+        assert_eq!(KeyLayout::compose('\'', 'e'), Some('é'));
+        assert_eq!(KeyLayout::compose('"', 'u'), Some('ü'));
+        assert_eq!(KeyLayout::compose('~', ' '), Some('~'));
+        assert_eq!(KeyLayout::compose('~', 'x'), None);
+    }
+}
@@ -1,7 +1,11 @@
+pub mod ansi;
+pub mod chardev;
 pub mod hid;
+pub mod keylayout;
 
 mod error;
 use alloc::vec::Vec;
+use core::fmt;
 pub use error::*;
 
 pub type Result<T> = core::result::Result<T, Error>;
@@ -21,5 +25,104 @@ pub trait Write {
 
     fn flush(&mut self) -> Result<()>;
 
-    //fn write_all(&mut self, buf: &[u8]) -> Result<()>
+    fn write_all(&mut self, mut buf: &[u8]) -> Result<()> {
+        while !buf.is_empty() {
+            match self.write(buf)? {
+                0 => return Err(Error::with_context(ErrorKind::WriteZero, "write_all")),
+                n => buf = &buf[n..],
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes formatted arguments, so that `write!` can be used on any [Write].
+    fn write_fmt(&mut self, args: fmt::Arguments<'_>) -> Result<()> {
+        struct Adapter<'a, T: ?Sized> {
+            inner: &'a mut T,
+            error: Result<()>,
+        }
+
+        impl<T: Write + ?Sized> fmt::Write for Adapter<'_, T> {
+            fn write_str(&mut self, s: &str) -> fmt::Result {
+                self.inner.write_all(s.as_bytes()).map_err(|err| {
+                    self.error = Err(err);
+                    fmt::Error
+                })
+            }
+        }
+
+        let mut adapter = Adapter {
+            inner: self,
+            error: Ok(()),
+        };
+        match fmt::write(&mut adapter, args) {
+            Ok(_) => Ok(()),
+            Err(_) => match adapter.error {
+                Err(err) => Err(err),
+                Ok(_) => Err(Error::with_context(ErrorKind::Other, "formatter error")),
+            },
+        }
+    }
+}
+
+/// A writer that discards the data and only counts the number of bytes written
+///
+/// Useful for measuring the length of formatted output before allocating a buffer for it.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct CountingWriter {
+    count: usize,
+}
+
+impl CountingWriter {
+    #[inline]
+    pub const fn new() -> Self {
+        Self { count: 0 }
+    }
+
+    /// Returns the number of bytes written so far.
+    #[inline]
+    pub const fn count(&self) -> usize {
+        self.count
+    }
+}
+
+impl Write for CountingWriter {
+    #[inline]
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        self.count += buf.len();
+        Ok(buf.len())
+    }
+
+    #[inline]
+    fn flush(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
+impl fmt::Write for CountingWriter {
+    #[inline]
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.count += s.len();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::format;
+
+    #[test]
+    fn counting_writer() {
+        let name = "addr";
+        let expected = format!("{}:{:04x} {}", name, 0xbeefu32, -42);
+
+        let mut writer = CountingWriter::new();
+        write!(writer, "{}:{:04x} {}", name, 0xbeefu32, -42).unwrap();
+        assert_eq!(writer.count(), expected.len());
+
+        let mut writer = CountingWriter::new();
+        fmt::Write::write_fmt(&mut writer, format_args!("{}:{:04x}", name, 0xbeefu32)).unwrap();
+        assert_eq!(writer.count(), "addr:beef".len());
+    }
 }
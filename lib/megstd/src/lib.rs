@@ -7,21 +7,32 @@
 pub mod sys;
 
 pub use meggl as drawing;
+pub mod audio;
+pub mod backtrace;
 pub mod error;
+pub mod exec;
 pub mod fs;
+pub mod future;
 pub mod game;
 pub mod io;
 pub mod mem;
+pub mod net;
+pub mod offscreen;
 pub mod osstr;
+pub mod panic;
 pub mod path;
 pub mod rand;
+pub mod shell;
+pub mod stat;
 pub mod string;
 pub mod time;
+pub mod user;
 pub mod uuid;
 
 #[cfg(feature = "window")]
 #[allow(unused_imports)]
 pub mod window {
+    pub use crate::offscreen::{OffscreenContext, OffscreenDrawingContext};
     pub use crate::sys::window::*;
 }
 
@@ -1,3 +1,54 @@
 // mem
 
 pub mod dispose;
+
+use core::mem::{align_of, size_of};
+
+/// Reinterprets the head of the byte slice as a reference to `T`.
+///
+/// Returns `None` if the slice is shorter than `T` or is not aligned for `T`.
+///
+/// # Safety
+///
+/// `T` must be valid for any bit pattern, such as a `#[repr(C)]` struct of integers.
+#[inline]
+pub unsafe fn try_from_bytes<T>(bytes: &[u8]) -> Option<&T> {
+    let ptr = bytes.as_ptr();
+    (bytes.len() >= size_of::<T>() && (ptr as usize) & (align_of::<T>() - 1) == 0)
+        .then(|| &*(ptr as *const T))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[repr(C, align(8))]
+    struct AlignedBuffer([u8; 16]);
+
+    #[repr(C)]
+    #[derive(Debug, PartialEq)]
+    struct Pair {
+        a: u32,
+        b: u32,
+    }
+
+    #[test]
+    fn try_from_bytes_checked() {
+        let mut buffer = AlignedBuffer([0; 16]);
+        buffer.0[..4].copy_from_slice(&0x1234_5678u32.to_ne_bytes());
+        buffer.0[4..8].copy_from_slice(&0xdead_beefu32.to_ne_bytes());
+
+        let pair = unsafe { try_from_bytes::<Pair>(&buffer.0) }.unwrap();
+        assert_eq!(
+            pair,
+            &Pair {
+                a: 0x1234_5678,
+                b: 0xdead_beef
+            }
+        );
+
+        assert!(unsafe { try_from_bytes::<Pair>(&buffer.0[1..]) }.is_none());
+        assert!(unsafe { try_from_bytes::<Pair>(&buffer.0[..7]) }.is_none());
+        assert!(unsafe { try_from_bytes::<Pair>(&buffer.0[8..]) }.is_some());
+    }
+}
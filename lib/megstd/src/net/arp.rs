@@ -0,0 +1,39 @@
+//! Address Resolution Protocol
+
+use super::*;
+use alloc::vec::Vec;
+
+const ARP_PACKET_SIZE: usize = 28;
+const ARP_HTYPE_ETHERNET: u16 = 1;
+const ARP_OPER_REQUEST: u16 = 1;
+const ARP_OPER_REPLY: u16 = 2;
+
+/// Returns the reply frame if the frame is an ARP request for the address.
+pub fn reply_arp(frame: &[u8], mac: MacAddress, ip: Ipv4Address) -> Option<Vec<u8>> {
+    let packet = frame.get(ETHER_HEADER_SIZE..ETHER_HEADER_SIZE + ARP_PACKET_SIZE)?;
+    let word = |offset: usize| u16::from_be_bytes([packet[offset], packet[offset + 1]]);
+    if word(0) != ARP_HTYPE_ETHERNET
+        || word(2) != EtherType::IPV4.0
+        || packet[4] != 6
+        || packet[5] != 4
+        || word(6) != ARP_OPER_REQUEST
+        || Ipv4Address::from_slice(&packet[24..28])? != ip
+    {
+        return None;
+    }
+    let sender_mac = &packet[8..14];
+    let sender_ip = &packet[14..18];
+
+    let mut reply = Vec::with_capacity(ETHER_HEADER_SIZE + ARP_PACKET_SIZE);
+    reply.extend_from_slice(sender_mac);
+    reply.extend_from_slice(mac.as_bytes());
+    reply.extend_from_slice(&EtherType::ARP.0.to_be_bytes());
+    reply.extend_from_slice(&packet[0..6]);
+    reply.extend_from_slice(&ARP_OPER_REPLY.to_be_bytes());
+    reply.extend_from_slice(mac.as_bytes());
+    reply.extend_from_slice(ip.as_bytes());
+    reply.extend_from_slice(sender_mac);
+    reply.extend_from_slice(sender_ip);
+
+    Some(reply)
+}
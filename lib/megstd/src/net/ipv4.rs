@@ -0,0 +1,117 @@
+//! Internet Protocol version 4 and ICMP echo
+
+use super::*;
+use alloc::vec::Vec;
+use core::str::FromStr;
+
+#[repr(transparent)]
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub struct Ipv4Address(pub [u8; 4]);
+
+impl Ipv4Address {
+    pub const UNSPECIFIED: Self = Self([0; 4]);
+    pub const BROADCAST: Self = Self([0xFF; 4]);
+
+    #[inline]
+    pub const fn as_bytes(&self) -> &[u8; 4] {
+        &self.0
+    }
+
+    #[inline]
+    pub fn from_slice(slice: &[u8]) -> Option<Self> {
+        slice.try_into().ok().map(Self)
+    }
+}
+
+impl FromStr for Ipv4Address {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut result = [0u8; 4];
+        let mut iter = s.split('.');
+        for octet in result.iter_mut() {
+            *octet = iter.next().ok_or(())?.parse().map_err(|_| ())?;
+        }
+        if iter.next().is_some() {
+            return Err(());
+        }
+        Ok(Self(result))
+    }
+}
+
+impl fmt::Debug for Ipv4Address {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let v = &self.0;
+        write!(f, "{}.{}.{}.{}", v[0], v[1], v[2], v[3])
+    }
+}
+
+impl fmt::Display for Ipv4Address {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self, f)
+    }
+}
+
+/// Computes the internet checksum (RFC 1071).
+pub fn checksum(data: &[u8]) -> u16 {
+    let mut sum = data
+        .chunks(2)
+        .map(|v| u16::from_be_bytes([v[0], v.get(1).copied().unwrap_or(0)]) as u32)
+        .sum::<u32>();
+    while (sum >> 16) != 0 {
+        sum = (sum & 0xFFFF) + (sum >> 16);
+    }
+    !(sum as u16)
+}
+
+const IP_PROTO_ICMP: u8 = 1;
+const ICMP_ECHO_REPLY: u8 = 0;
+const ICMP_ECHO_REQUEST: u8 = 8;
+const DEFAULT_TTL: u8 = 64;
+
+/// Returns the echo reply frame if the frame is an ICMP echo request to the address.
+pub fn reply_icmp_echo(frame: &[u8], mac: MacAddress, ip: Ipv4Address) -> Option<Vec<u8>> {
+    let packet = frame.get(ETHER_HEADER_SIZE..)?;
+    let version_ihl = *packet.first()?;
+    let header_len = (version_ihl & 0x0F) as usize * 4;
+    let total_len = u16::from_be_bytes([*packet.get(2)?, *packet.get(3)?]) as usize;
+    if (version_ihl >> 4) != 4
+        || header_len < 20
+        || total_len < header_len + 8
+        || total_len > packet.len()
+        || packet[9] != IP_PROTO_ICMP
+        || Ipv4Address::from_slice(&packet[16..20])? != ip
+        || checksum(&packet[..header_len]) != 0
+    {
+        return None;
+    }
+    // Fragmented requests are not supported
+    if (u16::from_be_bytes([packet[6], packet[7]]) & 0x3FFF) != 0 {
+        return None;
+    }
+    let icmp = &packet[header_len..total_len];
+    if icmp[0] != ICMP_ECHO_REQUEST || icmp[1] != 0 || checksum(icmp) != 0 {
+        return None;
+    }
+
+    let mut reply = frame[..ETHER_HEADER_SIZE + total_len].to_vec();
+    reply[0..6].copy_from_slice(&frame[6..12]);
+    reply[6..12].copy_from_slice(mac.as_bytes());
+
+    let packet = &mut reply[ETHER_HEADER_SIZE..];
+    packet.copy_within(12..16, 16);
+    packet[12..16].copy_from_slice(ip.as_bytes());
+    packet[8] = DEFAULT_TTL;
+    packet[10..12].fill(0);
+    let sum = checksum(&packet[..header_len]);
+    packet[10..12].copy_from_slice(&sum.to_be_bytes());
+
+    let icmp = &mut packet[header_len..];
+    icmp[0] = ICMP_ECHO_REPLY;
+    icmp[2..4].fill(0);
+    let sum = checksum(icmp);
+    icmp[2..4].copy_from_slice(&sum.to_be_bytes());
+
+    Some(reply)
+}
@@ -0,0 +1,214 @@
+//! Ethernet frames
+
+mod arp;
+mod ipv4;
+pub use arp::*;
+pub use ipv4::*;
+
+use core::fmt;
+
+/// Maximum length of the ethernet frame without the FCS
+pub const MAX_FRAME_SIZE: usize = 1514;
+/// Minimum length of the ethernet frame without the FCS
+pub const MIN_FRAME_SIZE: usize = 60;
+/// Size of the ethernet header without the VLAN tag
+pub const ETHER_HEADER_SIZE: usize = 14;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NetError {
+    /// The transmit queue is full
+    Busy,
+    /// The frame is too short or too long
+    InvalidLength,
+    /// There is no such device
+    NoDevice,
+}
+
+#[repr(transparent)]
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub struct MacAddress(pub [u8; 6]);
+
+impl MacAddress {
+    pub const BROADCAST: Self = Self([0xFF; 6]);
+
+    #[inline]
+    pub const fn as_bytes(&self) -> &[u8; 6] {
+        &self.0
+    }
+}
+
+impl fmt::Debug for MacAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let v = &self.0;
+        write!(
+            f,
+            "{:02x}:{:02x}:{:02x}:{:02x}:{:02x}:{:02x}",
+            v[0], v[1], v[2], v[3], v[4], v[5]
+        )
+    }
+}
+
+impl fmt::Display for MacAddress {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self, f)
+    }
+}
+
+#[repr(transparent)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct EtherType(pub u16);
+
+impl EtherType {
+    pub const IPV4: Self = Self(0x0800);
+    pub const ARP: Self = Self(0x0806);
+
+    #[inline]
+    pub fn from_frame(frame: &[u8]) -> Option<Self> {
+        frame
+            .get(12..14)
+            .map(|v| Self(u16::from_be_bytes([v[0], v[1]])))
+    }
+}
+
+/// Copies the received frame into the buffer and returns its length.
+///
+/// Frames that do not fit in the buffer are rejected instead of being truncated.
+#[inline]
+pub fn copy_frame(frame: &[u8], buffer: &mut [u8]) -> Result<usize, NetError> {
+    let len = frame.len();
+    buffer
+        .get_mut(..len)
+        .ok_or(NetError::InvalidLength)?
+        .copy_from_slice(frame);
+    Ok(len)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::{string::ToString, vec, vec::Vec};
+
+    #[test]
+    fn mac_address() {
+        let mac = MacAddress([0x52, 0x54, 0x00, 0x12, 0x34, 0xAB]);
+        assert_eq!(mac.to_string(), "52:54:00:12:34:ab");
+        assert_eq!(MacAddress::BROADCAST.to_string(), "ff:ff:ff:ff:ff:ff");
+    }
+
+    #[test]
+    fn ether_type() {
+        let mut frame = [0u8; ETHER_HEADER_SIZE];
+        frame[12..14].copy_from_slice(&[0x08, 0x06]);
+        assert_eq!(EtherType::from_frame(&frame), Some(EtherType::ARP));
+        assert_eq!(EtherType::from_frame(&frame[..13]), None);
+    }
+
+    #[test]
+    fn copy_frame_rejects_short_buffer() {
+        let frame = [0xAA; MAX_FRAME_SIZE];
+        let mut buffer = [0u8; MAX_FRAME_SIZE];
+        assert_eq!(copy_frame(&frame, &mut buffer), Ok(MAX_FRAME_SIZE));
+        assert_eq!(buffer, frame);
+
+        let frame = [0x55; MIN_FRAME_SIZE];
+        assert_eq!(copy_frame(&frame, &mut buffer), Ok(MIN_FRAME_SIZE));
+        assert_eq!(&buffer[..MIN_FRAME_SIZE], &frame);
+
+        let mut short = [0u8; MIN_FRAME_SIZE - 1];
+        assert_eq!(copy_frame(&frame, &mut short), Err(NetError::InvalidLength));
+        assert_eq!(short, [0u8; MIN_FRAME_SIZE - 1]);
+    }
+
+    const HOST_MAC: MacAddress = MacAddress([0x52, 0x54, 0x00, 0x12, 0x34, 0x56]);
+    const HOST_IP: Ipv4Address = Ipv4Address([10, 0, 2, 15]);
+    const PEER_MAC: MacAddress = MacAddress([0x52, 0x55, 0x0A, 0x00, 0x02, 0x02]);
+    const PEER_IP: Ipv4Address = Ipv4Address([10, 0, 2, 2]);
+
+    #[test]
+    fn ipv4_address() {
+        assert_eq!("10.0.2.15".parse(), Ok(HOST_IP));
+        assert_eq!(HOST_IP.to_string(), "10.0.2.15");
+        assert!("10.0.2".parse::<Ipv4Address>().is_err());
+        assert!("10.0.2.15.1".parse::<Ipv4Address>().is_err());
+        assert!("10.0.2.256".parse::<Ipv4Address>().is_err());
+    }
+
+    #[test]
+    fn arp_reply() {
+        let mut frame = Vec::new();
+        frame.extend_from_slice(MacAddress::BROADCAST.as_bytes());
+        frame.extend_from_slice(PEER_MAC.as_bytes());
+        frame.extend_from_slice(&EtherType::ARP.0.to_be_bytes());
+        frame.extend_from_slice(&[0x00, 0x01, 0x08, 0x00, 6, 4, 0x00, 0x01]);
+        frame.extend_from_slice(PEER_MAC.as_bytes());
+        frame.extend_from_slice(PEER_IP.as_bytes());
+        frame.extend_from_slice(&[0; 6]);
+        frame.extend_from_slice(HOST_IP.as_bytes());
+        frame.resize(MIN_FRAME_SIZE, 0);
+
+        let reply = reply_arp(&frame, HOST_MAC, HOST_IP).unwrap();
+        assert_eq!(&reply[0..6], PEER_MAC.as_bytes());
+        assert_eq!(&reply[6..12], HOST_MAC.as_bytes());
+        assert_eq!(EtherType::from_frame(&reply), Some(EtherType::ARP));
+        assert_eq!(&reply[20..22], &[0x00, 0x02]);
+        assert_eq!(&reply[22..28], HOST_MAC.as_bytes());
+        assert_eq!(&reply[28..32], HOST_IP.as_bytes());
+        assert_eq!(&reply[32..38], PEER_MAC.as_bytes());
+        assert_eq!(&reply[38..42], PEER_IP.as_bytes());
+
+        // requests for other hosts are not answered
+        assert!(reply_arp(&frame, HOST_MAC, PEER_IP).is_none());
+    }
+
+    #[test]
+    fn icmp_echo_reply() {
+        let payload = b"abcdefgh";
+        let mut icmp = vec![8, 0, 0, 0, 0x12, 0x34, 0x00, 0x01];
+        icmp.extend_from_slice(payload);
+        let sum = checksum(&icmp);
+        icmp[2..4].copy_from_slice(&sum.to_be_bytes());
+
+        let total_len = 20 + icmp.len() as u16;
+        let mut ip = vec![0x45, 0x00];
+        ip.extend_from_slice(&total_len.to_be_bytes());
+        ip.extend_from_slice(&[0xAB, 0xCD, 0x40, 0x00, 0x40, 0x01, 0x00, 0x00]);
+        ip.extend_from_slice(PEER_IP.as_bytes());
+        ip.extend_from_slice(HOST_IP.as_bytes());
+        let sum = checksum(&ip);
+        ip[10..12].copy_from_slice(&sum.to_be_bytes());
+
+        let mut frame = Vec::new();
+        frame.extend_from_slice(HOST_MAC.as_bytes());
+        frame.extend_from_slice(PEER_MAC.as_bytes());
+        frame.extend_from_slice(&EtherType::IPV4.0.to_be_bytes());
+        frame.extend_from_slice(&ip);
+        frame.extend_from_slice(&icmp);
+        frame.resize(MIN_FRAME_SIZE, 0);
+
+        let reply = reply_icmp_echo(&frame, HOST_MAC, HOST_IP).unwrap();
+        assert_eq!(reply.len(), ETHER_HEADER_SIZE + total_len as usize);
+        // the addresses are swapped
+        assert_eq!(&reply[0..6], PEER_MAC.as_bytes());
+        assert_eq!(&reply[6..12], HOST_MAC.as_bytes());
+        assert_eq!(EtherType::from_frame(&reply), Some(EtherType::IPV4));
+        let packet = &reply[ETHER_HEADER_SIZE..];
+        assert_eq!(&packet[12..16], HOST_IP.as_bytes());
+        assert_eq!(&packet[16..20], PEER_IP.as_bytes());
+        assert_eq!(checksum(&packet[..20]), 0);
+        // the type is flipped to the echo reply, the rest is echoed back
+        let icmp_reply = &packet[20..];
+        assert_eq!(icmp_reply[0], 0);
+        assert_eq!(&icmp_reply[4..8], &icmp[4..8]);
+        assert_eq!(&icmp_reply[8..], payload);
+        assert_eq!(checksum(icmp_reply), 0);
+
+        // requests to other hosts are not answered
+        assert!(reply_icmp_echo(&frame, HOST_MAC, PEER_IP).is_none());
+        // nor are the broken ones
+        let mut broken = frame.clone();
+        broken[ETHER_HEADER_SIZE + 20 + 8] ^= 0xFF;
+        assert!(reply_icmp_echo(&broken, HOST_MAC, HOST_IP).is_none());
+        assert!(reply_icmp_echo(&frame[..ETHER_HEADER_SIZE + 30], HOST_MAC, HOST_IP).is_none());
+    }
+}
@@ -0,0 +1,369 @@
+//! Off-screen rendering without a visible window
+
+use crate::drawing::{font::*, *};
+
+/// Context to render into a bitmap without a visible window
+///
+/// It is created along with the bitmap, and [OffscreenContext::draw] passes the surface
+/// with the same drawing methods as the drawing context of the window.
+/// The results are never composited to the screen, e.g. to save them with [encode_bmp].
+pub struct OffscreenContext {
+    size: Size,
+}
+
+impl OffscreenContext {
+    /// Creates a context and the transparent bitmap of the size to render into.
+    #[inline]
+    pub fn new(size: Size) -> (Self, OwnedBitmap32) {
+        (
+            Self { size },
+            OwnedBitmap32::new(size, TrueColor::TRANSPARENT),
+        )
+    }
+
+    #[inline]
+    pub fn size(&self) -> Size {
+        self.size
+    }
+
+    /// Draws into the bitmap, like `Window::draw` draws into the window.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the bitmap is not the size of this context.
+    #[inline]
+    pub fn draw<F, R>(&self, bitmap: &mut OwnedBitmap32, f: F) -> R
+    where
+        F: FnOnce(&mut OffscreenDrawingContext) -> R,
+    {
+        assert_eq!(bitmap.size(), self.size);
+        let mut context = OffscreenDrawingContext { bitmap };
+        f(&mut context)
+    }
+}
+
+/// Drawing surface of [OffscreenContext] with the same methods as `DrawingContext`
+pub struct OffscreenDrawingContext<'a> {
+    bitmap: &'a mut OwnedBitmap32,
+}
+
+impl OffscreenDrawingContext<'_> {
+    /// Same as the system font
+    const FONT: FixedFontDriver<'static> = FixedFontDriver::new(
+        FONT_MEGH0816_WIDTH,
+        FONT_MEGH0816_HEIGHT,
+        &FONT_MEGH0816_DATA,
+    );
+
+    #[inline]
+    fn true_color(color: PackedColor) -> TrueColor {
+        color.as_color().into_true_color()
+    }
+
+    /// Draws the string with the system font wrapped at the right edge of the bitmap, and
+    /// returns the end of the last line, so that the next string can be drawn from there.
+    pub fn draw_string(&mut self, s: &str, origin: Point, color: PackedColor) -> Point {
+        let bitmap = self.bitmap.as_mut();
+        let color = Self::true_color(color);
+        let size = Size::new(
+            bitmap.size().width() - origin.x,
+            bitmap.size().height() - origin.y,
+        );
+        let lines = line_statuses(
+            s,
+            size,
+            0,
+            LineBreakMode::default(),
+            Self::FONT.line_height(),
+            |_| Self::FONT.width(),
+            |_, _| 0,
+        );
+        let mut end = origin;
+        let mut y = origin.y;
+        for line in lines {
+            let mut x = origin.x;
+            for character in s
+                .chars()
+                .skip(line.start_position)
+                .take(line.end_position - line.start_position)
+            {
+                Self::FONT.draw_glyph(character, Point::new(x, y), |glyph, size, origin| {
+                    bitmap.draw_glyph(glyph, size, origin, color)
+                });
+                x += Self::FONT.width();
+            }
+            end = Point::new(origin.x + line.width, y);
+            y += line.height;
+        }
+        end
+    }
+
+    #[inline]
+    pub fn draw_line(&mut self, c1: Point, c2: Point, color: PackedColor) {
+        self.bitmap
+            .as_mut()
+            .draw_line(c1, c2, Self::true_color(color));
+    }
+
+    #[inline]
+    pub fn fill_rect(&mut self, rect: Rect, color: PackedColor) {
+        self.bitmap
+            .as_mut()
+            .fill_rect(rect, Self::true_color(color));
+    }
+
+    pub fn draw_shape(
+        &mut self,
+        rect: Rect,
+        radius: isize,
+        bg_color: PackedColor,
+        border_color: PackedColor,
+    ) {
+        let bitmap = self.bitmap.as_mut();
+        if bg_color != PackedColor::TRANSPARENT {
+            bitmap.fill_round_rect(rect, radius, Self::true_color(bg_color));
+        }
+        if border_color != PackedColor::TRANSPARENT {
+            bitmap.draw_round_rect(rect, radius, Self::true_color(border_color));
+        }
+    }
+
+    /// Draws the set pixels of the monochrome bitmap with the color,
+    /// magnified by `mode` times.
+    pub fn blt1<'a, T: AsRef<BitmapRef1<'a>>>(
+        &mut self,
+        bitmap: &T,
+        origin: Point,
+        color: PackedColor,
+        mode: usize,
+    ) {
+        let src = bitmap.as_ref();
+        let dest = self.bitmap.as_mut();
+        let color = Self::true_color(color);
+        let scale = mode.max(1) as isize;
+        for y in 0..src.height() as isize {
+            for x in 0..src.width() as isize {
+                if src.get_pixel(Point::new(x, y)) == Some(Monochrome::One) {
+                    let point = Point::new(origin.x + x * scale, origin.y + y * scale);
+                    dest.fill_rect(Rect::from((point, Size::new(scale, scale))), color);
+                }
+            }
+        }
+    }
+
+    #[inline]
+    pub fn blt8<'a, T: AsRef<BitmapRef8<'a>>>(&mut self, bitmap: &T, origin: Point) {
+        let src = bitmap.as_ref();
+        self.bitmap
+            .as_mut()
+            .blt_convert(src, origin, src.bounds(), |c| c.as_true_color());
+    }
+
+    #[inline]
+    pub fn blt32<'a, T: AsRef<BitmapRef32<'a>>>(&mut self, bitmap: &T, origin: Point) {
+        let src = bitmap.as_ref();
+        self.bitmap.as_mut().blt(src, origin, src.bounds());
+    }
+}
+
+/// Back buffer of a window for double buffering
+///
+/// The buffer is allocated on the first [BackBuffer::get] and again whenever the requested
+/// size changes, in which case the contents have to be drawn again.
+/// The OS window surface cannot be exchanged, so [BackBuffer::present] copies the buffer
+/// instead of swapping it; the buffer keeps its contents for the next frame.
+#[derive(Default)]
+pub struct BackBuffer {
+    bitmap: Option<OwnedBitmap32>,
+}
+
+impl BackBuffer {
+    #[inline]
+    pub const fn new() -> Self {
+        Self { bitmap: None }
+    }
+
+    /// Returns the buffer of the size, allocating it if needed.
+    pub fn get(&mut self, size: Size) -> BitmapRefMut32<'_> {
+        if self
+            .bitmap
+            .as_ref()
+            .is_some_and(|bitmap| bitmap.size() != size)
+        {
+            self.bitmap = None;
+        }
+        let bitmap: &mut BitmapRefMut32 = self
+            .bitmap
+            .get_or_insert_with(|| OwnedBitmap32::new(size, TrueColor::TRANSPARENT))
+            .as_mut();
+        bitmap.clone_mut()
+    }
+
+    /// Passes the buffer to `blt` to copy it to the visible surface.
+    ///
+    /// Does nothing if the buffer has not been allocated yet.
+    #[inline]
+    pub fn present<F>(&self, blt: F)
+    where
+        F: FnOnce(&BitmapRef32),
+    {
+        if let Some(bitmap) = self.bitmap.as_ref() {
+            blt(bitmap.as_ref());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec::Vec;
+
+    #[test]
+    fn offscreen() {
+        let (context, mut bitmap) = OffscreenContext::new(Size::new(8, 8));
+        context.draw(&mut bitmap, |ctx| {
+            ctx.fill_rect(Rect::new(2, 2, 4, 4), PackedColor::WHITE);
+        });
+
+        assert_eq!(
+            bitmap.get_pixel(Point::new(0, 0)),
+            Some(TrueColor::TRANSPARENT)
+        );
+        assert_eq!(bitmap.get_pixel(Point::new(2, 2)), Some(TrueColor::WHITE));
+        assert_eq!(bitmap.get_pixel(Point::new(5, 5)), Some(TrueColor::WHITE));
+        assert_eq!(
+            bitmap.get_pixel(Point::new(6, 6)),
+            Some(TrueColor::TRANSPARENT)
+        );
+
+        let bmp = encode_bmp(bitmap.as_ref());
+        assert_eq!(
+            decode_bmp(&bmp).unwrap().get_pixel(Point::new(3, 3)),
+            Some(TrueColor::WHITE)
+        );
+    }
+
+    #[test]
+    fn offscreen_blt1() {
+        // 0b1000_0001 on the first row, 0b0100_0000 on the second row
+        let bits = [0x81, 0x40];
+        let src = BitmapRef1::from_bytes(&bits, Size::new(8, 2));
+        let (context, mut bitmap) = OffscreenContext::new(Size::new(16, 4));
+        context.draw(&mut bitmap, |ctx| {
+            ctx.blt1(&src, Point::new(0, 0), PackedColor::WHITE, 2);
+        });
+
+        let white = Some(TrueColor::WHITE);
+        let transparent = Some(TrueColor::TRANSPARENT);
+        assert_eq!(bitmap.get_pixel(Point::new(0, 0)), white);
+        assert_eq!(bitmap.get_pixel(Point::new(1, 1)), white);
+        assert_eq!(bitmap.get_pixel(Point::new(2, 0)), transparent);
+        assert_eq!(bitmap.get_pixel(Point::new(14, 0)), white);
+        assert_eq!(bitmap.get_pixel(Point::new(15, 1)), white);
+        assert_eq!(bitmap.get_pixel(Point::new(2, 2)), white);
+        assert_eq!(bitmap.get_pixel(Point::new(3, 3)), white);
+        assert_eq!(bitmap.get_pixel(Point::new(0, 2)), transparent);
+    }
+
+    #[test]
+    fn offscreen_draw_string() {
+        let (context, mut bitmap) = OffscreenContext::new(Size::new(32, 20));
+        let next = context.draw(&mut bitmap, |ctx| {
+            ctx.draw_string("A A", Point::new(4, 0), PackedColor::WHITE)
+        });
+        assert_eq!(next, Point::new(4 + 8 * 3, 0));
+
+        let has_pixels = |x: isize| {
+            (0..20).any(|y| {
+                (x..x + 8).any(|x| bitmap.get_pixel(Point::new(x, y)) == Some(TrueColor::WHITE))
+            })
+        };
+        assert!(has_pixels(4));
+        // the space has no glyph
+        assert!(!has_pixels(12));
+        assert!(has_pixels(20));
+
+        // the end follows the line wrapped at the right edge
+        let (context, mut bitmap) = OffscreenContext::new(Size::new(32, 48));
+        let next = context.draw(&mut bitmap, |ctx| {
+            ctx.draw_string("AAAA", Point::new(4, 0), PackedColor::WHITE)
+        });
+        assert_eq!(next, Point::new(4 + 8, 20));
+        assert!((20..40).any(|y| bitmap.get_pixel(Point::new(6, y)) == Some(TrueColor::WHITE)));
+
+        // a character missing from the font advances by a single cell
+        let (context, mut bitmap) = OffscreenContext::new(Size::new(32, 20));
+        let next = context.draw(&mut bitmap, |ctx| {
+            ctx.draw_string("é", Point::new(4, 0), PackedColor::WHITE)
+        });
+        assert_eq!(next, Point::new(4 + 8, 0));
+        assert_eq!(bitmap.get_pixel(Point::new(5, 3)), Some(TrueColor::WHITE));
+    }
+
+    #[test]
+    fn chained_draw_string() {
+        // The full blocks fill the whole cells, so the columns of the strings are exact
+        let first = TrueColor::WHITE;
+        let second = TrueColor::from_rgb(0xFF0000);
+        let (context, mut bitmap) = OffscreenContext::new(Size::new(48, 20));
+        let next = context.draw(&mut bitmap, |ctx| {
+            let next = ctx.draw_string("\u{2588}\u{2588}", Point::new(4, 0), first.into());
+            ctx.draw_string("\u{2588}", next, second.into())
+        });
+        assert_eq!(next, Point::new(4 + 8 * 3, 0));
+
+        let columns_of = |color| {
+            (0..48)
+                .filter(|&x| (0..20).any(|y| bitmap.get_pixel(Point::new(x, y)) == Some(color)))
+                .collect::<Vec<_>>()
+        };
+        let first = columns_of(first);
+        let second = columns_of(second);
+        assert_eq!(first, (4..20).collect::<Vec<_>>());
+        assert_eq!(second.first(), Some(&(first.last().unwrap() + 1)));
+        assert_eq!(second.last(), Some(&27));
+    }
+
+    #[test]
+    fn back_buffer() {
+        // The visible surface of a mock window
+        let mut window = OwnedBitmap32::new(Size::new(4, 4), TrueColor::TRANSPARENT);
+        let mut buffer = BackBuffer::new();
+        let present = |buffer: &BackBuffer, window: &mut OwnedBitmap32| {
+            buffer.present(|src| window.as_mut().blt(src, Point::default(), src.bounds()));
+        };
+
+        present(&buffer, &mut window);
+        assert_eq!(
+            window.get_pixel(Point::new(1, 1)),
+            Some(TrueColor::TRANSPARENT)
+        );
+
+        buffer
+            .get(window.size())
+            .fill_rect(Rect::new(0, 0, 2, 2), TrueColor::WHITE);
+        assert_eq!(
+            window.get_pixel(Point::new(1, 1)),
+            Some(TrueColor::TRANSPARENT)
+        );
+
+        present(&buffer, &mut window);
+        assert_eq!(window.get_pixel(Point::new(1, 1)), Some(TrueColor::WHITE));
+        assert_eq!(
+            window.get_pixel(Point::new(2, 2)),
+            Some(TrueColor::TRANSPARENT)
+        );
+
+        // presenting copies, so the back buffer keeps the previous frame
+        let back = buffer.get(window.size());
+        assert_eq!(back.get_pixel(Point::new(1, 1)), Some(TrueColor::WHITE));
+
+        // a new size drops the contents
+        let back = buffer.get(Size::new(8, 8));
+        assert_eq!(back.size(), Size::new(8, 8));
+        assert_eq!(
+            back.get_pixel(Point::new(1, 1)),
+            Some(TrueColor::TRANSPARENT)
+        );
+    }
+}
@@ -0,0 +1,104 @@
+//! What the kernel does after reporting a panic
+
+use core::time::Duration;
+
+/// What the panic handler does after reporting the panic
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PanicAction {
+    /// Stops the processor, so that the report stays on the screen.
+    Halt,
+    /// Resets the system after the delay, so that unattended boots do not hang.
+    Reboot(Duration),
+}
+
+impl PanicAction {
+    /// Returns [PanicAction::Reboot] with the delay, or [PanicAction::Halt] if the delay is zero.
+    #[inline]
+    pub const fn reboot_after(delay: Duration) -> Self {
+        if delay.is_zero() {
+            Self::Halt
+        } else {
+            Self::Reboot(delay)
+        }
+    }
+
+    /// Waits for the delay and calls `reset` if the action is [PanicAction::Reboot].
+    ///
+    /// `clock` returns the elapsed time of a monotonic clock, or `None` if no clock is available yet,
+    /// in which case the system is reset without waiting. Interrupts are disabled in the panic handler,
+    /// so the clock is polled, calling `relax` in between.
+    pub fn reset_after_delay<C, R, F>(self, mut clock: C, mut relax: R, reset: F)
+    where
+        C: FnMut() -> Option<Duration>,
+        R: FnMut(),
+        F: FnOnce(),
+    {
+        let Self::Reboot(delay) = self else { return };
+        if let Some(start) = clock() {
+            let deadline = start.saturating_add(delay);
+            while clock().is_some_and(|now| now < deadline) {
+                relax();
+            }
+        }
+        reset();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::cell::Cell;
+
+    #[test]
+    fn reboot_after() {
+        assert_eq!(PanicAction::reboot_after(Duration::ZERO), PanicAction::Halt);
+        assert_eq!(
+            PanicAction::reboot_after(Duration::from_secs(5)),
+            PanicAction::Reboot(Duration::from_secs(5))
+        );
+    }
+
+    #[test]
+    fn reset_after_delay() {
+        // A clock that advances 300ms per read
+        let now = Cell::new(Duration::from_secs(100));
+        let clock = || {
+            let result = now.get();
+            now.set(result + Duration::from_millis(300));
+            Some(result)
+        };
+        let reset_at = Cell::new(None);
+        let n_relaxed = Cell::new(0);
+        PanicAction::Reboot(Duration::from_secs(2)).reset_after_delay(
+            clock,
+            || n_relaxed.set(n_relaxed.get() + 1),
+            || reset_at.set(Some(now.get())),
+        );
+        // the read at 102.1s is the first one past the deadline, and the next read would be 102.4s
+        assert_eq!(reset_at.get(), Some(Duration::from_millis(102_400)));
+        assert_eq!(n_relaxed.get(), 6);
+
+        // Without a clock, it resets right away
+        let is_reset = Cell::new(false);
+        PanicAction::Reboot(Duration::from_secs(2)).reset_after_delay(
+            || None,
+            || unreachable!(),
+            || is_reset.set(true),
+        );
+        assert!(is_reset.get());
+    }
+
+    #[test]
+    fn halt() {
+        let n_reads = Cell::new(0);
+        PanicAction::Halt.reset_after_delay(
+            || {
+                n_reads.set(n_reads.get() + 1);
+                Some(Duration::ZERO)
+            },
+            || (),
+            || unreachable!(),
+        );
+        assert_eq!(n_reads.get(), 0);
+    }
+}
@@ -1,11 +1,45 @@
 // Random Number Generator
 
-use core::num::{NonZeroU32, NonZeroU64};
+use core::{
+    mem::size_of,
+    num::{NonZeroU32, NonZeroU64},
+    sync::atomic::{AtomicU64, AtomicUsize, Ordering},
+};
 
 /// Random Number Generator
 pub trait Rng {
     type Output;
     fn rand(&mut self) -> Result<Self::Output, ()>;
+
+    /// Fills the buffer with random bytes, including a trailing partial word.
+    fn fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), ()>
+    where
+        Self::Output: Into<u64>,
+    {
+        for chunk in dest.chunks_mut(size_of::<Self::Output>()) {
+            let bytes = self.rand()?.into().to_le_bytes();
+            chunk.copy_from_slice(&bytes[..chunk.len()]);
+        }
+        Ok(())
+    }
+
+    /// Returns `true` with the probability `p`.
+    ///
+    /// `p <= 0.0` always returns `false` and `p >= 1.0` always returns `true`.
+    fn gen_bool(&mut self, p: f64) -> Result<bool, ()>
+    where
+        Self::Output: Into<u64>,
+    {
+        if p <= 0.0 {
+            return Ok(false);
+        } else if p >= 1.0 {
+            return Ok(true);
+        }
+        let bits = size_of::<Self::Output>() * 8;
+        let range = (1u64 << (bits - 1)) as f64 * 2.0;
+        let threshold = (p * range) as u64;
+        Ok(self.rand()?.into() < threshold)
+    }
 }
 
 /// Pseudo Random Number Generator
@@ -78,3 +112,350 @@ impl Prng for XorShift32 {
         x
     }
 }
+
+/// Entropy pool that keys a ChaCha20 generator
+///
+/// The generator is rekeyed from the pool and its previous output whenever entropy is credited.
+/// The output is available before the pool is seeded, see [`EntropyPool::is_seeded`].
+pub struct EntropyPool {
+    seed: [u8; Self::SEED_SIZE],
+    position: usize,
+    collected_bits: usize,
+    rng: Option<ChaCha20Rng>,
+}
+
+impl EntropyPool {
+    pub const SEED_SIZE: usize = 32;
+
+    #[inline]
+    pub const fn new() -> Self {
+        Self {
+            seed: [0; Self::SEED_SIZE],
+            position: 0,
+            collected_bits: 0,
+            rng: None,
+        }
+    }
+
+    /// Returns whether the pool has collected a full seed of entropy.
+    #[inline]
+    pub fn is_seeded(&self) -> bool {
+        self.collected_bits >= Self::SEED_SIZE * 8
+    }
+
+    /// Mixes the data into the pool and credits it as `bits` bits of entropy.
+    pub fn credit(&mut self, data: &[u8], bits: usize) {
+        for byte in data {
+            self.seed[self.position] ^= *byte;
+            self.position = (self.position + 1) % Self::SEED_SIZE;
+        }
+        self.collected_bits = self.collected_bits.saturating_add(bits);
+        self.rekey();
+    }
+
+    /// Replaces the generator with the one keyed by the current pool and the previous output.
+    fn rekey(&mut self) {
+        let mut key = self.seed;
+        if let Some(rng) = self.rng.as_mut() {
+            for chunk in key.chunks_mut(4) {
+                let prev = rng.next().to_le_bytes();
+                for (key, prev) in chunk.iter_mut().zip(prev.iter()) {
+                    *key ^= *prev;
+                }
+            }
+        }
+        self.rng = Some(ChaCha20Rng::new(key));
+    }
+
+    #[inline]
+    fn rng(&mut self) -> &mut ChaCha20Rng {
+        if self.rng.is_none() {
+            self.rekey();
+        }
+        self.rng.as_mut().unwrap()
+    }
+
+    /// Fills the buffer with the keystream of the generator.
+    #[inline]
+    pub fn fill_bytes(&mut self, dest: &mut [u8]) {
+        let _ = self.rng().fill_bytes(dest);
+    }
+
+    #[inline]
+    pub fn next_u64(&mut self) -> u64 {
+        let rng = self.rng();
+        rng.next() as u64 | (rng.next() as u64) << 32
+    }
+}
+
+impl Default for EntropyPool {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Samples such as the interrupt timings, collected without a lock
+///
+/// They are mixed into the [`EntropyPool`] by [`PendingEntropy::drain_into`] on its next access.
+pub struct PendingEntropy {
+    samples: AtomicU64,
+    bits: AtomicUsize,
+}
+
+impl PendingEntropy {
+    /// Maximum entropy credited for a single event
+    pub const MAX_BITS_PER_EVENT: usize = 2;
+
+    #[inline]
+    pub const fn new() -> Self {
+        Self {
+            samples: AtomicU64::new(0),
+            bits: AtomicUsize::new(0),
+        }
+    }
+
+    /// Adds the sample and credits at most [`PendingEntropy::MAX_BITS_PER_EVENT`] bits for it.
+    ///
+    /// Lock-free, so that it can be called in interrupt handlers.
+    #[inline]
+    pub fn add(&self, sample: u64, bits: usize) {
+        let _ = self
+            .samples
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |v| {
+                Some(v.rotate_left(7) ^ sample)
+            });
+        self.bits
+            .fetch_add(bits.min(Self::MAX_BITS_PER_EVENT), Ordering::Relaxed);
+    }
+
+    /// Moves the pending samples into the pool.
+    pub fn drain_into(&self, pool: &mut EntropyPool) {
+        let bits = self.bits.swap(0, Ordering::Relaxed);
+        let samples = self.samples.swap(0, Ordering::Relaxed);
+        if bits > 0 || samples != 0 {
+            pool.credit(&samples.to_le_bytes(), bits);
+        }
+    }
+}
+
+impl Default for PendingEntropy {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// ChaCha20 based cryptographically secure pseudo random number generator
+///
+/// The output is the ChaCha20 keystream of RFC 8439, so it is reproducible for a given seed.
+pub struct ChaCha20Rng {
+    state: [u32; 16],
+    block: [u32; 16],
+    index: usize,
+}
+
+impl ChaCha20Rng {
+    const CONSTANTS: [u32; 4] = [0x6170_7865, 0x3320_646e, 0x7962_2d32, 0x6b20_6574];
+    const BLOCK_WORDS: usize = 16;
+
+    /// Creates a generator from the 256-bit seed with a zero nonce.
+    #[inline]
+    pub fn new(seed: [u8; 32]) -> Self {
+        Self::with_nonce(seed, [0; 12])
+    }
+
+    /// Creates a generator from the 256-bit seed and 96-bit nonce, starting at block 0.
+    pub fn with_nonce(seed: [u8; 32], nonce: [u8; 12]) -> Self {
+        let mut state = [0; 16];
+        state[..4].copy_from_slice(&Self::CONSTANTS);
+        for (word, bytes) in state[4..12].iter_mut().zip(seed.chunks_exact(4)) {
+            *word = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+        }
+        for (word, bytes) in state[13..16].iter_mut().zip(nonce.chunks_exact(4)) {
+            *word = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+        }
+        Self {
+            state,
+            block: [0; 16],
+            index: Self::BLOCK_WORDS,
+        }
+    }
+
+    /// Seeks to the beginning of the specified block.
+    #[inline]
+    pub fn set_block_counter(&mut self, counter: u32) {
+        self.state[12] = counter;
+        self.index = Self::BLOCK_WORDS;
+    }
+
+    #[inline]
+    fn quarter_round(x: &mut [u32; 16], a: usize, b: usize, c: usize, d: usize) {
+        x[a] = x[a].wrapping_add(x[b]);
+        x[d] = (x[d] ^ x[a]).rotate_left(16);
+        x[c] = x[c].wrapping_add(x[d]);
+        x[b] = (x[b] ^ x[c]).rotate_left(12);
+        x[a] = x[a].wrapping_add(x[b]);
+        x[d] = (x[d] ^ x[a]).rotate_left(8);
+        x[c] = x[c].wrapping_add(x[d]);
+        x[b] = (x[b] ^ x[c]).rotate_left(7);
+    }
+
+    fn refill(&mut self) {
+        let mut x = self.state;
+        for _ in 0..10 {
+            Self::quarter_round(&mut x, 0, 4, 8, 12);
+            Self::quarter_round(&mut x, 1, 5, 9, 13);
+            Self::quarter_round(&mut x, 2, 6, 10, 14);
+            Self::quarter_round(&mut x, 3, 7, 11, 15);
+            Self::quarter_round(&mut x, 0, 5, 10, 15);
+            Self::quarter_round(&mut x, 1, 6, 11, 12);
+            Self::quarter_round(&mut x, 2, 7, 8, 13);
+            Self::quarter_round(&mut x, 3, 4, 9, 14);
+        }
+        for (output, (x, state)) in self.block.iter_mut().zip(x.iter().zip(self.state.iter())) {
+            *output = x.wrapping_add(*state);
+        }
+        self.state[12] = self.state[12].wrapping_add(1);
+        self.index = 0;
+    }
+}
+
+impl Prng for ChaCha20Rng {
+    type Output = u32;
+    fn next(&mut self) -> u32 {
+        if self.index >= Self::BLOCK_WORDS {
+            self.refill();
+        }
+        let result = self.block[self.index];
+        self.index += 1;
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fill_bytes() {
+        for len in [0, 1, 7, 8, 9, 31] {
+            let mut buf = [0u8; 31];
+            let buf = &mut buf[..len];
+            let mut rng = XorShift32::default();
+            rng.fill_bytes(buf).unwrap();
+            // With this seed, every byte of the first few words is non-zero
+            assert!(buf.iter().all(|v| *v != 0), "{:?}", buf);
+        }
+
+        let mut buf1 = [0u8; 12];
+        let mut buf2 = [0u8; 12];
+        XorShift64::default().fill_bytes(&mut buf1).unwrap();
+        let mut rng = XorShift64::default();
+        buf2[..8].copy_from_slice(&rng.next().to_le_bytes());
+        buf2[8..].copy_from_slice(&rng.next().to_le_bytes()[..4]);
+        assert_eq!(buf1, buf2);
+    }
+
+    #[test]
+    fn gen_bool() {
+        let mut rng = XorShift64::default();
+        for _ in 0..100 {
+            assert_eq!(rng.gen_bool(0.0), Ok(false));
+            assert_eq!(rng.gen_bool(1.0), Ok(true));
+        }
+
+        let mut rng = XorShift32::default();
+        let count = (0..1000).filter(|_| rng.gen_bool(0.5).unwrap()).count();
+        assert!(count > 400 && count < 600, "{}", count);
+    }
+
+    #[test]
+    fn entropy_pool_read() {
+        // reads like /dev/urandom, before the pool is seeded
+        let mut pool = EntropyPool::new();
+        assert!(!pool.is_seeded());
+        let mut buf1 = [0u8; 64];
+        let mut buf2 = [0u8; 64];
+        pool.fill_bytes(&mut buf1);
+        pool.fill_bytes(&mut buf2);
+        assert!(buf1.iter().any(|v| *v != 0));
+        assert!(buf2.iter().any(|v| *v != 0));
+        assert_ne!(buf1, buf2);
+
+        let mut values = (0..64)
+            .map(|_| pool.next_u64())
+            .collect::<alloc::vec::Vec<_>>();
+        values.sort_unstable();
+        values.dedup();
+        assert_eq!(values.len(), 64);
+
+        // the samples change the output
+        let mut pool1 = EntropyPool::new();
+        let mut pool2 = EntropyPool::new();
+        pool1.credit(&[0x12, 0x34], 0);
+        pool2.credit(&[0x12, 0x35], 0);
+        assert_ne!(pool1.next_u64(), pool2.next_u64());
+    }
+
+    #[test]
+    fn pending_entropy() {
+        let mut pool = EntropyPool::new();
+        let pending = PendingEntropy::new();
+
+        // the interrupt timings seed the pool
+        for tick in 0..(EntropyPool::SEED_SIZE * 8 / PendingEntropy::MAX_BITS_PER_EVENT) as u64 {
+            pending.add(tick * 0x1_0000 + 0x1234, 8);
+            if tick % 16 == 15 {
+                pending.drain_into(&mut pool);
+            }
+        }
+        pending.drain_into(&mut pool);
+        assert!(pool.is_seeded());
+
+        // uncredited samples are still mixed in
+        let mut pool1 = EntropyPool::new();
+        let mut pool2 = EntropyPool::new();
+        pending.add(0x5678, 0);
+        pending.drain_into(&mut pool1);
+        pending.drain_into(&mut pool2);
+        assert!(!pool1.is_seeded());
+        assert_ne!(pool1.next_u64(), pool2.next_u64());
+    }
+
+    #[test]
+    fn chacha20_zero_key() {
+        // RFC 8439 A.1 Test Vector #1
+        let expected = [
+            0x76, 0xb8, 0xe0, 0xad, 0xa0, 0xf1, 0x3d, 0x90, 0x40, 0x5d, 0x6a, 0xe5, 0x53, 0x86,
+            0xbd, 0x28, 0xbd, 0xd2, 0x19, 0xb8, 0xa0, 0x8d, 0xed, 0x1a, 0xa8, 0x36, 0xef, 0xcc,
+            0x8b, 0x77, 0x0d, 0xc7, 0xda, 0x41, 0x59, 0x7c, 0x51, 0x57, 0x48, 0x8d, 0x77, 0x24,
+            0xe0, 0x3f, 0xb8, 0xd8, 0x4a, 0x37, 0x6a, 0x43, 0xb8, 0xf4, 0x15, 0x18, 0xa1, 0x1c,
+            0xc3, 0x87, 0xb6, 0x69, 0xb2, 0xee, 0x65, 0x86,
+        ];
+        let mut rng = ChaCha20Rng::new([0; 32]);
+        let mut buf = [0u8; 64];
+        rng.fill_bytes(&mut buf).unwrap();
+        assert_eq!(buf, expected);
+    }
+
+    #[test]
+    fn chacha20_block() {
+        // RFC 8439 2.3.2 Test Vector for the ChaCha20 Block Function
+        let mut seed = [0u8; 32];
+        for (index, byte) in seed.iter_mut().enumerate() {
+            *byte = index as u8;
+        }
+        let nonce = [0, 0, 0, 0x09, 0, 0, 0, 0x4a, 0, 0, 0, 0];
+        let expected = [
+            0xe4e7f110, 0x15593bd1, 0x1fdd0f50, 0xc47120a3, 0xc7f4d1c7, 0x0368c033, 0x9aaa2204,
+            0x4e6cd4c3, 0x466482d2, 0x09aa9f07, 0x05d7c214, 0xa2028bd9, 0xd19c12b5, 0xb94e16de,
+            0xe883d0cb, 0x4e3c50a2,
+        ];
+        let mut rng = ChaCha20Rng::with_nonce(seed, nonce);
+        rng.set_block_counter(1);
+        for word in expected {
+            assert_eq!(rng.next(), word);
+        }
+    }
+}
@@ -0,0 +1,321 @@
+//! Command line of the shell
+
+use alloc::{borrow::ToOwned, collections::BTreeMap, string::String, vec::Vec};
+use core::{fmt::Write, iter::Peekable, str::Chars};
+
+/// Reasons why the command line cannot be run
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CmdLineError {
+    Empty,
+    InvalidQuote,
+    InvalidPipe,
+}
+
+/// Variables of the shell, which are also passed to the spawned programs
+#[derive(Debug, Default)]
+pub struct ShellEnv {
+    vars: BTreeMap<String, String>,
+    last_status: i32,
+}
+
+impl ShellEnv {
+    #[inline]
+    pub const fn new() -> Self {
+        Self {
+            vars: BTreeMap::new(),
+            last_status: 0,
+        }
+    }
+
+    #[inline]
+    pub fn vars(&self) -> &BTreeMap<String, String> {
+        &self.vars
+    }
+
+    #[inline]
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.vars.get(name).map(|v| v.as_str())
+    }
+
+    /// Sets the variable from `NAME=value`, and returns `false` if the name is not valid.
+    pub fn set(&mut self, assignment: &str) -> bool {
+        match assignment.split_once('=') {
+            Some((name, value)) if Self::is_valid_name(name) => {
+                self.vars.insert(name.to_owned(), value.to_owned());
+                true
+            }
+            _ => false,
+        }
+    }
+
+    #[inline]
+    pub fn unset(&mut self, name: &str) {
+        self.vars.remove(name);
+    }
+
+    /// The exit code of the last command, which `$?` expands to
+    #[inline]
+    pub const fn last_status(&self) -> i32 {
+        self.last_status
+    }
+
+    #[inline]
+    pub fn set_last_status(&mut self, status: i32) {
+        self.last_status = status;
+    }
+
+    #[inline]
+    pub fn is_valid_name(name: &str) -> bool {
+        !name.is_empty() && name.chars().all(Self::is_valid_name_char)
+    }
+
+    #[inline]
+    fn is_valid_name_char(c: char) -> bool {
+        c.is_ascii_alphanumeric() || c == '_'
+    }
+
+    /// Expands the variable following `$`. Undefined variables expand to nothing.
+    fn expand_var(&self, chars: &mut Peekable<Chars>, sb: &mut String) {
+        let mut name = String::new();
+        match chars.peek() {
+            Some('?') => {
+                chars.next();
+                write!(sb, "{}", self.last_status).unwrap();
+                return;
+            }
+            Some('{') => {
+                chars.next();
+                for c in chars.by_ref() {
+                    if c == '}' {
+                        break;
+                    }
+                    name.push(c);
+                }
+            }
+            Some(&c) if Self::is_valid_name_char(c) => {
+                while let Some(&c) = chars.peek() {
+                    if !Self::is_valid_name_char(c) {
+                        break;
+                    }
+                    name.push(c);
+                    chars.next();
+                }
+            }
+            _ => {
+                sb.push('$');
+                return;
+            }
+        }
+        if let Some(value) = self.vars.get(&name) {
+            sb.push_str(value);
+        }
+    }
+}
+
+/// Splits the command line into the stages of the pipeline
+pub fn split_pipeline(cmdline: &str) -> Result<Vec<&str>, CmdLineError> {
+    let mut stages = Vec::new();
+    let mut quote = None;
+    let mut start = 0;
+    for (index, c) in cmdline.char_indices() {
+        match quote {
+            Some(q) => {
+                if c == q {
+                    quote = None;
+                }
+            }
+            None => match c {
+                '\'' | '\"' => quote = Some(c),
+                '|' => {
+                    stages.push(&cmdline[start..index]);
+                    start = index + 1;
+                }
+                _ => (),
+            },
+        }
+    }
+    if quote.is_some() {
+        return Err(CmdLineError::InvalidQuote);
+    }
+    stages.push(&cmdline[start..]);
+
+    if stages.len() > 1 && stages.iter().any(|v| v.trim().is_empty()) {
+        return Err(CmdLineError::InvalidPipe);
+    }
+    Ok(stages)
+}
+
+/// Splits the command line into the arguments, expanding the variables and the leading `~`.
+///
+/// Returns the command name along with all the arguments including it.
+pub fn parse_cmd(
+    cmdline: &str,
+    env: &ShellEnv,
+    home: &str,
+) -> Result<(String, Vec<String>), CmdLineError> {
+    enum CmdLinePhase {
+        SkippingSpace,
+        Token,
+        SingleQuote,
+        DoubleQuote,
+    }
+
+    if cmdline.is_empty() {
+        return Err(CmdLineError::Empty);
+    }
+
+    let mut sb = String::new();
+    let mut args = Vec::new();
+    let mut phase = CmdLinePhase::SkippingSpace;
+    let mut chars = cmdline.chars().peekable();
+    while let Some(c) = chars.next() {
+        match phase {
+            CmdLinePhase::SkippingSpace => match c {
+                ' ' | '\t' | '\r' | '\n' => (),
+                '\'' => {
+                    phase = CmdLinePhase::SingleQuote;
+                }
+                '\"' => {
+                    phase = CmdLinePhase::DoubleQuote;
+                }
+                '$' => {
+                    env.expand_var(&mut chars, &mut sb);
+                    phase = CmdLinePhase::Token;
+                }
+                '~' => {
+                    match chars.peek() {
+                        None | Some('/' | ' ' | '\t' | '\r' | '\n') => sb.push_str(home),
+                        _ => sb.push(c),
+                    }
+                    phase = CmdLinePhase::Token;
+                }
+                _ => {
+                    sb.push(c);
+                    phase = CmdLinePhase::Token;
+                }
+            },
+            CmdLinePhase::Token => match c {
+                ' ' | '\t' | '\r' | '\n' => {
+                    // Unquoted empty expansions disappear
+                    if !sb.is_empty() {
+                        args.push(sb);
+                        sb = String::new();
+                    }
+                    phase = CmdLinePhase::SkippingSpace;
+                }
+                '$' => {
+                    env.expand_var(&mut chars, &mut sb);
+                }
+                _ => {
+                    sb.push(c);
+                }
+            },
+            CmdLinePhase::SingleQuote => match c {
+                '\'' => {
+                    args.push(sb);
+                    phase = CmdLinePhase::SkippingSpace;
+                    sb = String::new();
+                }
+                _ => {
+                    sb.push(c);
+                }
+            },
+            CmdLinePhase::DoubleQuote => match c {
+                '\"' => {
+                    args.push(sb);
+                    phase = CmdLinePhase::SkippingSpace;
+                    sb = String::new();
+                }
+                '$' => {
+                    env.expand_var(&mut chars, &mut sb);
+                }
+                _ => {
+                    sb.push(c);
+                }
+            },
+        }
+    }
+    match phase {
+        CmdLinePhase::SkippingSpace | CmdLinePhase::Token => (),
+        CmdLinePhase::SingleQuote | CmdLinePhase::DoubleQuote => {
+            return Err(CmdLineError::InvalidQuote)
+        }
+    }
+    if !sb.is_empty() {
+        args.push(sb);
+    }
+    if let Some(cmd) = args.first() {
+        Ok((cmd.to_owned(), args))
+    } else {
+        Err(CmdLineError::Empty)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    fn parse(cmdline: &str, env: &ShellEnv) -> Vec<String> {
+        parse_cmd(cmdline, env, "/home/user").unwrap().1
+    }
+
+    #[test]
+    fn set_var() {
+        let mut env = ShellEnv::new();
+        assert!(env.set("NAME=value"));
+        assert!(env.set("EMPTY="));
+        assert_eq!(env.get("NAME"), Some("value"));
+        assert_eq!(env.get("EMPTY"), Some(""));
+        assert!(!env.set("NAME"));
+        assert!(!env.set("=value"));
+        assert!(!env.set("A-B=value"));
+
+        assert_eq!(parse("echo $NAME", &env), ["echo", "value"]);
+        env.unset("NAME");
+        assert_eq!(env.get("NAME"), None);
+        assert_eq!(env.vars().len(), 1);
+    }
+
+    #[test]
+    fn expand_var() {
+        let mut env = ShellEnv::new();
+        env.set("NAME=value");
+        env.set_last_status(42);
+
+        // in the middle of the token
+        assert_eq!(parse("echo a$NAME.txt", &env), ["echo", "avalue.txt"]);
+        assert_eq!(parse("echo a${NAME}b", &env), ["echo", "avalueb"]);
+        assert_eq!(
+            parse("echo \"<$NAME>\" '$NAME'", &env),
+            ["echo", "<value>", "$NAME"]
+        );
+        assert_eq!(parse("echo $?", &env), ["echo", "42"]);
+        assert_eq!(parse("echo $ a$", &env), ["echo", "$", "a$"]);
+
+        // undefined variables expand to nothing, and so do the whole tokens of them
+        assert_eq!(parse("echo a${UNDEFINED}b", &env), ["echo", "ab"]);
+        assert_eq!(parse("echo $UNDEFINED end", &env), ["echo", "end"]);
+        assert_eq!(parse("echo \"$UNDEFINED\"", &env), ["echo", ""]);
+    }
+
+    #[test]
+    fn parse_cmdline() {
+        let env = ShellEnv::new();
+        assert_eq!(parse("cd ~/docs", &env), ["cd", "/home/user/docs"]);
+        assert_eq!(parse("echo ~root a~", &env), ["echo", "~root", "a~"]);
+        assert_eq!(parse_cmd("", &env, "/"), Err(CmdLineError::Empty));
+        assert_eq!(parse_cmd("  ", &env, "/"), Err(CmdLineError::Empty));
+        assert_eq!(
+            parse_cmd("echo 'a", &env, "/"),
+            Err(CmdLineError::InvalidQuote)
+        );
+
+        assert_eq!(
+            split_pipeline("ls | cat 'a|b'"),
+            Ok(vec!["ls ", " cat 'a|b'"])
+        );
+        assert_eq!(split_pipeline("ls |"), Err(CmdLineError::InvalidPipe));
+        assert_eq!(split_pipeline("echo \"a"), Err(CmdLineError::InvalidQuote));
+    }
+}
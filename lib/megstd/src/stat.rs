@@ -0,0 +1,67 @@
+//! Columns of the process and thread statistics
+
+use core::{fmt, time::Duration};
+
+/// Load in permille, displayed as a percentage
+///
+/// Loads of 10% or more are displayed without the fraction to keep the column width.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Load(pub u32);
+
+impl fmt::Display for Load {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let load0 = self.0 % 10;
+        let load1 = self.0 / 10;
+        if load1 >= 10 {
+            write!(f, "{:4}", load1)
+        } else {
+            write!(f, "{:2}.{:1}", load1, load0)
+        }
+    }
+}
+
+/// Consumed CPU time, displayed as `MM:SS.ss` or `HH:MM:SS` beyond an hour
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CpuTime(pub Duration);
+
+impl fmt::Display for CpuTime {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let time = self.0.as_secs();
+        let dsec = self.0.subsec_millis() / 10;
+        let sec = time % 60;
+        let min = time / 60 % 60;
+        let hour = time / 3600;
+        if hour > 0 {
+            write!(f, "{:02}:{:02}:{:02}", hour, min, sec)
+        } else {
+            write!(f, "{:02}:{:02}.{:02}", min, sec, dsec)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::format;
+
+    #[test]
+    fn load() {
+        assert_eq!(format!("{}", Load(0)), " 0.0");
+        assert_eq!(format!("{}", Load(95)), " 9.5");
+        assert_eq!(format!("{}", Load(100)), "  10");
+        assert_eq!(format!("{}", Load(1000)), " 100");
+    }
+
+    #[test]
+    fn cpu_time() {
+        assert_eq!(format!("{}", CpuTime(Duration::ZERO)), "00:00.00");
+        assert_eq!(
+            format!("{}", CpuTime(Duration::from_millis(61_234))),
+            "01:01.23"
+        );
+        assert_eq!(
+            format!("{}", CpuTime(Duration::from_secs(3600 + 62))),
+            "01:01:02"
+        );
+    }
+}
@@ -77,3 +77,60 @@ impl AsRef<str> for Sb255 {
         self.as_str()
     }
 }
+
+/// Formats the bytes like `xxd`, e.g. `{:?}`
+#[repr(transparent)]
+pub struct HexDump<'a>(pub &'a [u8]);
+
+impl HexDump<'_> {
+    const BYTES_PER_LINE: usize = 16;
+}
+
+impl fmt::Debug for HexDump<'_> {
+    /// Formats like `xxd`: offset, 16 bytes in 2-byte groups, and the printable ASCII gutter.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (index, line) in self.0.chunks(Self::BYTES_PER_LINE).enumerate() {
+            if index > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{:08x}:", index * Self::BYTES_PER_LINE)?;
+            for i in 0..Self::BYTES_PER_LINE {
+                if i & 1 == 0 {
+                    write!(f, " ")?;
+                }
+                match line.get(i) {
+                    Some(c) => write!(f, "{:02x}", *c)?,
+                    None => write!(f, "  ")?,
+                }
+            }
+            write!(f, "  ")?;
+            for c in line.iter() {
+                let c = *c;
+                if (0x20..0x7F).contains(&c) {
+                    write!(f, "{}", c as char)?;
+                } else {
+                    write!(f, ".")?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::format;
+
+    #[test]
+    fn hex_dump() {
+        let bytes = b"Hello, world!\n\x00\x7F\xFFABC";
+        assert_eq!(bytes.len(), 20);
+        assert_eq!(
+            format!("{:?}", HexDump(bytes)),
+            "00000000: 4865 6c6c 6f2c 2077 6f72 6c64 210a 007f  Hello, world!...\n\
+             00000010: ff41 4243                                .ABC"
+        );
+        assert_eq!(format!("{:?}", HexDump(&[])), "");
+    }
+}
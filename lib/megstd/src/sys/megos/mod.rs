@@ -1,8 +1,171 @@
+use crate::drawing::{Point, Size};
+use alloc::vec::Vec;
+
 pub mod svc;
 
 /// Invalid character representation in Rust
 pub const OPTION_CHAR_NONE: u32 = 0x110000;
 
+/// Returned by [svc::Function::GetEnv] if the variable is not set
+pub const OPTION_ENV_NONE: u32 = u32::MAX;
+
+/// Packs the size into the return value of [svc::Function::GetWindowSize].
+///
+/// Each dimension is clamped to 16 bits.
+#[inline]
+pub fn pack_size(size: Size) -> u32 {
+    let width = size.width().clamp(0, 0xFFFF) as u32;
+    let height = size.height().clamp(0, 0xFFFF) as u32;
+    width | (height << 16)
+}
+
+/// Unpacks the return value of [svc::Function::GetWindowSize].
+#[inline]
+pub fn unpack_size(value: u32) -> Size {
+    Size::new((value & 0xFFFF) as isize, (value >> 16) as isize)
+}
+
+/// Packs the point into the return value of [svc::Function::DrawString].
+///
+/// Each coordinate is clamped to signed 16 bits.
+#[inline]
+pub fn pack_point(point: Point) -> u32 {
+    let x = point.x.clamp(i16::MIN as isize, i16::MAX as isize) as u16 as u32;
+    let y = point.y.clamp(i16::MIN as isize, i16::MAX as isize) as u16 as u32;
+    x | (y << 16)
+}
+
+/// Unpacks the return value of [svc::Function::DrawString].
+#[inline]
+pub fn unpack_point(value: u32) -> Point {
+    Point::new(
+        value as u16 as i16 as isize,
+        (value >> 16) as u16 as i16 as isize,
+    )
+}
+
+/// Reads a value of variable length by a system call that returns the length of the whole value.
+///
+/// The length is queried with a null buffer first, and then the call is repeated
+/// until the buffer is large enough, since the value may grow between the calls.
+/// Returns `None` if the call returns `None`.
+pub fn read_sized<F>(mut read: F) -> Option<Vec<u8>>
+where
+    F: FnMut(*mut u8, usize) -> Option<usize>,
+{
+    let mut len = read(core::ptr::null_mut(), 0)?;
+    let mut buf = Vec::new();
+    while len > buf.len() {
+        buf.resize(len, 0);
+        len = read(buf.as_mut_ptr(), buf.len())?;
+    }
+    buf.truncate(len);
+    Some(buf)
+}
+
+/// An event of a window returned by [svc::Function::WaitEvent]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WindowEvent {
+    /// A character has been typed
+    Char(char),
+    /// The content area has been resized, and has to be drawn again
+    Resized(Size),
+}
+
+impl WindowEvent {
+    const RESIZED: u32 = 0x8000_0000;
+    const RESIZED_MAX: isize = 0x7FFF;
+
+    /// Packs the event into the return value of [svc::Function::WaitEvent].
+    ///
+    /// Each dimension of the size is clamped to 15 bits.
+    #[inline]
+    pub fn pack(self) -> u32 {
+        match self {
+            Self::Char(c) => c as u32,
+            Self::Resized(size) => {
+                let width = size.width().clamp(0, Self::RESIZED_MAX) as u32;
+                let height = size.height().clamp(0, Self::RESIZED_MAX) as u32;
+                Self::RESIZED | width | (height << 15)
+            }
+        }
+    }
+
+    /// Unpacks the return value of [svc::Function::WaitEvent].
+    #[inline]
+    pub fn unpack(value: u32) -> Option<Self> {
+        if value & Self::RESIZED != 0 {
+            let mask = Self::RESIZED_MAX as u32;
+            Some(Self::Resized(Size::new(
+                (value & mask) as isize,
+                ((value >> 15) & mask) as isize,
+            )))
+        } else {
+            char::from_u32(value).map(Self::Char)
+        }
+    }
+}
+
+/// The main display returned by [svc::Function::GetDisplayInfo]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct OsDisplayInfo {
+    pub width: u32,
+    pub height: u32,
+    pub flags: u32,
+    /// Suggested integer scale for pixel art
+    pub scale: u32,
+}
+
+impl OsDisplayInfo {
+    pub const FLAG_PORTRAIT: u32 = 0x0000_0001;
+
+    /// Size of the buffer that [svc::Function::GetDisplayInfo] writes to
+    pub const SIZE: usize = 16;
+
+    #[inline]
+    pub const fn is_portrait(&self) -> bool {
+        (self.flags & Self::FLAG_PORTRAIT) != 0
+    }
+
+    /// Packs the information into the buffer of [svc::Function::GetDisplayInfo].
+    pub fn to_bytes(&self) -> [u8; Self::SIZE] {
+        let mut bytes = [0; Self::SIZE];
+        bytes[0..4].copy_from_slice(&self.width.to_le_bytes());
+        bytes[4..8].copy_from_slice(&self.height.to_le_bytes());
+        bytes[8..12].copy_from_slice(&self.flags.to_le_bytes());
+        bytes[12..16].copy_from_slice(&self.scale.to_le_bytes());
+        bytes
+    }
+
+    /// Unpacks the buffer of [svc::Function::GetDisplayInfo].
+    pub fn from_bytes(bytes: &[u8; Self::SIZE]) -> Self {
+        let read = |offset: usize| {
+            u32::from_le_bytes([
+                bytes[offset],
+                bytes[offset + 1],
+                bytes[offset + 2],
+                bytes[offset + 3],
+            ])
+        };
+        Self {
+            width: read(0),
+            height: read(4),
+            flags: read(8),
+            scale: read(12),
+        }
+    }
+
+    /// Reads the information by a system call that writes it to the buffer.
+    pub fn read<F>(read: F) -> Self
+    where
+        F: FnOnce(*mut u8),
+    {
+        let mut bytes = [0; Self::SIZE];
+        read(bytes.as_mut_ptr());
+        Self::from_bytes(&bytes)
+    }
+}
+
 pub mod window {
     /// Use 32bit bitmap in window
     pub const USE_BITMAP32: u32 = 1 << 0;
@@ -13,3 +176,142 @@ pub mod window {
     /// Full Screen
     pub const FULLSCREEN: u32 = 1 << 4;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn window_size() {
+        let size = Size::new(640, 480);
+        assert_eq!(unpack_size(pack_size(size)), size);
+        assert_eq!(unpack_size(pack_size(Size::new(0, 0))), Size::new(0, 0));
+        assert_eq!(
+            unpack_size(pack_size(Size::new(0x12345, -1))),
+            Size::new(0xFFFF, 0)
+        );
+    }
+
+    #[test]
+    fn draw_string_end() {
+        let point = Point::new(123, -45);
+        assert_eq!(unpack_point(pack_point(point)), point);
+        assert_eq!(
+            unpack_point(pack_point(Point::new(0x12345, -0x12345))),
+            Point::new(i16::MAX as isize, i16::MIN as isize)
+        );
+    }
+
+    #[test]
+    fn display_info() {
+        // A mock of the system call that reports a portrait display of 1080x1920 at 2x
+        fn get_display_info(ptr: *mut u8) {
+            let bytes = [
+                0x38, 0x04, 0, 0, 0x80, 0x07, 0, 0, 0x01, 0, 0, 0, 0x02, 0, 0, 0,
+            ];
+            unsafe { ptr.copy_from_nonoverlapping(bytes.as_ptr(), bytes.len()) };
+        }
+
+        let info = OsDisplayInfo::read(get_display_info);
+        assert_eq!(
+            info,
+            OsDisplayInfo {
+                width: 1080,
+                height: 1920,
+                flags: OsDisplayInfo::FLAG_PORTRAIT,
+                scale: 2,
+            }
+        );
+        assert!(info.is_portrait());
+        assert_eq!(OsDisplayInfo::from_bytes(&info.to_bytes()), info);
+
+        let landscape = OsDisplayInfo {
+            width: 800,
+            height: 600,
+            flags: 0,
+            scale: 1,
+        };
+        assert!(!landscape.is_portrait());
+        assert_eq!(
+            OsDisplayInfo::read(|ptr| unsafe {
+                ptr.copy_from_nonoverlapping(landscape.to_bytes().as_ptr(), OsDisplayInfo::SIZE)
+            }),
+            landscape
+        );
+    }
+
+    #[test]
+    fn read_sized_text() {
+        // A mock of the system call that copies the clipboard if the buffer is large enough
+        fn clipboard_get(text: &str, ptr: *mut u8, len: usize) -> usize {
+            let bytes = text.as_bytes();
+            if ptr.is_null() {
+                assert_eq!(len, 0);
+            } else if bytes.len() <= len {
+                unsafe { ptr.copy_from_nonoverlapping(bytes.as_ptr(), bytes.len()) };
+            }
+            bytes.len()
+        }
+
+        let text = "クリップボード📋 clipboard";
+        let mut calls = Vec::new();
+        let buf = read_sized(|ptr, len| {
+            calls.push(ptr.is_null());
+            Some(clipboard_get(text, ptr, len))
+        })
+        .unwrap();
+        assert_eq!(core::str::from_utf8(&buf), Ok(text));
+        // queries the length with a null buffer, and then reads once
+        assert_eq!(calls, [true, false]);
+
+        // the text grows between the calls
+        let texts = ["短い", "長くなったテキスト", "長くなったテキスト"];
+        let mut texts = texts.iter();
+        let buf =
+            read_sized(|ptr, len| Some(clipboard_get(texts.next().unwrap(), ptr, len))).unwrap();
+        assert_eq!(core::str::from_utf8(&buf), Ok("長くなったテキスト"));
+
+        // the text shrinks between the calls
+        let mut texts = ["長いテキスト", "短い"].iter();
+        let buf =
+            read_sized(|ptr, len| Some(clipboard_get(texts.next().unwrap(), ptr, len))).unwrap();
+        assert_eq!(core::str::from_utf8(&buf), Ok("短い"));
+
+        assert_eq!(
+            read_sized(|ptr, len| Some(clipboard_get("", ptr, len))),
+            Some(Vec::new())
+        );
+        assert_eq!(read_sized(|_, _| None), None);
+    }
+
+    #[test]
+    fn window_event() {
+        use alloc::collections::VecDeque;
+
+        // The events that the runtime posts to the guest
+        let mut queue = VecDeque::new();
+        queue.push_back(WindowEvent::Char('a').pack());
+        queue.push_back(WindowEvent::Char('\0').pack());
+        queue.push_back(WindowEvent::Resized(Size::new(800, 600)).pack());
+        queue.push_back(WindowEvent::Char('\u{10FFFF}').pack());
+        queue.push_back(OPTION_CHAR_NONE);
+
+        // The guest tells the resize from the characters, including NUL
+        let events: Vec<_> = queue.drain(..).map(WindowEvent::unpack).collect();
+        assert_eq!(
+            events,
+            [
+                Some(WindowEvent::Char('a')),
+                Some(WindowEvent::Char('\0')),
+                Some(WindowEvent::Resized(Size::new(800, 600))),
+                Some(WindowEvent::Char('\u{10FFFF}')),
+                None,
+            ]
+        );
+
+        assert_eq!(
+            WindowEvent::unpack(WindowEvent::Resized(Size::new(0x12345, -1)).pack()),
+            Some(WindowEvent::Resized(Size::new(0x7FFF, 0)))
+        );
+    }
+}
@@ -2,7 +2,7 @@
 #[repr(u32)]
 #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub enum Function {
-    /// Terminate the process normally
+    /// Terminate the process with the exit code
     Exit = 0,
     /// Display a string
     PrintString,
@@ -80,4 +80,25 @@ pub enum Function {
     OpenDir,
 
     ReadDir,
+
+    /// Read from the standard input
+    ReadStdin,
+    /// Changes the size of an allocated memory block
+    Realloc,
+    /// Blocks a thread for the specified nanoseconds
+    Nanosleep,
+    /// Get the display information
+    GetDisplayInfo,
+    /// Set a text to the clipboard
+    ClipboardSet,
+    /// Get a text from the clipboard
+    ClipboardGet,
+    /// Play 16-bit monaural PCM samples
+    AudioPlay,
+    /// Get the size of the content area of a window
+    GetWindowSize,
+    /// Get the value of an environment variable
+    GetEnv,
+    /// Wait for a key or a resize of a window
+    WaitEvent,
 }
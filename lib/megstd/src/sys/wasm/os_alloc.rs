@@ -14,6 +14,10 @@ unsafe impl GlobalAlloc for CustomAlloc {
     unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
         os_dealloc(ptr, layout.size(), layout.align());
     }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        os_realloc(ptr, layout.size(), layout.align(), new_size)
+    }
 }
 
 #[cfg(not(test))]
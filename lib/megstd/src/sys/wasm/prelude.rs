@@ -20,7 +20,7 @@ macro_rules! println {
 #[panic_handler]
 fn panic(_info: &core::panic::PanicInfo) -> ! {
     // println!("{}", info);
-    os_exit();
+    os_exit(1);
 }
 
 pub struct OsPrint();
@@ -1,5 +1,11 @@
-use crate::sys::megos::svc::Function;
-use core::arch::asm;
+use crate::drawing::{Alpha8, TrueColor};
+pub use crate::sys::megos::OsDisplayInfo;
+use crate::sys::megos::{self, svc::Function};
+use alloc::string::String;
+use core::{
+    arch::asm,
+    mem::{size_of, size_of_val},
+};
 
 #[allow(dead_code)]
 #[link(wasm_import_module = "megos-canary")]
@@ -60,9 +66,9 @@ macro_rules! syscall {
 }
 
 #[inline]
-pub fn os_exit() -> ! {
+pub fn os_exit(exit_code: i32) -> ! {
     unsafe {
-        syscall!(Exit);
+        syscall!(Exit, exit_code);
         asm!("", options(noreturn, nostack));
     }
 }
@@ -73,6 +79,14 @@ pub fn os_print(s: &str) {
     unsafe { syscall!(PrintString, s.as_ptr(), s.len()) };
 }
 
+/// Read from the standard input.
+///
+/// Returns `0` at the end of the stream.
+#[inline]
+pub fn os_read_stdin(buf: &mut [u8]) -> usize {
+    unsafe { syscall!(ReadStdin, buf.as_mut_ptr(), buf.len()) }
+}
+
 /// Get the value of the monotonic timer in microseconds.
 #[inline]
 pub fn os_monotonic() -> u32 {
@@ -90,12 +104,38 @@ pub fn os_usleep(us: u32) {
     unsafe { syscall!(Usleep, us) };
 }
 
+/// Blocks a thread for the specified nanoseconds without spinning.
+///
+/// Returns the remaining nanoseconds if the sleep was interrupted, otherwise `0`.
+#[inline]
+pub fn os_nanosleep(nanos: u64) -> u64 {
+    let mut remaining = [0u8; size_of::<u64>()];
+    unsafe {
+        syscall!(
+            Nanosleep,
+            nanos as u32,
+            (nanos >> 32) as u32,
+            remaining.as_mut_ptr(),
+            remaining.len()
+        )
+    };
+    u64::from_le_bytes(remaining)
+}
+
 /// Get the system version information.
 #[inline]
 pub fn os_version() -> u32 {
     unsafe { syscall!(GetSystemInfo, 0) as u32 }
 }
 
+/// Get the size, orientation and suggested scale of the main display.
+#[inline]
+pub fn os_display_info() -> OsDisplayInfo {
+    OsDisplayInfo::read(|ptr| unsafe {
+        syscall!(GetDisplayInfo, ptr);
+    })
+}
+
 /// Create a new window.
 #[inline]
 #[must_use]
@@ -145,9 +185,11 @@ pub fn os_end_draw(ctx: usize) {
 }
 
 /// Draw a string in a window.
+///
+/// Returns the end of the last line, packed by [megos::pack_point](crate::sys::megos::pack_point).
 #[inline]
-pub fn os_win_draw_string(ctx: usize, x: usize, y: usize, s: &str, color: usize) {
-    unsafe { syscall!(DrawString, ctx, x, y, s.as_ptr(), s.len(), color) };
+pub fn os_win_draw_string(ctx: usize, x: usize, y: usize, s: &str, color: usize) -> u32 {
+    unsafe { syscall!(DrawString, ctx, x, y, s.as_ptr(), s.len(), color) as u32 }
 }
 
 #[inline]
@@ -193,6 +235,22 @@ pub fn os_read_char(window: usize) -> u32 {
     unsafe { syscall!(ReadChar, window) as u32 }
 }
 
+/// Wait for an event of a window.
+///
+/// The event is packed by [megos::WindowEvent::pack](crate::sys::megos::WindowEvent::pack).
+#[inline]
+pub fn os_wait_event(window: usize) -> u32 {
+    unsafe { syscall!(WaitEvent, window) as u32 }
+}
+
+/// Get the size of the content area of a window.
+///
+/// The size is packed by [megos::pack_size](crate::sys::megos::pack_size).
+#[inline]
+pub fn os_window_size(window: usize) -> u32 {
+    unsafe { syscall!(GetWindowSize, window) as u32 }
+}
+
 /// Draw a bitmap in a window
 #[inline]
 pub fn os_blt8(ctx: usize, x: usize, y: usize, bitmap: usize) {
@@ -216,6 +274,52 @@ pub fn os_blend_rect(bitmap: usize, x: usize, y: usize, width: usize, height: us
     unsafe { syscall!(BlendRect, bitmap, x, y, width, height, color) };
 }
 
+/// Blends a rectangle with the color's alpha multiplied by the layer `opacity`.
+///
+/// An opacity of 255 is the same as [os_blend_rect].
+#[inline]
+pub fn os_blend_rect_alpha(
+    bitmap: usize,
+    x: usize,
+    y: usize,
+    width: usize,
+    height: usize,
+    color: u32,
+    opacity: u8,
+) {
+    let color = TrueColor::from_argb(color);
+    let color = color.with_opacity(color.opacity() * Alpha8(opacity));
+    os_blend_rect(bitmap, x, y, width, height, color.argb());
+}
+
+/// Copies the text to the clipboard.
+#[inline]
+pub fn os_clipboard_set(text: &str) {
+    unsafe { syscall!(ClipboardSet, text.as_ptr(), text.len()) };
+}
+
+/// Returns the text in the clipboard, or an empty string if the clipboard is empty.
+pub fn os_clipboard_get() -> String {
+    megos::read_sized(|ptr, len| Some(unsafe { syscall!(ClipboardGet, ptr, len) }))
+        .and_then(|v| String::from_utf8(v).ok())
+        .unwrap_or_default()
+}
+
+/// Returns the value of the environment variable inherited from the shell, if set.
+pub fn os_getenv(name: &str) -> Option<String> {
+    megos::read_sized(|ptr, len| {
+        let len = unsafe { syscall!(GetEnv, name.as_ptr(), name.len(), ptr, len) };
+        (len as u32 != megos::OPTION_ENV_NONE).then_some(len)
+    })
+    .and_then(|v| String::from_utf8(v).ok())
+}
+
+/// Plays 16-bit monaural PCM samples at the sample rate, mixed with other sounds.
+#[inline]
+pub fn os_audio_play(samples: &[i16], rate: u32) {
+    unsafe { syscall!(AudioPlay, samples.as_ptr(), size_of_val(samples), rate) };
+}
+
 /// Returns a simple pseudo-random number
 ///
 /// # Safety
@@ -246,6 +350,20 @@ pub unsafe fn os_dealloc(ptr: *mut u8, size: usize, align: usize) {
     syscall!(Dealloc, ptr, size, align);
 }
 
+/// Changes the size of an allocated memory block, preserving its contents
+///
+/// The block grows in place if possible, otherwise it is moved and the old block is freed.
+///
+/// # Safety
+///
+/// `ptr` must be a block returned by [`os_alloc`] or [`os_realloc`] that has not been freed yet,
+/// and `size` and `align` must be the same as the ones it was allocated with.
+#[inline]
+#[must_use]
+pub unsafe fn os_realloc(ptr: *mut u8, size: usize, align: usize, new_size: usize) -> *mut u8 {
+    syscall!(Realloc, ptr, size, align, new_size) as *mut u8
+}
+
 #[inline]
 #[must_use]
 pub fn os_open(name: &str, options: usize) -> isize {
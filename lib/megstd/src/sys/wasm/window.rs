@@ -1,6 +1,7 @@
 // MEG-OS Window API
 
 pub use crate::drawing::*;
+use crate::offscreen::BackBuffer;
 use crate::sys::megos;
 use crate::sys::syscall::{self, OsDrawShape};
 
@@ -11,6 +12,7 @@ pub type WindowColor = PackedColor;
 
 pub struct Window {
     handle: WindowHandle,
+    back_buffer: BackBuffer,
 }
 
 impl Window {
@@ -21,7 +23,10 @@ impl Window {
             size.width as usize,
             size.height as usize,
         ));
-        Self { handle }
+        Self {
+            handle,
+            back_buffer: BackBuffer::new(),
+        }
     }
 
     #[inline]
@@ -34,6 +39,12 @@ impl Window {
         self.handle
     }
 
+    /// Returns the size of the content area, which changes when the window is resized.
+    #[inline]
+    pub fn size(&self) -> Size {
+        megos::unpack_size(syscall::os_window_size(self.handle.0))
+    }
+
     #[inline]
     pub fn begin_draw(&self) -> DrawingContext {
         unsafe { DrawingContext::from_raw(syscall::os_begin_draw(self.handle.0)) }
@@ -48,11 +59,39 @@ impl Window {
         f(&mut context)
     }
 
+    /// Returns the off-screen buffer of the same size as the content area.
+    ///
+    /// The buffer is allocated on the first call and is shown by [Window::present].
+    /// It is allocated again with the new size after the window is resized,
+    /// so the contents have to be drawn again.
+    #[inline]
+    pub fn back_buffer(&mut self) -> BitmapRefMut32<'_> {
+        let size = self.size();
+        self.back_buffer.get(size)
+    }
+
+    /// Makes the contents of the back buffer visible.
+    ///
+    /// The window surface is owned by the OS and cannot be swapped, so the back buffer is
+    /// copied to it. The back buffer keeps its contents and can be updated incrementally.
+    #[inline]
+    pub fn present(&self) {
+        self.back_buffer
+            .present(|buffer| self.draw(|ctx| ctx.blt32(buffer, Point::default())));
+    }
+
+    /// Waits for a key event.
     #[inline]
     pub fn wait_char(&self) -> char {
         core::char::from_u32(syscall::os_wait_char(self.handle.0)).unwrap_or('\0')
     }
 
+    /// Waits for a key event or a resize of the window.
+    #[inline]
+    pub fn wait_event(&self) -> Option<megos::WindowEvent> {
+        megos::WindowEvent::unpack(syscall::os_wait_event(self.handle.0))
+    }
+
     #[inline]
     pub fn read_char(&self) -> Option<char> {
         match syscall::os_read_char(self.handle.0) {
@@ -77,15 +116,17 @@ impl DrawingContext {
         self.ctx
     }
 
+    /// Draws the string wrapped in the window and returns the end of the last line,
+    /// so that the next string can be drawn from there.
     #[inline]
-    pub fn draw_string(&mut self, s: &str, origin: Point, color: WindowColor) {
-        syscall::os_win_draw_string(
+    pub fn draw_string(&mut self, s: &str, origin: Point, color: WindowColor) -> Point {
+        megos::unpack_point(syscall::os_win_draw_string(
             self.ctx,
             origin.x as usize,
             origin.y as usize,
             s,
             color.0 as usize,
-        );
+        ))
     }
 
     #[inline]
@@ -207,7 +248,10 @@ impl WindowBuilder {
             self.bg_color.0 as usize,
             self.options as usize,
         ));
-        Window { handle }
+        Window {
+            handle,
+            back_buffer: BackBuffer::new(),
+        }
     }
 
     /// Set window size
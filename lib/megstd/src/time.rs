@@ -1,7 +1,288 @@
 //
 
-#[derive(Debug, Copy, Clone)]
+use core::{
+    sync::atomic::{AtomicBool, AtomicU64, Ordering},
+    time::Duration,
+};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub struct SystemTime {
     pub secs: u64,
     pub nanos: u32,
 }
+
+impl SystemTime {
+    #[inline]
+    const fn into_duration(self) -> Duration {
+        Duration::new(self.secs, self.nanos)
+    }
+
+    #[inline]
+    const fn from_duration(duration: Duration) -> Self {
+        Self {
+            secs: duration.as_secs(),
+            nanos: duration.subsec_nanos(),
+        }
+    }
+
+    /// Returns the time after the duration, saturating at the maximum.
+    #[inline]
+    pub fn saturating_add(self, duration: Duration) -> Self {
+        Self::from_duration(self.into_duration().saturating_add(duration))
+    }
+
+    /// Returns the time before the duration, saturating at the epoch.
+    #[inline]
+    pub fn saturating_sub(self, duration: Duration) -> Self {
+        Self::from_duration(self.into_duration().saturating_sub(duration))
+    }
+}
+
+/// Returns the number of ticks of a counter running at `freq_khz` in the duration, rounded up.
+#[inline]
+pub fn ticks_for(duration: Duration, freq_khz: u64) -> u64 {
+    let ticks = duration
+        .as_nanos()
+        .saturating_mul(freq_khz as u128)
+        .div_ceil(1_000_000);
+    u64::try_from(ticks).unwrap_or(u64::MAX)
+}
+
+/// Busy-waits until the counter running at `freq_khz` advances for at least the duration.
+///
+/// The counter may wrap around.
+pub fn busy_wait<F, R>(duration: Duration, freq_khz: u64, mut counter: F, mut relax: R)
+where
+    F: FnMut() -> u64,
+    R: FnMut(),
+{
+    let ticks = ticks_for(duration, freq_khz);
+    let start = counter();
+    while counter().wrapping_sub(start) < ticks {
+        relax();
+    }
+}
+
+/// The deadline of a watchdog timer
+///
+/// The time is given by the caller in each call, as the elapsed time of a monotonic clock.
+#[derive(Debug)]
+pub struct WatchdogDeadline {
+    timeout: Duration,
+    /// In milliseconds, or [`Self::SUSPENDED`]
+    deadline: AtomicU64,
+    is_hung: AtomicBool,
+}
+
+impl WatchdogDeadline {
+    const SUSPENDED: u64 = u64::MAX;
+
+    #[inline]
+    pub fn new(timeout: Duration, now: Duration) -> Self {
+        Self {
+            timeout,
+            deadline: AtomicU64::new(Self::deadline_for(timeout, now)),
+            is_hung: AtomicBool::new(false),
+        }
+    }
+
+    #[inline]
+    fn deadline_for(timeout: Duration, now: Duration) -> u64 {
+        (now + timeout).as_millis().min(Self::SUSPENDED as u128 - 1) as u64
+    }
+
+    #[inline]
+    pub const fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    /// Resets the deadline, and resumes if suspended.
+    #[inline]
+    pub fn pet(&self, now: Duration) {
+        self.deadline
+            .store(Self::deadline_for(self.timeout, now), Ordering::SeqCst);
+        self.is_hung.store(false, Ordering::SeqCst);
+    }
+
+    /// Stops checking the deadline until the next [`Self::pet`].
+    #[inline]
+    pub fn suspend(&self) {
+        self.deadline.store(Self::SUSPENDED, Ordering::SeqCst);
+        self.is_hung.store(false, Ordering::SeqCst);
+    }
+
+    #[inline]
+    pub fn is_suspended(&self) -> bool {
+        self.deadline.load(Ordering::SeqCst) == Self::SUSPENDED
+    }
+
+    /// Returns whether the deadline has been missed since the last check or pet.
+    #[inline]
+    pub fn is_hung(&self) -> bool {
+        self.is_hung.load(Ordering::Relaxed)
+    }
+
+    /// Checks the deadline, and returns `true` only when it is newly missed.
+    pub fn check(&self, now: Duration) -> bool {
+        let deadline = self.deadline.load(Ordering::SeqCst);
+        if deadline == Self::SUSPENDED || (now.as_millis() as u64) < deadline {
+            return false;
+        }
+        !self.is_hung.swap(true, Ordering::SeqCst)
+    }
+}
+
+/// Deadlines on a fixed cadence
+///
+/// Each deadline is computed from the start time, so the delay in waking up does not accumulate.
+#[derive(Debug, Clone, Copy)]
+pub struct Cadence {
+    interval: Duration,
+    deadline: Duration,
+}
+
+impl Cadence {
+    #[inline]
+    pub fn new(interval: Duration, now: Duration) -> Self {
+        Self {
+            interval,
+            deadline: now + interval,
+        }
+    }
+
+    #[inline]
+    pub const fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    /// Returns the time of the next deadline.
+    #[inline]
+    pub const fn deadline(&self) -> Duration {
+        self.deadline
+    }
+
+    /// Moves on to the following deadline, and returns the time to wait for the current one.
+    ///
+    /// Returns `None` if the current deadline has already passed, so that a missed tick catches up.
+    pub fn advance(&mut self, now: Duration) -> Option<Duration> {
+        let deadline = self.deadline;
+        self.deadline += self.interval;
+        deadline.checked_sub(now).filter(|v| !v.is_zero())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::cell::Cell;
+
+    #[test]
+    fn ticks() {
+        assert_eq!(ticks_for(Duration::from_micros(1), 1_000_000), 1_000);
+        assert_eq!(ticks_for(Duration::from_millis(10), 3_000_000), 30_000_000);
+        // rounded up
+        assert_eq!(ticks_for(Duration::from_nanos(1), 1_000_000), 1);
+        assert_eq!(ticks_for(Duration::from_nanos(1), 1), 1);
+        assert_eq!(ticks_for(Duration::ZERO, 1_000_000), 0);
+        assert_eq!(ticks_for(Duration::MAX, u64::MAX), u64::MAX);
+    }
+
+    #[test]
+    fn busy_wait_mock_clock() {
+        // 1 GHz, which advances 7 ticks per read
+        let clock = Cell::new(0u64);
+        let counter = || {
+            let now = clock.get();
+            clock.set(now + 7);
+            now
+        };
+        let relaxed = Cell::new(0);
+        busy_wait(Duration::from_micros(1), 1_000_000, counter, || {
+            relaxed.set(relaxed.get() + 1)
+        });
+        // returns at the first read of 1001, and the next read would be 1008
+        assert_eq!(clock.get(), 1_008);
+        assert_eq!(relaxed.get(), 142);
+
+        // across the wrap around
+        let clock = Cell::new(u64::MAX - 10);
+        let counter = || {
+            let now = clock.get();
+            clock.set(now.wrapping_add(1));
+            now
+        };
+        busy_wait(Duration::from_nanos(20), 1_000_000, counter, || ());
+        // returns at the read of 9
+        assert_eq!(clock.get(), 10);
+    }
+
+    #[test]
+    fn watchdog_deadline() {
+        let secs = Duration::from_secs;
+        let watchdog = WatchdogDeadline::new(secs(10), secs(100));
+        assert!(!watchdog.check(secs(105)));
+        assert!(!watchdog.is_hung());
+
+        watchdog.pet(secs(105));
+        assert!(!watchdog.check(secs(114)));
+        assert!(watchdog.check(secs(115)));
+        assert!(watchdog.is_hung());
+        // reported once
+        assert!(!watchdog.check(secs(116)));
+
+        watchdog.pet(secs(120));
+        assert!(!watchdog.is_hung());
+        assert!(!watchdog.check(secs(125)));
+
+        // a long running child process does not count as hung
+        watchdog.suspend();
+        assert!(watchdog.is_suspended());
+        assert!(!watchdog.check(secs(1000)));
+        assert!(!watchdog.is_hung());
+
+        watchdog.pet(secs(1000));
+        assert!(!watchdog.is_suspended());
+        assert!(!watchdog.check(secs(1009)));
+        assert!(watchdog.check(secs(1010)));
+    }
+
+    #[test]
+    fn cadence() {
+        let millis = Duration::from_millis;
+        let mut cadence = Cadence::new(millis(10), millis(100));
+        assert_eq!(cadence.interval(), millis(10));
+        assert_eq!(cadence.deadline(), millis(110));
+
+        assert_eq!(cadence.advance(millis(103)), Some(millis(7)));
+        // waking up late does not shift the following deadlines
+        assert_eq!(cadence.deadline(), millis(120));
+        assert_eq!(cadence.advance(millis(112)), Some(millis(8)));
+
+        // the tick at 130 is missed, and the next one completes immediately
+        assert_eq!(cadence.advance(millis(135)), None);
+        assert_eq!(cadence.advance(millis(135)), Some(millis(5)));
+        // exactly at the deadline
+        assert_eq!(cadence.advance(millis(150)), None);
+        assert_eq!(cadence.deadline(), millis(160));
+    }
+
+    #[test]
+    fn system_time() {
+        let time = |secs, nanos| SystemTime { secs, nanos };
+        let base = time(1_000, 0);
+        assert_eq!(
+            base.saturating_add(Duration::from_millis(2_500)),
+            time(1_002, 500_000_000)
+        );
+        assert_eq!(
+            base.saturating_sub(Duration::from_millis(2_500)),
+            time(997, 500_000_000)
+        );
+        // before the epoch
+        assert_eq!(base.saturating_sub(Duration::from_secs(1_001)), time(0, 0));
+        assert_eq!(
+            time(u64::MAX, 0).saturating_add(Duration::from_secs(1)),
+            time(u64::MAX, 999_999_999)
+        );
+    }
+}
@@ -0,0 +1,86 @@
+//! User Identity
+
+use alloc::{borrow::ToOwned, format, string::String};
+
+/// User ID
+#[repr(transparent)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Uid(pub u32);
+
+impl Uid {
+    pub const ROOT: Self = Self(0);
+    pub const DEFAULT: Self = Self(1000);
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct User {
+    uid: Uid,
+    name: String,
+    home: String,
+}
+
+impl User {
+    #[inline]
+    pub fn new(uid: Uid, name: &str, home: &str) -> Self {
+        Self {
+            uid,
+            name: name.to_owned(),
+            home: home.to_owned(),
+        }
+    }
+
+    /// The user who logs in when no one else is specified
+    #[inline]
+    pub fn default_user() -> Self {
+        Self::new(Uid::DEFAULT, "user", "/home/user")
+    }
+
+    #[inline]
+    pub const fn uid(&self) -> Uid {
+        self.uid
+    }
+
+    #[inline]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the home directory.
+    #[inline]
+    pub fn home(&self) -> &str {
+        &self.home
+    }
+
+    /// Replaces the leading `~` of the path with the home directory of this user.
+    pub fn expand_tilde(&self, path: &str) -> String {
+        match path.strip_prefix('~') {
+            Some(rest) if rest.is_empty() || rest.starts_with('/') => {
+                format!("{}{}", self.home, rest)
+            }
+            _ => path.to_owned(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expand_tilde() {
+        let user = User::default_user();
+        assert_eq!(user.uid(), Uid::DEFAULT);
+        assert_eq!(user.name(), "user");
+        assert_eq!(user.expand_tilde("~"), "/home/user");
+        assert_eq!(user.expand_tilde("~/docs/a.txt"), "/home/user/docs/a.txt");
+        // other users' homes and tildes elsewhere are left as is
+        assert_eq!(user.expand_tilde("~root"), "~root");
+        assert_eq!(user.expand_tilde("/tmp/~"), "/tmp/~");
+        assert_eq!(user.expand_tilde(""), "");
+
+        // switching the user changes the home
+        let root = User::new(Uid::ROOT, "root", "/root");
+        assert_eq!(root.home(), "/root");
+        assert_eq!(root.expand_tilde("~/bin"), "/root/bin");
+    }
+}
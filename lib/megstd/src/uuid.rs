@@ -1,4 +1,5 @@
-use core::{fmt::*, mem::transmute};
+use alloc::{collections::BTreeMap, sync::Arc};
+use core::{any::Any, fmt::*, mem::transmute};
 use num_derive::FromPrimitive;
 use num_traits::FromPrimitive;
 
@@ -109,7 +110,7 @@ impl Uuid {
 impl PartialEq for Uuid {
     #[inline]
     fn eq(&self, other: &Self) -> bool {
-        unsafe { *self.as_u128() == *other.as_u128() }
+        self.0 == other.0
     }
 }
 
@@ -158,6 +159,47 @@ pub unsafe trait Identify {
     const UUID: Uuid;
 }
 
+/// Registry of objects looked up by the UUID of their types at runtime
+#[derive(Default)]
+pub struct IdentifyRegistry {
+    entries: BTreeMap<Uuid, Arc<dyn Any + Send + Sync>>,
+}
+
+impl IdentifyRegistry {
+    #[inline]
+    pub const fn new() -> Self {
+        Self {
+            entries: BTreeMap::new(),
+        }
+    }
+
+    /// Registers the object and returns the one previously registered with the same UUID.
+    #[inline]
+    pub fn register<T: Identify + Any + Send + Sync>(
+        &mut self,
+        object: Arc<T>,
+    ) -> Option<Arc<dyn Any + Send + Sync>> {
+        self.entries.insert(T::UUID, object)
+    }
+
+    #[inline]
+    pub fn unregister(&mut self, uuid: &Uuid) -> Option<Arc<dyn Any + Send + Sync>> {
+        self.entries.remove(uuid)
+    }
+
+    /// Returns the object registered with the UUID.
+    #[inline]
+    pub fn lookup(&self, uuid: &Uuid) -> Option<Arc<dyn Any + Send + Sync>> {
+        self.entries.get(uuid).cloned()
+    }
+
+    /// Returns the object registered with the UUID of the type.
+    #[inline]
+    pub fn get<T: Identify + Any + Send + Sync>(&self) -> Option<Arc<T>> {
+        self.lookup(&T::UUID).and_then(|v| v.downcast().ok())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -203,4 +245,30 @@ mod tests {
         assert_eq!(uuid2.d(), 0x8899);
         assert_eq!(uuid2.e_u48(), 0xAABB_CCDD_EEFF);
     }
+
+    #[test]
+    fn registry() {
+        struct Foo(u32);
+        unsafe impl Identify for Foo {
+            const UUID: Uuid = Uuid::from_parts(0x1234_5678, 0x9ABC, 0xDEF0, 0xFEDC, [0xBA; 6]);
+        }
+
+        struct Bar(&'static str);
+        unsafe impl Identify for Bar {
+            const UUID: Uuid = Uuid::from_parts(0x0011_2233, 0x4455, 0x6677, 0x8899, [0xAA; 6]);
+        }
+
+        let mut registry = IdentifyRegistry::new();
+        assert!(registry.register(Arc::new(Foo(42))).is_none());
+        assert!(registry.register(Arc::new(Bar("bar"))).is_none());
+
+        let foo = registry.lookup(&Foo::UUID).unwrap();
+        assert_eq!(foo.downcast_ref::<Foo>().unwrap().0, 42);
+        assert!(foo.downcast_ref::<Bar>().is_none());
+        assert_eq!(registry.get::<Bar>().unwrap().0, "bar");
+
+        assert!(registry.lookup(&Uuid::NULL).is_none());
+        assert!(registry.unregister(&Foo::UUID).is_some());
+        assert!(registry.get::<Foo>().is_none());
+    }
 }
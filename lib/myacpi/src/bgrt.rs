@@ -6,7 +6,7 @@ pub struct Bgrt {
     hdr: AcpiHeader,
     version: u16,
     status: u8,
-    image_type: ImageType,
+    image_type: u8,
     image_address: u64,
     offset_x: u32,
     offset_y: u32,
@@ -17,11 +17,22 @@ unsafe impl AcpiTable for Bgrt {
 }
 
 impl Bgrt {
+    /// Returns the type of the image, or `None` if it is unknown.
     #[inline]
-    pub fn image_type(&self) -> ImageType {
-        self.image_type
+    pub const fn image_type(&self) -> Option<ImageType> {
+        match self.image_type {
+            0 => Some(ImageType::Bitmap),
+            _ => None,
+        }
     }
 
+    /// Returns whether the image is currently displayed on the screen.
+    #[inline]
+    pub const fn is_displayed(&self) -> bool {
+        (self.status & 0b001) != 0
+    }
+
+    /// Returns the clockwise rotation of the image in degrees.
     #[inline]
     pub const fn orientation_offset(&self) -> usize {
         match (self.status & 0b110) >> 1 {
@@ -42,6 +53,19 @@ impl Bgrt {
     pub const fn offset(&self) -> (usize, usize) {
         (self.offset_x as usize, self.offset_y as usize)
     }
+
+    /// Returns the location of the boot logo, if the image is a BMP.
+    pub const fn boot_logo(&self) -> Option<BootLogo> {
+        match self.image_type() {
+            Some(ImageType::Bitmap) => Some(BootLogo {
+                image_address: self.image_address,
+                offset: self.offset(),
+                orientation: self.orientation_offset(),
+                is_displayed: self.is_displayed(),
+            }),
+            _ => None,
+        }
+    }
 }
 
 #[repr(u8)]
@@ -50,3 +74,63 @@ impl Bgrt {
 pub enum ImageType {
     Bitmap = 0,
 }
+
+/// The boot logo described by [`Bgrt`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BootLogo {
+    /// Physical address of the BMP image
+    pub image_address: u64,
+    /// Placement of the upper left corner of the image on the screen
+    pub offset: (usize, usize),
+    /// Clockwise rotation of the image in degrees
+    pub orientation: usize,
+    /// Whether the image is currently displayed
+    pub is_displayed: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::mem::size_of;
+
+    fn table(status: u8, image_type: u8) -> [u8; size_of::<Bgrt>()] {
+        let mut bytes = [0u8; size_of::<Bgrt>()];
+        bytes[0..4].copy_from_slice(&TableId::BGRT.0);
+        bytes[4..8].copy_from_slice(&(size_of::<Bgrt>() as u32).to_le_bytes());
+        bytes[36..38].copy_from_slice(&1u16.to_le_bytes());
+        bytes[38] = status;
+        bytes[39] = image_type;
+        bytes[40..48].copy_from_slice(&0x7E00_0000u64.to_le_bytes());
+        bytes[48..52].copy_from_slice(&320u32.to_le_bytes());
+        bytes[52..56].copy_from_slice(&240u32.to_le_bytes());
+        bytes
+    }
+
+    fn bgrt(bytes: &[u8]) -> &Bgrt {
+        let header = unsafe { &*(bytes.as_ptr() as *const AcpiHeader) };
+        header.assume::<Bgrt>().unwrap()
+    }
+
+    #[test]
+    fn boot_logo() {
+        let bytes = table(0b011, 0);
+        assert_eq!(
+            bgrt(&bytes).boot_logo(),
+            Some(BootLogo {
+                image_address: 0x7E00_0000,
+                offset: (320, 240),
+                orientation: 90,
+                is_displayed: true,
+            })
+        );
+
+        let bytes = table(0b100, 0);
+        let logo = bgrt(&bytes).boot_logo().unwrap();
+        assert_eq!(logo.orientation, 180);
+        assert!(!logo.is_displayed);
+
+        let bytes = table(0b001, 1);
+        assert_eq!(bgrt(&bytes).image_type(), None);
+        assert_eq!(bgrt(&bytes).boot_logo(), None);
+    }
+}
@@ -1,4 +1,4 @@
-use super::*;
+use super::{fadt::SleepType, *};
 
 /// Differentiated System Description Table
 #[repr(C, packed)]
@@ -10,6 +10,67 @@ unsafe impl AcpiTable for Dsdt {
     const TABLE_ID: TableId = TableId::DSDT;
 }
 
+impl Dsdt {
+    /// Returns the sleep type defined by the `\_Sx` object for the sleeping state.
+    #[inline]
+    pub fn sleep_type(&self, state: u8) -> Option<SleepType> {
+        find_sleep_type(unsafe { self.header().data() }, state)
+    }
+}
+
+/// Finds the `\_Sx` package in the AML and returns its first two elements.
+///
+/// This is not a full AML interpreter, it only recognizes the common form
+/// `Name (_Sx, Package () { SLP_TYPa, SLP_TYPb, ... })`.
+pub fn find_sleep_type(aml: &[u8], state: u8) -> Option<SleepType> {
+    const NAME_OP: u8 = 0x08;
+    const ROOT_CHAR: u8 = b'\\';
+    const PACKAGE_OP: u8 = 0x12;
+
+    if state > 5 {
+        return None;
+    }
+    let name = [b'_', b'S', b'0' + state, b'_'];
+    let mut offset = 0;
+    while let Some(position) = aml[offset..].windows(name.len()).position(|v| v == name) {
+        let position = offset + position;
+        offset = position + name.len();
+
+        let prefix = &aml[..position];
+        let is_name = matches!(prefix, [.., NAME_OP] | [.., NAME_OP, ROOT_CHAR]);
+        if !is_name || aml.get(offset) != Some(&PACKAGE_OP) {
+            continue;
+        }
+
+        // PackageOp PkgLength NumElements PackageElementList
+        let pkg_length = aml.get(offset + 1)?;
+        let mut elements = aml.get(offset + 3 + (*pkg_length as usize >> 6)..)?;
+        let slp_typa = read_byte_data(&mut elements)?;
+        let slp_typb = read_byte_data(&mut elements)?;
+        return Some(SleepType::new(slp_typa, slp_typb));
+    }
+    None
+}
+
+fn read_byte_data(aml: &mut &[u8]) -> Option<u8> {
+    const ZERO_OP: u8 = 0x00;
+    const ONE_OP: u8 = 0x01;
+    const BYTE_PREFIX: u8 = 0x0A;
+    const WORD_PREFIX: u8 = 0x0B;
+    const DWORD_PREFIX: u8 = 0x0C;
+
+    let (value, len) = match *aml.first()? {
+        ZERO_OP => (0, 1),
+        ONE_OP => (1, 1),
+        BYTE_PREFIX => (*aml.get(1)?, 2),
+        WORD_PREFIX => (*aml.get(1)?, 3),
+        DWORD_PREFIX => (*aml.get(1)?, 5),
+        _ => return None,
+    };
+    *aml = aml.get(len..)?;
+    Some(value)
+}
+
 /// Secondary System Descriptor Table
 #[repr(C, packed)]
 pub struct Ssdt {
@@ -19,3 +80,21 @@ pub struct Ssdt {
 unsafe impl AcpiTable for Ssdt {
     const TABLE_ID: TableId = TableId::SSDT;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sleep_type() {
+        // Name (\_S3, Package (0x04) { One, One, Zero, Zero })
+        // Name (_S5, Package (0x04) { 0x07, Zero, Zero, Zero })
+        let aml = [
+            0x10, 0x20, 0x08, 0x5C, b'_', b'S', b'3', b'_', 0x12, 0x06, 0x04, 0x01, 0x01, 0x00,
+            0x00, 0x08, b'_', b'S', b'5', b'_', 0x12, 0x07, 0x04, 0x0A, 0x07, 0x00, 0x00, 0x00,
+        ];
+        assert_eq!(find_sleep_type(&aml, 3), Some(SleepType::new(1, 1)));
+        assert_eq!(find_sleep_type(&aml, 5), Some(SleepType::new(7, 0)));
+        assert_eq!(find_sleep_type(&aml, 4), None);
+    }
+}
@@ -1,4 +1,5 @@
-use super::*;
+use super::{dsdt::Dsdt, *};
+use core::mem::{offset_of, size_of};
 
 /// Fixed ACPI Description Table
 #[repr(C, packed)]
@@ -64,15 +65,37 @@ pub struct Fadt {
 
 unsafe impl AcpiTable for Fadt {
     const TABLE_ID: TableId = TableId::FADT;
+
+    /// ACPI 1.0 ends at `flags`, `reset_reg` and later fields are revision dependent.
+    const MIN_LEN: usize = offset_of!(Fadt, reset_reg);
 }
 
 impl Fadt {
+    /// SLP_EN bit in the PM1 control registers
+    pub const PM1_CNT_SLP_EN: u16 = 1 << 13;
+    /// SLP_TYP field in the PM1 control registers
+    pub const PM1_CNT_SLP_TYP: u16 = 7 << Self::PM1_CNT_SLP_TYP_SHIFT;
+    const PM1_CNT_SLP_TYP_SHIFT: usize = 10;
+    /// TMR_VAL_EXT flag, the PM timer is 32-bit instead of 24-bit
+    pub const FLAG_TMR_VAL_EXT: u32 = 1 << 8;
+
+    /// Reads the field at `offset` if this revision of the table contains it.
     #[inline]
-    fn _blk(gas: UncheckedGas, val: u64) -> Option<Gas> {
-        if gas.is_empty() {
-            unsafe { UncheckedGas::from_u64(val) }
-        } else {
-            gas
+    fn _get<T: Copy>(&self, offset: usize, f: impl FnOnce(&Self) -> T) -> Option<T> {
+        (self.hdr.len() >= offset + size_of::<T>()).then(|| f(self))
+    }
+
+    /// Returns the extended block if present, or the legacy block in the I/O space.
+    #[inline]
+    fn _blk(gas: Option<UncheckedGas>, val: u64) -> Option<Gas> {
+        match gas {
+            Some(gas) if !gas.is_empty() => gas,
+            _ => {
+                if val == 0 {
+                    return None;
+                }
+                UncheckedGas::from_io_port(val)
+            }
         }
         .checked()
     }
@@ -98,12 +121,24 @@ impl Fadt {
 
     #[inline]
     pub fn reset(&self) -> Option<(Gas, u8)> {
-        self.reset_reg.checked().map(|v| (v, self.reset_value))
+        let reset_value = self._get(offset_of!(Self, reset_value), |v| v.reset_value)?;
+        self.reset_reg.checked().map(|v| (v, reset_value))
     }
 
     #[inline]
     pub fn dsdt(&self) -> u64 {
-        Self::_x_value(self.x_dsdt, self.dsdt)
+        let x_dsdt = self._get(offset_of!(Self, x_dsdt), |v| v.x_dsdt);
+        Self::_x_value(x_dsdt.unwrap_or_default(), self.dsdt)
+    }
+
+    /// Returns the DSDT, assuming the physical address is identity mapped like the XSDT.
+    #[inline]
+    pub fn dsdt_table(&self) -> Option<&Dsdt> {
+        let dsdt = self.dsdt() as usize;
+        if dsdt == 0 {
+            return None;
+        }
+        unsafe { &*(dsdt as *const AcpiHeader) }.assume()
     }
 
     #[inline]
@@ -123,32 +158,50 @@ impl Fadt {
 
     #[inline]
     pub fn pm1a_cnt_blk(&self) -> Option<Gas> {
-        Self::_blk(self.x_pm1a_cnt_blk, self.pm1a_cnt_blk as u64)
+        Self::_blk(
+            self._get(offset_of!(Self, x_pm1a_cnt_blk), |v| v.x_pm1a_cnt_blk),
+            self.pm1a_cnt_blk as u64,
+        )
     }
 
     #[inline]
     pub fn pm1b_cnt_blk(&self) -> Option<Gas> {
-        Self::_blk(self.x_pm1b_cnt_blk, self.pm1b_cnt_blk as u64)
+        Self::_blk(
+            self._get(offset_of!(Self, x_pm1b_cnt_blk), |v| v.x_pm1b_cnt_blk),
+            self.pm1b_cnt_blk as u64,
+        )
     }
 
     #[inline]
     pub fn pm1a_evt_blk(&self) -> Option<Gas> {
-        Self::_blk(self.x_pm1a_evt_blk, self.pm1a_evt_blk as u64)
+        Self::_blk(
+            self._get(offset_of!(Self, x_pm1a_evt_blk), |v| v.x_pm1a_evt_blk),
+            self.pm1a_evt_blk as u64,
+        )
     }
 
     #[inline]
     pub fn pm1b_evt_blk(&self) -> Option<Gas> {
-        Self::_blk(self.x_pm1b_evt_blk, self.pm1b_evt_blk as u64)
+        Self::_blk(
+            self._get(offset_of!(Self, x_pm1b_evt_blk), |v| v.x_pm1b_evt_blk),
+            self.pm1b_evt_blk as u64,
+        )
     }
 
     #[inline]
     pub fn pm2_cnt_blk(&self) -> Option<Gas> {
-        Self::_blk(self.x_pm2_cnt_blk, self.pm2_cnt_blk as u64)
+        Self::_blk(
+            self._get(offset_of!(Self, x_pm2_cnt_blk), |v| v.x_pm2_cnt_blk),
+            self.pm2_cnt_blk as u64,
+        )
     }
 
     #[inline]
     pub fn pm_tmr_blk(&self) -> Option<Gas> {
-        Self::_blk(self.x_pm_tmr_blk, self.pm_tmr_blk as u64)
+        Self::_blk(
+            self._get(offset_of!(Self, x_pm_tmr_blk), |v| v.x_pm_tmr_blk),
+            self.pm_tmr_blk as u64,
+        )
     }
 
     #[inline]
@@ -156,14 +209,32 @@ impl Fadt {
         self.pm_tmr_len as usize
     }
 
+    /// Returns the PM timer, if supported.
+    #[inline]
+    pub fn pm_timer(&self) -> Option<PmTimer> {
+        if self.pm_tmr_len() != 4 {
+            return None;
+        }
+        self.pm_tmr_blk().map(|blk| PmTimer {
+            blk,
+            is_32bit: (self.flags & Self::FLAG_TMR_VAL_EXT) != 0,
+        })
+    }
+
     #[inline]
     pub fn gpe0_blk(&self) -> Option<Gas> {
-        Self::_blk(self.x_gpe0_blk, self.gpe0_blk as u64)
+        Self::_blk(
+            self._get(offset_of!(Self, x_gpe0_blk), |v| v.x_gpe0_blk),
+            self.gpe0_blk as u64,
+        )
     }
 
     #[inline]
     pub fn gpe1_blk(&self) -> Option<Gas> {
-        Self::_blk(self.x_gpe1_blk, self.gpe1_blk as u64)
+        Self::_blk(
+            self._get(offset_of!(Self, x_gpe1_blk), |v| v.x_gpe1_blk),
+            self.gpe1_blk as u64,
+        )
     }
 
     #[inline]
@@ -178,11 +249,226 @@ impl Fadt {
 
     #[inline]
     pub fn sleep_control_reg(&self) -> Option<Gas> {
-        self.sleep_control_reg.checked()
+        self._get(offset_of!(Self, sleep_control_reg), |v| v.sleep_control_reg)?
+            .checked()
     }
 
     #[inline]
     pub fn sleep_status_reg(&self) -> Option<Gas> {
-        self.sleep_status_reg.checked()
+        self._get(offset_of!(Self, sleep_status_reg), |v| v.sleep_status_reg)?
+            .checked()
+    }
+
+    /// Returns the value to be written to the PM1 control register to enter the sleeping state.
+    #[inline]
+    pub const fn pm1_cnt_sleep_value(slp_typ: u8) -> u16 {
+        (((slp_typ as u16) << Self::PM1_CNT_SLP_TYP_SHIFT) & Self::PM1_CNT_SLP_TYP)
+            | Self::PM1_CNT_SLP_EN
+    }
+
+    /// Enters the sleeping state by writing SLP_TYPx and SLP_EN to the PM1 control registers.
+    ///
+    /// `write` performs the actual register access, it should preserve the bits other than
+    /// [`Fadt::PM1_CNT_SLP_TYP`] and [`Fadt::PM1_CNT_SLP_EN`].
+    pub fn enter_sleep_state<F>(&self, sleep_type: SleepType, mut write: F) -> Result<(), ()>
+    where
+        F: FnMut(Gas, u16),
+    {
+        let pm1a = self.pm1a_cnt_blk().ok_or(())?;
+        let pm1b = self.pm1b_cnt_blk();
+        write(pm1a, Self::pm1_cnt_sleep_value(sleep_type.slp_typa));
+        if let Some(pm1b) = pm1b {
+            write(pm1b, Self::pm1_cnt_sleep_value(sleep_type.slp_typb));
+        }
+        Ok(())
+    }
+}
+
+/// The values of SLP_TYPa and SLP_TYPb for a sleeping state, defined by the `\_Sx` objects
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SleepType {
+    pub slp_typa: u8,
+    pub slp_typb: u8,
+}
+
+impl SleepType {
+    #[inline]
+    pub const fn new(slp_typa: u8, slp_typb: u8) -> Self {
+        Self { slp_typa, slp_typb }
+    }
+}
+
+/// ACPI Power Management Timer
+#[derive(Debug, Clone, Copy)]
+pub struct PmTimer {
+    blk: Gas,
+    is_32bit: bool,
+}
+
+impl PmTimer {
+    /// Frequency of the PM timer in Hz
+    pub const FREQUENCY: u64 = 3_579_545;
+
+    /// Returns the register of the counter.
+    #[inline]
+    pub const fn blk(&self) -> Gas {
+        self.blk
+    }
+
+    /// Returns whether the counter is 32-bit instead of 24-bit.
+    #[inline]
+    pub const fn is_32bit(&self) -> bool {
+        self.is_32bit
+    }
+
+    /// Returns the mask of the valid bits of the counter.
+    #[inline]
+    pub const fn mask(&self) -> u32 {
+        if self.is_32bit {
+            u32::MAX
+        } else {
+            0x00FF_FFFF
+        }
+    }
+
+    /// Reads the counter.
+    ///
+    /// `read` performs the actual 32-bit register access,
+    /// the bits beyond the width of the counter are masked.
+    #[inline]
+    pub fn read<F>(&self, read: F) -> u32
+    where
+        F: FnOnce(Gas) -> u32,
+    {
+        read(self.blk) & self.mask()
+    }
+
+    /// Returns the number of ticks from `from` to `to`, taking a wrap around into account.
+    #[inline]
+    pub const fn ticks_between(&self, from: u32, to: u32) -> u32 {
+        to.wrapping_sub(from) & self.mask()
+    }
+
+    /// Extends the counter to 64 bits from the last extended value and the current counter.
+    ///
+    /// The counter must be read at least once before it wraps around,
+    /// about 4.7 seconds for the 24-bit counter.
+    #[inline]
+    pub const fn extend(&self, last: u64, now: u32) -> u64 {
+        last + self.ticks_between(last as u32 & self.mask(), now) as u64
+    }
+
+    /// Converts the ticks into microseconds.
+    #[inline]
+    pub const fn ticks_to_micros(ticks: u64) -> u64 {
+        (ticks as u128 * 1_000_000 / Self::FREQUENCY as u128) as u64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn enter_sleep_state() {
+        let mut fadt: Fadt = unsafe { core::mem::zeroed() };
+        fadt.pm1a_cnt_blk = 0x0604;
+
+        let mut writes = [(0u64, 0u16); 2];
+        let mut n_writes = 0;
+        fadt.enter_sleep_state(SleepType::new(5, 0), |gas, value| {
+            writes[n_writes] = (gas.address, value);
+            n_writes += 1;
+        })
+        .unwrap();
+
+        assert_eq!(n_writes, 1);
+        assert_eq!(writes[0], (0x0604, (5 << 10) | (1 << 13)));
+        assert_ne!(writes[0].1 & Fadt::PM1_CNT_SLP_EN, 0);
+        assert_eq!(fadt.pm1a_cnt_blk().unwrap().id, GasAddressSpaceId::SystemIo);
+
+        let fadt: Fadt = unsafe { core::mem::zeroed() };
+        assert!(fadt
+            .enter_sleep_state(SleepType::new(5, 0), |_, _| unreachable!())
+            .is_err());
+    }
+
+    #[test]
+    fn pm_timer() {
+        let mut fadt: Fadt = unsafe { core::mem::zeroed() };
+        fadt.pm_tmr_blk = 0x0608;
+        assert!(fadt.pm_timer().is_none());
+
+        fadt.pm_tmr_len = 4;
+        let timer = fadt.pm_timer().unwrap();
+        assert!(!timer.is_32bit());
+        assert_eq!({ timer.blk().address }, 0x0608);
+        assert_eq!(timer.blk().id, GasAddressSpaceId::SystemIo);
+        assert_eq!(timer.read(|_| 0xFF12_3456), 0x0012_3456);
+        assert_eq!(timer.ticks_between(0x00FF_FFF0, 0x0000_0010), 0x20);
+        assert_eq!(timer.extend(0x00FF_FFF0, 0x0000_0010), 0x0100_0010);
+        assert_eq!(timer.extend(0x0100_0010, 0x0000_0020), 0x0100_0020);
+
+        fadt.flags = Fadt::FLAG_TMR_VAL_EXT;
+        let timer = fadt.pm_timer().unwrap();
+        assert!(timer.is_32bit());
+        assert_eq!(timer.read(|_| 0xFF12_3456), 0xFF12_3456);
+        assert_eq!(timer.ticks_between(0xFFFF_FFF0, 0x0000_0010), 0x20);
+        assert_eq!(
+            timer.extend(0x0000_0001_FFFF_FFF0, 0x10),
+            0x0000_0002_0000_0010
+        );
+
+        assert_eq!(PmTimer::ticks_to_micros(PmTimer::FREQUENCY), 1_000_000);
+        assert_eq!(PmTimer::ticks_to_micros(3_580), 1_000);
+    }
+
+    #[test]
+    fn revision3_fadt() {
+        #[repr(C, align(8))]
+        struct Buffer([u8; 276]);
+
+        // QEMU provides a revision 3 FADT of 244 bytes without the sleep registers
+        let mut buffer = Buffer([0xFF; 276]);
+        let table = &mut buffer.0;
+        table[..36].fill(0);
+        table[..4].copy_from_slice(b"FACP");
+        table[4..8].copy_from_slice(&244u32.to_le_bytes());
+        table[8] = 3;
+        table[36..244].fill(0);
+        table[64..68].copy_from_slice(&0x0604u32.to_le_bytes());
+        table[76..80].copy_from_slice(&0x0608u32.to_le_bytes());
+        table[91] = 4;
+        table[116] = GasAddressSpaceId::SystemIo as u8;
+        table[120..128].copy_from_slice(&0x0cf9u64.to_le_bytes());
+        table[128] = 0x06;
+        table[140..148].copy_from_slice(&0x1234_0000u64.to_le_bytes());
+
+        let header = unsafe { &*(table.as_ptr() as *const AcpiHeader) };
+        let fadt = header.assume::<Fadt>().unwrap();
+        assert_eq!(fadt.header().len(), 244);
+        assert_eq!({ fadt.pm1a_cnt_blk().unwrap().address }, 0x0604);
+        assert!(fadt.pm1b_cnt_blk().is_none());
+        assert_eq!({ fadt.pm_timer().unwrap().blk().address }, 0x0608);
+        assert_eq!(
+            fadt.reset().map(|(gas, value)| (gas.address, value)),
+            Some((0x0cf9, 6))
+        );
+        assert_eq!(fadt.dsdt(), 0x1234_0000);
+        // the bytes beyond the table are not part of it
+        assert!(fadt.sleep_control_reg().is_none());
+        assert!(fadt.sleep_status_reg().is_none());
+
+        // ACPI 1.0 has neither the reset register nor the extended addresses
+        table[4..8].copy_from_slice(&116u32.to_le_bytes());
+        let header = unsafe { &*(table.as_ptr() as *const AcpiHeader) };
+        let fadt = header.assume::<Fadt>().unwrap();
+        assert!(fadt.reset().is_none());
+        assert_eq!({ fadt.pm1a_cnt_blk().unwrap().address }, 0x0604);
+
+        // shorter than ACPI 1.0
+        table[4..8].copy_from_slice(&112u32.to_le_bytes());
+        let header = unsafe { &*(table.as_ptr() as *const AcpiHeader) };
+        assert!(header.assume::<Fadt>().is_none());
     }
 }
@@ -1,5 +1,5 @@
 //! Advanced Configuration and Power Interface (ACPI)
-#![no_std]
+#![cfg_attr(not(test), no_std)]
 #![deny(unsafe_op_in_unsafe_fn)]
 
 mod tables;
@@ -1,5 +1,6 @@
 use super::*;
-use core::mem::transmute;
+use core::{mem::transmute, slice};
+use megstd::mem::try_from_bytes;
 
 /// Multiple APIC Description Table
 #[repr(C, packed)]
@@ -35,7 +36,7 @@ impl Madt {
     }
 
     #[inline]
-    pub fn entries<T: RawEntry>(&self) -> impl Iterator<Item = &T> {
+    pub fn entries<'a, T: RawEntry + 'a>(&'a self) -> impl Iterator<Item = &'a T> {
         self.raw_entries().filter_map(|v| v.assume())
     }
 
@@ -100,7 +101,11 @@ impl EntryHeader {
 
     #[inline]
     pub fn assume<T: RawEntry + Sized>(&self) -> Option<&T> {
-        (self.entry_type() == T::ENTRY_TYPE).then(|| unsafe { transmute(self) })
+        if self.entry_type() != T::ENTRY_TYPE {
+            return None;
+        }
+        let bytes = unsafe { slice::from_raw_parts(self as *const _ as *const u8, self.len()) };
+        unsafe { try_from_bytes(bytes) }
     }
 }
 
@@ -1,11 +1,6 @@
 use crate::fadt::Fadt;
-use core::{
-    ffi::c_void,
-    fmt::Display,
-    mem::{size_of, transmute},
-    slice,
-    str::from_utf8_unchecked,
-};
+use core::{ffi::c_void, fmt::Display, mem::size_of, slice, str::from_utf8_unchecked};
+use megstd::mem::try_from_bytes;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub struct TableId(pub [u8; 4]);
@@ -71,9 +66,16 @@ impl AcpiHeader {
         self.len as usize
     }
 
+    /// Returns the table as `T` if the signature matches and the table is long enough.
+    ///
+    /// The fields of `T` beyond [`AcpiTable::MIN_LEN`] may be absent in older revisions,
+    /// they must be checked against [`AcpiHeader::len`] before reading.
     #[inline]
     pub fn assume<T: AcpiTable>(&self) -> Option<&T> {
-        (self.signature() == T::TABLE_ID).then(|| unsafe { transmute(self) })
+        if self.signature() != T::TABLE_ID || self.len() < T::MIN_LEN.max(size_of::<Self>()) {
+            return None;
+        }
+        Some(unsafe { &*(self as *const _ as *const T) })
     }
 
     #[inline]
@@ -87,9 +89,14 @@ impl AcpiHeader {
 pub unsafe trait AcpiTable: Sized {
     const TABLE_ID: TableId;
 
+    /// The length of the oldest revision of the table, later revisions may append fields.
+    const MIN_LEN: usize = size_of::<Self>();
+
     #[inline]
     fn header(&self) -> &AcpiHeader {
-        unsafe { transmute(self) }
+        let bytes =
+            unsafe { slice::from_raw_parts(self as *const _ as *const u8, size_of::<Self>()) };
+        unsafe { try_from_bytes(bytes) }.unwrap()
     }
 }
 
@@ -128,17 +135,6 @@ pub(crate) struct UncheckedGas {
 }
 
 impl UncheckedGas {
-    #[inline]
-    pub unsafe fn from_u64(address: u64) -> Self {
-        Self {
-            id: GasAddressSpaceId::SystemMemory,
-            bit_width: 0,
-            bit_offset: 0,
-            access_size: GasAccessSize::Undefined,
-            address,
-        }
-    }
-
     #[inline]
     pub fn is_empty(&self) -> bool {
         matches!(self.id, GasAddressSpaceId::SystemMemory)
@@ -148,9 +144,26 @@ impl UncheckedGas {
             && self.address == 0
     }
 
+    #[inline]
+    pub fn from_io_port(port: u64) -> Self {
+        Self {
+            id: GasAddressSpaceId::SystemIo,
+            bit_width: 0,
+            bit_offset: 0,
+            access_size: GasAccessSize::Undefined,
+            address: port,
+        }
+    }
+
     #[inline]
     pub fn checked(&self) -> Option<Gas> {
-        self.is_empty().then(|| unsafe { transmute(*self) })
+        (!self.is_empty()).then_some(Gas {
+            id: self.id,
+            bit_width: self.bit_width,
+            bit_offset: self.bit_offset,
+            access_size: self.access_size,
+            address: self.address,
+        })
     }
 }
 
@@ -205,6 +218,8 @@ pub struct Xsdt {
 
 unsafe impl AcpiTable for Xsdt {
     const TABLE_ID: TableId = TableId::XSDT;
+
+    const MIN_LEN: usize = size_of::<AcpiHeader>();
 }
 
 impl Xsdt {
@@ -222,7 +237,7 @@ impl Xsdt {
     }
 
     #[inline]
-    pub fn find<T: AcpiTable>(&self) -> impl Iterator<Item = &T> {
+    pub fn find<'a, T: AcpiTable + 'a>(&'a self) -> impl Iterator<Item = &'a T> {
         self.tables().map(|v| v.assume()).filter_map(|v| v)
     }
 
@@ -259,3 +274,27 @@ impl<'a> Iterator for XsdtTables<'a> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn assume_table() {
+        let mut table = [0u8; 48];
+        table[..4].copy_from_slice(b"APIC");
+        table[4..8].copy_from_slice(&44u32.to_le_bytes());
+        table[36..40].copy_from_slice(&0xfee0_0000u32.to_le_bytes());
+
+        let header = unsafe { &*(table.as_ptr() as *const AcpiHeader) };
+        assert!(header.assume::<Fadt>().is_none());
+        let madt = header.assume::<crate::madt::Madt>().unwrap();
+        assert_eq!(madt.local_apic_address(), 0xfee0_0000);
+        assert_eq!(madt.header().len(), 44);
+
+        // shorter than the table itself
+        table[4..8].copy_from_slice(&40u32.to_le_bytes());
+        let header = unsafe { &*(table.as_ptr() as *const AcpiHeader) };
+        assert!(header.assume::<crate::madt::Madt>().is_none());
+    }
+}
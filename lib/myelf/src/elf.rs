@@ -163,6 +163,24 @@ pub const PT_HIPROC: SegmentType = SegmentType(0x7FFF_FFFF);
 
 pub const PT_AARCH64_MEMTAG_MTE: SegmentType = SegmentType(0x7000_0002);
 
+//
+// These constants are for the tags of the dynamic section
+//
+pub const DT_NULL: i64 = 0;
+pub const DT_RELA: i64 = 7;
+pub const DT_RELASZ: i64 = 8;
+pub const DT_RELAENT: i64 = 9;
+
+//
+// These constants are for the relocation types
+//
+pub const R_X86_64_NONE: u32 = 0;
+pub const R_X86_64_RELATIVE: u32 = 8;
+pub const R_AARCH64_NONE: u32 = 0;
+pub const R_AARCH64_RELATIVE: u32 = 1027;
+pub const R_RISCV_NONE: u32 = 0;
+pub const R_RISCV_RELATIVE: u32 = 3;
+
 //
 // These constants define the different elf file types
 //
@@ -274,4 +292,54 @@ pub mod elf64 {
         pub p_memsz: ElfXWord,
         pub p_align: ElfXWord,
     }
+
+    #[repr(C)]
+    #[derive(Debug, Clone, Copy)]
+    pub struct Dyn {
+        pub d_tag: i64,
+        pub d_val: ElfXWord,
+    }
+
+    impl Dyn {
+        pub const SIZE: usize = 16;
+
+        #[inline]
+        pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+            Some(Self {
+                d_tag: i64::from_le_bytes(bytes.get(0..8)?.try_into().ok()?),
+                d_val: u64::from_le_bytes(bytes.get(8..16)?.try_into().ok()?),
+            })
+        }
+    }
+
+    #[repr(C)]
+    #[derive(Debug, Clone, Copy)]
+    pub struct Rela {
+        pub r_offset: ElfAddr,
+        pub r_info: ElfXWord,
+        pub r_addend: i64,
+    }
+
+    impl Rela {
+        pub const SIZE: usize = 24;
+
+        #[inline]
+        pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+            Some(Self {
+                r_offset: u64::from_le_bytes(bytes.get(0..8)?.try_into().ok()?),
+                r_info: u64::from_le_bytes(bytes.get(8..16)?.try_into().ok()?),
+                r_addend: i64::from_le_bytes(bytes.get(16..24)?.try_into().ok()?),
+            })
+        }
+
+        #[inline]
+        pub const fn r_sym(&self) -> u32 {
+            (self.r_info >> 32) as u32
+        }
+
+        #[inline]
+        pub const fn r_type(&self) -> u32 {
+            self.r_info as u32
+        }
+    }
 }
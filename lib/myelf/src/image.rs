@@ -0,0 +1,278 @@
+//! Executable image that can be loaded at any address
+
+use crate::{elf64::*, *};
+use core::mem::size_of;
+
+/// An executable file parsed to load its segments
+pub struct ElfImage<'a> {
+    blob: &'a [u8],
+    header: Header,
+    image_base: ElfAddr,
+    image_size: usize,
+}
+
+impl<'a> ElfImage<'a> {
+    pub const PAGE_SIZE: u64 = 0x1000;
+
+    /// Parses the executable for the machine.
+    ///
+    /// Returns `None` unless the headers and the contents of all the loadable segments are in the file.
+    pub fn parse(blob: &'a [u8], machine: Machine) -> Option<Self> {
+        let header = _read::<Header>(blob, 0)?;
+        if !(header.is_valid(ET_EXEC, machine) || header.is_valid(ET_DYN, machine))
+            || (header.e_phentsize as usize) < size_of::<ProgramHeader>()
+        {
+            return None;
+        }
+        let phdrs_size = header.e_phentsize as usize * header.e_phnum as usize;
+        usize::try_from(header.e_phoff)
+            .ok()
+            .and_then(|v| v.checked_add(phdrs_size))
+            .filter(|&v| v <= blob.len())?;
+
+        let mut result = Self {
+            blob,
+            header,
+            image_base: 0,
+            image_size: 0,
+        };
+
+        let page_mask = Self::PAGE_SIZE - 1;
+        let mut image_base = u64::MAX;
+        let mut image_end = 0;
+        for phdr in result.program_headers().filter(|v| v.p_type == PT_LOAD) {
+            phdr.p_offset
+                .checked_add(phdr.p_filesz)
+                .filter(|&v| v <= blob.len() as u64 && phdr.p_filesz <= phdr.p_memsz)?;
+            image_base = image_base.min(phdr.p_vaddr);
+            image_end = image_end.max(phdr.p_vaddr.checked_add(phdr.p_memsz)?);
+        }
+        if image_base >= image_end {
+            return None;
+        }
+        result.image_base = image_base & !page_mask;
+        result.image_size = usize::try_from(
+            image_end
+                .checked_add(page_mask)?
+                .checked_sub(result.image_base)?
+                & !page_mask,
+        )
+        .ok()?;
+
+        Some(result)
+    }
+
+    #[inline]
+    pub fn program_headers(&self) -> impl Iterator<Item = ProgramHeader> + '_ {
+        let phoff = self.header.e_phoff as usize;
+        let phentsize = self.header.e_phentsize as usize;
+        (0..self.header.e_phnum as usize)
+            .filter_map(move |index| _read(self.blob, phoff + index * phentsize))
+    }
+
+    #[inline]
+    pub const fn machine(&self) -> Machine {
+        self.header.e_machine
+    }
+
+    /// Returns the lowest page address of the loadable segments.
+    #[inline]
+    pub const fn image_base(&self) -> ElfAddr {
+        self.image_base
+    }
+
+    /// Returns the size in pages of the memory to load the image.
+    #[inline]
+    pub const fn image_size(&self) -> usize {
+        self.image_size
+    }
+
+    /// Returns the entry point when the image runs at `load_base`.
+    #[inline]
+    pub const fn entry(&self, load_base: ElfAddr) -> ElfAddr {
+        self.header
+            .e_entry
+            .wrapping_sub(self.image_base)
+            .wrapping_add(load_base)
+    }
+
+    #[inline]
+    pub fn tls_template(&self) -> Option<TlsTemplate> {
+        TlsTemplate::from_program_headers(self.program_headers())
+    }
+
+    /// Copies the loadable segments to `image` that will run at `load_base`, and applies the relocations.
+    ///
+    /// The rest of the image is cleared. Returns the number of relocations applied.
+    pub fn load(&self, image: &mut [u8], load_base: ElfAddr) -> Result<usize, RelocationError> {
+        let image = image
+            .get_mut(..self.image_size)
+            .ok_or(RelocationError::OutOfBounds)?;
+        image.fill(0);
+        for phdr in self.program_headers().filter(|v| v.p_type == PT_LOAD) {
+            let size = phdr.p_filesz as usize;
+            let offset = _offset(image, self.image_base, phdr.p_vaddr, size)?;
+            let src = phdr.p_offset as usize;
+            image[offset..offset + size].copy_from_slice(&self.blob[src..src + size]);
+        }
+        relocate(
+            image,
+            self.image_base,
+            load_base,
+            self.machine(),
+            self.program_headers(),
+        )
+    }
+}
+
+/// Reads the structure at the offset, if it is in the blob.
+#[inline]
+fn _read<T: Copy>(blob: &[u8], offset: usize) -> Option<T> {
+    let bytes = blob.get(offset..offset.checked_add(size_of::<T>())?)?;
+    // SAFETY: the headers consist of integers, for which any bytes are valid.
+    Some(unsafe { (bytes.as_ptr() as *const T).read_unaligned() })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LOAD_BASE: u64 = 0x40_0000;
+    const HEADER_SIZE: usize = size_of::<Header>();
+    const PHDR_SIZE: usize = size_of::<ProgramHeader>();
+
+    fn put<T>(blob: &mut [u8], offset: usize, value: T) {
+        let bytes =
+            unsafe { core::slice::from_raw_parts(&value as *const T as *const u8, size_of::<T>()) };
+        blob[offset..offset + bytes.len()].copy_from_slice(bytes);
+    }
+
+    fn program_header(
+        p_type: SegmentType,
+        p_vaddr: u64,
+        p_filesz: u64,
+        p_memsz: u64,
+    ) -> ProgramHeader {
+        ProgramHeader {
+            p_type,
+            p_flags: 0,
+            p_offset: p_vaddr,
+            p_vaddr,
+            p_paddr: p_vaddr,
+            p_filesz,
+            p_memsz,
+            p_align: 8,
+        }
+    }
+
+    /// A position independent executable whose file layout is the same as its memory layout
+    ///
+    /// * 0x100 entry point
+    /// * 0x120 pointer to 0x18, relocated by the RELATIVE entry at 0x180
+    /// * 0x140 dynamic section
+    /// * 0x1C0 TLS template of 4 bytes, 12 bytes in memory
+    /// * 0x200..0x1800 bss
+    fn executable() -> [u8; 0x200] {
+        let mut blob = [0u8; 0x200];
+        let mut ident = [0u8; EI_NIDENT];
+        ident[..4].copy_from_slice(&ELFMAG);
+        ident[EI_CLASS] = ELFCLASS64;
+        ident[EI_DATA] = ELFDATA2LSB;
+        ident[EI_VERSION] = EV_CURRENT;
+        let phdrs = [
+            program_header(PT_LOAD, 0, 0x200, 0x1800),
+            program_header(PT_DYNAMIC, 0x140, 0x40, 0x40),
+            program_header(PT_TLS, 0x1C0, 4, 12),
+        ];
+        put(
+            &mut blob,
+            0,
+            Header {
+                e_ident: ident,
+                e_type: ET_DYN,
+                e_machine: EM_X86_64,
+                e_version: EV_CURRENT as ElfWord,
+                e_entry: 0x100,
+                e_phoff: HEADER_SIZE as u64,
+                e_shoff: 0,
+                e_flags: 0,
+                e_ehsize: HEADER_SIZE as u16,
+                e_phentsize: PHDR_SIZE as u16,
+                e_phnum: phdrs.len() as u16,
+                e_shentsize: 0,
+                e_shnum: 0,
+                e_shstrndx: 0,
+            },
+        );
+        for (index, phdr) in phdrs.into_iter().enumerate() {
+            put(&mut blob, HEADER_SIZE + index * PHDR_SIZE, phdr);
+        }
+        for (index, (tag, val)) in [
+            (DT_RELA, 0x180),
+            (DT_RELASZ, 24),
+            (DT_RELAENT, 24),
+            (DT_NULL, 0),
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            put(
+                &mut blob,
+                0x140 + index * Dyn::SIZE,
+                Dyn {
+                    d_tag: tag,
+                    d_val: val,
+                },
+            );
+        }
+        put(
+            &mut blob,
+            0x180,
+            Rela {
+                r_offset: 0x120,
+                r_info: R_X86_64_RELATIVE as u64,
+                r_addend: 0x18,
+            },
+        );
+        blob[0x1C0..0x1C4].copy_from_slice(&[1, 2, 3, 4]);
+        blob
+    }
+
+    #[test]
+    fn load_relative() {
+        let blob = executable();
+        let elf = ElfImage::parse(&blob, EM_X86_64).unwrap();
+        assert_eq!(elf.image_base(), 0);
+        assert_eq!(elf.image_size(), 0x2000);
+        assert_eq!(elf.entry(LOAD_BASE), LOAD_BASE + 0x100);
+
+        let mut image = vec![0xFFu8; elf.image_size()];
+        assert_eq!(elf.load(&mut image, LOAD_BASE), Ok(1));
+        assert_eq!(image[0x120..0x128], (LOAD_BASE + 0x18).to_le_bytes());
+        assert_eq!(image[0x1C0..0x1C4], [1, 2, 3, 4]);
+        assert!(image[0x200..].iter().all(|v| *v == 0));
+
+        let tls = elf.tls_template().unwrap();
+        let mut block = vec![0xFFu8; tls.block_size()];
+        tls.init_block(&image, elf.image_base(), &mut block)
+            .unwrap();
+        assert_eq!(block, [1, 2, 3, 4, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+
+        // too small to load
+        let mut image = vec![0u8; 0x1000];
+        assert_eq!(
+            elf.load(&mut image, LOAD_BASE),
+            Err(RelocationError::OutOfBounds)
+        );
+    }
+
+    #[test]
+    fn invalid_executable() {
+        let blob = executable();
+        assert!(ElfImage::parse(&blob, EM_AARCH64).is_none());
+        // the program headers are truncated
+        assert!(ElfImage::parse(&blob[..HEADER_SIZE + PHDR_SIZE], EM_X86_64).is_none());
+        // the loadable segment is truncated
+        assert!(ElfImage::parse(&blob[..0x1FF], EM_X86_64).is_none());
+    }
+}
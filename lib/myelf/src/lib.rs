@@ -1,5 +1,9 @@
 //! Executable and Linking Format
-#![no_std]
+#![cfg_attr(not(test), no_std)]
 
 mod elf;
+mod image;
+mod reloc;
 pub use elf::*;
+pub use image::*;
+pub use reloc::*;
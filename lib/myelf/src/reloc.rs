@@ -0,0 +1,258 @@
+//! Relocation and Thread Local Storage
+
+use crate::{elf64::*, *};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RelocationError {
+    /// The machine is not supported
+    UnsupportedMachine(Machine),
+    /// The relocation type is not supported
+    UnsupportedType(u32),
+    /// The dynamic section is malformed
+    InvalidFormat,
+    /// The table or the target is out of the image
+    OutOfBounds,
+}
+
+/// Applies the relocations in the dynamic segment to the loaded image.
+///
+/// `image` is the memory image that starts at `image_base` in the address space of the ELF file,
+/// and it will run at `load_base`. Returns the number of entries applied.
+pub fn relocate(
+    image: &mut [u8],
+    image_base: ElfAddr,
+    load_base: ElfAddr,
+    machine: Machine,
+    mut program_headers: impl Iterator<Item = ProgramHeader>,
+) -> Result<usize, RelocationError> {
+    let (type_none, type_relative) = match machine {
+        EM_X86_64 => (R_X86_64_NONE, R_X86_64_RELATIVE),
+        EM_AARCH64 => (R_AARCH64_NONE, R_AARCH64_RELATIVE),
+        EM_RISCV => (R_RISCV_NONE, R_RISCV_RELATIVE),
+        _ => return Err(RelocationError::UnsupportedMachine(machine)),
+    };
+
+    let Some(dynamic) = program_headers.find(|v| v.p_type == PT_DYNAMIC) else {
+        return Ok(0);
+    };
+    let dynamic = _offset(image, image_base, dynamic.p_vaddr, dynamic.p_memsz as usize)
+        .map(|v| &image[v..v + dynamic.p_memsz as usize])?;
+
+    let mut rela = None;
+    let mut rela_size = 0;
+    let mut rela_ent = Rela::SIZE;
+    for entry in dynamic.chunks_exact(Dyn::SIZE).filter_map(Dyn::from_bytes) {
+        match entry.d_tag {
+            DT_NULL => break,
+            DT_RELA => rela = Some(entry.d_val),
+            DT_RELASZ => rela_size = entry.d_val as usize,
+            DT_RELAENT => rela_ent = entry.d_val as usize,
+            _ => (),
+        }
+    }
+    let Some(rela) = rela else {
+        return Ok(0);
+    };
+    if rela_ent < Rela::SIZE {
+        return Err(RelocationError::InvalidFormat);
+    }
+    let rela_offset = _offset(image, image_base, rela, rela_size)?;
+
+    let delta = load_base.wrapping_sub(image_base);
+    let mut count = 0;
+    for index in 0..rela_size / rela_ent {
+        let offset = rela_offset + index * rela_ent;
+        let entry = Rela::from_bytes(&image[offset..offset + Rela::SIZE])
+            .ok_or(RelocationError::InvalidFormat)?;
+        match entry.r_type() {
+            r_type if r_type == type_none => (),
+            r_type if r_type == type_relative => {
+                let target = _offset(image, image_base, entry.r_offset, 8)?;
+                let value = delta.wrapping_add(entry.r_addend as u64);
+                image[target..target + 8].copy_from_slice(&value.to_le_bytes());
+                count += 1;
+            }
+            r_type => return Err(RelocationError::UnsupportedType(r_type)),
+        }
+    }
+
+    Ok(count)
+}
+
+/// Returns the offset in the image of the range starting at the virtual address.
+#[inline]
+pub(crate) fn _offset(
+    image: &[u8],
+    image_base: ElfAddr,
+    vaddr: ElfAddr,
+    size: usize,
+) -> Result<usize, RelocationError> {
+    vaddr
+        .checked_sub(image_base)
+        .and_then(|v| usize::try_from(v).ok())
+        .filter(|&v| v.checked_add(size).is_some_and(|end| end <= image.len()))
+        .ok_or(RelocationError::OutOfBounds)
+}
+
+/// Template of the thread local storage described by `PT_TLS`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TlsTemplate {
+    pub vaddr: ElfAddr,
+    pub file_size: usize,
+    pub mem_size: usize,
+    pub align: usize,
+}
+
+impl TlsTemplate {
+    #[inline]
+    pub fn from_program_headers(
+        mut program_headers: impl Iterator<Item = ProgramHeader>,
+    ) -> Option<Self> {
+        program_headers.find(|v| v.p_type == PT_TLS).map(|v| Self {
+            vaddr: v.p_vaddr,
+            file_size: v.p_filesz as usize,
+            mem_size: v.p_memsz as usize,
+            align: (v.p_align as usize).max(1),
+        })
+    }
+
+    /// Returns the size of the TLS block for each thread.
+    ///
+    /// In the variant II used by x86-64, the thread pointer points to the end of the block.
+    #[inline]
+    pub const fn block_size(&self) -> usize {
+        self.mem_size.next_multiple_of(self.align)
+    }
+
+    /// Initializes the TLS block with the template in the loaded image.
+    ///
+    /// The initialized data is followed by zeros for the rest of the block.
+    pub fn init_block(
+        &self,
+        image: &[u8],
+        image_base: ElfAddr,
+        block: &mut [u8],
+    ) -> Result<(), RelocationError> {
+        if self.file_size > self.mem_size || self.file_size > block.len() {
+            return Err(RelocationError::InvalidFormat);
+        }
+        if block.len() < self.block_size() {
+            return Err(RelocationError::OutOfBounds);
+        }
+        let offset = _offset(image, image_base, self.vaddr, self.file_size)?;
+        block[..self.file_size].copy_from_slice(&image[offset..offset + self.file_size]);
+        block[self.file_size..].fill(0);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LOAD_BASE: u64 = 0x40_0000;
+
+    fn program_header(p_type: SegmentType, p_vaddr: u64, p_filesz: u64) -> ProgramHeader {
+        ProgramHeader {
+            p_type,
+            p_flags: 0,
+            p_offset: p_vaddr,
+            p_vaddr,
+            p_paddr: p_vaddr,
+            p_filesz,
+            p_memsz: p_filesz,
+            p_align: 8,
+        }
+    }
+
+    /// Image with a pointer at 0x20 and its relocation at 0x80
+    fn relocatable_image(r_type: u32) -> [u8; 0x100] {
+        let mut image = [0u8; 0x100];
+        for (index, (tag, val)) in [
+            (DT_RELA, 0x80),
+            (DT_RELASZ, 48),
+            (DT_RELAENT, 24),
+            (DT_NULL, 0),
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            let offset = 0x40 + index * Dyn::SIZE;
+            image[offset..offset + 8].copy_from_slice(&i64::to_le_bytes(tag));
+            image[offset + 8..offset + 16].copy_from_slice(&u64::to_le_bytes(val));
+        }
+        for (index, (r_offset, r_type, r_addend)) in
+            [(0x20u64, r_type, 0x18i64), (0, R_X86_64_NONE, 0)]
+                .into_iter()
+                .enumerate()
+        {
+            let offset = 0x80 + index * Rela::SIZE;
+            image[offset..offset + 8].copy_from_slice(&r_offset.to_le_bytes());
+            image[offset + 8..offset + 16].copy_from_slice(&(r_type as u64).to_le_bytes());
+            image[offset + 16..offset + 24].copy_from_slice(&r_addend.to_le_bytes());
+        }
+        image
+    }
+
+    #[test]
+    fn relative() {
+        let mut image = relocatable_image(R_X86_64_RELATIVE);
+        let phdrs = [program_header(PT_DYNAMIC, 0x40, 0x40)];
+
+        let result = relocate(&mut image, 0, LOAD_BASE, EM_X86_64, phdrs.into_iter());
+        assert_eq!(result, Ok(1));
+        assert_eq!(image[0x20..0x28], (LOAD_BASE + 0x18).to_le_bytes());
+    }
+
+    #[test]
+    fn unsupported() {
+        let phdrs = [program_header(PT_DYNAMIC, 0x40, 0x40)];
+
+        let mut image = relocatable_image(1);
+        let result = relocate(&mut image, 0, LOAD_BASE, EM_X86_64, phdrs.into_iter());
+        assert_eq!(result, Err(RelocationError::UnsupportedType(1)));
+
+        let mut image = relocatable_image(R_X86_64_RELATIVE);
+        let result = relocate(&mut image, 0x1000, LOAD_BASE, EM_X86_64, phdrs.into_iter());
+        assert_eq!(result, Err(RelocationError::OutOfBounds));
+
+        let mut image = [0u8; 0x100];
+        let result = relocate(&mut image, 0, LOAD_BASE, EM_X86_64, [].into_iter());
+        assert_eq!(result, Ok(0));
+    }
+
+    #[test]
+    fn tls() {
+        let mut image = [0u8; 0x40];
+        image[0x10..0x14].copy_from_slice(&[1, 2, 3, 4]);
+        let mut phdr = program_header(PT_TLS, 0x10, 4);
+        phdr.p_memsz = 12;
+        let template = TlsTemplate::from_program_headers([phdr].into_iter()).unwrap();
+        assert_eq!(template.block_size(), 16);
+
+        let mut block = [0xFFu8; 16];
+        template.init_block(&image, 0, &mut block).unwrap();
+        assert_eq!(block, [1, 2, 3, 4, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+
+        let mut block = [0u8; 8];
+        assert_eq!(
+            template.init_block(&image, 0, &mut block),
+            Err(RelocationError::OutOfBounds)
+        );
+
+        // The initialized data must fit in the block
+        let mut phdr = program_header(PT_TLS, 0x10, 8);
+        phdr.p_memsz = 4;
+        let template = TlsTemplate::from_program_headers([phdr].into_iter()).unwrap();
+        let mut block = [0u8; 16];
+        assert_eq!(
+            template.init_block(&image, 0, &mut block),
+            Err(RelocationError::InvalidFormat)
+        );
+        let mut block = [0u8; 4];
+        assert_eq!(
+            template.init_block(&image, 0, &mut block),
+            Err(RelocationError::InvalidFormat)
+        );
+    }
+}
@@ -0,0 +1,369 @@
+//! Flattened Device Tree
+#![no_std]
+
+extern crate alloc;
+
+use core::{slice, str};
+
+/// Flattened Device Tree (Devicetree Blob)
+#[derive(Clone, Copy)]
+pub struct Fdt<'a> {
+    structs: &'a [u8],
+    strings: &'a [u8],
+}
+
+impl<'a> Fdt<'a> {
+    pub const MAGIC: u32 = 0xD00D_FEED;
+
+    const HEADER_SIZE: usize = 40;
+    const DEFAULT_ADDRESS_CELLS: u32 = 2;
+    const DEFAULT_SIZE_CELLS: u32 = 1;
+
+    /// Parses the devicetree blob.
+    pub fn new(blob: &'a [u8]) -> Option<Self> {
+        let header = |index: usize| read_be32(blob, index * 4);
+        if header(0)? != Self::MAGIC {
+            return None;
+        }
+        let blob = blob.get(..header(1)? as usize)?;
+        let off_dt_struct = header(2)? as usize;
+        let off_dt_strings = header(3)? as usize;
+        let size_dt_strings = header(8)? as usize;
+        let size_dt_struct = header(9)? as usize;
+
+        Some(Self {
+            structs: blob.get(off_dt_struct..off_dt_struct + size_dt_struct)?,
+            strings: blob.get(off_dt_strings..off_dt_strings + size_dt_strings)?,
+        })
+    }
+
+    /// Parses the devicetree blob at the specified address.
+    ///
+    /// # Safety
+    ///
+    /// The pointer must point to a valid devicetree blob that lives for `'a`.
+    pub unsafe fn from_ptr(ptr: *const u8) -> Option<Self> {
+        let header = slice::from_raw_parts(ptr, Self::HEADER_SIZE);
+        if read_be32(header, 0)? != Self::MAGIC {
+            return None;
+        }
+        let total_size = read_be32(header, 4)? as usize;
+        Self::new(slice::from_raw_parts(ptr, total_size))
+    }
+
+    /// Returns the root node.
+    #[inline]
+    pub fn root(&self) -> Option<FdtNode<'a>> {
+        let mut offset = 0;
+        while self.token(offset)? == FdtToken::NOP {
+            offset += 4;
+        }
+        FdtNode::new(
+            *self,
+            offset,
+            Self::DEFAULT_ADDRESS_CELLS,
+            Self::DEFAULT_SIZE_CELLS,
+        )
+    }
+
+    /// Finds the node by the path such as `/soc/serial@1000`.
+    ///
+    /// The unit address can be omitted, in which case the first node with the name matches.
+    pub fn find_node(&self, path: &str) -> Option<FdtNode<'a>> {
+        let mut node = self.root()?;
+        for component in path.split('/').filter(|v| !v.is_empty()) {
+            node = node.children().find(|v| {
+                let name = v.name();
+                name == component
+                    || (!component.contains('@') && name.split('@').next() == Some(component))
+            })?;
+        }
+        Some(node)
+    }
+
+    /// Returns an iterator over all nodes in depth-first order.
+    #[inline]
+    pub fn nodes(&self) -> impl Iterator<Item = FdtNode<'a>> {
+        let mut stack = alloc::vec::Vec::new();
+        stack.extend(self.root());
+        core::iter::from_fn(move || {
+            let node = stack.pop()?;
+            let index = stack.len();
+            stack.extend(node.children());
+            stack[index..].reverse();
+            Some(node)
+        })
+    }
+
+    /// Finds the first node compatible with the specified string.
+    #[inline]
+    pub fn find_compatible(&self, compatible: &str) -> Option<FdtNode<'a>> {
+        self.nodes().find(|v| v.is_compatible(compatible))
+    }
+
+    #[inline]
+    fn token(&self, offset: usize) -> Option<FdtToken> {
+        read_be32(self.structs, offset).map(FdtToken)
+    }
+
+    #[inline]
+    fn string_at(&self, offset: usize) -> Option<&'a str> {
+        cstr(self.strings, offset)
+    }
+
+    /// Returns the offset next to the node that begins at the offset.
+    fn skip_node(&self, offset: usize) -> Option<usize> {
+        let mut offset = offset;
+        let mut depth = 0usize;
+        loop {
+            match self.token(offset)? {
+                FdtToken::BEGIN_NODE => {
+                    let name = cstr(self.structs, offset + 4)?;
+                    offset = align4(offset + 4 + name.len() + 1);
+                    depth += 1;
+                }
+                FdtToken::END_NODE => {
+                    offset += 4;
+                    depth = depth.checked_sub(1)?;
+                    if depth == 0 {
+                        return Some(offset);
+                    }
+                }
+                FdtToken::PROP => {
+                    let len = read_be32(self.structs, offset + 4)? as usize;
+                    offset = align4(offset + 12 + len);
+                }
+                FdtToken::NOP => offset += 4,
+                _ => return None,
+            }
+        }
+    }
+}
+
+#[repr(transparent)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct FdtToken(u32);
+
+impl FdtToken {
+    const BEGIN_NODE: Self = Self(1);
+    const END_NODE: Self = Self(2);
+    const PROP: Self = Self(3);
+    const NOP: Self = Self(4);
+}
+
+/// A node in the devicetree
+#[derive(Clone, Copy)]
+pub struct FdtNode<'a> {
+    fdt: Fdt<'a>,
+    offset: usize,
+    name: &'a str,
+    /// Offset of the first property
+    body: usize,
+    /// `#address-cells` of the parent
+    address_cells: u32,
+    /// `#size-cells` of the parent
+    size_cells: u32,
+}
+
+impl<'a> FdtNode<'a> {
+    fn new(fdt: Fdt<'a>, offset: usize, address_cells: u32, size_cells: u32) -> Option<Self> {
+        if fdt.token(offset)? != FdtToken::BEGIN_NODE {
+            return None;
+        }
+        let name = cstr(fdt.structs, offset + 4)?;
+        Some(Self {
+            fdt,
+            offset,
+            name,
+            body: align4(offset + 4 + name.len() + 1),
+            address_cells,
+            size_cells,
+        })
+    }
+
+    /// Returns the name of the node including the unit address, the root node is empty.
+    #[inline]
+    pub const fn name(&self) -> &'a str {
+        self.name
+    }
+
+    /// Returns an iterator over the properties of the node.
+    #[inline]
+    pub fn properties(&self) -> impl Iterator<Item = FdtProperty<'a>> {
+        let fdt = self.fdt;
+        let mut offset = self.body;
+        core::iter::from_fn(move || loop {
+            match fdt.token(offset)? {
+                FdtToken::NOP => offset += 4,
+                FdtToken::PROP => {
+                    let len = read_be32(fdt.structs, offset + 4)? as usize;
+                    let name_offset = read_be32(fdt.structs, offset + 8)? as usize;
+                    let value = fdt.structs.get(offset + 12..offset + 12 + len)?;
+                    offset = align4(offset + 12 + len);
+                    return Some(FdtProperty {
+                        name: fdt.string_at(name_offset)?,
+                        value,
+                    });
+                }
+                _ => return None,
+            }
+        })
+    }
+
+    /// Returns the property of the specified name.
+    #[inline]
+    pub fn property(&self, name: &str) -> Option<FdtProperty<'a>> {
+        self.properties().find(|v| v.name() == name)
+    }
+
+    /// Returns an iterator over the child nodes.
+    pub fn children(&self) -> impl Iterator<Item = FdtNode<'a>> {
+        let fdt = self.fdt;
+        let address_cells = self
+            .property("#address-cells")
+            .and_then(|v| v.as_u32())
+            .unwrap_or(Fdt::DEFAULT_ADDRESS_CELLS);
+        let size_cells = self
+            .property("#size-cells")
+            .and_then(|v| v.as_u32())
+            .unwrap_or(Fdt::DEFAULT_SIZE_CELLS);
+
+        let mut offset = self.first_child_offset();
+        core::iter::from_fn(move || loop {
+            let current = offset?;
+            match fdt.token(current)? {
+                FdtToken::NOP => offset = Some(current + 4),
+                FdtToken::BEGIN_NODE => {
+                    offset = fdt.skip_node(current);
+                    return FdtNode::new(fdt, current, address_cells, size_cells);
+                }
+                _ => return None,
+            }
+        })
+    }
+
+    fn first_child_offset(&self) -> Option<usize> {
+        let mut offset = self.body;
+        loop {
+            match self.fdt.token(offset)? {
+                FdtToken::NOP => offset += 4,
+                FdtToken::PROP => {
+                    let len = read_be32(self.fdt.structs, offset + 4)? as usize;
+                    offset = align4(offset + 12 + len);
+                }
+                _ => return Some(offset),
+            }
+        }
+    }
+
+    /// Returns an iterator over the strings of the `compatible` property.
+    #[inline]
+    pub fn compatible(&self) -> impl Iterator<Item = &'a str> {
+        self.property("compatible")
+            .into_iter()
+            .flat_map(|v| v.strings())
+    }
+
+    #[inline]
+    pub fn is_compatible(&self, compatible: &str) -> bool {
+        self.compatible().any(|v| v == compatible)
+    }
+
+    /// Returns an iterator over the `(address, size)` pairs of the `reg` property.
+    pub fn reg(&self) -> impl Iterator<Item = (u64, u64)> + 'a {
+        let address_cells = self.address_cells as usize;
+        let size_cells = self.size_cells as usize;
+        let entry_size = (address_cells + size_cells) * 4;
+        let value = self.property("reg").map(|v| v.value()).unwrap_or(&[]);
+        let read_cells = |bytes: &[u8]| {
+            bytes.chunks_exact(4).fold(0u64, |acc, v| {
+                (acc << 32) | u32::from_be_bytes(v.try_into().unwrap()) as u64
+            })
+        };
+        value
+            .chunks_exact(entry_size.max(1))
+            .filter(move |_| entry_size > 0)
+            .map(move |v| {
+                let (address, size) = v.split_at(address_cells * 4);
+                (read_cells(address), read_cells(size))
+            })
+    }
+
+    /// Returns the offset of the node in the structure block
+    #[inline]
+    pub const fn offset(&self) -> usize {
+        self.offset
+    }
+}
+
+/// A property of the devicetree node
+#[derive(Clone, Copy)]
+pub struct FdtProperty<'a> {
+    name: &'a str,
+    value: &'a [u8],
+}
+
+impl<'a> FdtProperty<'a> {
+    #[inline]
+    pub const fn name(&self) -> &'a str {
+        self.name
+    }
+
+    #[inline]
+    pub const fn value(&self) -> &'a [u8] {
+        self.value
+    }
+
+    #[inline]
+    pub fn as_u32(&self) -> Option<u32> {
+        (self.value.len() == 4)
+            .then(|| read_be32(self.value, 0))
+            .flatten()
+    }
+
+    #[inline]
+    pub fn as_u64(&self) -> Option<u64> {
+        match self.value.len() {
+            4 => self.as_u32().map(|v| v as u64),
+            8 => Some(u64::from_be_bytes(self.value.try_into().unwrap())),
+            _ => None,
+        }
+    }
+
+    /// Returns the value as a string without the terminating NULL.
+    #[inline]
+    pub fn as_str(&self) -> Option<&'a str> {
+        cstr(self.value, 0)
+    }
+
+    /// Returns an iterator over the strings of the string list.
+    #[inline]
+    pub fn strings(&self) -> impl Iterator<Item = &'a str> {
+        self.value
+            .split(|v| *v == 0)
+            .filter(|v| !v.is_empty())
+            .filter_map(|v| str::from_utf8(v).ok())
+    }
+}
+
+#[inline]
+fn read_be32(bytes: &[u8], offset: usize) -> Option<u32> {
+    bytes
+        .get(offset..offset + 4)
+        .map(|v| u32::from_be_bytes(v.try_into().unwrap()))
+}
+
+#[inline]
+fn cstr(bytes: &[u8], offset: usize) -> Option<&str> {
+    let bytes = bytes.get(offset..)?;
+    let len = bytes.iter().position(|v| *v == 0)?;
+    str::from_utf8(&bytes[..len]).ok()
+}
+
+#[inline]
+const fn align4(value: usize) -> usize {
+    (value + 3) & !3
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,196 @@
+use super::*;
+use alloc::vec::Vec;
+
+/// Builds a devicetree blob from the tokens
+struct FdtBuilder {
+    structs: Vec<u8>,
+    strings: Vec<u8>,
+}
+
+impl FdtBuilder {
+    fn new() -> Self {
+        Self {
+            structs: Vec::new(),
+            strings: Vec::new(),
+        }
+    }
+
+    fn token(&mut self, token: FdtToken) -> &mut Self {
+        self.structs.extend_from_slice(&token.0.to_be_bytes());
+        self
+    }
+
+    fn pad(&mut self) {
+        while !self.structs.len().is_multiple_of(4) {
+            self.structs.push(0);
+        }
+    }
+
+    fn begin(&mut self, name: &str) -> &mut Self {
+        self.token(FdtToken::BEGIN_NODE);
+        self.structs.extend_from_slice(name.as_bytes());
+        self.structs.push(0);
+        self.pad();
+        self
+    }
+
+    fn end(&mut self) -> &mut Self {
+        self.token(FdtToken::END_NODE)
+    }
+
+    fn prop(&mut self, name: &str, value: &[u8]) -> &mut Self {
+        let name_offset = self.strings.len() as u32;
+        self.strings.extend_from_slice(name.as_bytes());
+        self.strings.push(0);
+        self.token(FdtToken::PROP);
+        self.structs
+            .extend_from_slice(&(value.len() as u32).to_be_bytes());
+        self.structs.extend_from_slice(&name_offset.to_be_bytes());
+        self.structs.extend_from_slice(value);
+        self.pad();
+        self
+    }
+
+    fn prop_cells(&mut self, name: &str, cells: &[u32]) -> &mut Self {
+        let value = cells
+            .iter()
+            .flat_map(|v| v.to_be_bytes())
+            .collect::<Vec<_>>();
+        self.prop(name, &value)
+    }
+
+    fn build(&mut self) -> Vec<u8> {
+        // FDT_END
+        self.token(FdtToken(9));
+        let off_dt_struct = Fdt::HEADER_SIZE + 16;
+        let off_dt_strings = off_dt_struct + self.structs.len();
+        let total_size = off_dt_strings + self.strings.len();
+        let header = [
+            Fdt::MAGIC,
+            total_size as u32,
+            off_dt_struct as u32,
+            off_dt_strings as u32,
+            Fdt::HEADER_SIZE as u32,
+            17,
+            16,
+            0,
+            self.strings.len() as u32,
+            self.structs.len() as u32,
+        ];
+        let mut blob = header
+            .iter()
+            .flat_map(|v| v.to_be_bytes())
+            .collect::<Vec<_>>();
+        // empty memory reservation block
+        blob.extend_from_slice(&[0; 16]);
+        blob.extend_from_slice(&self.structs);
+        blob.extend_from_slice(&self.strings);
+        blob
+    }
+}
+
+fn sample_blob() -> Vec<u8> {
+    FdtBuilder::new()
+        .token(FdtToken::NOP)
+        .begin("")
+        .prop_cells("#address-cells", &[2])
+        .prop_cells("#size-cells", &[2])
+        .prop("compatible", b"acme,board\0acme,generic\0")
+        .prop("model", b"ACME Board\0")
+        .begin("memory@80000000")
+        .prop("device_type", b"memory\0")
+        .prop_cells("reg", &[0, 0x8000_0000, 0, 0x4000_0000])
+        .end()
+        .begin("soc")
+        .prop_cells("#address-cells", &[1])
+        .prop_cells("#size-cells", &[1])
+        .token(FdtToken::NOP)
+        .begin("serial@1000")
+        .prop("compatible", b"ns16550a\0")
+        .prop_cells("reg", &[0x1000, 0x100, 0x2000, 0x100])
+        .prop_cells("clock-frequency", &[3_686_400])
+        .end()
+        .begin("serial@3000")
+        .prop("compatible", b"ns16550a\0")
+        .end()
+        .end()
+        .end()
+        .build()
+}
+
+#[test]
+fn parse_header() {
+    let blob = sample_blob();
+    assert!(Fdt::new(&blob).is_some());
+    assert!(Fdt::new(&blob[..blob.len() - 1]).is_none());
+
+    let mut bad_magic = blob.clone();
+    bad_magic[0] = 0;
+    assert!(Fdt::new(&bad_magic).is_none());
+
+    let fdt = unsafe { Fdt::from_ptr(blob.as_ptr()) }.unwrap();
+    assert_eq!(fdt.root().unwrap().name(), "");
+}
+
+#[test]
+fn walk_nodes() {
+    let blob = sample_blob();
+    let fdt = Fdt::new(&blob).unwrap();
+
+    assert_eq!(
+        fdt.nodes().map(|v| v.name()).collect::<Vec<_>>(),
+        ["", "memory@80000000", "soc", "serial@1000", "serial@3000"]
+    );
+
+    let root = fdt.root().unwrap();
+    assert_eq!(
+        root.compatible().collect::<Vec<_>>(),
+        ["acme,board", "acme,generic"]
+    );
+    assert_eq!(
+        root.property("model").and_then(|v| v.as_str()),
+        Some("ACME Board")
+    );
+    assert_eq!(
+        root.children().map(|v| v.name()).collect::<Vec<_>>(),
+        ["memory@80000000", "soc"]
+    );
+
+    // the unit address can be omitted
+    let serial = fdt.find_node("/soc/serial").unwrap();
+    assert_eq!(serial.name(), "serial@1000");
+    assert_eq!(
+        fdt.find_node("/soc/serial@3000").map(|v| v.name()),
+        Some("serial@3000")
+    );
+    assert!(fdt.find_node("/soc/serial@2000").is_none());
+    assert_eq!(
+        fdt.find_compatible("ns16550a").map(|v| v.offset()),
+        Some(serial.offset())
+    );
+    assert_eq!(
+        serial.property("clock-frequency").and_then(|v| v.as_u64()),
+        Some(3_686_400)
+    );
+}
+
+#[test]
+fn reg_cells() {
+    let blob = sample_blob();
+    let fdt = Fdt::new(&blob).unwrap();
+
+    // two cells each from the root
+    let memory = fdt.find_node("/memory").unwrap();
+    assert_eq!(
+        memory.reg().collect::<Vec<_>>(),
+        [(0x8000_0000, 0x4000_0000)]
+    );
+    // one cell each from the soc
+    let serial = fdt.find_node("/soc/serial@1000").unwrap();
+    assert_eq!(
+        serial.reg().collect::<Vec<_>>(),
+        [(0x1000, 0x100), (0x2000, 0x100)]
+    );
+    let serial = fdt.find_node("/soc/serial@3000").unwrap();
+    assert_eq!(serial.reg().count(), 0);
+}
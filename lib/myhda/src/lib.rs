@@ -0,0 +1,84 @@
+//! High Definition Audio
+#![no_std]
+
+use core::{
+    ptr::read_volatile,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+/// An entry of the DMA Position Buffer
+#[repr(C, align(8))]
+#[derive(Debug, Default)]
+pub struct DmaPosition {
+    position: u32,
+    _reserved: u32,
+}
+
+impl DmaPosition {
+    /// DMA Position Buffer Enable
+    pub const DMA_POS_ENABLE: u32 = 0x0000_0001;
+
+    /// Returns the values of DPLBASE and DPUBASE that enable the buffer at the base address.
+    #[inline]
+    pub const fn base_registers(base: u64) -> (u32, u32) {
+        (
+            (base as u32 & !0x7F) | Self::DMA_POS_ENABLE,
+            (base >> 32) as u32,
+        )
+    }
+
+    /// Returns the current position in the cyclic buffer written by the controller.
+    #[inline]
+    pub fn position(&self) -> usize {
+        unsafe { read_volatile(&self.position) as usize }
+    }
+}
+
+/// Tracks the chunk of the cyclic buffer to be filled next
+#[derive(Debug)]
+pub struct CyclicBufferCursor {
+    chunk_size: usize,
+    num_chunks: usize,
+    next: AtomicUsize,
+}
+
+impl CyclicBufferCursor {
+    #[inline]
+    pub const fn new(chunk_size: usize, num_chunks: usize) -> Self {
+        Self {
+            chunk_size,
+            num_chunks,
+            next: AtomicUsize::new(0),
+        }
+    }
+
+    #[inline]
+    pub const fn chunk_size(&self) -> usize {
+        self.chunk_size
+    }
+
+    #[inline]
+    pub const fn num_chunks(&self) -> usize {
+        self.num_chunks
+    }
+
+    /// Returns the index of the chunk that contains the position.
+    #[inline]
+    pub const fn chunk_at(&self, position: usize) -> usize {
+        (position / self.chunk_size) % self.num_chunks
+    }
+
+    /// Claims the next chunk unless the controller is reading it at the position,
+    /// and returns its index.
+    pub fn claim(&self, position: usize) -> Option<usize> {
+        let current = self.chunk_at(position);
+        self.next
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |next| {
+                (next != current).then_some((next + 1) % self.num_chunks)
+            })
+            .ok()
+    }
+}
+
+#[cfg(test)]
+mod tests;
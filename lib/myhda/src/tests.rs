@@ -0,0 +1,40 @@
+use super::*;
+
+#[test]
+fn dma_position() {
+    assert_eq!(
+        DmaPosition::base_registers(0x0000_0001_2345_6780),
+        (0x2345_6781, 0x0000_0001)
+    );
+    // the buffer is aligned to 128 bytes
+    assert_eq!(DmaPosition::base_registers(0x1234_56FF), (0x1234_5681, 0));
+
+    let dma_position = DmaPosition {
+        position: 0x1234,
+        ..Default::default()
+    };
+    assert_eq!(dma_position.position(), 0x1234);
+}
+
+#[test]
+fn cyclic_buffer_cursor() {
+    let cursor = CyclicBufferCursor::new(0x1000, 4);
+    assert_eq!(cursor.chunk_at(0x0FFF), 0);
+    assert_eq!(cursor.chunk_at(0x1000), 1);
+    assert_eq!(cursor.chunk_at(0x3FFF), 3);
+    assert_eq!(cursor.chunk_at(0x4000), 0);
+
+    // the controller is reading the first chunk
+    assert_eq!(cursor.claim(0x0800), None);
+
+    assert_eq!(cursor.claim(0x1800), Some(0));
+    assert_eq!(cursor.claim(0x1800), None);
+    assert_eq!(cursor.claim(0x3000), Some(1));
+    assert_eq!(cursor.claim(0x3000), Some(2));
+    assert_eq!(cursor.claim(0x3000), None);
+
+    // wraps around
+    assert_eq!(cursor.claim(0x0000), Some(3));
+    assert_eq!(cursor.claim(0x0000), None);
+    assert_eq!(cursor.claim(0x1000), Some(0));
+}
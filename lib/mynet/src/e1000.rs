@@ -0,0 +1,398 @@
+//! Descriptor rings of the Intel 8254x Gigabit Ethernet Controller
+//!
+//! The driver owns the descriptors from its index up to the head of the controller,
+//! and gives them to the controller by advancing the tail register.
+
+use core::sync::atomic::{fence, Ordering};
+
+/// Legacy Receive Descriptor
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RxDescriptor {
+    pub addr: u64,
+    pub length: u16,
+    pub checksum: u16,
+    pub status: u8,
+    pub errors: u8,
+    pub special: u16,
+}
+
+impl RxDescriptor {
+    /// Descriptor Done
+    pub const STATUS_DD: u8 = 0x01;
+    /// End of Packet
+    pub const STATUS_EOP: u8 = 0x02;
+
+    #[inline]
+    pub const fn new(addr: u64) -> Self {
+        Self {
+            addr,
+            length: 0,
+            checksum: 0,
+            status: 0,
+            errors: 0,
+            special: 0,
+        }
+    }
+}
+
+/// Legacy Transmit Descriptor
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TxDescriptor {
+    pub addr: u64,
+    pub length: u16,
+    pub cso: u8,
+    pub cmd: u8,
+    pub status: u8,
+    pub css: u8,
+    pub special: u16,
+}
+
+impl TxDescriptor {
+    /// End of Packet
+    pub const CMD_EOP: u8 = 0x01;
+    /// Insert FCS
+    pub const CMD_IFCS: u8 = 0x02;
+    /// Report Status
+    pub const CMD_RS: u8 = 0x08;
+
+    #[inline]
+    pub const fn new(addr: u64, len: usize) -> Self {
+        Self {
+            addr,
+            length: len as u16,
+            cso: 0,
+            cmd: Self::CMD_EOP | Self::CMD_IFCS | Self::CMD_RS,
+            status: 0,
+            css: 0,
+            special: 0,
+        }
+    }
+}
+
+/// Registers of the controller that move the rings
+pub trait RingRegisters {
+    /// Reads TDH, the next descriptor the controller transmits.
+    fn tx_head(&self) -> usize;
+
+    /// Writes TDT, the descriptor after the last one queued for the controller.
+    fn set_tx_tail(&self, value: usize);
+
+    /// Writes RDT, the last descriptor given to the controller.
+    fn set_rx_tail(&self, value: usize);
+}
+
+/// Descriptors shared with the controller, each of which has its own buffer
+pub trait DescriptorMemory<T> {
+    fn num_descriptors(&self) -> usize;
+
+    fn read_descriptor(&self, index: usize) -> T;
+
+    fn write_descriptor(&self, index: usize, value: T);
+
+    /// Returns the bus address of the buffer.
+    fn buffer_address(&self, index: usize) -> u64;
+
+    /// Returns the first `len` bytes of the buffer.
+    fn buffer(&self, index: usize, len: usize) -> &[u8];
+
+    /// Copies the data into the buffer, followed by zeros up to `len` bytes.
+    fn write_buffer(&self, index: usize, data: &[u8], len: usize);
+}
+
+/// The error returned when all the descriptors of the transmit ring are in use
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RingFull;
+
+/// The transmit ring
+pub struct TxRing<M> {
+    memory: M,
+    /// The next descriptor to be queued
+    index: usize,
+}
+
+impl<M: DescriptorMemory<TxDescriptor>> TxRing<M> {
+    #[inline]
+    pub const fn new(memory: M) -> Self {
+        Self { memory, index: 0 }
+    }
+
+    #[inline]
+    pub const fn memory(&self) -> &M {
+        &self.memory
+    }
+
+    /// Queues the frame padded to `min_len` bytes, and lets the controller transmit it.
+    ///
+    /// Returns [RingFull] if the controller has not transmitted the frames queued before yet.
+    pub fn send<R: RingRegisters>(
+        &mut self,
+        regs: &R,
+        frame: &[u8],
+        min_len: usize,
+    ) -> Result<(), RingFull> {
+        let index = self.index;
+        let next = (index + 1) % self.memory.num_descriptors();
+        if next == regs.tx_head() {
+            return Err(RingFull);
+        }
+
+        let len = frame.len().max(min_len);
+        self.memory.write_buffer(index, frame, len);
+        self.memory.write_descriptor(
+            index,
+            TxDescriptor::new(self.memory.buffer_address(index), len),
+        );
+        self.index = next;
+
+        fence(Ordering::SeqCst);
+        regs.set_tx_tail(next);
+
+        Ok(())
+    }
+}
+
+/// The receive ring
+pub struct RxRing<M> {
+    memory: M,
+    /// The next descriptor to be written by the controller
+    index: usize,
+}
+
+impl<M: DescriptorMemory<RxDescriptor>> RxRing<M> {
+    /// Prepares all the descriptors to be given to the controller.
+    ///
+    /// The tail register is set to `memory.num_descriptors() - 1` when the receiver is enabled.
+    pub fn new(memory: M) -> Self {
+        for index in 0..memory.num_descriptors() {
+            memory.write_descriptor(index, RxDescriptor::new(memory.buffer_address(index)));
+        }
+        Self { memory, index: 0 }
+    }
+
+    #[inline]
+    pub const fn memory(&self) -> &M {
+        &self.memory
+    }
+
+    /// Passes the next received frame of up to `max_len` bytes to `f`,
+    /// or returns `None` if no frame has been received.
+    ///
+    /// Frames that span multiple buffers or have errors are discarded.
+    pub fn recv<R, F, T>(&mut self, regs: &R, max_len: usize, mut f: F) -> Option<T>
+    where
+        R: RingRegisters,
+        F: FnMut(&[u8]) -> T,
+    {
+        loop {
+            let index = self.index;
+            let desc = self.memory.read_descriptor(index);
+            if (desc.status & RxDescriptor::STATUS_DD) == 0 {
+                return None;
+            }
+            fence(Ordering::SeqCst);
+
+            let result = if (desc.status & RxDescriptor::STATUS_EOP) != 0 && desc.errors == 0 {
+                let len = (desc.length as usize).min(max_len);
+                Some(f(self.memory.buffer(index, len)))
+            } else {
+                None
+            };
+
+            // Give the descriptor back to the controller
+            self.memory
+                .write_descriptor(index, RxDescriptor::new(self.memory.buffer_address(index)));
+            self.index = (index + 1) % self.memory.num_descriptors();
+            fence(Ordering::SeqCst);
+            regs.set_rx_tail(index);
+
+            if result.is_some() {
+                return result;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{cell::Cell, vec, vec::Vec};
+
+    const BUFFER_BASE: u64 = 0x1000_0000;
+    const SIZE_OF_BUFFER: usize = 64;
+
+    struct MockMemory<T> {
+        descriptors: Vec<Cell<T>>,
+        buffers: Vec<Cell<u8>>,
+    }
+
+    impl<T: Copy + Default> MockMemory<T> {
+        fn new(len: usize) -> Self {
+            Self {
+                descriptors: (0..len).map(|_| Cell::new(T::default())).collect(),
+                buffers: vec![Cell::new(0xCC); len * SIZE_OF_BUFFER],
+            }
+        }
+    }
+
+    impl<T: Copy> DescriptorMemory<T> for MockMemory<T> {
+        fn num_descriptors(&self) -> usize {
+            self.descriptors.len()
+        }
+
+        fn read_descriptor(&self, index: usize) -> T {
+            self.descriptors[index].get()
+        }
+
+        fn write_descriptor(&self, index: usize, value: T) {
+            self.descriptors[index].set(value);
+        }
+
+        fn buffer_address(&self, index: usize) -> u64 {
+            BUFFER_BASE + (index * SIZE_OF_BUFFER) as u64
+        }
+
+        fn buffer(&self, index: usize, len: usize) -> &[u8] {
+            let buffer = &self.buffers[index * SIZE_OF_BUFFER..][..len];
+            // Cell<u8> has the same layout as u8
+            unsafe { core::slice::from_raw_parts(buffer.as_ptr() as *const u8, len) }
+        }
+
+        fn write_buffer(&self, index: usize, data: &[u8], len: usize) {
+            let buffer = &self.buffers[index * SIZE_OF_BUFFER..][..len];
+            for (index, byte) in buffer.iter().enumerate() {
+                byte.set(data.get(index).copied().unwrap_or(0));
+            }
+        }
+    }
+
+    #[derive(Default)]
+    struct MockRegisters {
+        tdh: Cell<usize>,
+        tdt: Cell<Option<usize>>,
+        rdt: Cell<Option<usize>>,
+    }
+
+    impl RingRegisters for MockRegisters {
+        fn tx_head(&self) -> usize {
+            self.tdh.get()
+        }
+
+        fn set_tx_tail(&self, value: usize) {
+            self.tdt.set(Some(value));
+        }
+
+        fn set_rx_tail(&self, value: usize) {
+            self.rdt.set(Some(value));
+        }
+    }
+
+    #[test]
+    fn send() {
+        let regs = MockRegisters::default();
+        let mut tx = TxRing::new(MockMemory::new(4));
+
+        tx.send(&regs, b"hello", 8).unwrap();
+        let desc = tx.memory().read_descriptor(0);
+        assert_eq!(desc.addr, BUFFER_BASE);
+        assert_eq!(desc.length, 8);
+        assert_eq!(
+            desc.cmd,
+            TxDescriptor::CMD_EOP | TxDescriptor::CMD_IFCS | TxDescriptor::CMD_RS
+        );
+        // padded to the minimum length
+        assert_eq!(tx.memory().buffer(0, 9), b"hello\0\0\0\xCC");
+        assert_eq!(regs.tdt.get(), Some(1));
+
+        tx.send(&regs, b"0123456789", 8).unwrap();
+        let desc = tx.memory().read_descriptor(1);
+        assert_eq!(desc.addr, BUFFER_BASE + SIZE_OF_BUFFER as u64);
+        assert_eq!(desc.length, 10);
+        assert_eq!(tx.memory().buffer(1, 10), b"0123456789");
+        assert_eq!(regs.tdt.get(), Some(2));
+    }
+
+    #[test]
+    fn send_full() {
+        let regs = MockRegisters::default();
+        let mut tx = TxRing::new(MockMemory::new(4));
+
+        // One descriptor is always left empty to tell the full ring from the empty one
+        for tail in 1..4 {
+            tx.send(&regs, b"frame", 8).unwrap();
+            assert_eq!(regs.tdt.get(), Some(tail));
+        }
+        assert_eq!(tx.send(&regs, b"full", 8), Err(RingFull));
+        assert_eq!(regs.tdt.get(), Some(3));
+        assert_eq!(tx.memory().read_descriptor(3), TxDescriptor::default());
+
+        // The controller has transmitted the first one, and the tail wraps around
+        regs.tdh.set(1);
+        tx.send(&regs, b"next", 8).unwrap();
+        assert_eq!(tx.memory().read_descriptor(3).length, 8);
+        assert_eq!(regs.tdt.get(), Some(0));
+        assert_eq!(tx.send(&regs, b"full", 8), Err(RingFull));
+    }
+
+    #[test]
+    fn recv() {
+        let regs = MockRegisters::default();
+        let mut rx = RxRing::new(MockMemory::new(4));
+        for index in 0..4 {
+            assert_eq!(
+                rx.memory().read_descriptor(index),
+                RxDescriptor::new(BUFFER_BASE + (index * SIZE_OF_BUFFER) as u64)
+            );
+        }
+        assert_eq!(rx.recv(&regs, SIZE_OF_BUFFER, |v| v.to_vec()), None);
+        assert_eq!(regs.rdt.get(), None);
+
+        // The controller writes a frame into the first descriptor
+        let memory = rx.memory();
+        memory.write_buffer(0, b"frame", 5);
+        memory.write_descriptor(
+            0,
+            RxDescriptor {
+                length: 5,
+                status: RxDescriptor::STATUS_DD | RxDescriptor::STATUS_EOP,
+                ..memory.read_descriptor(0)
+            },
+        );
+        assert_eq!(
+            rx.recv(&regs, SIZE_OF_BUFFER, |v| v.to_vec()),
+            Some(b"frame".to_vec())
+        );
+        // the descriptor is returned to the controller
+        assert_eq!(
+            rx.memory().read_descriptor(0),
+            RxDescriptor::new(BUFFER_BASE)
+        );
+        assert_eq!(regs.rdt.get(), Some(0));
+        assert_eq!(rx.recv(&regs, SIZE_OF_BUFFER, |v| v.to_vec()), None);
+
+        // A frame with errors and a partial frame are skipped
+        let memory = rx.memory();
+        let received = |errors, status, length| RxDescriptor {
+            length,
+            status: RxDescriptor::STATUS_DD | status,
+            errors,
+            ..RxDescriptor::new(0)
+        };
+        memory.write_descriptor(1, received(0x01, RxDescriptor::STATUS_EOP, 5));
+        memory.write_descriptor(2, received(0, 0, 5));
+        memory.write_buffer(3, b"next", 4);
+        memory.write_descriptor(3, received(0, RxDescriptor::STATUS_EOP, 4));
+        assert_eq!(rx.recv(&regs, 2, |v| v.to_vec()), Some(b"ne".to_vec()));
+        assert_eq!(regs.rdt.get(), Some(3));
+        for index in 1..4 {
+            assert_eq!(rx.memory().read_descriptor(index).status, 0);
+        }
+
+        // wraps around
+        let memory = rx.memory();
+        memory.write_descriptor(0, received(0, RxDescriptor::STATUS_EOP, 1));
+        assert_eq!(rx.recv(&regs, SIZE_OF_BUFFER, |v| v.len()), Some(1));
+        assert_eq!(regs.rdt.get(), Some(0));
+    }
+}
@@ -0,0 +1,4 @@
+//! Network controller rules independent of the kernel
+#![cfg_attr(not(test), no_std)]
+
+pub mod e1000;
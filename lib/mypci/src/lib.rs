@@ -0,0 +1,282 @@
+//! Peripheral Component Interconnect identifiers and registers
+#![no_std]
+
+use core::{fmt, time::Duration};
+
+#[repr(transparent)]
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq)]
+pub struct PciVendorId(pub u16);
+
+impl PciVendorId {
+    pub const INVALID_0000: Self = Self(0x0000);
+    pub const INVALID_FFFF: Self = Self(0xFFFF);
+
+    pub const VIRTIO: Self = Self(0x1AF4);
+
+    #[inline]
+    pub const fn is_valid(&self) -> bool {
+        self.0 != Self::INVALID_0000.0 && self.0 != Self::INVALID_FFFF.0
+    }
+}
+
+impl fmt::Debug for PciVendorId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_fmt(format_args!("VEN_{:04x}", self.0))
+    }
+}
+
+#[repr(transparent)]
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq)]
+pub struct PciDeviceId(pub u16);
+
+impl PciDeviceId {
+    pub const INVALID_0000: Self = Self(0x0000);
+    pub const INVALID_FFFF: Self = Self(0xFFFF);
+
+    pub const VIRTIO_MIN: Self = Self(0x1000);
+    pub const VIRTIO_MAX: Self = Self(0x107F);
+
+    #[inline]
+    pub const fn is_valid(&self) -> bool {
+        self.0 != Self::INVALID_0000.0 && self.0 != Self::INVALID_FFFF.0
+    }
+}
+
+impl fmt::Debug for PciDeviceId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_fmt(format_args!("DEV_{:04x}", self.0))
+    }
+}
+
+/// Selects the devices that a driver binds to
+#[derive(Clone, Copy)]
+pub enum PciDeviceMatcher {
+    /// Devices that match the class code, see [`PciClass::matches`]
+    Class(PciClass),
+    /// Any device of the vendor
+    Vendor(PciVendorId),
+    /// Devices of the vendor and the device ID
+    Device(PciVendorId, PciDeviceId),
+}
+
+impl PciDeviceMatcher {
+    /// Returns whether the device of the identifiers matches.
+    #[inline]
+    pub fn matches(
+        &self,
+        vendor_id: PciVendorId,
+        device_id: PciDeviceId,
+        class_code: PciClass,
+    ) -> bool {
+        match *self {
+            Self::Class(v) => class_code.matches(v),
+            Self::Vendor(v) => vendor_id == v,
+            Self::Device(v, d) => vendor_id == v && device_id == d,
+        }
+    }
+}
+
+/// Device power states of PCI Power Management
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum PciPowerState {
+    D0 = 0,
+    D1,
+    D2,
+    D3Hot,
+}
+
+impl PciPowerState {
+    /// PowerState field of PMCSR
+    const PMCSR_POWER_STATE: u32 = 0x0000_0003;
+    /// PME_Status of PMCSR is cleared by writing 1
+    const PMCSR_PME_STATUS: u32 = 0x0000_8000;
+    /// D1_Support of PMC
+    const PMC_D1_SUPPORT: u32 = 0x0200_0000;
+    /// D2_Support of PMC
+    const PMC_D2_SUPPORT: u32 = 0x0400_0000;
+
+    #[inline]
+    pub const fn from_pmcsr(pmcsr: u32) -> Self {
+        match pmcsr & Self::PMCSR_POWER_STATE {
+            0 => Self::D0,
+            1 => Self::D1,
+            2 => Self::D2,
+            _ => Self::D3Hot,
+        }
+    }
+
+    /// Returns the value of the PMCSR register to be written to enter this state.
+    #[inline]
+    pub const fn pmcsr(&self, current: u32) -> u32 {
+        (current & !(Self::PMCSR_POWER_STATE | Self::PMCSR_PME_STATUS)) | *self as u32
+    }
+
+    /// Returns whether the device supports this state from the first register of the capability.
+    #[inline]
+    pub const fn is_supported(&self, pmc: u32) -> bool {
+        match self {
+            Self::D0 | Self::D3Hot => true,
+            Self::D1 => (pmc & Self::PMC_D1_SUPPORT) != 0,
+            Self::D2 => (pmc & Self::PMC_D2_SUPPORT) != 0,
+        }
+    }
+
+    /// Returns the delay required after the transition before the device can be accessed.
+    pub const fn settling_time(from: Self, to: Self) -> Duration {
+        match (from, to) {
+            (Self::D3Hot, _) | (_, Self::D3Hot) => Duration::from_millis(10),
+            (Self::D2, _) | (_, Self::D2) => Duration::from_micros(200),
+            _ => Duration::from_micros(0),
+        }
+    }
+}
+
+/// A type that defines the PCI class code and interface.
+///
+/// For example, the class code for XHCI (`0x0C_03_30`) is expressed as follows.
+/// ```
+/// # use mypci::PciClass;
+/// let cc = PciClass::code(0x0C).sub(0x03).interface(0x30);
+/// ```
+///
+/// To see if one class code is included in another class code or interface, compare the following
+/// ```
+/// # use mypci::PciClass;
+/// # let cc = PciClass::code(0x0C).sub(0x03).interface(0x30);
+/// let mask = PciClass::code(0x0C).sub(0x03);
+/// if cc.matches(mask) {
+///   // code here
+/// }
+/// ```
+#[repr(transparent)]
+#[derive(Clone, Copy)]
+pub struct PciClass(pub u32);
+
+impl PciClass {
+    /// Makes an instance from the PCI class code register (0x02)
+    #[inline]
+    pub const fn from_pci(data: u32) -> Self {
+        Self((data & 0xFF_FF_FF_00) | PciClassType::Interface as u32)
+    }
+
+    /// Makes an instance from class code.
+    #[inline]
+    pub const fn code(code: u8) -> Self {
+        Self(((code as u32) << 24) | PciClassType::ClassCode as u32)
+    }
+
+    /// Chains subclasses to the class code.
+    #[inline]
+    pub const fn sub(self, sub: u8) -> Self {
+        Self(self.0 & 0xFF_00_00_00 | ((sub as u32) << 16) | PciClassType::Subclass as u32)
+    }
+
+    /// Chains the programming interface to the class code and subclasses.
+    #[inline]
+    pub const fn interface(self, interface: u8) -> Self {
+        Self(self.0 & 0xFF_FF_00_00 | ((interface as u32) << 8) | PciClassType::Interface as u32)
+    }
+
+    #[inline]
+    const fn class_type(&self) -> PciClassType {
+        PciClassType::from_raw(self.0 & 0xFF)
+    }
+
+    #[inline]
+    pub const fn raw_data(&self) -> u32 {
+        self.0 & self.class_type().mask()
+    }
+
+    #[inline]
+    pub const fn data(&self) -> u32 {
+        self.raw_data() >> 8
+    }
+
+    #[inline]
+    pub const fn get_class_code(&self) -> u8 {
+        (self.0 >> 24) as u8
+    }
+
+    #[inline]
+    pub const fn get_sub_class(&self) -> u8 {
+        (self.0 >> 16) as u8
+    }
+
+    #[inline]
+    pub const fn get_interface(&self) -> u8 {
+        (self.0 >> 8) as u8
+    }
+
+    /// Returns whether or not this instance matches the specified class code, subclass, or programming interface.
+    #[inline]
+    pub const fn matches(&self, other: Self) -> bool {
+        match other.class_type() {
+            PciClassType::Unspecified => false,
+            _ => {
+                if self.class_type().mask() < other.class_type().mask() {
+                    false
+                } else {
+                    let mask = other.class_type().mask();
+                    (self.0 & mask) == (other.0 & mask)
+                }
+            }
+        }
+    }
+}
+
+impl fmt::Debug for PciClass {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // f.debug_tuple("PciClass").field(&self.0).finish()
+        match self.class_type() {
+            PciClassType::Unspecified => f.write_str("PciClass::Unspecified"),
+            PciClassType::ClassCode => {
+                f.write_fmt(format_args!("CC_{:02x}", self.get_class_code()))
+            }
+            PciClassType::Subclass => f.write_fmt(format_args!(
+                "CC_{:02x}{:02x}",
+                self.get_class_code(),
+                self.get_sub_class()
+            )),
+            PciClassType::Interface => f.write_fmt(format_args!(
+                "CC_{:02x}{:02x}{:02x}",
+                self.get_class_code(),
+                self.get_sub_class(),
+                self.get_interface()
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum PciClassType {
+    Unspecified = 0,
+    ClassCode = 1,
+    Subclass = 2,
+    Interface = 3,
+}
+
+impl PciClassType {
+    #[inline]
+    pub const fn mask(&self) -> u32 {
+        match *self {
+            PciClassType::Unspecified => 0,
+            PciClassType::ClassCode => 0xFF_00_00_00,
+            PciClassType::Subclass => 0xFF_FF_00_00,
+            PciClassType::Interface => 0xFF_FF_FF_00,
+        }
+    }
+
+    #[inline]
+    pub const fn from_raw(raw: u32) -> Self {
+        match raw {
+            1 => Self::ClassCode,
+            2 => Self::Subclass,
+            3 => Self::Interface,
+            _ => Self::Unspecified,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests;
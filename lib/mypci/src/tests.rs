@@ -0,0 +1,104 @@
+use super::*;
+extern crate alloc;
+use alloc::format;
+
+#[test]
+fn class_code() {
+    // the class code register of an XHCI controller, revision 0x10
+    let xhci = PciClass::from_pci(0x0C03_3010);
+    assert_eq!(xhci.get_class_code(), 0x0C);
+    assert_eq!(xhci.get_sub_class(), 0x03);
+    assert_eq!(xhci.get_interface(), 0x30);
+    assert_eq!(xhci.data(), 0x0C_03_30);
+
+    assert_eq!(PciClass::code(0x0C).data(), 0x0C_00_00);
+    assert_eq!(PciClass::code(0x0C).sub(0x03).data(), 0x0C_03_00);
+    assert_eq!(
+        PciClass::code(0x0C).sub(0x03).interface(0x30).raw_data(),
+        PciClass::from_pci(0x0C03_3000).raw_data()
+    );
+
+    assert!(xhci.matches(PciClass::code(0x0C)));
+    assert!(xhci.matches(PciClass::code(0x0C).sub(0x03)));
+    assert!(xhci.matches(PciClass::code(0x0C).sub(0x03).interface(0x30)));
+    // EHCI
+    assert!(!xhci.matches(PciClass::code(0x0C).sub(0x03).interface(0x20)));
+    assert!(!xhci.matches(PciClass::code(0x0C).sub(0x05)));
+    assert!(!xhci.matches(PciClass(0)));
+    // a subclass does not match a more specific interface
+    assert!(!PciClass::code(0x0C)
+        .sub(0x03)
+        .matches(PciClass::code(0x0C).sub(0x03).interface(0x30)));
+}
+
+#[test]
+fn device_matcher() {
+    let intel = PciVendorId(0x8086);
+    let e1000 = PciDeviceId(0x100E);
+    let ethernet = PciClass::from_pci(0x0200_0003);
+
+    assert!(PciDeviceMatcher::Class(PciClass::code(0x02)).matches(intel, e1000, ethernet));
+    assert!(
+        !PciDeviceMatcher::Class(PciClass::code(0x04).sub(0x03)).matches(intel, e1000, ethernet)
+    );
+
+    assert!(PciDeviceMatcher::Vendor(intel).matches(intel, e1000, ethernet));
+    assert!(!PciDeviceMatcher::Vendor(PciVendorId::VIRTIO).matches(intel, e1000, ethernet));
+
+    assert!(PciDeviceMatcher::Device(intel, e1000).matches(intel, e1000, ethernet));
+    assert!(!PciDeviceMatcher::Device(intel, PciDeviceId(0x10D3)).matches(intel, e1000, ethernet));
+    assert!(!PciDeviceMatcher::Device(PciVendorId(0x10EC), e1000).matches(intel, e1000, ethernet));
+}
+
+#[test]
+fn ids() {
+    assert!(PciVendorId(0x8086).is_valid());
+    assert!(!PciVendorId::INVALID_0000.is_valid());
+    assert!(!PciVendorId::INVALID_FFFF.is_valid());
+    assert!(!PciDeviceId::INVALID_FFFF.is_valid());
+
+    assert_eq!(format!("{:?}", PciVendorId(0x8086)), "VEN_8086");
+    assert_eq!(format!("{:?}", PciDeviceId(0x100E)), "DEV_100e");
+    assert_eq!(
+        format!("{:?}", PciClass::from_pci(0x0C03_3010)),
+        "CC_0c0330"
+    );
+    assert_eq!(format!("{:?}", PciClass::code(0x02)), "CC_02");
+}
+
+#[test]
+fn power_state() {
+    assert_eq!(PciPowerState::from_pmcsr(0x0000_0000), PciPowerState::D0);
+    assert_eq!(PciPowerState::from_pmcsr(0x0000_8102), PciPowerState::D2);
+    assert_eq!(PciPowerState::from_pmcsr(0x0000_0003), PciPowerState::D3Hot);
+
+    // keeps PME_En and the data select, and does not clear PME_Status by writing it back
+    let pmcsr = 0x0000_8103;
+    assert_eq!(PciPowerState::D0.pmcsr(pmcsr), 0x0000_0100);
+    assert_eq!(PciPowerState::D1.pmcsr(pmcsr), 0x0000_0101);
+
+    // only D1 is supported
+    let pmc = 0x0203_0003;
+    assert!(PciPowerState::D0.is_supported(pmc));
+    assert!(PciPowerState::D1.is_supported(pmc));
+    assert!(!PciPowerState::D2.is_supported(pmc));
+    assert!(PciPowerState::D3Hot.is_supported(pmc));
+
+    let settling_time = PciPowerState::settling_time;
+    assert_eq!(
+        settling_time(PciPowerState::D0, PciPowerState::D3Hot),
+        Duration::from_millis(10)
+    );
+    assert_eq!(
+        settling_time(PciPowerState::D3Hot, PciPowerState::D0),
+        Duration::from_millis(10)
+    );
+    assert_eq!(
+        settling_time(PciPowerState::D2, PciPowerState::D0),
+        Duration::from_micros(200)
+    );
+    assert_eq!(
+        settling_time(PciPowerState::D0, PciPowerState::D1),
+        Duration::ZERO
+    );
+}
@@ -0,0 +1,227 @@
+//! System Management BIOS structures
+#![no_std]
+
+mod views;
+pub use views::*;
+
+use core::str;
+
+#[repr(transparent)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct HeaderType(pub u8);
+
+impl HeaderType {
+    pub const BIOS_INFO: Self = Self(0);
+    pub const SYSTEM_INFO: Self = Self(1);
+    pub const BASEBOARD_INFO: Self = Self(2);
+    pub const SYSTEM_ENCLOSURE: Self = Self(3);
+    pub const PROCESSOR_INFO: Self = Self(4);
+    pub const MEMORY_CONTROLLER_INFO: Self = Self(5);
+    pub const MEMORY_MODULE_INFO: Self = Self(6);
+    pub const CACHE_INFO: Self = Self(7);
+    pub const PORT_CONNECTOR_INFO: Self = Self(8);
+    pub const SYSTEM_SLOTS: Self = Self(9);
+    pub const ONBOARD_DEVICE_INFO: Self = Self(10);
+    pub const OEM_STRINGS: Self = Self(11);
+    pub const SYSTEM_CONFIGURATION_OPTIONS: Self = Self(12);
+    pub const BIOS_LANGUAGE_INFO: Self = Self(13);
+    pub const GROUP_ASSOCIATIONS: Self = Self(14);
+    pub const SYSTEM_EVENT_LOG: Self = Self(15);
+    pub const PHYSICAL_MEMORY_ARRAY: Self = Self(16);
+    pub const MEMORY_DEVICE: Self = Self(17);
+    pub const _32BIT_MEMORY_ERROR_INFO: Self = Self(18);
+    pub const MEMORY_ARRAY_MAPPED_ADDRESS: Self = Self(19);
+    pub const MEMORY_DEVICE_MAPPED_ADDRESS: Self = Self(20);
+    pub const BUILT_IN_POINTING_DEVICE: Self = Self(21);
+    pub const PORTABLE_BATTERY: Self = Self(22);
+    pub const SYSTEM_RESET: Self = Self(23);
+    pub const HARDWARE_SECURITY: Self = Self(24);
+    pub const SYSTEM_POWER_CONTROLS: Self = Self(25);
+    pub const VOLTAGE_PROBE: Self = Self(26);
+    pub const COOLING_DEVICE: Self = Self(27);
+    pub const TEMPERATURE_PROBE: Self = Self(28);
+    pub const ELECTRICAL_CURRENT_PROBE: Self = Self(29);
+    pub const OUT_OF_BAND_REMOTE_ACCESS: Self = Self(30);
+    pub const BOOT_INTEGRITY_SERVICE: Self = Self(31);
+    pub const SYSTEM_BOOT_INFO: Self = Self(32);
+    pub const _64BIT_MEMORY_ERROR_INFO: Self = Self(33);
+    pub const MANAGEMENT_DEVICE: Self = Self(34);
+    pub const MANAGEMENT_DEVICE_COMPONENT: Self = Self(35);
+    pub const MANAGEMENT_DEVICE_THRESHOLD_DATA: Self = Self(36);
+    pub const MEMORY_CHANNEL: Self = Self(37);
+    pub const IPMI_DEVICE_INFO: Self = Self(38);
+    pub const SYSTEM_POWER_SUPPLY: Self = Self(39);
+    pub const ADDITIONAL_INFO: Self = Self(40);
+    pub const ONBOARD_DEVICES_EXTENDED_INFO: Self = Self(41);
+    pub const MANAGEMENT_CONTROLLER_HOST_INTERFACE: Self = Self(42);
+    pub const TPM_DEVICE: Self = Self(43);
+    pub const PROCESSOR_ADDITIONAL_INFO: Self = Self(44);
+}
+
+/// An iterator over the structures in the structure table
+#[derive(Debug, Clone)]
+pub struct SmBiosStructIterator<'a> {
+    table: &'a [u8],
+    remaining: usize,
+}
+
+impl<'a> SmBiosStructIterator<'a> {
+    /// Iterates up to `n_structures` structures, and stops at the first broken one.
+    #[inline]
+    pub const fn new(table: &'a [u8], n_structures: usize) -> Self {
+        Self {
+            table,
+            remaining: n_structures,
+        }
+    }
+}
+
+impl<'a> Iterator for SmBiosStructIterator<'a> {
+    type Item = SmBiosHeader<'a>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        let Some(header) = SmBiosHeader::parse(self.table) else {
+            self.remaining = 0;
+            return None;
+        };
+        self.table = &self.table[header.struct_size()..];
+        self.remaining -= 1;
+        Some(header)
+    }
+}
+
+/// Common definition of SmBios's structures
+///
+/// A structure is the formatted area that begins with the header, followed by the strings.
+#[derive(Debug, Clone, Copy)]
+pub struct SmBiosHeader<'a> {
+    formatted: &'a [u8],
+    strings: &'a [u8],
+}
+
+impl<'a> SmBiosHeader<'a> {
+    /// Some products return meaningless strings.
+    pub const DEFAULT_STRING: &'static str = "Default string";
+    /// Some products return meaningless strings.
+    pub const TO_BE_FILLED_BY_OEM: &'static str = "To be filled by O.E.M.";
+
+    const MIN_SIZE: usize = 4;
+
+    /// Parses the structure at the beginning of the bytes.
+    ///
+    /// Returns `None` if the formatted area or the double NULL that terminates the strings
+    /// does not fit in the bytes.
+    pub fn parse(bytes: &'a [u8]) -> Option<Self> {
+        let size = *bytes.get(1)? as usize;
+        if size < Self::MIN_SIZE {
+            return None;
+        }
+        let (formatted, rest) = bytes.split_at_checked(size)?;
+        let end = rest.windows(2).position(|v| v == [0, 0])? + 2;
+        Some(Self {
+            formatted,
+            strings: &rest[..end],
+        })
+    }
+
+    #[inline]
+    pub const fn header_type(&self) -> HeaderType {
+        HeaderType(self.formatted[0])
+    }
+
+    #[inline]
+    pub const fn header_size(&self) -> usize {
+        self.formatted.len()
+    }
+
+    #[inline]
+    pub const fn handle(&self) -> u16 {
+        u16::from_le_bytes([self.formatted[2], self.formatted[3]])
+    }
+
+    /// Returns the formatted area of the structure.
+    #[inline]
+    pub const fn as_slice(&self) -> &'a [u8] {
+        self.formatted
+    }
+
+    /// Returns the byte at the offset from the beginning of the structure, if the structure
+    /// is long enough.
+    #[inline]
+    pub fn byte(&self, offset: usize) -> Option<u8> {
+        self.formatted.get(offset).copied()
+    }
+
+    #[inline]
+    pub fn word(&self, offset: usize) -> Option<u16> {
+        self.formatted
+            .get(offset..offset + 2)
+            .map(|v| u16::from_le_bytes(v.try_into().unwrap()))
+    }
+
+    #[inline]
+    pub fn dword(&self, offset: usize) -> Option<u32> {
+        self.formatted
+            .get(offset..offset + 4)
+            .map(|v| u32::from_le_bytes(v.try_into().unwrap()))
+    }
+
+    #[inline]
+    pub fn qword(&self, offset: usize) -> Option<u64> {
+        self.formatted
+            .get(offset..offset + 8)
+            .map(|v| u64::from_le_bytes(v.try_into().unwrap()))
+    }
+
+    /// Returns the string referenced by the string number at the offset.
+    #[inline]
+    pub fn string_at(&self, offset: usize) -> Option<&'a str> {
+        self.byte(offset).and_then(|v| self.string(v as usize))
+    }
+
+    /// Returns the typed view of this structure.
+    pub fn structure(self) -> SmBiosStructure<'a> {
+        match self.header_type() {
+            HeaderType::BIOS_INFO => SmBiosStructure::BiosInfo(BiosInfo(self)),
+            HeaderType::SYSTEM_INFO => SmBiosStructure::SystemInfo(SystemInfo(self)),
+            HeaderType::BASEBOARD_INFO => SmBiosStructure::BaseboardInfo(BaseboardInfo(self)),
+            HeaderType::PROCESSOR_INFO => SmBiosStructure::ProcessorInfo(ProcessorInfo(self)),
+            HeaderType::MEMORY_DEVICE => SmBiosStructure::MemoryDevice(MemoryDevice(self)),
+            _ => SmBiosStructure::Other(self),
+        }
+    }
+
+    #[inline]
+    fn strings(&self) -> impl Iterator<Item = &'a str> {
+        self.strings
+            .split(|v| *v == 0)
+            .take_while(|v| !v.is_empty())
+            .map(|v| str::from_utf8(v).unwrap_or("?"))
+    }
+
+    /// Returns the string of the string number, which starts from 1.
+    #[inline]
+    pub fn string(&self, index: usize) -> Option<&'a str> {
+        if index > 0 {
+            self.strings().nth(index - 1).and_then(|v| match v {
+                Self::DEFAULT_STRING | Self::TO_BE_FILLED_BY_OEM => None,
+                _ => Some(v),
+            })
+        } else {
+            None
+        }
+    }
+
+    /// Returns the size of the structure including the strings.
+    #[inline]
+    pub const fn struct_size(&self) -> usize {
+        self.formatted.len() + self.strings.len()
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,190 @@
+use super::*;
+extern crate alloc;
+use alloc::{vec, vec::Vec};
+
+/// Appends a structure with the formatted area and the strings.
+fn push_struct(table: &mut Vec<u8>, formatted: &[u8], strings: &[&str]) {
+    table.extend_from_slice(formatted);
+    if strings.is_empty() {
+        table.push(0);
+    }
+    for string in strings {
+        table.extend_from_slice(string.as_bytes());
+        table.push(0);
+    }
+    table.push(0);
+}
+
+fn formatted(
+    header_type: HeaderType,
+    size: usize,
+    handle: u16,
+    fields: &[(usize, &[u8])],
+) -> Vec<u8> {
+    let mut result = vec![0; size];
+    result[0] = header_type.0;
+    result[1] = size as u8;
+    result[2..4].copy_from_slice(&handle.to_le_bytes());
+    for (offset, bytes) in fields {
+        result[*offset..*offset + bytes.len()].copy_from_slice(bytes);
+    }
+    result
+}
+
+fn sample_table() -> Vec<u8> {
+    let mut table = Vec::new();
+    push_struct(
+        &mut table,
+        &formatted(
+            HeaderType::BIOS_INFO,
+            0x18,
+            0x0000,
+            &[(0x04, &[1, 2]), (0x08, &[3, 0x0F]), (0x14, &[2, 7])],
+        ),
+        &["Vendor", "1.23", "01/02/2026"],
+    );
+    push_struct(
+        &mut table,
+        &formatted(
+            HeaderType::SYSTEM_INFO,
+            0x1B,
+            0x0001,
+            &[(0x04, &[1, 2, 3, 0]), (0x08, &[0x55; 16])],
+        ),
+        &["ACME", "Model X", SmBiosHeader::TO_BE_FILLED_BY_OEM],
+    );
+    // processor information of an old version without the core count
+    push_struct(
+        &mut table,
+        &formatted(
+            HeaderType::PROCESSOR_INFO,
+            0x1A,
+            0x0004,
+            &[(0x04, &[1]), (0x14, &3600u16.to_le_bytes())],
+        ),
+        &["CPU0"],
+    );
+    // 32GiB is in the extended size
+    push_struct(
+        &mut table,
+        &formatted(
+            HeaderType::MEMORY_DEVICE,
+            0x22,
+            0x0011,
+            &[
+                (0x0C, &0x7FFFu16.to_le_bytes()),
+                (0x10, &[1, 0, 0x1A]),
+                (0x15, &3200u16.to_le_bytes()),
+                (0x1C, &32768u32.to_le_bytes()),
+            ],
+        ),
+        &["DIMM 0"],
+    );
+    // end of table
+    push_struct(&mut table, &formatted(HeaderType(127), 4, 0xFEFF, &[]), &[]);
+    table
+}
+
+#[test]
+fn iterate_structures() {
+    let table = sample_table();
+    let headers = SmBiosStructIterator::new(&table, 5).collect::<Vec<_>>();
+    assert_eq!(
+        headers.iter().map(|v| v.header_type()).collect::<Vec<_>>(),
+        [
+            HeaderType::BIOS_INFO,
+            HeaderType::SYSTEM_INFO,
+            HeaderType::PROCESSOR_INFO,
+            HeaderType::MEMORY_DEVICE,
+            HeaderType(127),
+        ]
+    );
+    assert_eq!(headers[1].handle(), 0x0001);
+    assert_eq!(headers[1].header_size(), 0x1B);
+    assert_eq!(headers[4].handle(), 0xFEFF);
+    // a structure without strings ends with a double NULL
+    assert_eq!(headers[4].struct_size(), 6);
+    assert_eq!(
+        headers.iter().map(|v| v.struct_size()).sum::<usize>(),
+        table.len()
+    );
+
+    // the number of structures is a limit
+    assert_eq!(SmBiosStructIterator::new(&table, 2).count(), 2);
+    // stops at the truncated structure
+    assert_eq!(
+        SmBiosStructIterator::new(&table[..table.len() - 1], 5).count(),
+        4
+    );
+}
+
+#[test]
+fn parse_header() {
+    assert!(SmBiosHeader::parse(&[]).is_none());
+    // shorter than the header
+    assert!(SmBiosHeader::parse(&[1, 3, 0, 0, 0]).is_none());
+    // the formatted area does not fit
+    assert!(SmBiosHeader::parse(&[1, 8, 0, 0, 0, 0]).is_none());
+    // no double NULL
+    assert!(SmBiosHeader::parse(&[1, 4, 0, 0, b'a', 0]).is_none());
+
+    let header = SmBiosHeader::parse(&[1, 5, 0x34, 0x12, 1, b'a', 0, b'b', 0, 0, 0xFF]).unwrap();
+    assert_eq!(header.handle(), 0x1234);
+    assert_eq!(header.as_slice(), &[1, 5, 0x34, 0x12, 1]);
+    assert_eq!(header.struct_size(), 10);
+    assert_eq!(header.string(0), None);
+    assert_eq!(header.string(1), Some("a"));
+    assert_eq!(header.string(2), Some("b"));
+    assert_eq!(header.string(3), None);
+    assert_eq!(header.string_at(4), Some("a"));
+    assert_eq!(header.word(3), Some(0x0112));
+    assert_eq!(header.dword(2), None);
+}
+
+#[test]
+fn typed_views() {
+    let table = sample_table();
+    let mut structures = SmBiosStructIterator::new(&table, 5).map(|v| v.structure());
+
+    let Some(SmBiosStructure::BiosInfo(bios)) = structures.next() else {
+        panic!()
+    };
+    assert_eq!(bios.vendor(), Some("Vendor"));
+    assert_eq!(bios.version(), Some("1.23"));
+    assert_eq!(bios.release_date(), Some("01/02/2026"));
+    assert_eq!(bios.rom_size(), Some(0x10_0000));
+    assert_eq!(bios.release(), Some((2, 7)));
+
+    let Some(SmBiosStructure::SystemInfo(system)) = structures.next() else {
+        panic!()
+    };
+    assert_eq!(system.manufacturer(), Some("ACME"));
+    assert_eq!(system.product_name(), Some("Model X"));
+    // placeholders are not returned
+    assert_eq!(system.version(), None);
+    assert_eq!(system.serial_number(), None);
+    assert_eq!(system.uuid(), Some([0x55; 16]));
+
+    let Some(SmBiosStructure::ProcessorInfo(processor)) = structures.next() else {
+        panic!()
+    };
+    assert_eq!(processor.socket_designation(), Some("CPU0"));
+    assert_eq!(processor.max_speed(), Some(3600));
+    assert_eq!(processor.current_speed(), None);
+    assert_eq!(processor.core_count(), None);
+
+    let Some(SmBiosStructure::MemoryDevice(memory)) = structures.next() else {
+        panic!()
+    };
+    assert_eq!(memory.device_locator(), Some("DIMM 0"));
+    assert_eq!(memory.bank_locator(), None);
+    assert_eq!(memory.size(), Some(32 << 30));
+    assert_eq!(memory.memory_type(), Some(0x1A));
+    assert_eq!(memory.speed(), Some(3200));
+
+    let Some(SmBiosStructure::Other(other)) = structures.next() else {
+        panic!()
+    };
+    assert_eq!(other.header_type(), HeaderType(127));
+    assert!(structures.next().is_none());
+}
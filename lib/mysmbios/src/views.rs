@@ -0,0 +1,247 @@
+//! Typed views of the structures
+
+use crate::SmBiosHeader;
+
+/// Typed views of SMBIOS structures
+///
+/// Fields added in later versions of the specification are `None` if the structure is too short.
+#[non_exhaustive]
+#[derive(Clone, Copy)]
+pub enum SmBiosStructure<'a> {
+    BiosInfo(BiosInfo<'a>),
+    SystemInfo(SystemInfo<'a>),
+    BaseboardInfo(BaseboardInfo<'a>),
+    ProcessorInfo(ProcessorInfo<'a>),
+    MemoryDevice(MemoryDevice<'a>),
+    Other(SmBiosHeader<'a>),
+}
+
+impl<'a> SmBiosStructure<'a> {
+    #[inline]
+    pub const fn header(&self) -> SmBiosHeader<'a> {
+        match self {
+            Self::BiosInfo(v) => v.0,
+            Self::SystemInfo(v) => v.0,
+            Self::BaseboardInfo(v) => v.0,
+            Self::ProcessorInfo(v) => v.0,
+            Self::MemoryDevice(v) => v.0,
+            Self::Other(v) => *v,
+        }
+    }
+}
+
+/// BIOS Information (Type 0)
+#[derive(Clone, Copy)]
+pub struct BiosInfo<'a>(pub(crate) SmBiosHeader<'a>);
+
+impl<'a> BiosInfo<'a> {
+    #[inline]
+    pub fn vendor(&self) -> Option<&'a str> {
+        self.0.string_at(0x04)
+    }
+
+    #[inline]
+    pub fn version(&self) -> Option<&'a str> {
+        self.0.string_at(0x05)
+    }
+
+    #[inline]
+    pub fn release_date(&self) -> Option<&'a str> {
+        self.0.string_at(0x08)
+    }
+
+    /// The size of the BIOS ROM in bytes
+    #[inline]
+    pub fn rom_size(&self) -> Option<usize> {
+        self.0.byte(0x09).map(|v| (v as usize + 1) * 0x10000)
+    }
+
+    /// Major and minor release of the system BIOS
+    #[inline]
+    pub fn release(&self) -> Option<(u8, u8)> {
+        self.0.byte(0x14).zip(self.0.byte(0x15))
+    }
+}
+
+/// System Information (Type 1)
+#[derive(Clone, Copy)]
+pub struct SystemInfo<'a>(pub(crate) SmBiosHeader<'a>);
+
+impl<'a> SystemInfo<'a> {
+    #[inline]
+    pub fn manufacturer(&self) -> Option<&'a str> {
+        self.0.string_at(0x04)
+    }
+
+    #[inline]
+    pub fn product_name(&self) -> Option<&'a str> {
+        self.0.string_at(0x05)
+    }
+
+    #[inline]
+    pub fn version(&self) -> Option<&'a str> {
+        self.0.string_at(0x06)
+    }
+
+    #[inline]
+    pub fn serial_number(&self) -> Option<&'a str> {
+        self.0.string_at(0x07)
+    }
+
+    #[inline]
+    pub fn uuid(&self) -> Option<[u8; 16]> {
+        self.0
+            .as_slice()
+            .get(0x08..0x18)
+            .map(|v| v.try_into().unwrap())
+    }
+
+    #[inline]
+    pub fn sku_number(&self) -> Option<&'a str> {
+        self.0.string_at(0x19)
+    }
+
+    #[inline]
+    pub fn family(&self) -> Option<&'a str> {
+        self.0.string_at(0x1A)
+    }
+}
+
+/// Baseboard Information (Type 2)
+#[derive(Clone, Copy)]
+pub struct BaseboardInfo<'a>(pub(crate) SmBiosHeader<'a>);
+
+impl<'a> BaseboardInfo<'a> {
+    #[inline]
+    pub fn manufacturer(&self) -> Option<&'a str> {
+        self.0.string_at(0x04)
+    }
+
+    #[inline]
+    pub fn product(&self) -> Option<&'a str> {
+        self.0.string_at(0x05)
+    }
+
+    #[inline]
+    pub fn version(&self) -> Option<&'a str> {
+        self.0.string_at(0x06)
+    }
+
+    #[inline]
+    pub fn serial_number(&self) -> Option<&'a str> {
+        self.0.string_at(0x07)
+    }
+
+    #[inline]
+    pub fn asset_tag(&self) -> Option<&'a str> {
+        self.0.string_at(0x08)
+    }
+}
+
+/// Processor Information (Type 4)
+#[derive(Clone, Copy)]
+pub struct ProcessorInfo<'a>(pub(crate) SmBiosHeader<'a>);
+
+impl<'a> ProcessorInfo<'a> {
+    #[inline]
+    pub fn socket_designation(&self) -> Option<&'a str> {
+        self.0.string_at(0x04)
+    }
+
+    #[inline]
+    pub fn manufacturer(&self) -> Option<&'a str> {
+        self.0.string_at(0x07)
+    }
+
+    #[inline]
+    pub fn version(&self) -> Option<&'a str> {
+        self.0.string_at(0x10)
+    }
+
+    /// Maximum speed in MHz
+    #[inline]
+    pub fn max_speed(&self) -> Option<u16> {
+        self.0.word(0x14).filter(|v| *v != 0)
+    }
+
+    /// Current speed in MHz
+    #[inline]
+    pub fn current_speed(&self) -> Option<u16> {
+        self.0.word(0x16).filter(|v| *v != 0)
+    }
+
+    #[inline]
+    pub fn core_count(&self) -> Option<usize> {
+        match self.0.byte(0x23)? {
+            0 => None,
+            0xFF => self.0.word(0x2A).map(|v| v as usize),
+            v => Some(v as usize),
+        }
+    }
+
+    #[inline]
+    pub fn thread_count(&self) -> Option<usize> {
+        match self.0.byte(0x25)? {
+            0 => None,
+            0xFF => self.0.word(0x2E).map(|v| v as usize),
+            v => Some(v as usize),
+        }
+    }
+}
+
+/// Memory Device (Type 17)
+#[derive(Clone, Copy)]
+pub struct MemoryDevice<'a>(pub(crate) SmBiosHeader<'a>);
+
+impl<'a> MemoryDevice<'a> {
+    #[inline]
+    pub fn device_locator(&self) -> Option<&'a str> {
+        self.0.string_at(0x10)
+    }
+
+    #[inline]
+    pub fn bank_locator(&self) -> Option<&'a str> {
+        self.0.string_at(0x11)
+    }
+
+    /// The size of the memory device in bytes, or `None` if no device is installed or unknown
+    pub fn size(&self) -> Option<u64> {
+        match self.0.word(0x0C)? {
+            0 | 0xFFFF => None,
+            // The actual size is in the Extended Size field in megabytes
+            0x7FFF => self
+                .0
+                .dword(0x1C)
+                .map(|v| (v & 0x7FFF_FFFF) as u64 * 1024 * 1024),
+            v if (v & 0x8000) != 0 => Some((v & 0x7FFF) as u64 * 1024),
+            v => Some(v as u64 * 1024 * 1024),
+        }
+    }
+
+    /// Memory type such as DDR4 (0x1A)
+    #[inline]
+    pub fn memory_type(&self) -> Option<u8> {
+        self.0.byte(0x12)
+    }
+
+    /// Maximum speed in MT/s
+    #[inline]
+    pub fn speed(&self) -> Option<u16> {
+        self.0.word(0x15).filter(|v| *v != 0)
+    }
+
+    #[inline]
+    pub fn manufacturer(&self) -> Option<&'a str> {
+        self.0.string_at(0x17)
+    }
+
+    #[inline]
+    pub fn serial_number(&self) -> Option<&'a str> {
+        self.0.string_at(0x18)
+    }
+
+    #[inline]
+    pub fn part_number(&self) -> Option<&'a str> {
+        self.0.string_at(0x1A)
+    }
+}
@@ -0,0 +1,264 @@
+//! Bounded byte channel between threads
+//!
+//! The blocking and the queue are provided by the kernel, so that the logic of the end of the
+//! stream can be tested with the threads of the host.
+
+use alloc::sync::Arc;
+use core::{
+    fmt,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+/// A counting semaphore that blocks the current thread
+pub trait ChannelSemaphore {
+    fn new(value: usize) -> Self;
+
+    /// Takes a token if available without blocking.
+    fn try_lock(&self) -> bool;
+
+    /// Takes a token, blocking until one is available.
+    fn wait(&self);
+
+    /// Returns a token and wakes up a waiting thread.
+    fn signal(&self);
+}
+
+/// A bounded first in first out queue shared by threads
+pub trait ChannelFifo<T> {
+    fn with_capacity(capacity: usize) -> Self;
+
+    fn enqueue(&self, value: T) -> Result<(), T>;
+
+    fn dequeue(&self) -> Option<T>;
+}
+
+/// A bounded, blocking byte pipe.
+///
+/// Writers block while the channel is full and readers block while it is empty.
+/// Once the channel is closed, readers drain the remaining bytes and then get the end of stream.
+pub struct Channel<S, F> {
+    fifo: F,
+    readable: S,
+    writable: S,
+    is_closed: AtomicBool,
+}
+
+impl<S: ChannelSemaphore, F: ChannelFifo<u8>> Channel<S, F> {
+    pub const DEFAULT_CAPACITY: usize = 4096;
+
+    #[inline]
+    pub fn new(capacity: usize) -> Arc<Self> {
+        Arc::new(Self {
+            fifo: F::with_capacity(capacity),
+            readable: S::new(0),
+            writable: S::new(capacity),
+            is_closed: AtomicBool::new(false),
+        })
+    }
+
+    #[inline]
+    pub fn is_closed(&self) -> bool {
+        self.is_closed.load(Ordering::SeqCst)
+    }
+
+    /// Closes the channel and wakes up the blocked peers.
+    pub fn close(&self) {
+        if !self.is_closed.swap(true, Ordering::SeqCst) {
+            self.readable.signal();
+            self.writable.signal();
+        }
+    }
+
+    /// Writes all bytes, blocking while the channel is full.
+    ///
+    /// Returns the number of bytes written, which is less than `buf.len()` only if the channel was closed.
+    pub fn write(&self, buf: &[u8]) -> usize {
+        for (index, byte) in buf.iter().enumerate() {
+            if self.is_closed() {
+                return index;
+            }
+            self.writable.wait();
+            if self.is_closed() {
+                // pass the wake-up on to the other writers
+                self.writable.signal();
+                return index;
+            }
+            if self.fifo.enqueue(*byte).is_err() {
+                return index;
+            }
+            self.readable.signal();
+        }
+        buf.len()
+    }
+
+    /// Reads the available bytes, blocking until at least one byte arrives.
+    ///
+    /// Returns `0` at the end of the stream.
+    pub fn read(&self, buf: &mut [u8]) -> usize {
+        let mut len = 0;
+        if buf.is_empty() {
+            return len;
+        }
+
+        self.readable.wait();
+        loop {
+            match self.fifo.dequeue() {
+                Some(byte) => {
+                    buf[len] = byte;
+                    len += 1;
+                    self.writable.signal();
+                }
+                None => {
+                    // The channel has been closed, leave the token for the other readers
+                    self.readable.signal();
+                    break;
+                }
+            }
+            if len >= buf.len() || !self.readable.try_lock() {
+                break;
+            }
+        }
+        len
+    }
+}
+
+impl<S: ChannelSemaphore, F: ChannelFifo<u8>> fmt::Debug for Channel<S, F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Channel")
+            .field("is_closed", &self.is_closed())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{
+        collections::VecDeque,
+        sync::{Condvar, Mutex},
+        thread,
+    };
+
+    struct MockSemaphore {
+        value: Mutex<usize>,
+        cond: Condvar,
+    }
+
+    impl ChannelSemaphore for MockSemaphore {
+        fn new(value: usize) -> Self {
+            Self {
+                value: Mutex::new(value),
+                cond: Condvar::new(),
+            }
+        }
+
+        fn try_lock(&self) -> bool {
+            let mut value = self.value.lock().unwrap();
+            if *value > 0 {
+                *value -= 1;
+                true
+            } else {
+                false
+            }
+        }
+
+        fn wait(&self) {
+            let mut value = self
+                .cond
+                .wait_while(self.value.lock().unwrap(), |v| *v == 0)
+                .unwrap();
+            *value -= 1;
+        }
+
+        fn signal(&self) {
+            *self.value.lock().unwrap() += 1;
+            self.cond.notify_one();
+        }
+    }
+
+    struct MockFifo {
+        data: Mutex<VecDeque<u8>>,
+        capacity: usize,
+    }
+
+    impl ChannelFifo<u8> for MockFifo {
+        fn with_capacity(capacity: usize) -> Self {
+            Self {
+                data: Mutex::new(VecDeque::with_capacity(capacity)),
+                capacity,
+            }
+        }
+
+        fn enqueue(&self, value: u8) -> Result<(), u8> {
+            let mut data = self.data.lock().unwrap();
+            if data.len() < self.capacity {
+                data.push_back(value);
+                Ok(())
+            } else {
+                Err(value)
+            }
+        }
+
+        fn dequeue(&self) -> Option<u8> {
+            self.data.lock().unwrap().pop_front()
+        }
+    }
+
+    type MockChannel = Channel<MockSemaphore, MockFifo>;
+
+    #[test]
+    fn pipe() {
+        // Much more bytes than the capacity, so that both sides block many times
+        let bytes = (0..10_000).map(|v| (v * 7 % 251) as u8).collect::<Vec<_>>();
+        let channel = MockChannel::new(16);
+
+        let producer = {
+            let channel = channel.clone();
+            let bytes = bytes.clone();
+            thread::spawn(move || {
+                for chunk in bytes.chunks(100) {
+                    assert_eq!(channel.write(chunk), chunk.len());
+                }
+                channel.close();
+            })
+        };
+        let consumer = {
+            let channel = channel.clone();
+            thread::spawn(move || {
+                let mut received = Vec::new();
+                let mut buf = [0; 64];
+                loop {
+                    let len = channel.read(&mut buf);
+                    if len == 0 {
+                        break;
+                    }
+                    received.extend_from_slice(&buf[..len]);
+                }
+                received
+            })
+        };
+        producer.join().unwrap();
+        let received = consumer.join().unwrap();
+        assert_eq!(received, bytes);
+
+        // the end of the stream stays
+        assert!(channel.is_closed());
+        assert_eq!(channel.read(&mut [0; 4]), 0);
+        assert_eq!(channel.write(b"abc"), 0);
+    }
+
+    #[test]
+    fn drain_after_close() {
+        let channel = MockChannel::new(8);
+        assert_eq!(channel.write(b"hello"), 5);
+        channel.close();
+
+        let mut buf = [0; 3];
+        assert_eq!(channel.read(&mut buf), 3);
+        assert_eq!(&buf, b"hel");
+        assert_eq!(channel.read(&mut buf), 2);
+        assert_eq!(&buf[..2], b"lo");
+        assert_eq!(channel.read(&mut buf), 0);
+        assert_eq!(channel.read(&mut []), 0);
+    }
+}
@@ -0,0 +1,120 @@
+//! Detection of possible deadlocks on the spinlocks
+
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+/// The number of spin waits before reporting a possible deadlock
+pub const DEADLOCK_THRESHOLD: usize = 0x0100_0000;
+
+/// The thread that owns a spinlock, identified by a non-zero value such as the thread handle
+pub struct LockOwner(AtomicUsize);
+
+impl LockOwner {
+    #[inline]
+    pub const fn new() -> Self {
+        Self(AtomicUsize::new(0))
+    }
+
+    #[inline]
+    pub fn get(&self) -> Option<usize> {
+        match self.0.load(Ordering::Relaxed) {
+            0 => None,
+            owner => Some(owner),
+        }
+    }
+
+    /// Records the owner that has taken the lock, or `None` if it is not owned by a thread.
+    #[inline]
+    pub fn set(&self, owner: Option<usize>) {
+        self.0.store(owner.unwrap_or(0), Ordering::Relaxed);
+    }
+
+    #[inline]
+    pub fn clear(&self) {
+        self.set(None);
+    }
+
+    /// Checks the lock that `current` has been waiting for `n_waits` times.
+    ///
+    /// Returns the owner as an error if the lock is already held by `current`, or if it has
+    /// not been released for too long.
+    pub fn check(&self, current: Option<usize>, n_waits: usize) -> Result<(), Option<usize>> {
+        let owner = self.get();
+        let is_recursive = owner.is_some() && current == owner;
+        if is_recursive || n_waits >= DEADLOCK_THRESHOLD {
+            Err(owner)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl Default for LockOwner {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::sync::atomic::AtomicBool;
+
+    /// A spinlock taken by the simulated threads
+    struct MockSpinlock {
+        value: AtomicBool,
+        owner: LockOwner,
+    }
+
+    impl MockSpinlock {
+        fn new() -> Self {
+            Self {
+                value: AtomicBool::new(false),
+                owner: LockOwner::new(),
+            }
+        }
+
+        fn lock(&self, thread: usize) -> Result<(), Option<usize>> {
+            let mut n_waits = 0;
+            while self.value.swap(true, Ordering::Acquire) {
+                self.owner.check(Some(thread), n_waits)?;
+                n_waits += 1;
+            }
+            self.owner.set(Some(thread));
+            Ok(())
+        }
+
+        fn unlock(&self) {
+            self.owner.clear();
+            self.value.store(false, Ordering::Release);
+        }
+    }
+
+    #[test]
+    fn recursive_lock() {
+        let lock = MockSpinlock::new();
+        assert_eq!(lock.lock(1), Ok(()));
+        assert_eq!(lock.owner.get(), Some(1));
+
+        // re-locking from the same owner would spin forever
+        assert_eq!(lock.lock(1), Err(Some(1)));
+
+        lock.unlock();
+        assert_eq!(lock.owner.get(), None);
+        assert_eq!(lock.lock(1), Ok(()));
+    }
+
+    #[test]
+    fn lock_timeout() {
+        let owner = LockOwner::new();
+        owner.set(Some(1));
+        assert_eq!(owner.check(Some(2), 0), Ok(()));
+        assert_eq!(owner.check(None, DEADLOCK_THRESHOLD - 1), Ok(()));
+        assert_eq!(owner.check(Some(2), DEADLOCK_THRESHOLD), Err(Some(1)));
+
+        // a lock taken outside of the threads is only reported after the timeout
+        owner.set(None);
+        assert_eq!(owner.check(None, 0), Ok(()));
+        assert_eq!(owner.check(None, DEADLOCK_THRESHOLD), Err(None));
+    }
+}
@@ -0,0 +1,115 @@
+//! Futures for the asynchronous tasks
+
+use core::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+/// A future that returns `Pending` once, to yield to the other ready tasks.
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct YieldNow {
+    is_yielded: bool,
+}
+
+impl YieldNow {
+    #[inline]
+    pub const fn new() -> Self {
+        Self { is_yielded: false }
+    }
+}
+
+impl Default for YieldNow {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Future for YieldNow {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        if self.is_yielded {
+            Poll::Ready(())
+        } else {
+            self.is_yielded = true;
+            cx.waker().wake_by_ref();
+            Poll::Pending
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{
+        cell::RefCell,
+        collections::VecDeque,
+        rc::Rc,
+        sync::{Arc, Mutex},
+        task::{Wake, Waker},
+    };
+
+    /// Puts the task back to the ready queue when it is woken
+    struct TaskWaker {
+        id: usize,
+        ready: Arc<Mutex<VecDeque<usize>>>,
+    }
+
+    impl Wake for TaskWaker {
+        fn wake(self: Arc<Self>) {
+            self.wake_by_ref();
+        }
+
+        fn wake_by_ref(self: &Arc<Self>) {
+            self.ready.lock().unwrap().push_back(self.id);
+        }
+    }
+
+    /// Runs the tasks on a single thread until none of them is ready
+    fn run(tasks: Vec<Pin<Box<dyn Future<Output = ()>>>>) -> usize {
+        let mut tasks: Vec<_> = tasks.into_iter().map(Some).collect();
+        let ready = Arc::new(Mutex::new((0..tasks.len()).collect::<VecDeque<_>>()));
+        loop {
+            // The lock must be released before polling, as the waker takes it
+            let id = ready.lock().unwrap().pop_front();
+            let Some(id) = id else { break };
+            let waker = Waker::from(Arc::new(TaskWaker {
+                id,
+                ready: ready.clone(),
+            }));
+            let mut cx = Context::from_waker(&waker);
+            if let Some(task) = tasks[id].as_mut() {
+                if task.as_mut().poll(&mut cx).is_ready() {
+                    tasks[id] = None;
+                }
+            }
+        }
+        tasks.iter().filter(|v| v.is_some()).count()
+    }
+
+    #[test]
+    fn yield_now() {
+        let log = Rc::new(RefCell::new(Vec::new()));
+
+        let log_a = log.clone();
+        let log_b = log.clone();
+        let pending = run(vec![
+            Box::pin(async move {
+                log_a.borrow_mut().push("a1");
+                YieldNow::new().await;
+                log_a.borrow_mut().push("a2");
+            }),
+            Box::pin(async move {
+                log_b.borrow_mut().push("b1");
+                YieldNow::new().await;
+                log_b.borrow_mut().push("b2");
+            }),
+        ]);
+
+        // the yielding task resumes after the other ready task has made progress
+        assert_eq!(*log.borrow(), ["a1", "b1", "a2", "b2"]);
+        assert_eq!(pending, 0);
+    }
+}
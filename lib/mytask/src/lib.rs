@@ -0,0 +1,16 @@
+//! Thread management independent of the kernel
+#![cfg_attr(not(test), no_std)]
+
+extern crate alloc;
+
+pub mod channel;
+pub mod deadlock;
+pub mod future;
+pub mod once;
+pub mod priority;
+pub mod process;
+pub mod queue;
+pub mod rwlock;
+pub mod state;
+pub mod stats;
+pub mod tls;
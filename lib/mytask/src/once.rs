@@ -0,0 +1,181 @@
+//! A cell which can be written to only once, like std::sync::OnceLock
+
+use core::{cell::UnsafeCell, fmt, hint, mem::MaybeUninit, sync::atomic::*};
+
+/// A cell which can be written to only once, like std::sync::OnceLock
+///
+/// Since it may be used before the scheduler starts, contending processors spin until the initialization completes.
+pub struct Once<T> {
+    state: AtomicUsize,
+    data: UnsafeCell<MaybeUninit<T>>,
+}
+
+unsafe impl<T: Send> Send for Once<T> {}
+
+unsafe impl<T: Send + Sync> Sync for Once<T> {}
+
+impl<T> Once<T> {
+    const UNINITIALIZED: usize = 0;
+    const RUNNING: usize = 1;
+    const COMPLETE: usize = 2;
+
+    #[inline]
+    pub const fn new() -> Self {
+        Self {
+            state: AtomicUsize::new(Self::UNINITIALIZED),
+            data: UnsafeCell::new(MaybeUninit::uninit()),
+        }
+    }
+
+    #[inline]
+    pub fn is_initialized(&self) -> bool {
+        self.state.load(Ordering::Acquire) == Self::COMPLETE
+    }
+
+    /// Returns the value if it has been initialized.
+    #[inline]
+    pub fn get(&self) -> Option<&T> {
+        self.is_initialized()
+            .then(|| unsafe { (&*self.data.get()).assume_init_ref() })
+    }
+
+    /// Sets the value, or returns it back if the cell has already been initialized.
+    pub fn set(&self, value: T) -> Result<(), T> {
+        let mut value = Some(value);
+        self.get_or_init(|| value.take().unwrap());
+        match value {
+            Some(value) => Err(value),
+            None => Ok(()),
+        }
+    }
+
+    /// Returns the value, initializing it with `f` if the cell is empty.
+    ///
+    /// When called concurrently, `f` runs exactly once and all the callers observe the same value.
+    pub fn get_or_init<F>(&self, f: F) -> &T
+    where
+        F: FnOnce() -> T,
+    {
+        if let Some(value) = self.get() {
+            return value;
+        }
+        match self.state.compare_exchange(
+            Self::UNINITIALIZED,
+            Self::RUNNING,
+            Ordering::Acquire,
+            Ordering::Acquire,
+        ) {
+            Ok(_) => {
+                unsafe {
+                    (&mut *self.data.get()).write(f());
+                }
+                self.state.store(Self::COMPLETE, Ordering::Release);
+            }
+            Err(_) => {
+                while !self.is_initialized() {
+                    hint::spin_loop();
+                }
+            }
+        }
+        unsafe { (&*self.data.get()).assume_init_ref() }
+    }
+
+    #[inline]
+    pub fn into_inner(mut self) -> Option<T> {
+        self.take()
+    }
+
+    #[inline]
+    pub fn take(&mut self) -> Option<T> {
+        if *self.state.get_mut() == Self::COMPLETE {
+            *self.state.get_mut() = Self::UNINITIALIZED;
+            Some(unsafe { self.data.get_mut().assume_init_read() })
+        } else {
+            None
+        }
+    }
+}
+
+impl<T> Default for Once<T> {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Drop for Once<T> {
+    #[inline]
+    fn drop(&mut self) {
+        if *self.state.get_mut() == Self::COMPLETE {
+            unsafe {
+                self.data.get_mut().assume_init_drop();
+            }
+        }
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for Once<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.get() {
+            Some(value) => f.debug_tuple("Once").field(value).finish(),
+            None => f.write_str("Once(<uninit>)"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{
+        sync::{Arc, Barrier},
+        thread,
+        time::Duration,
+        vec::Vec,
+    };
+
+    #[test]
+    fn get_or_init() {
+        const N_THREADS: usize = 8;
+        let once = Arc::new(Once::new());
+        let n_calls = Arc::new(AtomicUsize::new(0));
+        let barrier = Arc::new(Barrier::new(N_THREADS));
+
+        let threads = (0..N_THREADS)
+            .map(|index| {
+                let once = once.clone();
+                let n_calls = n_calls.clone();
+                let barrier = barrier.clone();
+                thread::spawn(move || {
+                    barrier.wait();
+                    let value = once.get_or_init(|| {
+                        n_calls.fetch_add(1, Ordering::SeqCst);
+                        // keep the other threads waiting for the initialization
+                        thread::sleep(Duration::from_millis(10));
+                        index
+                    });
+                    value as *const usize as usize
+                })
+            })
+            .collect::<Vec<_>>();
+        let results = threads
+            .into_iter()
+            .map(|v| v.join().unwrap())
+            .collect::<Vec<_>>();
+
+        assert_eq!(n_calls.load(Ordering::SeqCst), 1);
+        let value = once.get().unwrap() as *const usize as usize;
+        assert!(results.iter().all(|v| *v == value));
+        assert!(*once.get().unwrap() < N_THREADS);
+    }
+
+    #[test]
+    fn set() {
+        let mut once = Once::new();
+        assert!(once.get().is_none());
+        assert_eq!(once.set(1), Ok(()));
+        assert_eq!(once.set(2), Err(2));
+        assert_eq!(once.get_or_init(|| 3), &1);
+        assert_eq!(once.take(), Some(1));
+        assert!(!once.is_initialized());
+    }
+}
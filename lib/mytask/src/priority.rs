@@ -0,0 +1,199 @@
+//! Priority inheritance through locks
+//!
+//! While a thread is waiting for a lock, the thread holding the lock inherits its priority.
+//! The levels are the priorities of the kernel as `usize`.
+
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+/// Counts of the items at each of `N` levels, to find the highest level in use
+///
+/// Each lock held by a thread adds its inherited priority,
+/// so that releasing one lock keeps the highest priority of the rest.
+#[derive(Debug)]
+pub struct LevelCounts<const N: usize> {
+    counts: [AtomicUsize; N],
+}
+
+impl<const N: usize> LevelCounts<N> {
+    #[inline]
+    pub const fn new() -> Self {
+        Self {
+            counts: [const { AtomicUsize::new(0) }; N],
+        }
+    }
+
+    /// Adds an item at the level.
+    #[inline]
+    pub fn add(&self, level: usize) {
+        if let Some(count) = self.counts.get(level) {
+            count.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    /// Removes an item at the level, if any.
+    #[inline]
+    pub fn remove(&self, level: usize) {
+        if let Some(count) = self.counts.get(level) {
+            let _ = count.fetch_update(Ordering::SeqCst, Ordering::SeqCst, |v| v.checked_sub(1));
+        }
+    }
+
+    /// Moves an item from the old level, if any, to the new level.
+    #[inline]
+    pub fn raise(&self, old: Option<usize>, new: usize) {
+        self.add(new);
+        if let Some(old) = old {
+            self.remove(old);
+        }
+    }
+
+    /// Returns the highest level with any items.
+    #[inline]
+    pub fn max(&self) -> Option<usize> {
+        self.counts
+            .iter()
+            .rposition(|v| v.load(Ordering::SeqCst) > 0)
+    }
+}
+
+impl<const N: usize> Default for LevelCounts<N> {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The level inherited by the owner of a lock from the threads waiting for it
+///
+/// The caller keeps the owner from changing while calling these methods.
+#[derive(Debug, Default)]
+pub struct InheritedLevel {
+    /// The level plus one, or zero if none
+    level: AtomicUsize,
+}
+
+impl InheritedLevel {
+    #[inline]
+    pub const fn new() -> Self {
+        Self {
+            level: AtomicUsize::new(0),
+        }
+    }
+
+    /// Returns the level inherited through the lock.
+    #[inline]
+    pub fn get(&self) -> Option<usize> {
+        self.level.load(Ordering::SeqCst).checked_sub(1)
+    }
+
+    /// Lets the owner inherit the level of a waiter, if higher than the one inherited already.
+    pub fn inherit<const N: usize>(&self, owner: &LevelCounts<N>, level: usize) {
+        let old = self.get();
+        if old.is_none_or(|v| v < level) {
+            owner.raise(old, level);
+            self.level.store(level + 1, Ordering::SeqCst);
+        }
+    }
+
+    /// Drops the level inherited by the owner through the lock, when the lock is released.
+    pub fn restore<const N: usize>(&self, owner: &LevelCounts<N>) {
+        if let Some(level) = self.level.swap(0, Ordering::SeqCst).checked_sub(1) {
+            owner.remove(level);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LOW: usize = 1;
+    const NORMAL: usize = 2;
+    const HIGH: usize = 3;
+    const REALTIME: usize = 4;
+
+    #[test]
+    fn level_counts() {
+        let counts = LevelCounts::<5>::new();
+        assert_eq!(counts.max(), None);
+
+        // A thread holds two locks, and the waiters raise each of them
+        counts.raise(None, LOW);
+        counts.raise(Some(LOW), HIGH);
+        counts.raise(None, REALTIME);
+        assert_eq!(counts.max(), Some(REALTIME));
+
+        // Releasing the lock with the realtime waiter keeps the other one
+        counts.remove(REALTIME);
+        assert_eq!(counts.max(), Some(HIGH));
+
+        counts.remove(HIGH);
+        assert_eq!(counts.max(), None);
+
+        // Out of range and extra removals are ignored
+        counts.remove(HIGH);
+        counts.add(5);
+        assert_eq!(counts.max(), None);
+        counts.add(HIGH);
+        counts.add(HIGH);
+        counts.remove(HIGH);
+        assert_eq!(counts.max(), Some(HIGH));
+    }
+
+    /// A thread with its own priority and the priorities inherited through the locks it holds
+    struct MockThread {
+        priority: usize,
+        inherited: LevelCounts<5>,
+    }
+
+    impl MockThread {
+        fn new(priority: usize) -> Self {
+            Self {
+                priority,
+                inherited: LevelCounts::new(),
+            }
+        }
+
+        fn effective_priority(&self) -> usize {
+            self.inherited
+                .max()
+                .map_or(self.priority, |v| v.max(self.priority))
+        }
+    }
+
+    #[test]
+    fn inherit() {
+        let owner = MockThread::new(LOW);
+        let lock1 = InheritedLevel::new();
+        let lock2 = InheritedLevel::new();
+
+        // The waiters of the locks held by the owner
+        lock1.inherit(&owner.inherited, NORMAL);
+        assert_eq!(owner.effective_priority(), NORMAL);
+        lock1.inherit(&owner.inherited, HIGH);
+        assert_eq!(owner.effective_priority(), HIGH);
+        // A lower waiter does not lower it
+        lock1.inherit(&owner.inherited, NORMAL);
+        assert_eq!(lock1.get(), Some(HIGH));
+        lock2.inherit(&owner.inherited, REALTIME);
+        assert_eq!(owner.effective_priority(), REALTIME);
+
+        // Unlocking one of them keeps the priority inherited through the other
+        lock2.restore(&owner.inherited);
+        assert_eq!(lock2.get(), None);
+        assert_eq!(owner.effective_priority(), HIGH);
+        lock1.restore(&owner.inherited);
+        assert_eq!(owner.effective_priority(), LOW);
+
+        // Unlocking without any waiters changes nothing
+        lock1.restore(&owner.inherited);
+        assert_eq!(owner.effective_priority(), LOW);
+
+        // The own priority is kept if higher than the waiters
+        let owner = MockThread::new(HIGH);
+        lock1.inherit(&owner.inherited, NORMAL);
+        assert_eq!(owner.effective_priority(), HIGH);
+        lock1.restore(&owner.inherited);
+        assert_eq!(owner.effective_priority(), HIGH);
+    }
+}
@@ -0,0 +1,98 @@
+//! Process lifetime
+
+use core::sync::atomic::{AtomicBool, AtomicI32, Ordering};
+
+/// The exit code that a process leaves for its parent
+///
+/// The process sets the code any time before it exits, and the parent reads it after that.
+#[derive(Debug, Default)]
+pub struct ExitStatus {
+    code: AtomicI32,
+    exited: AtomicBool,
+}
+
+impl ExitStatus {
+    #[inline]
+    pub const fn new() -> Self {
+        Self {
+            code: AtomicI32::new(0),
+            exited: AtomicBool::new(false),
+        }
+    }
+
+    /// Sets the exit code, which is `0` unless set.
+    #[inline]
+    pub fn set_code(&self, code: i32) {
+        self.code.store(code, Ordering::SeqCst);
+    }
+
+    /// Marks the process as exited, and returns `false` if it has already been.
+    #[inline]
+    pub fn exit(&self) -> bool {
+        !self.exited.swap(true, Ordering::SeqCst)
+    }
+
+    /// Returns the exit code if the process has exited.
+    #[inline]
+    pub fn code(&self) -> Option<i32> {
+        self.exited
+            .load(Ordering::SeqCst)
+            .then(|| self.code.load(Ordering::SeqCst))
+    }
+
+    /// Waits for the process to exit and returns its exit code.
+    ///
+    /// `wait` blocks until the process may have exited, and is called again if it has not.
+    pub fn join<F: FnMut()>(&self, mut wait: F) -> i32 {
+        loop {
+            if let Some(code) = self.code() {
+                return code;
+            }
+            wait();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{
+        sync::{mpsc, Arc},
+        thread,
+    };
+
+    #[test]
+    fn join_exit_code() {
+        let status = Arc::new(ExitStatus::new());
+        let (signal, sem) = mpsc::channel();
+
+        // the child exits with 42
+        let child = {
+            let status = status.clone();
+            thread::spawn(move || {
+                status.set_code(42);
+                assert!(status.exit());
+                signal.send(()).unwrap();
+            })
+        };
+        assert_eq!(status.join(|| sem.recv().unwrap()), 42);
+        child.join().unwrap();
+
+        // exits only once, and the code stays
+        assert!(!status.exit());
+        assert_eq!(status.code(), Some(42));
+    }
+
+    #[test]
+    fn default_exit_code() {
+        let status = ExitStatus::new();
+        assert_eq!(status.code(), None);
+
+        // the code set while running is not visible until the exit
+        status.set_code(-1);
+        assert_eq!(status.code(), None);
+        status.set_code(0);
+        status.exit();
+        assert_eq!(status.join(|| unreachable!()), 0);
+    }
+}
@@ -0,0 +1,168 @@
+//! Run queues shared by processors
+
+/// What to do with an item taken from the queue
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Selection {
+    /// Takes the item
+    Take,
+    /// Puts the item back at the end of the queue
+    Skip,
+    /// Drops the item, such as a thread that has already exited
+    Discard,
+}
+
+/// Takes the first item that `select` accepts, putting back the skipped ones at the end of the queue.
+///
+/// Looks at no more than `max_skips` items, and returns `None` if the queue is empty or
+/// none of them is accepted. Other processors may fill the queue between taking an item and
+/// putting it back, so putting back is retried until they take one, and then `None` is returned.
+pub fn take_selected<T, D, P, S>(
+    max_skips: usize,
+    mut dequeue: D,
+    mut put_back: P,
+    mut select: S,
+) -> Option<T>
+where
+    D: FnMut() -> Option<T>,
+    P: FnMut(T) -> Result<(), T>,
+    S: FnMut(&T) -> Selection,
+{
+    for _ in 0..max_skips {
+        let item = dequeue()?;
+        match select(&item) {
+            Selection::Take => return Some(item),
+            Selection::Discard => (),
+            Selection::Skip => {
+                if let Err(mut item) = put_back(item) {
+                    while let Err(v) = put_back(item) {
+                        item = v;
+                        core::hint::spin_loop();
+                    }
+                    return None;
+                }
+            }
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{cell::RefCell, collections::VecDeque};
+
+    /// A thread with the processors it may run on
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    struct Thread {
+        id: usize,
+        affinity: usize,
+    }
+
+    /// A bounded run queue shared by the simulated processors
+    struct RunQueue {
+        queue: RefCell<VecDeque<Thread>>,
+        capacity: usize,
+    }
+
+    impl RunQueue {
+        fn new(capacity: usize, threads: &[Thread]) -> Self {
+            Self {
+                queue: RefCell::new(threads.iter().copied().collect()),
+                capacity,
+            }
+        }
+
+        fn enqueue(&self, thread: Thread) -> Result<(), Thread> {
+            let mut queue = self.queue.borrow_mut();
+            if queue.len() < self.capacity {
+                queue.push_back(thread);
+                Ok(())
+            } else {
+                Err(thread)
+            }
+        }
+
+        fn dequeue_for(&self, cpu: usize) -> Option<Thread> {
+            take_selected(
+                8,
+                || self.queue.borrow_mut().pop_front(),
+                |thread| self.enqueue(thread),
+                |thread| {
+                    if thread.affinity == 0 {
+                        Selection::Discard
+                    } else if thread.affinity & (1 << cpu) != 0 {
+                        Selection::Take
+                    } else {
+                        Selection::Skip
+                    }
+                },
+            )
+        }
+    }
+
+    #[test]
+    fn pinned_thread() {
+        let pinned = Thread {
+            id: 1,
+            affinity: 0b10,
+        };
+        let any = Thread {
+            id: 2,
+            affinity: usize::MAX,
+        };
+        let queue = RunQueue::new(4, &[pinned, any]);
+
+        // CPU 0 skips the thread pinned to CPU 1
+        for _ in 0..10 {
+            assert_eq!(queue.dequeue_for(0), Some(any));
+            queue.enqueue(any).unwrap();
+        }
+        assert_eq!(queue.dequeue_for(0), Some(any));
+        assert_eq!(queue.dequeue_for(0), None);
+        assert_eq!(queue.queue.borrow().len(), 1);
+
+        assert_eq!(queue.dequeue_for(1), Some(pinned));
+        assert_eq!(queue.dequeue_for(1), None);
+    }
+
+    #[test]
+    fn discarded_thread() {
+        let exited = Thread { id: 1, affinity: 0 };
+        let queue = RunQueue::new(4, &[exited]);
+        assert_eq!(queue.dequeue_for(0), None);
+        assert!(queue.queue.borrow().is_empty());
+    }
+
+    #[test]
+    fn full_queue() {
+        let pinned = Thread {
+            id: 1,
+            affinity: 0b10,
+        };
+        let other = Thread {
+            id: 2,
+            affinity: 0b10,
+        };
+        let queue = RunQueue::new(1, &[pinned]);
+
+        // Another processor fills the queue before the pinned thread is put back once
+        let mut filled = false;
+        let result = take_selected(
+            8,
+            || queue.queue.borrow_mut().pop_front(),
+            |thread| {
+                if !filled {
+                    filled = true;
+                    queue.enqueue(other).unwrap();
+                } else if queue.queue.borrow().front() == Some(&other) {
+                    queue.queue.borrow_mut().pop_front();
+                }
+                queue.enqueue(thread)
+            },
+            |_| Selection::Skip,
+        );
+        // gives up without losing the thread
+        assert_eq!(result, None);
+        assert_eq!(queue.queue.borrow().iter().collect::<Vec<_>>(), [&pinned]);
+    }
+}
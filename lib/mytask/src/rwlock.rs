@@ -0,0 +1,152 @@
+//! State of the reader-writer lock
+//!
+//! A waiting writer keeps new readers from taking the lock, so that the readers cannot starve it.
+
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+/// Readers and the writer holding the lock
+pub struct RwLockState {
+    data: AtomicUsize,
+}
+
+impl RwLockState {
+    const LOCK_WRITE: usize = 1;
+    /// Set while a writer is waiting, so that new readers cannot starve it.
+    const WRITER_WAITING: usize = 2;
+    const LOCK_COUNT: usize = 4;
+
+    #[inline]
+    pub const fn new() -> Self {
+        Self {
+            data: AtomicUsize::new(0),
+        }
+    }
+
+    /// Takes the lock for reading unless a writer holds it or is waiting for it.
+    #[inline]
+    pub fn try_read(&self) -> bool {
+        self.data
+            .fetch_update(Ordering::Acquire, Ordering::Relaxed, |v| {
+                if (v & (Self::LOCK_WRITE | Self::WRITER_WAITING)) == 0 {
+                    Some(v + Self::LOCK_COUNT)
+                } else {
+                    None
+                }
+            })
+            .is_ok()
+    }
+
+    /// Takes the lock for writing if no one holds it.
+    #[inline]
+    pub fn try_write(&self) -> bool {
+        self.data
+            .fetch_update(Ordering::Acquire, Ordering::Relaxed, |v| {
+                if (v & !Self::WRITER_WAITING) == 0 {
+                    Some(Self::LOCK_WRITE)
+                } else {
+                    None
+                }
+            })
+            .is_ok()
+    }
+
+    /// Takes the lock for writing, or marks a writer waiting for it if it is held.
+    #[inline]
+    pub fn try_write_or_wait(&self) -> bool {
+        self.data.fetch_or(Self::WRITER_WAITING, Ordering::Relaxed);
+        self.try_write()
+    }
+
+    /// Releases the lock for reading, and returns whether the waiting threads should be woken up.
+    #[inline]
+    pub fn read_unlock(&self) -> bool {
+        let old = self.data.fetch_sub(Self::LOCK_COUNT, Ordering::Release);
+        ((old - Self::LOCK_COUNT) & !Self::WRITER_WAITING) == 0
+    }
+
+    /// Releases the lock for writing.
+    #[inline]
+    pub fn write_unlock(&self) {
+        self.data.store(0, Ordering::Release);
+    }
+}
+
+impl Default for RwLockState {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{cell::UnsafeCell, sync::Arc, thread};
+
+    #[test]
+    fn readers_block_writer() {
+        let lock = RwLockState::new();
+
+        // two readers at once
+        assert!(lock.try_read());
+        assert!(lock.try_read());
+        assert!(!lock.try_write_or_wait());
+
+        // new readers wait behind the writer
+        assert!(!lock.try_read());
+        assert!(!lock.read_unlock());
+        assert!(!lock.try_write_or_wait());
+
+        // the last reader wakes the writer up
+        assert!(lock.read_unlock());
+        assert!(lock.try_write_or_wait());
+        assert!(!lock.try_read());
+        assert!(!lock.try_write());
+
+        lock.write_unlock();
+        assert!(lock.try_read());
+        assert!(lock.read_unlock());
+        assert!(lock.try_write());
+    }
+
+    #[test]
+    fn concurrent_writers() {
+        struct Counter {
+            lock: RwLockState,
+            value: UnsafeCell<usize>,
+        }
+        unsafe impl Sync for Counter {}
+
+        let counter = Arc::new(Counter {
+            lock: RwLockState::new(),
+            value: UnsafeCell::new(0),
+        });
+        let threads = (0..4)
+            .map(|_| {
+                let counter = counter.clone();
+                thread::spawn(move || {
+                    for _ in 0..1000 {
+                        while !counter.lock.try_write_or_wait() {
+                            thread::yield_now();
+                        }
+                        unsafe {
+                            *counter.value.get() += 1;
+                        }
+                        counter.lock.write_unlock();
+
+                        while !counter.lock.try_read() {
+                            thread::yield_now();
+                        }
+                        counter.lock.read_unlock();
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+
+        assert!(counter.lock.try_read());
+        assert_eq!(unsafe { *counter.value.get() }, 4000);
+    }
+}
@@ -0,0 +1,145 @@
+//! State of the threads shown in the statistics
+
+/// State of a thread at the time of the snapshot
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ThreadState {
+    /// The thread is running on a processor
+    Running,
+    /// The thread is waiting in the run queue
+    Ready,
+    /// The thread is waiting for an event
+    Sleeping,
+    /// The thread has exited but is not yet reaped
+    Zombie,
+}
+
+impl ThreadState {
+    /// Determines the state of the thread.
+    ///
+    /// `is_running` tells whether the thread is the current thread of any processor.
+    /// A running thread may still be marked as queued until it is switched out,
+    /// so the mark is only checked for the threads that are not running.
+    #[inline]
+    pub const fn new(is_running: bool, is_queued: bool, is_asleep: bool, is_zombie: bool) -> Self {
+        if is_zombie {
+            Self::Zombie
+        } else if is_running {
+            Self::Running
+        } else if is_queued && !is_asleep {
+            Self::Ready
+        } else {
+            Self::Sleeping
+        }
+    }
+
+    /// Returns the character used in the thread statistics.
+    #[inline]
+    pub const fn to_char(&self) -> char {
+        match self {
+            Self::Running => '-',
+            Self::Ready => 'R',
+            Self::Sleeping => 'S',
+            Self::Zombie => 'z',
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{vec, vec::Vec};
+
+    struct MockThread {
+        id: usize,
+        name: &'static str,
+        is_queued: bool,
+        is_asleep: bool,
+        is_zombie: bool,
+    }
+
+    impl MockThread {
+        const fn new(id: usize, name: &'static str) -> Self {
+            Self {
+                id,
+                name,
+                is_queued: false,
+                is_asleep: false,
+                is_zombie: false,
+            }
+        }
+    }
+
+    fn snapshot(threads: &[MockThread], current: &[usize]) -> Vec<(&'static str, ThreadState)> {
+        threads
+            .iter()
+            .map(|thread| {
+                let state = ThreadState::new(
+                    current.contains(&thread.id),
+                    thread.is_queued,
+                    thread.is_asleep,
+                    thread.is_zombie,
+                );
+                (thread.name, state)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn snapshot_states() {
+        let threads = [
+            // The queued mark stays on the running thread until it is switched out
+            MockThread {
+                is_queued: true,
+                ..MockThread::new(1, "repl")
+            },
+            MockThread {
+                is_queued: true,
+                ..MockThread::new(2, "worker")
+            },
+            MockThread {
+                is_asleep: true,
+                ..MockThread::new(3, "timer")
+            },
+            MockThread {
+                is_queued: true,
+                is_zombie: true,
+                ..MockThread::new(4, "killed")
+            },
+            MockThread::new(5, "shell"),
+        ];
+        // the current threads of the two processors
+        let result = snapshot(&threads, &[1, 5]);
+
+        assert_eq!(
+            result,
+            vec![
+                ("repl", ThreadState::Running),
+                ("worker", ThreadState::Ready),
+                ("timer", ThreadState::Sleeping),
+                ("killed", ThreadState::Zombie),
+                ("shell", ThreadState::Running),
+            ]
+        );
+        assert_eq!(
+            result
+                .iter()
+                .map(|(_, state)| state.to_char())
+                .collect::<Vec<_>>(),
+            vec!['-', 'R', 'S', 'z', '-']
+        );
+    }
+
+    #[test]
+    fn asleep_in_queue() {
+        // A thread going to sleep is not ready even if it has not left the queue yet
+        assert_eq!(
+            ThreadState::new(false, true, true, false),
+            ThreadState::Sleeping
+        );
+        // and is still running until it is switched out
+        assert_eq!(
+            ThreadState::new(true, true, true, false),
+            ThreadState::Running
+        );
+    }
+}
@@ -0,0 +1,125 @@
+//! CPU time accounting of the threads
+//!
+//! The times are in the unit of the timestamps passed in, which is microseconds in the kernel.
+
+use core::sync::atomic::{AtomicU32, AtomicUsize, Ordering};
+
+/// CPU time and context switches of a thread
+#[derive(Debug, Default)]
+pub struct ThreadStatistics {
+    measure: AtomicUsize,
+    cpu_time: AtomicUsize,
+    n_switches: AtomicUsize,
+    load0: AtomicU32,
+    load: AtomicU32,
+}
+
+impl ThreadStatistics {
+    #[inline]
+    pub const fn new() -> Self {
+        Self {
+            measure: AtomicUsize::new(0),
+            cpu_time: AtomicUsize::new(0),
+            n_switches: AtomicUsize::new(0),
+            load0: AtomicU32::new(0),
+            load: AtomicU32::new(0),
+        }
+    }
+
+    /// Starts measuring the thread that has been switched in.
+    #[inline]
+    pub fn switch_in(&self, now: usize) {
+        self.measure.store(now, Ordering::SeqCst);
+        self.n_switches.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Adds the time the thread has been running since the last measurement.
+    #[inline]
+    pub fn update(&self, now: usize) {
+        let then = self.measure.swap(now, Ordering::SeqCst);
+        // The timestamp may go slightly backwards when the thread migrates to another processor
+        let diff = now.saturating_sub(then);
+        self.cpu_time.fetch_add(diff, Ordering::SeqCst);
+        self.load0.fetch_add(diff as u32, Ordering::SeqCst);
+    }
+
+    /// Total time the thread has been running on any processor
+    #[inline]
+    pub fn cpu_time(&self) -> usize {
+        self.cpu_time.load(Ordering::Relaxed)
+    }
+
+    /// Number of times the thread has been switched in
+    #[inline]
+    pub fn context_switches(&self) -> usize {
+        self.n_switches.load(Ordering::Relaxed)
+    }
+
+    /// Load of the thread in permille at the last sampling
+    #[inline]
+    pub fn load(&self) -> u32 {
+        self.load.load(Ordering::Relaxed)
+    }
+
+    /// Samples the load over the interval since the last sampling.
+    ///
+    /// Returns the time the thread has been running in the interval and the load in permille.
+    pub fn sample_load(&self, interval: usize) -> (usize, u32) {
+        let load0 = self.load0.swap(0, Ordering::SeqCst) as usize;
+        let load = usize::min(load0 * 1000 / interval.max(1), 1000) as u32;
+        self.load.store(load, Ordering::SeqCst);
+        (load0, load)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cpu_time() {
+        let busy = ThreadStatistics::new();
+        let idle = ThreadStatistics::new();
+
+        // Round robin of a thread that uses up the quantum and another that yields soon
+        let mut now = 1000;
+        let mut last = (0, 0);
+        for _ in 0..5 {
+            busy.switch_in(now);
+            now += 10;
+            busy.update(now);
+
+            idle.switch_in(now);
+            now += 1;
+            idle.update(now);
+
+            let times = (busy.cpu_time(), idle.cpu_time());
+            assert!(times.0 > last.0 && times.1 > last.1);
+            last = times;
+        }
+
+        assert_eq!(busy.cpu_time(), 50);
+        assert_eq!(idle.cpu_time(), 5);
+        assert_eq!(busy.cpu_time() + idle.cpu_time(), now - 1000);
+        assert_eq!(busy.context_switches(), 5);
+        assert_eq!(idle.context_switches(), 5);
+
+        assert_eq!(busy.sample_load(55), (50, 909));
+        assert_eq!(idle.sample_load(55), (5, 90));
+        assert_eq!(busy.load(), 909);
+        // the load is sampled from the time since the last sampling
+        assert_eq!(busy.sample_load(55), (0, 0));
+        assert_eq!(busy.cpu_time(), 50);
+    }
+
+    #[test]
+    fn clock_skew() {
+        let stats = ThreadStatistics::new();
+        stats.switch_in(100);
+        // migrated to a processor whose clock is slightly behind
+        stats.update(99);
+        assert_eq!(stats.cpu_time(), 0);
+        stats.update(120);
+        assert_eq!(stats.cpu_time(), 21);
+    }
+}
@@ -0,0 +1,133 @@
+//! Thread Local Storage
+
+use alloc::{boxed::Box, collections::BTreeMap};
+use core::{
+    any::Any,
+    marker::PhantomData,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+/// A key of the thread local storage
+///
+/// Each thread has its own [`TlsSlots`], so that the values of a key do not leak between threads.
+pub struct TlsKey<T: 'static> {
+    id: AtomicUsize,
+    init: fn() -> T,
+    _phantom: PhantomData<T>,
+}
+
+// SAFETY: The key itself holds no value of `T`, only its id and initializer, so sharing
+// it does not share the values. Each value lives in the slots of one thread, and the
+// slots may be dropped after the thread has gone, so the values must be `Send`.
+unsafe impl<T: Send + 'static> Sync for TlsKey<T> {}
+
+impl<T: Send + 'static> TlsKey<T> {
+    #[inline]
+    pub const fn new(init: fn() -> T) -> Self {
+        Self {
+            id: AtomicUsize::new(0),
+            init,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Returns the unique id of this key, allocating it on the first call.
+    pub fn id(&self) -> usize {
+        static NEXT_ID: AtomicUsize = AtomicUsize::new(1);
+
+        let id = self.id.load(Ordering::Acquire);
+        if id != 0 {
+            return id;
+        }
+        let new_id = NEXT_ID.fetch_add(1, Ordering::SeqCst);
+        match self
+            .id
+            .compare_exchange(0, new_id, Ordering::AcqRel, Ordering::Acquire)
+        {
+            Ok(_) => new_id,
+            Err(id) => id,
+        }
+    }
+
+    /// Returns a new value to initialize the slot of a thread.
+    #[inline]
+    pub fn init_value(&self) -> T {
+        (self.init)()
+    }
+}
+
+/// The thread local values of a thread
+#[derive(Default)]
+pub struct TlsSlots {
+    slots: BTreeMap<usize, Box<dyn Any + Send>>,
+}
+
+impl TlsSlots {
+    #[inline]
+    pub const fn new() -> Self {
+        Self {
+            slots: BTreeMap::new(),
+        }
+    }
+
+    /// Returns the value of the key, if it has been initialized in this thread.
+    #[inline]
+    pub fn get<T: Send + 'static>(&self, key: &TlsKey<T>) -> Option<&T> {
+        self.slots
+            .get(&key.id())
+            .and_then(|v| v.downcast_ref::<T>())
+    }
+
+    /// Inserts the value of the key and returns the value in the slot.
+    ///
+    /// If the slot has been initialized while making the value, that one is kept.
+    /// The boxed value does not move until the slots are cleared.
+    pub fn insert<T: Send + 'static>(&mut self, key: &TlsKey<T>, value: T) -> &T {
+        let value: Box<dyn Any + Send> = Box::new(value);
+        self.slots
+            .entry(key.id())
+            .or_insert(value)
+            .downcast_ref::<T>()
+            .unwrap()
+    }
+
+    /// Drops all the values.
+    #[inline]
+    pub fn clear(&mut self) {
+        self.slots.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::cell::Cell;
+
+    #[test]
+    fn tls_slots() {
+        static COUNTER: TlsKey<Cell<usize>> = TlsKey::new(|| Cell::new(0));
+        static NAME: TlsKey<&str> = TlsKey::new(|| "main");
+        assert_ne!(COUNTER.id(), NAME.id());
+        assert_eq!(COUNTER.id(), COUNTER.id());
+
+        // the slots of two threads
+        let mut thread1 = TlsSlots::new();
+        let mut thread2 = TlsSlots::new();
+        assert!(thread1.get(&COUNTER).is_none());
+
+        thread1.insert(&COUNTER, COUNTER.init_value()).set(1);
+        thread2.insert(&COUNTER, Cell::new(2));
+        thread1.get(&COUNTER).unwrap().set(3);
+        assert_eq!(thread1.get(&COUNTER).map(|v| v.get()), Some(3));
+        assert_eq!(thread2.get(&COUNTER).map(|v| v.get()), Some(2));
+
+        // keeps the value inserted first
+        assert_eq!(thread1.insert(&COUNTER, Cell::new(4)).get(), 3);
+        assert_eq!(*thread1.insert(&NAME, NAME.init_value()), "main");
+        assert!(thread2.get(&NAME).is_none());
+
+        thread1.clear();
+        assert!(thread1.get(&COUNTER).is_none());
+        assert_eq!(thread2.get(&COUNTER).map(|v| v.get()), Some(2));
+    }
+}
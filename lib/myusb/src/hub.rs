@@ -0,0 +1,177 @@
+//! Port handling of the hub class driver
+//!
+//! A failure on one port is reported and the other ports are still handled, so that a broken
+//! port does not stop the hub.
+
+use core::{future::Future, num::NonZeroU8};
+
+/// Returns whether the port has changed in the bitmap reported by the status change endpoint.
+///
+/// Bit 0 is for the hub itself and the bit N is for the port N.
+#[inline]
+pub fn is_port_changed(bitmap: &[u8], port: NonZeroU8) -> bool {
+    let port = port.get() as usize;
+    bitmap
+        .get(port / 8)
+        .map(|v| (v & (1 << (port % 8))) != 0)
+        .unwrap_or(false)
+}
+
+/// Requests to the ports of a hub
+pub trait HubPorts {
+    type Error;
+
+    /// Reads the status of the port and acknowledges its changes.
+    ///
+    /// Returns whether a device is connected if the connection has changed, or `None` if
+    /// only the other changes have been acknowledged.
+    fn connection_change(
+        &self,
+        port: NonZeroU8,
+    ) -> impl Future<Output = Result<Option<bool>, Self::Error>>;
+
+    /// Resets the port and enumerates the device connected to it.
+    fn attach_device(&self, port: NonZeroU8) -> impl Future<Output = Result<(), Self::Error>>;
+
+    /// Removes the device that was connected to the port.
+    fn detach_device(&self, port: NonZeroU8) -> impl Future<Output = Result<(), Self::Error>>;
+}
+
+/// Handles the ports that have changed in the bitmap of the status change endpoint.
+///
+/// The errors are passed to `on_error` along with the port, and do not stop handling the
+/// remaining ports.
+pub async fn handle_port_changes<H, I, F>(hub: &H, bitmap: &[u8], ports: I, mut on_error: F)
+where
+    H: HubPorts,
+    I: IntoIterator<Item = NonZeroU8>,
+    F: FnMut(NonZeroU8, H::Error),
+{
+    for port in ports {
+        if !is_port_changed(bitmap, port) {
+            continue;
+        }
+        let result = match hub.connection_change(port).await {
+            Ok(Some(true)) => hub.attach_device(port).await,
+            Ok(Some(false)) => hub.detach_device(port).await,
+            Ok(None) => Ok(()),
+            Err(err) => Err(err),
+        };
+        if let Err(err) = result {
+            on_error(port, err);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::{
+        cell::RefCell,
+        pin::pin,
+        task::{Context, Poll, Waker},
+    };
+
+    fn block_on<F: Future>(future: F) -> F::Output {
+        let mut future = pin!(future);
+        let mut cx = Context::from_waker(Waker::noop());
+        loop {
+            if let Poll::Ready(v) = future.as_mut().poll(&mut cx) {
+                return v;
+            }
+        }
+    }
+
+    fn port(n: u8) -> NonZeroU8 {
+        NonZeroU8::new(n).unwrap()
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    enum Request {
+        Attach(u8),
+        Detach(u8),
+    }
+
+    /// A hub that reports the connection of each port, or fails to read the status of it
+    struct MockHub {
+        connections: Vec<Result<Option<bool>, &'static str>>,
+        requests: RefCell<Vec<Request>>,
+    }
+
+    impl MockHub {
+        fn new(connections: &[Result<Option<bool>, &'static str>]) -> Self {
+            Self {
+                connections: connections.to_vec(),
+                requests: RefCell::new(Vec::new()),
+            }
+        }
+    }
+
+    impl HubPorts for MockHub {
+        type Error = &'static str;
+
+        async fn connection_change(&self, port: NonZeroU8) -> Result<Option<bool>, Self::Error> {
+            self.connections[port.get() as usize - 1]
+        }
+
+        async fn attach_device(&self, port: NonZeroU8) -> Result<(), Self::Error> {
+            self.requests.borrow_mut().push(Request::Attach(port.get()));
+            Ok(())
+        }
+
+        async fn detach_device(&self, port: NonZeroU8) -> Result<(), Self::Error> {
+            self.requests.borrow_mut().push(Request::Detach(port.get()));
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn port_changed() {
+        let bitmap = [0b0000_0100, 0b0000_0010];
+        assert!(!is_port_changed(&bitmap, port(1)));
+        assert!(is_port_changed(&bitmap, port(2)));
+        assert!(is_port_changed(&bitmap, port(9)));
+        assert!(!is_port_changed(&bitmap, port(17)));
+    }
+
+    #[test]
+    fn port_connect() {
+        let hub = MockHub::new(&[Ok(None), Ok(Some(true)), Ok(Some(false))]);
+        let ports = (1..=3).map(port);
+        let mut errors = Vec::new();
+
+        // only the port 2 has changed
+        block_on(handle_port_changes(
+            &hub,
+            &[0b0100],
+            ports.clone(),
+            |port, err| errors.push((port.get(), err)),
+        ));
+        assert_eq!(*hub.requests.borrow(), [Request::Attach(2)]);
+
+        hub.requests.borrow_mut().clear();
+        block_on(handle_port_changes(&hub, &[0b1110], ports, |port, err| {
+            errors.push((port.get(), err))
+        }));
+        assert_eq!(
+            *hub.requests.borrow(),
+            [Request::Attach(2), Request::Detach(3)]
+        );
+        assert!(errors.is_empty());
+    }
+
+    #[test]
+    fn port_error() {
+        let hub = MockHub::new(&[Err("stall"), Ok(Some(true)), Err("timeout")]);
+        let mut errors = Vec::new();
+
+        block_on(handle_port_changes(
+            &hub,
+            &[0b1110],
+            (1..=3).map(port),
+            |port, err| errors.push((port.get(), err)),
+        ));
+        assert_eq!(*hub.requests.borrow(), [Request::Attach(2)]);
+        assert_eq!(errors, [(1, "stall"), (3, "timeout")]);
+    }
+}
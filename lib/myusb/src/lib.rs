@@ -0,0 +1,4 @@
+//! USB class driver rules independent of the kernel
+#![cfg_attr(not(test), no_std)]
+
+pub mod hub;
@@ -0,0 +1,95 @@
+//! Screen capture
+//!
+//! The windows are composited in the logical orientation of the screen, so the capture is
+//! never rotated even if the framebuffer is portrait or rotated.
+
+use megstd::drawing::*;
+
+/// The windows composited into the screen
+pub trait Compositor {
+    /// Size of the screen in the logical orientation
+    fn screen_size(&self) -> Size;
+
+    /// Draws the windows in the rectangle of the screen into the bitmap.
+    fn draw_into(&self, bitmap: &mut BitmapRefMut32, rect: Rect);
+}
+
+/// Captures the composited screen into a new bitmap of the screen size.
+pub fn capture_screen<C: Compositor>(compositor: &C) -> OwnedBitmap32 {
+    let size = compositor.screen_size();
+    let mut bitmap = OwnedBitmap32::new(size, TrueColor::TRANSPARENT);
+    compositor.draw_into(bitmap.as_mut(), size.into());
+    bitmap
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A desktop with a window at the top left corner
+    struct MockCompositor {
+        screen_size: Size,
+    }
+
+    impl MockCompositor {
+        const DESKTOP: TrueColor = TrueColor::from_rgb(0x0000FF);
+        const WINDOW: TrueColor = TrueColor::from_rgb(0xFFFFFF);
+    }
+
+    impl Compositor for MockCompositor {
+        fn screen_size(&self) -> Size {
+            self.screen_size
+        }
+
+        fn draw_into(&self, bitmap: &mut BitmapRefMut32, rect: Rect) {
+            bitmap.fill_rect(rect, Self::DESKTOP);
+            bitmap.fill_rect(Rect::new(0, 0, 2, 2), Self::WINDOW);
+        }
+    }
+
+    fn corners(bitmap: &OwnedBitmap32) -> [Option<TrueColor>; 4] {
+        let size = bitmap.size();
+        [
+            Point::new(0, 0),
+            Point::new(size.width() - 1, 0),
+            Point::new(0, size.height() - 1),
+            Point::new(size.width() - 1, size.height() - 1),
+        ]
+        .map(|point| bitmap.get_pixel(point))
+    }
+
+    #[test]
+    fn capture_landscape() {
+        let bitmap = capture_screen(&MockCompositor {
+            screen_size: Size::new(8, 6),
+        });
+        assert_eq!(bitmap.size(), Size::new(8, 6));
+        assert_eq!(
+            corners(&bitmap),
+            [
+                Some(MockCompositor::WINDOW),
+                Some(MockCompositor::DESKTOP),
+                Some(MockCompositor::DESKTOP),
+                Some(MockCompositor::DESKTOP),
+            ]
+        );
+    }
+
+    #[test]
+    fn capture_portrait() {
+        // The screen of the landscape framebuffer rotated to portrait
+        let bitmap = capture_screen(&MockCompositor {
+            screen_size: Size::new(6, 8),
+        });
+        assert_eq!(bitmap.size(), Size::new(6, 8));
+        assert_eq!(
+            bitmap.get_pixel(Point::new(1, 1)),
+            Some(MockCompositor::WINDOW)
+        );
+        assert_eq!(
+            bitmap.get_pixel(Point::new(2, 1)),
+            Some(MockCompositor::DESKTOP)
+        );
+        assert_eq!(corners(&bitmap)[1..], [Some(MockCompositor::DESKTOP); 3]);
+    }
+}
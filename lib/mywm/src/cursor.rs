@@ -0,0 +1,110 @@
+//! Mouse cursor
+
+use megstd::drawing::{Movement, Point};
+
+/// Where to put the image of the mouse cursor
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CursorPlacement {
+    /// The hotspot of the hardware cursor, which needs no redrawing
+    Hardware(Point),
+    /// The origin of the window that draws the image in software,
+    /// which redraws only the old and new frames of the window
+    Software(Point),
+}
+
+/// The mouse cursor drawn by the hardware or in software
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cursor {
+    hotspot: Movement,
+    is_hardware: bool,
+}
+
+impl Cursor {
+    #[inline]
+    pub const fn new(hotspot: Movement, is_hardware: bool) -> Self {
+        Self {
+            hotspot,
+            is_hardware,
+        }
+    }
+
+    #[inline]
+    pub const fn hotspot(&self) -> Movement {
+        self.hotspot
+    }
+
+    #[inline]
+    pub const fn is_hardware(&self) -> bool {
+        self.is_hardware
+    }
+
+    /// Returns where to put the image so that its hotspot is at the point.
+    #[inline]
+    pub fn placement(&self, point: Point) -> CursorPlacement {
+        if self.is_hardware {
+            CursorPlacement::Hardware(point)
+        } else {
+            CursorPlacement::Software(point - self.hotspot)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::{vec, vec::Vec};
+    use megstd::drawing::{Rect, Size};
+
+    /// A screen that records the areas to redraw
+    struct MockScreen {
+        cursor_size: Size,
+        window_origin: Point,
+        hardware_point: Option<Point>,
+        dirty: Vec<Rect>,
+    }
+
+    impl MockScreen {
+        fn apply(&mut self, placement: CursorPlacement) {
+            match placement {
+                CursorPlacement::Hardware(point) => self.hardware_point = Some(point),
+                CursorPlacement::Software(origin) => {
+                    let old_origin = core::mem::replace(&mut self.window_origin, origin);
+                    self.dirty.push(Rect::from((old_origin, self.cursor_size)));
+                    self.dirty.push(Rect::from((origin, self.cursor_size)));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn cursor_placement() {
+        let hotspot = Movement::new(4, 2);
+        let mut screen = MockScreen {
+            cursor_size: Size::new(16, 16),
+            window_origin: Point::new(0, 0),
+            hardware_point: None,
+            dirty: Vec::new(),
+        };
+
+        let cursor = Cursor::new(hotspot, true);
+        assert!(cursor.is_hardware());
+        screen.apply(cursor.placement(Point::new(100, 50)));
+        screen.apply(cursor.placement(Point::new(101, 52)));
+        assert_eq!(screen.hardware_point, Some(Point::new(101, 52)));
+        // the hardware cursor redraws nothing
+        assert!(screen.dirty.is_empty());
+
+        let cursor = Cursor::new(hotspot, false);
+        assert_eq!(cursor.hotspot(), hotspot);
+        screen.apply(cursor.placement(Point::new(100, 50)));
+        assert_eq!(screen.window_origin, Point::new(96, 48));
+        screen.dirty.clear();
+        screen.apply(cursor.placement(Point::new(101, 52)));
+        assert_eq!(screen.window_origin, Point::new(97, 50));
+        // only the old and new frames of the cursor, not the whole screen
+        assert_eq!(
+            screen.dirty,
+            vec![Rect::new(96, 48, 16, 16), Rect::new(97, 50, 16, 16)]
+        );
+    }
+}
@@ -0,0 +1,104 @@
+//! Drag and drop between windows
+
+/// An ongoing drag and drop
+///
+/// The window manager gives the window under the pointer, and the session decides
+/// which windows are notified as it moves.
+#[derive(Debug)]
+pub struct DragSession<H, P> {
+    source: H,
+    payload: P,
+    /// The window that accepts the drop under the pointer
+    over: Option<H>,
+}
+
+/// Windows to notify after the pointer moved while dragging
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DragTransition<H> {
+    pub leave: Option<H>,
+    pub enter: Option<H>,
+}
+
+impl<H: Copy + PartialEq, P> DragSession<H, P> {
+    #[inline]
+    pub const fn new(source: H, payload: P) -> Self {
+        Self {
+            source,
+            payload,
+            over: None,
+        }
+    }
+
+    #[inline]
+    pub const fn source(&self) -> H {
+        self.source
+    }
+
+    #[inline]
+    pub const fn over(&self) -> Option<H> {
+        self.over
+    }
+
+    /// Moves the pointer over the window, and returns the windows to notify if the target has changed.
+    ///
+    /// Neither the source window nor windows that do not accept drops become the target.
+    pub fn hover(&mut self, window: H, accepts_drop: bool) -> Option<DragTransition<H>> {
+        let enter = (window != self.source && accepts_drop).then_some(window);
+        if enter == self.over {
+            return None;
+        }
+        let leave = core::mem::replace(&mut self.over, enter);
+        Some(DragTransition { leave, enter })
+    }
+
+    /// Ends the session, and returns the window to drop the payload on, if any.
+    #[inline]
+    pub fn into_drop(self) -> Option<(H, P)> {
+        self.over.map(|target| (target, self.payload))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn drag_session() {
+        const SOURCE: usize = 1;
+        const ACCEPTS: usize = 2;
+        const REJECTS: usize = 3;
+        let enter = |v| {
+            Some(DragTransition {
+                leave: None,
+                enter: Some(v),
+            })
+        };
+
+        let mut session = DragSession::new(SOURCE, "payload");
+        assert_eq!(session.source(), SOURCE);
+        // the source does not receive its own drop
+        assert_eq!(session.hover(SOURCE, true), None);
+
+        assert_eq!(session.hover(ACCEPTS, true), enter(ACCEPTS));
+        assert_eq!(session.hover(ACCEPTS, true), None);
+        assert_eq!(session.over(), Some(ACCEPTS));
+
+        assert_eq!(
+            session.hover(REJECTS, false),
+            Some(DragTransition {
+                leave: Some(ACCEPTS),
+                enter: None,
+            })
+        );
+        assert_eq!(session.hover(REJECTS, false), None);
+
+        assert_eq!(session.hover(ACCEPTS, true), enter(ACCEPTS));
+        assert_eq!(session.into_drop(), Some((ACCEPTS, "payload")));
+
+        // dropped outside of any target
+        let mut session = DragSession::new(SOURCE, "payload");
+        session.hover(ACCEPTS, true);
+        session.hover(SOURCE, true);
+        assert_eq!(session.into_drop(), None);
+    }
+}
@@ -0,0 +1,11 @@
+//! Window manager rules independent of the kernel
+#![cfg_attr(not(test), no_std)]
+
+extern crate alloc;
+
+pub mod capture;
+pub mod cursor;
+pub mod drag;
+pub mod pointer;
+pub mod scrollback;
+pub mod zorder;
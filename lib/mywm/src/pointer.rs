@@ -0,0 +1,236 @@
+//! Delivery of the pointer state to windows
+
+use core::sync::atomic::{AtomicIsize, Ordering};
+use megstd::{drawing::Point, io::hid::MouseButton};
+
+/// A signed amount that producers add to and a consumer takes at once
+///
+/// The mouse wheel is reported in small steps, and the window manager delivers their sum.
+#[derive(Debug, Default)]
+pub struct PendingDelta(AtomicIsize);
+
+impl PendingDelta {
+    #[inline]
+    pub const fn new() -> Self {
+        Self(AtomicIsize::new(0))
+    }
+
+    #[inline]
+    pub fn add(&self, delta: isize) {
+        if delta != 0 {
+            self.0.fetch_add(delta, Ordering::SeqCst);
+        }
+    }
+
+    /// Takes the sum added so far, or `None` if it is zero.
+    #[inline]
+    pub fn take(&self) -> Option<isize> {
+        match self.0.swap(0, Ordering::SeqCst) {
+            0 => None,
+            delta => Some(delta),
+        }
+    }
+}
+
+/// A pointer message for a window, in its content coordinates
+#[derive(Debug, Clone, Copy)]
+pub enum PointerMessage {
+    /// The pointer has moved with no buttons changed
+    Move(Point, MouseButton),
+    /// Buttons have been pressed: the point, all the buttons held, and the new ones
+    Down(Point, MouseButton, MouseButton),
+    /// Buttons have been released: the point, all the buttons held, and the released ones
+    Up(Point, MouseButton, MouseButton),
+    /// The wheel has been rotated, positive values mean away from the user
+    Wheel(isize),
+}
+
+/// A window that receives pointer messages
+pub trait PointerTarget {
+    type Error;
+
+    fn post_pointer(&self, message: PointerMessage) -> Result<(), Self::Error>;
+}
+
+/// Posts the messages for the change of the buttons, or a move if none changed.
+///
+/// Both the down and the up messages are posted even if the first one fails,
+/// and then the last error is returned.
+pub fn post_buttons<T: PointerTarget>(
+    target: &T,
+    point: Point,
+    buttons: MouseButton,
+    down: MouseButton,
+    up: MouseButton,
+) -> Result<(), T::Error> {
+    if down.is_empty() && up.is_empty() {
+        return target.post_pointer(PointerMessage::Move(point, buttons));
+    }
+    let mut result = Ok(());
+    if !down.is_empty() {
+        if let Err(err) = target.post_pointer(PointerMessage::Down(point, buttons, down)) {
+            result = Err(err);
+        }
+    }
+    if !up.is_empty() {
+        if let Err(err) = target.post_pointer(PointerMessage::Up(point, buttons, up)) {
+            result = Err(err);
+        }
+    }
+    result
+}
+
+/// Posts the wheel rotation accumulated so far, if any.
+pub fn post_wheel<T: PointerTarget>(target: &T, wheel: &PendingDelta) -> Result<(), T::Error> {
+    match wheel.take() {
+        Some(delta) => target.post_pointer(PointerMessage::Wheel(delta)),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::cell::RefCell;
+
+    /// A window that records the messages, and refuses them once full
+    struct MockWindow {
+        messages: RefCell<Vec<PointerMessage>>,
+        capacity: usize,
+    }
+
+    impl MockWindow {
+        fn new(capacity: usize) -> Self {
+            Self {
+                messages: RefCell::new(Vec::new()),
+                capacity,
+            }
+        }
+
+        fn take(&self) -> Vec<PointerMessage> {
+            self.messages.take()
+        }
+    }
+
+    impl PointerTarget for MockWindow {
+        type Error = PointerMessage;
+
+        fn post_pointer(&self, message: PointerMessage) -> Result<(), Self::Error> {
+            let mut messages = self.messages.borrow_mut();
+            if messages.len() < self.capacity {
+                messages.push(message);
+                Ok(())
+            } else {
+                Err(message)
+            }
+        }
+    }
+
+    #[test]
+    fn pending_delta() {
+        let wheel = PendingDelta::new();
+        assert_eq!(wheel.take(), None);
+
+        wheel.add(1);
+        wheel.add(2);
+        wheel.add(0);
+        assert_eq!(wheel.take(), Some(3));
+        assert_eq!(wheel.take(), None);
+
+        // steps in the opposite directions cancel out
+        wheel.add(-2);
+        wheel.add(2);
+        assert_eq!(wheel.take(), None);
+
+        wheel.add(-1);
+        assert_eq!(wheel.take(), Some(-1));
+    }
+
+    #[test]
+    fn wheel_delivery() {
+        let window = MockWindow::new(8);
+        let wheel = PendingDelta::new();
+
+        // nothing is posted until the wheel rotates
+        post_wheel(&window, &wheel).unwrap();
+        assert!(window.take().is_empty());
+
+        wheel.add(1);
+        wheel.add(1);
+        post_wheel(&window, &wheel).unwrap();
+        match window.take().as_slice() {
+            [PointerMessage::Wheel(2)] => (),
+            messages => panic!("unexpected {:?}", messages),
+        }
+
+        // the rotation is delivered once
+        post_wheel(&window, &wheel).unwrap();
+        assert!(window.take().is_empty());
+
+        // a window that cannot take it reports the error
+        let full = MockWindow::new(0);
+        wheel.add(-3);
+        assert!(matches!(
+            post_wheel(&full, &wheel),
+            Err(PointerMessage::Wheel(-3))
+        ));
+    }
+
+    #[test]
+    fn button_delivery() {
+        let window = MockWindow::new(8);
+        let point = Point::new(12, 34);
+
+        post_buttons(
+            &window,
+            point,
+            MouseButton::empty(),
+            MouseButton::empty(),
+            MouseButton::empty(),
+        )
+        .unwrap();
+        match window.take().as_slice() {
+            [PointerMessage::Move(p, buttons)] => {
+                assert_eq!(*p, point);
+                assert!(buttons.is_empty());
+            }
+            messages => panic!("unexpected {:?}", messages),
+        }
+
+        // the secondary button is pressed while the primary one is released
+        post_buttons(
+            &window,
+            point,
+            MouseButton::SECONDARY,
+            MouseButton::SECONDARY,
+            MouseButton::PRIMARY,
+        )
+        .unwrap();
+        match window.take().as_slice() {
+            [PointerMessage::Down(p1, b1, down), PointerMessage::Up(p2, b2, up)] => {
+                assert_eq!(*p1, point);
+                assert_eq!(*p2, point);
+                assert_eq!(b1.bits(), MouseButton::SECONDARY.bits());
+                assert_eq!(b2.bits(), MouseButton::SECONDARY.bits());
+                assert_eq!(down.bits(), MouseButton::SECONDARY.bits());
+                assert_eq!(up.bits(), MouseButton::PRIMARY.bits());
+            }
+            messages => panic!("unexpected {:?}", messages),
+        }
+
+        // a window that can take only one of them reports the error of the other
+        let window = MockWindow::new(1);
+        let result = post_buttons(
+            &window,
+            point,
+            MouseButton::empty(),
+            MouseButton::PRIMARY,
+            MouseButton::PRIMARY,
+        );
+        assert!(matches!(result, Err(PointerMessage::Up(..))));
+        assert!(matches!(
+            window.take().as_slice(),
+            [PointerMessage::Down(..)]
+        ));
+    }
+}
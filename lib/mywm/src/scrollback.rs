@@ -0,0 +1,207 @@
+//! Lines of a terminal with the scrollback
+//!
+//! The lines that scrolled off the top of the screen are kept up to the limit, and the view
+//! can be moved back to them by an offset from the bottom.
+
+use alloc::{collections::VecDeque, vec::Vec};
+
+/// A character and its attribute
+pub type Cell = (char, u8);
+
+/// Lines of the scrollback followed by the lines of the screen
+pub struct LineBuffer {
+    lines: VecDeque<Vec<Cell>>,
+    rows: usize,
+    limit: usize,
+    offset: usize,
+}
+
+impl LineBuffer {
+    /// Creates the empty screen of `rows` lines, keeping up to `limit` lines of the scrollback.
+    pub fn new(rows: usize, limit: usize) -> Self {
+        Self {
+            lines: (0..rows).map(|_| Vec::new()).collect(),
+            rows,
+            limit,
+            offset: 0,
+        }
+    }
+
+    #[inline]
+    pub const fn rows(&self) -> usize {
+        self.rows
+    }
+
+    #[inline]
+    pub const fn limit(&self) -> usize {
+        self.limit
+    }
+
+    /// Number of lines that have scrolled off the top of the screen
+    #[inline]
+    pub fn scrollback_len(&self) -> usize {
+        self.lines.len() - self.rows
+    }
+
+    /// Sets the maximum number of lines kept in the scrollback, discarding the oldest ones.
+    pub fn set_limit(&mut self, limit: usize) {
+        self.limit = limit;
+        self.trim();
+    }
+
+    fn trim(&mut self) {
+        while self.lines.len() > self.rows + self.limit {
+            self.lines.pop_front();
+        }
+        self.offset = self.offset.min(self.scrollback_len());
+    }
+
+    /// Number of lines the view is scrolled back from the screen
+    #[inline]
+    pub const fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Scrolls the view back to earlier lines, and returns whether the view has moved.
+    #[inline]
+    pub fn scroll_up(&mut self, lines: usize) -> bool {
+        self.set_offset(self.offset.saturating_add(lines))
+    }
+
+    /// Scrolls the view towards the latest lines, and returns whether the view has moved.
+    #[inline]
+    pub fn scroll_down(&mut self, lines: usize) -> bool {
+        self.set_offset(self.offset.saturating_sub(lines))
+    }
+
+    /// Scrolls the view back to the screen, and returns whether the view has moved.
+    #[inline]
+    pub fn scroll_to_bottom(&mut self) -> bool {
+        self.set_offset(0)
+    }
+
+    fn set_offset(&mut self, offset: usize) -> bool {
+        let offset = offset.min(self.scrollback_len());
+        let old_value = self.offset;
+        self.offset = offset;
+        old_value != offset
+    }
+
+    /// Scrolls the screen by adding an empty line at the bottom.
+    ///
+    /// The top line of the screen moves to the scrollback.
+    pub fn push_line(&mut self) {
+        self.lines.push_back(Vec::new());
+        self.trim();
+    }
+
+    /// Returns the line of the screen, which is shorter than the screen until it is written.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `y` is not less than the number of rows.
+    #[inline]
+    pub fn line_mut(&mut self, y: usize) -> &mut Vec<Cell> {
+        assert!(y < self.rows);
+        let top = self.scrollback_len();
+        &mut self.lines[top + y]
+    }
+
+    /// Clears the lines of the screen, leaving the scrollback.
+    pub fn clear_screen(&mut self) {
+        let top = self.scrollback_len();
+        for line in self.lines.range_mut(top..) {
+            line.clear();
+        }
+    }
+
+    /// Returns the lines visible at the current offset from top to bottom.
+    pub fn view(&self) -> impl Iterator<Item = &[Cell]> {
+        let top = self.scrollback_len() - self.offset;
+        self.lines
+            .range(top..top + self.rows)
+            .map(|line| line.as_slice())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_line(buffer: &mut LineBuffer, y: usize, s: &str) {
+        let line = buffer.line_mut(y);
+        line.clear();
+        line.extend(s.chars().map(|c| (c, 0)));
+    }
+
+    fn view(buffer: &LineBuffer) -> Vec<String> {
+        buffer
+            .view()
+            .map(|line| line.iter().map(|(c, _)| c).collect())
+            .collect()
+    }
+
+    /// Writes `n` numbered lines to the last row, scrolling the screen like a terminal does.
+    fn buffer_with_lines(rows: usize, limit: usize, n: usize) -> LineBuffer {
+        let mut buffer = LineBuffer::new(rows, limit);
+        for i in 0..n {
+            if i >= rows {
+                buffer.push_line();
+            }
+            write_line(&mut buffer, i.min(rows - 1), &i.to_string());
+        }
+        buffer
+    }
+
+    #[test]
+    fn scroll_view() {
+        let mut buffer = buffer_with_lines(3, 10, 5);
+        assert_eq!(buffer.scrollback_len(), 2);
+        assert_eq!(view(&buffer), ["2", "3", "4"]);
+
+        assert!(buffer.scroll_up(1));
+        assert_eq!(buffer.offset(), 1);
+        assert_eq!(view(&buffer), ["1", "2", "3"]);
+
+        // the offset stops at the oldest line
+        assert!(buffer.scroll_up(100));
+        assert_eq!(buffer.offset(), 2);
+        assert_eq!(view(&buffer), ["0", "1", "2"]);
+        assert!(!buffer.scroll_up(1));
+
+        assert!(buffer.scroll_down(1));
+        assert_eq!(view(&buffer), ["1", "2", "3"]);
+        assert!(buffer.scroll_to_bottom());
+        assert_eq!(view(&buffer), ["2", "3", "4"]);
+        assert!(!buffer.scroll_down(1));
+    }
+
+    #[test]
+    fn scrollback_limit() {
+        let mut buffer = buffer_with_lines(3, 4, 20);
+        assert_eq!(buffer.scrollback_len(), 4);
+        buffer.scroll_up(100);
+        assert_eq!(buffer.offset(), 4);
+        assert_eq!(view(&buffer), ["13", "14", "15"]);
+
+        // shrinking the limit discards the oldest lines and keeps the view within them
+        buffer.set_limit(1);
+        assert_eq!(buffer.scrollback_len(), 1);
+        assert_eq!(buffer.offset(), 1);
+        assert_eq!(view(&buffer), ["16", "17", "18"]);
+
+        buffer.set_limit(0);
+        buffer.push_line();
+        assert_eq!(buffer.scrollback_len(), 0);
+        assert_eq!(view(&buffer), ["18", "19", ""]);
+    }
+
+    #[test]
+    fn clear_screen() {
+        let mut buffer = buffer_with_lines(2, 10, 4);
+        buffer.clear_screen();
+        assert_eq!(view(&buffer), ["", ""]);
+        buffer.scroll_up(2);
+        assert_eq!(view(&buffer), ["0", "1"]);
+    }
+}
@@ -0,0 +1,130 @@
+//! Stacking order and focus of windows
+//!
+//! The windows are ordered from back to front, and grouped by their levels in ascending order.
+
+use alloc::vec::Vec;
+
+/// Moves the window in front of the others of the same level, or adds it if not yet in the order.
+pub fn bring_to_front<H, L, F>(orders: &mut Vec<H>, handle: H, level_of: F)
+where
+    H: Copy + PartialEq,
+    L: Ord,
+    F: Fn(&H) -> L,
+{
+    orders.retain(|v| *v != handle);
+    let level = level_of(&handle);
+    let insert_position = orders
+        .iter()
+        .position(|v| level_of(v) > level)
+        .unwrap_or(orders.len());
+    orders.insert(insert_position, handle);
+}
+
+/// Moves the window behind the others of the same level.
+///
+/// Returns the new foremost window of that level, which is `None` if the window is not in
+/// the order or it is the only one of that level.
+pub fn send_to_back<H, L, F>(orders: &mut Vec<H>, handle: H, level_of: F) -> Option<H>
+where
+    H: Copy + PartialEq,
+    L: Ord,
+    F: Fn(&H) -> L,
+{
+    let index = orders.iter().position(|v| *v == handle)?;
+    orders.remove(index);
+    let level = level_of(&handle);
+    let insert_position = orders
+        .iter()
+        .position(|v| level_of(v) >= level)
+        .unwrap_or(orders.len());
+    orders.insert(insert_position, handle);
+
+    orders
+        .iter()
+        .rev()
+        .find(|v| **v != handle && level_of(v) == level)
+        .copied()
+}
+
+/// Windows to notify when the focus moves
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FocusTransition<H> {
+    pub lost: Option<H>,
+    pub gained: Option<H>,
+}
+
+impl<H: PartialEq> FocusTransition<H> {
+    /// Returns the windows to notify, or `None` if the focus does not move.
+    #[inline]
+    pub fn new(old: Option<H>, new: Option<H>) -> Option<Self> {
+        (old != new).then_some(Self {
+            lost: old,
+            gained: new,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    /// Windows whose ids in the hundreds are their levels
+    fn level_of(handle: &usize) -> usize {
+        handle / 100
+    }
+
+    #[test]
+    fn stacking_order() {
+        let mut orders = vec![0];
+        for handle in [101, 102, 201, 103] {
+            bring_to_front(&mut orders, handle, level_of);
+        }
+        assert_eq!(orders, [0, 101, 102, 103, 201]);
+
+        // a lower window comes to the front of its level, but not above the higher level
+        bring_to_front(&mut orders, 101, level_of);
+        assert_eq!(orders, [0, 102, 103, 101, 201]);
+        bring_to_front(&mut orders, 101, level_of);
+        assert_eq!(orders, [0, 102, 103, 101, 201]);
+
+        assert_eq!(send_to_back(&mut orders, 101, level_of), Some(103));
+        assert_eq!(orders, [0, 101, 102, 103, 201]);
+        assert_eq!(send_to_back(&mut orders, 201, level_of), None);
+        assert_eq!(send_to_back(&mut orders, 999, level_of), None);
+        assert_eq!(orders, [0, 101, 102, 103, 201]);
+    }
+
+    #[test]
+    fn focus_transition() {
+        let mut orders = vec![0, 101, 102];
+        let mut focused = Some(102);
+
+        // mock of the window manager that brings the window to front and gives it the focus
+        let mut activate = |orders: &mut Vec<usize>, handle| {
+            bring_to_front(orders, handle, level_of);
+            let transition = FocusTransition::new(focused, Some(handle));
+            focused = Some(handle);
+            transition
+        };
+
+        assert_eq!(
+            activate(&mut orders, 101),
+            Some(FocusTransition {
+                lost: Some(102),
+                gained: Some(101),
+            })
+        );
+        assert_eq!(orders, [0, 102, 101]);
+        // already focused
+        assert_eq!(activate(&mut orders, 101), None);
+
+        assert_eq!(
+            FocusTransition::new(None, Some(101)),
+            Some(FocusTransition {
+                lost: None,
+                gained: Some(101),
+            })
+        );
+    }
+}
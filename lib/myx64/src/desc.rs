@@ -0,0 +1,454 @@
+//! Segment and gate descriptors
+
+#[repr(transparent)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct DescriptorEntry(u64);
+
+impl DescriptorEntry {
+    pub const PRESENT: u64 = 0x8000_0000_0000;
+    pub const BIG_DATA: u64 = 0x0040_0000_0000_0000;
+
+    #[inline]
+    pub const fn null() -> Self {
+        Self(0)
+    }
+
+    #[inline]
+    pub const fn from_raw(value: u64) -> Self {
+        Self(value)
+    }
+
+    #[inline]
+    pub fn flat_code_segment(dpl: PrivilegeLevel, size: DefaultSize) -> DescriptorEntry {
+        Self::code_segment(Linear32(0), Limit32::MAX, dpl, size)
+    }
+
+    #[inline]
+    pub fn code_segment(
+        base: Linear32,
+        limit: Limit32,
+        dpl: PrivilegeLevel,
+        size: DefaultSize,
+    ) -> DescriptorEntry {
+        DescriptorEntry(
+            0x0000_1A00_0000_0000u64
+                | base.as_segment_base()
+                | limit.as_descriptor_entry()
+                | Self::PRESENT
+                | dpl.as_descriptor_entry()
+                | size.as_descriptor_entry(),
+        )
+    }
+
+    #[inline]
+    pub fn flat_data_segment(dpl: PrivilegeLevel) -> DescriptorEntry {
+        Self::data_segment(Linear32(0), Limit32::MAX, dpl)
+    }
+
+    #[inline]
+    pub fn data_segment(base: Linear32, limit: Limit32, dpl: PrivilegeLevel) -> DescriptorEntry {
+        DescriptorEntry(
+            0x0000_1200_0000_0000u64
+                | base.as_segment_base()
+                | limit.as_descriptor_entry()
+                | Self::PRESENT
+                | Self::BIG_DATA
+                | dpl.as_descriptor_entry(),
+        )
+    }
+
+    #[inline]
+    pub fn tss_descriptor(base: Linear64, limit: Limit16) -> DescriptorPair {
+        let (base_low, base_high) = base.as_segment_base_pair();
+        let low = DescriptorEntry(
+            DescriptorType::Tss.as_descriptor_entry()
+                | base_low
+                | limit.as_descriptor_entry()
+                | Self::PRESENT,
+        );
+        let high = DescriptorEntry(base_high);
+        DescriptorPair::new(low, high)
+    }
+
+    #[inline]
+    pub fn gate_descriptor(
+        offset: Offset64,
+        sel: Selector,
+        dpl: PrivilegeLevel,
+        ty: DescriptorType,
+        ist: Option<InterruptStackTable>,
+    ) -> DescriptorPair {
+        let (offset_low, offset_high) = offset.as_gate_offset_pair();
+        let low = DescriptorEntry(
+            ty.as_descriptor_entry()
+                | offset_low
+                | sel.as_descriptor_entry()
+                | ist.as_descriptor_entry()
+                | dpl.as_descriptor_entry()
+                | Self::PRESENT,
+        );
+        let high = DescriptorEntry(offset_high);
+
+        DescriptorPair::new(low, high)
+    }
+
+    #[inline]
+    pub const fn is_null(&self) -> bool {
+        self.0 == 0
+    }
+
+    #[inline]
+    pub const fn is_present(&self) -> bool {
+        (self.0 & Self::PRESENT) != 0
+    }
+
+    #[inline]
+    pub const fn is_segment(&self) -> bool {
+        (self.0 & 0x1000_0000_0000) != 0
+    }
+
+    #[inline]
+    pub const fn is_code_segment(&self) -> bool {
+        self.is_segment() && (self.0 & 0x0800_0000_0000) != 0
+    }
+
+    #[inline]
+    pub const fn default_operand_size(&self) -> Option<DefaultSize> {
+        DefaultSize::from_descriptor(*self)
+    }
+}
+
+pub trait AsDescriptorEntry {
+    fn as_descriptor_entry(&self) -> u64;
+}
+
+impl<T: AsDescriptorEntry> AsDescriptorEntry for Option<T> {
+    fn as_descriptor_entry(&self) -> u64 {
+        match self {
+            Some(v) => v.as_descriptor_entry(),
+            None => 0,
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, PartialEq)]
+pub struct DescriptorPair {
+    pub low: DescriptorEntry,
+    pub high: DescriptorEntry,
+}
+
+impl DescriptorPair {
+    #[inline]
+    pub const fn new(low: DescriptorEntry, high: DescriptorEntry) -> Self {
+        DescriptorPair { low, high }
+    }
+}
+
+/// Type of x86 Segment Limit
+#[repr(transparent)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Limit16(pub u16);
+
+impl AsDescriptorEntry for Limit16 {
+    #[inline]
+    fn as_descriptor_entry(&self) -> u64 {
+        self.0 as u64
+    }
+}
+
+/// Type of x86 Segment Limit
+#[repr(transparent)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Limit32(pub u32);
+
+impl Limit32 {
+    pub const MAX: Self = Self(u32::MAX);
+}
+
+impl AsDescriptorEntry for Limit32 {
+    #[inline]
+    fn as_descriptor_entry(&self) -> u64 {
+        let limit = self.0;
+        if limit > 0xFFFF {
+            0x0080_0000_0000_0000
+                | ((limit as u64) >> 12) & 0xFFFF
+                | ((limit as u64 & 0xF000_0000) << 20)
+        } else {
+            limit as u64
+        }
+    }
+}
+
+/// Type of 32bit Linear Address
+#[repr(transparent)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Linear32(pub u32);
+
+impl Linear32 {
+    #[inline]
+    pub const fn as_segment_base(&self) -> u64 {
+        ((self.0 as u64 & 0x00FF_FFFF) << 16) | ((self.0 as u64 & 0xFF00_0000) << 32)
+    }
+}
+
+/// Type of 64bit Linear Address
+#[repr(transparent)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Linear64(pub u64);
+
+impl Linear64 {
+    #[inline]
+    pub const fn as_segment_base_pair(&self) -> (u64, u64) {
+        let low = Linear32(self.0 as u32).as_segment_base();
+        let high = self.0 >> 32;
+        (low, high)
+    }
+}
+
+/// Type of 32bit Offset Address
+#[repr(transparent)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Offset32(pub u32);
+
+impl Offset32 {
+    #[inline]
+    pub const fn as_gate_offset(&self) -> u64 {
+        let offset = self.0 as u64;
+        (offset & 0xFFFF) | (offset & 0xFFFF_0000) << 32
+    }
+}
+
+/// Type of 64bit Offset Address
+#[repr(transparent)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Offset64(pub u64);
+
+impl Offset64 {
+    #[inline]
+    pub const fn as_gate_offset_pair(&self) -> (u64, u64) {
+        let low = Offset32(self.0 as u32).as_gate_offset();
+        let high = self.0 >> 32;
+        (low, high)
+    }
+}
+
+/// Type of x86 Segment Selector
+#[repr(transparent)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Selector(pub u16);
+
+impl Selector {
+    /// The NULL selector that does not contain anything
+    pub const NULL: Selector = Selector(0);
+    pub const KERNEL_CODE: Selector = Selector::new(1, PrivilegeLevel::Kernel);
+    pub const KERNEL_DATA: Selector = Selector::new(2, PrivilegeLevel::Kernel);
+    pub const LEGACY_CODE: Selector = Selector::new(3, PrivilegeLevel::User);
+    pub const LEGACY_DATA: Selector = Selector::new(4, PrivilegeLevel::User);
+    pub const USER_CODE: Selector = Selector::new(5, PrivilegeLevel::User);
+    pub const USER_DATA: Selector = Selector::new(6, PrivilegeLevel::User);
+    pub const SYSTEM_TSS: Selector = Selector::new(8, PrivilegeLevel::Kernel);
+
+    /// Make a new instance of the selector from the specified index and RPL
+    #[inline]
+    pub const fn new(index: usize, rpl: PrivilegeLevel) -> Self {
+        Selector((index << 3) as u16 | rpl as u16)
+    }
+
+    /// Returns the requested privilege level in the selector
+    #[inline]
+    pub const fn rpl(self) -> PrivilegeLevel {
+        PrivilegeLevel::from_usize(self.0 as usize)
+    }
+
+    /// Returns the index field in the selector
+    #[inline]
+    pub const fn index(self) -> usize {
+        (self.0 >> 3) as usize
+    }
+}
+
+impl AsDescriptorEntry for Selector {
+    #[inline]
+    fn as_descriptor_entry(&self) -> u64 {
+        (self.0 as u64) << 16
+    }
+}
+
+/// DPL, CPL, RPL and IOPL
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum PrivilegeLevel {
+    /// Ring 0, Kernel mode
+    Kernel = 0,
+    /// Ring 1, Useless in 64bit mode
+    Ring1 = 1,
+    /// Ring 2, Useless in 64bit mode
+    Ring2 = 2,
+    /// Ring 3, User mode
+    User = 3,
+}
+
+impl PrivilegeLevel {
+    #[inline]
+    pub const fn from_usize(value: usize) -> Self {
+        match value & 3 {
+            0 => PrivilegeLevel::Kernel,
+            1 => PrivilegeLevel::Ring1,
+            2 => PrivilegeLevel::Ring2,
+            3 => PrivilegeLevel::User,
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl AsDescriptorEntry for PrivilegeLevel {
+    #[inline]
+    fn as_descriptor_entry(&self) -> u64 {
+        (*self as u64) << 45
+    }
+}
+
+impl From<usize> for PrivilegeLevel {
+    #[inline]
+    fn from(value: usize) -> Self {
+        Self::from_usize(value)
+    }
+}
+
+#[non_exhaustive]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum DescriptorType {
+    Null = 0,
+    Tss = 9,
+    TssBusy = 11,
+    InterruptGate = 14,
+    TrapGate = 15,
+}
+
+impl AsDescriptorEntry for DescriptorType {
+    #[inline]
+    fn as_descriptor_entry(&self) -> u64 {
+        let ty = *self as u64;
+        ty << 40
+    }
+}
+
+#[repr(transparent)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct InterruptVector(pub u8);
+
+impl InterruptVector {
+    pub const IPI_INVALIDATE_TLB: Self = Self(0xEE);
+    pub const IPI_SCHEDULE: Self = Self(0xFC);
+}
+
+#[repr(u64)]
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum DefaultSize {
+    Use16 = 0x0000_0000_0000_0000,
+    Use32 = 0x0040_0000_0000_0000,
+    Use64 = 0x0020_0000_0000_0000,
+}
+
+impl AsDescriptorEntry for DefaultSize {
+    #[inline]
+    fn as_descriptor_entry(&self) -> u64 {
+        *self as u64
+    }
+}
+
+impl DefaultSize {
+    #[inline]
+    pub const fn as_descriptor_entry(&self) -> u64 {
+        *self as u64
+    }
+
+    #[inline]
+    pub const fn from_descriptor(value: DescriptorEntry) -> Option<Self> {
+        if value.is_code_segment() {
+            let is_32 = (value.0 & Self::Use32.as_descriptor_entry()) != 0;
+            let is_64 = (value.0 & Self::Use64.as_descriptor_entry()) != 0;
+            match (is_32, is_64) {
+                (false, false) => Some(Self::Use16),
+                (false, true) => Some(Self::Use64),
+                (true, false) => Some(Self::Use32),
+                (true, true) => None,
+            }
+        } else {
+            None
+        }
+    }
+}
+
+impl TryFrom<DescriptorEntry> for DefaultSize {
+    type Error = ();
+    fn try_from(value: DescriptorEntry) -> Result<Self, Self::Error> {
+        Self::from_descriptor(value).ok_or(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum InterruptStackTable {
+    IST1 = 1,
+    IST2,
+    IST3,
+    IST4,
+    IST5,
+    IST6,
+    IST7,
+}
+
+impl AsDescriptorEntry for InterruptStackTable {
+    #[inline]
+    fn as_descriptor_entry(&self) -> u64 {
+        (*self as u64) << 32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn segment_descriptors() {
+        assert_eq!(
+            DescriptorEntry::flat_code_segment(PrivilegeLevel::Kernel, DefaultSize::Use64).0,
+            0x00AF_9A00_0000_FFFF
+        );
+        assert_eq!(
+            DescriptorEntry::flat_code_segment(PrivilegeLevel::User, DefaultSize::Use32).0,
+            0x00CF_FA00_0000_FFFF
+        );
+        assert_eq!(
+            DescriptorEntry::flat_data_segment(PrivilegeLevel::Kernel).0,
+            0x00CF_9200_0000_FFFF
+        );
+        assert_eq!(
+            DescriptorEntry::flat_data_segment(PrivilegeLevel::User).0,
+            0x00CF_F200_0000_FFFF
+        );
+
+        let code = DescriptorEntry::flat_code_segment(PrivilegeLevel::User, DefaultSize::Use64);
+        assert_eq!(code.default_operand_size(), Some(DefaultSize::Use64));
+        assert_eq!(
+            DescriptorEntry::flat_data_segment(PrivilegeLevel::User).default_operand_size(),
+            None
+        );
+    }
+
+    #[test]
+    fn tss_descriptor() {
+        let pair = DescriptorEntry::tss_descriptor(Linear64(0x1234_5678_9ABC_DEF0), Limit16(0x67));
+        assert_eq!(pair.low.0, 0x9A00_89BC_DEF0_0067);
+        assert_eq!(pair.high.0, 0x0000_0000_1234_5678);
+    }
+
+    #[test]
+    fn selector() {
+        assert_eq!(Selector::KERNEL_CODE.0, 0x08);
+        assert_eq!(Selector::USER_CODE.0, 0x2B);
+        assert_eq!(Selector::SYSTEM_TSS.0, 0x40);
+        assert_eq!(Selector::USER_DATA.index(), 6);
+        assert_eq!(Selector::USER_DATA.rpl(), PrivilegeLevel::User);
+    }
+}
@@ -0,0 +1,175 @@
+//! General-purpose registers
+
+use core::{arch::global_asm, fmt};
+
+/// A snapshot of the general-purpose registers
+#[repr(C)]
+#[derive(Default, Clone, Copy, PartialEq, Eq)]
+pub struct Registers {
+    pub rax: u64,
+    pub rbx: u64,
+    pub rcx: u64,
+    pub rdx: u64,
+    pub rsi: u64,
+    pub rdi: u64,
+    pub rbp: u64,
+    pub rsp: u64,
+    pub r8: u64,
+    pub r9: u64,
+    pub r10: u64,
+    pub r11: u64,
+    pub r12: u64,
+    pub r13: u64,
+    pub r14: u64,
+    pub r15: u64,
+    pub rip: u64,
+    pub rflags: u64,
+}
+
+extern "C" {
+    /// Stores all registers to `regs` without modifying any of them.
+    fn myx64_capture_registers(regs: *mut Registers);
+}
+
+global_asm!(
+    "
+.global myx64_capture_registers
+myx64_capture_registers:
+    mov [rdi], rax
+    mov [rdi + 8], rbx
+    mov [rdi + 16], rcx
+    mov [rdi + 24], rdx
+    mov [rdi + 32], rsi
+    mov [rdi + 40], rdi
+    mov [rdi + 48], rbp
+    lea rax, [rsp + 8]
+    mov [rdi + 56], rax
+    mov [rdi + 64], r8
+    mov [rdi + 72], r9
+    mov [rdi + 80], r10
+    mov [rdi + 88], r11
+    mov [rdi + 96], r12
+    mov [rdi + 104], r13
+    mov [rdi + 112], r14
+    mov [rdi + 120], r15
+    mov rax, [rsp]
+    mov [rdi + 128], rax
+    pushfq
+    pop rax
+    mov [rdi + 136], rax
+    mov rax, [rdi]
+    ret
+"
+);
+
+impl Registers {
+    /// Captures the registers of the caller at the call site.
+    ///
+    /// This is the state of the function that calls it, not of the code that caused the fault.
+    /// When called from the panic handler, it records the handler itself,
+    /// which is still useful to see the stack and the callee-saved registers,
+    /// but the state at an exception is only available from its own context.
+    ///
+    /// `rip` and `rsp` are the values just after the call returns,
+    /// and `rdi` holds the pointer to the snapshot as it is the first argument.
+    #[inline(always)]
+    pub fn capture() -> Self {
+        let mut regs = Self::default();
+        unsafe {
+            myx64_capture_registers(&mut regs);
+        }
+        regs
+    }
+}
+
+impl fmt::Debug for Registers {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "rax {:016x} rbx {:016x} rcx {:016x} rdx {:016x}",
+            self.rax, self.rbx, self.rcx, self.rdx,
+        )?;
+        writeln!(
+            f,
+            "rsi {:016x} rdi {:016x} rbp {:016x} rsp {:016x}",
+            self.rsi, self.rdi, self.rbp, self.rsp,
+        )?;
+        writeln!(
+            f,
+            "r8  {:016x} r9  {:016x} r10 {:016x} r11 {:016x}",
+            self.r8, self.r9, self.r10, self.r11,
+        )?;
+        writeln!(
+            f,
+            "r12 {:016x} r13 {:016x} r14 {:016x} r15 {:016x}",
+            self.r12, self.r13, self.r14, self.r15,
+        )?;
+        write!(f, "rip {:016x} rflags {:08x}", self.rip, self.rflags)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::arch::asm;
+
+    #[test]
+    fn capture_round_trip() {
+        let mut regs = Registers::default();
+        let ptr = &mut regs as *mut Registers;
+        let rax: u64;
+        unsafe {
+            // skip the red zone before the call pushes the return address
+            asm!(
+                "
+                sub rsp, 128
+                call {capture}
+                add rsp, 128
+                ",
+                capture = sym myx64_capture_registers,
+                in("rdi") ptr,
+                inout("rax") 0x1111_0000_0000_0000u64 => rax,
+                inout("rcx") 0x3333_0000_0000_0000u64 => _,
+                inout("rdx") 0x4444_0000_0000_0000u64 => _,
+                inout("rsi") 0x5555_0000_0000_0000u64 => _,
+                inout("r8") 0x0808_0000_0000_0000u64 => _,
+                inout("r9") 0x0909_0000_0000_0000u64 => _,
+                inout("r10") 0x1010_0000_0000_0000u64 => _,
+                inout("r11") 0x1111_1111_0000_0000u64 => _,
+                in("r12") 0x1212_0000_0000_0000u64,
+                in("r13") 0x1313_0000_0000_0000u64,
+                in("r14") 0x1414_0000_0000_0000u64,
+                in("r15") 0x1515_0000_0000_0000u64,
+            );
+        }
+
+        // the scratch register is restored
+        assert_eq!(rax, 0x1111_0000_0000_0000);
+        assert_eq!(regs.rax, 0x1111_0000_0000_0000);
+        assert_eq!(regs.rcx, 0x3333_0000_0000_0000);
+        assert_eq!(regs.rdx, 0x4444_0000_0000_0000);
+        assert_eq!(regs.rsi, 0x5555_0000_0000_0000);
+        assert_eq!(regs.rdi, ptr as u64);
+        assert_eq!(regs.r8, 0x0808_0000_0000_0000);
+        assert_eq!(regs.r9, 0x0909_0000_0000_0000);
+        assert_eq!(regs.r10, 0x1010_0000_0000_0000);
+        assert_eq!(regs.r11, 0x1111_1111_0000_0000);
+        assert_eq!(regs.r12, 0x1212_0000_0000_0000);
+        assert_eq!(regs.r13, 0x1313_0000_0000_0000);
+        assert_eq!(regs.r14, 0x1414_0000_0000_0000);
+        assert_eq!(regs.r15, 0x1515_0000_0000_0000);
+        // bit 1 of RFLAGS is always set
+        assert_eq!(regs.rflags & 2, 2);
+        assert_ne!(regs.rip, 0);
+        assert_eq!(regs.rsp & 7, 0);
+    }
+
+    #[test]
+    fn capture_caller() {
+        let regs = Registers::capture();
+        let here = capture_caller as *const () as u64;
+        // the return address is in this function
+        assert!(regs.rip > here && regs.rip < here + 0x1000);
+        assert_ne!(regs.rsp, 0);
+    }
+}
@@ -0,0 +1,82 @@
+//! Interrupt descriptor table
+
+use crate::desc::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IdtError {
+    /// The vector already has a handler
+    InUse(InterruptVector),
+    /// The vector is beyond the end of the table
+    OutOfRange(InterruptVector),
+}
+
+/// Writes the gate of the handler at `offset` to the vector in the table,
+/// which has two entries for each vector.
+///
+/// Handlers for the kernel are interrupt gates, and handlers callable from the user are trap gates.
+pub fn set_gate(
+    table: &mut [DescriptorEntry],
+    vec: InterruptVector,
+    offset: u64,
+    dpl: PrivilegeLevel,
+) -> Result<(), IdtError> {
+    let index = vec.0 as usize * 2;
+    let entries = table
+        .get_mut(index..index + 2)
+        .ok_or(IdtError::OutOfRange(vec))?;
+    if !entries[0].is_null() {
+        return Err(IdtError::InUse(vec));
+    }
+    let pair = DescriptorEntry::gate_descriptor(
+        Offset64(offset),
+        Selector::KERNEL_CODE,
+        dpl,
+        if dpl == PrivilegeLevel::Kernel {
+            DescriptorType::InterruptGate
+        } else {
+            DescriptorType::TrapGate
+        },
+        None,
+    );
+    // The present bit is in the low half, so it is written last.
+    entries[1] = pair.high;
+    entries[0] = pair.low;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn update_table() {
+        let mut table = [DescriptorEntry::null(); 8];
+        let vec = InterruptVector(2);
+
+        set_gate(
+            &mut table,
+            vec,
+            0xFFFF_8000_1234_5678,
+            PrivilegeLevel::Kernel,
+        )
+        .unwrap();
+        assert!(table[..4].iter().all(|v| v.is_null()));
+        assert!(table[4].is_present());
+        assert_eq!(table[4], DescriptorEntry::from_raw(0x1234_8E00_0008_5678));
+        assert_eq!(table[5], DescriptorEntry::from_raw(0x0000_0000_FFFF_8000));
+        assert!(table[6..].iter().all(|v| v.is_null()));
+
+        assert_eq!(
+            set_gate(&mut table, vec, 0, PrivilegeLevel::Kernel),
+            Err(IdtError::InUse(vec))
+        );
+
+        set_gate(&mut table, InterruptVector(3), 0x4000, PrivilegeLevel::User).unwrap();
+        assert_eq!(table[6], DescriptorEntry::from_raw(0x0000_EF00_0008_4000));
+
+        assert_eq!(
+            set_gate(&mut table, InterruptVector(4), 0, PrivilegeLevel::Kernel),
+            Err(IdtError::OutOfRange(InterruptVector(4)))
+        );
+    }
+}
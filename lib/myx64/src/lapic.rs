@@ -0,0 +1,200 @@
+//! Local APIC timer
+
+use crate::desc::InterruptVector;
+use core::time::Duration;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum TimerMode {
+    OneShot = 0 << 17,
+    Periodic = 1 << 17,
+    TscDeadline = 2 << 17,
+}
+
+impl TimerMode {
+    /// Mask of the timer mode bits in the LVT timer register
+    pub const MASK: u32 = 3 << 17;
+
+    #[inline]
+    pub const fn from_lvt(lvt: u32) -> Option<Self> {
+        match lvt & Self::MASK {
+            0x0000_0000 => Some(Self::OneShot),
+            0x0002_0000 => Some(Self::Periodic),
+            0x0004_0000 => Some(Self::TscDeadline),
+            _ => None,
+        }
+    }
+}
+
+/// Values of the divide configuration register
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum TimerDivide {
+    By1 = 0b1011,
+    By2 = 0b0000,
+    By4 = 0b0001,
+    By8 = 0b0010,
+    By16 = 0b0011,
+    By32 = 0b1000,
+    By64 = 0b1001,
+    By128 = 0b1010,
+}
+
+impl TimerDivide {
+    /// All divisors from the smallest
+    pub const ALL: [Self; 8] = [
+        Self::By1,
+        Self::By2,
+        Self::By4,
+        Self::By8,
+        Self::By16,
+        Self::By32,
+        Self::By64,
+        Self::By128,
+    ];
+
+    #[inline]
+    pub const fn divisor(&self) -> u64 {
+        match self {
+            Self::By1 => 1,
+            Self::By2 => 2,
+            Self::By4 => 4,
+            Self::By8 => 8,
+            Self::By16 => 16,
+            Self::By32 => 32,
+            Self::By64 => 64,
+            Self::By128 => 128,
+        }
+    }
+}
+
+/// Registers of the local APIC that drive the timer
+pub trait TimerRegisters {
+    fn write_divide_configuration(&mut self, value: u32);
+
+    fn write_lvt_timer(&mut self, value: u32);
+
+    fn write_initial_count(&mut self, value: u32);
+}
+
+/// Initial count and divisor for a duration
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct TimerSetting {
+    pub divide: TimerDivide,
+    pub initial_count: u32,
+}
+
+impl TimerSetting {
+    /// Converts the duration with the calibrated frequency of the timer at the divisor of 1.
+    ///
+    /// The smallest divisor that the count fits in is chosen to keep the resolution,
+    /// and the count is saturated if it does not fit even with the largest one.
+    pub fn new(ticks_per_ms: u32, duration: Duration) -> Self {
+        let ticks = ticks_per_ms as u128 * duration.as_micros() / 1000;
+        for divide in TimerDivide::ALL {
+            let count = ticks / divide.divisor() as u128;
+            if count <= u32::MAX as u128 {
+                return Self {
+                    divide,
+                    initial_count: (count as u32).max(1),
+                };
+            }
+        }
+        Self {
+            divide: TimerDivide::By128,
+            initial_count: u32::MAX,
+        }
+    }
+
+    /// Programs the timer. Writing the initial count starts the timer, so it is written last.
+    pub fn program<R: TimerRegisters>(&self, regs: &mut R, mode: TimerMode, vec: InterruptVector) {
+        regs.write_divide_configuration(self.divide as u32);
+        regs.write_lvt_timer(vec.0 as u32 | mode as u32);
+        regs.write_initial_count(self.initial_count);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Default)]
+    struct MockApic {
+        divide_configuration: Option<u32>,
+        lvt_timer: Option<u32>,
+        initial_count: Option<u32>,
+        started_before_configured: bool,
+    }
+
+    impl TimerRegisters for MockApic {
+        fn write_divide_configuration(&mut self, value: u32) {
+            self.started_before_configured |= self.initial_count.is_some();
+            self.divide_configuration = Some(value);
+        }
+
+        fn write_lvt_timer(&mut self, value: u32) {
+            self.started_before_configured |= self.initial_count.is_some();
+            self.lvt_timer = Some(value);
+        }
+
+        fn write_initial_count(&mut self, value: u32) {
+            self.initial_count = Some(value);
+        }
+    }
+
+    #[test]
+    fn periodic_timer() {
+        let mut apic = MockApic::default();
+        let setting = TimerSetting::new(100_000, Duration::from_millis(1));
+        setting.program(&mut apic, TimerMode::Periodic, InterruptVector(0x20));
+
+        assert_eq!(apic.divide_configuration, Some(0b1011));
+        assert_eq!(apic.lvt_timer, Some(0x0002_0020));
+        assert_eq!(apic.initial_count, Some(100_000));
+        assert!(!apic.started_before_configured);
+        assert_eq!(
+            TimerMode::from_lvt(apic.lvt_timer.unwrap()),
+            Some(TimerMode::Periodic)
+        );
+    }
+
+    #[test]
+    fn one_shot_timer() {
+        let mut apic = MockApic::default();
+        let setting = TimerSetting::new(100_000, Duration::from_micros(250));
+        setting.program(&mut apic, TimerMode::OneShot, InterruptVector(0x20));
+
+        assert_eq!(apic.divide_configuration, Some(0b1011));
+        assert_eq!(apic.lvt_timer, Some(0x0000_0020));
+        assert_eq!(apic.initial_count, Some(25_000));
+        assert_eq!(
+            TimerMode::from_lvt(apic.lvt_timer.unwrap()),
+            Some(TimerMode::OneShot)
+        );
+    }
+
+    #[test]
+    fn timer_setting() {
+        // too short to count
+        assert_eq!(
+            TimerSetting::new(100_000, Duration::ZERO),
+            TimerSetting {
+                divide: TimerDivide::By1,
+                initial_count: 1,
+            }
+        );
+        // 1GHz for 10 seconds needs the divisor of 4
+        assert_eq!(
+            TimerSetting::new(1_000_000, Duration::from_secs(10)),
+            TimerSetting {
+                divide: TimerDivide::By4,
+                initial_count: 2_500_000_000,
+            }
+        );
+        assert_eq!(
+            TimerSetting::new(u32::MAX, Duration::MAX),
+            TimerSetting {
+                divide: TimerDivide::By128,
+                initial_count: u32::MAX,
+            }
+        );
+    }
+}
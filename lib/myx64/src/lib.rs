@@ -0,0 +1,9 @@
+//! Processor structures of x86-64
+#![no_std]
+
+pub mod desc;
+#[cfg(target_arch = "x86_64")]
+pub mod gpr;
+pub mod idt;
+pub mod lapic;
+pub mod syscall;
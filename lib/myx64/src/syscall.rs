@@ -0,0 +1,86 @@
+//! SYSCALL/SYSRET
+
+use crate::desc::Selector;
+
+/// Values of the MSRs that configure SYSCALL/SYSRET
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SyscallMsrs {
+    /// IA32_STAR
+    pub star: u64,
+    /// IA32_LSTAR
+    pub lstar: u64,
+    /// IA32_FMASK
+    pub fmask: u64,
+}
+
+impl SyscallMsrs {
+    /// Computes the MSR values.
+    ///
+    /// SYSCALL enters `handler` with `kernel_code` and the following data segment,
+    /// and masks the RFLAGS bits in `rflags_mask`.
+    /// SYSRET returns to 64-bit mode with the code segment at `user_base + 16`
+    /// and the stack segment at `user_base + 8`.
+    #[inline]
+    pub const fn new(
+        handler: u64,
+        kernel_code: Selector,
+        user_base: Selector,
+        rflags_mask: u64,
+    ) -> Self {
+        Self {
+            star: ((user_base.0 as u64) << 48) | ((kernel_code.0 as u64) << 32),
+            lstar: handler,
+            // The upper half of IA32_FMASK is reserved
+            fmask: rflags_mask & 0xFFFF_FFFF,
+        }
+    }
+
+    /// Returns the selector of the code segment that SYSCALL loads.
+    #[inline]
+    pub const fn syscall_cs(&self) -> Selector {
+        Selector((self.star >> 32) as u16 & !3)
+    }
+
+    /// Returns the selector of the stack segment that SYSCALL loads.
+    #[inline]
+    pub const fn syscall_ss(&self) -> Selector {
+        Selector(((self.star >> 32) as u16 & !3) + 8)
+    }
+
+    /// Returns the selector of the 64-bit code segment that SYSRET loads.
+    #[inline]
+    pub const fn sysret_cs(&self) -> Selector {
+        Selector(((self.star >> 48) as u16 + 16) | 3)
+    }
+
+    /// Returns the selector of the stack segment that SYSRET loads.
+    #[inline]
+    pub const fn sysret_ss(&self) -> Selector {
+        Selector(((self.star >> 48) as u16 + 8) | 3)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn syscall_msrs() {
+        // IF | TF | DF | AC, and a reserved bit in the upper half
+        let mask = 0x0000_0001_0004_0700;
+        let msrs = SyscallMsrs::new(
+            0xFFFF_8000_1234_5678,
+            Selector::KERNEL_CODE,
+            Selector::LEGACY_CODE,
+            mask,
+        );
+        assert_eq!(msrs.star, 0x001B_0008_0000_0000);
+        assert_eq!(msrs.lstar, 0xFFFF_8000_1234_5678);
+        assert_eq!(msrs.fmask, 0x0004_0700);
+
+        assert_eq!(msrs.syscall_cs(), Selector::KERNEL_CODE);
+        assert_eq!(msrs.syscall_ss(), Selector::KERNEL_DATA);
+        assert_eq!(msrs.sysret_cs(), Selector::USER_CODE);
+        assert_eq!(msrs.sysret_ss(), Selector::LEGACY_DATA);
+    }
+}
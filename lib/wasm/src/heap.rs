@@ -0,0 +1,237 @@
+//! Heap allocator for the linear memory
+
+use crate::{WasmMemory, WasmRuntimeErrorKind};
+use alloc::vec::Vec;
+use core::{alloc::Layout, num::NonZeroU32};
+
+/// A simple allocator that manages the free blocks of the linear memory
+pub struct SimpleAllocator {
+    data: Vec<SimpleFreePair>,
+    strategy: AllocationStrategy,
+}
+
+impl SimpleAllocator {
+    const MIN_MASK: u32 = 0x0000_000F;
+
+    #[inline]
+    pub const fn new(strategy: AllocationStrategy) -> Self {
+        Self {
+            data: Vec::new(),
+            strategy,
+        }
+    }
+
+    fn merge(&mut self, new_data: Option<SimpleFreePair>) {
+        if let Some(new_data) = new_data {
+            self.data.push(new_data);
+        }
+        self.data.sort_by_key(|v| v.base);
+
+        let mut index = 0;
+        while index + 1 < self.data.len() {
+            if self.data[index].next_base() == self.data[index + 1].base {
+                let next = self.data.remove(index + 1);
+                self.data[index].size += next.size;
+            } else {
+                index += 1;
+            }
+        }
+    }
+
+    pub fn append_block(&mut self, base: u32, size: u32) {
+        self.merge(Some(SimpleFreePair::new(base, size)));
+    }
+
+    #[inline]
+    fn alloc_size(layout: Layout) -> u32 {
+        let layout_align = u32::max(layout.align() as u32, Self::MIN_MASK + 1);
+        (u32::max(layout_align, layout.size() as u32) + Self::MIN_MASK) & !Self::MIN_MASK
+    }
+
+    pub fn alloc(&mut self, layout: Layout) -> Option<NonZeroU32> {
+        let layout_align = u32::max(layout.align() as u32, Self::MIN_MASK + 1);
+        let layout_mask = layout_align - 1;
+        let min_alloc = Self::alloc_size(layout);
+
+        let mut result = 0;
+        let mut padding = None;
+        match self.strategy {
+            AllocationStrategy::FirstFit => {
+                for pair in &mut self.data {
+                    // The unaligned head of the block is left free
+                    let redundant = pair.base.wrapping_neg() & layout_mask;
+                    if pair.size >= redundant + min_alloc {
+                        if redundant > 0 {
+                            padding = Some(SimpleFreePair::new(pair.base, redundant));
+                            pair.base += redundant;
+                            pair.size -= redundant;
+                        }
+
+                        result = pair.base;
+                        pair.size -= min_alloc;
+                        pair.base += min_alloc;
+                        break;
+                    }
+                }
+            }
+            AllocationStrategy::BestFit => todo!(),
+        }
+        if padding.is_some() {
+            self.merge(padding);
+        }
+
+        NonZeroU32::new(result)
+    }
+
+    /// Allocates the block, growing the memory if there is no free block large enough.
+    pub fn alloc_or_grow(
+        &mut self,
+        memory: &WasmMemory,
+        layout: Layout,
+    ) -> Result<NonZeroU32, WasmRuntimeErrorKind> {
+        if let Some(result) = self.alloc(layout) {
+            return Ok(result);
+        }
+
+        let page_size = WasmMemory::PAGE_SIZE;
+        let delta = layout.size().div_ceil(page_size) as i32;
+        let new_page = memory.grow(delta);
+        if new_page > 0 {
+            self.append_block(
+                new_page as u32 * page_size as u32,
+                delta as u32 * page_size as u32,
+            );
+        } else {
+            return Err(WasmRuntimeErrorKind::OutOfMemory);
+        }
+
+        self.alloc(layout).ok_or(WasmRuntimeErrorKind::OutOfMemory)
+    }
+
+    /// Changes the size of the allocated block without moving it.
+    ///
+    /// Shrinking always succeeds, growing succeeds only if the following free block is large enough.
+    pub fn resize_in_place(&mut self, base: u32, layout: Layout, new_size: usize) -> bool {
+        let Ok(new_layout) = Layout::from_size_align(new_size, layout.align()) else {
+            return false;
+        };
+        let old_alloc = Self::alloc_size(layout);
+        let new_alloc = Self::alloc_size(new_layout);
+
+        if new_alloc <= old_alloc {
+            if new_alloc < old_alloc {
+                self.merge(Some(SimpleFreePair::new(
+                    base + new_alloc,
+                    old_alloc - new_alloc,
+                )));
+            }
+            return true;
+        }
+
+        let delta = new_alloc - old_alloc;
+        let Some(index) = self
+            .data
+            .iter()
+            .position(|pair| pair.base == base + old_alloc && pair.size >= delta)
+        else {
+            return false;
+        };
+        let pair = &mut self.data[index];
+        pair.base += delta;
+        pair.size -= delta;
+        if pair.size == 0 {
+            self.data.remove(index);
+        }
+        true
+    }
+
+    /// Changes the size of the allocated block, preserving its contents.
+    ///
+    /// The block is resized in place if possible, otherwise it is moved to a new block.
+    pub fn realloc(
+        &mut self,
+        memory: &WasmMemory,
+        base: u32,
+        layout: Layout,
+        new_size: usize,
+    ) -> Result<NonZeroU32, WasmRuntimeErrorKind> {
+        if self.resize_in_place(base, layout, new_size) {
+            return NonZeroU32::new(base).ok_or(WasmRuntimeErrorKind::InvalidParameter);
+        }
+
+        let new_layout = Layout::from_size_align(new_size, layout.align())
+            .map_err(|_| WasmRuntimeErrorKind::InvalidParameter)?;
+        let result = self.alloc_or_grow(memory, new_layout)?;
+        memory.copy(
+            result.get() as usize,
+            base as usize,
+            usize::min(layout.size(), new_size),
+        )?;
+        self.dealloc(base, layout);
+
+        Ok(result)
+    }
+
+    pub fn dealloc(&mut self, base: u32, layout: Layout) {
+        let min_alloc = Self::alloc_size(layout);
+
+        let new_pair = SimpleFreePair::new(base, min_alloc);
+
+        let mut cursor = None;
+        for (index, pair) in self.data.iter_mut().enumerate() {
+            if new_pair.next_base() == pair.base {
+                pair.base = new_pair.base;
+                pair.size += min_alloc;
+                cursor = Some(index);
+                break;
+            } else if pair.next_base() == base {
+                pair.size += min_alloc;
+                cursor = Some(index);
+                break;
+            }
+        }
+
+        if let Some(index) = cursor {
+            if index + 1 < self.data.len()
+                && self.data[index].next_base() == self.data[index + 1].base
+            {
+                let next = self.data.remove(index + 1);
+                self.data[index].size += next.size;
+            }
+        } else {
+            self.merge(Some(new_pair));
+        }
+    }
+}
+
+impl Default for SimpleAllocator {
+    #[inline]
+    fn default() -> Self {
+        Self::new(AllocationStrategy::FirstFit)
+    }
+}
+
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum AllocationStrategy {
+    FirstFit,
+    BestFit,
+}
+
+#[derive(Clone, Copy)]
+struct SimpleFreePair {
+    base: u32,
+    size: u32,
+}
+
+impl SimpleFreePair {
+    #[inline]
+    pub const fn new(base: u32, size: u32) -> Self {
+        Self { base, size }
+    }
+
+    #[inline]
+    pub const fn next_base(&self) -> u32 {
+        self.base + self.size
+    }
+}
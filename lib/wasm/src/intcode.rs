@@ -197,6 +197,67 @@ pub enum WasmIntMnemonic {
     FusedI64BrZ(usize),
     FusedI64BrEq(usize),
     FusedI64BrNe(usize),
+
+    // Memory access without the bounds check
+    I32LoadUnchecked(u32),
+    I32Load8UUnchecked(u32),
+    I32StoreUnchecked(u32),
+    I32Store8Unchecked(u32),
+    I64LoadUnchecked(u32),
+    I64StoreUnchecked(u32),
+}
+
+impl WasmIntMnemonic {
+    /// Returns the offset and the size of the memory access if this is a load or a store.
+    pub const fn memory_access(&self) -> Option<(u32, u32)> {
+        use WasmIntMnemonic::*;
+        match *self {
+            I32Load8S(offset) | I32Load8U(offset) | I32Store8(offset) | I64Load8S(offset)
+            | I64Load8U(offset) | I64Store8(offset) => Some((offset, 1)),
+
+            I32Load16S(offset) | I32Load16U(offset) | I32Store16(offset) | I64Load16S(offset)
+            | I64Load16U(offset) | I64Store16(offset) => Some((offset, 2)),
+
+            I32Load(offset) | I32Store(offset) | I64Load32S(offset) | I64Load32U(offset)
+            | I64Store32(offset) => Some((offset, 4)),
+            #[cfg(feature = "float")]
+            F32Load(offset) | F32Store(offset) => Some((offset, 4)),
+
+            I64Load(offset) | I64Store(offset) => Some((offset, 8)),
+            #[cfg(feature = "float64")]
+            F64Load(offset) | F64Store(offset) => Some((offset, 8)),
+
+            _ => None,
+        }
+    }
+
+    /// Returns whether this is a memory store.
+    pub const fn is_memory_store(&self) -> bool {
+        use WasmIntMnemonic::*;
+        match *self {
+            I32Store(_) | I32Store8(_) | I32Store16(_) | I64Store(_) | I64Store8(_)
+            | I64Store16(_) | I64Store32(_) => true,
+            #[cfg(feature = "float")]
+            F32Store(_) => true,
+            #[cfg(feature = "float64")]
+            F64Store(_) => true,
+            _ => false,
+        }
+    }
+
+    /// Returns the same memory access without the bounds check, if available.
+    pub const fn without_bounds_check(&self) -> Option<Self> {
+        use WasmIntMnemonic::*;
+        match *self {
+            I32Load(offset) => Some(I32LoadUnchecked(offset)),
+            I32Load8U(offset) => Some(I32Load8UUnchecked(offset)),
+            I32Store(offset) => Some(I32StoreUnchecked(offset)),
+            I32Store8(offset) => Some(I32Store8Unchecked(offset)),
+            I64Load(offset) => Some(I64LoadUnchecked(offset)),
+            I64Store(offset) => Some(I64StoreUnchecked(offset)),
+            _ => None,
+        }
+    }
 }
 
 /// Wasm Intermediate Code
@@ -11,19 +11,42 @@ const INITIAL_VALUE_STACK_SIZE: usize = 512;
 pub struct WasmInterpreter<'a> {
     module: &'a WasmModule,
     func_index: usize,
+    call_depth: usize,
+    max_call_depth: usize,
 }
 
 impl<'a> WasmInterpreter<'a> {
+    /// Default maximum depth of nested calls
+    ///
+    /// Each nested call consumes the host stack,
+    /// so this must fit in the kernel thread stack.
+    pub const DEFAULT_MAX_CALL_DEPTH: usize = 64;
+
     #[inline]
     pub fn new(module: &'a WasmModule) -> Self {
         Self {
             module,
             func_index: 0,
+            call_depth: 0,
+            max_call_depth: Self::DEFAULT_MAX_CALL_DEPTH,
         }
     }
+
+    /// Sets the maximum depth of nested calls.
+    /// Calls beyond this depth trap with `CallStackExhausted`.
+    #[inline]
+    pub const fn with_max_call_depth(mut self, max_call_depth: usize) -> Self {
+        self.max_call_depth = max_call_depth;
+        self
+    }
 }
 
 impl WasmInterpreter<'_> {
+    #[inline]
+    pub const fn max_call_depth(&self) -> usize {
+        self.max_call_depth
+    }
+
     #[inline]
     fn error(&self, kind: WasmRuntimeErrorKind, code: &WasmImc) -> WasmRuntimeError {
         let function_name = self
@@ -56,6 +79,7 @@ impl WasmInterpreter<'_> {
         result_types: &[WasmValType],
     ) -> Result<Option<WasmValue>, WasmRuntimeError> {
         let mut heap = StackHeap::with_capacity(0x10000);
+        self.call_depth = 0;
         self._interpret(
             func_index,
             code_block,
@@ -81,7 +105,15 @@ impl WasmInterpreter<'_> {
 
         let mut result_stack_level = StackLevel::zero();
 
-        let memory = unsafe { self.module.memory_unchecked(0) };
+        // The memory instructions are rejected on loading a module without memory
+        let empty_memory;
+        let memory = match self.module.memory(0) {
+            Some(v) => v,
+            None => {
+                empty_memory = WasmMemory::new(WasmLimit::new(0, 0));
+                &empty_memory
+            }
+        };
 
         while let Some(code) = codes.fetch() {
             match *code.mnemonic() {
@@ -1061,6 +1093,50 @@ impl WasmInterpreter<'_> {
                         codes.set_position(target);
                     }
                 }
+
+                // The bounds have already been checked by the preceding access
+                WasmIntMnemonic::I32LoadUnchecked(offset) => {
+                    let var = unsafe { value_stack.get_unchecked_mut(code.base_stack_level()) };
+                    *var = WasmUnsafeValue::from(unsafe {
+                        memory.read_u32_unchecked(offset, var.get_u32())
+                    });
+                }
+                WasmIntMnemonic::I32Load8UUnchecked(offset) => {
+                    let var = unsafe { value_stack.get_unchecked_mut(code.base_stack_level()) };
+                    *var = WasmUnsafeValue::from(unsafe {
+                        memory.read_u8_unchecked(offset, var.get_u32()) as u32
+                    });
+                }
+                WasmIntMnemonic::I64LoadUnchecked(offset) => {
+                    let var = unsafe { value_stack.get_unchecked_mut(code.base_stack_level()) };
+                    *var = WasmUnsafeValue::from(unsafe {
+                        memory.read_u64_unchecked(offset, var.get_u32())
+                    });
+                }
+                WasmIntMnemonic::I32StoreUnchecked(offset) => {
+                    let stack_level = code.base_stack_level();
+                    unsafe {
+                        let index = value_stack.get_unchecked(stack_level).get_u32();
+                        let data = value_stack.get_unchecked(stack_level + 1).get_u32();
+                        memory.write_u32_unchecked(offset, index, data);
+                    }
+                }
+                WasmIntMnemonic::I32Store8Unchecked(offset) => {
+                    let stack_level = code.base_stack_level();
+                    unsafe {
+                        let index = value_stack.get_unchecked(stack_level).get_u32();
+                        let data = value_stack.get_unchecked(stack_level + 1).get_u8();
+                        memory.write_u8_unchecked(offset, index, data);
+                    }
+                }
+                WasmIntMnemonic::I64StoreUnchecked(offset) => {
+                    let stack_level = code.base_stack_level();
+                    unsafe {
+                        let index = value_stack.get_unchecked(stack_level).get_u32();
+                        let data = value_stack.get_unchecked(stack_level + 1).get_u64();
+                        memory.write_u64_unchecked(offset, index, data);
+                    }
+                }
             }
         }
         if let Some(result_type) = result_types.first() {
@@ -1091,6 +1167,9 @@ impl WasmInterpreter<'_> {
         let stack_under = stack_pointer - StackOffset::new(param_len);
 
         if let Some(code_block) = target.code_block() {
+            if self.call_depth >= self.max_call_depth {
+                return Err(self.error(WasmRuntimeErrorKind::CallStackExhausted, code));
+            }
             heap.snapshot(|heap| {
                 let local_len = param_len + code_block.local_types().len();
 
@@ -1119,20 +1198,21 @@ impl WasmInterpreter<'_> {
                     *local = WasmUnsafeValue::zero();
                 }
 
-                self._interpret(
+                self.call_depth += 1;
+                let result = self._interpret(
                     target.index(),
                     code_block,
                     locals.as_locals(),
                     result_types,
                     heap,
-                )
-                .and_then(|v| {
+                );
+                self.call_depth -= 1;
+                result.map(|v| {
                     if let Some(result) = v {
                         let var = unsafe { value_stack.get_unchecked_mut(stack_under) };
                         *var = WasmUnsafeValue::from(result);
                     }
                     self.func_index = current_function;
-                    Ok(())
                 })
             })
         } else if let Some(function) = target.dlink() {
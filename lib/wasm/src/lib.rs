@@ -11,6 +11,7 @@
 mod wasm;
 pub use crate::wasm::*;
 
+pub mod heap;
 pub mod intcode;
 pub mod intr;
 pub mod opcode;
@@ -1,7 +1,9 @@
 // test
 
 use crate::{
+    intcode::WasmIntMnemonic,
     intr::{WasmInterpreter, WasmInvocation},
+    opcode::WasmSingleOpcode,
     WasmValType, *,
 };
 use alloc::borrow::ToOwned;
@@ -152,7 +154,8 @@ fn const_local() {
         WasmCodeBlock::generate(0, 0, &mut stream, &param_types, &result_types, &module).unwrap();
     let mut interp = WasmInterpreter::new(&module);
 
-    let mut locals = [];
+    // The declared local variable
+    let mut locals = [0.into()];
     let result = interp
         .invoke(0, &info, &mut locals, &result_types)
         .unwrap()
@@ -993,3 +996,180 @@ fn float64_reinterpret() {
         .unwrap();
     assert_eq!(result, 0x400921fb54442d18u64);
 }
+
+#[test]
+fn memory_bounds_check_elision() {
+    #[rustfmt::skip]
+    let slice = [
+        0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00, 0x01, 0x06, 0x01, 0x60, 0x01, 0x7f, 0x01,
+        0x7f, 0x03, 0x02, 0x01, 0x00, 0x05, 0x03, 0x01, 0x00, 0x01, 0x0a, 0x28, 0x01, 0x26, 0x00,
+        // i32.store offset=8 (local 0) 0x12345678
+        0x20, 0x00, 0x41, 0xf8, 0xac, 0xd1, 0x91, 0x01, 0x36, 0x02, 0x08,
+        // i32.store8 offset=4 (local 0) 0x55
+        0x20, 0x00, 0x41, 0xd5, 0x00, 0x3a, 0x00, 0x04,
+        // i32.load8_u offset=4 (local 0)
+        0x20, 0x00, 0x2d, 0x00, 0x04,
+        // i32.load offset=8 (local 0)
+        0x20, 0x00, 0x28, 0x02, 0x08,
+        0x6a,
+        // i32.load offset=12 (local 0)
+        0x20, 0x00, 0x28, 0x02, 0x0c,
+        0x6a,
+        0x0b,
+    ];
+    let module = WasmLoader::instantiate(&slice, |_, _, _| unreachable!()).unwrap();
+    let runnable = module.func_by_index(0).unwrap();
+
+    // The accesses within the range checked by the first store are not checked again
+    let codes = runnable
+        .function()
+        .code_block()
+        .unwrap()
+        .intermediate_codes();
+    let n_unchecked = codes
+        .iter()
+        .filter(|v| {
+            matches!(
+                v.mnemonic(),
+                WasmIntMnemonic::I32StoreUnchecked(_)
+                    | WasmIntMnemonic::I32Store8Unchecked(_)
+                    | WasmIntMnemonic::I32LoadUnchecked(_)
+                    | WasmIntMnemonic::I32Load8UUnchecked(_)
+            )
+        })
+        .count();
+    assert_eq!(n_unchecked, 3);
+
+    let result = runnable
+        .invoke(&[0.into()])
+        .unwrap()
+        .unwrap()
+        .get_u32()
+        .unwrap();
+    assert_eq!(result, 0x1234_56CD);
+
+    // The last load is out of bounds and must still trap
+    let index = WasmMemory::PAGE_SIZE as u32 - 12;
+    let err = runnable.invoke(&[index.into()]).unwrap_err();
+    assert_eq!(err.kind(), WasmRuntimeErrorKind::OutOfBounds);
+    let memory = module.memory(0).unwrap();
+    assert_eq!(memory.read_u32(8, index), Ok(0x1234_5678));
+    assert_eq!(memory.read_u8(4, index), Ok(0x55));
+
+    // The first store is out of bounds
+    let index = WasmMemory::PAGE_SIZE as u32 - 11;
+    let err = runnable.invoke(&[index.into()]).unwrap_err();
+    assert_eq!(err.kind(), WasmRuntimeErrorKind::OutOfBounds);
+}
+
+#[test]
+fn unreachable_trap() {
+    #[rustfmt::skip]
+    let slice = [
+        0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00, 0x01, 0x06, 0x01, 0x60, 0x01, 0x7f, 0x01,
+        0x7f, 0x03, 0x02, 0x01, 0x00, 0x05, 0x03, 0x01, 0x00, 0x01, 0x0a, 0x07, 0x01, 0x05, 0x00,
+        // local.get 0
+        0x20, 0x00,
+        // unreachable
+        0x00,
+        0x0b,
+    ];
+    let module = WasmLoader::instantiate(&slice, |_, _, _| unreachable!()).unwrap();
+    let runnable = module.func_by_index(0).unwrap();
+
+    let err = runnable.invoke(&[1234.into()]).unwrap_err();
+    assert_eq!(err.kind(), WasmRuntimeErrorKind::Unreachable);
+    assert!(err.kind().is_trap());
+    assert_eq!(err.function(), 0);
+    assert_eq!(err.opcode(), WasmSingleOpcode::Unreachable.into());
+    assert_eq!(err.position(), 3);
+    assert_eq!(err.file_position(), 32);
+}
+
+#[test]
+fn call_stack_exhausted() {
+    #[rustfmt::skip]
+    let slice = [
+        0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00, 0x01, 0x06, 0x01, 0x60, 0x01, 0x7f, 0x01,
+        0x7f, 0x03, 0x02, 0x01, 0x00, 0x05, 0x03, 0x01, 0x00, 0x01, 0x0a, 0x16, 0x01, 0x14, 0x00,
+        // block
+        0x02, 0x40,
+        // br_if 0 (i32.eqz (local 0))
+        0x20, 0x00, 0x45, 0x0d, 0x00,
+        // return (call 0 (i32.sub (local 0) 1))
+        0x20, 0x00, 0x41, 0x01, 0x6b, 0x10, 0x00, 0x0f,
+        0x0b,
+        0x41, 0x00,
+        0x0b,
+    ];
+    let module = WasmLoader::instantiate(&slice, |_, _, _| unreachable!()).unwrap();
+    let runnable = module.func_by_index(0).unwrap();
+    let function = runnable.function();
+    let code_block = function.code_block().unwrap();
+    let result_types = function.result_types();
+
+    const MAX_DEPTH: usize = 8;
+    let mut interp = WasmInterpreter::new(&module).with_max_call_depth(MAX_DEPTH);
+    assert_eq!(interp.max_call_depth(), MAX_DEPTH);
+
+    let mut locals = [(MAX_DEPTH as i32).into()];
+    let result = interp
+        .invoke(0, code_block, &mut locals, result_types)
+        .unwrap()
+        .unwrap()
+        .get_i32()
+        .unwrap();
+    assert_eq!(result, 0);
+
+    let mut locals = [(MAX_DEPTH as i32 + 1).into()];
+    let err = interp
+        .invoke(0, code_block, &mut locals, result_types)
+        .unwrap_err();
+    assert_eq!(err.kind(), WasmRuntimeErrorKind::CallStackExhausted);
+    assert_eq!(err.opcode(), WasmSingleOpcode::Call.into());
+
+    // Unbounded recursion traps at the default depth
+    let err = runnable.invoke(&[(-1).into()]).unwrap_err();
+    assert_eq!(err.kind(), WasmRuntimeErrorKind::CallStackExhausted);
+}
+
+#[test]
+fn heap_realloc() {
+    use crate::heap::SimpleAllocator;
+    use core::alloc::Layout;
+
+    let memory = WasmMemory::new(WasmLimit::new(1, 4));
+    let mut heap = SimpleAllocator::default();
+    heap.append_block(0x1000, 0x1000);
+    let layout = |size| Layout::from_size_align(size, 8).unwrap();
+    let prefix = b"0123456789abcdef";
+
+    let a = heap.alloc_or_grow(&memory, layout(16)).unwrap().get();
+    let b = heap.alloc_or_grow(&memory, layout(16)).unwrap().get();
+    assert_eq!((a, b), (0x1000, 0x1010));
+    memory.write_slice(a as usize, prefix).unwrap();
+
+    // The following block is in use, so the block moves
+    let c = heap.realloc(&memory, a, layout(16), 64).unwrap().get();
+    assert_eq!(c, 0x1020);
+    assert_eq!(memory.slice(c as usize, 16).unwrap(), prefix);
+
+    // Grows and shrinks in place
+    let d = heap.realloc(&memory, c, layout(64), 128).unwrap().get();
+    assert_eq!(d, c);
+    let d = heap.realloc(&memory, d, layout(128), 32).unwrap().get();
+    assert_eq!(d, c);
+    assert_eq!(memory.slice(d as usize, 16).unwrap(), prefix);
+    let e = heap.alloc_or_grow(&memory, layout(64)).unwrap().get();
+    assert_eq!(e, c + 32);
+
+    // Grows the memory for the larger block
+    let f = heap.realloc(&memory, d, layout(32), 0x2000).unwrap().get();
+    assert_eq!(f, 0x10000);
+    assert_eq!(memory.size(), 2);
+    assert_eq!(memory.slice(f as usize, 16).unwrap(), prefix);
+
+    // The old block is free again
+    let g = heap.alloc_or_grow(&memory, layout(32)).unwrap().get();
+    assert_eq!(g, d);
+}
@@ -988,6 +988,11 @@ pub struct WasmLimit {
 }
 
 impl WasmLimit {
+    #[inline]
+    pub const fn new(min: u32, max: u32) -> Self {
+        Self { min, max }
+    }
+
     #[inline]
     fn from_stream(stream: &mut Leb128Stream) -> Result<Self, WasmDecodeErrorKind> {
         match stream.read_unsigned() {
@@ -1208,6 +1213,76 @@ impl WasmMemory {
         }
     }
 
+    /// Reads a byte without the bounds check.
+    ///
+    /// # Safety
+    ///
+    /// `offset + index + 1` must not exceed the memory size.
+    #[inline]
+    pub unsafe fn read_u8_unchecked(&self, offset: u32, index: u32) -> u8 {
+        unsafe {
+            self.as_slice()
+                .as_ptr()
+                .add(offset as usize + index as usize)
+                .read()
+        }
+    }
+
+    /// Writes a byte without the bounds check.
+    ///
+    /// # Safety
+    ///
+    /// `offset + index + 1` must not exceed the memory size.
+    #[inline]
+    pub unsafe fn write_u8_unchecked(&self, offset: u32, index: u32, val: u8) {
+        unsafe {
+            self.as_mut_slice()
+                .as_mut_ptr()
+                .add(offset as usize + index as usize)
+                .write(val)
+        }
+    }
+
+    /// Reads a 32-bit value without the bounds check.
+    ///
+    /// # Safety
+    ///
+    /// `offset + index + 4` must not exceed the memory size.
+    #[inline]
+    pub unsafe fn read_u32_unchecked(&self, offset: u32, index: u32) -> u32 {
+        unsafe { WasmEndian::read_u32(self.as_slice(), offset as usize + index as usize) }
+    }
+
+    /// Writes a 32-bit value without the bounds check.
+    ///
+    /// # Safety
+    ///
+    /// `offset + index + 4` must not exceed the memory size.
+    #[inline]
+    pub unsafe fn write_u32_unchecked(&self, offset: u32, index: u32, val: u32) {
+        unsafe { WasmEndian::write_u32(self.as_mut_slice(), offset as usize + index as usize, val) }
+    }
+
+    /// Reads a 64-bit value without the bounds check.
+    ///
+    /// # Safety
+    ///
+    /// `offset + index + 8` must not exceed the memory size.
+    #[inline]
+    pub unsafe fn read_u64_unchecked(&self, offset: u32, index: u32) -> u64 {
+        unsafe { WasmEndian::read_u64(self.as_slice(), offset as usize + index as usize) }
+    }
+
+    /// Writes a 64-bit value without the bounds check.
+    ///
+    /// # Safety
+    ///
+    /// `offset + index + 8` must not exceed the memory size.
+    #[inline]
+    pub unsafe fn write_u64_unchecked(&self, offset: u32, index: u32, val: u64) {
+        unsafe { WasmEndian::write_u64(self.as_mut_slice(), offset as usize + index as usize, val) }
+    }
+
     #[inline]
     pub fn read_u8(&self, offset: u32, index: u32) -> Result<u8, WasmRuntimeErrorKind> {
         let slice = self.as_slice();
@@ -1635,12 +1710,28 @@ pub enum WasmRuntimeErrorKind {
     // InternalInconsistency,
     InvalidParameter,
     NotSupprted,
+    /// Trap: `unreachable` instruction was executed
     Unreachable,
+    /// Trap: Out of bounds memory access
     OutOfBounds,
     OutOfMemory,
     NoMethod,
+    /// Trap: Integer division by zero
     DivideByZero,
     TypeMismatch,
+    /// Trap: The call stack is exhausted
+    CallStackExhausted,
+}
+
+impl WasmRuntimeErrorKind {
+    /// Returns whether this error was raised by a faulting guest instruction.
+    #[inline]
+    pub const fn is_trap(&self) -> bool {
+        matches!(
+            self,
+            Self::Unreachable | Self::OutOfBounds | Self::DivideByZero | Self::CallStackExhausted
+        )
+    }
 }
 
 /// A type that holds a WebAssembly primitive value with a type information tag.
@@ -4565,6 +4656,8 @@ impl WasmCodeBlock {
             }
         }
 
+        Self::elide_bounds_checks(&mut int_codes);
+
         // compaction and block adjustment
         let mut actual_len = 0;
         for index in 0..int_codes.len() {
@@ -4614,6 +4707,84 @@ impl WasmCodeBlock {
             int_codes: int_codes.into_boxed_slice(),
         })
     }
+
+    /// Elides the bounds checks of memory accesses that are known to be in bounds.
+    ///
+    /// An access whose base address is a local variable is in bounds if an access
+    /// with the same local variable and the same or larger end offset has already been checked
+    /// in the same basic block and the local variable has not been modified since then.
+    /// The linear memory never shrinks, so such a check remains valid.
+    /// Branch targets are always block markers, where everything is forgotten.
+    fn elide_bounds_checks(int_codes: &mut [WasmImc]) {
+        use WasmIntMnemonic::*;
+
+        let prev_code = |int_codes: &[WasmImc], index: usize| {
+            (0..index)
+                .rev()
+                .find(|&index| !matches!(int_codes[index].mnemonic(), Nop))
+        };
+
+        // Pairs of a local variable and the end offset checked with it
+        let mut checked: Vec<(LocalVarIndex, u64)> = Vec::new();
+        for index in 0..int_codes.len() {
+            match *int_codes[index].mnemonic() {
+                Block(_) | End(_) | Undefined => {
+                    checked.clear();
+                    continue;
+                }
+                LocalSet(local)
+                | LocalTee(local)
+                | LocalSet32(local)
+                | LocalTee32(local)
+                | FusedI32SetConst(local, _)
+                | FusedI64SetConst(local, _) => {
+                    checked.retain(|v| v.0 != local);
+                    continue;
+                }
+                _ => (),
+            }
+
+            let mnemonic = int_codes[index].mnemonic();
+            let Some((offset, size)) = mnemonic.memory_access() else {
+                continue;
+            };
+
+            // The base address must be pushed by the local.get just before,
+            // or just before the value of the store pushed by a simple instruction.
+            let base_index = if mnemonic.is_memory_store() {
+                prev_code(int_codes, index).and_then(|value_index| {
+                    match int_codes[value_index].mnemonic() {
+                        LocalGet(_) | LocalGet32(_) | GlobalGet(_) | I32Const(_) | I64Const(_) => {
+                            prev_code(int_codes, value_index)
+                        }
+                        _ => None,
+                    }
+                })
+            } else {
+                prev_code(int_codes, index)
+            };
+            let Some(local) = base_index.and_then(|v| match *int_codes[v].mnemonic() {
+                LocalGet(local) | LocalGet32(local) => Some(local),
+                _ => None,
+            }) else {
+                continue;
+            };
+
+            let end = offset as u64 + size as u64;
+            match checked.iter_mut().find(|v| v.0 == local) {
+                Some(entry) => {
+                    if end <= entry.1 {
+                        if let Some(unchecked) = mnemonic.without_bounds_check() {
+                            int_codes[index].mnemonic = unchecked;
+                        }
+                    } else {
+                        entry.1 = end;
+                    }
+                }
+                None => checked.push((local, end)),
+            }
+        }
+    }
 }
 
 /// A type of block instruction (e.g., `block`, `loop`, `if`).
@@ -1,7 +1,14 @@
 //! Advanced Programmable Interrupt Controller
 
-use super::{cpu::*, hpet::*, page::PageManager};
+use super::{
+    cpu::*,
+    hpet::*,
+    idt::{self, InterruptHandler, InterruptStackFrame},
+    page::PageManager,
+    pmtimer::PmTimer,
+};
 use crate::{
+    fs::dev::random::Random,
     mem::mmio::*,
     mem::*,
     sync::{semaphore::BinarySemaphore, spinlock::SpinMutex},
@@ -18,6 +25,7 @@ use core::{
     time::Duration,
 };
 use myacpi::madt;
+use myx64::lapic::{TimerDivide, TimerMode, TimerRegisters, TimerSetting};
 use seq_macro::seq;
 
 pub type AffinityBits = usize;
@@ -82,7 +90,8 @@ pub(super) struct Apic {
     gsi_table: [GsiProps; 256],
     idt: [usize; Irq::MAX.0 as usize],
     idt_params: [usize; Irq::MAX.0 as usize],
-    lapic_timer_value: u32,
+    /// Ticks of the local APIC timer per millisecond, calibrated against the reference timer
+    lapic_ticks_per_ms: u32,
     tlb_flush_bitmap: AtomicAffinityBits,
     ipi_mutex: BinarySemaphore,
 }
@@ -92,6 +101,9 @@ impl Apic {
     const MSI_DATA: u16 = 0xC000;
     const MSI_BASE: u64 = 0xFEE00000;
 
+    /// Interval of the scheduler tick
+    const TICK_INTERVAL: Duration = Duration::from_millis(1);
+
     const fn new() -> Self {
         Apic {
             master_apic_id: ApicId(0),
@@ -99,7 +111,7 @@ impl Apic {
             gsi_table: [GsiProps::default(); 256],
             idt: [0; Irq::MAX.0 as usize],
             idt_params: [0; Irq::MAX.0 as usize],
-            lapic_timer_value: 0,
+            lapic_ticks_per_ms: 0,
             tlb_flush_bitmap: AtomicAffinityBits::new(0),
             ipi_mutex: BinarySemaphore::new(),
         }
@@ -150,11 +162,7 @@ impl Apic {
         }
 
         seq!(N in 1..64 {
-            InterruptDescriptorTable::register(
-                Irq(N).into(),
-                handle_irq_~N as usize,
-                super::cpu::PrivilegeLevel::Kernel,
-            );
+            idt::register(Irq(N).into(), InterruptHandler::Interrupt(handle_irq_~N)).unwrap();
         });
 
         // then enable irq
@@ -164,43 +172,45 @@ impl Apic {
         // Local APIC Timer
         let vec_latimer = Irq(0).as_vec();
         LocalApic::clear_timer();
-        LocalApic::set_timer_div(LocalApicTimerDivide::By1);
-        if let Some(hpet_info) = System::acpi().unwrap().find_first::<myacpi::hpet::Hpet>() {
-            // Use HPET
+        LocalApicTimer.write_divide_configuration(TimerDivide::By1 as u32);
+        let acpi = System::acpi().unwrap();
+        if let Some(hpet_info) = acpi.find_first::<myacpi::hpet::Hpet>() {
             Timer::set_timer(Box::new(Hpet::new(hpet_info)));
-
+        } else if let Some(pm_timer) = acpi
+            .fadt()
+            .and_then(|v| v.pm_timer())
+            .and_then(|v| PmTimer::new(v))
+        {
+            Timer::set_timer(Box::new(pm_timer));
+        }
+        if Timer::is_available() {
             let magic_number = 100;
             Timer::epsilon().repeat_until(|| Hal::cpu().spin_loop_hint());
-            let timer = Timer::new(Duration::from_micros(100_0000 / magic_number));
+            let duration = Duration::from_micros(100_0000 / magic_number);
+            let timer = Timer::new(duration);
             LocalApic::TimerInitialCount.write(u32::MAX);
+            let tsc = Cpu::rdtsc();
             timer.repeat_until(|| Hal::cpu().spin_loop_hint());
             let count = LocalApic::TimerCurrentCount.read() as u64;
-            shared.lapic_timer_value = ((u32::MAX as u64 - count) * magic_number / 1000) as u32;
+            Cpu::set_tsc_frequency(Cpu::rdtsc() - tsc, duration);
+            shared.lapic_ticks_per_ms = ((u32::MAX as u64 - count) * magic_number / 1000) as u32;
         } else {
             panic!("No Reference Timer found");
         }
-        InterruptDescriptorTable::register(
-            vec_latimer,
-            timer_handler as usize,
-            PrivilegeLevel::Kernel,
-        );
-        LocalApic::set_timer(
-            LocalApicTimerMode::Periodic,
-            vec_latimer,
-            shared.lapic_timer_value,
-        );
+        idt::register(vec_latimer, InterruptHandler::Interrupt(timer_handler)).unwrap();
+        Self::set_periodic_timer(Self::TICK_INTERVAL);
 
-        InterruptDescriptorTable::register(
+        idt::register(
             InterruptVector::IPI_INVALIDATE_TLB,
-            ipi_tlb_flush_handler as usize,
-            PrivilegeLevel::Kernel,
-        );
+            InterruptHandler::Interrupt(ipi_tlb_flush_handler),
+        )
+        .unwrap();
 
-        InterruptDescriptorTable::register(
+        idt::register(
             InterruptVector::IPI_SCHEDULE,
-            ipi_schedule_handler as usize,
-            PrivilegeLevel::Kernel,
-        );
+            InterruptHandler::Interrupt(ipi_schedule_handler),
+        )
+        .unwrap();
 
         // Start SMP
         let sipi_vec = InterruptVector(MemoryManager::static_alloc_real().unwrap().get());
@@ -225,7 +235,7 @@ impl Apic {
         for lapic in lapics.iter().take(max_cpu) {
             let apic_id = ApicId(lapic.apic_id());
             LocalApic::send_init_ipi(apic_id);
-            Timer::new(Duration::from_millis(10)).repeat_until(|| Hal::cpu().wait_for_interrupt());
+            Hal::cpu().delay_us(10_000);
 
             AP_BOOT_OK.store(false, Ordering::SeqCst);
             LocalApic::send_startup_ipi(apic_id, sipi_vec);
@@ -255,6 +265,44 @@ impl Apic {
         unsafe { &*APIC.get() }
     }
 
+    /// Converts the duration to the setting of the local APIC timer.
+    #[inline]
+    fn timer_setting_for(duration: Duration) -> TimerSetting {
+        TimerSetting::new(Self::shared().lapic_ticks_per_ms, duration)
+    }
+
+    /// Starts the local APIC timer of the current processor in periodic mode.
+    pub unsafe fn set_periodic_timer(interval: Duration) {
+        Self::timer_setting_for(interval).program(
+            &mut LocalApicTimer,
+            TimerMode::Periodic,
+            Irq(0).as_vec(),
+        );
+    }
+
+    /// Arms the local APIC timer of the current processor to fire once after the duration.
+    ///
+    /// The timer handler returns to the periodic tick after it fires.
+    pub unsafe fn set_one_shot_timer(duration: Duration) {
+        Self::timer_setting_for(duration).program(
+            &mut LocalApicTimer,
+            TimerMode::OneShot,
+            Irq(0).as_vec(),
+        );
+    }
+
+    /// Brings the next timer interrupt of the current processor forward
+    /// if the deadline comes before the next scheduler tick.
+    pub fn set_next_deadline(duration: Duration) {
+        if duration < Self::TICK_INTERVAL && Self::shared().lapic_ticks_per_ms > 0 {
+            unsafe {
+                without_interrupts!({
+                    Self::set_one_shot_timer(duration);
+                });
+            }
+        }
+    }
+
     #[inline]
     fn shared_mut<'a>() -> &'a mut Self {
         unsafe { &mut *APIC.get() }
@@ -396,6 +444,7 @@ impl Apic {
                 panic!("IRQ {} is Enabled, But not Installed", irq.0);
             }
             entry => {
+                Random::add_interrupt_entropy(Cpu::rdtsc() ^ irq.0 as u64, 1);
                 let f: IrqHandler = transmute(entry);
                 let param = shared.idt_params[irq.0 as usize];
                 Irql::Device.raise(|| f(param));
@@ -408,22 +457,26 @@ impl Apic {
 pub type IrqHandler = fn(usize) -> ();
 
 seq!(N in 1..64 {
-    unsafe extern "x86-interrupt" fn handle_irq_~N () {
+    unsafe extern "x86-interrupt" fn handle_irq_~N (_frame: InterruptStackFrame) {
         Apic::handle_irq(Irq(N));
     }
 });
 
-unsafe extern "x86-interrupt" fn timer_handler() {
+unsafe extern "x86-interrupt" fn timer_handler(_frame: InterruptStackFrame) {
+    if TimerMode::from_lvt(LocalApic::LvtTimer.read()) == Some(TimerMode::OneShot) {
+        // Fired for the deadline before the tick, so resume the periodic tick
+        Apic::set_periodic_timer(Apic::TICK_INTERVAL);
+    }
     LocalApic::eoi();
     Scheduler::reschedule();
 }
 
-unsafe extern "x86-interrupt" fn ipi_schedule_handler() {
+unsafe extern "x86-interrupt" fn ipi_schedule_handler(_frame: InterruptStackFrame) {
     LocalApic::eoi();
     Scheduler::reschedule();
 }
 
-unsafe extern "x86-interrupt" fn ipi_tlb_flush_handler() {
+unsafe extern "x86-interrupt" fn ipi_tlb_flush_handler(_frame: InterruptStackFrame) {
     let shared = Apic::shared();
     PageManager::invalidate_all_tlb();
     Hal::sync().fetch_reset(
@@ -660,7 +713,6 @@ impl LocalApic {
     }
 
     unsafe fn init_ap() -> ApicId {
-        let shared = Apic::shared();
         MSR::IA32_APIC_BASE
             .write(LOCAL_APIC_PA.load(Ordering::Relaxed) | Self::IA32_APIC_BASE_MSR_ENABLE);
 
@@ -668,14 +720,8 @@ impl LocalApic {
 
         LocalApic::SpuriousInterrupt.write(0x010F);
 
-        let vec_latimer = Irq(0).as_vec();
         LocalApic::clear_timer();
-        LocalApic::set_timer_div(LocalApicTimerDivide::By1);
-        LocalApic::set_timer(
-            LocalApicTimerMode::Periodic,
-            vec_latimer,
-            shared.lapic_timer_value,
-        );
+        Apic::set_periodic_timer(Apic::TICK_INTERVAL);
 
         apicid
     }
@@ -704,19 +750,6 @@ impl LocalApic {
         Self::Eoi.write(0);
     }
 
-    #[inline]
-    #[track_caller]
-    fn set_timer_div(div: LocalApicTimerDivide) {
-        Self::TimerDivideConfiguration.write(div as u32);
-    }
-
-    #[inline]
-    #[track_caller]
-    fn set_timer(mode: LocalApicTimerMode, vec: InterruptVector, count: u32) {
-        Self::TimerInitialCount.write(count);
-        Self::LvtTimer.write((vec.0 as u32) | mode as u32);
-    }
-
     #[inline]
     #[track_caller]
     fn clear_timer() {
@@ -791,18 +824,24 @@ impl LocalApic {
     }
 }
 
-#[allow(dead_code)]
-#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
-enum LocalApicTimerMode {
-    OneShot = 0 << 17,
-    Periodic = 1 << 17,
-    TscDeadline = 2 << 17,
-}
+/// Timer registers of the local APIC of the current processor
+struct LocalApicTimer;
 
-#[non_exhaustive]
-#[derive(Debug, Copy, Clone, PartialEq)]
-enum LocalApicTimerDivide {
-    By1 = 0b1011,
+impl TimerRegisters for LocalApicTimer {
+    #[inline]
+    fn write_divide_configuration(&mut self, value: u32) {
+        LocalApic::TimerDivideConfiguration.write(value);
+    }
+
+    #[inline]
+    fn write_lvt_timer(&mut self, value: u32) {
+        LocalApic::LvtTimer.write(value);
+    }
+
+    #[inline]
+    fn write_initial_count(&mut self, value: u32) {
+        LocalApic::TimerInitialCount.write(value);
+    }
 }
 
 #[repr(transparent)]
@@ -1,23 +1,33 @@
 use super::apic::*;
 use crate::{
+    fs::dev::random::Random,
     rt::{LegacyAppContext, RuntimeEnvironment},
     system::{ProcessorCoreType, ProcessorIndex},
-    task::scheduler::Scheduler,
+    task::scheduler::{Scheduler, Timer},
     *,
 };
 use alloc::boxed::Box;
 use core::{
     arch::{asm, x86_64::__cpuid_count},
     cell::UnsafeCell,
-    convert::TryFrom,
     ffi::c_void,
     mem::{size_of, transmute},
     sync::atomic::*,
+    time::Duration,
 };
 use megstd::Vec;
+pub use myx64::{
+    desc::*,
+    idt::{set_gate, IdtError},
+    syscall::SyscallMsrs,
+};
 use paste::paste;
 
 static mut SHARED_CPU: UnsafeCell<SharedCpu> = UnsafeCell::new(SharedCpu::new());
+/// TSC jitter samples added to the entropy pool for each value
+const JITTER_ROUNDS: usize = 8;
+/// TSC ticks per millisecond, calibrated against the reference timer
+static TSC_FREQ_KHZ: AtomicU64 = AtomicU64::new(0);
 
 pub struct Cpu {
     apic_id: ApicId,
@@ -313,6 +323,14 @@ impl Cpu {
         result
     }
 
+    /// Hints to the processor that the caller is in a spin-wait loop.
+    #[inline]
+    pub fn relax() {
+        unsafe {
+            asm!("pause", options(nomem, nostack));
+        }
+    }
+
     #[inline]
     pub(super) fn rdtsc() -> u64 {
         let eax: u32;
@@ -338,6 +356,86 @@ impl Cpu {
         (eax as u64 + edx as u64 * 0x10000_0000, ecx)
     }
 
+    /// Sets the TSC frequency measured against the reference timer.
+    #[inline]
+    pub(super) fn set_tsc_frequency(ticks: u64, duration: Duration) {
+        let khz = (ticks as u128 * 1000 / duration.as_micros().max(1)) as u64;
+        TSC_FREQ_KHZ.store(khz, Ordering::SeqCst);
+    }
+
+    /// Returns the TSC frequency in kHz, or `None` if not yet calibrated.
+    #[inline]
+    pub fn tsc_frequency_khz() -> Option<u64> {
+        match TSC_FREQ_KHZ.load(Ordering::Relaxed) {
+            0 => None,
+            khz => Some(khz),
+        }
+    }
+
+    /// Busy-waits for at least the specified number of microseconds.
+    #[inline]
+    pub fn delay_us(us: u64) {
+        Self::delay_ns(us.saturating_mul(1000));
+    }
+
+    /// Busy-waits for at least the specified number of nanoseconds.
+    ///
+    /// Until the TSC is calibrated, it waits with the timer, which is less precise.
+    pub fn delay_ns(ns: u64) {
+        let Some(khz) = Self::tsc_frequency_khz() else {
+            let deadline = Timer::new(Duration::from_micros(ns.div_ceil(1000) + 1));
+            deadline.repeat_until(|| Self::relax());
+            return;
+        };
+        megstd::time::busy_wait(Duration::from_nanos(ns), khz, Self::rdtsc, Self::relax);
+    }
+
+    /// Returns a random number, generated by `RDRAND` if supported.
+    ///
+    /// Otherwise it falls back to the kernel entropy pool, which is of lower quality.
+    /// See [`Cpu::has_hardware_rand`].
+    #[inline]
+    pub fn secure_rand() -> u64 {
+        Self::hardware_rand().unwrap_or_else(|| {
+            for _ in 0..JITTER_ROUNDS {
+                let start = Self::rdtsc();
+                Self::relax();
+                let end = Self::rdtsc();
+                Random::add_interrupt_entropy(end.wrapping_sub(start) ^ end, 0);
+            }
+            Random::next_u64()
+        })
+    }
+
+    #[inline]
+    pub fn has_hardware_rand() -> bool {
+        unsafe { Feature::F01C(F01C::RDRND).has_feature() }
+    }
+
+    fn hardware_rand() -> Option<u64> {
+        if !Self::has_hardware_rand() {
+            return None;
+        }
+        // RDRAND may fail temporarily if the entropy is exhausted
+        for _ in 0..10 {
+            let value: u64;
+            let ok: u8;
+            unsafe {
+                asm!(
+                    "rdrand {0}",
+                    "setc {1}",
+                    out(reg) value,
+                    out(reg_byte) ok,
+                    options(nomem, nostack),
+                );
+            }
+            if ok != 0 {
+                return Some(value);
+            }
+        }
+        None
+    }
+
     #[allow(dead_code)]
     #[inline]
     pub(super) unsafe fn read_tsc() -> u64 {
@@ -351,7 +449,11 @@ impl Cpu {
     }
 
     /// Launch the user mode application.
-    pub(super) unsafe fn invoke_user(start: usize, stack_pointer: usize) -> ! {
+    pub(super) unsafe fn invoke_user(
+        start: usize,
+        stack_pointer: usize,
+        thread_pointer: usize,
+    ) -> ! {
         Hal::cpu().disable_interrupt();
 
         let gdt = GlobalDescriptorTable::current();
@@ -363,12 +465,20 @@ impl Cpu {
         // The initial value of the Rflags register is interrupt allowed.
         let rflags = Rflags::IF;
 
-        // Reproduce the stack at the time of pseudo interrupt, return with an IRETQ, and transition to user mode.
         asm!("
             mov ds, {new_ss:e}
             mov es, {new_ss:e}
             mov fs, {new_ss:e}
             mov gs, {new_ss:e}
+            ",
+            new_ss = in (reg) Selector::USER_DATA.0 as usize,
+        );
+
+        // Loading the selector clears the base, so the thread pointer is set after that.
+        MSR::IA32_FS_BASE.write(thread_pointer as u64);
+
+        // Reproduce the stack at the time of pseudo interrupt, return with an IRETQ, and transition to user mode.
+        asm!("
             push {new_ss}
             push {new_sp}
             push {new_fl}
@@ -731,146 +841,6 @@ impl Rflags {
     }
 }
 
-#[repr(transparent)]
-#[derive(Copy, Clone, Eq, PartialEq)]
-pub struct DescriptorEntry(u64);
-
-impl DescriptorEntry {
-    pub const PRESENT: u64 = 0x8000_0000_0000;
-    pub const BIG_DATA: u64 = 0x0040_0000_0000_0000;
-
-    #[inline]
-    pub const fn null() -> Self {
-        Self(0)
-    }
-
-    #[inline]
-    pub fn flat_code_segment(dpl: PrivilegeLevel, size: DefaultSize) -> DescriptorEntry {
-        Self::code_segment(Linear32(0), Limit32::MAX, dpl, size)
-    }
-
-    #[inline]
-    pub fn code_segment(
-        base: Linear32,
-        limit: Limit32,
-        dpl: PrivilegeLevel,
-        size: DefaultSize,
-    ) -> DescriptorEntry {
-        DescriptorEntry(
-            0x0000_1A00_0000_0000u64
-                | base.as_segment_base()
-                | limit.as_descriptor_entry()
-                | Self::PRESENT
-                | dpl.as_descriptor_entry()
-                | size.as_descriptor_entry(),
-        )
-    }
-
-    #[inline]
-    pub fn flat_data_segment(dpl: PrivilegeLevel) -> DescriptorEntry {
-        Self::data_segment(Linear32(0), Limit32::MAX, dpl)
-    }
-
-    #[inline]
-    pub fn data_segment(base: Linear32, limit: Limit32, dpl: PrivilegeLevel) -> DescriptorEntry {
-        DescriptorEntry(
-            0x0000_1200_0000_0000u64
-                | base.as_segment_base()
-                | limit.as_descriptor_entry()
-                | Self::PRESENT
-                | Self::BIG_DATA
-                | dpl.as_descriptor_entry(),
-        )
-    }
-
-    #[inline]
-    pub fn tss_descriptor(base: Linear64, limit: Limit16) -> DescriptorPair {
-        let (base_low, base_high) = base.as_segment_base_pair();
-        let low = DescriptorEntry(
-            DescriptorType::Tss.as_descriptor_entry()
-                | base_low
-                | limit.as_descriptor_entry()
-                | Self::PRESENT,
-        );
-        let high = DescriptorEntry(base_high);
-        DescriptorPair::new(low, high)
-    }
-
-    #[inline]
-    pub fn gate_descriptor(
-        offset: Offset64,
-        sel: Selector,
-        dpl: PrivilegeLevel,
-        ty: DescriptorType,
-        ist: Option<InterruptStackTable>,
-    ) -> DescriptorPair {
-        let (offset_low, offset_high) = offset.as_gate_offset_pair();
-        let low = DescriptorEntry(
-            ty.as_descriptor_entry()
-                | offset_low
-                | sel.as_descriptor_entry()
-                | ist.as_descriptor_entry()
-                | dpl.as_descriptor_entry()
-                | Self::PRESENT,
-        );
-        let high = DescriptorEntry(offset_high);
-
-        DescriptorPair::new(low, high)
-    }
-
-    #[inline]
-    pub const fn is_null(&self) -> bool {
-        self.0 == 0
-    }
-
-    #[inline]
-    pub const fn is_present(&self) -> bool {
-        (self.0 & Self::PRESENT) != 0
-    }
-
-    #[inline]
-    pub const fn is_segment(&self) -> bool {
-        (self.0 & 0x1000_0000_0000) != 0
-    }
-
-    #[inline]
-    pub const fn is_code_segment(&self) -> bool {
-        self.is_segment() && (self.0 & 0x0800_0000_0000) != 0
-    }
-
-    #[inline]
-    pub const fn default_operand_size(&self) -> Option<DefaultSize> {
-        DefaultSize::from_descriptor(*self)
-    }
-}
-
-pub trait AsDescriptorEntry {
-    fn as_descriptor_entry(&self) -> u64;
-}
-
-impl<T: AsDescriptorEntry> AsDescriptorEntry for Option<T> {
-    fn as_descriptor_entry(&self) -> u64 {
-        match self {
-            Some(v) => v.as_descriptor_entry(),
-            None => 0,
-        }
-    }
-}
-
-#[repr(C)]
-#[derive(Copy, Clone, PartialEq)]
-pub struct DescriptorPair {
-    pub low: DescriptorEntry,
-    pub high: DescriptorEntry,
-}
-
-impl DescriptorPair {
-    #[inline]
-    pub const fn new(low: DescriptorEntry, high: DescriptorEntry) -> Self {
-        DescriptorPair { low, high }
-    }
-}
-
 #[repr(C, align(16))]
 pub struct GlobalDescriptorTable {
     table: [DescriptorEntry; Self::NUM_ITEMS],
@@ -889,52 +859,28 @@ impl GlobalDescriptorTable {
             tss: TaskStateSegment::new(),
         });
 
-        gdt.set_item(
-            Selector::KERNEL_CODE,
-            DescriptorEntry::flat_code_segment(PrivilegeLevel::Kernel, DefaultSize::Use64),
-        )
-        .unwrap();
-        gdt.set_item(
-            Selector::KERNEL_DATA,
-            DescriptorEntry::flat_data_segment(PrivilegeLevel::Kernel),
-        )
-        .unwrap();
-
-        gdt.set_item(
-            Selector::USER_CODE,
-            DescriptorEntry::flat_code_segment(PrivilegeLevel::User, DefaultSize::Use64),
-        )
-        .unwrap();
-        gdt.set_item(
-            Selector::USER_DATA,
-            DescriptorEntry::flat_data_segment(PrivilegeLevel::User),
-        )
-        .unwrap();
-
-        let tss_pair = gdt.tss.as_descriptor_pair();
-        let tss_index = Selector::SYSTEM_TSS.index();
-        gdt.table[tss_index] = tss_pair.low;
-        gdt.table[tss_index + 1] = tss_pair.high;
-
-        gdt.reload();
-        asm!("
-            mov {temp}, rsp
-            push {new_ss:r}
-            push {temp}
-            pushfq
-            push {new_cs:r}
-            .byte 0xE8, 2, 0, 0, 0, 0xEB, 0x02, 0x48, 0xCF
-            mov ds, {new_ss:e}
-            mov es, {new_ss:e}
-            mov fs, {new_ss:e}
-            mov gs, {new_ss:e}
-            ", 
-            temp = out(reg) _,
-            new_ss = in(reg) Selector::KERNEL_DATA.0,
-            new_cs = in(reg) Selector::KERNEL_CODE.0,
-        );
-
-        asm!("ltr {0:x}", in(reg) Selector::SYSTEM_TSS.0);
+        // The order of the entries must match the predefined selectors,
+        // as SYSCALL/SYSRET and the legacy mode depend on them.
+        let mut builder = GdtBuilder::new(&mut gdt);
+        let kernel_code = builder
+            .add_code_segment(PrivilegeLevel::Kernel, DefaultSize::Use64)
+            .unwrap();
+        let kernel_data = builder.add_data_segment(PrivilegeLevel::Kernel).unwrap();
+        builder.add_null().unwrap();
+        builder.add_null().unwrap();
+        let user_code = builder
+            .add_code_segment(PrivilegeLevel::User, DefaultSize::Use64)
+            .unwrap();
+        let user_data = builder.add_data_segment(PrivilegeLevel::User).unwrap();
+        builder.add_null().unwrap();
+        let tss = builder.add_tss().unwrap();
+        debug_assert_eq!(kernel_code, Selector::KERNEL_CODE);
+        debug_assert_eq!(kernel_data, Selector::KERNEL_DATA);
+        debug_assert_eq!(user_code, Selector::USER_CODE);
+        debug_assert_eq!(user_data, Selector::USER_DATA);
+        debug_assert_eq!(tss, Selector::SYSTEM_TSS);
+
+        builder.load(kernel_code, kernel_data);
 
         gdt
     }
@@ -981,203 +927,97 @@ impl GlobalDescriptorTable {
     }
 }
 
-/// Type of x86 Segment Limit
-#[repr(transparent)]
-#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
-pub struct Limit16(pub u16);
-
-impl AsDescriptorEntry for Limit16 {
-    #[inline]
-    fn as_descriptor_entry(&self) -> u64 {
-        self.0 as u64
-    }
+/// Builds the GDT entries in order and returns their selectors
+pub struct GdtBuilder<'a> {
+    gdt: &'a mut GlobalDescriptorTable,
+    len: usize,
+    tss: Option<Selector>,
 }
 
-/// Type of x86 Segment Limit
-#[repr(transparent)]
-#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
-pub struct Limit32(pub u32);
-
-impl Limit32 {
-    pub const MAX: Self = Self(u32::MAX);
-}
-
-impl AsDescriptorEntry for Limit32 {
+impl<'a> GdtBuilder<'a> {
+    /// Starts building the table. The first entry is always the NULL descriptor.
     #[inline]
-    fn as_descriptor_entry(&self) -> u64 {
-        let limit = self.0;
-        if limit > 0xFFFF {
-            0x0080_0000_0000_0000
-                | ((limit as u64) >> 12) & 0xFFFF
-                | ((limit as u64 & 0xF000_0000) << 20)
-        } else {
-            limit as u64
+    pub fn new(gdt: &'a mut GlobalDescriptorTable) -> Self {
+        gdt.table[0] = DescriptorEntry::null();
+        Self {
+            gdt,
+            len: 1,
+            tss: None,
         }
     }
-}
-
-/// Type of 32bit Linear Address
-#[repr(transparent)]
-#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
-pub struct Linear32(pub u32);
-
-impl Linear32 {
-    #[inline]
-    pub const fn as_segment_base(&self) -> u64 {
-        ((self.0 as u64 & 0x00FF_FFFF) << 16) | ((self.0 as u64 & 0xFF00_0000) << 32)
-    }
-}
-
-/// Type of 64bit Linear Address
-#[repr(transparent)]
-#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
-pub struct Linear64(pub u64);
-
-impl Linear64 {
-    #[inline]
-    pub const fn as_segment_base_pair(&self) -> (u64, u64) {
-        let low = Linear32(self.0 as u32).as_segment_base();
-        let high = self.0 >> 32;
-        (low, high)
-    }
-}
-
-/// Type of 32bit Offset Address
-#[repr(transparent)]
-#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
-pub struct Offset32(pub u32);
-
-impl Offset32 {
-    #[inline]
-    pub const fn as_gate_offset(&self) -> u64 {
-        let offset = self.0 as u64;
-        (offset & 0xFFFF) | (offset & 0xFFFF_0000) << 32
-    }
-}
 
-/// Type of 64bit Offset Address
-#[repr(transparent)]
-#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
-pub struct Offset64(pub u64);
-
-impl Offset64 {
     #[inline]
-    pub const fn as_gate_offset_pair(&self) -> (u64, u64) {
-        let low = Offset32(self.0 as u32).as_gate_offset();
-        let high = self.0 >> 32;
-        (low, high)
+    fn push(&mut self, desc: DescriptorEntry, rpl: PrivilegeLevel) -> Option<Selector> {
+        let index = self.len;
+        *self.gdt.table.get_mut(index)? = desc;
+        self.len = index + 1;
+        Some(Selector::new(index, rpl))
     }
-}
 
-/// Type of x86 Segment Selector
-#[repr(transparent)]
-#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
-pub struct Selector(pub u16);
-
-impl Selector {
-    /// The NULL selector that does not contain anything
-    pub const NULL: Selector = Selector(0);
-    pub const KERNEL_CODE: Selector = Selector::new(1, PrivilegeLevel::Kernel);
-    pub const KERNEL_DATA: Selector = Selector::new(2, PrivilegeLevel::Kernel);
-    pub const LEGACY_CODE: Selector = Selector::new(3, PrivilegeLevel::User);
-    pub const LEGACY_DATA: Selector = Selector::new(4, PrivilegeLevel::User);
-    pub const USER_CODE: Selector = Selector::new(5, PrivilegeLevel::User);
-    pub const USER_DATA: Selector = Selector::new(6, PrivilegeLevel::User);
-    pub const SYSTEM_TSS: Selector = Selector::new(8, PrivilegeLevel::Kernel);
-
-    /// Make a new instance of the selector from the specified index and RPL
-    #[inline]
-    pub const fn new(index: usize, rpl: PrivilegeLevel) -> Self {
-        Selector((index << 3) as u16 | rpl as u16)
-    }
-
-    /// Returns the requested privilege level in the selector
+    /// Reserves an empty entry to be filled in later.
     #[inline]
-    pub const fn rpl(self) -> PrivilegeLevel {
-        PrivilegeLevel::from_usize(self.0 as usize)
+    pub fn add_null(&mut self) -> Option<Selector> {
+        self.push(DescriptorEntry::null(), PrivilegeLevel::Kernel)
     }
 
-    /// Returns the index field in the selector
+    /// Adds a flat code segment.
     #[inline]
-    pub const fn index(self) -> usize {
-        (self.0 >> 3) as usize
+    pub fn add_code_segment(&mut self, dpl: PrivilegeLevel, size: DefaultSize) -> Option<Selector> {
+        self.push(DescriptorEntry::flat_code_segment(dpl, size), dpl)
     }
-}
 
-impl AsDescriptorEntry for Selector {
+    /// Adds a flat data segment.
     #[inline]
-    fn as_descriptor_entry(&self) -> u64 {
-        (self.0 as u64) << 16
+    pub fn add_data_segment(&mut self, dpl: PrivilegeLevel) -> Option<Selector> {
+        self.push(DescriptorEntry::flat_data_segment(dpl), dpl)
     }
-}
 
-/// DPL, CPL, RPL and IOPL
-#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
-pub enum PrivilegeLevel {
-    /// Ring 0, Kernel mode
-    Kernel = 0,
-    /// Ring 1, Useless in 64bit mode
-    Ring1 = 1,
-    /// Ring 2, Useless in 64bit mode
-    Ring2 = 2,
-    /// Ring 3, User mode
-    User = 3,
-}
-
-impl PrivilegeLevel {
-    #[inline]
-    pub const fn from_usize(value: usize) -> Self {
-        match value & 3 {
-            0 => PrivilegeLevel::Kernel,
-            1 => PrivilegeLevel::Ring1,
-            2 => PrivilegeLevel::Ring2,
-            3 => PrivilegeLevel::User,
-            _ => unreachable!(),
+    /// Adds the descriptor of the TSS in this table, which occupies two entries.
+    pub fn add_tss(&mut self) -> Option<Selector> {
+        let index = self.len;
+        if index + 2 > self.gdt.table.len() {
+            return None;
         }
-    }
-}
-
-impl AsDescriptorEntry for PrivilegeLevel {
-    #[inline]
-    fn as_descriptor_entry(&self) -> u64 {
-        (*self as u64) << 45
-    }
-}
-
-impl From<usize> for PrivilegeLevel {
-    #[inline]
-    fn from(value: usize) -> Self {
-        Self::from_usize(value)
-    }
-}
-
-#[non_exhaustive]
-#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
-pub enum DescriptorType {
-    Null = 0,
-    Tss = 9,
-    TssBusy = 11,
-    InterruptGate = 14,
-    TrapGate = 15,
-}
+        let pair = self.gdt.tss.as_descriptor_pair();
+        self.gdt.table[index] = pair.low;
+        self.gdt.table[index + 1] = pair.high;
+        self.len = index + 2;
+        let selector = Selector::new(index, PrivilegeLevel::Kernel);
+        self.tss = Some(selector);
+        Some(selector)
+    }
+
+    /// Loads the table and reloads the segment registers, and the task register if the TSS was added.
+    ///
+    /// # Safety
+    ///
+    /// `code` and `data` must be the selectors of the kernel code and data segments in this table,
+    /// and the table must outlive its use by the processor.
+    pub unsafe fn load(self, code: Selector, data: Selector) {
+        self.gdt.reload();
+        asm!("
+            mov {temp}, rsp
+            push {new_ss:r}
+            push {temp}
+            pushfq
+            push {new_cs:r}
+            .byte 0xE8, 2, 0, 0, 0, 0xEB, 0x02, 0x48, 0xCF
+            mov ds, {new_ss:e}
+            mov es, {new_ss:e}
+            mov fs, {new_ss:e}
+            mov gs, {new_ss:e}
+            ",
+            temp = out(reg) _,
+            new_ss = in(reg) data.0,
+            new_cs = in(reg) code.0,
+        );
 
-impl AsDescriptorEntry for DescriptorType {
-    #[inline]
-    fn as_descriptor_entry(&self) -> u64 {
-        let ty = *self as u64;
-        ty << 40
+        if let Some(tss) = self.tss {
+            asm!("ltr {0:x}", in(reg) tss.0);
+        }
     }
 }
 
-#[repr(transparent)]
-#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
-pub struct InterruptVector(pub u8);
-
-impl InterruptVector {
-    pub const IPI_INVALIDATE_TLB: Self = Self(0xEE);
-    pub const IPI_SCHEDULE: Self = Self(0xFC);
-}
-
 #[repr(u8)]
 #[non_exhaustive]
 #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
@@ -1329,69 +1169,6 @@ impl TaskStateSegment {
     }
 }
 
-#[repr(u64)]
-#[derive(Debug, Copy, Clone, PartialEq)]
-pub enum DefaultSize {
-    Use16 = 0x0000_0000_0000_0000,
-    Use32 = 0x0040_0000_0000_0000,
-    Use64 = 0x0020_0000_0000_0000,
-}
-
-impl AsDescriptorEntry for DefaultSize {
-    #[inline]
-    fn as_descriptor_entry(&self) -> u64 {
-        *self as u64
-    }
-}
-
-impl DefaultSize {
-    #[inline]
-    pub const fn as_descriptor_entry(&self) -> u64 {
-        *self as u64
-    }
-
-    #[inline]
-    pub const fn from_descriptor(value: DescriptorEntry) -> Option<Self> {
-        if value.is_code_segment() {
-            let is_32 = (value.0 & Self::Use32.as_descriptor_entry()) != 0;
-            let is_64 = (value.0 & Self::Use64.as_descriptor_entry()) != 0;
-            match (is_32, is_64) {
-                (false, false) => Some(Self::Use16),
-                (false, true) => Some(Self::Use64),
-                (true, false) => Some(Self::Use32),
-                (true, true) => None,
-            }
-        } else {
-            None
-        }
-    }
-}
-
-impl TryFrom<DescriptorEntry> for DefaultSize {
-    type Error = ();
-    fn try_from(value: DescriptorEntry) -> Result<Self, Self::Error> {
-        Self::from_descriptor(value).ok_or(())
-    }
-}
-
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
-pub enum InterruptStackTable {
-    IST1 = 1,
-    IST2,
-    IST3,
-    IST4,
-    IST5,
-    IST6,
-    IST7,
-}
-
-impl AsDescriptorEntry for InterruptStackTable {
-    #[inline]
-    fn as_descriptor_entry(&self) -> u64 {
-        (*self as u64) << 32
-    }
-}
-
 static mut IDT: UnsafeCell<InterruptDescriptorTable> =
     UnsafeCell::new(InterruptDescriptorTable::new());
 
@@ -1453,25 +1230,21 @@ impl InterruptDescriptorTable {
 
     #[track_caller]
     pub unsafe fn register(vec: InterruptVector, offset: usize, dpl: PrivilegeLevel) {
-        let table_offset = vec.0 as usize * 2;
-        let idt = IDT.get_mut();
-        if !idt.table[table_offset].is_null() {
-            panic!("IDT entry #{} is already in use", vec.0);
+        if let Err(err) = Self::try_register(vec, offset, dpl) {
+            panic!("IDT entry #{} is not available: {:?}", vec.0, err);
         }
-        let pair = DescriptorEntry::gate_descriptor(
-            Offset64(offset as u64),
-            Selector::KERNEL_CODE,
-            dpl,
-            if dpl == PrivilegeLevel::Kernel {
-                DescriptorType::InterruptGate
-            } else {
-                DescriptorType::TrapGate
-            },
-            None,
-        );
-        idt.table[table_offset + 1] = pair.high;
-        idt.table[table_offset] = pair.low;
+    }
+
+    /// Registers the entry if it is not in use.
+    pub unsafe fn try_register(
+        vec: InterruptVector,
+        offset: usize,
+        dpl: PrivilegeLevel,
+    ) -> Result<(), IdtError> {
+        let idt = IDT.get_mut();
+        set_gate(&mut idt.table, vec, offset as u64, dpl)?;
         fence(Ordering::SeqCst);
+        Ok(())
     }
 }
 
@@ -1826,6 +1599,39 @@ impl MSR {
             .swap_bytes();
         MSR::IA32_PAT.write(data);
     }
+
+    /// Enables the SYSCALL/SYSRET instructions.
+    ///
+    /// See [SyscallMsrs::new] for the meaning of the parameters.
+    pub unsafe fn enable_syscall(
+        handler: usize,
+        kernel_code: Selector,
+        user_base: Selector,
+        mask: Rflags,
+    ) {
+        let msrs = SyscallMsrs::new(handler as u64, kernel_code, user_base, mask.bits() as u64);
+        MSR::IA32_STAR.write(msrs.star);
+        MSR::IA32_LSTAR.write(msrs.lstar);
+        MSR::IA32_FMASK.write(msrs.fmask);
+
+        let mut efer = Efer::from_bits_retain(MSR::IA32_EFER.read());
+        efer.insert(Efer::SCE);
+        MSR::IA32_EFER.write(efer.bits());
+    }
+}
+
+my_bitflags! {
+    /// Extended Feature Enable Register
+    pub struct Efer: u64 {
+        /// System call extensions
+        const SCE = 0x0000_0001;
+        /// Long mode enable
+        const LME = 0x0000_0100;
+        /// Long mode active
+        const LMA = 0x0000_0400;
+        /// No-execute enable
+        const NXE = 0x0000_0800;
+    }
 }
 
 #[repr(transparent)]
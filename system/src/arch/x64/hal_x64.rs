@@ -7,13 +7,17 @@ use crate::{
     drivers::pci::PciConfigAddress,
     hal::*,
     system::ProcessorIndex,
+    task::scheduler::Scheduler,
     *,
 };
 use core::{
     arch::asm,
     fmt,
-    sync::atomic::{AtomicBool, AtomicUsize, Ordering},
+    sync::atomic::{AtomicBool, Ordering},
+    time::Duration,
 };
+#[cfg(debug_assertions)]
+use mytask::deadlock::LockOwner;
 
 #[derive(Clone, Copy)]
 pub struct Hal;
@@ -53,9 +57,7 @@ impl HalCpu for CpuImpl {
 
     #[inline]
     fn spin_loop_hint(&self) {
-        unsafe {
-            asm!("pause", options(nomem, nostack));
-        }
+        Cpu::relax();
     }
 
     #[inline]
@@ -122,8 +124,42 @@ impl HalCpu for CpuImpl {
     }
 
     #[inline]
-    unsafe fn invoke_user(&self, start: usize, stack_pointer: usize) -> ! {
-        Cpu::invoke_user(start, stack_pointer);
+    fn secure_rand(&self) -> u64 {
+        Cpu::secure_rand()
+    }
+
+    #[inline]
+    fn has_hardware_rand(&self) -> bool {
+        Cpu::has_hardware_rand()
+    }
+
+    #[inline]
+    fn delay_us(&self, us: u64) {
+        Cpu::delay_us(us);
+    }
+
+    #[inline]
+    fn delay_ns(&self, ns: u64) {
+        Cpu::delay_ns(ns);
+    }
+
+    #[inline]
+    fn set_next_deadline(&self, duration: Duration) {
+        Apic::set_next_deadline(duration);
+    }
+
+    #[inline(always)]
+    fn frame_pointer(&self) -> usize {
+        let rbp: usize;
+        unsafe {
+            asm!("mov {}, rbp", out(reg) rbp, options(nomem, nostack));
+        }
+        rbp
+    }
+
+    #[inline]
+    unsafe fn invoke_user(&self, start: usize, stack_pointer: usize, thread_pointer: usize) -> ! {
+        Cpu::invoke_user(start, stack_pointer, thread_pointer);
     }
 
     #[cfg(target_arch = "x86_64")]
@@ -216,6 +252,9 @@ impl Into<u32> for PciConfigAddress {
 
 pub struct Spinlock {
     value: AtomicBool,
+    /// The thread handle of the owner (debug builds only)
+    #[cfg(debug_assertions)]
+    owner: LockOwner,
 }
 
 impl Spinlock {
@@ -226,6 +265,38 @@ impl Spinlock {
     pub const fn new() -> Self {
         Self {
             value: AtomicBool::new(Self::UNLOCKED_VALUE),
+            #[cfg(debug_assertions)]
+            owner: LockOwner::new(),
+        }
+    }
+
+    #[inline]
+    fn set_owner(&self) {
+        #[cfg(debug_assertions)]
+        self.owner.set(Self::current_owner());
+    }
+
+    #[inline]
+    fn clear_owner(&self) {
+        #[cfg(debug_assertions)]
+        self.owner.clear();
+    }
+
+    #[cfg(debug_assertions)]
+    #[inline]
+    fn current_owner() -> Option<usize> {
+        Scheduler::current_thread().map(|v| v.as_usize())
+    }
+
+    /// Panics if the lock cannot be acquired for too long or is already held by the current thread.
+    #[cfg(debug_assertions)]
+    #[track_caller]
+    fn check_deadlock(&self, n_waits: usize) {
+        if let Err(owner) = self.owner.check(Self::current_owner(), n_waits) {
+            panic!(
+                "possible deadlock, held by thread {:#x}",
+                owner.unwrap_or(0)
+            );
         }
     }
 }
@@ -234,17 +305,25 @@ impl HalSpinlock for Spinlock {
     #[inline]
     #[must_use]
     fn try_lock(&self) -> bool {
-        self.value
+        let result = self
+            .value
             .compare_exchange(
                 Self::UNLOCKED_VALUE,
                 Self::LOCKED_VALUE,
                 Ordering::AcqRel,
                 Ordering::Relaxed,
             )
-            .is_ok()
+            .is_ok();
+        if result {
+            self.set_owner();
+        }
+        result
     }
 
+    #[track_caller]
     fn lock(&self) {
+        #[cfg(debug_assertions)]
+        let mut n_waits = 0;
         while self
             .value
             .compare_exchange(
@@ -257,13 +336,20 @@ impl HalSpinlock for Spinlock {
         {
             let mut spin_loop = SpinLoopWait::new();
             while self.value.load(Ordering::Acquire) {
+                #[cfg(debug_assertions)]
+                {
+                    self.check_deadlock(n_waits);
+                    n_waits += 1;
+                }
                 spin_loop.wait();
             }
         }
+        self.set_owner();
     }
 
     #[inline]
     unsafe fn force_unlock(&self) -> Option<()> {
+        self.clear_owner();
         self.value
             .compare_exchange(
                 Self::LOCKED_VALUE,
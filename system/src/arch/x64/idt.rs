@@ -0,0 +1,42 @@
+//! Interrupt handler registration
+
+use super::cpu::{IdtError, InterruptDescriptorTable, InterruptVector, PrivilegeLevel};
+
+/// The stack frame pushed by the processor on an interrupt
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct InterruptStackFrame {
+    pub rip: u64,
+    pub cs: u64,
+    pub rflags: u64,
+    pub rsp: u64,
+    pub ss: u64,
+}
+
+/// An interrupt handler with its frame layout
+///
+/// The compiler generates the entry and exit code of `x86-interrupt` functions from their signature,
+/// including popping the error code pushed by some exceptions.
+#[derive(Clone, Copy)]
+pub enum InterruptHandler {
+    /// External interrupts, IPIs and exceptions without an error code
+    Interrupt(unsafe extern "x86-interrupt" fn(InterruptStackFrame)),
+    /// Exceptions with an error code
+    Exception(unsafe extern "x86-interrupt" fn(InterruptStackFrame, u64)),
+}
+
+impl InterruptHandler {
+    #[inline]
+    pub fn entry_point(&self) -> usize {
+        match *self {
+            Self::Interrupt(f) => f as usize,
+            Self::Exception(f) => f as usize,
+        }
+    }
+}
+
+/// Claims the vector for the handler. Returns [IdtError::InUse] if the vector is already in use.
+#[inline]
+pub unsafe fn register(vec: InterruptVector, handler: InterruptHandler) -> Result<(), IdtError> {
+    InterruptDescriptorTable::try_register(vec, handler.entry_point(), PrivilegeLevel::Kernel)
+}
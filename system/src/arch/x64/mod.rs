@@ -1,11 +1,15 @@
 pub mod cpu;
+pub use myx64::gpr;
 
 #[doc(hidden)]
 pub mod apic;
 #[doc(hidden)]
 pub mod hpet;
+pub mod idt;
 pub mod page;
 #[doc(hidden)]
+pub mod pmtimer;
+#[doc(hidden)]
 pub mod ps2;
 #[doc(hidden)]
 pub mod rtc;
@@ -18,6 +22,10 @@ use crate::{assert_call_once, system::*};
 use bootprot::BootInfo;
 use core::arch::asm;
 use megstd::time::SystemTime;
+use myacpi::{
+    fadt::{Fadt, SleepType},
+    GasAddressSpaceId,
+};
 
 pub struct Arch;
 
@@ -75,7 +83,7 @@ impl Arch {
             if (al & 0x02) == 0 {
                 break;
             }
-            asm!("pause");
+            cpu::Cpu::relax();
         }
     }
 
@@ -83,4 +91,35 @@ impl Arch {
     pub fn system_time() -> SystemTime {
         rtc::Rtc::system_time()
     }
+
+    #[inline]
+    pub fn boot_time() -> SystemTime {
+        rtc::Rtc::boot_time()
+    }
+
+    /// Enters the ACPI sleeping state `\_Sx`.
+    ///
+    /// If the DSDT does not define the sleep type,
+    /// the value known for the Intel ICH/PCH is used for S5.
+    pub unsafe fn enter_sleep_state(state: u8) -> Result<(), ()> {
+        const FALLBACK_S5: SleepType = SleepType::new(7, 7);
+
+        let fadt = System::acpi().and_then(|v| v.fadt()).ok_or(())?;
+        let sleep_type = fadt
+            .dsdt_table()
+            .and_then(|v| v.sleep_type(state))
+            .or((state == 5).then_some(FALLBACK_S5))
+            .ok_or(())?;
+
+        fadt.enter_sleep_state(sleep_type, |gas, value| match gas.id {
+            GasAddressSpaceId::SystemIo => {
+                let port = gas.address as u16;
+                let mask = Fadt::PM1_CNT_SLP_TYP | Fadt::PM1_CNT_SLP_EN;
+                cpu::Cpu::out16(port, (cpu::Cpu::in16(port) & !mask) | value);
+            }
+            _ => {
+                // TODO: memory mapped PM1 control registers
+            }
+        })
+    }
 }
@@ -0,0 +1,71 @@
+//! ACPI Power Management Timer
+
+use super::cpu::Cpu;
+use crate::task::scheduler::*;
+use core::{
+    sync::atomic::{AtomicU64, Ordering},
+    time::Duration,
+};
+use myacpi::{fadt, GasAddressSpaceId};
+
+/// Reference timer for the systems without the HPET
+pub(super) struct PmTimer {
+    timer: fadt::PmTimer,
+    port: u16,
+    /// The counter extended to 64 bits
+    ticks: AtomicU64,
+}
+
+impl PmTimer {
+    /// Returns the PM timer if it is in the I/O space.
+    pub unsafe fn new(timer: fadt::PmTimer) -> Option<Self> {
+        let blk = timer.blk();
+        if blk.id != GasAddressSpaceId::SystemIo {
+            return None;
+        }
+        let port = blk.address as u16;
+        let ticks = timer.read(|_| unsafe { Cpu::in32(port) });
+        Some(Self {
+            timer,
+            port,
+            ticks: AtomicU64::new(ticks as u64),
+        })
+    }
+
+    /// Returns the elapsed ticks since the boot.
+    ///
+    /// The scheduler tick reads it far more often than the counter wraps around.
+    fn ticks(&self) -> u64 {
+        let mut last = self.ticks.load(Ordering::SeqCst);
+        loop {
+            // Read the counter after the last value, so that it is never behind
+            let now = self.timer.read(|_| unsafe { Cpu::in32(self.port) });
+            let ticks = self.timer.extend(last, now);
+            match self
+                .ticks
+                .compare_exchange(last, ticks, Ordering::SeqCst, Ordering::SeqCst)
+            {
+                Ok(_) => return ticks,
+                Err(v) => last = v,
+            }
+        }
+    }
+}
+
+impl TimerSource for PmTimer {
+    fn monotonic(&self) -> u64 {
+        fadt::PmTimer::ticks_to_micros(self.ticks()) / 1000
+    }
+
+    fn measure(&self) -> TimeSpec {
+        TimeSpec(fadt::PmTimer::ticks_to_micros(self.ticks()) as isize)
+    }
+
+    fn from_duration(&self, val: Duration) -> TimeSpec {
+        TimeSpec(val.as_micros() as isize)
+    }
+
+    fn into_duration(&self, val: TimeSpec) -> Duration {
+        Duration::from_micros(val.0 as u64)
+    }
+}
@@ -4,27 +4,33 @@ use crate::{
     sync::spinlock::{SpinMutex, SpinMutexGuard},
     task::scheduler::*,
 };
-use core::arch::asm;
+use core::{arch::asm, time::Duration};
 use megstd::time::SystemTime;
 
 static RTC: SpinMutex<Rtc> = SpinMutex::new(Rtc::new());
 
 pub(super) struct Rtc {
-    base: u64,
-    offset: u64,
+    base: SystemTime,
+    offset: Duration,
 }
 
 impl Rtc {
     #[inline]
     const fn new() -> Self {
-        Self { base: 0, offset: 0 }
+        Self {
+            base: SystemTime { secs: 0, nanos: 0 },
+            offset: Duration::ZERO,
+        }
     }
 
     pub unsafe fn init() {
         let mut shared = Self::shared();
 
-        shared.base = Self::fetch_time();
-        shared.offset = Timer::monotonic().as_nanos() as u64;
+        shared.base = SystemTime {
+            secs: Self::fetch_time(),
+            nanos: 0,
+        };
+        shared.offset = Timer::monotonic();
     }
 
     #[inline]
@@ -34,13 +40,15 @@ impl Rtc {
 
     pub fn system_time() -> SystemTime {
         let shared = Self::shared();
+        shared
+            .base
+            .saturating_add(Timer::monotonic().saturating_sub(shared.offset))
+    }
 
-        let nanos_per_sec = 1_000_000_000;
-        let diff = Timer::monotonic().as_nanos() as u64 - shared.offset;
-        let secs = shared.base + diff / nanos_per_sec;
-        let nanos = (diff % nanos_per_sec) as u32;
-
-        SystemTime { secs, nanos }
+    /// Returns the system time when the monotonic timer started.
+    pub fn boot_time() -> SystemTime {
+        let shared = Self::shared();
+        shared.base.saturating_sub(shared.offset)
     }
 
     unsafe fn fetch_time() -> u64 {
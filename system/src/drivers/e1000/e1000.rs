@@ -0,0 +1,434 @@
+// Intel 8254x Gigabit Ethernet Controller
+
+use crate::{
+    drivers::pci::*,
+    io::net::{copy_frame, MacAddress, NetDevice, NetError, NetManager},
+    mem::{mmio::MmioSlice, MemoryManager},
+    sync::Mutex,
+    task::scheduler::{SpawnOption, Timer},
+    *,
+};
+use alloc::{format, string::String, sync::Arc};
+use core::{ptr::copy_nonoverlapping, slice, time::Duration};
+use mynet::e1000::*;
+
+pub struct E1000 {
+    addr: PciConfigAddress,
+    mmio: MmioSlice,
+    mac_address: MacAddress,
+    rx: Mutex<RxRing<DescriptorRing<RxDescriptor>>>,
+    tx: Mutex<TxRing<DescriptorRing<TxDescriptor>>>,
+}
+
+unsafe impl Send for E1000 {}
+unsafe impl Sync for E1000 {}
+
+impl E1000 {
+    pub const DRIVER_NAME: &'static str = "e1000";
+
+    const VENDOR_ID: PciVendorId = PciVendorId(0x8086);
+    const DEVICE_IDS: [PciDeviceId; 3] = [
+        // 82540EM
+        PciDeviceId(0x100E),
+        // 82545EM
+        PciDeviceId(0x100F),
+        // 82574L
+        PciDeviceId(0x10D3),
+    ];
+
+    const NUM_RX_DESC: usize = 32;
+    const NUM_TX_DESC: usize = 32;
+    const SIZE_OF_BUFFER: usize = 2048;
+
+    /// IPGT 10, IPGR1 8, IPGR2 6
+    const TIPG_DEFAULT: u32 = 0x0060_200A;
+
+    /// Polling interval for the controllers without MSI
+    const POLLING_INTERVAL: Duration = Duration::from_millis(10);
+
+    #[inline]
+    pub fn register_driver() {
+        for device_id in Self::DEVICE_IDS {
+            Pci::register_driver(
+                PciDeviceMatcher::Device(Self::VENDOR_ID, device_id),
+                |device| unsafe { Self::new(device) },
+            );
+        }
+    }
+
+    pub unsafe fn new(device: &PciDevice) -> Option<Arc<dyn PciDriver>> {
+        let Some(bar) = device.bars().next() else {
+            return None;
+        };
+        let Some(mmio) = MmioSlice::from_bar(bar) else {
+            return None;
+        };
+
+        device.set_pci_command(PciCommand::MEM_SPACE | PciCommand::BUS_MASTER);
+
+        // Reset
+        mmio.write_u32(Reg::IMC, u32::MAX);
+        mmio.write_u32(Reg::CTRL, mmio.read_u32(Reg::CTRL) | Ctrl::RST);
+        let deadline = Timer::new(Duration::from_millis(100));
+        loop {
+            if deadline.is_expired() || (mmio.read_u32(Reg::CTRL) & Ctrl::RST) == 0 {
+                break;
+            }
+            Timer::sleep(Duration::from_millis(1));
+        }
+        if (mmio.read_u32(Reg::CTRL) & Ctrl::RST) != 0 {
+            return None;
+        }
+        mmio.write_u32(Reg::IMC, u32::MAX);
+        let _ = mmio.read_u32(Reg::ICR);
+
+        mmio.write_u32(Reg::CTRL, mmio.read_u32(Reg::CTRL) | Ctrl::SLU | Ctrl::ASDE);
+
+        let mac_address = Self::read_mac_address(&mmio);
+
+        for i in 0..128 {
+            mmio.write_u32(Reg::MTA + i * 4, 0);
+        }
+
+        // Receive
+        let rx = RxRing::new(DescriptorRing::new(
+            Self::NUM_RX_DESC,
+            Self::SIZE_OF_BUFFER,
+        )?);
+        let base = rx.memory().descriptors_pa().as_u64();
+        mmio.write_u32(Reg::RDBAL, base as u32);
+        mmio.write_u32(Reg::RDBAH, (base >> 32) as u32);
+        mmio.write_u32(Reg::RDLEN, rx.memory().size_of_descriptors() as u32);
+        mmio.write_u32(Reg::RDH, 0);
+        mmio.write_u32(Reg::RDT, (Self::NUM_RX_DESC - 1) as u32);
+        mmio.write_u32(
+            Reg::RCTL,
+            Rctl::EN | Rctl::BAM | Rctl::BSIZE_2048 | Rctl::SECRC,
+        );
+
+        // Transmit
+        let tx = TxRing::new(DescriptorRing::new(
+            Self::NUM_TX_DESC,
+            Self::SIZE_OF_BUFFER,
+        )?);
+        let base = tx.memory().descriptors_pa().as_u64();
+        mmio.write_u32(Reg::TDBAL, base as u32);
+        mmio.write_u32(Reg::TDBAH, (base >> 32) as u32);
+        mmio.write_u32(Reg::TDLEN, tx.memory().size_of_descriptors() as u32);
+        mmio.write_u32(Reg::TDH, 0);
+        mmio.write_u32(Reg::TDT, 0);
+        mmio.write_u32(
+            Reg::TCTL,
+            Tctl::EN | Tctl::PSP | Tctl::CT_DEFAULT | Tctl::COLD_FULL_DUPLEX,
+        );
+        mmio.write_u32(Reg::TIPG, Self::TIPG_DEFAULT);
+
+        let driver = Arc::new(Self {
+            addr: device.address(),
+            mmio,
+            mac_address,
+            rx: Mutex::new(rx),
+            tx: Mutex::new(tx),
+        });
+
+        NetManager::register_device(driver.clone());
+
+        let p = Arc::as_ptr(&driver);
+        if device.register_msi(Self::_msi_handler, p as usize).is_ok() {
+            driver
+                .mmio
+                .write_u32(Reg::IMS, Ims::RXDMT0 | Ims::RXO | Ims::RXT0);
+        } else {
+            SpawnOption::new().start(Self::_polling_thread, 0, "e1000 polling");
+        }
+
+        Some(driver as Arc<dyn PciDriver>)
+    }
+
+    fn _msi_handler(p: usize) {
+        let this = unsafe { &*(p as *const Self) };
+        // Reading ICR clears the interrupt causes
+        let _ = this.mmio.read_u32(Reg::ICR);
+        NetManager::notify_rx();
+    }
+
+    fn _polling_thread(_: usize) {
+        loop {
+            Timer::sleep(Self::POLLING_INTERVAL);
+            NetManager::notify_rx();
+        }
+    }
+
+    fn read_mac_address(mmio: &MmioSlice) -> MacAddress {
+        let ral = mmio.read_u32(Reg::RAL0);
+        let rah = mmio.read_u32(Reg::RAH0);
+        if (rah & Reg::RAH_AV) != 0 {
+            let ral = ral.to_le_bytes();
+            let rah = rah.to_le_bytes();
+            return MacAddress([ral[0], ral[1], ral[2], ral[3], rah[0], rah[1]]);
+        }
+
+        // The address is not loaded, read it from the EEPROM
+        let mut result = [0; 6];
+        for (index, chunk) in result.chunks_exact_mut(2).enumerate() {
+            mmio.write_u32(Reg::EERD, ((index as u32) << 8) | Reg::EERD_START);
+            let deadline = Timer::new(Duration::from_millis(10));
+            let mut spin_loop = Hal::cpu().spin_wait();
+            let data = loop {
+                let data = mmio.read_u32(Reg::EERD);
+                if (data & Reg::EERD_DONE) != 0 || deadline.is_expired() {
+                    break data;
+                }
+                spin_loop.wait();
+            };
+            chunk.copy_from_slice(&((data >> 16) as u16).to_le_bytes());
+        }
+        mmio.write_u32(
+            Reg::RAL0,
+            u32::from_le_bytes([result[0], result[1], result[2], result[3]]),
+        );
+        mmio.write_u32(
+            Reg::RAH0,
+            u32::from_le_bytes([result[4], result[5], 0, 0]) | Reg::RAH_AV,
+        );
+        MacAddress(result)
+    }
+
+    #[inline]
+    pub fn is_link_up(&self) -> bool {
+        (self.mmio.read_u32(Reg::STATUS) & Reg::STATUS_LU) != 0
+    }
+}
+
+impl PciDriver for E1000 {
+    fn address(&self) -> PciConfigAddress {
+        self.addr
+    }
+
+    fn name<'a>(&self) -> &'a str {
+        Self::DRIVER_NAME
+    }
+
+    fn current_status(&self) -> String {
+        format!(
+            "{} {}",
+            self.mac_address,
+            if self.is_link_up() {
+                "LINK UP"
+            } else {
+                "LINK DOWN"
+            }
+        )
+    }
+}
+
+impl NetDevice for E1000 {
+    fn mac_address(&self) -> MacAddress {
+        self.mac_address
+    }
+
+    fn send_frame(&self, frame: &[u8]) -> Result<(), NetError> {
+        if frame.len() > NetManager::MAX_FRAME_SIZE {
+            return Err(NetError::InvalidLength);
+        }
+        self.tx
+            .lock()
+            .unwrap()
+            .send(self, frame, NetManager::MIN_FRAME_SIZE)
+            .map_err(|_| NetError::Busy)
+    }
+
+    fn recv_frame(&self, buffer: &mut [u8]) -> Option<Result<usize, NetError>> {
+        self.rx
+            .lock()
+            .unwrap()
+            .recv(self, Self::SIZE_OF_BUFFER, |frame| {
+                copy_frame(frame, buffer)
+            })
+    }
+}
+
+impl RingRegisters for E1000 {
+    #[inline]
+    fn tx_head(&self) -> usize {
+        self.mmio.read_u32(Reg::TDH) as usize
+    }
+
+    #[inline]
+    fn set_tx_tail(&self, value: usize) {
+        self.mmio.write_u32(Reg::TDT, value as u32);
+    }
+
+    #[inline]
+    fn set_rx_tail(&self, value: usize) {
+        self.mmio.write_u32(Reg::RDT, value as u32);
+    }
+}
+
+/// Register offsets
+struct Reg;
+
+impl Reg {
+    const CTRL: usize = 0x0000;
+    const STATUS: usize = 0x0008;
+    const EERD: usize = 0x0014;
+    const ICR: usize = 0x00C0;
+    const IMS: usize = 0x00D0;
+    const IMC: usize = 0x00D8;
+    const RCTL: usize = 0x0100;
+    const TCTL: usize = 0x0400;
+    const TIPG: usize = 0x0410;
+    const RDBAL: usize = 0x2800;
+    const RDBAH: usize = 0x2804;
+    const RDLEN: usize = 0x2808;
+    const RDH: usize = 0x2810;
+    const RDT: usize = 0x2818;
+    const TDBAL: usize = 0x3800;
+    const TDBAH: usize = 0x3804;
+    const TDLEN: usize = 0x3808;
+    const TDH: usize = 0x3810;
+    const TDT: usize = 0x3818;
+    const MTA: usize = 0x5200;
+    const RAL0: usize = 0x5400;
+    const RAH0: usize = 0x5404;
+
+    /// Link Up
+    const STATUS_LU: u32 = 0x0000_0002;
+    const EERD_START: u32 = 0x0000_0001;
+    const EERD_DONE: u32 = 0x0000_0010;
+    /// Address Valid
+    const RAH_AV: u32 = 0x8000_0000;
+}
+
+struct Ctrl;
+
+impl Ctrl {
+    /// Auto-Speed Detection Enable
+    const ASDE: u32 = 0x0000_0020;
+    /// Set Link Up
+    const SLU: u32 = 0x0000_0040;
+    /// Device Reset
+    const RST: u32 = 0x0400_0000;
+}
+
+/// Interrupt causes
+struct Ims;
+
+impl Ims {
+    /// Receive Descriptor Minimum Threshold Reached
+    const RXDMT0: u32 = 0x0000_0010;
+    /// Receiver Overrun
+    const RXO: u32 = 0x0000_0040;
+    /// Receiver Timer Interrupt
+    const RXT0: u32 = 0x0000_0080;
+}
+
+struct Rctl;
+
+impl Rctl {
+    /// Receiver Enable
+    const EN: u32 = 0x0000_0002;
+    /// Broadcast Accept Mode
+    const BAM: u32 = 0x0000_8000;
+    /// Receive Buffer Size
+    const BSIZE_2048: u32 = 0x0000_0000;
+    /// Strip Ethernet CRC
+    const SECRC: u32 = 0x0400_0000;
+}
+
+struct Tctl;
+
+impl Tctl {
+    /// Transmit Enable
+    const EN: u32 = 0x0000_0002;
+    /// Pad Short Packets
+    const PSP: u32 = 0x0000_0008;
+    /// Collision Threshold
+    const CT_DEFAULT: u32 = 0x0F << 4;
+    /// Collision Distance
+    const COLD_FULL_DUPLEX: u32 = 0x40 << 12;
+}
+
+/// A ring of descriptors, each of which has its own DMA buffer
+pub struct DescriptorRing<T> {
+    descriptors: *mut T,
+    descriptors_pa: PhysicalAddress,
+    buffers: *mut u8,
+    buffers_pa: PhysicalAddress,
+    len: usize,
+    size_of_buffer: usize,
+}
+
+impl<T: Default> DescriptorRing<T> {
+    unsafe fn new(len: usize, size_of_buffer: usize) -> Option<Self> {
+        let (descriptors_pa, descriptors) = MemoryManager::alloc_dma::<T>(len)?;
+        let (buffers_pa, buffers) = MemoryManager::alloc_dma::<u8>(len * size_of_buffer)?;
+        for index in 0..len {
+            descriptors.add(index).write(T::default());
+        }
+        Some(Self {
+            descriptors,
+            descriptors_pa,
+            buffers,
+            buffers_pa,
+            len,
+            size_of_buffer,
+        })
+    }
+
+    #[inline]
+    const fn descriptors_pa(&self) -> PhysicalAddress {
+        self.descriptors_pa
+    }
+
+    #[inline]
+    const fn size_of_descriptors(&self) -> usize {
+        self.len * core::mem::size_of::<T>()
+    }
+
+    #[inline]
+    fn buffer_ptr(&self, index: usize) -> *mut u8 {
+        assert!(index < self.len);
+        unsafe { self.buffers.add(index * self.size_of_buffer) }
+    }
+}
+
+impl<T> DescriptorMemory<T> for DescriptorRing<T> {
+    #[inline]
+    fn num_descriptors(&self) -> usize {
+        self.len
+    }
+
+    #[inline]
+    fn read_descriptor(&self, index: usize) -> T {
+        assert!(index < self.len);
+        unsafe { self.descriptors.add(index).read_volatile() }
+    }
+
+    #[inline]
+    fn write_descriptor(&self, index: usize, value: T) {
+        assert!(index < self.len);
+        unsafe { self.descriptors.add(index).write_volatile(value) }
+    }
+
+    #[inline]
+    fn buffer_address(&self, index: usize) -> u64 {
+        (self.buffers_pa + index * self.size_of_buffer).as_u64()
+    }
+
+    #[inline]
+    fn buffer(&self, index: usize, len: usize) -> &[u8] {
+        assert!(len <= self.size_of_buffer);
+        unsafe { slice::from_raw_parts(self.buffer_ptr(index), len) }
+    }
+
+    #[inline]
+    fn write_buffer(&self, index: usize, data: &[u8], len: usize) {
+        assert!(data.len() <= len && len <= self.size_of_buffer);
+        unsafe {
+            let buffer = self.buffer_ptr(index);
+            copy_nonoverlapping(data.as_ptr(), buffer, data.len());
+            buffer.add(data.len()).write_bytes(0, len - data.len());
+        }
+    }
+}
@@ -0,0 +1,4 @@
+//! Intel 8254x Gigabit Ethernet Controller
+
+mod e1000;
+pub use e1000::*;
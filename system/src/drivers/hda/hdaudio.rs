@@ -19,30 +19,10 @@ use core::{
     sync::atomic::{fence, AtomicUsize, Ordering},
     time::Duration,
 };
+use myhda::{CyclicBufferCursor, DmaPosition};
 
 pub type Result<T> = core::result::Result<T, ControllerError>;
 
-pub struct HdaDriverRegistrar();
-
-impl HdaDriverRegistrar {
-    const PREFERRED_CLASS: PciClass = PciClass::code(0x04).sub(0x03);
-
-    #[inline]
-    pub fn new() -> Box<dyn PciDriverRegistrar> {
-        Box::new(Self()) as Box<dyn PciDriverRegistrar>
-    }
-}
-
-impl PciDriverRegistrar for HdaDriverRegistrar {
-    fn instantiate(&self, device: &PciDevice) -> Option<Arc<dyn PciDriver>> {
-        if device.class_code().matches(Self::PREFERRED_CLASS) {
-            unsafe { HdAudioController::new(device) }
-        } else {
-            None
-        }
-    }
-}
-
 #[allow(dead_code)]
 pub struct HdAudioController {
     addr: PciConfigAddress,
@@ -74,9 +54,14 @@ impl HdAudioController {
     pub const CURRENT_VERSION: (usize, usize) = (1, 0);
     pub const WAIT_DELAY_MS: u64 = 100;
 
+    const PREFERRED_CLASS: PciClass = PciClass::code(0x04).sub(0x03);
+
     #[inline]
-    pub fn registrar() -> Box<dyn PciDriverRegistrar> {
-        HdaDriverRegistrar::new()
+    pub fn register_driver() {
+        Pci::register_driver(
+            PciDeviceMatcher::Class(Self::PREFERRED_CLASS),
+            |device| unsafe { Self::new(device) },
+        );
     }
 
     pub unsafe fn new(device: &PciDevice) -> Option<Arc<dyn PciDriver>> {
@@ -128,10 +113,18 @@ impl HdAudioController {
         let iss = gcap.iss;
         let oss = gcap.oss;
 
+        // DMA Position Buffer
+        let num_streams = iss + oss + gcap.bss;
+        let (pa_dpb, dpb) = MemoryManager::alloc_dma::<DmaPosition>(num_streams)?;
+        dpb.write_bytes(0, num_streams);
+        let dma_position = mmio.transmute::<DmaPositionRegisterSet>(0x70);
+        dma_position.set_base(pa_dpb);
+
         let mut idss = Vec::with_capacity(iss);
         for i in 0..iss {
             idss.push(Mutex::new(StreamDescriptor::new(
                 mmio.transmute::<StreamDescriptorRegisterSet>(0x80 + i * 0x20),
+                dpb.add(i),
             )));
         }
 
@@ -139,6 +132,7 @@ impl HdAudioController {
         for i in 0..oss {
             odss.push(Mutex::new(StreamDescriptor::new(
                 mmio.transmute::<StreamDescriptorRegisterSet>(0x80 + iss * 0x20 + i * 0x20),
+                dpb.add(iss + i),
             )));
         }
 
@@ -841,22 +835,33 @@ pub type DmaBufferChunk = [u8; SIZE_OF_BUFFER];
 
 pub struct StreamDescriptor {
     regs: &'static StreamDescriptorRegisterSet,
+    dma_position: *const DmaPosition,
     id: Option<StreamId>,
     current_buffer: Option<*mut u8>,
-    current_pos: AtomicUsize,
+    cursor: CyclicBufferCursor,
 }
 
 impl StreamDescriptor {
     #[inline]
-    pub fn new(regs: &'static StreamDescriptorRegisterSet) -> Self {
+    pub fn new(
+        regs: &'static StreamDescriptorRegisterSet,
+        dma_position: *const DmaPosition,
+    ) -> Self {
         Self {
             regs,
+            dma_position,
             id: None,
             current_buffer: None,
-            current_pos: AtomicUsize::new(0),
+            cursor: CyclicBufferCursor::new(SIZE_OF_BUFFER, NUM_OF_BUFFER),
         }
     }
 
+    /// Returns the current position in the cyclic buffer reported by the DMA Position Buffer.
+    #[inline]
+    pub fn position(&self) -> usize {
+        unsafe { (*self.dma_position).position() }
+    }
+
     #[inline]
     pub fn stream_id(&self) -> Option<StreamId> {
         self.id
@@ -916,21 +921,12 @@ impl StreamDescriptor {
         };
         let src = data.as_ptr();
 
-        let lp = self.regs.link_position() / SIZE_OF_BUFFER;
-        let pos = self.current_pos.load(Ordering::SeqCst);
-
-        if lp != pos {
-            self.current_pos
-                .store((pos + 1) % NUM_OF_BUFFER, Ordering::SeqCst);
-
-            unsafe {
-                copy_nonoverlapping(src, buffer.add(pos * SIZE_OF_BUFFER), SIZE_OF_BUFFER);
-            }
-            fence(Ordering::SeqCst);
-            Some(())
-        } else {
-            None
+        let pos = self.cursor.claim(self.position())?;
+        unsafe {
+            copy_nonoverlapping(src, buffer.add(pos * SIZE_OF_BUFFER), SIZE_OF_BUFFER);
         }
+        fence(Ordering::SeqCst);
+        Some(())
     }
 }
 
@@ -1192,6 +1188,22 @@ impl From<u16> for GlobalCapabilities {
     }
 }
 
+#[repr(C)]
+pub struct DmaPositionRegisterSet {
+    dplbase: MmioRegU32,
+    dpubase: MmioRegU32,
+}
+
+impl DmaPositionRegisterSet {
+    /// Sets the base address of the buffer and enables it.
+    #[inline]
+    pub fn set_base(&self, base: PhysicalAddress) {
+        let (lower, upper) = DmaPosition::base_registers(base.as_u64());
+        self.dpubase.write_volatile(upper);
+        self.dplbase.write_volatile(lower);
+    }
+}
+
 #[repr(C)]
 #[allow(dead_code)]
 pub struct CorbRegisterSet {
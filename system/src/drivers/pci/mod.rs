@@ -1,16 +1,18 @@
 //! Peripheral Component Interconnect Bus
 
 mod pci;
-use megstd::{Box, Vec};
 pub use pci::*;
 
-fn install_drivers(drivers: &mut Vec<Box<dyn PciDriverRegistrar>>) {
+fn install_drivers() {
     // XHCI
-    drivers.push(super::usb::xhci::Xhci::registrar());
+    super::usb::xhci::Xhci::register_driver();
 
     // High Definition Audio
-    drivers.push(super::hda::HdAudioController::registrar());
+    super::hda::HdAudioController::register_driver();
+
+    // Intel 8254x Gigabit Ethernet
+    super::e1000::E1000::register_driver();
 
     // VIRTIO
-    // drivers.push(super::virtio::Virtio::registrar());
+    // super::virtio::Virtio::register_driver();
 }
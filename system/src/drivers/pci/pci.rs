@@ -1,7 +1,8 @@
 use super::install_drivers;
-use crate::{sync::RwLock, system::System, *};
+use crate::{sync::RwLock, system::System, task::scheduler::Timer, *};
 use alloc::{boxed::Box, collections::BTreeMap, format, string::String, sync::Arc, vec::Vec};
 use core::{cell::UnsafeCell, fmt, num::NonZeroU8, ops::Add};
+pub use mypci::{PciClass, PciDeviceId, PciDeviceMatcher, PciPowerState, PciVendorId};
 
 #[derive(Copy, Clone, Default, PartialEq, Eq, PartialOrd, Ord)]
 pub struct PciConfigAddress {
@@ -87,6 +88,27 @@ pub trait PciDriverRegistrar {
     fn instantiate(&self, device: &'static PciDevice) -> Option<Arc<dyn PciDriver>>;
 }
 
+/// Instantiates the driver for the device that matches [`PciDeviceMatcher`]
+pub type PciProbeFn = fn(&'static PciDevice) -> Option<Arc<dyn PciDriver>>;
+
+struct PciMatchingRegistrar {
+    matcher: PciDeviceMatcher,
+    probe: PciProbeFn,
+}
+
+impl PciDriverRegistrar for PciMatchingRegistrar {
+    fn instantiate(&self, device: &'static PciDevice) -> Option<Arc<dyn PciDriver>> {
+        if self
+            .matcher
+            .matches(device.vendor_id(), device.device_id(), device.class_code())
+        {
+            (self.probe)(device)
+        } else {
+            None
+        }
+    }
+}
+
 pub trait PciDriver {
     /// Returns the PCI configuration address of this device instance.
     fn address(&self) -> PciConfigAddress;
@@ -103,7 +125,7 @@ static mut PCI: UnsafeCell<Pci> = UnsafeCell::new(Pci::new());
 #[allow(dead_code)]
 pub struct Pci {
     devices: BTreeMap<PciConfigAddress, PciDevice>,
-    registrars: Vec<Box<dyn PciDriverRegistrar>>,
+    registrars: RwLock<Vec<Box<dyn PciDriverRegistrar>>>,
     drivers: RwLock<BTreeMap<PciConfigAddress, Arc<dyn PciDriver>>>,
 }
 
@@ -111,7 +133,7 @@ impl Pci {
     const fn new() -> Self {
         Self {
             devices: BTreeMap::new(),
-            registrars: Vec::new(),
+            registrars: RwLock::new(Vec::new()),
             drivers: RwLock::new(BTreeMap::new()),
         }
     }
@@ -129,26 +151,50 @@ impl Pci {
     pub unsafe fn init() {
         assert_call_once!();
 
-        let shared = Self::shared_mut();
-        install_drivers(&mut shared.registrars);
+        install_drivers();
 
         let bus = 0;
         for dev in 0..32 {
             PciDevice::instantiate(bus, dev, 0);
         }
 
+        let shared = Self::shared();
+        let registrars = shared.registrars.read().unwrap();
         for device in Self::devices() {
-            for registrar in &shared.registrars {
-                match registrar.instantiate(&device) {
-                    Some(v) => {
-                        shared.drivers.write().unwrap().insert(device.address(), v);
-                    }
-                    None => {}
-                }
+            for registrar in registrars.iter() {
+                shared.bind(device, registrar.as_ref());
             }
         }
     }
 
+    /// Registers the driver that is probed for each device matching the matcher.
+    ///
+    /// Drivers registered after enumeration are probed for the devices that have no driver yet.
+    pub fn register_driver(matcher: PciDeviceMatcher, probe: PciProbeFn) {
+        Self::register_registrar(Box::new(PciMatchingRegistrar { matcher, probe }));
+    }
+
+    pub fn register_registrar(registrar: Box<dyn PciDriverRegistrar>) {
+        let shared = Self::shared();
+        for device in Self::devices() {
+            shared.bind(device, registrar.as_ref());
+        }
+        shared.registrars.write().unwrap().push(registrar);
+    }
+
+    /// Instantiates the driver for the device if no driver is bound to the device yet.
+    fn bind(&self, device: &'static PciDevice, registrar: &dyn PciDriverRegistrar) {
+        if self.drivers.read().unwrap().contains_key(&device.address()) {
+            return;
+        }
+        if let Some(driver) = registrar.instantiate(device) {
+            self.drivers
+                .write()
+                .unwrap()
+                .insert(device.address(), driver);
+        }
+    }
+
     pub fn devices() -> impl Iterator<Item = &'static PciDevice> {
         Self::shared().devices.values().into_iter()
     }
@@ -170,51 +216,6 @@ impl Pci {
     }
 }
 
-#[repr(transparent)]
-#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq)]
-pub struct PciVendorId(pub u16);
-
-impl PciVendorId {
-    pub const INVALID_0000: Self = Self(0x0000);
-    pub const INVALID_FFFF: Self = Self(0xFFFF);
-
-    pub const VIRTIO: Self = Self(0x1AF4);
-
-    #[inline]
-    pub const fn is_valid(&self) -> bool {
-        self.0 != Self::INVALID_0000.0 && self.0 != Self::INVALID_FFFF.0
-    }
-}
-
-impl fmt::Debug for PciVendorId {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        f.write_fmt(format_args!("VEN_{:04x}", self.0))
-    }
-}
-
-#[repr(transparent)]
-#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq)]
-pub struct PciDeviceId(pub u16);
-
-impl PciDeviceId {
-    pub const INVALID_0000: Self = Self(0x0000);
-    pub const INVALID_FFFF: Self = Self(0xFFFF);
-
-    pub const VIRTIO_MIN: Self = Self(0x1000);
-    pub const VIRTIO_MAX: Self = Self(0x107F);
-
-    #[inline]
-    pub const fn is_valid(&self) -> bool {
-        self.0 != Self::INVALID_0000.0 && self.0 != Self::INVALID_FFFF.0
-    }
-}
-
-impl fmt::Debug for PciDeviceId {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        f.write_fmt(format_args!("DEV_{:04x}", self.0))
-    }
-}
-
 #[repr(C)]
 #[derive(Debug)]
 pub struct PciDevice {
@@ -427,6 +428,53 @@ impl PciDevice {
         Ok(())
     }
 
+    /// Returns the current power state, if the device has the power management capability.
+    pub unsafe fn power_state(&self) -> Option<PciPowerState> {
+        let pm_reg = self.pm_capability()?;
+        let pmcsr = Hal::pci().read_pci(self.addr.register(pm_reg) + 1);
+        Some(PciPowerState::from_pmcsr(pmcsr))
+    }
+
+    /// Changes the power state through the PMCSR of the power management capability.
+    ///
+    /// Waits for the settling time required by the transition before returning.
+    pub unsafe fn set_power_state(&self, state: PciPowerState) -> Result<(), ()> {
+        let Some(pm_reg) = self.pm_capability() else {
+            return Err(());
+        };
+        let pmc = Hal::pci().read_pci(self.addr.register(pm_reg));
+        if !state.is_supported(pmc) {
+            return Err(());
+        }
+        let pmcsr_reg = self.addr.register(pm_reg) + 1;
+        let pmcsr = Hal::pci().read_pci(pmcsr_reg);
+        let current = PciPowerState::from_pmcsr(pmcsr);
+        if current == state {
+            return Ok(());
+        }
+
+        Hal::pci().write_pci(pmcsr_reg, state.pmcsr(pmcsr));
+
+        let mut spin_loop = Hal::cpu().spin_wait();
+        let deadline = Timer::new(PciPowerState::settling_time(current, state));
+        while deadline.is_alive() {
+            spin_loop.wait();
+        }
+
+        if PciPowerState::from_pmcsr(Hal::pci().read_pci(pmcsr_reg)) == state {
+            Ok(())
+        } else {
+            Err(())
+        }
+    }
+
+    #[inline]
+    fn pm_capability(&self) -> Option<u8> {
+        self.capabilities()
+            .find(|(id, _)| *id == PciCapabilityId::PM)
+            .map(|(_, offset)| *offset)
+    }
+
     pub unsafe fn read_pci_command(&self) -> PciCommand {
         PciCommand::from_bits_retain(Hal::pci().read_pci(self.addr.register(1)))
     }
@@ -627,146 +675,3 @@ impl From<u8> for PciCapabilityId {
         Self(raw)
     }
 }
-
-/// A type that defines the PCI class code and interface.
-///
-/// For example, the class code for XHCI (`0x0C_03_30`) is expressed as follows.
-/// ```
-/// let cc = PciClass::code(0x0C).sub(0x03).interface(0x30);
-/// ```
-///
-/// To see if one class code is included in another class code or interface, compare the following
-/// ```
-/// let mask = PciClass::code(0x03).sub(0x00);
-/// if cc.matches(mask) {
-///   // code here
-/// }
-/// ```
-#[repr(transparent)]
-#[derive(Clone, Copy)]
-pub struct PciClass(pub u32);
-
-impl PciClass {
-    /// Makes an instance from the PCI class code register (0x02)
-    #[inline]
-    pub const fn from_pci(data: u32) -> Self {
-        Self((data & 0xFF_FF_FF_00) | PciClassType::Interface as u32)
-    }
-
-    /// Makes an instance from class code.
-    #[inline]
-    pub const fn code(code: u8) -> Self {
-        Self(((code as u32) << 24) | PciClassType::ClassCode as u32)
-    }
-
-    /// Chains subclasses to the class code.
-    #[inline]
-    pub const fn sub(self, sub: u8) -> Self {
-        Self(self.0 & 0xFF_00_00_00 | ((sub as u32) << 16) | PciClassType::Subclass as u32)
-    }
-
-    /// Chains the programming interface to the class code and subclasses.
-    #[inline]
-    pub const fn interface(self, interface: u8) -> Self {
-        Self(self.0 & 0xFF_FF_00_00 | ((interface as u32) << 8) | PciClassType::Interface as u32)
-    }
-
-    #[inline]
-    const fn class_type(&self) -> PciClassType {
-        PciClassType::from_raw(self.0 & 0xFF)
-    }
-
-    #[inline]
-    pub const fn raw_data(&self) -> u32 {
-        self.0 & self.class_type().mask()
-    }
-
-    #[inline]
-    pub const fn data(&self) -> u32 {
-        self.raw_data() >> 8
-    }
-
-    #[inline]
-    pub const fn get_class_code(&self) -> u8 {
-        (self.0 >> 24) as u8
-    }
-
-    #[inline]
-    pub const fn get_sub_class(&self) -> u8 {
-        (self.0 >> 16) as u8
-    }
-
-    #[inline]
-    pub const fn get_interface(&self) -> u8 {
-        (self.0 >> 8) as u8
-    }
-
-    /// Returns whether or not this instance matches the specified class code, subclass, or programming interface.
-    #[inline]
-    pub const fn matches(&self, other: Self) -> bool {
-        match other.class_type() {
-            PciClassType::Unspecified => false,
-            _ => {
-                if self.class_type().mask() < other.class_type().mask() {
-                    false
-                } else {
-                    let mask = other.class_type().mask();
-                    (self.0 & mask) == (other.0 & mask)
-                }
-            }
-        }
-    }
-}
-
-impl fmt::Debug for PciClass {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        // f.debug_tuple("PciClass").field(&self.0).finish()
-        match self.class_type() {
-            PciClassType::Unspecified => f.write_str("PciClass::Unspecified"),
-            PciClassType::ClassCode => {
-                f.write_fmt(format_args!("CC_{:02x}", self.get_class_code()))
-            }
-            PciClassType::Subclass => f.write_fmt(format_args!(
-                "CC_{:02x}{:02x}",
-                self.get_class_code(),
-                self.get_sub_class()
-            )),
-            PciClassType::Interface => f.write_fmt(format_args!(
-                "CC_{:02x}{:02x}{:02x}",
-                self.get_class_code(),
-                self.get_sub_class(),
-                self.get_interface()
-            )),
-        }
-    }
-}
-
-#[derive(Debug, Clone, Copy)]
-enum PciClassType {
-    Unspecified = 0,
-    ClassCode = 1,
-    Subclass = 2,
-    Interface = 3,
-}
-
-impl PciClassType {
-    #[inline]
-    pub const fn mask(&self) -> u32 {
-        match *self {
-            PciClassType::Unspecified => 0,
-            PciClassType::ClassCode => 0xFF_00_00_00,
-            PciClassType::Subclass => 0xFF_FF_00_00,
-            PciClassType::Interface => 0xFF_FF_FF_00,
-        }
-    }
-
-    #[inline]
-    pub const fn from_raw(raw: u32) -> Self {
-        match raw {
-            1 => Self::ClassCode,
-            2 => Self::Subclass,
-            3 => Self::Interface,
-            _ => Self::Unspecified,
-        }
-    }
-}
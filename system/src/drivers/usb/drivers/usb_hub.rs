@@ -6,8 +6,9 @@ use crate::{
     *,
 };
 use alloc::sync::Arc;
-use core::{mem::transmute, pin::Pin, time::Duration};
+use core::{mem::transmute, num::NonZeroU8, pin::Pin, time::Duration};
 use futures_util::Future;
+use myusb::hub::{self, HubPorts};
 
 pub struct UsbHubStarter;
 
@@ -34,14 +35,14 @@ impl UsbHubStarter {
         };
         let endpoint = match interface.endpoints().first() {
             Some(v) => v,
-            None => todo!(),
+            None => return Err(UsbError::InvalidDescriptor),
         };
         let ep = endpoint.address();
         let ps = endpoint.descriptor().max_packet_size();
         if ps > 8 {
             return Err(UsbError::InvalidDescriptor);
         }
-        device.configure_endpoint(endpoint.descriptor()).unwrap();
+        device.configure_endpoint(endpoint.descriptor())?;
 
         match class {
             UsbClass::HUB_FS | UsbClass::HUB_HS_MTT | UsbClass::HUB_HS_STT => {
@@ -109,25 +110,37 @@ impl UsbHub2Driver {
     async fn _main_task(self: &Arc<Self>) -> Result<(), UsbError> {
         let focus = self.device.focus_device();
         for port in self.hub_desc.ports() {
-            self.set_port_feature(UsbHub2PortFeatureSel::PORT_POWER, port)
-                .await?;
+            if let Err(err) = self
+                .set_port_feature(UsbHub2PortFeatureSel::PORT_POWER, port)
+                .await
+            {
+                log!("USB2 HUB PORT {} POWER {:?}", port.0, err);
+            }
             Timer::sleep_async(Duration::from_millis(10)).await;
         }
         Timer::sleep_async(self.hub_desc.power_on_to_power_good()).await;
         for port in self.hub_desc.ports() {
-            self.clear_port_feature(UsbHub2PortFeatureSel::C_PORT_CONNECTION, port)
-                .await?;
+            if let Err(err) = self
+                .clear_port_feature(UsbHub2PortFeatureSel::C_PORT_CONNECTION, port)
+                .await
+            {
+                log!("USB2 HUB PORT {} CLEAR CONNECTION {:?}", port.0, err);
+            }
             Timer::sleep_async(Duration::from_millis(10)).await;
         }
         // Timer::sleep_async(self.hub_desc.power_on_to_power_good()).await;
 
         for port in self.hub_desc.ports() {
-            let status = self.get_port_status(port).await?;
-            if status
-                .status
-                .contains(UsbHub2PortStatusBit::PORT_CONNECTION)
-            {
-                let _ = self.attach_device(port).await;
+            match self.get_port_status(port).await {
+                Ok(status) => {
+                    if status
+                        .status
+                        .contains(UsbHub2PortStatusBit::PORT_CONNECTION)
+                    {
+                        let _ = self.attach_device(port).await;
+                    }
+                }
+                Err(err) => log!("USB2 HUB PORT {} STATUS {:?}", port.0, err),
             }
             Timer::sleep_async(Duration::from_millis(10)).await;
         }
@@ -142,46 +155,13 @@ impl UsbHub2Driver {
             {
                 Ok(_) => {
                     let focus = self.device.focus_device();
-                    let port_change_bitmap = (port_event[0] as u16) | ((port_event[1] as u16) << 8);
-                    for port in self.hub_desc.ports() {
-                        if (port_change_bitmap & (1 << port.0.get())) != 0 {
-                            let status = self.get_port_status(port).await.unwrap();
-                            if status
-                                .change
-                                .contains(UsbHub2PortChangeBit::C_PORT_CONNECTION)
-                            {
-                                Timer::sleep_async(self.hub_desc.power_on_to_power_good()).await;
-                                self.clear_port_feature(
-                                    UsbHub2PortFeatureSel::C_PORT_CONNECTION,
-                                    port,
-                                )
-                                .await
-                                .unwrap();
-
-                                if status
-                                    .status
-                                    .contains(UsbHub2PortStatusBit::PORT_CONNECTION)
-                                {
-                                    let _ = self.attach_device(port).await;
-                                } else {
-                                    let _ = self.detatch_device(port).await;
-                                }
-                            } else {
-                                self.clear_status_changes(
-                                    status,
-                                    &[
-                                        UsbHub2PortFeatureSel::C_PORT_ENABLE,
-                                        UsbHub2PortFeatureSel::C_PORT_SUSPEND,
-                                        UsbHub2PortFeatureSel::C_PORT_OVER_CURRENT,
-                                        UsbHub2PortFeatureSel::C_PORT_RESET,
-                                    ],
-                                    port,
-                                )
-                                .await
-                                .unwrap();
-                            }
-                        }
-                    }
+                    hub::handle_port_changes(
+                        self.as_ref(),
+                        &port_event,
+                        self.hub_desc.ports().map(|port| port.0),
+                        |port, err| log!("USB2 HUB PORT {} ERROR {:?}", port, err),
+                    )
+                    .await;
                     drop(focus);
                 }
                 Err(UsbError::Aborted) => break,
@@ -194,10 +174,7 @@ impl UsbHub2Driver {
         Ok(())
     }
 
-    pub async fn attach_device(
-        self: &Arc<Self>,
-        port: UsbHubPortNumber,
-    ) -> Result<UsbAddress, UsbError> {
+    pub async fn attach_device(&self, port: UsbHubPortNumber) -> Result<UsbAddress, UsbError> {
         self.set_port_feature(UsbHub2PortFeatureSel::PORT_RESET, port)
             .await?;
         Timer::sleep_async(self.hub_desc.power_on_to_power_good()).await;
@@ -235,14 +212,14 @@ impl UsbHub2Driver {
     }
 
     pub async fn get_port_status(
-        self: &Arc<Self>,
+        &self,
         port: UsbHubPortNumber,
     ) -> Result<UsbHub2PortStatus, UsbError> {
         UsbHubCommon::get_port_status(&self.device, port).await
     }
 
     pub async fn set_port_feature(
-        self: &Arc<Self>,
+        &self,
         feature_sel: UsbHub2PortFeatureSel,
         port: UsbHubPortNumber,
     ) -> Result<(), UsbError> {
@@ -250,7 +227,7 @@ impl UsbHub2Driver {
     }
 
     pub async fn clear_port_feature(
-        self: &Arc<Self>,
+        &self,
         feature_sel: UsbHub2PortFeatureSel,
         port: UsbHubPortNumber,
     ) -> Result<(), UsbError> {
@@ -258,7 +235,7 @@ impl UsbHub2Driver {
     }
 
     pub async fn clear_status_changes(
-        self: &Arc<Self>,
+        &self,
         status: UsbHub2PortStatus,
         features: &[UsbHub2PortFeatureSel],
         port: UsbHubPortNumber,
@@ -292,7 +269,14 @@ impl UsbHub3Driver {
                 return;
             }
         };
-        Self::set_hub_depth(&device).await.unwrap();
+        match Self::set_hub_depth(&device).await {
+            Ok(_) => (),
+            Err(_err) => {
+                // TODO:
+                log!("USB3 SET HUB DEPTH {:?}", _err);
+                return;
+            }
+        }
 
         match device.configure_hub3(&hub_desc) {
             Ok(_) => (),
@@ -315,17 +299,27 @@ impl UsbHub3Driver {
     async fn _main_task(self: Arc<Self>) -> Result<(), UsbError> {
         let focus = self.device.focus_device();
         for port in self.hub_desc.ports() {
-            let status = self.get_port_status(port).await?;
-            self.set_port_feature(UsbHub3PortFeatureSel::PORT_POWER, port)
+            let status = match self.get_port_status(port).await {
+                Ok(v) => v,
+                Err(err) => {
+                    log!("USB3 HUB PORT {} STATUS {:?}", port.0, err);
+                    continue;
+                }
+            };
+            if let Err(err) = self
+                .set_port_feature(UsbHub3PortFeatureSel::PORT_POWER, port)
                 .await
-                .unwrap();
+            {
+                log!("USB3 HUB PORT {} POWER {:?}", port.0, err);
+                continue;
+            }
             Timer::sleep_async(Duration::from_millis(10)).await;
             // Timer::sleep_async(hub_desc.power_on_to_power_good()).await;
             if status
                 .status
                 .contains(UsbHub3PortStatusBit::PORT_CONNECTION | UsbHub3PortStatusBit::PORT_ENABLE)
             {
-                let _ = self.clone().attach_device(port).await;
+                let _ = self.attach_device(port).await;
             }
         }
         drop(focus);
@@ -338,48 +332,14 @@ impl UsbHub3Driver {
                 .await
             {
                 Ok(_) => {
-                    let port_change_bitmap = (port_event[0] as u16) | ((port_event[1] as u16) << 8);
                     let focus = self.device.focus_device();
-                    for port in self.hub_desc.ports() {
-                        if (port_change_bitmap & (1 << port.0.get())) != 0 {
-                            let status = self.get_port_status(port).await.unwrap();
-                            if status
-                                .change
-                                .contains(UsbHub3PortChangeBit::C_PORT_CONNECTION)
-                            {
-                                Timer::sleep_async(self.hub_desc.power_on_to_power_good()).await;
-                                self.clear_port_feature(
-                                    UsbHub3PortFeatureSel::C_PORT_CONNECTION,
-                                    port,
-                                )
-                                .await
-                                .unwrap();
-
-                                if status
-                                    .status
-                                    .contains(UsbHub3PortStatusBit::PORT_CONNECTION)
-                                {
-                                    let _ = self.attach_device(port).await;
-                                } else {
-                                    let _ = self.detatch_device(port).await;
-                                }
-                            } else {
-                                self.clear_status_changes(
-                                    status,
-                                    &[
-                                        UsbHub3PortFeatureSel::C_BH_PORT_RESET,
-                                        UsbHub3PortFeatureSel::C_PORT_RESET,
-                                        UsbHub3PortFeatureSel::C_PORT_OVER_CURRENT,
-                                        UsbHub3PortFeatureSel::C_PORT_LINK_STATE,
-                                        UsbHub3PortFeatureSel::C_PORT_CONFIG_ERROR,
-                                    ],
-                                    port,
-                                )
-                                .await
-                                .unwrap();
-                            }
-                        }
-                    }
+                    hub::handle_port_changes(
+                        self.as_ref(),
+                        &port_event,
+                        self.hub_desc.ports().map(|port| port.0),
+                        |port, err| log!("USB3 HUB PORT {} ERROR {:?}", port, err),
+                    )
+                    .await;
                     drop(focus);
                 }
                 Err(UsbError::Aborted) => break,
@@ -393,10 +353,7 @@ impl UsbHub3Driver {
         Ok(())
     }
 
-    pub async fn attach_device(
-        self: &Arc<Self>,
-        port: UsbHubPortNumber,
-    ) -> Result<UsbAddress, UsbError> {
+    pub async fn attach_device(&self, port: UsbHubPortNumber) -> Result<UsbAddress, UsbError> {
         self.set_port_feature(UsbHub3PortFeatureSel::BH_PORT_RESET, port)
             .await?;
 
@@ -427,7 +384,7 @@ impl UsbHub3Driver {
         )
         .await?;
 
-        let status = self.get_port_status(port).await.unwrap();
+        let status = self.get_port_status(port).await?;
         if status
             .status
             .contains(UsbHub3PortStatusBit::PORT_CONNECTION | UsbHub3PortStatusBit::PORT_ENABLE)
@@ -456,14 +413,14 @@ impl UsbHub3Driver {
     }
 
     pub async fn get_port_status(
-        self: &Arc<Self>,
+        &self,
         port: UsbHubPortNumber,
     ) -> Result<UsbHub3PortStatus, UsbError> {
         UsbHubCommon::get_port_status(&self.device, port).await
     }
 
     pub async fn set_port_feature(
-        self: &Arc<Self>,
+        &self,
         feature_sel: UsbHub3PortFeatureSel,
         port: UsbHubPortNumber,
     ) -> Result<(), UsbError> {
@@ -471,7 +428,7 @@ impl UsbHub3Driver {
     }
 
     pub async fn clear_port_feature(
-        self: &Arc<Self>,
+        &self,
         feature_sel: UsbHub3PortFeatureSel,
         port: UsbHubPortNumber,
     ) -> Result<(), UsbError> {
@@ -479,7 +436,7 @@ impl UsbHub3Driver {
     }
 
     pub async fn clear_status_changes(
-        self: &Arc<Self>,
+        &self,
         status: UsbHub3PortStatus,
         features: &[UsbHub3PortFeatureSel],
         port: UsbHubPortNumber,
@@ -495,6 +452,97 @@ impl UsbHub3Driver {
     }
 }
 
+impl HubPorts for UsbHub2Driver {
+    type Error = UsbError;
+
+    async fn connection_change(&self, port: NonZeroU8) -> Result<Option<bool>, UsbError> {
+        let port = UsbHubPortNumber(port);
+        let status = self.get_port_status(port).await?;
+        if status
+            .change
+            .contains(UsbHub2PortChangeBit::C_PORT_CONNECTION)
+        {
+            Timer::sleep_async(self.hub_desc.power_on_to_power_good()).await;
+            self.clear_port_feature(UsbHub2PortFeatureSel::C_PORT_CONNECTION, port)
+                .await?;
+            Ok(Some(
+                status
+                    .status
+                    .contains(UsbHub2PortStatusBit::PORT_CONNECTION),
+            ))
+        } else {
+            self.clear_status_changes(
+                status,
+                &[
+                    UsbHub2PortFeatureSel::C_PORT_ENABLE,
+                    UsbHub2PortFeatureSel::C_PORT_SUSPEND,
+                    UsbHub2PortFeatureSel::C_PORT_OVER_CURRENT,
+                    UsbHub2PortFeatureSel::C_PORT_RESET,
+                ],
+                port,
+            )
+            .await?;
+            Ok(None)
+        }
+    }
+
+    async fn attach_device(&self, port: NonZeroU8) -> Result<(), UsbError> {
+        UsbHub2Driver::attach_device(self, UsbHubPortNumber(port))
+            .await
+            .map(|_| ())
+    }
+
+    async fn detach_device(&self, port: NonZeroU8) -> Result<(), UsbError> {
+        UsbHub2Driver::detatch_device(self, UsbHubPortNumber(port)).await
+    }
+}
+
+impl HubPorts for UsbHub3Driver {
+    type Error = UsbError;
+
+    async fn connection_change(&self, port: NonZeroU8) -> Result<Option<bool>, UsbError> {
+        let port = UsbHubPortNumber(port);
+        let status = self.get_port_status(port).await?;
+        if status
+            .change
+            .contains(UsbHub3PortChangeBit::C_PORT_CONNECTION)
+        {
+            Timer::sleep_async(self.hub_desc.power_on_to_power_good()).await;
+            self.clear_port_feature(UsbHub3PortFeatureSel::C_PORT_CONNECTION, port)
+                .await?;
+            Ok(Some(
+                status
+                    .status
+                    .contains(UsbHub3PortStatusBit::PORT_CONNECTION),
+            ))
+        } else {
+            self.clear_status_changes(
+                status,
+                &[
+                    UsbHub3PortFeatureSel::C_BH_PORT_RESET,
+                    UsbHub3PortFeatureSel::C_PORT_RESET,
+                    UsbHub3PortFeatureSel::C_PORT_OVER_CURRENT,
+                    UsbHub3PortFeatureSel::C_PORT_LINK_STATE,
+                    UsbHub3PortFeatureSel::C_PORT_CONFIG_ERROR,
+                ],
+                port,
+            )
+            .await?;
+            Ok(None)
+        }
+    }
+
+    async fn attach_device(&self, port: NonZeroU8) -> Result<(), UsbError> {
+        UsbHub3Driver::attach_device(self, UsbHubPortNumber(port))
+            .await
+            .map(|_| ())
+    }
+
+    async fn detach_device(&self, port: NonZeroU8) -> Result<(), UsbError> {
+        UsbHub3Driver::detatch_device(self, UsbHubPortNumber(port)).await
+    }
+}
+
 pub struct UsbHubCommon;
 
 impl UsbHubCommon {
@@ -1317,4 +1317,6 @@ pub enum UsbError {
     ShortPacket,
     UsbTransactionError,
     OutOfMemory,
+    /// The transfer was cancelled because it did not complete in time
+    Timeout,
 }
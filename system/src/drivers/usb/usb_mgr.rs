@@ -17,7 +17,7 @@ use core::{
     time::Duration,
 };
 use futures_util::Future;
-use megstd::uuid::Uuid;
+use megstd::{future::cancel_on_timeout, uuid::Uuid};
 
 /// USB Driver to Host interface
 pub trait UsbHostInterface {
@@ -58,6 +58,11 @@ pub trait UsbHostInterface {
         data: *const u8,
     ) -> Pin<Box<dyn Future<Output = Result<usize, UsbError>>>>;
 
+    /// Cancels the pending transfer on the endpoint, `None` for the default control pipe.
+    ///
+    /// The cancelled transfer completes with [`UsbError::Aborted`].
+    fn cancel_transfer(&self, ep: Option<UsbEndpointAddress>) -> Result<(), UsbError>;
+
     unsafe fn read(
         self: Arc<Self>,
         ep: UsbEndpointAddress,
@@ -347,6 +352,9 @@ pub struct UsbDeviceContext {
 }
 
 impl UsbDeviceContext {
+    /// The USB specification allows a device up to 5 seconds to complete a standard request.
+    pub const CONTROL_TIMEOUT: Duration = Duration::from_secs(5);
+
     #[inline]
     async fn new(addr: UsbAddress, host: Arc<dyn UsbHostInterface>) -> Result<Self, UsbError> {
         let mut device_desc: Option<UsbDeviceDescriptor> = None;
@@ -363,6 +371,7 @@ impl UsbDeviceContext {
                         8,
                     ),
                     &mut packet,
+                    Self::CONTROL_TIMEOUT,
                 )
                 .await
                 {
@@ -808,9 +817,45 @@ impl UsbDeviceContext {
             })
     }
 
+    /// Reads from the endpoint, cancelling the transfer if it does not complete in time.
+    pub async fn read_slice_with_timeout(
+        &self,
+        ep: UsbEndpointAddress,
+        buffer: &mut [u8],
+        min_len: usize,
+        max_len: usize,
+        timeout: Duration,
+    ) -> Result<usize, UsbError> {
+        Self::_with_timeout(
+            &self.host_clone(),
+            Some(ep),
+            timeout,
+            self.read_slice(ep, buffer, min_len, max_len),
+        )
+        .await
+    }
+
+    /// Writes to the endpoint, cancelling the transfer if it does not complete in time.
+    pub async fn write_slice_with_timeout(
+        &self,
+        ep: UsbEndpointAddress,
+        buffer: &[u8],
+        timeout: Duration,
+    ) -> Result<(), UsbError> {
+        Self::_with_timeout(
+            &self.host_clone(),
+            Some(ep),
+            timeout,
+            self.write_slice(ep, buffer),
+        )
+        .await
+    }
+
+    /// Control transfers without the timeout specified fail after [`Self::CONTROL_TIMEOUT`].
     #[inline]
     pub async fn control_nodata(&self, setup: UsbControlSetupData) -> Result<(), UsbError> {
-        Self::_control_nodata(&self.host_clone(), setup).await
+        self.control_nodata_with_timeout(setup, Self::CONTROL_TIMEOUT)
+            .await
     }
 
     #[inline]
@@ -821,7 +866,15 @@ impl UsbDeviceContext {
         min_len: usize,
         max_len: usize,
     ) -> Result<(), UsbError> {
-        Self::_control_vec(&self.host_clone(), setup, vec, min_len, max_len).await
+        Self::_control_vec(
+            &self.host_clone(),
+            setup,
+            vec,
+            min_len,
+            max_len,
+            Self::CONTROL_TIMEOUT,
+        )
+        .await
     }
 
     #[inline]
@@ -830,33 +883,109 @@ impl UsbDeviceContext {
         setup: UsbControlSetupData,
         data: &mut [u8],
     ) -> Result<(), UsbError> {
-        Self::_control_slice(&self.host_clone(), setup, data).await
+        self.control_slice_with_timeout(setup, data, Self::CONTROL_TIMEOUT)
+            .await
     }
 
+    #[inline]
     pub async fn control_send(
+        &self,
+        setup: UsbControlSetupData,
+        max_len: usize,
+        data: &[u8],
+    ) -> Result<(), UsbError> {
+        self.control_send_with_timeout(setup, max_len, data, Self::CONTROL_TIMEOUT)
+            .await
+    }
+
+    #[inline]
+    pub async fn control_nodata_with_timeout(
+        &self,
+        setup: UsbControlSetupData,
+        timeout: Duration,
+    ) -> Result<(), UsbError> {
+        Self::_control_nodata(&self.host_clone(), setup, timeout).await
+    }
+
+    #[inline]
+    pub async fn control_slice_with_timeout(
+        &self,
+        setup: UsbControlSetupData,
+        data: &mut [u8],
+        timeout: Duration,
+    ) -> Result<(), UsbError> {
+        Self::_control_slice(&self.host_clone(), setup, data, timeout).await
+    }
+
+    pub async fn control_send_with_timeout(
         &self,
         mut setup: UsbControlSetupData,
         max_len: usize,
         data: &[u8],
+        timeout: Duration,
     ) -> Result<(), UsbError> {
         if max_len > data.len() {
             return Err(UsbError::InvalidParameter);
         }
         setup.wLength = max_len as u16;
-        match unsafe { self.host_clone().control_send(setup, data.as_ptr()) }.await {
-            Ok(_v) => Ok(()),
-            Err(err) => Err(err),
+        unsafe { Self::_control_send(&self.host_clone(), setup, data.as_ptr(), timeout) }
+            .await
+            .map(|_| ())
+    }
+
+    /// Runs the transfer, and cancels it with [`UsbError::Timeout`] if the timeout expires first.
+    async fn _with_timeout<T>(
+        host: &Arc<dyn UsbHostInterface>,
+        ep: Option<UsbEndpointAddress>,
+        timeout: Duration,
+        transfer: impl Future<Output = Result<T, UsbError>>,
+    ) -> Result<T, UsbError> {
+        // The transfer is drained after cancelling, because the host controller may still own the ring
+        match cancel_on_timeout(transfer, Timer::sleep_async(timeout), || {
+            host.cancel_transfer(ep)
+        })
+        .await?
+        {
+            (Err(UsbError::Aborted), true) => Err(UsbError::Timeout),
+            (result, _) => result,
         }
     }
 
+    #[inline]
+    async unsafe fn _control_recv(
+        host: &Arc<dyn UsbHostInterface>,
+        setup: UsbControlSetupData,
+        data: *mut u8,
+        timeout: Duration,
+    ) -> Result<usize, UsbError> {
+        Self::_with_timeout(host, None, timeout, unsafe {
+            host.clone().control_recv(setup, data)
+        })
+        .await
+    }
+
+    #[inline]
+    async unsafe fn _control_send(
+        host: &Arc<dyn UsbHostInterface>,
+        setup: UsbControlSetupData,
+        data: *const u8,
+        timeout: Duration,
+    ) -> Result<usize, UsbError> {
+        Self::_with_timeout(host, None, timeout, unsafe {
+            host.clone().control_send(setup, data)
+        })
+        .await
+    }
+
     async fn _control_nodata(
         host: &Arc<dyn UsbHostInterface>,
         setup: UsbControlSetupData,
+        timeout: Duration,
     ) -> Result<(), UsbError> {
         if setup.wLength > 0 {
             return Err(UsbError::InvalidParameter);
         }
-        unsafe { host.clone().control_recv(setup, null_mut()) }
+        unsafe { Self::_control_recv(host, setup, null_mut(), timeout) }
             .await
             .map(|_| ())
     }
@@ -867,6 +996,7 @@ impl UsbDeviceContext {
         buffer: &mut Vec<u8>,
         min_len: usize,
         max_len: usize,
+        timeout: Duration,
     ) -> Result<(), UsbError> {
         buffer.resize(0, 0);
         buffer
@@ -875,7 +1005,7 @@ impl UsbDeviceContext {
         buffer.resize(max_len, 0);
 
         setup.wLength = max_len as u16;
-        unsafe { host.clone().control_recv(setup, buffer.as_mut_ptr()) }
+        unsafe { Self::_control_recv(host, setup, buffer.as_mut_ptr(), timeout) }
             .await
             .and_then(|len| {
                 if len >= min_len {
@@ -891,9 +1021,10 @@ impl UsbDeviceContext {
         host: &Arc<dyn UsbHostInterface>,
         mut setup: UsbControlSetupData,
         data: &mut [u8],
+        timeout: Duration,
     ) -> Result<(), UsbError> {
         setup.wLength = data.len() as u16;
-        unsafe { host.clone().control_recv(setup, data.as_mut_ptr()) }
+        unsafe { Self::_control_recv(host, setup, data.as_mut_ptr(), timeout) }
             .await
             .and_then(|len| {
                 if len == data.len() {
@@ -928,6 +1059,7 @@ impl UsbDeviceContext {
                 total_length,
             ),
             vec.as_mut_slice(),
+            Self::CONTROL_TIMEOUT,
         )
         .await?;
 
@@ -960,17 +1092,18 @@ impl UsbDeviceContext {
         let size_of_t = size_of::<T>();
         let mut result = MaybeUninit::<T>::zeroed();
         match unsafe {
-            host.clone()
-                .control_recv(
-                    UsbControlSetupData::get_descriptor(
-                        request_type,
-                        T::DESCRIPTOR_TYPE,
-                        index,
-                        size_of_t,
-                    ),
-                    result.as_mut_ptr() as *mut u8,
-                )
-                .await
+            Self::_control_recv(
+                host,
+                UsbControlSetupData::get_descriptor(
+                    request_type,
+                    T::DESCRIPTOR_TYPE,
+                    index,
+                    size_of_t,
+                ),
+                result.as_mut_ptr() as *mut u8,
+                Self::CONTROL_TIMEOUT,
+            )
+            .await
         } {
             Ok(len) => {
                 if len != size_of_t {
@@ -1011,7 +1144,7 @@ impl UsbDeviceContext {
         .index(lang_id.0);
 
         let mut vec = Vec::new();
-        match Self::_control_vec(host, setup, &mut vec, 4, 255).await {
+        match Self::_control_vec(host, setup, &mut vec, 4, 255, Self::CONTROL_TIMEOUT).await {
             Ok(_) => (),
             Err(_) => return None,
         }
@@ -1043,6 +1176,7 @@ impl UsbDeviceContext {
                 wIndex: 0,
                 wLength: 0,
             },
+            Self::CONTROL_TIMEOUT,
         )
         .await
     }
@@ -1059,6 +1193,7 @@ impl UsbDeviceContext {
                 UsbControlRequest::CLEAR_FEATURE,
             )
             .value(feature_sel as u16),
+            Self::CONTROL_TIMEOUT,
         )
         .await
     }
@@ -1075,6 +1210,7 @@ impl UsbDeviceContext {
                 UsbControlRequest::SET_FEATURE,
             )
             .value(feature_sel as u16),
+            Self::CONTROL_TIMEOUT,
         )
         .await
     }
@@ -1092,13 +1228,15 @@ impl UsbDeviceContext {
         let length = 6;
         let data = values as *const _ as *const u8;
         unsafe {
-            host.clone().control_send(
+            Self::_control_send(
+                host,
                 UsbControlSetupData::request(
                     UsbControlRequestBitmap::SET_DEVICE,
                     UsbControlRequest::SET_SEL,
                 )
                 .length(length),
                 data,
+                Self::CONTROL_TIMEOUT,
             )
         }
         .await
@@ -1123,6 +1261,7 @@ impl UsbDeviceContext {
             )
             .index_if(if_no),
             slice,
+            Self::CONTROL_TIMEOUT,
         )
         .await
     }
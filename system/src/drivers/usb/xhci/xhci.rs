@@ -27,26 +27,6 @@ use megstd::mem::dispose::*;
 use num_derive::FromPrimitive;
 use num_traits::FromPrimitive;
 
-pub struct XhciRegistrar();
-
-impl XhciRegistrar {
-    const PREFERRED_CLASS: PciClass = PciClass::code(0x0C).sub(0x03).interface(0x30);
-
-    pub fn new() -> Box<dyn PciDriverRegistrar> {
-        Box::new(Self()) as Box<dyn PciDriverRegistrar>
-    }
-}
-
-impl PciDriverRegistrar for XhciRegistrar {
-    fn instantiate(&self, device: &PciDevice) -> Option<Arc<dyn PciDriver>> {
-        if device.class_code().matches(Self::PREFERRED_CLASS) {
-            unsafe { Xhci::new(device) }
-        } else {
-            None
-        }
-    }
-}
-
 /// Extensible Host Controller Interface
 ///
 /// Many methods are made public for documentation purposes, but are not intended to be called from the outside.
@@ -99,9 +79,14 @@ impl Xhci {
     const MAX_TR_INDEX: usize = Self::SIZE_EP_RING - 1;
     const MAX_PORT_CHANGE: usize = 64;
 
+    const PREFERRED_CLASS: PciClass = PciClass::code(0x0C).sub(0x03).interface(0x30);
+
     #[inline]
-    pub fn registrar() -> Box<dyn PciDriverRegistrar> {
-        XhciRegistrar::new()
+    pub fn register_driver() {
+        Pci::register_driver(
+            PciDeviceMatcher::Class(Self::PREFERRED_CLASS),
+            |device| unsafe { Self::new(device) },
+        );
     }
 
     unsafe fn new(device: &PciDevice) -> Option<Arc<dyn PciDriver>> {
@@ -218,11 +203,11 @@ impl Xhci {
                         usb_leg_sup.write_volatile(xecp | USBLEGSUP_OS_OWNED);
 
                         if (usb_leg_sup.read_volatile() & USBLEGSUP_BIOS_OWNED) != 0 {
-                            for _ in 0..100 {
-                                if (usb_leg_sup.read_volatile() & USBLEGSUP_BIOS_OWNED) == 0 {
-                                    break;
-                                }
-                                Timer::sleep(Duration::from_millis(10));
+                            let deadline = Timer::new(Duration::from_secs(1));
+                            while deadline.is_alive()
+                                && (usb_leg_sup.read_volatile() & USBLEGSUP_BIOS_OWNED) != 0
+                            {
+                                Hal::cpu().delay_us(100);
                             }
                             // Force BIOS ownership to be disabled.
                             usb_leg_sup.write_volatile(
@@ -783,6 +768,36 @@ impl Xhci {
         self.execute_command(trb.as_trb())
     }
 
+    /// Stops the endpoint and discards the pending transfers on its ring.
+    ///
+    /// The pending transfer completes with [`UsbError::Aborted`].
+    pub fn cancel_transfer(&self, slot_id: SlotId, dci: DCI) -> Result<(), UsbError> {
+        let Some(index) = self.ep_ring_index(Some(slot_id), Some(dci)) else {
+            return Err(UsbError::InvalidParameter);
+        };
+
+        let trb = TrbStopEndpointCommand::new(slot_id, dci);
+        self.execute_command(trb.as_trb())
+            .map_err(|err| err.to_usb_error())?;
+
+        let ctx = &self.ring_context.read().unwrap()[index];
+        let ctx = unsafe { &*ctx.as_ptr() };
+        let result = match ctx.enqueue_value() {
+            Some(tr_value) => {
+                let trb = TrbSetTrDequeuePointerCommand::new(slot_id, dci, tr_value);
+                self.execute_command(trb.as_trb())
+                    .map(|_| ())
+                    .map_err(|err| err.to_usb_error())
+            }
+            None => Err(UsbError::InvalidParameter),
+        };
+
+        // If the transfer had not been started, no transfer event will be generated
+        ctx.abort();
+
+        result
+    }
+
     pub fn configure_endpoint(
         &self,
         slot_id: SlotId,
@@ -1106,6 +1121,13 @@ impl Xhci {
 
                     match unsafe { event_trb.peek().trb_type() } {
                         Some(TrbType::NORMAL) | Some(TrbType::STATUS) => {}
+                        // The dequeue pointer is moved by the canceller
+                        _ if matches!(
+                            event.completion_code(),
+                            Some(TrbCompletionCode::STOPPED)
+                                | Some(TrbCompletionCode::STOPPED_LENGTH_INVALID)
+                                | Some(TrbCompletionCode::STOPPED_SHORT_PACKET)
+                        ) => {}
                         _ => {
                             // log!(
                             //     "USB Transfer error {} {:?} {:?}",
@@ -1636,6 +1658,26 @@ impl EpRingContext {
     pub fn set_scheduled(&self) {
         self.set_state(RequestState::Scheduled);
     }
+
+    /// Aborts the scheduled request, if any.
+    #[inline]
+    pub fn abort(&self) {
+        if self
+            .compare_exchange_state(RequestState::Scheduled, RequestState::Aborted)
+            .is_ok()
+        {
+            unsafe {
+                self.signal.assume_init_ref().signal();
+            }
+        }
+    }
+
+    /// Returns the next enqueue pointer with DCS for SET_TR_DEQUEUE_POINTER_COMMAND
+    #[inline]
+    pub fn enqueue_value(&self) -> Option<PhysicalAddress> {
+        self.tr_base
+            .map(|v| (v.get() + size_of::<Trb>() * self.index) | self.pcs.tr_value())
+    }
 }
 
 pub struct EpRingScopeGuard<'a>(&'a mut EpRingContext);
@@ -1933,6 +1975,11 @@ impl UsbHostInterface for HciContext {
         Box::pin(self.host().attach_child_device(self.clone(), port, speed))
     }
 
+    fn cancel_transfer(&self, ep: Option<UsbEndpointAddress>) -> Result<(), UsbError> {
+        let dci = ep.map(DCI::from).unwrap_or(DCI::CONTROL);
+        self.host().cancel_transfer(self.device().slot_id, dci)
+    }
+
     fn configure_endpoint(&self, desc: &UsbEndpointDescriptor) -> Result<(), UsbError> {
         let host = self.host();
         let device = self.device();
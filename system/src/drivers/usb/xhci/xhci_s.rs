@@ -455,6 +455,8 @@ pub enum TrbType {
     CONFIGURE_ENDPOINT_COMMAND,
     EVALUATE_CONTEXT_COMMAND,
     RESET_ENDPOINT_COMMAND,
+    STOP_ENDPOINT_COMMAND,
+    SET_TR_DEQUEUE_POINTER_COMMAND,
 
     NOP_COMMAND = 23,
 
@@ -536,6 +538,9 @@ impl From<TrbCompletionCode> for UsbError {
             TrbCompletionCode::INVALID => UsbError::InvalidParameter,
             TrbCompletionCode::USB_TRANSACTION_ERROR => UsbError::UsbTransactionError,
             TrbCompletionCode::SHORT_PACKET => UsbError::ShortPacket,
+            TrbCompletionCode::STOPPED
+            | TrbCompletionCode::STOPPED_LENGTH_INVALID
+            | TrbCompletionCode::STOPPED_SHORT_PACKET => UsbError::Aborted,
             _ => UsbError::ControllerError(value as usize),
         }
     }
@@ -930,6 +935,58 @@ impl TrbSlotId for TrbResetEndpointCommand {}
 
 impl TrbDci for TrbResetEndpointCommand {}
 
+/// TRB for STOP_ENDPOINT_COMMAND
+pub struct TrbStopEndpointCommand(TrbRawData);
+
+impl TrbStopEndpointCommand {
+    #[inline]
+    pub fn new(slot_id: SlotId, dci: DCI) -> Self {
+        let result: Self = unsafe { transmute(Trb::new(TrbType::STOP_ENDPOINT_COMMAND)) };
+        result.set_slot_id(slot_id);
+        result.set_dci(dci);
+        result
+    }
+}
+
+impl TrbBase for TrbStopEndpointCommand {
+    #[inline]
+    fn raw_data(&self) -> &TrbRawData {
+        &self.0
+    }
+}
+
+impl TrbSlotId for TrbStopEndpointCommand {}
+
+impl TrbDci for TrbStopEndpointCommand {}
+
+/// TRB for SET_TR_DEQUEUE_POINTER_COMMAND
+pub struct TrbSetTrDequeuePointerCommand(TrbRawData);
+
+impl TrbSetTrDequeuePointerCommand {
+    /// Creates a command, `tr_value` is the new dequeue pointer with DCS in bit 0.
+    #[inline]
+    pub fn new(slot_id: SlotId, dci: DCI, tr_value: PhysicalAddress) -> Self {
+        let result: Self = unsafe { transmute(Trb::new(TrbType::SET_TR_DEQUEUE_POINTER_COMMAND)) };
+        result.set_slot_id(slot_id);
+        result.set_dci(dci);
+        result.set_ptr(tr_value);
+        result
+    }
+}
+
+impl TrbBase for TrbSetTrDequeuePointerCommand {
+    #[inline]
+    fn raw_data(&self) -> &TrbRawData {
+        &self.0
+    }
+}
+
+impl TrbSlotId for TrbSetTrDequeuePointerCommand {}
+
+impl TrbDci for TrbSetTrDequeuePointerCommand {}
+
+impl TrbPtr for TrbSetTrDequeuePointerCommand {}
+
 /// xHC Event Ring Segment Table Entry
 #[allow(dead_code)]
 #[repr(C)]
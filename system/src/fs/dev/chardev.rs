@@ -0,0 +1,46 @@
+use crate::fs::{devfs::*, *};
+use alloc::borrow::ToOwned;
+pub use megstd::io::chardev::CharDeviceOps;
+use megstd::{io::Result, Arc, String};
+
+/// Creates the character device node `/dev/<name>` that calls the callbacks.
+pub fn register_char_device(
+    name: &str,
+    ops: CharDeviceOps,
+) -> core::result::Result<MinorDevNo, InstallDeviceError> {
+    DevFs::install_minor_device(Arc::new(CharDevice {
+        name: name.to_owned(),
+        ops,
+    }))
+}
+
+struct CharDevice {
+    name: String,
+    ops: CharDeviceOps,
+}
+
+impl DeviceFileDriver for CharDevice {
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn open(&self) -> Result<Arc<dyn DeviceAccessToken>> {
+        Ok(Arc::new(CharDeviceAccessToken(self.ops)))
+    }
+}
+
+struct CharDeviceAccessToken(CharDeviceOps);
+
+impl DeviceAccessToken for CharDeviceAccessToken {
+    fn read_data(&self, _offset: OffsetType, buf: &mut [u8]) -> Result<usize> {
+        (self.0.read)(buf)
+    }
+
+    fn write_data(&self, _offset: OffsetType, buf: &[u8]) -> Result<usize> {
+        (self.0.write)(buf)
+    }
+
+    fn ioctl(&self, request: usize, arg: usize) -> Result<usize> {
+        (self.0.ioctl)(request, arg)
+    }
+}
@@ -1,8 +1,10 @@
+mod chardev;
 pub mod full;
 pub mod null;
 pub mod random;
 // pub mod stdio;
 pub mod zero;
+pub use chardev::*;
 
 use crate::{assert_call_once, System};
 
@@ -12,6 +14,6 @@ pub(super) fn install_drivers() {
     null::Null::init();
     zero::Zero::init();
     full::Full::init();
-    // random::Random::init();
+    random::Random::init();
     // stdio::StdIo::init();
 }
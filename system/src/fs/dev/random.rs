@@ -1,29 +1,93 @@
-use crate::fs::{devfs::*, *};
-use alloc::borrow::ToOwned;
-use megstd::{io::Result, Arc, String};
+use super::*;
+use crate::{
+    sync::{Mutex, MutexGuard},
+    task::scheduler::Timer,
+    *,
+};
+use core::time::Duration;
+use megstd::rand::*;
 
-/// Random Device `/dev/random`
+static POOL: Mutex<EntropyPool> = Mutex::new(EntropyPool::new());
+
+/// Samples from interrupt handlers, mixed into the pool on the next access
+static PENDING: PendingEntropy = PendingEntropy::new();
+
+/// Random Devices `/dev/random` and `/dev/urandom`
+///
+/// Both devices read the ChaCha20 keystream keyed by the kernel entropy pool.
+/// `/dev/random` blocks until the pool has collected enough entropy, `/dev/urandom` never blocks.
 pub struct Random;
 
 impl Random {
+    const POLLING_INTERVAL_MS: u64 = 10;
+
     pub fn init() {
-        DevFs::install_minor_device(Arc::new(Self)).unwrap();
+        if Hal::cpu().has_hardware_rand() {
+            let mut seed = [0u8; EntropyPool::SEED_SIZE];
+            for chunk in seed.chunks_mut(8) {
+                let value = Hal::cpu().secure_rand();
+                chunk.copy_from_slice(&value.to_le_bytes());
+            }
+            Self::add_entropy(&seed);
+        } else {
+            // Not credited as entropy, but differs from boot to boot
+            Self::pool().credit(&(Timer::monotonic().as_nanos() as u64).to_le_bytes(), 0);
+        }
+
+        register_char_device(
+            "random",
+            CharDeviceOps {
+                read: |buf| {
+                    while !Self::pool().is_seeded() {
+                        Timer::sleep(Duration::from_millis(Self::POLLING_INTERVAL_MS));
+                    }
+                    Self::pool().fill_bytes(buf);
+                    Ok(buf.len())
+                },
+                ..CharDeviceOps::DEFAULT
+            },
+        )
+        .unwrap();
+
+        register_char_device(
+            "urandom",
+            CharDeviceOps {
+                read: |buf| {
+                    Self::pool().fill_bytes(buf);
+                    Ok(buf.len())
+                },
+                ..CharDeviceOps::DEFAULT
+            },
+        )
+        .unwrap();
     }
-}
 
-impl DeviceFileDriver for Random {
-    fn name(&self) -> String {
-        "random".to_owned()
+    /// Adds the data to the entropy pool, each byte is credited as a byte of entropy.
+    ///
+    /// This takes a blocking lock, use [`Random::add_interrupt_entropy`] in interrupt handlers.
+    pub fn add_entropy(data: &[u8]) {
+        Self::pool().credit(data, data.len() * 8);
     }
 
-    fn open(&self) -> Result<Arc<dyn DeviceAccessToken>> {
-        Ok(Arc::new(Self))
+    /// Adds a sample such as the timing of an interrupt without taking a lock.
+    ///
+    /// At most [`PendingEntropy::MAX_BITS_PER_EVENT`] bits are credited,
+    /// the sample is mixed into the pool on its next access.
+    #[inline]
+    pub fn add_interrupt_entropy(sample: u64, bits: usize) {
+        PENDING.add(sample, bits);
+    }
+
+    /// Returns a random number from the entropy pool, which never blocks.
+    #[inline]
+    pub fn next_u64() -> u64 {
+        Self::pool().next_u64()
     }
-}
 
-impl DeviceAccessToken for Random {
-    fn read_data(&self, _offset: OffsetType, _buf: &mut [u8]) -> Result<usize> {
-        todo!()
-        // Ok(buf.len())
+    /// Locks the pool after mixing in the pending samples.
+    fn pool<'a>() -> MutexGuard<'a, EntropyPool> {
+        let mut pool = POOL.lock().unwrap();
+        PENDING.drain_into(&mut pool);
+        pool
     }
 }
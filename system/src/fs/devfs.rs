@@ -313,6 +313,10 @@ pub trait DeviceAccessToken {
     fn lseek(&self, _offset: OffsetType, _whence: Whence) -> Result<OffsetType> {
         Err(ErrorKind::NotSeekable.into())
     }
+
+    fn ioctl(&self, _request: usize, _arg: usize) -> Result<usize> {
+        Err(ErrorKind::Unsupported.into())
+    }
 }
 
 struct DevFsAccessToken {
@@ -347,4 +351,8 @@ impl FsAccessToken for DevFsAccessToken {
     fn lseek(&self, offset: OffsetType, whence: Whence) -> Result<OffsetType> {
         self.device.lseek(offset, whence)
     }
+
+    fn ioctl(&self, request: usize, arg: usize) -> Result<usize> {
+        self.device.ioctl(request, arg)
+    }
 }
@@ -10,7 +10,10 @@ use alloc::{
 };
 use core::{fmt::Display, num::NonZeroU64};
 use megstd::{
-    fs::FileType,
+    fs::{
+        tree::{self, DirTree, EntryKind},
+        FileType,
+    },
     io::{Error, ErrorKind, Read, Result, Write},
 };
 use myos_archive::ArchiveReader;
@@ -32,6 +35,8 @@ unsafe impl Sync for FileManager {}
 impl FileManager {
     pub const PATH_SEPARATOR: &'static str = "/";
 
+    const COPY_BUFFER_SIZE: usize = 4096;
+
     #[inline]
     const fn new() -> Self {
         Self {
@@ -216,7 +221,10 @@ impl FileManager {
         let (fs, inode) = Self::resolve_all(path)?;
         let stat = fs.stat(inode).ok_or(ErrorKind::NotFound)?;
         if !stat.file_type().is_dir() {
-            return Err(ErrorKind::NotADirectory.into());
+            return Err(megstd::io::Error::with_context(
+                ErrorKind::NotADirectory,
+                "change directory",
+            ));
         }
 
         Scheduler::current_pid().set_cwd(Self::_join_path(&path_components).as_str());
@@ -233,10 +241,13 @@ impl FileManager {
         let (fs, inode) = Self::resolve_all(path)?;
 
         let Some(stat) = fs.stat(inode) else {
-            return Err(ErrorKind::NotFound.into());
+            return Err(megstd::io::Error::with_context(ErrorKind::NotFound, "open"));
         };
         if stat.file_type().is_dir() {
-            return Err(ErrorKind::IsADirectory.into());
+            return Err(megstd::io::Error::with_context(
+                ErrorKind::IsADirectory,
+                "open",
+            ));
         }
 
         let access_token = fs.open(inode)?;
@@ -347,12 +358,113 @@ impl FileManager {
         }
     }
 
+    /// Removes a directory after recursively removing all its contents.
+    ///
+    /// If some entries cannot be removed, the remaining entries are still removed
+    /// and the first error is returned.
+    pub fn remove_dir_all(path: &str) -> Result<()> {
+        tree::remove_dir_all(&FileTree, &Self::canonical_path(path))
+    }
+
+    /// Copies a directory and all its contents recursively.
+    ///
+    /// Copying a directory into itself is rejected.
+    /// If some entries cannot be copied, the remaining entries are still copied
+    /// and the first error is returned.
+    pub fn copy_dir_all(src: &str, dst: &str) -> Result<()> {
+        tree::copy_dir_all(
+            &FileTree,
+            &Self::canonical_path(src),
+            &Self::canonical_path(dst),
+        )
+    }
+
+    /// Copies a file like `cp`, into the destination if it is a directory.
+    ///
+    /// Returns the path of the copied file.
+    pub fn copy(src: &str, dst: &str) -> Result<String> {
+        tree::copy_file(
+            &FileTree,
+            &Self::canonical_path(src),
+            &Self::canonical_path(dst),
+        )
+    }
+
+    /// Copies the contents of a file, creating or truncating the destination.
+    pub fn copy_file(src: &str, dst: &str) -> Result<()> {
+        let mut src = Self::open(src, OpenOptions::new().read(true))?;
+        let mut dst = match Self::creat(dst) {
+            Ok(v) => v,
+            Err(err) if err.kind() == ErrorKind::AlreadyExists => {
+                let mut file = Self::open(dst, OpenOptions::new().write(true))?;
+                file.truncate(0)?;
+                file
+            }
+            Err(err) => return Err(err),
+        };
+
+        let mut buf = Vec::new();
+        buf.resize(Self::COPY_BUFFER_SIZE, 0);
+        loop {
+            match src.read(&mut buf)? {
+                0 => break,
+                size => dst.write_all(&buf[..size])?,
+            }
+        }
+        dst.flush()
+    }
+
     pub fn mount_points<'a>() -> RwLockReadGuard<'a, BTreeMap<String, Arc<dyn FsDriver>>> {
         let shared = FileManager::shared();
         shared.mount_points.read().unwrap()
     }
 }
 
+/// Recursive operations on the tree of [`FileManager`]
+struct FileTree;
+
+impl FileTree {
+    #[inline]
+    fn kind(file_type: FileType) -> EntryKind {
+        if file_type.is_dir() {
+            EntryKind::Dir
+        } else if file_type.is_symlink() {
+            EntryKind::Symlink
+        } else {
+            EntryKind::File
+        }
+    }
+}
+
+impl DirTree for FileTree {
+    fn entries(&self, path: &str) -> Result<Vec<(String, EntryKind)>> {
+        Ok(FileManager::read_dir(path)?
+            .map(|entry| {
+                (
+                    entry.name().to_owned(),
+                    Self::kind(entry.metadata().file_type()),
+                )
+            })
+            .collect())
+    }
+
+    fn kind_of(&self, path: &str) -> Result<EntryKind> {
+        FileManager::stat(path).map(|stat| Self::kind(stat.file_type()))
+    }
+
+    fn mkdir(&self, path: &str) -> Result<()> {
+        FileManager::mkdir(path)
+    }
+
+    fn unlink(&self, path: &str) -> Result<()> {
+        FileManager::unlink(path)
+    }
+
+    fn copy_contents(&self, src: &str, dst: &str) -> Result<()> {
+        FileManager::copy_file(src, dst)
+    }
+}
+
 #[repr(transparent)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub struct INodeType(NonZeroU64);
@@ -447,6 +559,10 @@ pub trait FsAccessToken {
     fn flush(&self) -> Result<()> {
         Ok(())
     }
+
+    fn ioctl(&self, _request: usize, _arg: usize) -> Result<usize> {
+        Err(ErrorKind::Unsupported.into())
+    }
 }
 
 pub struct FsRawReadDir {
@@ -581,6 +697,12 @@ impl FsRawFileControlBlock {
     pub fn fstat(&self) -> Option<FsRawMetaData> {
         self.access_token.stat()
     }
+
+    /// Performs the device-specific request.
+    #[inline]
+    pub fn ioctl(&mut self, request: usize, arg: usize) -> Result<usize> {
+        self.access_token.ioctl(request, arg)
+    }
 }
 
 impl Read for FsRawFileControlBlock {
@@ -1,3 +1,5 @@
 //! Firmware supports
 
 pub mod smbios;
+
+pub use myfdt as fdt;
@@ -1,12 +1,16 @@
 //! Hardware Abstraction Layer
 
-use crate::{drivers::pci::PciConfigAddress, system::ProcessorIndex};
+use crate::{
+    drivers::pci::PciConfigAddress, fs::dev::random::Random, system::ProcessorIndex,
+    task::scheduler::Timer,
+};
 use core::{
     fmt,
     iter::Step,
     num::NonZeroU64,
     ops::{Add, BitAnd, BitOr, Mul, Not, Sub},
     sync::atomic::{AtomicUsize, Ordering},
+    time::Duration,
 };
 
 pub use crate::arch::hal::{Hal, InterruptGuard, Spinlock};
@@ -74,7 +78,48 @@ pub trait HalCpu {
 
     fn broadcast_invalidate_tlb(&self) -> Result<(), ()>;
 
-    unsafe fn invoke_user(&self, start: usize, stack_pointer: usize) -> !;
+    /// Returns a random number, which may come from the kernel entropy pool.
+    fn secure_rand(&self) -> u64 {
+        Random::next_u64()
+    }
+
+    /// Returns whether [`HalCpu::secure_rand`] uses the hardware entropy source.
+    fn has_hardware_rand(&self) -> bool {
+        false
+    }
+
+    /// Busy-waits for at least the specified number of microseconds.
+    #[inline]
+    fn delay_us(&self, us: u64) {
+        self.delay_ns(us.saturating_mul(1000));
+    }
+
+    /// Busy-waits for at least the specified number of nanoseconds.
+    ///
+    /// The default implementation waits with the timer, so it is rounded up to microseconds.
+    fn delay_ns(&self, ns: u64) {
+        // One more tick, since the current tick may have almost elapsed
+        let deadline = Timer::new(Duration::from_micros(ns.div_ceil(1000) + 1));
+        deadline.repeat_until(|| self.spin_loop_hint());
+    }
+
+    /// Requests a timer interrupt on the current processor after the duration,
+    /// if it comes before the next scheduler tick.
+    ///
+    /// The default implementation does nothing, and the timer event fires on the next tick.
+    #[inline]
+    fn set_next_deadline(&self, _duration: Duration) {}
+
+    /// Returns the frame pointer of the caller, or `0` if it is not available.
+    #[inline]
+    fn frame_pointer(&self) -> usize {
+        0
+    }
+
+    /// Launches the user mode application.
+    ///
+    /// `thread_pointer` points to the thread local storage of the application, or is `0` if it has none.
+    unsafe fn invoke_user(&self, start: usize, stack_pointer: usize, thread_pointer: usize) -> !;
 
     #[cfg(target_arch = "x86_64")]
     unsafe fn invoke_legacy(&self, ctx: &crate::rt::LegacyAppContext) -> !;
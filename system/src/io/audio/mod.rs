@@ -14,7 +14,7 @@ use core::{
     sync::atomic::{AtomicUsize, Ordering},
     time::Duration,
 };
-use megstd::{Arc, BTreeMap, Box, Vec, Weak};
+use megstd::{audio::PcmMixer, Arc, BTreeMap, Box, Vec, Weak};
 
 pub type FreqType = f64;
 pub type SampleType = f64;
@@ -30,6 +30,7 @@ pub struct AudioManager {
     audio_driver: Mutex<Option<Arc<dyn AudioDriver>>>,
     emitters: Mutex<BTreeMap<AudioContextHandle, AudioEmitter>>,
     contexts: Mutex<BTreeMap<AudioContextHandle, Weak<AudioContext>>>,
+    pcm_mixer: Mutex<PcmMixer<AudioContextHandle>>,
 }
 
 impl AudioManager {
@@ -50,6 +51,7 @@ impl AudioManager {
             audio_driver: Mutex::new(None),
             emitters: Mutex::new(BTreeMap::new()),
             contexts: Mutex::new(BTreeMap::new()),
+            pcm_mixer: Mutex::new(PcmMixer::new(Self::DEFAULT_SAMPLE_RATE as u32)),
         }
     }
 
@@ -107,6 +109,32 @@ impl AudioManager {
         let _ = shared.emitters.lock().unwrap().remove(&handle);
     }
 
+    /// Plays 16-bit monaural PCM samples at the sample rate.
+    #[inline]
+    pub fn play_pcm(samples: Vec<i16>, sample_rate: u32) -> AudioContextHandle {
+        let shared = Self::shared();
+        let handle = Self::next_handle();
+        shared
+            .pcm_mixer
+            .lock()
+            .unwrap()
+            .play(handle, samples, sample_rate);
+        handle
+    }
+
+    /// Sets the volume of the PCM stream, [`megstd::audio::PCM_VOLUME_MAX`] plays as is.
+    #[inline]
+    pub fn set_pcm_volume(handle: AudioContextHandle, volume: u16) {
+        let shared = Self::shared();
+        shared.pcm_mixer.lock().unwrap().set_volume(&handle, volume);
+    }
+
+    #[inline]
+    pub fn stop_pcm(handle: AudioContextHandle) {
+        let shared = Self::shared();
+        shared.pcm_mixer.lock().unwrap().stop(&handle);
+    }
+
     /// Audio Scheduler
     fn _audio_thread(_: usize) {
         let shared = Self::shared();
@@ -131,25 +159,34 @@ impl AudioManager {
         let wave_buffer =
             unsafe { slice::from_raw_parts_mut(transmute(buffer.get_unchecked_mut(0)), wave_len) };
 
+        let mut mix_buffer = Vec::with_capacity(wave_len);
+        mix_buffer.resize(wave_len, 0i16);
+
         let timer_len = (wave_len as f64 / Self::DEFAULT_SAMPLE_RATE * 1000.0) as u64 - 1;
 
         // panic!("LEN {} {}", wave_len, timer_len);
 
         loop {
             let mut emitters = shared.emitters.lock().unwrap();
-            let is_mute = if emitters.len() > 0 {
+            let mut pcm_mixer = shared.pcm_mixer.lock().unwrap();
+            let is_mute = if emitters.len() > 0 || !pcm_mixer.is_empty() {
                 let master_gain = Self::master_gain();
-                for data in wave_buffer.iter_mut() {
+                for data in mix_buffer.iter_mut() {
                     let mut sum = 0.0;
                     for emitter in emitters.values_mut() {
                         sum += emitter.render(master_gain);
                     }
-                    *data = (Self::reinterpret_i16(sum) as u32) * 0x0001_0001;
+                    *data = Self::reinterpret_i16(sum);
+                }
+                pcm_mixer.mix_into(&mut mix_buffer);
+                for (data, sample) in wave_buffer.iter_mut().zip(mix_buffer.iter()) {
+                    *data = (*sample as u16 as u32) * 0x0001_0001;
                 }
                 false
             } else {
                 true
             };
+            drop(pcm_mixer);
             drop(emitters);
             if is_mute {
                 loop {
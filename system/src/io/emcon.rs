@@ -1,6 +1,6 @@
 //! Emergency debugging console
 
-use super::tty::{NullTty, Tty, TtyRead, TtyReadResult, TtyWrite};
+use super::tty::{GlyphSet, NullTty, Tty, TtyRead, TtyReadResult, TtyWrite};
 use crate::system::*;
 use crate::ui::font::*;
 use alloc::boxed::Box;
@@ -13,6 +13,7 @@ pub struct EmConsole {
     fg_color: Color,
     bg_color: Color,
     font: &'static FixedFontDriver<'static>,
+    glyph_set: GlyphSet,
 }
 
 impl EmConsole {
@@ -28,6 +29,7 @@ impl EmConsole {
             fg_color: Self::DEFAULT_FG_COLOR,
             bg_color: Self::DEFAULT_BG_COLOR,
             font,
+            glyph_set: GlyphSet::Unicode,
         }
     }
 
@@ -71,6 +73,7 @@ impl EmConsole {
                 self.y += 1;
             }
             _ => {
+                let c = self.glyph_set.map_char(c);
                 let origin = Point::new(
                     self.x as isize * font_size.width + Self::PADDING,
                     self.y as isize * font_size.height + Self::PADDING,
@@ -149,6 +152,14 @@ impl TtyWrite for EmConsole {
             self.bg_color = Self::DEFAULT_BG_COLOR;
         }
     }
+
+    fn glyph_set(&self) -> GlyphSet {
+        self.glyph_set
+    }
+
+    fn set_glyph_set(&mut self, glyph_set: GlyphSet) -> GlyphSet {
+        core::mem::replace(&mut self.glyph_set, glyph_set)
+    }
 }
 
 impl TtyRead for EmConsole {
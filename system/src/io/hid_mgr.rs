@@ -1,21 +1,25 @@
 //! Human Interface Device Manager
 
 use crate::{
+    fs::dev::random::Random,
     sync::atomic::{AtomicFlags, AtomicWrapperU8},
     sync::RwLock,
+    task::scheduler::Timer,
     ui::window::*,
     *,
 };
 use alloc::{collections::BTreeMap, sync::Arc, vec::Vec};
 use core::{
     num::*,
-    sync::atomic::{AtomicIsize, AtomicUsize, Ordering},
+    sync::atomic::{AtomicIsize, AtomicU32, AtomicUsize, Ordering},
 };
-use megstd::{drawing::*, io::hid::*};
+use megstd::{
+    drawing::*,
+    io::{hid::*, keylayout::*},
+};
+use mywm::pointer::PendingDelta;
 use num_traits::FromPrimitive;
 
-const INVALID_UNICHAR: char = '\u{FEFF}';
-
 #[derive(Debug, Clone, Copy)]
 pub struct KeyEventFlags(u8);
 
@@ -189,7 +193,7 @@ pub struct MouseState {
     pub prev_buttons: AtomicWrapperU8<MouseButton>,
     pub x: AtomicIsize,
     pub y: AtomicIsize,
-    pub wheel: AtomicIsize,
+    pub wheel: PendingDelta,
     pub max_x: isize,
     pub max_y: isize,
 }
@@ -202,7 +206,7 @@ impl MouseState {
             prev_buttons: AtomicWrapperU8::empty(),
             x: AtomicIsize::new(0),
             y: AtomicIsize::new(0),
-            wheel: AtomicIsize::new(0),
+            wheel: PendingDelta::new(),
             max_x: 0,
             max_y: 0,
         }
@@ -217,6 +221,7 @@ impl MouseState {
             .store(self.current_buttons.swap(report.buttons));
         self.x.fetch_add(report.x.into(), Ordering::SeqCst);
         self.y.fetch_add(report.y.into(), Ordering::SeqCst);
+        self.wheel.add(report.wheel.into());
         WindowManager::post_relative_pointer(self);
     }
 
@@ -229,6 +234,7 @@ impl MouseState {
             .store(self.current_buttons.swap(report.buttons));
         self.x.store(report.x.into(), Ordering::SeqCst);
         self.y.store(report.y.into(), Ordering::SeqCst);
+        self.wheel.add(report.wheel.into());
         WindowManager::post_absolute_pointer(self);
     }
 }
@@ -875,6 +881,8 @@ impl core::fmt::Debug for ParsedReportMainItem {
 /// Keyboard scancodes will be converted to the Usage specified by the USB-HID specification on all platforms.
 pub struct HidManager {
     key_modifier: AtomicFlags<Modifier>,
+    key_layout: AtomicWrapperU8<KeyLayout>,
+    dead_key: AtomicU32,
     simulated_game_input: RwLock<GameInput>,
     game_inputs: RwLock<BTreeMap<GameInputHandle, Arc<RwLock<GameInput>>>>,
     current_game_inputs: RwLock<Option<GameInputHandle>>,
@@ -887,6 +895,8 @@ impl HidManager {
     const fn new() -> Self {
         HidManager {
             key_modifier: AtomicFlags::empty(),
+            key_layout: AtomicWrapperU8::empty(),
+            dead_key: AtomicU32::new(0),
             simulated_game_input: RwLock::new(GameInput::empty()),
             game_inputs: RwLock::new(BTreeMap::new()),
             current_game_inputs: RwLock::new(None),
@@ -911,109 +921,57 @@ impl HidManager {
             shared.key_modifier.set(bit_position, !event.is_break());
         }
         let event = KeyEvent::new(usage, shared.key_modifier.value(), event.flags());
+
+        // The timing of keystrokes is a source of entropy, but only a little
+        let timing = Timer::monotonic().as_micros() as u64;
+        Random::add_interrupt_entropy(timing ^ ((usage.0 as u64) << 56), 1);
+
         WindowManager::post_key_event(event);
     }
 
     #[inline]
-    fn key_event_to_char(event: KeyEvent) -> char {
-        if event.flags().contains(KeyEventFlags::BREAK) || event.usage() == Usage::NONE {
-            '\0'
-        } else {
-            Self::usage_to_char_109(event.usage(), event.modifier())
-        }
+    pub fn key_layout() -> KeyLayout {
+        Self::shared().key_layout.value()
     }
 
-    #[allow(dead_code)]
-    fn usage_to_char_101(usage: Usage, modifier: Modifier) -> char {
-        let mut uni: char = INVALID_UNICHAR;
-
-        if usage >= Usage::ALPHABET_MIN && usage <= Usage::ALPHABET_MAX {
-            uni = (usage.0 - Usage::KEY_A.0 + 0x61) as char;
-            if modifier.has_shift() {
-                uni = (uni as u8 ^ 0x20) as char;
-            }
-        } else if usage >= Usage::NUMBER_MIN && usage <= Usage::NON_ALPHABET_MAX {
-            if modifier.has_shift() {
-                uni = USAGE_TO_CHAR_NON_ALPLABET_101_S[(usage.0 - Usage::NUMBER_MIN.0) as usize];
-            } else {
-                uni = USAGE_TO_CHAR_NON_ALPLABET_101[(usage.0 - Usage::NUMBER_MIN.0) as usize];
-            }
-        } else if usage == Usage::DELETE {
-            uni = '\x7F';
-        } else if usage >= Usage::NUMPAD_MIN && usage <= Usage::NUMPAD_MAX {
-            uni = USAGE_TO_CHAR_NUMPAD[(usage.0 - Usage::NUMPAD_MIN.0) as usize];
-        }
-
-        if uni >= '\x40' && uni < '\x7F' {
-            if modifier.has_ctrl() {
-                uni = (uni as u8 & 0x1F) as char;
-            }
-        }
-
-        uni
+    #[inline]
+    pub fn set_key_layout(layout: KeyLayout) {
+        let shared = Self::shared();
+        shared.key_layout.store(layout);
+        shared.dead_key.store(0, Ordering::SeqCst);
     }
 
-    #[allow(dead_code)]
-    fn usage_to_char_109(usage: Usage, modifier: Modifier) -> char {
-        let mut uni: char = INVALID_UNICHAR;
-
-        if usage >= Usage::ALPHABET_MIN && usage <= Usage::ALPHABET_MAX {
-            uni = (usage.0 - Usage::KEY_A.0 + 0x61) as char;
-        } else if usage >= Usage::NUMBER_MIN && usage <= Usage::NON_ALPHABET_MAX {
-            uni = USAGE_TO_CHAR_NON_ALPLABET_109[(usage.0 - Usage::NUMBER_MIN.0) as usize];
-            if uni > ' ' && uni < '\x40' && uni != '0' && modifier.has_shift() {
-                uni = (uni as u8 ^ 0x10) as char;
-            }
-        } else if usage == Usage::DELETE {
-            uni = '\x7F';
-        } else if usage >= Usage::NUMPAD_MIN && usage <= Usage::NUMPAD_MAX {
-            uni = USAGE_TO_CHAR_NUMPAD[(usage.0 - Usage::NUMPAD_MIN.0) as usize];
-        } else if usage == Usage::INTERNATIONAL_3 {
-            // '\|'
-            uni = '\\';
+    /// Converts the key event to a character with the current layout.
+    ///
+    /// Dead keys are stateful, so this should be called only once for each key event.
+    fn key_event_to_char(event: KeyEvent) -> char {
+        if event.flags().contains(KeyEventFlags::BREAK) || event.usage() == Usage::NONE {
+            return '\0';
         }
-
-        if uni >= '\x40' && uni < '\x7F' {
-            if modifier.has_ctrl() {
-                uni = (uni as u8 & 0x1F) as char;
-            } else if modifier.has_shift() {
-                uni = (uni as u8 ^ 0x20) as char;
+        let shared = Self::shared();
+        let layout = shared.key_layout.value();
+
+        if let Some(accent) = layout.dead_key(event.usage(), event.modifier()) {
+            let prev = shared.dead_key.swap(accent as u32, Ordering::SeqCst);
+            if prev == accent as u32 {
+                // Pressing the same dead key twice produces the accent itself
+                shared.dead_key.store(0, Ordering::SeqCst);
+                return accent;
             }
+            return INVALID_UNICHAR;
         }
 
-        if usage == Usage::INTERNATIONAL_1 {
-            if modifier.has_shift() {
-                uni = '_';
-            } else {
-                uni = '\\';
-            }
+        let uni = layout.usage_to_char(event.usage(), event.modifier());
+        if uni == INVALID_UNICHAR {
+            return uni;
+        }
+        match char::from_u32(shared.dead_key.swap(0, Ordering::SeqCst)) {
+            Some('\0') | None => uni,
+            Some(accent) => KeyLayout::compose(accent, uni).unwrap_or(uni),
         }
-
-        uni
     }
 }
 
-// Non Alphabet
-static USAGE_TO_CHAR_NON_ALPLABET_101: [char; 27] = [
-    '1', '2', '3', '4', '5', '6', '7', '8', '9', '0', '\x0D', '\x1B', '\x08', '\x09', ' ', '-',
-    '+', '[', ']', '\\', '\\', ';', '\'', '`', ',', '.', '/',
-];
-
-static USAGE_TO_CHAR_NON_ALPLABET_101_S: [char; 27] = [
-    '!', '@', '#', '$', '%', '^', '&', '*', '(', ')', '\x0D', '\x1B', '\x08', '\x09', ' ', '_',
-    '=', '{', '}', '|', '|', ':', '"', '~', '<', '>', '?',
-];
-
-static USAGE_TO_CHAR_NON_ALPLABET_109: [char; 27] = [
-    '1', '2', '3', '4', '5', '6', '7', '8', '9', '0', '\x0D', '\x1B', '\x08', '\x09', ' ', '-',
-    '^', '@', '[', ']', ']', ';', ':', '`', ',', '.', '/',
-];
-
-// Numpads
-static USAGE_TO_CHAR_NUMPAD: [char; 16] = [
-    '/', '*', '-', '+', '\x0D', '1', '2', '3', '4', '5', '6', '7', '8', '9', '0', '.',
-];
-
 #[derive(Debug, Clone, Copy)]
 pub enum HidBitStreamError {
     InvalidParameter,
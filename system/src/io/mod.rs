@@ -1,6 +1,8 @@
+pub use megstd::io::ansi;
 pub mod audio;
 pub mod hid_mgr;
 pub mod image;
+pub mod net;
 pub mod screen;
 pub mod tty;
 
@@ -0,0 +1,192 @@
+//! Network API
+
+use crate::{
+    sync::{fifo::EventQueue, semaphore::Semaphore, RwLock},
+    task::scheduler::{Priority, SpawnOption},
+    *,
+};
+use core::{
+    mem::MaybeUninit,
+    sync::atomic::{AtomicBool, Ordering},
+};
+pub use megstd::net::{
+    copy_frame, reply_arp, reply_icmp_echo, EtherType, Ipv4Address, MacAddress, NetError,
+    ETHER_HEADER_SIZE,
+};
+use megstd::{Arc, Vec};
+
+static mut NET_MANAGER: MaybeUninit<NetManager> = MaybeUninit::uninit();
+
+/// Network interface controllers that send and receive raw ethernet frames
+pub trait NetDevice {
+    /// Returns the MAC address of the interface.
+    fn mac_address(&self) -> MacAddress;
+
+    /// Sends an ethernet frame without the FCS.
+    fn send_frame(&self, frame: &[u8]) -> Result<(), NetError>;
+
+    /// Receives an ethernet frame into the buffer and returns its length.
+    ///
+    /// Returns `None` if there are no frames received.
+    /// Returns `Some(Err(NetError::InvalidLength))` and discards the frame
+    /// if the buffer is too short.
+    fn recv_frame(&self, buffer: &mut [u8]) -> Option<Result<usize, NetError>>;
+}
+
+/// Index of the network device registered in [`NetManager`]
+#[repr(transparent)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct NetDeviceIndex(pub usize);
+
+/// An ethernet frame received by the device
+pub struct NetFrame {
+    pub device: NetDeviceIndex,
+    pub data: Vec<u8>,
+}
+
+pub struct NetManager {
+    devices: RwLock<Vec<Arc<dyn NetDevice>>>,
+    rx_queue: EventQueue<NetFrame>,
+    rx_signal: Semaphore,
+    rx_thread_started: AtomicBool,
+    ipv4_address: RwLock<Option<Ipv4Address>>,
+}
+
+impl NetManager {
+    /// Maximum length of the ethernet frame without the FCS
+    pub const MAX_FRAME_SIZE: usize = megstd::net::MAX_FRAME_SIZE;
+    /// Minimum length of the ethernet frame without the FCS
+    pub const MIN_FRAME_SIZE: usize = megstd::net::MIN_FRAME_SIZE;
+
+    const RX_QUEUE_SIZE: usize = 64;
+
+    #[inline]
+    pub unsafe fn init() {
+        assert_call_once!();
+
+        NET_MANAGER = MaybeUninit::new(Self::new());
+    }
+
+    #[inline]
+    fn new() -> Self {
+        Self {
+            devices: RwLock::new(Vec::new()),
+            rx_queue: EventQueue::new(Self::RX_QUEUE_SIZE),
+            rx_signal: Semaphore::new(0),
+            rx_thread_started: AtomicBool::new(false),
+            ipv4_address: RwLock::new(None),
+        }
+    }
+
+    #[inline]
+    fn shared<'a>() -> &'a Self {
+        unsafe { &*NET_MANAGER.assume_init_ref() }
+    }
+
+    /// Registers the device, and starts the receiving thread for the first device.
+    pub fn register_device(device: Arc<dyn NetDevice>) -> NetDeviceIndex {
+        let shared = Self::shared();
+        let index = {
+            let mut devices = shared.devices.write().unwrap();
+            let index = NetDeviceIndex(devices.len());
+            devices.push(device);
+            index
+        };
+        if !shared.rx_thread_started.swap(true, Ordering::SeqCst) {
+            SpawnOption::with_priority(Priority::High).start(Self::_rx_thread, 0, "Net Manager");
+        }
+        index
+    }
+
+    /// Notifies that some frames may have been received.
+    ///
+    /// Devices call this from their interrupt handlers.
+    #[inline]
+    pub fn notify_rx() {
+        Self::shared().rx_signal.signal();
+    }
+
+    #[inline]
+    pub fn device(index: NetDeviceIndex) -> Option<Arc<dyn NetDevice>> {
+        Self::shared().devices.read().unwrap().get(index.0).cloned()
+    }
+
+    #[inline]
+    pub fn devices() -> Vec<Arc<dyn NetDevice>> {
+        Self::shared().devices.read().unwrap().clone()
+    }
+
+    /// Returns the IPv4 address of this host, if configured.
+    #[inline]
+    pub fn ipv4_address() -> Option<Ipv4Address> {
+        *Self::shared().ipv4_address.read().unwrap()
+    }
+
+    /// Sets the IPv4 address to answer ARP requests and ICMP echo requests.
+    #[inline]
+    pub fn set_ipv4_address(address: Option<Ipv4Address>) {
+        *Self::shared().ipv4_address.write().unwrap() = address;
+    }
+
+    /// Returns the reply frame if the frame is a request that this host answers.
+    pub fn reply_to(frame: &[u8], mac: MacAddress, ip: Ipv4Address) -> Option<Vec<u8>> {
+        match EtherType::from_frame(frame)? {
+            EtherType::ARP => reply_arp(frame, mac, ip),
+            EtherType::IPV4 => reply_icmp_echo(frame, mac, ip),
+            _ => None,
+        }
+    }
+
+    /// Sends the frame via the device.
+    pub fn send_frame(index: NetDeviceIndex, frame: &[u8]) -> Result<(), NetError> {
+        if frame.len() > Self::MAX_FRAME_SIZE {
+            return Err(NetError::InvalidLength);
+        }
+        Self::device(index)
+            .ok_or(NetError::NoDevice)?
+            .send_frame(frame)
+    }
+
+    /// Returns the received frame if any.
+    #[inline]
+    pub fn recv_frame() -> Option<NetFrame> {
+        Self::shared().rx_queue.get_event()
+    }
+
+    /// Waits for a frame to be received.
+    #[inline]
+    pub fn wait_frame() -> NetFrame {
+        Self::shared().rx_queue.wait_event()
+    }
+
+    /// Waits for the notifications and queues the received frames
+    fn _rx_thread(_: usize) {
+        let shared = Self::shared();
+        let mut buffer = Vec::new();
+        buffer.resize(Self::MAX_FRAME_SIZE, 0);
+        loop {
+            shared.rx_signal.wait();
+            let devices = Self::devices();
+            for (index, device) in devices.iter().enumerate() {
+                while let Some(result) = device.recv_frame(&mut buffer) {
+                    // Frames that do not fit are dropped
+                    let Ok(len) = result else {
+                        continue;
+                    };
+                    let reply = Self::ipv4_address()
+                        .and_then(|ip| Self::reply_to(&buffer[..len], device.mac_address(), ip));
+                    if let Some(reply) = reply {
+                        let _ = device.send_frame(&reply);
+                        continue;
+                    }
+                    let frame = NetFrame {
+                        device: NetDeviceIndex(index),
+                        data: buffer[..len].to_vec(),
+                    };
+                    // Frames are dropped when the queue is full
+                    let _ = shared.rx_queue.post(frame);
+                }
+            }
+        }
+    }
+}
@@ -59,6 +59,25 @@ where
         let _ = value;
         Err(self.orientation())
     }
+
+    /// Sets the image of the hardware cursor, if supported.
+    ///
+    /// Returns `false` if the screen does not have a hardware cursor.
+    fn set_hardware_cursor(&self, bitmap: &T, hotspot: Movement) -> bool {
+        let _ = bitmap;
+        let _ = hotspot;
+        false
+    }
+
+    /// Moves the hotspot of the hardware cursor to the point.
+    fn move_hardware_cursor(&self, point: Point) {
+        let _ = point;
+    }
+
+    /// Shows or hides the hardware cursor.
+    fn set_hardware_cursor_visible(&self, visible: bool) {
+        let _ = visible;
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
@@ -8,6 +8,7 @@ use core::{
     pin::Pin,
     task::{Context, Poll},
 };
+pub use megstd::io::ansi::GlyphSet;
 
 pub trait TtyWrite: Write {
     fn reset(&mut self) -> Result<(), TtyError>;
@@ -27,6 +28,15 @@ pub trait TtyWrite: Write {
     fn attributes(&self) -> u8 {
         0
     }
+
+    fn glyph_set(&self) -> GlyphSet {
+        GlyphSet::Unicode
+    }
+
+    /// Changes the glyph set and returns the previous one.
+    fn set_glyph_set(&mut self, _glyph_set: GlyphSet) -> GlyphSet {
+        self.glyph_set()
+    }
 }
 
 pub trait TtyRead {
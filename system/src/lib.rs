@@ -45,7 +45,10 @@ pub use crate::hal::*;
 use crate::system::System;
 use bootprot::*;
 use core::{fmt::Write, panic::PanicInfo};
-use megstd::Box;
+use megstd::{
+    backtrace::{FrameWalker, SymbolTable},
+    Box,
+};
 
 extern crate alloc;
 
@@ -72,40 +75,83 @@ macro_rules! log {
 }
 
 static PANIC_GLOBAL_LOCK: Spinlock = Spinlock::new();
+const PANIC_LOCK_RETRIES: usize = 0x0100_0000;
+/// Symbol table generated by `nm -n` from the kernel, embedded in the initrd if present
+const KERNEL_SYMBOLS: &str = "kernel.sym";
 
 #[panic_handler]
 fn panic(info: &PanicInfo) -> ! {
+    // The state of the panic handler itself, captured before it is disturbed any further
+    #[cfg(target_arch = "x86_64")]
+    let regs = arch::gpr::Registers::capture();
     unsafe {
         Hal::cpu().disable_interrupt();
         task::scheduler::Scheduler::freeze(true);
-        PANIC_GLOBAL_LOCK.synchronized(|| {
-            let stdout = System::log();
-            stdout.set_attribute(0x4F);
-            if let Some(thread) = task::scheduler::Scheduler::current_thread() {
-                if let Some(name) = thread.name() {
-                    let _ = write!(stdout, "thread '{}' ", name);
-                } else {
-                    let _ = write!(stdout, "thread {} ", thread.as_usize());
-                }
+        // Do not wait forever, a nested panic would be reported as a deadlock again
+        let mut is_locked = false;
+        for _ in 0..PANIC_LOCK_RETRIES {
+            if PANIC_GLOBAL_LOCK.try_lock() {
+                is_locked = true;
+                break;
             }
-            let _ = writeln!(stdout, "{}", info);
+            Hal::cpu().spin_loop_hint();
+        }
+
+        let stdout = System::log();
+        stdout.set_attribute(0x4F);
+        if let Some(thread) = task::scheduler::Scheduler::current_thread() {
+            if let Some(name) = thread.name() {
+                let _ = write!(stdout, "thread '{}' ", name);
+            } else {
+                let _ = write!(stdout, "thread {} ", thread.as_usize());
+            }
+        }
+        let _ = writeln!(stdout, "{}", info);
+
+        // Requires the kernel to be compiled with frame pointers
+        let symbols = res::ResourceBundle::shared()
+            .load(KERNEL_SYMBOLS)
+            .and_then(SymbolTable::from_bytes);
+        // A broken frame pointer must not cause another fault, so only the stack is read
+        let stack = task::scheduler::Scheduler::current_thread().and_then(|v| v.stack_range());
+        let walker = FrameWalker::new(Hal::cpu().frame_pointer(), |va| {
+            let stack = stack.as_ref()?;
+            va.checked_add(core::mem::size_of::<usize>())
+                .is_some_and(|end| stack.start <= va && end <= stack.end)
+                .then(|| (va as *const usize).read_volatile())
         });
-        Hal::cpu().stop();
-    }
-}
+        let _ = writeln!(stdout, "Guru Meditation:");
+        #[cfg(target_arch = "x86_64")]
+        let _ = writeln!(stdout, "{:?}", regs);
+        for (index, address) in walker.enumerate() {
+            let _ = match symbols.and_then(|v| v.lookup(address)) {
+                Some(symbol) => writeln!(stdout, "  #{:<2} {:016x} {}", index, address, symbol),
+                None => writeln!(stdout, "  #{:<2} {:016x}", index, address),
+            };
+        }
 
-#[repr(transparent)]
-pub struct HexDump<'a>(pub &'a [u8]);
+        if is_locked {
+            PANIC_GLOBAL_LOCK.force_unlock();
+        }
 
-impl core::fmt::Debug for HexDump<'_> {
-    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
-        for c in self.0.iter() {
-            write!(f, " {:02x}", *c)?;
+        let action = System::panic_action();
+        if let system::PanicAction::Reboot(delay) = action {
+            let _ = writeln!(stdout, "Rebooting in {} seconds...", delay.as_secs());
         }
-        Ok(())
+        action.reset_after_delay(
+            || {
+                use task::scheduler::Timer;
+                Timer::is_available().then(Timer::monotonic)
+            },
+            || Hal::cpu().spin_loop_hint(),
+            || Hal::cpu().reset(),
+        );
+        Hal::cpu().stop();
     }
 }
 
+pub use megstd::string::HexDump;
+
 // like bitflags
 #[macro_export]
 macro_rules! my_bitflags {
@@ -1,7 +1,12 @@
 //! Log Event Manager
 
-use crate::{r, sync::fifo::AsyncEventQueue, system::System, *};
-use core::{fmt::Write, mem::MaybeUninit, pin::Pin};
+use crate::{
+    r,
+    sync::{fifo::AsyncEventQueue, Once},
+    system::System,
+    *,
+};
+use core::{fmt::Write, pin::Pin};
 use futures_util::Future;
 use megstd::{Box, String, ToString};
 
@@ -31,7 +36,7 @@ impl Write for Log {
     }
 }
 
-static mut EVENT_MANAGER: MaybeUninit<EventManager> = MaybeUninit::uninit();
+static EVENT_MANAGER: Once<EventManager> = Once::new();
 
 pub struct EventManager {
     message_queue: AsyncEventQueue<SimpleMessagePayload>,
@@ -47,14 +52,12 @@ impl EventManager {
     pub(crate) fn init() {
         assert_call_once!();
 
-        unsafe {
-            EVENT_MANAGER.write(Self::new());
-        }
+        let _ = EVENT_MANAGER.set(Self::new());
     }
 
     #[inline]
     fn shared<'a>() -> &'a Self {
-        unsafe { &*EVENT_MANAGER.as_ptr() }
+        EVENT_MANAGER.get().unwrap()
     }
 
     pub fn system_log(s: &str) {
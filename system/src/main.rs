@@ -7,12 +7,21 @@
 
 extern crate alloc;
 use bootprot::*;
-use core::{fmt, fmt::Write, num::NonZeroU8};
+use core::{fmt, fmt::Write, num::NonZeroU8, time::Duration};
 use kernel::{
-    drivers::pci, drivers::usb, fs::OpenOptions, fs::*, mem::*, rt::*, system::*,
-    task::scheduler::*, ui::window::WindowManager, user::userenv::UserEnv, *,
+    drivers::pci, drivers::usb, fs::OpenOptions, fs::*, io::hid_mgr::HidManager, io::net::*,
+    mem::*, rt::*, system::*, task::scheduler::*, task::watchdog::*, task::Channel,
+    ui::window::WindowManager, user::userenv::UserEnv, *,
+};
+use megstd::{
+    drawing::encode_bmp,
+    io::keylayout::KeyLayout,
+    io::ErrorKind,
+    io::Read,
+    io::Write as _,
+    shell::{self, CmdLineError, ShellEnv},
+    Arc, String, ToOwned, ToString, Vec,
 };
-use megstd::{io::Read, String, ToOwned, ToString, Vec};
 
 /// Kernel entry point
 #[no_mangle]
@@ -24,17 +33,18 @@ static mut MAIN: Shell = Shell::new();
 
 pub struct Shell {
     path_ext: Vec<String>,
-}
-
-enum ParsedCmdLine {
-    Empty,
-    InvalidQuote,
+    env: ShellEnv,
+    watchdog: Option<Arc<Watchdog>>,
 }
 
 impl Shell {
+    const WATCHDOG_TIMEOUT: Duration = Duration::from_secs(10);
+
     const fn new() -> Self {
         Self {
             path_ext: Vec::new(),
+            env: ShellEnv::new(),
+            watchdog: None,
         }
     }
 
@@ -50,9 +60,22 @@ impl Shell {
         }
 
         Scheduler::spawn_async(Self::repl_main());
+        Scheduler::spawn_async(Self::repl_heartbeat());
         Scheduler::perform_tasks();
     }
 
+    /// Pets the watchdog while the tasks of the REPL thread are running
+    ///
+    /// The watchdog is suspended while the REPL thread waits for a child process.
+    async fn repl_heartbeat() {
+        let watchdog = Watchdog::new(Self::WATCHDOG_TIMEOUT, WatchdogAction::Log);
+        Self::shared().watchdog = Some(watchdog.clone());
+        loop {
+            watchdog.pet();
+            Timer::sleep_async(Self::WATCHDOG_TIMEOUT / 4).await;
+        }
+    }
+
     async fn repl_main() {
         loop {
             print!("# ");
@@ -63,10 +86,31 @@ impl Shell {
     }
 
     fn exec_cmd(cmdline: &str) {
+        match shell::split_pipeline(cmdline) {
+            Ok(stages) => {
+                if stages.len() > 1 {
+                    Self::shared()
+                        .env
+                        .set_last_status(Self::exec_pipeline(stages.as_slice()));
+                    return;
+                }
+            }
+            Err(CmdLineError::Empty) => return,
+            Err(CmdLineError::InvalidQuote) => {
+                println!("Error: Invalid quote");
+                return;
+            }
+            Err(CmdLineError::InvalidPipe) => {
+                println!("Error: Invalid pipe");
+                return;
+            }
+        }
+
         match Self::parse_cmd(cmdline) {
             Ok((cmd, args)) => {
                 let name = cmd.as_str();
                 let mut args = args.iter().map(|v| v.as_str()).collect::<Vec<&str>>();
+                let mut status = 0;
                 match name {
                     "clear" | "cls" | "reset" => System::stdout().reset().unwrap(),
                     "exit" => println!("Feature not available"),
@@ -101,8 +145,7 @@ impl Shell {
                         let time_m = (sec / 60) % 60;
                         let time_h = (sec / 3600) % 24;
 
-                        let uptime = Timer::monotonic();
-                        let sec = uptime.as_secs();
+                        let sec = System::uptime().as_secs();
                         let upt_s = sec % 60;
                         let upt_m = (sec / 60) % 60;
                         let upt_h = (sec / 3600) % 24;
@@ -128,7 +171,7 @@ impl Shell {
                     "open" | "ncst" => {
                         let args = &args[1..];
                         let name = args[0];
-                        Self::spawn(name, args, false);
+                        status = Self::spawn(name, args, false);
                     }
                     _ => match Self::command(name) {
                         Some(exec) => {
@@ -137,123 +180,128 @@ impl Shell {
                         None => {
                             if args.len() > 1 && args.last() == Some(&"&") {
                                 args.remove(args.len() - 1);
-                                Self::spawn(name, args.as_slice(), false);
+                                status = Self::spawn(name, args.as_slice(), false);
                             } else {
-                                Self::spawn(name, args.as_slice(), true);
+                                status = Self::spawn(name, args.as_slice(), true);
                             }
                         }
                     },
                 }
+                Self::shared().env.set_last_status(status);
             }
-            Err(ParsedCmdLine::Empty) => (),
-            Err(ParsedCmdLine::InvalidQuote) => {
+            Err(CmdLineError::Empty) => (),
+            Err(CmdLineError::InvalidQuote) => {
                 println!("Error: Invalid quote");
             }
+            Err(CmdLineError::InvalidPipe) => {
+                println!("Error: Invalid pipe");
+            }
         }
     }
 
-    fn parse_cmd(cmdline: &str) -> Result<(String, Vec<String>), ParsedCmdLine> {
-        enum CmdLinePhase {
-            SkippingSpace,
-            Token,
-            SingleQuote,
-            DoubleQuote,
-        }
-
-        if cmdline.len() == 0 {
-            return Err(ParsedCmdLine::Empty);
+    /// Runs all stages of the pipeline concurrently and waits for the last one.
+    ///
+    /// The exit status is that of the last stage.
+    fn exec_pipeline(stages: &[&str]) -> i32 {
+        let mut commands = Vec::with_capacity(stages.len());
+        for stage in stages {
+            match Self::parse_cmd(stage) {
+                Ok((cmd, args)) => commands.push((cmd, args)),
+                Err(_) => {
+                    println!("Error: Invalid pipe");
+                    return 1;
+                }
+            }
         }
 
-        let mut sb = String::new();
-        let mut args = Vec::new();
-        let mut phase = CmdLinePhase::SkippingSpace;
-        for c in cmdline.chars() {
-            match phase {
-                CmdLinePhase::SkippingSpace => match c {
-                    ' ' | '\t' | '\r' | '\n' => (),
-                    '\'' => {
-                        phase = CmdLinePhase::SingleQuote;
-                    }
-                    '\"' => {
-                        phase = CmdLinePhase::DoubleQuote;
-                    }
-                    _ => {
-                        sb.write_char(c).unwrap();
-                        phase = CmdLinePhase::Token;
-                    }
-                },
-                CmdLinePhase::Token => match c {
-                    ' ' | '\t' | '\r' | '\n' => {
-                        args.push(sb);
-                        phase = CmdLinePhase::SkippingSpace;
-                        sb = String::new();
-                    }
-                    _ => {
-                        sb.write_char(c).unwrap();
-                    }
-                },
-                CmdLinePhase::SingleQuote => match c {
-                    '\'' => {
-                        args.push(sb);
-                        phase = CmdLinePhase::SkippingSpace;
-                        sb = String::new();
+        let last_index = commands.len() - 1;
+        let mut last_child = None;
+        let mut stdin = None;
+        for (index, (cmd, args)) in commands.iter().enumerate() {
+            let argv = args.iter().map(|v| v.as_str()).collect::<Vec<&str>>();
+            let stdout = (index < last_index).then(|| Channel::new(Channel::DEFAULT_CAPACITY));
+            let stdin_pipe: Option<Arc<Channel>> = stdin.take();
+            match Self::spawn_piped(cmd, argv.as_slice(), stdin_pipe.clone(), stdout.clone()) {
+                Ok(child) => last_child = Some(child),
+                Err(status) => {
+                    // The other stages will see the end of the stream
+                    if let Some(stdin_pipe) = stdin_pipe {
+                        stdin_pipe.close();
                     }
-                    _ => {
-                        sb.write_char(c).unwrap();
+                    if let Some(stdout) = stdout {
+                        stdout.close();
                     }
-                },
-                CmdLinePhase::DoubleQuote => match c {
-                    '\"' => {
-                        args.push(sb);
-                        phase = CmdLinePhase::SkippingSpace;
-                        sb = String::new();
-                    }
-                    _ => {
-                        sb.write_char(c).unwrap();
-                    }
-                },
+                    return status;
+                }
             }
+            stdin = stdout;
         }
-        match phase {
-            CmdLinePhase::SkippingSpace | CmdLinePhase::Token => (),
-            CmdLinePhase::SingleQuote | CmdLinePhase::DoubleQuote => {
-                return Err(ParsedCmdLine::InvalidQuote)
+
+        last_child.map(Self::join).unwrap_or(0)
+    }
+
+    fn parse_cmd(cmdline: &str) -> Result<(String, Vec<String>), CmdLineError> {
+        shell::parse_cmd(cmdline, &Self::shared().env, &user::home())
+    }
+
+    fn spawn(name: &str, argv: &[&str], wait_until: bool) -> i32 {
+        match Self::spawn_piped(name, argv, None, None) {
+            Ok(child) => {
+                if wait_until {
+                    Self::join(child)
+                } else {
+                    0
+                }
             }
-        }
-        if sb.len() > 0 {
-            args.push(sb);
-        }
-        if let Some(cmd) = args.get(0) {
-            Ok((cmd.to_owned(), args))
-        } else {
-            Err(ParsedCmdLine::Empty)
+            Err(status) => status,
         }
     }
 
-    fn spawn(name: &str, argv: &[&str], wait_until: bool) -> usize {
-        Self::spawn_main(name, argv, wait_until).unwrap_or_else(|| {
+    /// Waits for the child process without being reported as hung.
+    fn join(child: ChildProcess) -> i32 {
+        let Some(watchdog) = Self::shared().watchdog.clone() else {
+            return child.join();
+        };
+        watchdog.suspend();
+        let status = child.join();
+        watchdog.pet();
+        status
+    }
+
+    fn spawn_piped(
+        name: &str,
+        argv: &[&str],
+        stdin: Option<Arc<Channel>>,
+        stdout: Option<Arc<Channel>>,
+    ) -> Result<ChildProcess, i32> {
+        Self::spawn_main(name, argv, &stdin, &stdout).unwrap_or_else(|| {
             let mut sb = String::new();
             let shared = Self::shared();
             for ext in &shared.path_ext {
                 sb.clear();
                 write!(sb, "{}.{}", name, ext).unwrap();
-                match Self::spawn_main(sb.as_str(), argv, wait_until) {
+                match Self::spawn_main(sb.as_str(), argv, &stdin, &stdout) {
                     Some(v) => return v,
                     None => (),
                 }
             }
             println!("Command not found: {}", name);
-            1
+            Err(1)
         })
     }
 
-    fn spawn_main(name: &str, argv: &[&str], wait_until: bool) -> Option<usize> {
+    fn spawn_main(
+        name: &str,
+        argv: &[&str],
+        stdin: &Option<Arc<Channel>>,
+        stdout: &Option<Arc<Channel>>,
+    ) -> Option<Result<ChildProcess, i32>> {
         FileManager::open(name, OpenOptions::new().read(true))
             .map(|mut fcb| {
                 let stat = fcb.fstat().unwrap();
                 if !stat.file_type().is_file() {
                     println!("permission denied: {}", name);
-                    return 1;
+                    return Err(1);
                 }
                 let file_size = stat.len() as usize;
                 if file_size > 0 {
@@ -262,39 +310,36 @@ impl Shell {
                         Ok(_v) => (),
                         Err(err) => {
                             println!("{}: File read error {:?}", name, err);
-                            return 1;
+                            return Err(1);
                         }
                     };
                     let blob = vec.as_slice();
                     if let Some(mut loader) = RuntimeEnvironment::recognize(blob) {
                         loader.option().name = name.to_string();
                         loader.option().argv = argv.iter().map(|v| v.to_string()).collect();
+                        loader.option().stdin = stdin.clone();
+                        loader.option().stdout = stdout.clone();
+                        loader.option().env = Self::shared().env.vars().clone();
                         match loader.load(blob) {
-                            Ok(_) => {
-                                let child = loader.invoke_start();
-                                if wait_until {
-                                    child.map(|thread| thread.join());
-                                }
-                            }
+                            Ok(_) => loader.invoke_start().ok_or(1),
                             Err(_) => {
                                 println!("Load error");
-                                return 1;
+                                Err(1)
                             }
                         }
                     } else {
                         println!("Bad executable");
-                        return 1;
+                        Err(1)
                     }
                 } else {
                     unreachable!()
                 }
-                0
             })
             .ok()
     }
 
     fn command(cmd: &str) -> Option<&'static fn(&[&str]) -> ()> {
-        for command in &Self::COMMAND_TABLE {
+        for command in Self::COMMAND_TABLE {
             if command.0 == cmd {
                 return Some(&command.1);
             }
@@ -302,39 +347,68 @@ impl Shell {
         None
     }
 
-    const COMMAND_TABLE: [(&'static str, fn(&[&str]) -> (), &'static str); 17] = [
+    const COMMAND_TABLE: &'static [(&'static str, fn(&[&str]) -> (), &'static str)] = &[
         ("cd", Self::cmd_cd, ""),
         ("mkdir", Self::cmd_mkdir, ""),
         ("rm", Self::cmd_rm, ""),
         ("mv", Self::cmd_mv, ""),
+        ("cp", Self::cmd_cp, ""),
         ("touch", Self::cmd_touch, ""),
         ("pwd", Self::cmd_pwd, ""),
+        ("set", Self::cmd_set, "Set environment variable"),
+        ("unset", Self::cmd_unset, ""),
         ("ls", Self::cmd_ls, "Show directory"),
         ("cat", Self::cmd_cat, "Show file"),
         ("dir", Self::cmd_ls, ""),
         ("type", Self::cmd_cat, ""),
         ("stat", Self::cmd_stat, ""),
         ("mount", Self::cmd_mount, ""),
+        ("screenshot", Self::cmd_screenshot, "Save screen as BMP"),
         ("ps", Self::cmd_ps, ""),
         ("lspci", Self::cmd_lspci, "Show List of PCI Devices"),
         ("lsusb", Self::cmd_lsusb, "Show List of USB Devices"),
+        ("ifconfig", Self::cmd_ifconfig, "Network Configuration"),
         ("sysctl", Self::cmd_sysctl, "System Control"),
+        ("setxkb", Self::cmd_setxkb, "Set keyboard layout"),
         ("help", Self::cmd_help, ""),
     ];
 
     fn cmd_help(_: &[&str]) {
-        for cmd in &Self::COMMAND_TABLE {
+        for cmd in Self::COMMAND_TABLE {
             if cmd.2.len() > 0 {
                 println!("{}\t{}", cmd.0, cmd.2);
             }
         }
     }
 
+    fn cmd_set(argv: &[&str]) {
+        let shared = Self::shared();
+        if argv.len() < 2 {
+            for (key, value) in shared.env.vars() {
+                println!("{}={}", key, value);
+            }
+            return;
+        }
+        for arg in &argv[1..] {
+            if !shared.env.set(arg) {
+                println!("usage: set NAME=value");
+            }
+        }
+    }
+
+    fn cmd_unset(argv: &[&str]) {
+        let shared = Self::shared();
+        for key in &argv[1..] {
+            shared.env.unset(key);
+        }
+    }
+
     fn cmd_cd(argv: &[&str]) {
-        match FileManager::chdir(argv.get(1).unwrap_or(&"/")) {
+        let path = argv.get(1).unwrap_or(&"/");
+        match FileManager::chdir(path) {
             Ok(_) => (),
             Err(err) => {
-                println!("{:?}", err.kind());
+                println!("cd: {}: {}", path, err);
             }
         }
     }
@@ -359,16 +433,24 @@ impl Shell {
     }
 
     fn cmd_rm(argv: &[&str]) {
-        let mut argv = argv.iter();
+        let mut argv = argv.iter().peekable();
         let arg0 = unsafe { argv.next().unwrap_unchecked() };
 
+        let recursive = argv.next_if(|&&v| v == "-r").is_some();
         if argv.len() < 1 {
-            println!("usage: {} file", arg0);
+            println!("usage: {} [-r] file", arg0);
             return;
         };
 
         for path in argv {
-            match FileManager::unlink(path) {
+            let result = if recursive
+                && FileManager::stat(path).is_ok_and(|stat| stat.file_type().is_dir())
+            {
+                FileManager::remove_dir_all(path)
+            } else {
+                FileManager::unlink(path)
+            };
+            match result {
                 Ok(_) => (),
                 Err(err) => {
                     println!("{}: {}: {:?}", arg0, path, err.kind());
@@ -396,6 +478,37 @@ impl Shell {
         }
     }
 
+    fn cmd_cp(argv: &[&str]) {
+        let mut argv = argv.iter().peekable();
+        let arg0 = unsafe { argv.next().unwrap_unchecked() };
+
+        let recursive = argv.next_if(|&&v| v == "-r").is_some();
+        if argv.len() < 2 {
+            println!("usage: {} [-r] source target", arg0);
+            return;
+        };
+
+        let src = argv.next().unwrap();
+        let dst = argv.next().unwrap();
+        let result = match FileManager::stat(src) {
+            Ok(stat) if stat.file_type().is_dir() => {
+                if recursive {
+                    FileManager::copy_dir_all(src, dst)
+                } else {
+                    Err(ErrorKind::IsADirectory.into())
+                }
+            }
+            Ok(_) => FileManager::copy(src, dst).map(|_| ()),
+            Err(err) => Err(err),
+        };
+        match result {
+            Ok(_) => (),
+            Err(err) => {
+                println!("{}: {} to {}: {:?}", arg0, src, dst, err.kind());
+            }
+        }
+    }
+
     fn cmd_touch(argv: &[&str]) {
         let mut argv = argv.iter();
         let arg0 = unsafe { argv.next().unwrap_unchecked() };
@@ -415,10 +528,83 @@ impl Shell {
         }
     }
 
+    fn cmd_screenshot(argv: &[&str]) {
+        let arg0 = argv[0];
+        let Some(path) = argv.get(1) else {
+            println!("usage: {} file", arg0);
+            return;
+        };
+
+        let bitmap = WindowManager::capture_screen();
+        let bmp = encode_bmp(bitmap.as_ref());
+
+        let mut file = match FileManager::creat(path) {
+            Ok(v) => v,
+            Err(err) => {
+                println!("{}: {}: {:?}", arg0, path, err.kind());
+                return;
+            }
+        };
+        let mut buf = bmp.as_slice();
+        while buf.len() > 0 {
+            match file.write(buf) {
+                Ok(0) => {
+                    println!("{}: {}: {:?}", arg0, path, ErrorKind::WriteZero);
+                    return;
+                }
+                Ok(size) => buf = &buf[size..],
+                Err(err) => {
+                    println!("{}: {}: {:?}", arg0, path, err.kind());
+                    return;
+                }
+            }
+        }
+    }
+
     fn cmd_pwd(_argv: &[&str]) {
         println!("{}", Scheduler::current_pid().cwd());
     }
 
+    fn cmd_setxkb(argv: &[&str]) {
+        let Some(name) = argv.get(1) else {
+            println!("{}", HidManager::key_layout().name());
+            return;
+        };
+        match KeyLayout::from_name(name) {
+            Some(layout) => HidManager::set_key_layout(layout),
+            None => {
+                print!("usage: setxkb [");
+                for (index, layout) in KeyLayout::ALL.iter().enumerate() {
+                    if index > 0 {
+                        print!(" | ");
+                    }
+                    print!("{}", layout.name());
+                }
+                println!("]");
+            }
+        }
+    }
+
+    fn cmd_ifconfig(argv: &[&str]) {
+        if let Some(arg) = argv.get(1) {
+            match arg.parse::<Ipv4Address>() {
+                Ok(address) => NetManager::set_ipv4_address(Some(address)),
+                Err(_) => {
+                    println!("usage: ifconfig [address]");
+                    return;
+                }
+            }
+        }
+
+        match NetManager::ipv4_address() {
+            Some(address) => println!("inet {}", address),
+            None => println!("inet (none)"),
+        }
+        for (index, device) in NetManager::devices().iter().enumerate() {
+            println!("net{}: ether {}", index, device.mac_address());
+        }
+    }
+
     fn cmd_sysctl(argv: &[&str]) {
         if argv.len() < 2 {
             println!("usage: sysctl command [options]");
@@ -1,2 +1,4 @@
 mod icons;
+mod registry;
 pub use icons::*;
+pub use registry::*;
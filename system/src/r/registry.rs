@@ -0,0 +1,30 @@
+//! Runtime registry of Identify types
+
+use crate::sync::RwLock;
+use core::any::Any;
+use megstd::{uuid::*, Arc};
+
+static REGISTRY: RwLock<IdentifyRegistry> = RwLock::new(IdentifyRegistry::new());
+
+/// Registers the component so that it can be looked up by its UUID.
+#[inline]
+pub fn register<T: Identify + Any + Send + Sync>(object: Arc<T>) {
+    REGISTRY.write().unwrap().register(object);
+}
+
+#[inline]
+pub fn unregister(uuid: &Uuid) -> Option<Arc<dyn Any + Send + Sync>> {
+    REGISTRY.write().unwrap().unregister(uuid)
+}
+
+/// Returns the component registered with the UUID.
+#[inline]
+pub fn lookup(uuid: &Uuid) -> Option<Arc<dyn Any + Send + Sync>> {
+    REGISTRY.read().unwrap().lookup(uuid)
+}
+
+/// Returns the component registered with the UUID of the type.
+#[inline]
+pub fn get<T: Identify + Any + Send + Sync>() -> Option<Arc<T>> {
+    REGISTRY.read().unwrap().get()
+}
@@ -0,0 +1,40 @@
+//! Resource Bundle
+
+use myos_archive::ArchiveReader;
+
+static mut RESOURCE_BUNDLE: ResourceBundle = ResourceBundle::empty();
+
+/// Resources stored in the initrd, identified by the logical name such as `fonts/small.fnt`
+pub struct ResourceBundle {
+    data: &'static [u8],
+}
+
+impl ResourceBundle {
+    #[inline]
+    const fn empty() -> Self {
+        Self { data: &[] }
+    }
+
+    /// Creates a bundle from the MAR archive.
+    #[inline]
+    pub const fn from_slice(data: &'static [u8]) -> Self {
+        Self { data }
+    }
+
+    pub(crate) unsafe fn init(initrd_base: *const u8, initrd_size: usize) {
+        assert_call_once!();
+
+        RESOURCE_BUNDLE = Self::from_slice(core::slice::from_raw_parts(initrd_base, initrd_size));
+    }
+
+    #[inline]
+    pub fn shared() -> &'static Self {
+        unsafe { &*core::ptr::addr_of!(RESOURCE_BUNDLE) }
+    }
+
+    /// Returns the content of the resource, or `None` if it does not exist.
+    #[inline]
+    pub fn load(&self, name: &str) -> Option<&'static [u8]> {
+        ArchiveReader::from_slice(self.data).ok()?.find(name)
+    }
+}
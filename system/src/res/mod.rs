@@ -1,3 +1,17 @@
 //! Resource Manager
 
+mod bundle;
 pub mod icon;
+pub use bundle::*;
+
+/// Returns the resource bundle in the initrd.
+#[inline]
+pub fn bundle() -> &'static ResourceBundle {
+    ResourceBundle::shared()
+}
+
+/// Returns the content of the resource in the initrd, or `None` if it does not exist.
+#[inline]
+pub fn load(name: &str) -> Option<&'static [u8]> {
+    bundle().load(name)
+}
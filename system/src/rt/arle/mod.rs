@@ -1,6 +1,9 @@
 //! Arlequin Subsystem
 
 use super::*;
+use crate::mem::MemoryManager;
+use core::{alloc::Layout, mem::size_of, slice};
+use myelf::*;
 // use megstd::*;
 
 /// Recognize .bin file
@@ -38,9 +41,15 @@ pub struct ArleLoader {
 pub struct LaunchOption {
     start: usize,
     stack_pointer: usize,
+    thread_pointer: usize,
 }
 
 impl ArleLoader {
+    #[cfg(target_arch = "x86_64")]
+    const MACHINE: Machine = EM_X86_64;
+    #[cfg(target_arch = "aarch64")]
+    const MACHINE: Machine = EM_AARCH64;
+
     #[inline]
     fn new(option: LaunchOption) -> Self {
         Self {
@@ -50,11 +59,8 @@ impl ArleLoader {
     }
 
     pub fn identity(blob: &[u8]) -> Option<Self> {
-        if blob[0] == 0xC3 {
-            Some(Self::new(LaunchOption {
-                start: 0,
-                stack_pointer: 0,
-            }))
+        if ElfImage::parse(blob, Self::MACHINE).is_some() || blob.first() == Some(&0xC3) {
+            Some(Self::new(LaunchOption::default()))
         } else {
             None
         }
@@ -66,7 +72,11 @@ impl ArleLoader {
             .get::<ArleContext>()
             .unwrap();
         unsafe {
-            Hal::cpu().invoke_user(ctx.option.start, ctx.option.stack_pointer);
+            Hal::cpu().invoke_user(
+                ctx.option.start,
+                ctx.option.stack_pointer,
+                ctx.option.thread_pointer,
+            );
         }
     }
 }
@@ -76,11 +86,41 @@ impl BinaryLoader for ArleLoader {
         &mut self.lio
     }
 
-    fn load(&mut self, _blob: &[u8]) -> Result<(), ()> {
-        todo!()
+    fn load(&mut self, blob: &[u8]) -> Result<(), ()> {
+        let elf = ElfImage::parse(blob, Self::MACHINE).ok_or(())?;
+        unsafe {
+            let image_base = MemoryManager::zalloc(Layout::from_size_align_unchecked(
+                elf.image_size(),
+                MemoryManager::PAGE_SIZE_MIN,
+            ))
+            .ok_or(())?
+            .get();
+            let image = slice::from_raw_parts_mut(image_base as *mut u8, elf.image_size());
+            elf.load(image, image_base as u64).map_err(|_| ())?;
+            self.option.start = elf.entry(image_base as u64) as usize;
+
+            if let Some(tls) = elf.tls_template() {
+                // In the variant II, the thread pointer points to the end of the block,
+                // where the pointer to itself is stored.
+                let block_size = tls.block_size();
+                let layout = Layout::from_size_align(
+                    block_size + size_of::<usize>(),
+                    tls.align.max(size_of::<usize>()),
+                )
+                .map_err(|_| ())?;
+                let tls_base = MemoryManager::zalloc(layout).ok_or(())?.get();
+                let block = slice::from_raw_parts_mut(tls_base as *mut u8, block_size);
+                tls.init_block(image, elf.image_base(), block)
+                    .map_err(|_| ())?;
+                let thread_pointer = tls_base + block_size;
+                (thread_pointer as *mut usize).write(thread_pointer);
+                self.option.thread_pointer = thread_pointer;
+            }
+        }
+        Ok(())
     }
 
-    fn invoke_start(self: Box<Self>) -> Option<ProcessId> {
+    fn invoke_start(self: Box<Self>) -> Option<ChildProcess> {
         SpawnOption::new()
             .personality(ArleContext::new(self.option))
             .start_process(Self::start, 0, self.lio.name.as_str())
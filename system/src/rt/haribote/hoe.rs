@@ -657,7 +657,7 @@ impl BinaryLoader for HrbBinaryLoader {
         Ok(())
     }
 
-    fn invoke_start(self: Box<Self>) -> Option<ProcessId> {
+    fn invoke_start(self: Box<Self>) -> Option<ChildProcess> {
         let cmdline = self.lio.argv.join(" ");
         SpawnOption::new()
             .personality(Hoe::new(self.ctx, cmdline))
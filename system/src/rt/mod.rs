@@ -1,9 +1,14 @@
 //! Runtime Environment and Personalities
 
-use crate::{task::scheduler::*, *};
-use alloc::{boxed::Box, string::String, string::*, vec::Vec};
+use crate::{sync::RwLock, task::scheduler::*, task::Channel, *};
+use alloc::{
+    boxed::Box, collections::BTreeMap, string::String, string::*, sync::Arc, vec::Vec,
+};
 use core::{cell::UnsafeCell, ffi::c_void};
-use megstd::uuid::{Identify, Uuid};
+use megstd::{
+    exec::MagicRecognizers,
+    uuid::{Identify, Uuid},
+};
 
 pub mod arle;
 
@@ -17,6 +22,7 @@ static mut RE: UnsafeCell<RuntimeEnvironment> = UnsafeCell::new(RuntimeEnvironme
 pub struct RuntimeEnvironment {
     path_ext: Vec<String>,
     image_loaders: Vec<Box<dyn BinaryRecognizer>>,
+    magic_recognizers: RwLock<MagicRecognizers<Box<dyn BinaryLoader>>>,
 }
 
 impl RuntimeEnvironment {
@@ -25,6 +31,7 @@ impl RuntimeEnvironment {
         Self {
             path_ext: Vec::new(),
             image_loaders: Vec::new(),
+            magic_recognizers: RwLock::new(MagicRecognizers::new()),
         }
     }
 
@@ -58,9 +65,26 @@ impl RuntimeEnvironment {
         Self::shared().path_ext.iter()
     }
 
+    /// Registers a recognizer for an additional executable format.
+    ///
+    /// Registered recognizers are tried in the order of registration before the built-in ones.
+    pub fn register_recognizer(
+        magic_matcher: fn(&[u8]) -> bool,
+        loader_factory: fn() -> Box<dyn BinaryLoader>,
+    ) {
+        Self::shared()
+            .magic_recognizers
+            .write()
+            .unwrap()
+            .register(magic_matcher, loader_factory);
+    }
+
     #[inline]
     pub fn recognize(blob: &[u8]) -> Option<Box<dyn BinaryLoader>> {
         let shared = Self::shared();
+        if let Some(loader) = shared.magic_recognizers.read().unwrap().recognize(blob) {
+            return Some(loader);
+        }
         for recognizer in &shared.image_loaders {
             if let Some(loader) = recognizer.recognize(blob) {
                 return Some(loader);
@@ -69,8 +93,10 @@ impl RuntimeEnvironment {
         None
     }
 
+    /// Exits the current process with the exit code that the parent receives.
     #[inline]
-    pub fn exit(_exit_code: usize) -> ! {
+    pub fn exit(exit_code: i32) -> ! {
+        Scheduler::current_pid().set_exit_code(exit_code);
         Scheduler::exit();
     }
 }
@@ -126,13 +152,19 @@ pub trait BinaryLoader {
 
     fn load(&mut self, blob: &[u8]) -> Result<(), ()>;
 
-    fn invoke_start(self: Box<Self>) -> Option<ProcessId>;
+    fn invoke_start(self: Box<Self>) -> Option<ChildProcess>;
 }
 
 #[derive(Debug, Default)]
 pub struct LoadedImageOption {
     pub name: String,
     pub argv: Vec<String>,
+    /// Standard input connected through a pipe, if any
+    pub stdin: Option<Arc<Channel>>,
+    /// Standard output connected through a pipe, if any
+    pub stdout: Option<Arc<Channel>>,
+    /// Environment variables inherited from the shell
+    pub env: BTreeMap<String, String>,
 }
 
 /// Contextual data for legacy applications
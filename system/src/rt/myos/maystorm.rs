@@ -1,8 +1,10 @@
 use super::*;
 use crate::{
     fs::*,
+    io::{audio::AudioManager, screen::Screen},
     sync::Mutex,
-    ui::theme::Theme,
+    task::Channel,
+    ui::{clipboard::Clipboard, theme::Theme},
     *,
     {io::hid_mgr::*, ui::text::*, ui::window::*},
 };
@@ -14,9 +16,10 @@ use megstd::{
     drawing::*,
     io::{Read, Write},
     rand::*,
+    sys::megos::{OsDisplayInfo, WindowEvent},
     Arc, BTreeMap,
 };
-use wasm::{intr::*, *};
+use wasm::{heap::SimpleAllocator, intr::*, *};
 
 pub struct MyosBinaryLoader {
     loader: WasmLoader,
@@ -69,13 +72,14 @@ impl BinaryLoader for MyosBinaryLoader {
             })
     }
 
-    fn invoke_start(self: Box<Self>) -> Option<ProcessId> {
+    fn invoke_start(self: Box<Self>) -> Option<ChildProcess> {
         match self.loader.module().func(MyosRuntime::ENTRY_FUNC_NAME) {
             Ok(_) => {
-                let module = self.loader.into_module();
+                let Self { loader, lio } = *self;
+                let module = loader.into_module();
                 SpawnOption::new()
-                    .personality(MyosRuntime::new(module))
-                    .start_process(Self::start, 0, self.lio.name.as_ref())
+                    .personality(MyosRuntime::new(module, lio.stdin, lio.stdout, lio.env))
+                    .start_process(Self::start, 0, lio.name.as_ref())
             }
             Err(err) => {
                 println!("error: {:?}", err);
@@ -95,6 +99,11 @@ pub struct MyosRuntime {
     key_buffer: Mutex<Vec<KeyEvent>>,
     malloc: Mutex<SimpleAllocator>,
     has_to_exit: AtomicBool,
+    exit_code: AtomicI32,
+    stdin: Option<Arc<Channel>>,
+    stdout: Option<Arc<Channel>>,
+    /// Environment variables inherited from the shell
+    env: BTreeMap<String, String>,
 }
 
 unsafe impl Identify for MyosRuntime {
@@ -110,17 +119,25 @@ impl Personality for MyosRuntime {
 
     fn on_exit(self: Box<Self>) {
         self.windows.lock().unwrap().clear();
+        self.stdin.as_ref().map(|v| v.close());
+        self.stdout.as_ref().map(|v| v.close());
     }
 }
 
 impl MyosRuntime {
     const MAX_FILES: usize = 20;
+    const MAX_AUDIO_SAMPLE_RATE: u32 = 192_000;
     const MOD_NAME: &'static str = "megos-canary";
     const ENTRY_FUNC_NAME: &'static str = "_start";
 
     const SIZE_KEYBUFFER: usize = 32;
 
-    fn new(module: WasmModule) -> PersonalityContext {
+    fn new(
+        module: WasmModule,
+        stdin: Option<Arc<Channel>>,
+        stdout: Option<Arc<Channel>>,
+        env: BTreeMap<String, String>,
+    ) -> PersonalityContext {
         PersonalityContext::new(Self {
             module,
             next_handle: AtomicUsize::new(1),
@@ -130,6 +147,10 @@ impl MyosRuntime {
             key_buffer: Mutex::new(Vec::with_capacity(Self::SIZE_KEYBUFFER)),
             malloc: Mutex::new(SimpleAllocator::default()),
             has_to_exit: AtomicBool::new(false),
+            exit_code: AtomicI32::new(0),
+            stdin,
+            stdout,
+            env,
         })
     }
 
@@ -151,11 +172,23 @@ impl MyosRuntime {
             Ok(_v) => (),
             Err(err) => match err.kind() {
                 WasmRuntimeErrorKind::Exit => (),
-                _ => println!("error: {:?}", err),
+                kind => {
+                    if kind.is_trap() {
+                        log!(
+                            "wasm trap: {:?} in ${} at {} (0x{:x})",
+                            kind,
+                            err.function(),
+                            err.position(),
+                            err.file_position()
+                        );
+                    }
+                    println!("error: {:?}", err);
+                    RuntimeEnvironment::exit(1);
+                }
             },
         }
 
-        RuntimeEnvironment::exit(0);
+        RuntimeEnvironment::exit(self.exit_code.load(Ordering::SeqCst));
     }
 
     fn syscall(
@@ -190,6 +223,9 @@ impl MyosRuntime {
 
         match func_no {
             Function::Exit => {
+                // Applications built before the exit code was introduced pass no arguments
+                let exit_code = params.get_i32().unwrap_or(0);
+                self.exit_code.store(exit_code, Ordering::SeqCst);
                 return Err(WasmRuntimeErrorKind::Exit);
             }
 
@@ -210,6 +246,19 @@ impl MyosRuntime {
                 let us = params.get_u32()? as u64;
                 Timer::sleep(Duration::from_micros(us));
             }
+            Function::Nanosleep => {
+                let lo = params.get_u32()? as u64;
+                let hi = params.get_u32()? as u64;
+                let duration = Duration::from_nanos(lo | (hi << 32));
+                let deadline = Timer::monotonic() + duration;
+                Timer::sleep(duration);
+                let remaining = deadline.saturating_sub(Timer::monotonic());
+                let remaining = (remaining.as_nanos() as u64).to_le_bytes();
+                let buf = params.get_buffer(memory)?;
+                if let Some(buf) = buf.get_mut(..remaining.len()) {
+                    buf.copy_from_slice(&remaining);
+                }
+            }
 
             Function::GetSystemInfo => {
                 let sub_func_no = params.get_usize()?;
@@ -219,8 +268,42 @@ impl MyosRuntime {
                 }
             }
 
+            Function::GetDisplayInfo => {
+                let offset = params.get_usize()?;
+                let bounds = WindowManager::main_screen_bounds();
+                let (is_portrait, ppi) = System::main_screen()
+                    .map(|v| (v.orientation().is_portrait(), v.pixels_per_inch()))
+                    .unwrap_or((false, 96));
+                let scale = usize::max(ppi / 96, 1);
+
+                let info = OsDisplayInfo {
+                    width: bounds.width() as u32,
+                    height: bounds.height() as u32,
+                    flags: if is_portrait {
+                        OsDisplayInfo::FLAG_PORTRAIT
+                    } else {
+                        0
+                    },
+                    scale: scale as u32,
+                };
+                memory.write_slice(offset, &info.to_bytes())?;
+            }
+
             Function::PrintString => {
-                params.get_string(memory).map(|s| print!("{}", s));
+                params.get_string(memory).map(|s| match self.stdout {
+                    Some(ref stdout) => {
+                        stdout.write(s.as_bytes());
+                    }
+                    None => print!("{}", s),
+                });
+            }
+            Function::ReadStdin => {
+                let buf = params.get_buffer(memory)?;
+                let len = match self.stdin {
+                    Some(ref stdin) => stdin.read(buf),
+                    None => 0,
+                };
+                return Ok(WasmValue::from(len as u32));
             }
 
             Function::Open => {
@@ -304,18 +387,24 @@ impl MyosRuntime {
                 let origin = params.get_point()?;
                 let text = params.get_string(memory).unwrap_or("");
                 let color = params.get_color()?;
+                let font = ui::font::FontManager::ui_font();
                 let mut rect = window.content_rect().bounds();
                 rect.origin = origin;
                 rect.size.width -= origin.x;
                 rect.size.height -= origin.y;
+                let mut end = origin;
                 window.draw_in_rect(rect, |bitmap| {
-                    AttributedString::new()
+                    let point = AttributedString::new()
+                        .font(&font)
                         .align(TextAlignment::Left)
                         .valign(VerticalAlignment::Top)
                         .color(color)
                         .text(text)
                         .draw_text(bitmap, rect.bounds(), 0);
+                    end = Point::new(origin.x + point.x, origin.y + point.y);
                 });
+                // The end of the last line, following the line wraps
+                return Ok(WasmValue::from(megstd::sys::megos::pack_point(end)));
             }
             Function::FillRect => {
                 let window = params.get_window(self)?;
@@ -384,6 +473,17 @@ impl MyosRuntime {
                         .unwrap_or(megstd::sys::megos::OPTION_CHAR_NONE),
                 ));
             }
+            Function::WaitEvent => {
+                let window = params.get_window(self)?;
+                return self
+                    .wait_event(window.native())
+                    .map(|v| WasmValue::from(v.pack()));
+            }
+            Function::GetWindowSize => {
+                let window = params.get_window(self)?;
+                let size = window.content_rect().size();
+                return Ok(WasmValue::from(megstd::sys::megos::pack_size(size)));
+            }
 
             Function::Blt8 => {
                 let window = params.get_window(self)?;
@@ -434,6 +534,50 @@ impl MyosRuntime {
                 });
             }
 
+            Function::ClipboardSet => {
+                let text = params
+                    .get_string(memory)
+                    .ok_or(WasmRuntimeErrorKind::InvalidParameter)?;
+                Clipboard::shared().set_text(text);
+            }
+            Function::ClipboardGet => {
+                let buf = params.get_buffer(memory)?;
+                let text = Clipboard::shared().text();
+                let bytes = text.as_bytes();
+                if bytes.len() <= buf.len() {
+                    buf[..bytes.len()].copy_from_slice(bytes);
+                }
+                return Ok(WasmValue::from(bytes.len() as u32));
+            }
+
+            Function::GetEnv => {
+                let name = params
+                    .get_string(memory)
+                    .ok_or(WasmRuntimeErrorKind::InvalidParameter)?;
+                let Some(value) = self.env.get(name) else {
+                    return Ok(WasmValue::from(megstd::sys::megos::OPTION_ENV_NONE));
+                };
+                let buf = params.get_buffer(memory)?;
+                let bytes = value.as_bytes();
+                if bytes.len() <= buf.len() {
+                    buf[..bytes.len()].copy_from_slice(bytes);
+                }
+                return Ok(WasmValue::from(bytes.len() as u32));
+            }
+
+            Function::AudioPlay => {
+                let buf = params.get_buffer(memory)?;
+                let rate = params.get_u32()?;
+                if rate == 0 || rate > Self::MAX_AUDIO_SAMPLE_RATE {
+                    return Err(WasmRuntimeErrorKind::InvalidParameter);
+                }
+                let samples = buf
+                    .chunks_exact(2)
+                    .map(|v| i16::from_le_bytes([v[0], v[1]]))
+                    .collect();
+                AudioManager::play_pcm(samples, rate);
+            }
+
             Function::Rand => {
                 return Ok(WasmValue::from(self.rng32.next()));
             }
@@ -464,6 +608,19 @@ impl MyosRuntime {
                 self.malloc.lock().unwrap().dealloc(base, layout);
             }
 
+            Function::Realloc => {
+                let base = params.get_u32()?;
+                let size = params.get_usize()?;
+                let align = params.get_usize()?;
+                let new_size = params.get_usize()?;
+                let layout = Layout::from_size_align(size, align)
+                    .map_err(|_| WasmRuntimeErrorKind::InvalidParameter)?;
+
+                return self
+                    .realloc(memory, base, layout, new_size)
+                    .map(|v| WasmValue::from(v.get()));
+            }
+
             #[allow(unreachable_patterns)]
             _ => return Err(WasmRuntimeErrorKind::NotSupprted),
         }
@@ -511,47 +668,50 @@ impl MyosRuntime {
         memory: &WasmMemory,
         layout: Layout,
     ) -> Result<NonZeroU32, WasmRuntimeErrorKind> {
-        let mut malloc = self.malloc.lock().unwrap();
+        self.malloc.lock().unwrap().alloc_or_grow(memory, layout)
+    }
 
-        if let Some(result) = malloc.alloc(layout) {
-            println!("alloc1 {:?} => {:08x}", layout, result);
-            return Ok(result);
-        } else {
-            let min_alloc = WasmMemory::PAGE_SIZE;
-            let delta = (((layout.size() + min_alloc - 1) / min_alloc) * min_alloc
-                / WasmMemory::PAGE_SIZE) as i32;
-            let new_page = memory.grow(delta);
-            if new_page > 0 {
-                println!("grow {} => {}", delta, new_page);
-                malloc.append_block(
-                    new_page as u32 * WasmMemory::PAGE_SIZE as u32,
-                    delta as u32 * WasmMemory::PAGE_SIZE as u32,
-                );
-            } else {
-                return Err(WasmRuntimeErrorKind::OutOfMemory);
-            }
+    fn realloc(
+        &self,
+        memory: &WasmMemory,
+        base: u32,
+        layout: Layout,
+        new_size: usize,
+    ) -> Result<NonZeroU32, WasmRuntimeErrorKind> {
+        self.malloc
+            .lock()
+            .unwrap()
+            .realloc(memory, base, layout, new_size)
+    }
 
-            let result = match malloc.alloc(layout) {
-                Some(v) => v.get(),
-                None => 0,
-            };
-            println!("alloc2 {:?} => {:08x}", layout, result);
-            NonZeroU32::new(result).ok_or(WasmRuntimeErrorKind::OutOfMemory)
+    fn wait_key(&self, window: WindowHandle) -> Result<Option<char>, WasmRuntimeErrorKind> {
+        loop {
+            match self.wait_event(window)? {
+                WindowEvent::Char(c) => return Ok(Some(c)),
+                WindowEvent::Resized(_) => (),
+            }
         }
     }
 
-    fn wait_key(&self, window: WindowHandle) -> Result<Option<char>, WasmRuntimeErrorKind> {
+    fn wait_event(&self, window: WindowHandle) -> Result<WindowEvent, WasmRuntimeErrorKind> {
         while let Some(message) = window.wait_message() {
+            let resized = match message {
+                WindowMessage::Resized(size) => Some(size),
+                _ => None,
+            };
             self.process_message(window, message);
             if self.has_to_exit.load(Ordering::Relaxed) {
                 return Err(WasmRuntimeErrorKind::Exit);
             }
+            if let Some(size) = resized {
+                return Ok(WindowEvent::Resized(size));
+            }
 
             if let Some(c) = self
                 .read_key_buffer()
                 .and_then(|v| v.key_data().map(|v| v.into_char()))
             {
-                return Ok(Some(c));
+                return Ok(WindowEvent::Char(c));
             }
         }
         Err(WasmRuntimeErrorKind::TypeMismatch)
@@ -588,6 +748,8 @@ impl MyosRuntime {
                     .key_data()
                     .map(|data| self.key_buffer.lock().unwrap().push(data));
             }
+            // The guest gets the new size by WaitEvent or GetWindowSize
+            WindowMessage::Resized(_) => (),
             _ => window.handle_default_message(message),
         }
     }
@@ -936,161 +1098,3 @@ impl Drop for OsWindow {
         self.native.close();
     }
 }
-
-pub struct SimpleAllocator {
-    data: Vec<SimpleFreePair>,
-    strategy: AllocationStrategy,
-}
-
-impl SimpleAllocator {
-    const MIN_MASK: u32 = 0x0000_000F;
-
-    #[inline]
-    pub const fn new(strategy: AllocationStrategy) -> Self {
-        Self {
-            data: Vec::new(),
-            strategy,
-        }
-    }
-
-    fn merge(&mut self, new_data: Option<SimpleFreePair>) {
-        if let Some(new_data) = new_data {
-            self.data.push(new_data);
-        }
-        self.data.sort_by_key(|v| v.base);
-
-        let mut do_retry = false;
-        let mut retry_index = 0;
-        loop {
-            for index in retry_index..self.data.len() - 1 {
-                let current = unsafe { self.data.get_unchecked(index) };
-                let next = unsafe { self.data.get_unchecked(index + 1) };
-                if current.next_base() == next.base {
-                    let next = self.data.remove(index + 1);
-                    self.data[index].size += next.size;
-                    retry_index = index;
-                    do_retry = true;
-                    break;
-                }
-            }
-            if do_retry == false {
-                break;
-            }
-        }
-    }
-
-    pub fn append_block(&mut self, base: u32, size: u32) {
-        self.merge(Some(SimpleFreePair::new(base, size)));
-    }
-
-    pub fn alloc(&mut self, layout: Layout) -> Option<NonZeroU32> {
-        let layout_align = u32::max(layout.align() as u32, Self::MIN_MASK + 1);
-        let layout_mask = layout_align - 1;
-        let min_alloc =
-            (u32::max(layout_align, layout.size() as u32) + Self::MIN_MASK) & !Self::MIN_MASK;
-        let max_alloc = (min_alloc + layout_mask) & layout_mask;
-
-        let mut result = 0;
-        let mut extend = Vec::new();
-        match self.strategy {
-            AllocationStrategy::FirstFit => {
-                for pair in &mut self.data {
-                    if (pair.base & layout_mask) == 0 && pair.size >= min_alloc {
-                        result = pair.base;
-                        pair.size -= min_alloc;
-                        pair.base += min_alloc;
-                        break;
-                    } else if pair.size >= max_alloc {
-                        let redundant = pair.base & layout_mask;
-                        extend.push(SimpleFreePair::new(pair.base, redundant));
-                        pair.base -= redundant;
-                        pair.size -= redundant;
-
-                        result = pair.base;
-                        pair.size -= min_alloc;
-                        pair.base += min_alloc;
-                        break;
-                    }
-                }
-            }
-            AllocationStrategy::BestFit => todo!(),
-        }
-        if extend.len() > 0 {
-            self.data.extend_from_slice(extend.as_slice());
-            self.merge(None);
-        }
-
-        NonZeroU32::new(result)
-    }
-
-    pub fn dealloc(&mut self, base: u32, layout: Layout) {
-        let layout_align = u32::max(layout.align() as u32, Self::MIN_MASK + 1);
-        // let layout_mask = layout_align - 1;
-        let min_alloc =
-            (u32::max(layout_align, layout.size() as u32) + Self::MIN_MASK) & !Self::MIN_MASK;
-
-        let new_pair = SimpleFreePair::new(base, min_alloc);
-
-        let mut cursor = None;
-        for (index, pair) in self.data.iter_mut().enumerate() {
-            if new_pair.next_base() == pair.base {
-                pair.base = new_pair.base;
-                pair.size += min_alloc;
-                cursor = Some(index);
-                break;
-            } else if pair.next_base() == base {
-                pair.size += min_alloc;
-                cursor = Some(index);
-                break;
-            }
-        }
-
-        if let Some(index) = cursor {
-            if index < self.data.len() - 1 {
-                let current = unsafe { self.data.get_unchecked(index) };
-                let next = unsafe { self.data.get_unchecked(index + 1) };
-                if current.next_base() == next.base {
-                    let next = self.data.remove(index + 1);
-                    self.data[index].size += next.size;
-                }
-            }
-        } else {
-            self.merge(Some(new_pair));
-        }
-        for data in &self.data {
-            println!("DATA {:08x} {}", data.base, data.size);
-        }
-    }
-}
-
-impl Default for SimpleAllocator {
-    #[inline]
-    fn default() -> Self {
-        Self::new(AllocationStrategy::FirstFit)
-    }
-}
-
-#[non_exhaustive]
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
-pub enum AllocationStrategy {
-    FirstFit,
-    BestFit,
-}
-
-#[derive(Clone, Copy)]
-struct SimpleFreePair {
-    base: u32,
-    size: u32,
-}
-
-impl SimpleFreePair {
-    #[inline]
-    pub const fn new(base: u32, size: u32) -> Self {
-        Self { base, size }
-    }
-
-    #[inline]
-    pub const fn next_base(&self) -> u32 {
-        self.base + self.size
-    }
-}
@@ -17,6 +17,8 @@ mod mutex;
 pub use mutex::*;
 mod rwlock;
 pub use rwlock::*;
+mod once;
+pub use once::*;
 
 use core::fmt;
 
@@ -7,8 +7,8 @@ use core::{
     mem,
     ops::{Deref, DerefMut},
     ptr,
-    sync::atomic::*,
 };
+use mytask::rwlock::RwLockState;
 
 /// A reader-writer lock like std::sync::RwLock
 pub struct RwLock<T: ?Sized> {
@@ -110,12 +110,6 @@ impl<T: Default> Default for RwLock<T> {
     }
 }
 
-impl<T: ?Sized> Drop for RwLock<T> {
-    fn drop(&mut self) {
-        todo!()
-    }
-}
-
 impl<T> From<T> for RwLock<T> {
     #[inline]
     fn from(t: T) -> Self {
@@ -198,18 +192,15 @@ impl<T: ?Sized> Drop for RwLockWriteGuard<'_, T> {
 }
 
 struct RwLockInner {
-    data: AtomicUsize,
+    state: RwLockState,
     signal: SignallingObject,
 }
 
 impl RwLockInner {
-    const LOCK_WRITE: usize = 1;
-    const LOCK_COUNT: usize = 2;
-
     #[inline]
     const fn new() -> Self {
         Self {
-            data: AtomicUsize::new(0),
+            state: RwLockState::new(),
             signal: SignallingObject::new(),
         }
     }
@@ -221,40 +212,23 @@ impl RwLockInner {
 
     #[inline]
     unsafe fn write(&self) {
-        self.signal.wait_for(|| self.try_write());
+        self.signal.wait_for(|| self.state.try_write_or_wait());
     }
 
     #[inline]
     fn try_read(&self) -> bool {
-        self.data
-            .fetch_update(Ordering::Acquire, Ordering::Relaxed, |v| {
-                if (v & Self::LOCK_WRITE) == 0 {
-                    Some(v + Self::LOCK_COUNT)
-                } else {
-                    None
-                }
-            })
-            .is_ok()
+        self.state.try_read()
     }
 
     #[inline]
     fn try_write(&self) -> bool {
-        self.data
-            .fetch_update(Ordering::Acquire, Ordering::Relaxed, |v| {
-                if v == 0 {
-                    Some(Self::LOCK_WRITE)
-                } else {
-                    None
-                }
-            })
-            .is_ok()
+        self.state.try_write()
     }
 
     #[inline]
     #[track_caller]
     unsafe fn read_unlock(&self) {
-        let _ = self.data.fetch_sub(Self::LOCK_COUNT, Ordering::Relaxed);
-        if self.data.load(Ordering::Relaxed) == 0 {
+        if self.state.read_unlock() {
             let _ = self.signal.signal();
         }
     }
@@ -262,9 +236,7 @@ impl RwLockInner {
     #[inline]
     #[track_caller]
     unsafe fn write_unlock(&self) {
-        let _ = self.data.store(0, Ordering::Release);
-        if self.data.load(Ordering::Relaxed) == 0 {
-            let _ = self.signal.signal();
-        }
+        self.state.write_unlock();
+        let _ = self.signal.signal();
     }
 }
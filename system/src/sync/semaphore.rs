@@ -1,7 +1,7 @@
 // Semaphore
 
 use super::{fifo::ConcurrentFifo, signal::SignallingObject};
-use crate::*;
+use crate::{task::scheduler::*, *};
 use alloc::{boxed::Box, sync::Arc};
 use core::{
     marker::PhantomData,
@@ -10,6 +10,7 @@ use core::{
     task::{Context, Poll, Waker},
 };
 use futures_util::Future;
+use mytask::priority::InheritedLevel;
 
 /// counting semaphore
 pub struct Semaphore {
@@ -63,8 +64,15 @@ impl Semaphore {
 }
 
 /// binary semaphore
+///
+/// While a thread is waiting for the lock, the thread holding the lock inherits its priority
+/// to avoid priority inversion. The inherited priority is dropped when the lock is released.
 pub struct BinarySemaphore {
     value: AtomicBool,
+    owner: AtomicUsize,
+    /// The priority inherited by the owner through this lock
+    inherited: Spinlock,
+    inherited_priority: InheritedLevel,
     signal: SignallingObject,
 }
 
@@ -73,6 +81,9 @@ impl BinarySemaphore {
     pub const fn new() -> Self {
         Self {
             value: AtomicBool::new(false),
+            owner: AtomicUsize::new(0),
+            inherited: Spinlock::new(),
+            inherited_priority: InheritedLevel::new(),
             signal: SignallingObject::new(),
         }
     }
@@ -80,18 +91,50 @@ impl BinarySemaphore {
     #[inline]
     #[must_use]
     pub fn try_lock(&self) -> bool {
-        self.value
+        let result = self
+            .value
             .compare_exchange_weak(false, true, Ordering::Acquire, Ordering::Relaxed)
-            .is_ok()
+            .is_ok();
+        if result {
+            let owner = Scheduler::current_thread().map_or(0, |v| v.as_usize());
+            self.owner.store(owner, Ordering::Release);
+        }
+        result
     }
 
     #[inline]
     pub fn lock(&self) {
-        self.signal.wait_for(|| self.try_lock())
+        self.signal.wait_for(|| {
+            if self.try_lock() {
+                true
+            } else {
+                self.inherit_priority();
+                false
+            }
+        })
+    }
+
+    /// Lets the owner of the lock inherit the priority of the current thread.
+    fn inherit_priority(&self) {
+        let Some(priority) = Scheduler::current_thread().and_then(|v| v.priority()) else {
+            return;
+        };
+        self.inherited.synchronized(|| {
+            let Some(owner) = ThreadHandle::new(self.owner.load(Ordering::Acquire)) else {
+                return;
+            };
+            owner.inherit_priority(&self.inherited_priority, priority);
+        })
     }
 
     #[inline]
     pub unsafe fn force_unlock(&self) -> Option<()> {
+        self.inherited.synchronized(|| {
+            let owner = self.owner.swap(0, Ordering::AcqRel);
+            if let Some(owner) = ThreadHandle::new(owner) {
+                owner.restore_priority(&self.inherited_priority);
+            }
+        });
         self.value
             .compare_exchange(true, false, Ordering::AcqRel, Ordering::Relaxed)
             .map(|_| {
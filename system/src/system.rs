@@ -11,9 +11,12 @@ use core::{
     fmt,
     mem::{transmute, MaybeUninit},
     sync::atomic::*,
+    time::Duration,
 };
 use megstd::{drawing::*, time::SystemTime, Arc, Box, String, Vec};
 
+pub use megstd::panic::PanicAction;
+
 /// A Kernel of MEG-OS codename Maystorm
 #[allow(dead_code)]
 pub struct System {
@@ -29,11 +32,17 @@ pub struct System {
     /// An instance of SMBIOS
     smbios: Option<Box<fw::smbios::SmBios>>,
 
+    /// An instance of the devicetree
+    fdt: Option<fw::fdt::Fdt<'static>>,
+
     // screens
     safe_screen: MaybeUninit<Option<Arc<BitmapScreen<'static>>>>,
     stdout: Option<Box<dyn Tty>>,
     emcon: MaybeUninit<Box<UnsafeCell<io::emcon::EmConsole>>>,
 
+    /// What the panic handler does after reporting the panic
+    panic_action: PanicAction,
+
     // copy of boot info
     boot_flags: BootFlags,
     initrd_base: PhysicalAddress,
@@ -56,6 +65,8 @@ impl System {
             cpus: Vec::new(),
             acpi: None,
             smbios: None,
+            fdt: None,
+            panic_action: PanicAction::Halt,
             boot_flags: BootFlags::empty(),
             safe_screen: MaybeUninit::zeroed(),
             emcon: MaybeUninit::zeroed(),
@@ -75,6 +86,15 @@ impl System {
         shared.initrd_size = info.initrd_size as usize;
         shared.current_device.total_memory_size = info.total_memory_size as usize;
 
+        // panic=N reboots N seconds after a panic, 0 halts as usual
+        if let Some(secs) = info
+            .cmdline_args()
+            .value_of("panic")
+            .and_then(|v| v.parse::<u64>().ok())
+        {
+            shared.panic_action = PanicAction::reboot_after(Duration::from_secs(secs));
+        }
+
         mem::MemoryManager::init_first(info);
 
         if info.vram_base > 0
@@ -114,6 +134,10 @@ impl System {
             shared.smbios = Some(smbios);
         }
 
+        if info.dtb != 0 {
+            shared.fdt = fw::fdt::Fdt::from_ptr(PhysicalAddress::new(info.dtb).direct_map::<u8>());
+        }
+
         arch::Arch::init_first(info);
 
         Scheduler::start(Self::init_second, f as usize);
@@ -149,9 +173,12 @@ impl System {
             Scheduler::init_second();
             mem::MemoryManager::init_second();
             fs::FileManager::init(shared.initrd_base.direct_map(), shared.initrd_size);
+            res::ResourceBundle::init(shared.initrd_base.direct_map(), shared.initrd_size);
+            user::init();
 
             io::hid_mgr::HidManager::init();
             io::audio::AudioManager::init();
+            io::net::NetManager::init();
             drivers::usb::UsbManager::init();
 
             drivers::pci::Pci::init();
@@ -207,12 +234,48 @@ impl System {
         Self::shared().boot_flags
     }
 
+    /// Returns what the panic handler does after reporting the panic.
+    #[inline]
+    pub fn panic_action() -> PanicAction {
+        Self::shared().panic_action
+    }
+
     /// Returns the current system time.
     #[inline]
     pub fn system_time() -> SystemTime {
         arch::Arch::system_time()
     }
 
+    /// Returns the system time when the system was booted.
+    #[inline]
+    pub fn boot_time() -> SystemTime {
+        arch::Arch::boot_time()
+    }
+
+    /// Returns the time elapsed since the system was booted.
+    #[inline]
+    pub fn uptime() -> Duration {
+        Timer::monotonic()
+    }
+
+    /// Turns off the power with ACPI S5, or stops the processor if it fails.
+    pub fn shutdown() -> ! {
+        unsafe {
+            Hal::cpu().disable_interrupt();
+            Scheduler::freeze(true);
+            let _ = arch::Arch::enter_sleep_state(5);
+            Hal::cpu().stop();
+        }
+    }
+
+    /// Suspends to RAM with ACPI S3.
+    ///
+    /// Not supported yet, because resuming requires the firmware waking vector
+    /// and restoring the processor states.
+    pub fn suspend() -> Result<(), ()> {
+        Err(())
+    }
+
     /// Returns whether the kernel is multiprocessor-capable.
     #[inline]
     pub const fn is_multi_processor_capable_kernel() -> bool {
@@ -278,6 +341,11 @@ impl System {
         Self::shared().smbios.as_ref().map(|v| v.as_ref())
     }
 
+    #[inline]
+    pub fn fdt<'a>() -> Option<&'a fw::fdt::Fdt<'static>> {
+        Self::shared().fdt.as_ref()
+    }
+
     /// Returns the current device information.
     #[inline]
     pub fn current_device<'a>() -> &'a DeviceInfo {
@@ -0,0 +1,46 @@
+//! Bounded byte channel between tasks
+
+use crate::sync::{fifo::ConcurrentFifo, semaphore::Semaphore};
+use mytask::channel::{ChannelFifo, ChannelSemaphore};
+
+/// A bounded, blocking byte pipe.
+pub type Channel = mytask::channel::Channel<Semaphore, ConcurrentFifo<u8>>;
+
+impl ChannelSemaphore for Semaphore {
+    #[inline]
+    fn new(value: usize) -> Self {
+        Semaphore::new(value)
+    }
+
+    #[inline]
+    fn try_lock(&self) -> bool {
+        Semaphore::try_lock(self)
+    }
+
+    #[inline]
+    fn wait(&self) {
+        Semaphore::wait(self);
+    }
+
+    #[inline]
+    fn signal(&self) {
+        Semaphore::signal(self);
+    }
+}
+
+impl ChannelFifo<u8> for ConcurrentFifo<u8> {
+    #[inline]
+    fn with_capacity(capacity: usize) -> Self {
+        ConcurrentFifo::with_capacity(capacity)
+    }
+
+    #[inline]
+    fn enqueue(&self, value: u8) -> Result<(), u8> {
+        ConcurrentFifo::enqueue(self, value)
+    }
+
+    #[inline]
+    fn dequeue(&self) -> Option<u8> {
+        ConcurrentFifo::dequeue(self)
+    }
+}
@@ -2,6 +2,13 @@
 
 pub mod executor;
 pub mod scheduler;
+pub mod tls;
+pub mod watchdog;
+
+mod channel;
+pub use channel::*;
+
+pub use mytask::future::YieldNow;
 
 use alloc::boxed::Box;
 use core::future::Future;
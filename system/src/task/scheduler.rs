@@ -1,4 +1,4 @@
-use super::{executor::Executor, *};
+use super::{executor::Executor, watchdog::Watchdog, *};
 use crate::{
     arch::cpu::*,
     rt::PersonalityContext,
@@ -18,7 +18,21 @@ use core::{
     cell::UnsafeCell, ffi::c_void, fmt, intrinsics::transmute, num::*, ops::*, sync::atomic::*,
     time::Duration,
 };
-use megstd::{string::*, Arc, BTreeMap, Box, String, ToOwned, Vec};
+use megstd::{
+    stat::{CpuTime, Load},
+    string::*,
+    time::Cadence,
+    Arc, BTreeMap, Box, String, ToOwned, Vec,
+};
+use mytask::{
+    priority::{InheritedLevel, LevelCounts},
+    process::ExitStatus,
+    queue::{self, Selection},
+    stats::ThreadStatistics,
+    tls::TlsSlots,
+};
+
+pub use mytask::state::ThreadState;
 
 const THRESHOLD_BUSY_THREAD: usize = 750;
 const THRESHOLD_ENTER_SAVING: usize = 500;
@@ -254,6 +268,12 @@ impl Scheduler {
             .map(|v| unsafe { &mut *v.get() })
     }
 
+    /// Get the thread local storage slots of the current thread
+    #[inline]
+    pub(super) fn current_tls_slots<'a>() -> &'a mut TlsSlots {
+        &mut Self::current_thread_data().tls
+    }
+
     /// Perform the preemption
     pub unsafe fn reschedule() {
         assert!(Hal::cpu().is_interrupt_disabled());
@@ -264,7 +284,7 @@ impl Scheduler {
         let local = Self::local_scheduler().unwrap();
         let current = local.current_thread();
         current.update_statistics();
-        let priority = { current.as_ref().priority };
+        let priority = { current.as_ref().effective_priority() };
         let shared = Self::shared();
         if shared.next_timer.value().is_expired() {
             Self::_process_timer_events();
@@ -278,18 +298,21 @@ impl Scheduler {
         }
         if Self::is_stalled_processor(local.index) {
             LocalScheduler::switch_context(local, local.idle);
-        } else if let Some(next) = shared.queue_realtime.dequeue() {
+        } else if let Some(next) = shared.queue_realtime.dequeue_for(local.index) {
             LocalScheduler::switch_context(local, next);
         } else if let Some(next) = (priority < Priority::High)
-            .then(|| shared.queue_urgent.dequeue())
+            .then(|| shared.queue_urgent.dequeue_for(local.index))
             .flatten()
         {
             LocalScheduler::switch_context(local, next);
         } else if let Some(next) = (priority < Priority::Normal)
-            .then(|| shared.queue_normal.dequeue())
+            .then(|| shared.queue_normal.dequeue_for(local.index))
             .flatten()
         {
             LocalScheduler::switch_context(local, next);
+        } else if !current.affinity().contains(local.index) {
+            // The affinity has been changed while running
+            LocalScheduler::switch_context(local, local.next_thread().unwrap_or(local.idle));
         } else if current.as_ref().quantum.consume() {
             if let Some(next) = local.next_thread() {
                 LocalScheduler::switch_context(local, next);
@@ -369,11 +392,11 @@ impl Scheduler {
 
         if Self::is_stalled_processor(index) {
             Some(scheduler.idle)
-        } else if let Some(next) = shared.queue_realtime.dequeue() {
+        } else if let Some(next) = shared.queue_realtime.dequeue_for(index) {
             Some(next)
-        } else if let Some(next) = shared.queue_urgent.dequeue() {
+        } else if let Some(next) = shared.queue_urgent.dequeue_for(index) {
             Some(next)
-        } else if let Some(next) = shared.queue_normal.dequeue() {
+        } else if let Some(next) = shared.queue_normal.dequeue_for(index) {
             Some(next)
         } else {
             None
@@ -381,7 +404,7 @@ impl Scheduler {
     }
 
     fn _enqueue(&self, handle: ThreadHandle) {
-        match handle.as_ref().priority {
+        match handle.as_ref().effective_priority() {
             Priority::Realtime => self.queue_realtime.enqueue(handle).unwrap(),
             Priority::High | Priority::Normal | Priority::Low => {
                 self.queue_normal.enqueue(handle).unwrap()
@@ -425,14 +448,24 @@ impl Scheduler {
     /// Schedule a timer event
     fn _schedule_timer(event: TimerEvent) {
         let shared = Self::shared();
+        let timer = event.timer;
         let mut events = shared.timer_events.lock();
         events.push(event);
         events.sort_by_key(|v| v.timer.deadline);
 
-        if let Some(event) = events.first() {
-            let _ = shared
-                .next_timer
-                .fetch_update(|v| (v > event.timer).then(|| event.timer));
+        let Some(first) = events.first().map(|v| v.timer) else {
+            return;
+        };
+        let _ = shared
+            .next_timer
+            .fetch_update(|v| (v > first).then_some(first));
+        drop(events);
+
+        // Sleeps shorter than the tick do not have to wait for the next tick
+        if first == timer {
+            if let Some(remaining) = timer.remaining() {
+                Hal::cpu().set_next_deadline(remaining);
+            }
         }
     }
 
@@ -465,26 +498,23 @@ impl Scheduler {
 
             let now = Timer::measure_deprecated();
             let actual = now.0 - measure.0;
-            let actual1000 = actual as usize * 1000;
 
             let mut n_busy_thread = 0;
             let mut usage = 0;
             for thread in ThreadPool::shared().data.lock().values() {
                 let thread = thread.clone();
 
-                let load0 = thread.load0.swap(0, Ordering::SeqCst);
-                let load = usize::min(load0 as usize * expect as usize / actual1000, 1000);
-                thread.load.store(load as u32, Ordering::SeqCst);
+                let (load0, load) = thread.stats.sample_load(actual as usize);
                 if thread.priority != Priority::Idle {
-                    usage += load;
+                    usage += load as usize;
                     if load >= THRESHOLD_BUSY_THREAD {
                         n_busy_thread += 1;
                     }
                 }
 
                 let process = thread.pid.get().unwrap();
-                process.cpu_time.fetch_add(load0 as usize, Ordering::SeqCst);
-                process.load0.fetch_add(load as u32, Ordering::SeqCst);
+                process.cpu_time.fetch_add(load0, Ordering::SeqCst);
+                process.load0.fetch_add(load, Ordering::SeqCst);
             }
 
             for process in ProcessPool::shared().read().unwrap().values() {
@@ -536,6 +566,8 @@ impl Scheduler {
                 }
             }
 
+            Watchdog::check_all();
+
             measure = now;
         }
     }
@@ -558,7 +590,7 @@ impl Scheduler {
         arg: usize,
         name: &str,
         options: SpawnOption,
-    ) -> Option<ThreadHandle> {
+    ) -> Option<(ThreadHandle, Arc<ProcessContextData>)> {
         let current_pid = Self::current_pid();
         let pid = if options.new_process {
             let child = ProcessContextData::new(
@@ -583,13 +615,30 @@ impl Scheduler {
             pid,
             priority,
             options.strong_affinity,
+            options.affinity,
             name,
             Some((start, arg)),
             options.personality,
         )
         .unwrap();
         Self::add(thread);
-        Some(thread)
+        Some((thread, target_process))
+    }
+
+    /// Restricts the processors on which the specified thread is allowed to run.
+    ///
+    /// Fails if the mask contains no available processors or the thread no longer exists.
+    pub fn set_affinity(thread: ThreadHandle, mask: CpuMask) -> Result<(), ()> {
+        let mask = mask & CpuMask::available();
+        if mask.is_empty() {
+            return Err(());
+        }
+        let thread = thread.get().ok_or(())?;
+        if thread.priority == Priority::Idle {
+            return Err(());
+        }
+        thread.affinity.store(mask.bits(), Ordering::SeqCst);
+        Ok(())
     }
 
     /// Spawning asynchronous tasks
@@ -606,6 +655,12 @@ impl Scheduler {
         thread.executor.as_ref().unwrap().spawn(task);
     }
 
+    /// Gives the other ready tasks on the current executor a chance to run.
+    #[inline]
+    pub fn yield_now() -> YieldNow {
+        YieldNow::new()
+    }
+
     /// Performing Asynchronous Tasks
     pub fn perform_tasks() -> ! {
         let thread = Self::current_thread_data();
@@ -624,7 +679,7 @@ impl Scheduler {
             if thread.priority != Priority::Idle {
                 break;
             }
-            vec.push(thread.load.load(Ordering::Relaxed));
+            vec.push(thread.stats.load());
         }
     }
 
@@ -647,76 +702,78 @@ impl Scheduler {
             .unwrap();
 
             let load = u32::min(process.load.load(Ordering::Relaxed), max_load);
-            let load0 = load % 10;
-            let load1 = load / 10;
-            if load1 >= 10 {
-                write!(sb, " {:4}", load1,).unwrap();
-            } else {
-                write!(sb, " {:2}.{:1}", load1, load0,).unwrap();
-            }
+            write!(sb, " {}", Load(load)).unwrap();
 
-            let time = process.cpu_time.load(Ordering::Relaxed) / 10_000;
-            let dsec = time % 100;
-            let sec = time / 100 % 60;
-            let min = time / 60_00 % 60;
-            let hour = time / 3600_00;
-            if hour > 0 {
-                write!(sb, " {:02}:{:02}:{:02}", hour, min, sec,).unwrap();
-            } else {
-                write!(sb, " {:02}:{:02}.{:02}", min, sec, dsec,).unwrap();
-            }
+            let cpu_time = Duration::from_micros(process.cpu_time.load(Ordering::Relaxed) as u64);
+            write!(sb, " {}", CpuTime(cpu_time)).unwrap();
 
             writeln!(sb, " {}", process.name(),).unwrap();
         }
     }
 
     pub fn get_thread_statistics(sb: &mut impl fmt::Write) {
-        writeln!(sb, " ID PID P ST %CPU TIME     NAME").unwrap();
-        for thread in ThreadPool::shared().data.lock().values() {
-            if thread.pid == ProcessId(0) {
-                continue;
-            }
-
-            let status_char = if thread.is_asleep() {
-                'S'
-            } else {
-                thread.attribute.to_char()
-            };
-
-            write!(
+        writeln!(sb, " ID PID P S %CPU TIME          CSW NAME").unwrap();
+        for thread in Self::snapshot() {
+            writeln!(
                 sb,
-                "{:3} {:3} {} {}{:01x}",
-                thread.handle.as_usize(),
+                "{:3} {:3} {} {} {} {} {:8} {}",
+                thread.tid.as_usize(),
                 thread.pid.0,
                 thread.priority as usize,
-                status_char,
-                thread.attribute.bits(),
+                thread.state.to_char(),
+                Load(thread.load),
+                CpuTime(thread.cpu_time),
+                thread.n_switches,
+                thread.name,
             )
             .unwrap();
-
-            let load = thread.load.load(Ordering::Relaxed);
-            let load0 = load % 10;
-            let load1 = load / 10;
-            if load1 >= 10 {
-                write!(sb, " {:4}", load1,).unwrap();
-            } else {
-                write!(sb, " {:2}.{:1}", load1, load0,).unwrap();
-            }
-
-            let time = thread.cpu_time.load(Ordering::Relaxed) / 10_000;
-            let dsec = time % 100;
-            let sec = time / 100 % 60;
-            let min = time / 60_00 % 60;
-            let hour = time / 3600_00;
-            if hour > 0 {
-                write!(sb, " {:02}:{:02}:{:02}", hour, min, sec,).unwrap();
-            } else {
-                write!(sb, " {:02}:{:02}.{:02}", min, sec, dsec,).unwrap();
-            }
-
-            writeln!(sb, " {}", thread.name()).unwrap();
         }
     }
+
+    /// Returns the snapshot of the threads except the idle threads.
+    pub fn snapshot() -> Vec<ThreadInfo> {
+        let running = Self::shared()
+            .locals
+            .iter()
+            .map(|v| v.current_thread())
+            .collect::<Vec<_>>();
+        ThreadPool::shared()
+            .data
+            .lock()
+            .values()
+            .filter(|thread| thread.pid != ProcessId(0))
+            .map(|thread| ThreadInfo {
+                pid: thread.pid,
+                tid: thread.handle,
+                name: thread.name(),
+                state: ThreadState::new(
+                    running.contains(&thread.handle),
+                    thread.attribute.contains(ThreadAttribute::QUEUED),
+                    thread.is_asleep(),
+                    thread.attribute.contains(ThreadAttribute::ZOMBIE),
+                ),
+                priority: thread.priority,
+                cpu_time: Duration::from_micros(thread.stats.cpu_time() as u64),
+                load: thread.stats.load(),
+                n_switches: thread.stats.context_switches(),
+            })
+            .collect()
+    }
+}
+
+/// Information of the thread returned by [`Scheduler::snapshot`]
+#[derive(Debug, Clone)]
+pub struct ThreadInfo {
+    pub pid: ProcessId,
+    pub tid: ThreadHandle,
+    pub name: String,
+    pub state: ThreadState,
+    pub priority: Priority,
+    pub cpu_time: Duration,
+    /// Load of the thread in permille
+    pub load: u32,
+    /// Number of the context switches
+    pub n_switches: usize,
 }
 
 /// Processor Local Scheduler
@@ -737,6 +794,7 @@ impl LocalScheduler {
             ProcessId(0),
             Priority::Idle,
             Some(index),
+            CpuMask::ALL,
             sb.as_str(),
             None,
             None,
@@ -778,8 +836,8 @@ impl LocalScheduler {
     unsafe fn _switch_context_after(&mut self, irql: Irql) {
         let current = self.current_thread().as_ref();
         current
-            .measure
-            .store(Timer::measure_deprecated().0 as usize, Ordering::SeqCst);
+            .stats
+            .switch_in(Timer::measure_deprecated().0 as usize);
         let retired = self.take_retired().unwrap();
         Scheduler::retire(retired);
         self.lower_irql(irql);
@@ -849,8 +907,8 @@ pub unsafe extern "C" fn setup_new_thread() {
     let lsch = Scheduler::local_scheduler().unwrap();
     let current = lsch.current_thread().as_ref();
     current
-        .measure
-        .store(Timer::measure_deprecated().0 as usize, Ordering::SeqCst);
+        .stats
+        .switch_in(Timer::measure_deprecated().0 as usize);
     let retired = lsch.take_retired().unwrap();
     Scheduler::retire(retired);
     lsch.lower_irql(Irql::Passive);
@@ -862,6 +920,7 @@ pub struct SpawnOption {
     new_process: bool,
     personality: Option<PersonalityContext>,
     strong_affinity: Option<ProcessorIndex>,
+    affinity: CpuMask,
 }
 
 impl SpawnOption {
@@ -872,6 +931,7 @@ impl SpawnOption {
             new_process: false,
             personality: None,
             strong_affinity: None,
+            affinity: CpuMask::ALL,
         }
     }
 
@@ -882,6 +942,7 @@ impl SpawnOption {
             new_process: false,
             personality: None,
             strong_affinity: None,
+            affinity: CpuMask::ALL,
         }
     }
 
@@ -898,19 +959,35 @@ impl SpawnOption {
         self
     }
 
+    /// Restricts the processors on which the new thread is allowed to run.
+    #[inline]
+    pub fn affinity(mut self, mask: CpuMask) -> Self {
+        self.affinity = mask;
+        self
+    }
+
+    /// Pins the new thread to the specified processor, as is needed by per-CPU driver threads.
+    #[inline]
+    pub fn pinned(self, index: ProcessorIndex) -> Self {
+        self.strong_affinity(index).affinity(CpuMask::single(index))
+    }
+
     /// Start the specified function in a new thread.
     #[inline]
     pub fn start(self, start: fn(usize), arg: usize, name: &str) -> Option<ThreadHandle> {
-        Scheduler::spawn_thread(start, arg, name, self)
+        Scheduler::spawn_thread(start, arg, name, self).map(|(thread, _)| thread)
     }
 
     /// Start the specified function in a new process.
     #[inline]
-    pub fn start_process(mut self, start: fn(usize), arg: usize, name: &str) -> Option<ProcessId> {
+    pub fn start_process(
+        mut self,
+        start: fn(usize),
+        arg: usize,
+        name: &str,
+    ) -> Option<ChildProcess> {
         self.new_process = true;
-        Scheduler::spawn_thread(start, arg, name, self)
-            .and_then(|v| v.get())
-            .map(|v| v.pid)
+        Scheduler::spawn_thread(start, arg, name, self).map(|(_, process)| ChildProcess(process))
     }
 
     /// Start the closure in a new thread.
@@ -951,7 +1028,7 @@ where
             mutex: Arc::clone(&mutex),
         });
         let ptr = Box::into_raw(boxed);
-        let thread =
+        let (thread, _) =
             Scheduler::spawn_thread(Self::_start_thread, ptr as usize, name, options).unwrap();
 
         JoinHandle { thread, mutex }
@@ -1073,6 +1150,15 @@ impl Timer {
         !self.is_alive()
     }
 
+    /// Returns the time until the deadline, or `None` if it has expired or never expires.
+    pub fn remaining(&self) -> Option<Duration> {
+        if self.is_just() || self.is_forever() {
+            return None;
+        }
+        let now = Self::timer_source().measure();
+        (self.deadline > now).then(|| TimeSpec(self.deadline.0 - now.0).into())
+    }
+
     #[inline]
     pub fn repeat_until<F>(&self, mut f: F)
     where
@@ -1088,6 +1174,12 @@ impl Timer {
         TIMER_SOURCE = Some(source);
     }
 
+    /// Returns whether the timer source has been set.
+    #[inline]
+    pub fn is_available() -> bool {
+        unsafe { TIMER_SOURCE.is_some() }
+    }
+
     fn timer_source<'a>() -> &'a Box<dyn TimerSource> {
         unsafe { TIMER_SOURCE.as_ref().unwrap() }
     }
@@ -1125,6 +1217,47 @@ impl Timer {
     pub fn monotonic() -> Duration {
         Duration::from_millis(Self::timer_source().monotonic())
     }
+
+    /// Creates a timer that ticks on a fixed cadence starting from now.
+    #[inline]
+    pub fn every(interval: Duration) -> PeriodicTimer {
+        PeriodicTimer::new(interval)
+    }
+}
+
+/// A timer that ticks at a fixed interval
+///
+/// If a tick is missed, the next `tick()` completes immediately to catch up.
+#[derive(Debug, Clone, Copy)]
+pub struct PeriodicTimer {
+    cadence: Cadence,
+}
+
+impl PeriodicTimer {
+    #[inline]
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            cadence: Cadence::new(interval, Timer::monotonic()),
+        }
+    }
+
+    #[inline]
+    pub const fn interval(&self) -> Duration {
+        self.cadence.interval()
+    }
+
+    /// Returns the monotonic time of the next tick.
+    #[inline]
+    pub const fn deadline(&self) -> Duration {
+        self.cadence.deadline()
+    }
+
+    /// Waits for the next tick.
+    pub async fn tick(&mut self) {
+        if let Some(remaining) = self.cadence.advance(Timer::monotonic()) {
+            Timer::sleep_async(remaining).await;
+        }
+    }
 }
 
 impl From<usize> for Timer {
@@ -1259,12 +1392,27 @@ pub enum Priority {
 }
 
 impl Priority {
+    /// Number of the priority levels
+    const LEVELS: usize = Self::Realtime as usize + 1;
+
     pub fn is_useful(self) -> bool {
         match self {
             Priority::Idle => false,
             _ => true,
         }
     }
+
+    #[inline]
+    const fn from_usize(value: usize) -> Option<Self> {
+        match value {
+            0 => Some(Self::Idle),
+            1 => Some(Self::Low),
+            2 => Some(Self::Normal),
+            3 => Some(Self::High),
+            4 => Some(Self::Realtime),
+            _ => None,
+        }
+    }
 }
 
 impl Default for Priority {
@@ -1274,6 +1422,91 @@ impl Default for Priority {
     }
 }
 
+/// A set of processors on which a thread is allowed to run.
+#[repr(transparent)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct CpuMask(usize);
+
+impl CpuMask {
+    pub const EMPTY: Self = Self(0);
+    pub const ALL: Self = Self(usize::MAX);
+
+    #[inline]
+    pub const fn from_bits(bits: usize) -> Self {
+        Self(bits)
+    }
+
+    #[inline]
+    pub const fn bits(&self) -> usize {
+        self.0
+    }
+
+    /// Returns a mask containing only the specified processor.
+    #[inline]
+    pub const fn single(index: ProcessorIndex) -> Self {
+        if index.0 < usize::BITS as usize {
+            Self(1 << index.0)
+        } else {
+            Self::EMPTY
+        }
+    }
+
+    /// Returns a mask containing all the available processors.
+    #[inline]
+    pub fn available() -> Self {
+        let num_of_cpus = System::current_device().num_of_logical_cpus();
+        if num_of_cpus < usize::BITS as usize {
+            Self((1 << num_of_cpus) - 1)
+        } else {
+            Self::ALL
+        }
+    }
+
+    #[inline]
+    pub const fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    #[inline]
+    pub const fn contains(&self, index: ProcessorIndex) -> bool {
+        if index.0 < usize::BITS as usize {
+            (self.0 & (1 << index.0)) != 0
+        } else {
+            self.0 == usize::MAX
+        }
+    }
+
+    #[inline]
+    pub const fn with(self, index: ProcessorIndex) -> Self {
+        Self(self.0 | Self::single(index).0)
+    }
+}
+
+impl Default for CpuMask {
+    #[inline]
+    fn default() -> Self {
+        Self::ALL
+    }
+}
+
+impl BitAnd<Self> for CpuMask {
+    type Output = Self;
+
+    #[inline]
+    fn bitand(self, rhs: Self) -> Self::Output {
+        Self(self.0 & rhs.0)
+    }
+}
+
+impl BitOr<Self> for CpuMask {
+    type Output = Self;
+
+    #[inline]
+    fn bitor(self, rhs: Self) -> Self::Output {
+        Self(self.0 | rhs.0)
+    }
+}
+
 pub struct Quantum {
     current: AtomicU8,
     default: u8,
@@ -1434,6 +1667,12 @@ impl ProcessId {
             .unwrap_or("".to_owned())
     }
 
+    /// Sets the exit code that the parent receives from [`ChildProcess::join`].
+    #[inline]
+    pub fn set_exit_code(&self, exit_code: i32) {
+        self.get().map(|v| v.exit_status.set_code(exit_code));
+    }
+
     #[inline]
     pub fn set_cwd(&self, path: &str) {
         self.get()
@@ -1448,6 +1687,22 @@ impl From<ProcessId> for usize {
     }
 }
 
+/// A process started by [`SpawnOption::start_process`]
+pub struct ChildProcess(Arc<ProcessContextData>);
+
+impl ChildProcess {
+    #[inline]
+    pub fn pid(&self) -> ProcessId {
+        self.0.pid
+    }
+
+    /// Waits for the process to exit and returns its exit code.
+    #[inline]
+    pub fn join(self) -> i32 {
+        self.0.exit_status.join(|| self.0.sem.wait())
+    }
+}
+
 #[allow(dead_code)]
 struct ProcessContextData {
     name: String,
@@ -1457,6 +1712,7 @@ struct ProcessContextData {
     n_threads: AtomicUsize,
     priority: Priority,
     sem: Semaphore,
+    exit_status: ExitStatus,
 
     start_time: TimeSpec,
     cpu_time: AtomicUsize,
@@ -1476,6 +1732,7 @@ impl ProcessContextData {
             n_threads: AtomicUsize::new(0),
             priority,
             sem: Semaphore::new(0),
+            exit_status: ExitStatus::new(),
             start_time: Timer::monotonic().into(),
             cpu_time: AtomicUsize::new(0),
             load0: AtomicU32::new(0),
@@ -1495,6 +1752,7 @@ impl ProcessContextData {
     }
 
     fn exit(&self) {
+        self.exit_status.exit();
         self.sem.signal();
         ProcessPool::shared().remove(self.pid);
     }
@@ -1552,6 +1810,40 @@ impl ThreadHandle {
         self.get().map(|v| v.name())
     }
 
+    /// Returns the address range of the stack allocated for the thread.
+    ///
+    /// Returns `None` for the threads running on the stack given by the boot loader.
+    #[inline]
+    pub fn stack_range(&self) -> Option<Range<usize>> {
+        let stack = self.get()?.stack.as_ref()?.as_ptr_range();
+        Some(stack.start as usize..stack.end as usize)
+    }
+
+    /// Returns the priority used for scheduling, including any inherited priority.
+    #[inline]
+    pub fn priority(&self) -> Option<Priority> {
+        self.get().map(|v| v.effective_priority())
+    }
+
+    /// Temporarily raises the priority of the thread through a lock held by this thread.
+    ///
+    /// This is used for priority inheritance, when a thread with a higher priority
+    /// is waiting for the lock.
+    #[inline]
+    pub fn inherit_priority(&self, lock: &InheritedLevel, priority: Priority) {
+        let Some(thread) = self.get() else { return };
+        lock.inherit(&thread.inherited_priority, priority as usize);
+    }
+
+    /// Drops the priority inherited through a lock by [ThreadHandle::inherit_priority].
+    ///
+    /// The priorities inherited through the other locks still held are kept.
+    #[inline]
+    pub fn restore_priority(&self, lock: &InheritedLevel) {
+        let Some(thread) = self.get() else { return };
+        lock.restore(&thread.inherited_priority);
+    }
+
     #[inline]
     pub fn wake(&self) {
         let Some(thread) = self.get() else { return };
@@ -1564,19 +1856,66 @@ impl ThreadHandle {
         self.get().map(|thread| thread.sem.wait());
     }
 
+    /// Terminates the thread forcibly.
+    ///
+    /// The locks held by the thread are not released, so this is the last resort for hung threads.
+    pub fn kill(&self) -> Result<(), ()> {
+        let Some(thread) = self.get() else {
+            return Err(());
+        };
+        if thread.priority == Priority::Idle || Scheduler::current_thread() == Some(*self) {
+            return Err(());
+        }
+        if thread.attribute.fetch_set(ThreadAttribute::ZOMBIE) {
+            // Already exiting or killed, the exit has been accounted for
+            return Err(());
+        }
+
+        thread.sem.signal();
+        if let Some(process) = thread.pid.get() {
+            if process.n_threads.fetch_sub(1, Ordering::SeqCst) == 1 {
+                process.exit();
+            }
+        }
+        // Threads in the queue or running will be removed when they are switched
+        if !thread.attribute.contains(ThreadAttribute::QUEUED) {
+            ThreadPool::remove(*self);
+        }
+        Ok(())
+    }
+
     #[inline]
     pub fn strong_affinity(&self) -> Option<ProcessorIndex> {
         self.get().and_then(|v| v.strong_affinity)
     }
 
+    #[inline]
+    pub fn affinity(&self) -> CpuMask {
+        self.get()
+            .map(|v| CpuMask::from_bits(v.affinity.load(Ordering::Relaxed)))
+            .unwrap_or(CpuMask::ALL)
+    }
+
+    /// Returns the total time the thread has been running on any processor.
+    #[inline]
+    pub fn cpu_time(&self) -> Duration {
+        self.get()
+            .map(|v| Duration::from_micros(v.stats.cpu_time() as u64))
+            .unwrap_or_default()
+    }
+
+    /// Returns the number of times the thread has been switched in.
+    #[inline]
+    pub fn context_switches(&self) -> usize {
+        self.get()
+            .map(|v| v.stats.context_switches())
+            .unwrap_or_default()
+    }
+
     fn update_statistics(&self) {
         let Some(thread) = self.get() else { return };
 
-        let now = Timer::measure_deprecated().0 as usize;
-        let then = thread.measure.swap(now, Ordering::SeqCst);
-        let diff = now - then;
-        thread.cpu_time.fetch_add(diff, Ordering::SeqCst);
-        thread.load0.fetch_add(diff as u32, Ordering::SeqCst);
+        thread.stats.update(Timer::measure_deprecated().0 as usize);
     }
 }
 
@@ -1599,18 +1938,21 @@ struct ThreadContextData {
     attribute: AtomicFlags<ThreadAttribute>,
     sleep_counter: AtomicIsize,
     priority: Priority,
+    /// Priorities inherited through the locks held by the thread
+    inherited_priority: LevelCounts<{ Priority::LEVELS }>,
     strong_affinity: Option<ProcessorIndex>,
+    affinity: AtomicUsize,
     quantum: Quantum,
 
     // Statistics
-    measure: AtomicUsize,
-    cpu_time: AtomicUsize,
-    load0: AtomicU32,
-    load: AtomicU32,
+    stats: ThreadStatistics,
 
     // Executor
     executor: Option<Executor>,
 
+    // Thread Local Storage
+    tls: TlsSlots,
+
     // Thread Name
     name: Sb255,
 }
@@ -1646,6 +1988,7 @@ impl ThreadContextData {
         pid: ProcessId,
         priority: Priority,
         strong_affinity: Option<ProcessorIndex>,
+        affinity: CpuMask,
         name: &str,
         start: Option<(ThreadStart, usize)>,
         personality: Option<PersonalityContext>,
@@ -1667,13 +2010,13 @@ impl ThreadContextData {
             attribute: AtomicFlags::empty(),
             sleep_counter: AtomicIsize::new(0),
             priority,
+            inherited_priority: LevelCounts::new(),
             strong_affinity,
+            affinity: AtomicUsize::new(affinity.bits()),
             quantum: Quantum::from(priority),
-            measure: AtomicUsize::new(0),
-            cpu_time: AtomicUsize::new(0),
-            load0: AtomicU32::new(0),
-            load: AtomicU32::new(0),
+            stats: ThreadStatistics::new(),
             executor: None,
+            tls: TlsSlots::new(),
             personality: personality.map(|v| UnsafeCell::new(v)),
             name,
         };
@@ -1697,17 +2040,22 @@ impl ThreadContextData {
     fn exit(&mut self) -> ! {
         Scheduler::yield_thread();
 
+        // Drop the thread local values in the context of this thread
+        self.tls.clear();
+
         self.sem.signal();
         if let Some(context) = self.personality.take() {
             context.into_inner().on_exit();
         }
 
-        let process = self.pid.get().unwrap();
-        if process.n_threads.fetch_sub(1, Ordering::SeqCst) == 1 {
-            process.exit();
+        // Whoever sets the flag first, this or ThreadHandle::kill, accounts for the exit
+        if !self.attribute.fetch_set(ThreadAttribute::ZOMBIE) {
+            let process = self.pid.get().unwrap();
+            if process.n_threads.fetch_sub(1, Ordering::SeqCst) == 1 {
+                process.exit();
+            }
         }
 
-        self.attribute.insert(ThreadAttribute::ZOMBIE);
         Scheduler::sleep_thread();
         unreachable!();
     }
@@ -1717,6 +2065,15 @@ impl ThreadContextData {
         self.sleep_counter.load(Ordering::Relaxed) > 0
     }
 
+    /// Returns the higher of the base priority and the inherited priorities.
+    #[inline]
+    fn effective_priority(&self) -> Priority {
+        self.inherited_priority
+            .max()
+            .and_then(Priority::from_usize)
+            .map_or(self.priority, |v| v.max(self.priority))
+    }
+
     fn name(&self) -> String {
         self.name.as_str().to_owned()
     }
@@ -1737,8 +2094,34 @@ impl ThreadQueue {
     }
 
     #[inline]
-    fn enqueue(&self, data: ThreadHandle) -> Result<(), ()> {
-        self.0.enqueue(data).map_err(|_| ())
+    fn enqueue(&self, data: ThreadHandle) -> Result<(), ThreadHandle> {
+        self.0.enqueue(data)
+    }
+
+    /// Dequeues the first thread that is allowed to run on the specified processor.
+    ///
+    /// Threads that are not allowed are put back at the end of the queue.
+    fn dequeue_for(&self, index: ProcessorIndex) -> Option<ThreadHandle> {
+        const MAX_SKIPS: usize = 8;
+        queue::take_selected(
+            MAX_SKIPS,
+            || self.dequeue(),
+            |handle| self.enqueue(handle),
+            |handle| {
+                let Some(thread) = handle.get() else {
+                    return Selection::Discard;
+                };
+                // Killed threads are removed here instead of being switched in
+                if thread.attribute.contains(ThreadAttribute::ZOMBIE) {
+                    ThreadPool::remove(*handle);
+                    Selection::Discard
+                } else if handle.affinity().contains(index) {
+                    Selection::Take
+                } else {
+                    Selection::Skip
+                }
+            },
+        )
     }
 }
 
@@ -0,0 +1,44 @@
+//! Thread Local Storage
+
+use super::scheduler::Scheduler;
+use mytask::tls::TlsKey;
+
+/// A key of the thread local storage
+///
+/// Each thread has its own value, which is initialized lazily on the first access in the thread.
+///
+/// ```ignore
+/// static COUNTER: Key<Cell<usize>> = Key::new(|| Cell::new(0));
+///
+/// COUNTER.with(|v| v.set(v.get() + 1));
+/// ```
+pub struct Key<T: Send + 'static>(TlsKey<T>);
+
+impl<T: Send + 'static> Key<T> {
+    #[inline]
+    pub const fn new(init: fn() -> T) -> Self {
+        Self(TlsKey::new(init))
+    }
+
+    /// Calls the function with the value of the current thread.
+    ///
+    /// # Panics
+    ///
+    /// Panics if it is called outside of the threads managed by the scheduler.
+    pub fn with<F, R>(&'static self, f: F) -> R
+    where
+        F: FnOnce(&T) -> R,
+    {
+        let value = match Scheduler::current_tls_slots().get(&self.0) {
+            Some(value) => value as *const T,
+            None => {
+                // The initializer may access the other keys
+                let value = self.0.init_value();
+                Scheduler::current_tls_slots().insert(&self.0, value) as *const T
+            }
+        };
+        // The boxed value does not move while the slots are modified,
+        // and it is dropped only when the thread exits.
+        f(unsafe { &*value })
+    }
+}
@@ -0,0 +1,99 @@
+//! Watchdog Timer
+
+use super::scheduler::{Scheduler, ThreadHandle, Timer};
+use crate::{sync::Mutex, *};
+use core::time::Duration;
+use megstd::{time::WatchdogDeadline, Arc, Vec, Weak};
+
+static WATCHDOGS: Mutex<Vec<Weak<Watchdog>>> = Mutex::new(Vec::new());
+
+/// Action taken when the watchdog is not petted in time
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WatchdogAction {
+    /// Logs the hung thread only
+    Log,
+    /// Logs and kills the hung thread
+    Kill,
+}
+
+/// A watchdog timer that the thread must pet within the timeout
+///
+/// The watchdogs are checked periodically by the scheduler,
+/// and it is unregistered when dropped.
+pub struct Watchdog {
+    thread: ThreadHandle,
+    action: WatchdogAction,
+    deadline: WatchdogDeadline,
+}
+
+impl Watchdog {
+    /// Creates and registers a watchdog for the current thread.
+    pub fn new(timeout: Duration, action: WatchdogAction) -> Arc<Self> {
+        let watchdog = Arc::new(Self {
+            thread: Scheduler::current_thread().unwrap(),
+            action,
+            deadline: WatchdogDeadline::new(timeout, Timer::monotonic()),
+        });
+        let mut watchdogs = WATCHDOGS.lock().unwrap();
+        watchdogs.retain(|v| v.strong_count() > 0);
+        watchdogs.push(Arc::downgrade(&watchdog));
+        watchdog
+    }
+
+    #[inline]
+    pub const fn thread(&self) -> ThreadHandle {
+        self.thread
+    }
+
+    #[inline]
+    pub const fn timeout(&self) -> Duration {
+        self.deadline.timeout()
+    }
+
+    /// Resets the deadline, and resumes if suspended.
+    #[inline]
+    pub fn pet(&self) {
+        self.deadline.pet(Timer::monotonic());
+    }
+
+    /// Stops checking until the next [`Self::pet`], while the thread is expected to block.
+    #[inline]
+    pub fn suspend(&self) {
+        self.deadline.suspend();
+    }
+
+    /// Returns whether the thread has missed the deadline.
+    #[inline]
+    pub fn is_hung(&self) -> bool {
+        self.deadline.is_hung()
+    }
+
+    /// Checks the deadline, and takes the action when the thread is newly found hung.
+    fn check(&self) {
+        if !self.deadline.check(Timer::monotonic()) {
+            return;
+        }
+        log!(
+            "WATCHDOG: thread {} ({}) did not respond within {} ms",
+            self.thread.as_usize(),
+            self.thread.name().unwrap_or_default(),
+            self.timeout().as_millis(),
+        );
+        if self.action == WatchdogAction::Kill && self.thread.kill().is_ok() {
+            log!("WATCHDOG: thread {} killed", self.thread.as_usize());
+        }
+    }
+
+    /// Checks all watchdogs, called by the scheduler.
+    pub(super) fn check_all() {
+        let watchdogs = WATCHDOGS
+            .lock()
+            .unwrap()
+            .iter()
+            .filter_map(|v| v.upgrade())
+            .collect::<Vec<_>>();
+        for watchdog in watchdogs {
+            watchdog.check();
+        }
+    }
+}
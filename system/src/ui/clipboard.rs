@@ -0,0 +1,41 @@
+//! Clipboard
+
+use crate::sync::Mutex;
+use megstd::{String, ToOwned};
+
+static CLIPBOARD: Clipboard = Clipboard::new();
+
+/// System-wide clipboard holding a text
+pub struct Clipboard {
+    text: Mutex<String>,
+}
+
+impl Clipboard {
+    #[inline]
+    const fn new() -> Self {
+        Self {
+            text: Mutex::new(String::new()),
+        }
+    }
+
+    #[inline]
+    pub fn shared<'a>() -> &'a Self {
+        &CLIPBOARD
+    }
+
+    /// Returns the text in the clipboard, or an empty string if the clipboard is empty.
+    #[inline]
+    pub fn text(&self) -> String {
+        self.text.lock().unwrap().clone()
+    }
+
+    #[inline]
+    pub fn set_text(&self, text: &str) {
+        *self.text.lock().unwrap() = text.to_owned();
+    }
+
+    #[inline]
+    pub fn clear(&self) {
+        self.text.lock().unwrap().clear();
+    }
+}
@@ -4,9 +4,11 @@ use alloc::collections::BTreeMap;
 use core::{cell::UnsafeCell, mem::MaybeUninit};
 use megstd::{drawing::*, io::Read, Arc, Vec};
 
+pub use megstd::drawing::font::FixedFontDriver;
+
 #[allow(dead_code)]
 mod embedded {
-    include!("megh0816.rs");
+    pub use megstd::drawing::font::*;
     // include!("megh0810.rs");
     // include!("megg0808.rs");
     include!("megh0710.rs");
@@ -217,6 +219,25 @@ impl FontDescriptor {
         self.driver.is_scalable()
     }
 
+    /// Returns the size of the string drawn with this font, including kerning.
+    ///
+    /// Each `\n` starts a new line.
+    pub fn measure_string(&self, text: &str) -> Size {
+        let mut width = 0;
+        let mut lines = 0;
+        for line in text.split('\n') {
+            width = isize::max(width, self.advance_of(line));
+            lines += 1;
+        }
+        Size::new(width, self.line_height() * lines)
+    }
+
+    /// Returns the horizontal advance of the single line string.
+    #[inline]
+    pub fn advance_of(&self, text: &str) -> isize {
+        megstd::drawing::font::advance_of(text, |c| self.width_of(c), |a, b| self.kern(a, b))
+    }
+
     #[inline]
     pub fn draw_char(
         &self,
@@ -251,67 +272,6 @@ pub trait FontDriver {
     );
 }
 
-pub struct FixedFontDriver<'a> {
-    size: Size,
-    data: &'a [u8],
-    offset: Movement,
-    line_height: isize,
-    stride: usize,
-}
-
-impl FixedFontDriver<'_> {
-    pub const fn new(width: usize, height: usize, data: &'static [u8]) -> FixedFontDriver<'static> {
-        let width = width as isize;
-        let height = height as isize;
-        let line_height = height * 5 / 4;
-        let offset = Movement::new(0, (line_height - height) / 2);
-        let stride = ((width as usize + 7) >> 3) * height as usize;
-        FixedFontDriver {
-            size: Size::new(width, height),
-            offset,
-            line_height,
-            stride,
-            data,
-        }
-    }
-
-    #[inline]
-    pub const fn width(&self) -> isize {
-        self.size.width
-    }
-
-    #[inline]
-    pub const fn line_height(&self) -> isize {
-        self.line_height
-    }
-
-    /// Glyph Data for Rasterized Font
-    #[inline]
-    fn glyph_for(&self, character: char) -> Option<&[u8]> {
-        let c = character as usize;
-        if c > 0x20 && c < 0x80 {
-            let base = self.stride * (c - 0x20);
-            Some(&self.data[base..base + self.stride])
-        } else {
-            None
-        }
-    }
-
-    #[inline]
-    pub fn draw_glyph<F>(&self, character: char, origin: Point, f: F)
-    where
-        F: FnOnce(&[u8], Size, Point),
-    {
-        if let Some(glyph) = self.glyph_for(character) {
-            f(
-                glyph,
-                Size::new(self.width_of(character), self.size.height()),
-                origin + self.offset,
-            );
-        }
-    }
-}
-
 impl FontDriver for FixedFontDriver<'_> {
     #[inline]
     fn is_scalable(&self) -> bool {
@@ -320,17 +280,17 @@ impl FontDriver for FixedFontDriver<'_> {
 
     #[inline]
     fn base_height(&self) -> isize {
-        self.size.height
+        self.size().height
     }
 
     #[inline]
     fn preferred_line_height(&self) -> isize {
-        self.line_height
+        self.line_height()
     }
 
     #[inline]
     fn width_of(&self, _character: char) -> isize {
-        self.size.width
+        self.width()
     }
 
     fn kern(&self, _first: char, _second: char) -> isize {
@@ -1,13 +1,15 @@
-use crate::{io::tty::*, ui::font::*, ui::window::*, *};
+use crate::{io::ansi::*, io::tty::*, sync::Mutex, ui::font::*, ui::window::*, *};
 use alloc::boxed::Box;
 use core::{
     fmt::Write,
     future::Future,
+    ops::Range,
     pin::Pin,
     sync::atomic::{AtomicUsize, Ordering},
     task::{Context, Poll},
 };
-use megstd::drawing::*;
+use megstd::{drawing::*, io::hid::Usage};
+use mywm::scrollback::LineBuffer;
 
 const DEFAULT_INSETS: EdgeInsets = EdgeInsets::new(0, 0, 0, 0);
 // const DEFAULT_ATTRIBUTE: u8 = 0x07;
@@ -69,6 +71,12 @@ pub struct Terminal {
     is_cursor_enabled: bool,
     font_cache: Option<OwnedBitmap32>,
     palette: [TrueColor; 16],
+    glyph_set: GlyphSet,
+    /// Characters and attributes of the scrollback followed by the screen
+    ///
+    /// Locked as the reader redraws them on scrolling while the writer updates them.
+    lines: Mutex<LineBuffer>,
+    ansi: AnsiParser,
 }
 
 impl Terminal {
@@ -91,6 +99,8 @@ impl Terminal {
         TrueColor::WHITE,
     ];
 
+    pub const DEFAULT_SCROLLBACK_LIMIT: usize = 500;
+
     pub fn from_window(
         window: WindowHandle,
         insets: Option<EdgeInsets>,
@@ -133,6 +143,9 @@ impl Terminal {
             is_cursor_enabled: true,
             font_cache: Self::_fill_cache(&font),
             palette,
+            glyph_set: GlyphSet::default(),
+            lines: Mutex::new(LineBuffer::new(rows, Self::DEFAULT_SCROLLBACK_LIMIT)),
+            ansi: AnsiParser::new(),
         }
     }
 
@@ -182,6 +195,9 @@ impl Terminal {
             is_cursor_enabled: true,
             font_cache: Self::_fill_cache(&font),
             palette,
+            glyph_set: GlyphSet::default(),
+            lines: Mutex::new(LineBuffer::new(rows, Self::DEFAULT_SCROLLBACK_LIMIT)),
+            ansi: AnsiParser::new(),
         }
     }
 
@@ -215,7 +231,69 @@ impl Terminal {
         )
     }
 
-    fn scroll_up(&mut self) {
+    /// Number of lines that have scrolled off the top of the screen
+    #[inline]
+    pub fn scrollback_len(&self) -> usize {
+        self.lines.lock().unwrap().scrollback_len()
+    }
+
+    /// Sets the maximum number of lines kept in the scrollback.
+    pub fn set_scrollback_limit(&mut self, limit: usize) {
+        self.scroll_to_bottom();
+        self.lines.lock().unwrap().set_limit(limit);
+    }
+
+    /// Scrolls the view back to earlier lines.
+    pub fn scroll_up(&self, lines: usize) {
+        let moved = self.lines.lock().unwrap().scroll_up(lines);
+        self.did_scroll(moved);
+    }
+
+    /// Scrolls the view towards the latest lines.
+    pub fn scroll_down(&self, lines: usize) {
+        let moved = self.lines.lock().unwrap().scroll_down(lines);
+        self.did_scroll(moved);
+    }
+
+    /// Scrolls the view back to the screen being written.
+    pub fn scroll_to_bottom(&self) {
+        let moved = self.lines.lock().unwrap().scroll_to_bottom();
+        self.did_scroll(moved);
+    }
+
+    fn did_scroll(&self, moved: bool) {
+        if moved {
+            self.redraw_lines();
+            self.set_needs_update_cursor();
+        }
+    }
+
+    /// Redraws the whole screen from the lines at the current scroll position.
+    fn redraw_lines(&self) {
+        let w = self.font.em_width();
+        let h = self.font.line_height();
+        let lines = self.lines.lock().unwrap();
+
+        let frame = Rect::from(self.window.content_size()).insets_by(self.insets);
+        self.window
+            .draw_in_rect(frame, |bitmap| {
+                bitmap.fill_rect(bitmap.bounds(), self.bg_color);
+                for (y, line) in lines.view().enumerate() {
+                    for (x, &(c, attribute)) in line.iter().enumerate() {
+                        let rect = Rect::new(w * x as isize, h * y as isize, w, h);
+                        let (fg_color, bg_color) = self.split_attr(attribute, self.alpha);
+                        bitmap.fill_rect(rect, bg_color);
+                        self.font.draw_char(c, bitmap, rect.origin, fg_color);
+                    }
+                }
+            })
+            .unwrap();
+        self.window.set_needs_display();
+    }
+
+    fn scroll_screen(&mut self) {
+        self.lines.lock().unwrap().push_line();
+
         let h = self.font.line_height();
 
         let frame = Rect::from(self.window.content_size()).insets_by(self.insets);
@@ -252,12 +330,13 @@ impl Terminal {
                 self.x = 0;
                 self.y += 1;
                 while self.y >= self.rows {
-                    self.scroll_up();
+                    self.scroll_screen();
                     self.y -= 1;
                 }
                 None
             }
             _ => {
+                let c = self.glyph_set.map_char(c);
                 let w = self.font.em_width();
                 let h = self.font.line_height();
 
@@ -266,10 +345,19 @@ impl Terminal {
                     self.y += 1;
                 }
                 if self.y >= self.rows {
-                    self.scroll_up();
+                    self.scroll_screen();
                     self.y = self.rows - 1;
                 }
 
+                {
+                    let mut lines = self.lines.lock().unwrap();
+                    let line = lines.line_mut(self.y);
+                    if line.len() <= self.x {
+                        line.resize(self.x + 1, (' ', self.default_attribute));
+                    }
+                    line[self.x] = (c, self.attribute);
+                }
+
                 let rect = Rect::new(
                     self.insets.left + self.x as isize * w,
                     self.insets.top + self.y as isize * h,
@@ -303,10 +391,13 @@ impl Terminal {
     }
 
     fn put_str(&mut self, s: &str) {
+        self.scroll_to_bottom();
         let old_cursor = self.set_cursor_enabled(false);
         let mut coords: Option<Coordinates> = None;
         for c in s.chars() {
-            self.put_char(c)
+            self.ansi
+                .feed(c)
+                .and_then(|event| self.execute_ansi(event))
                 .and_then(|v| Coordinates::from_rect(v).ok())
                 .map(|c2| match &mut coords {
                     Some(v) => *v += c2,
@@ -319,11 +410,89 @@ impl Terminal {
         }
     }
 
-    fn set_needs_update_cursor(&mut self) {
+    fn execute_ansi(&mut self, event: AnsiEvent) -> Option<Rect> {
+        let max_x = self.cols.saturating_sub(1);
+        let max_y = self.rows.saturating_sub(1);
+        match event {
+            AnsiEvent::Char(c) => return self.put_char(c),
+            AnsiEvent::CursorUp(n) => self.y = self.y.min(max_y).saturating_sub(n),
+            AnsiEvent::CursorDown(n) => self.y = (self.y + n).min(max_y),
+            AnsiEvent::CursorForward(n) => self.x = (self.x + n).min(max_x),
+            AnsiEvent::CursorBack(n) => self.x = self.x.min(max_x).saturating_sub(n),
+            AnsiEvent::CursorPosition(y, x) => {
+                self.x = x.min(max_x);
+                self.y = y.min(max_y);
+            }
+            AnsiEvent::EraseDisplay(mode) => {
+                let (x, y) = (self.x.min(self.cols), self.y.min(max_y));
+                let (above, below, current) = match mode {
+                    0 => (0..0, y + 1..self.rows, x..self.cols),
+                    1 => (0..y, 0..0, 0..(x + 1).min(self.cols)),
+                    _ => (0..y, y + 1..self.rows, 0..self.cols),
+                };
+                for line in above.chain(below) {
+                    self.erase_cells(line, 0..self.cols);
+                }
+                self.erase_cells(y, current);
+                return Some(self.window.content_size().into());
+            }
+            AnsiEvent::EraseLine(mode) => {
+                let (x, y) = (self.x.min(self.cols), self.y.min(max_y));
+                let range = match mode {
+                    0 => x..self.cols,
+                    1 => 0..(x + 1).min(self.cols),
+                    _ => 0..self.cols,
+                };
+                return self.erase_cells(y, range);
+            }
+            AnsiEvent::SelectGraphicRendition(params) => {
+                self.set_attribute(params.apply_sgr(self.attribute, self.default_attribute));
+            }
+            AnsiEvent::SelectGlyphSet(glyph_set) => self.glyph_set = glyph_set,
+        }
+        None
+    }
+
+    /// Fills the cells with spaces of the current attribute.
+    fn erase_cells(&mut self, y: usize, range: Range<usize>) -> Option<Rect> {
+        if range.is_empty() {
+            return None;
+        }
+        let w = self.font.em_width();
+        let h = self.font.line_height();
+
+        {
+            let mut lines = self.lines.lock().unwrap();
+            let line = lines.line_mut(y);
+            if line.len() < range.end {
+                line.resize(range.end, (' ', self.default_attribute));
+            }
+            line[range.clone()].fill((' ', self.attribute));
+        }
+
+        let rect = Rect::new(
+            self.insets.left + w * range.start as isize,
+            self.insets.top + h * y as isize,
+            w * range.len() as isize,
+            h,
+        );
+        let (_, bg_color) = self.split_attr(self.attribute, self.alpha);
+        self.window
+            .draw_in_rect(rect, |bitmap| {
+                bitmap.fill_rect(bitmap.bounds(), bg_color);
+            })
+            .unwrap();
+        Some(rect)
+    }
+
+    fn set_needs_update_cursor(&self) {
         let w = self.font.em_width();
         let h = self.font.line_height();
         let dims = self.dims();
-        if self.x >= dims.0 as usize || self.y >= dims.1 as usize {
+        if self.x >= dims.0 as usize
+            || self.y >= dims.1 as usize
+            || self.lines.lock().unwrap().offset() > 0
+        {
             return;
         }
 
@@ -361,14 +530,15 @@ impl TtyRead for Terminal {
     fn read_async(
         &self,
     ) -> core::pin::Pin<Box<dyn core::future::Future<Output = TtyReadResult> + '_>> {
-        Box::pin(ConsoleReader {
-            window: self.window,
-        })
+        Box::pin(ConsoleReader { terminal: self })
     }
 }
 
 impl TtyWrite for Terminal {
     fn reset(&mut self) -> Result<(), TtyError> {
+        self.scroll_to_bottom();
+        self.lines.lock().unwrap().clear_screen();
+
         let rect = self.window.content_size().into();
         self.window
             .draw_in_rect(rect, |bitmap| {
@@ -423,25 +593,41 @@ impl TtyWrite for Terminal {
     fn attributes(&self) -> u8 {
         self.attribute
     }
+
+    fn glyph_set(&self) -> GlyphSet {
+        self.glyph_set
+    }
+
+    fn set_glyph_set(&mut self, glyph_set: GlyphSet) -> GlyphSet {
+        core::mem::replace(&mut self.glyph_set, glyph_set)
+    }
 }
 
 impl Tty for Terminal {}
 
-struct ConsoleReader {
-    window: WindowHandle,
+struct ConsoleReader<'a> {
+    terminal: &'a Terminal,
 }
 
-impl Future for ConsoleReader {
+impl Future for ConsoleReader<'_> {
     type Output = TtyReadResult;
 
     fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let terminal = self.terminal;
+        let window = terminal.window;
+        let page = terminal.rows.saturating_sub(1).max(1);
         loop {
-            match self.window.poll_message(cx) {
+            match window.poll_message(cx) {
                 Poll::Ready(v) => {
                     if let Some(message) = v {
                         match message {
                             WindowMessage::Char(c) => return Poll::Ready(Ok(c)),
-                            _ => self.window.handle_default_message(message),
+                            WindowMessage::Key(key) if key.is_make() => match key.usage() {
+                                Usage::KEY_PAGE_UP => terminal.scroll_up(page),
+                                Usage::KEY_PAGE_DOWN => terminal.scroll_down(page),
+                                _ => window.handle_default_message(message),
+                            },
+                            _ => window.handle_default_message(message),
                         }
                     }
                 }
@@ -3,9 +3,10 @@
 use super::font::*;
 use crate::*;
 use alloc::{borrow::Cow, vec::Vec};
-use core::num::NonZeroUsize;
 use megstd::drawing::*;
 
+pub use megstd::drawing::font::{LineBreakMode, LineStatus};
+
 pub struct AttributedString<'a> {
     text: Cow<'a, str>,
     attributes: AttributeSet,
@@ -69,7 +70,7 @@ impl AttributedString<'_> {
     }
 
     #[inline]
-    pub fn draw_text(&self, bitmap: &mut BitmapRefMut, rect: Rect, max_lines: usize) {
+    pub fn draw_text(&self, bitmap: &mut BitmapRefMut, rect: Rect, max_lines: usize) -> Point {
         TextProcessing::draw_text(
             bitmap,
             &self.text,
@@ -82,7 +83,7 @@ impl AttributedString<'_> {
             self.valign(),
             self.shadow_color(),
             self.shadow_offset(),
-        );
+        )
     }
 }
 
@@ -233,20 +234,6 @@ impl AttributeSet {
 
 pub struct TextProcessing;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum LineBreakMode {
-    NoWrap,
-    CharWrapping,
-    WordWrapping,
-    TrancatingTail,
-}
-
-impl Default for LineBreakMode {
-    fn default() -> Self {
-        Self::CharWrapping
-    }
-}
-
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum TextAlignment {
     Left,
@@ -275,34 +262,6 @@ impl Default for VerticalAlignment {
     }
 }
 
-#[derive(Debug, Clone, Copy)]
-pub struct LineStatus {
-    pub start_position: usize,
-    pub end_position: usize,
-    pub width: isize,
-    pub height: isize,
-}
-
-impl LineStatus {
-    #[inline]
-    const fn empty() -> Self {
-        Self {
-            start_position: 0,
-            end_position: 0,
-            width: 0,
-            height: 0,
-        }
-    }
-
-    #[inline]
-    fn new_line(&mut self, start_position: usize, width: isize, height: isize) {
-        self.start_position = start_position;
-        self.end_position = start_position;
-        self.width = width;
-        self.height = height;
-    }
-}
-
 impl TextProcessing {
     pub fn line_statuses(
         font: &FontDescriptor,
@@ -311,60 +270,15 @@ impl TextProcessing {
         max_lines: usize,
         line_break: LineBreakMode,
     ) -> Vec<LineStatus> {
-        let max_lines = NonZeroUsize::new(max_lines)
-            .map(|v| v.get())
-            .unwrap_or(usize::MAX);
-        let limit_max_lines = 64;
-        let mut vec = Vec::with_capacity(usize::min(max_lines, limit_max_lines));
-
-        // TODO: Line Breaking
-        let no_wrap = max_lines == 1 && line_break == LineBreakMode::NoWrap;
-
-        let mut current_line = LineStatus::empty();
-        current_line.height = font.line_height();
-        let mut current_height = current_line.height;
-        let mut prev_char = ' ';
-        for (index, c) in text.chars().enumerate() {
-            if c == '\n' {
-                current_line.end_position = index;
-                current_height += current_line.height;
-                vec.push(current_line);
-                current_line = LineStatus::empty();
-                if vec.len() >= max_lines || current_height >= size.height() {
-                    break;
-                }
-                current_line.new_line(index + 1, 0, font.line_height());
-                prev_char = ' ';
-            } else {
-                current_line.end_position = index;
-                let current_width = font.width_of(c);
-                let new_line_width = current_line.width + font.kern(prev_char, c) + current_width;
-                let line_is_over = if no_wrap {
-                    current_line.width > size.width
-                } else {
-                    current_line.width > 0 && new_line_width > size.width
-                };
-                if line_is_over {
-                    current_height += current_line.height;
-                    vec.push(current_line);
-                    current_line = LineStatus::empty();
-                    if vec.len() >= max_lines || current_height >= size.height() {
-                        break;
-                    }
-                    current_line.new_line(index, current_width, font.line_height());
-                    prev_char = ' ';
-                } else {
-                    current_line.width = new_line_width;
-                    prev_char = c;
-                }
-            }
-        }
-        if vec.len() < max_lines && current_line.width > 0 {
-            current_line.end_position += 1;
-            vec.push(current_line);
-        }
-
-        vec
+        megstd::drawing::font::line_statuses(
+            text,
+            size,
+            max_lines,
+            line_break,
+            font.line_height(),
+            |c| font.width_of(c),
+            |a, b| font.kern(a, b),
+        )
     }
 
     pub fn bounding_size(
@@ -381,7 +295,21 @@ impl TextProcessing {
         )
     }
 
+    /// Draws the text wrapped at whitespace to fit the width of the rectangle.
+    ///
+    /// Words wider than the rectangle are broken, and the lines below the rectangle are clipped.
+    pub fn draw_text_wrapped(bitmap: &mut BitmapRefMut, rect: Rect, text: &str, color: Color) {
+        AttributedString::new()
+            .line_break_mode(LineBreakMode::WordWrapping)
+            .top_left()
+            .color(color)
+            .text(text)
+            .draw_text(bitmap, rect, 0);
+    }
+
     /// Write text to bitmap
+    ///
+    /// Returns the end of the last line drawn, where the next text would follow.
     pub fn draw_text(
         bitmap: &mut BitmapRefMut,
         text: &str,
@@ -394,9 +322,9 @@ impl TextProcessing {
         valign: VerticalAlignment,
         shadow_color: Color,
         shadow_offset: Movement,
-    ) {
+    ) -> Point {
         let Ok(coords) = Coordinates::from_rect(rect) else {
-            return
+            return rect.origin();
         };
 
         // bitmap.draw_rect(rect, Color::YELLOW);
@@ -413,18 +341,20 @@ impl TextProcessing {
             VerticalAlignment::Center => coords.top + (rect.height() - perferred_height) / 2,
             VerticalAlignment::Bottom => coords.bottom - perferred_height,
         };
+        let mut end = Point::new(coords.left, cursor.y);
 
         for line in lines {
             for _ in prev_position..line.start_position {
                 let _ = chars.next();
             }
 
+            let line_x = match align {
+                TextAlignment::Leading | TextAlignment::Left => coords.left,
+                TextAlignment::Trailing | TextAlignment::Right => coords.right - line.width,
+                TextAlignment::Center => coords.left + (rect.width() - line.width) / 2,
+            };
             if line.start_position < line.end_position {
-                cursor.x = match align {
-                    TextAlignment::Leading | TextAlignment::Left => coords.left,
-                    TextAlignment::Trailing | TextAlignment::Right => coords.right - line.width,
-                    TextAlignment::Center => coords.left + (rect.width() - line.width) / 2,
-                };
+                cursor.x = line_x;
                 let mut prev_char = ' ';
 
                 for index in line.start_position..line.end_position {
@@ -463,7 +393,10 @@ impl TextProcessing {
             }
 
             prev_position = line.end_position;
+            end = Point::new(line_x + line.width, cursor.y);
             cursor.y += line.height;
         }
+
+        end
     }
 }
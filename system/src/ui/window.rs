@@ -21,7 +21,19 @@ use core::{
     time::Duration,
 };
 use futures_util::task::AtomicWaker;
-use megstd::{drawing::*, io::hid::*, sys::megos, Arc, BTreeMap, Box, String, ToOwned, Vec};
+use megstd::{
+    drawing::*,
+    io::hid::*,
+    sys::megos,
+    Arc, BTreeMap, Box, String, ToOwned, Vec,
+};
+use mywm::{
+    capture::{self, Compositor},
+    cursor::{Cursor, CursorPlacement},
+    drag::DragSession,
+    pointer::{self, PendingDelta, PointerMessage, PointerTarget},
+    zorder::{self, FocusTransition},
+};
 
 const MAX_WINDOWS: usize = 255;
 const WINDOW_SYSTEM_EVENT_QUEUE_SIZE: usize = 100;
@@ -48,16 +60,17 @@ pub struct WindowManager<'a> {
     attributes: AtomicFlags<WindowManagerAttributes>,
     system_event: ConcurrentFifo<WindowSystemEvent>,
 
-    pointer_hotspot: Movement,
+    cursor: SpinMutex<Cursor>,
     pointer_x: AtomicIsize,
     pointer_y: AtomicIsize,
     buttons: AtomicFlags<MouseButton>,
     buttons_down: AtomicFlags<MouseButton>,
     buttons_up: AtomicFlags<MouseButton>,
+    wheel: PendingDelta,
 
     screen_size: Size,
     screen_insets: SpinMutex<EdgeInsets>,
-    update_coords: SpinMutex<Coordinates>,
+    update_region: SpinMutex<DirtyRegion>,
 
     resources: Resources<'a>,
 
@@ -70,6 +83,7 @@ pub struct WindowManager<'a> {
     active: AtomicWindowHandle,
     captured: AtomicWindowHandle,
     entered: AtomicWindowHandle,
+    drag: SpinMutex<Option<DragSession<WindowHandle, Arc<[u8]>>>>,
 }
 
 #[allow(dead_code)]
@@ -158,15 +172,16 @@ impl WindowManager<'static> {
             WM = Some(Box::new(WindowManager {
                 sem_event: Semaphore::new(0),
                 attributes: AtomicFlags::default(),
-                pointer_hotspot,
+                cursor: SpinMutex::new(Cursor::new(pointer_hotspot, false)),
                 pointer_x: AtomicIsize::new(pointer_x),
                 pointer_y: AtomicIsize::new(pointer_y),
                 buttons: AtomicFlags::empty(),
                 buttons_down: AtomicFlags::empty(),
                 buttons_up: AtomicFlags::empty(),
+                wheel: PendingDelta::new(),
                 screen_size,
                 screen_insets: SpinMutex::new(EdgeInsets::default()),
-                update_coords: SpinMutex::new(Coordinates::VOID),
+                update_region: SpinMutex::new(DirtyRegion::EMPTY),
                 resources: Resources {
                     _phantom: &(),
                     close_button,
@@ -183,6 +198,7 @@ impl WindowManager<'static> {
                 active: AtomicWindowHandle::default(),
                 captured: AtomicWindowHandle::default(),
                 entered: AtomicWindowHandle::default(),
+                drag: SpinMutex::new(None),
                 system_event: ConcurrentFifo::with_capacity(WINDOW_SYSTEM_EVENT_QUEUE_SIZE),
             }));
         }
@@ -275,9 +291,9 @@ impl WindowManager<'_> {
                     .attributes
                     .contains(WindowManagerAttributes::POINTER_HIDE_TEMP)
                 {
-                    shared.pointer.show();
+                    shared.show_cursor(true);
                 } else {
-                    shared.pointer.hide();
+                    shared.show_cursor(false);
                 }
             }
             if shared
@@ -290,6 +306,12 @@ impl WindowManager<'_> {
                     let buttons_down = shared.buttons_down.swap(MouseButton::empty());
                     let buttons_up = shared.buttons_up.swap(MouseButton::empty());
 
+                    let wheel_target = shared
+                        .captured
+                        .get()
+                        .unwrap_or_else(|| Self::window_at_point(position));
+                    let _ = pointer::post_wheel(&wheel_target, &shared.wheel);
+
                     if let Some(captured) = shared.captured.get() {
                         if current_buttons.contains(MouseButton::PRIMARY) {
                             if shared
@@ -341,6 +363,7 @@ impl WindowManager<'_> {
                                     buttons_down,
                                     buttons_up,
                                 );
+                                shared.drag_over(position, current_buttons);
                             }
                         } else {
                             if shared
@@ -371,6 +394,7 @@ impl WindowManager<'_> {
                                     buttons_down,
                                     buttons_up,
                                 );
+                                shared.drop_drag(position, current_buttons);
                             }
 
                             shared.captured.reset();
@@ -473,19 +497,25 @@ impl WindowManager<'_> {
                         }
                     }
 
-                    shared.pointer.move_to(position - shared.pointer_hotspot);
+                    shared.update_cursor(position);
                 }
             }
             if shared
                 .attributes
                 .fetch_reset(WindowManagerAttributes::NEEDS_REDRAW)
             {
-                let mut update_coords = shared.update_coords.lock();
-                if update_coords.is_valid() {
-                    let coords = *update_coords;
-                    *update_coords = Coordinates::VOID;
-                    drop(update_coords);
-                    shared.root.as_ref().draw_inner_to_screen(coords.into());
+                let update_region = shared.update_region.lock().take();
+                for rect in update_region.rects() {
+                    shared.root.as_ref().draw_inner_to_screen(rect);
+                }
+
+                let window_orders = shared.window_orders.read().unwrap().clone();
+                for handle in window_orders {
+                    let Some(window) = handle.get() else { continue };
+                    let damage = window.damage.lock().take();
+                    for rect in damage.rects() {
+                        window.draw_inner_to_screen(rect);
+                    }
                 }
             }
         }
@@ -512,34 +542,53 @@ impl WindowManager<'_> {
         down: MouseButton,
         up: MouseButton,
     ) -> Result<(), WindowPostError> {
+        let point = Self::content_point(target, position);
+        pointer::post_buttons(&target, point, buttons, down, up)
+    }
+
+    /// Converts the screen coordinates to the content coordinates of the window.
+    fn content_point(target: WindowHandle, position: Point) -> Point {
         let window = target.as_ref();
         let origin = window.frame.insets_by(window.content_insets).origin();
-        let point = Point::new(position.x - origin.x, position.y - origin.y);
+        Point::new(position.x - origin.x, position.y - origin.y)
+    }
 
-        if down.is_empty() && up.is_empty() {
-            return target.post(WindowMessage::MouseMove(MouseEvent::new(
+    /// Tracks the window under the pointer while dragging, and notifies it if it accepts drops.
+    fn drag_over(&self, position: Point, buttons: MouseButton) {
+        let mut drag = self.drag.lock();
+        let Some(session) = drag.as_mut() else {
+            return;
+        };
+        let target = Self::window_at_point(position);
+        let accepts_drop = target.as_ref().style.contains(WindowStyle::ACCEPTS_DROP);
+        let Some(transition) = session.hover(target, accepts_drop) else {
+            return;
+        };
+        if let Some(old) = transition.leave {
+            let _ = old.post(WindowMessage::DragLeave);
+        }
+        if let Some(new) = transition.enter {
+            let point = Self::content_point(new, position);
+            let _ = new.post(WindowMessage::DragEnter(MouseEvent::new(
                 point,
                 buttons,
                 MouseButton::empty(),
             )));
         }
-        let mut errors = None;
-        if !down.is_empty() {
-            match target.post(WindowMessage::MouseDown(MouseEvent::new(point, buttons, down))) {
-                Ok(_) => (),
-                Err(err) => errors = Some(err),
-            };
-        }
-        if !up.is_empty() {
-            match target.post(WindowMessage::MouseUp(MouseEvent::new(point, buttons, up))) {
-                Ok(_) => (),
-                Err(err) => errors = Some(err),
-            };
-        }
-        match errors {
-            Some(err) => Err(err),
-            None => Ok(()),
-        }
+    }
+
+    /// Ends the drag session and delivers the payload to the window under the pointer.
+    fn drop_drag(&self, position: Point, buttons: MouseButton) {
+        let Some((target, payload)) = self.drag.lock().take().and_then(|v| v.into_drop()) else {
+            return;
+        };
+        let point = Self::content_point(target, position);
+        let _ = target.update_opt(|window| window.drop_payload = Some(payload));
+        let _ = target.post(WindowMessage::DragDrop(MouseEvent::new(
+            point,
+            buttons,
+            MouseButton::empty(),
+        )));
     }
 
     fn make_enver_and_leave_event(
@@ -574,21 +623,8 @@ impl WindowManager<'_> {
     fn add_hierarchy(window: WindowHandle) {
         let Some(window) = window.get() else { return };
 
-        Self::remove_hierarchy(window.handle);
         let mut window_orders = WindowManager::shared().window_orders.write().unwrap();
-
-        let mut insert_position = None;
-        for (index, lhs) in window_orders.iter().enumerate() {
-            if lhs.as_ref().level > window.level {
-                insert_position = Some(index);
-                break;
-            }
-        }
-        if let Some(insert_position) = insert_position {
-            window_orders.insert(insert_position, window.handle);
-        } else {
-            window_orders.push(window.handle);
-        }
+        zorder::bring_to_front(&mut window_orders, window.handle, |v| v.as_ref().level);
 
         window.attributes.insert(WindowAttributes::VISIBLE);
 
@@ -643,16 +679,17 @@ impl WindowManager<'_> {
     #[inline]
     pub fn invalidate_screen(rect: Rect) {
         let shared = Self::shared();
-        let mut update_coords = shared.update_coords.lock();
+        let mut update_region = shared.update_region.lock();
         if let Ok(coords) = Coordinates::from_rect(rect) {
-            update_coords.merge(coords);
+            update_region.add(coords);
             shared.signal(WindowManagerAttributes::NEEDS_REDRAW);
         }
     }
 
     fn set_active(window: Option<WindowHandle>) {
         let shared = WindowManager::shared();
-        if let Some(old_active) = shared.active.get() {
+        let old_active = shared.active.get();
+        if let Some(old_active) = old_active {
             let _ = old_active.post(WindowMessage::Deactivated);
             shared.active.write(window);
             let _ = old_active.update_opt(|window| window.refresh_title());
@@ -663,6 +700,41 @@ impl WindowManager<'_> {
             let _ = active.post(WindowMessage::Activated);
             active.show();
         }
+        if let Some(transition) = FocusTransition::new(old_active, window) {
+            if let Some(lost) = transition.lost {
+                let _ = lost.post(WindowMessage::FocusLost);
+            }
+            if let Some(gained) = transition.gained {
+                let _ = gained.post(WindowMessage::FocusGained);
+            }
+        }
+    }
+
+    /// Returns the window that has the keyboard focus.
+    #[inline]
+    pub fn focused_window() -> Option<WindowHandle> {
+        Self::shared().active.get()
+    }
+
+    /// Moves the window behind the other windows of the same level.
+    ///
+    /// If the window has the focus, it moves to the new foremost window of that level.
+    fn send_to_back(handle: WindowHandle) {
+        let shared = WindowManager::shared();
+        let Some(window) = handle.get() else { return };
+        if !window.attributes.contains(WindowAttributes::VISIBLE) {
+            return;
+        }
+
+        let next_active = {
+            let mut window_orders = shared.window_orders.write().unwrap();
+            zorder::send_to_back(&mut window_orders, handle, |v| v.as_ref().level)
+        };
+
+        WindowManager::invalidate_screen(window.shadow_frame());
+        if shared.active.contains(handle) && next_active.is_some() {
+            WindowManager::set_active(next_active);
+        }
     }
 
     fn window_at_point(point: Point) -> WindowHandle {
@@ -684,6 +756,31 @@ impl WindowManager<'_> {
         )
     }
 
+    /// Moves the cursor image so that its hotspot is at the point.
+    fn update_cursor(&self, point: Point) {
+        let placement = self.cursor.lock().placement(point);
+        match placement {
+            CursorPlacement::Hardware(point) => {
+                if let Some(screen) = System::main_screen() {
+                    screen.move_hardware_cursor(point);
+                }
+            }
+            CursorPlacement::Software(origin) => self.pointer.move_to(origin),
+        }
+    }
+
+    fn show_cursor(&self, visible: bool) {
+        if self.cursor.lock().is_hardware() {
+            if let Some(screen) = System::main_screen() {
+                screen.set_hardware_cursor_visible(visible);
+            }
+        } else if visible {
+            self.pointer.show();
+        } else {
+            self.pointer.hide();
+        }
+    }
+
     fn _update_relative_coord(
         coord: &AtomicIsize,
         movement: isize,
@@ -744,11 +841,24 @@ impl WindowManager<'_> {
         button_changed
     }
 
+    fn _process_wheel(pointer_state: &MouseState) -> bool {
+        let Some(shared) = Self::shared_opt() else {
+            return false;
+        };
+
+        let Some(wheel) = pointer_state.wheel.take() else {
+            return false;
+        };
+        shared.wheel.add(wheel);
+        true
+    }
+
     pub fn post_relative_pointer(pointer_state: &MouseState) {
         let Some(shared) = Self::shared_opt() else {
             return;
         };
         let button_changed = Self::_process_buttons(pointer_state);
+        let wheel_changed = Self::_process_wheel(pointer_state);
 
         let screen_bounds: Rect = shared.screen_size.into();
 
@@ -769,7 +879,7 @@ impl WindowManager<'_> {
             screen_bounds.height() - 1,
         );
 
-        if button_changed | moved {
+        if button_changed | moved | wheel_changed {
             WindowManager::set_pointer_move();
         }
     }
@@ -779,6 +889,7 @@ impl WindowManager<'_> {
             return;
         };
         let button_changed = Self::_process_buttons(pointer_state);
+        let wheel_changed = Self::_process_wheel(pointer_state);
 
         let screen_bounds: Rect = shared.screen_size.into();
 
@@ -799,7 +910,7 @@ impl WindowManager<'_> {
             screen_bounds.height() - 1,
         );
 
-        if button_changed | moved {
+        if button_changed | moved | wheel_changed {
             WindowManager::set_pointer_move();
         }
     }
@@ -862,6 +973,47 @@ impl WindowManager<'_> {
         });
     }
 
+    /// Changes the image of the mouse cursor.
+    ///
+    /// The hardware cursor is used if the screen supports it, otherwise the image is drawn
+    /// in the pointer window, clipped to its size.
+    pub fn set_cursor(bitmap: &BitmapRef32, hotspot: Movement) {
+        let shared = Self::shared();
+        let is_hardware = System::main_screen()
+            .map(|screen| screen.set_hardware_cursor(bitmap, hotspot))
+            .unwrap_or(false);
+
+        *shared.cursor.lock() = Cursor::new(hotspot, is_hardware);
+        if is_hardware {
+            shared.pointer.hide();
+        } else {
+            if let Some(screen) = System::main_screen() {
+                screen.set_hardware_cursor_visible(false);
+            }
+            shared.pointer.draw(|target| {
+                target.fill_rect(target.bounds(), Color::TRANSPARENT);
+                target.blt(&BitmapRef::from(bitmap), Point::new(0, 0), bitmap.bounds());
+            });
+        }
+        shared.update_cursor(shared.pointer());
+        shared.signal(WindowManagerAttributes::EVENT_MOUSE_SHOW);
+    }
+
+    /// Moves the mouse cursor to the point.
+    ///
+    /// Only the areas covered by the old and new cursor are redrawn,
+    /// or nothing when the hardware cursor is in use.
+    pub fn move_cursor(point: Point) {
+        let shared = Self::shared();
+        let point = Point::new(
+            point.x.clamp(0, shared.screen_size.width() - 1),
+            point.y.clamp(0, shared.screen_size.height() - 1),
+        );
+        shared.pointer_x.store(point.x, Ordering::SeqCst);
+        shared.pointer_y.store(point.y, Ordering::SeqCst);
+        shared.update_cursor(point);
+    }
+
     #[inline]
     pub fn is_pointer_enabled() -> bool {
         Self::shared()
@@ -876,7 +1028,7 @@ impl WindowManager<'_> {
             .attributes
             .set(WindowManagerAttributes::POINTER_ENABLED, enabled);
         if !enabled {
-            shared.pointer.hide();
+            shared.show_cursor(false);
         }
         shared.signal(WindowManagerAttributes::EVENT_MOUSE_SHOW);
         result
@@ -899,7 +1051,7 @@ impl WindowManager<'_> {
             .attributes
             .remove(WindowManagerAttributes::POINTER_HIDE_TEMP);
         if !visible {
-            shared.pointer.hide();
+            shared.show_cursor(false);
         }
         shared.signal(WindowManagerAttributes::EVENT_MOUSE_SHOW);
         result
@@ -911,7 +1063,7 @@ impl WindowManager<'_> {
         shared
             .attributes
             .insert(WindowManagerAttributes::POINTER_HIDE_TEMP);
-        shared.pointer.hide();
+        shared.show_cursor(false);
     }
 
     #[inline]
@@ -942,7 +1094,7 @@ impl WindowManager<'_> {
             Some(attr)
         });
         if !is_enabled || !is_visible || is_temporarily_hidden {
-            shared.pointer.hide();
+            shared.show_cursor(false);
         }
         shared
             .attributes
@@ -965,6 +1117,14 @@ impl WindowManager<'_> {
         Self::while_hiding_pointer(|| shared.root.draw_into(bitmap, rect));
     }
 
+    /// Captures the composited screen into a new bitmap.
+    ///
+    /// The result is always in the logical orientation, even if the framebuffer is rotated.
+    #[inline]
+    pub fn capture_screen() -> OwnedBitmap32 {
+        capture::capture_screen(Self::shared())
+    }
+
     pub fn get_statistics(sb: &mut String) {
         let shared = Self::shared();
 
@@ -1003,6 +1163,18 @@ impl WindowManager<'_> {
     }
 }
 
+impl Compositor for WindowManager<'_> {
+    #[inline]
+    fn screen_size(&self) -> Size {
+        self.screen_size
+    }
+
+    #[inline]
+    fn draw_into(&self, bitmap: &mut BitmapRefMut32, rect: Rect) {
+        Self::save_screen_to(bitmap, rect);
+    }
+}
+
 my_bitflags! {
 
     pub struct WindowManagerAttributes: usize {
@@ -1078,6 +1250,10 @@ struct RawWindow {
     waker: AtomicWaker,
     sem: Semaphore,
     queue: Option<ConcurrentFifo<WindowMessage>>,
+    /// The area of the window to be redrawn by the window manager
+    damage: SpinMutex<DirtyRegion>,
+    /// The payload of the last drag and drop
+    drop_payload: Option<Arc<[u8]>>,
 }
 
 my_bitflags! {
@@ -1099,6 +1275,7 @@ my_bitflags! {
 
         const PINCHABLE         = 0b0001_0000_0000_0000;
         const FULLSCREEN        = 0b0010_0000_0000_0000;
+        const ACCEPTS_DROP      = 0b0100_0000_0000_0000;
         const SUSPENDED         = 0b1000_0000_0000_0000;
     }
 }
@@ -1219,8 +1396,13 @@ impl RawWindow {
     fn set_frame(&mut self, new_frame: Rect) {
         let old_frame = self.frame;
         if old_frame != new_frame {
+            let is_resized = old_frame.size() != new_frame.size();
             let old_frame = self.shadow_frame();
             self.frame = new_frame;
+            if is_resized {
+                let content_size = new_frame.bounds().insets_by(self.content_insets).size();
+                let _ = self.handle.post(WindowMessage::Resized(content_size));
+            }
             if self.attributes.contains(WindowAttributes::VISIBLE) {
                 self.draw_frame();
 
@@ -1674,7 +1856,9 @@ impl RawWindow {
 
     fn invalidate_rect(&mut self, rect: Rect) {
         if self.attributes.contains(WindowAttributes::VISIBLE) {
-            self.draw_inner_to_screen(rect);
+            // Small updates are coalesced until the window manager redraws them
+            self.damage.lock().add_rect(rect);
+            WindowManager::shared().signal(WindowManagerAttributes::NEEDS_REDRAW);
         }
     }
 
@@ -2014,6 +2198,8 @@ impl RawWindowBuilder {
             waker: AtomicWaker::new(),
             sem: Semaphore::new(0),
             queue,
+            damage: SpinMutex::new(DirtyRegion::EMPTY),
+            drop_payload: None,
             pid: Scheduler::current_pid(),
         }
     }
@@ -2219,6 +2405,27 @@ impl WindowHandle {
         });
     }
 
+    /// Starts dragging the payload from this window.
+    ///
+    /// This is valid only while the primary button is pressed in this window.
+    /// Windows with [`WindowStyle::ACCEPTS_DROP`] will receive
+    /// [`WindowMessage::DragEnter`], [`WindowMessage::DragLeave`] and [`WindowMessage::DragDrop`].
+    pub fn begin_drag(&self, payload: &[u8]) -> Result<(), ()> {
+        let shared = WindowManager::shared();
+        if shared.captured.get() != Some(*self) {
+            return Err(());
+        }
+        *shared.drag.lock() = Some(DragSession::new(*self, Arc::from(payload)));
+        Ok(())
+    }
+
+    /// Takes the payload delivered with [`WindowMessage::DragDrop`].
+    #[inline]
+    pub fn take_drop_payload(&self) -> Option<Arc<[u8]>> {
+        self.update_opt(|window| window.drop_payload.take())
+            .flatten()
+    }
+
     #[inline]
     pub fn set_bg_color(&self, color: Color) {
         self.update(|window| {
@@ -2313,6 +2520,18 @@ impl WindowHandle {
         WindowManager::set_active(Some(*self));
     }
 
+    /// Brings the window to the front of the same level and gives it the focus.
+    #[inline]
+    pub fn bring_to_front(&self) {
+        WindowManager::set_active(Some(*self));
+    }
+
+    /// Sends the window to the back of the same level.
+    #[inline]
+    pub fn send_to_back(&self) {
+        WindowManager::send_to_back(*self);
+    }
+
     #[inline]
     pub fn set_close_button_enabled(&self, enabled: bool) {
         self.update(|window| {
@@ -2498,6 +2717,26 @@ impl WindowHandle {
     }
 }
 
+impl PointerTarget for WindowHandle {
+    type Error = WindowPostError;
+
+    #[inline]
+    fn post_pointer(&self, message: PointerMessage) -> Result<(), Self::Error> {
+        self.post(match message {
+            PointerMessage::Move(point, buttons) => {
+                WindowMessage::MouseMove(MouseEvent::new(point, buttons, MouseButton::empty()))
+            }
+            PointerMessage::Down(point, buttons, down) => {
+                WindowMessage::MouseDown(MouseEvent::new(point, buttons, down))
+            }
+            PointerMessage::Up(point, buttons, up) => {
+                WindowMessage::MouseUp(MouseEvent::new(point, buttons, up))
+            }
+            PointerMessage::Wheel(delta) => WindowMessage::MouseWheel(delta),
+        })
+    }
+}
+
 #[repr(transparent)]
 #[derive(Default)]
 pub struct AtomicWindowHandle(AtomicUsize);
@@ -2614,6 +2853,10 @@ pub enum WindowMessage {
     // Active
     Activated,
     Deactivated,
+    /// The window has gained the keyboard focus
+    FocusGained,
+    /// The window has lost the keyboard focus
+    FocusLost,
     /// Raw keyboard event
     Key(KeyEvent),
     /// Unicode converted keyboard event
@@ -2624,8 +2867,18 @@ pub enum WindowMessage {
     MouseUp(MouseEvent),
     MouseEnter(MouseEvent),
     MouseLeave(MouseEvent),
+    /// The mouse wheel has been rotated, positive values mean away from the user
+    MouseWheel(isize),
+    /// Something is dragged over the window that accepts drops
+    DragEnter(MouseEvent),
+    /// The drag has left the window
+    DragLeave,
+    /// Something has been dropped, the payload can be taken by [`WindowHandle::take_drop_payload`]
+    DragDrop(MouseEvent),
     /// Timer event
     Timer(usize),
+    /// The content area has been resized to the new size
+    Resized(Size),
     /// User Defined
     User(usize),
 }
@@ -0,0 +1,49 @@
+//! User Identity
+
+use crate::{fs::FileManager, sync::RwLock, *};
+pub use megstd::user::{Uid, User};
+use megstd::String;
+
+static CURRENT_USER: RwLock<Option<User>> = RwLock::new(None);
+
+/// Creates the home directory of the default user.
+pub unsafe fn init() {
+    assert_call_once!();
+
+    let user = User::default_user();
+    let _ = FileManager::mkdir(user.home());
+    set_current(user);
+}
+
+/// Returns the current user.
+#[inline]
+pub fn current() -> User {
+    CURRENT_USER
+        .read()
+        .unwrap()
+        .clone()
+        .unwrap_or_else(User::default_user)
+}
+
+/// Switches the current user.
+#[inline]
+pub fn set_current(user: User) {
+    *CURRENT_USER.write().unwrap() = Some(user);
+}
+
+#[inline]
+pub fn uid() -> Uid {
+    current().uid()
+}
+
+/// Returns the home directory of the current user.
+#[inline]
+pub fn home() -> String {
+    current().home().into()
+}
+
+/// Replaces the leading `~` of the path with the home directory of the current user.
+#[inline]
+pub fn expand_tilde(path: &str) -> String {
+    current().expand_tilde(path)
+}
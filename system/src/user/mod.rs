@@ -1 +1,3 @@
+mod account;
 pub mod userenv;
+pub use account::*;
@@ -4,7 +4,10 @@ use crate::{
     log::{EventManager, SimpleMessagePayload},
     mem::*,
     res::icon::IconManager,
-    sync::fifo::{ConcurrentFifo, EventQueue},
+    sync::{
+        fifo::{ConcurrentFifo, EventQueue},
+        Lazy,
+    },
     system::*,
     task::scheduler::*,
     ui::font::*,
@@ -14,15 +17,11 @@ use crate::{
     ui::window::*,
     *,
 };
-use core::{
-    fmt::Write,
-    mem::{transmute, MaybeUninit},
-    time::Duration,
-};
+use core::{fmt::Write, mem::transmute, time::Duration};
 use megstd::{drawing::*, io::Read, string::*, Arc, String, Vec};
 
 static IS_GUI_BOOT: bool = true;
-static mut SHUTDOWN_COMMAND: MaybeUninit<EventQueue<ShutdownCommand>> = MaybeUninit::uninit();
+static SHUTDOWN_COMMAND: Lazy<EventQueue<ShutdownCommand>> = Lazy::new(|| EventQueue::new(100));
 static mut BG_TERMINAL: Option<WindowHandle> = None;
 
 pub struct UserEnv;
@@ -94,10 +93,6 @@ impl UserEnv {
             );
         }
 
-        unsafe {
-            SHUTDOWN_COMMAND.write(EventQueue::new(100));
-        }
-
         SpawnOption::with_priority(Priority::Normal)
             .start_process(Self::_main, f as usize, "init")
             .unwrap();
@@ -185,10 +180,7 @@ impl UserEnv {
 
         match command {
             ShutdownCommand::Reboot => reboot(),
-            ShutdownCommand::Shutdown => {
-                // TODO:
-                reboot()
-            }
+            ShutdownCommand::Shutdown => System::shutdown(),
         }
 
         unreachable!();
@@ -211,7 +203,7 @@ impl UserEnv {
     }
 
     fn shutdown_command<'a>() -> &'a EventQueue<ShutdownCommand> {
-        unsafe { SHUTDOWN_COMMAND.assume_init_ref() }
+        &SHUTDOWN_COMMAND
     }
 }
 
@@ -373,9 +365,7 @@ async fn status_bar_main() {
                     let bounds = Rect::from(window.content_size())
                         .insets_by(STATUS_BAR_PADDING)
                         .insets_by(INNER_PADDING);
-                    let width = ats
-                        .bounding_size(Size::new(isize::MAX, isize::MAX), 1)
-                        .width;
+                    let width = font.measure_string(sb0.as_str()).width;
                     let rect = Rect::new(
                         bounds.max_x() - width,
                         bounds.min_y(),